@@ -0,0 +1,157 @@
+//! Commit splitting: hunks of the working tree are assigned to named
+//! buckets, and each bucket becomes one commit (in bucket order)
+
+use crate::change::{Change, ChangeKind};
+use crate::diff::{split_lines, DiffResult};
+use crate::git::GitError;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A named set of hunks that becomes one commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket {
+    /// Commit message
+    pub name: String,
+    /// (file path, hunk index) pairs
+    pub hunks: BTreeSet<(PathBuf, usize)>,
+}
+
+impl Bucket {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            hunks: BTreeSet::new(),
+        }
+    }
+}
+
+/// A file as changed by one planned commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    /// Content before the commit
+    pub before: String,
+    /// Content after the commit (`None` deletes the file)
+    pub after: Option<String>,
+}
+
+/// A commit to be created from one bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedCommit {
+    pub message: String,
+    pub files: Vec<PlannedFile>,
+}
+
+fn old_side(change: &Change) -> Option<usize> {
+    change
+        .spans
+        .iter()
+        .any(|span| span.kind != ChangeKind::Insert)
+        .then(|| change.spans.iter().find_map(|span| span.old_line))
+        .flatten()
+}
+
+fn new_side(change: &Change) -> Option<usize> {
+    change
+        .spans
+        .iter()
+        .any(|span| span.kind != ChangeKind::Delete)
+        .then(|| change.spans.iter().find_map(|span| span.new_line))
+        .flatten()
+}
+
+/// `old` with only the hunks in `hunks` (indices into `diff.hunks`) applied
+pub fn partial_content(old: &str, new: &str, diff: &DiffResult, hunks: &BTreeSet<usize>) -> String {
+    let applied: BTreeSet<usize> = diff
+        .hunks
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| hunks.contains(index))
+        .flat_map(|(_, hunk)| hunk.change_ids.iter().copied())
+        .collect();
+    applied_content(old, new, diff, &applied)
+}
+
+/// `old` with only the changes in `applied` (change ids of `diff`) applied
+pub fn applied_content(
+    old: &str,
+    new: &str,
+    diff: &DiffResult,
+    applied: &BTreeSet<usize>,
+) -> String {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    let mut out = String::new();
+    for change in &diff.changes {
+        let line = if applied.contains(&change.id) {
+            new_side(change).and_then(|line| new_lines.get(line - 1))
+        } else {
+            old_side(change).and_then(|line| old_lines.get(line - 1))
+        };
+        if let Some(line) = line {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+/// Create one commit per planned commit. Refuses to run when other changes
+/// are staged, since they would end up in the first commit.
+pub fn create_commits(repo_root: &Path, plan: &[PlannedCommit]) -> Result<Vec<String>, GitError> {
+    if crate::git::has_staged_changes(repo_root)? {
+        return Err(GitError::CommandFailed(
+            "the index has staged changes; commit or unstage them first".to_string(),
+        ));
+    }
+    let mut commits = Vec::new();
+    for commit in plan {
+        for file in &commit.files {
+            crate::git::stage_content(repo_root, &file.path, file.after.as_deref())?;
+        }
+        commits.push(crate::git::commit_index(repo_root, &commit.message)?);
+    }
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffEngine;
+
+    #[test]
+    fn test_partial_content_applies_selected_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "A\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let diff = DiffEngine::new().with_context(1).diff_strings(old, new);
+        assert_eq!(diff.hunks.len(), 2);
+
+        let first = BTreeSet::from([0]);
+        assert_eq!(
+            partial_content(old, new, &diff, &first),
+            "A\nb\nc\nd\ne\nf\ng\nh\ni\nj\n"
+        );
+        let second = BTreeSet::from([1]);
+        assert_eq!(
+            partial_content(old, new, &diff, &second),
+            "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n"
+        );
+        assert_eq!(
+            partial_content(old, new, &diff, &BTreeSet::from([0, 1])),
+            new
+        );
+    }
+
+    #[test]
+    fn test_partial_content_counts_lone_carriage_returns_as_lines() {
+        let old = "a\rb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let new = "A\rb\nc\nd\ne\nf\ng\nh\ni\nj\nK\n";
+        let diff = DiffEngine::new().with_context(1).diff_strings(old, new);
+        assert_eq!(diff.hunks.len(), 2);
+
+        assert_eq!(
+            partial_content(old, new, &diff, &BTreeSet::from([1])),
+            "a\rb\nc\nd\ne\nf\ng\nh\ni\nj\nK\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Change representation for diff operations
 
+use crate::text::Text;
 use serde::{Deserialize, Serialize};
 
 /// The kind of change in a diff
@@ -20,10 +21,11 @@ pub enum ChangeKind {
 pub struct ChangeSpan {
     /// The kind of change
     pub kind: ChangeKind,
-    /// The text content (old content for Delete/Replace, new for Insert)
-    pub text: String,
+    /// The text content (old content for Delete/Replace, new for Insert),
+    /// shared with every clone of the span
+    pub text: Text,
     /// For Replace: the new text that replaces the old
-    pub new_text: Option<String>,
+    pub new_text: Option<Text>,
     /// Line number in the old file (if applicable)
     pub old_line: Option<usize>,
     /// Line number in the new file (if applicable)
@@ -31,7 +33,7 @@ pub struct ChangeSpan {
 }
 
 impl ChangeSpan {
-    pub fn new(kind: ChangeKind, text: impl Into<String>) -> Self {
+    pub fn new(kind: ChangeKind, text: impl Into<Text>) -> Self {
         Self {
             kind,
             text: text.into(),
@@ -41,19 +43,19 @@ impl ChangeSpan {
         }
     }
 
-    pub fn insert(text: impl Into<String>) -> Self {
+    pub fn insert(text: impl Into<Text>) -> Self {
         Self::new(ChangeKind::Insert, text)
     }
 
-    pub fn delete(text: impl Into<String>) -> Self {
+    pub fn delete(text: impl Into<Text>) -> Self {
         Self::new(ChangeKind::Delete, text)
     }
 
-    pub fn equal(text: impl Into<String>) -> Self {
+    pub fn equal(text: impl Into<Text>) -> Self {
         Self::new(ChangeKind::Equal, text)
     }
 
-    pub fn replace(old: impl Into<String>, new: impl Into<String>) -> Self {
+    pub fn replace(old: impl Into<Text>, new: impl Into<Text>) -> Self {
         Self {
             kind: ChangeKind::Replace,
             text: old.into(),
@@ -113,4 +115,67 @@ impl Change {
     pub fn has_changes(&self) -> bool {
         self.spans.iter().any(|s| s.is_change())
     }
+
+    /// Group of each span: runs of edits not separated by an unchanged span
+    /// share a group, numbered from 0 (`None` for unchanged spans)
+    pub fn span_groups(&self) -> Vec<Option<usize>> {
+        let mut groups = Vec::with_capacity(self.spans.len());
+        let mut next = 0;
+        let mut in_group = false;
+        for span in &self.spans {
+            if span.is_change() {
+                if !in_group {
+                    in_group = true;
+                    next += 1;
+                }
+                groups.push(Some(next - 1));
+            } else {
+                in_group = false;
+                groups.push(None);
+            }
+        }
+        groups
+    }
+
+    /// Kind of edit the change makes as a whole: [`ChangeKind::Insert`] or
+    /// [`ChangeKind::Delete`] if every edit is one, [`ChangeKind::Replace`]
+    /// for a mix, [`ChangeKind::Equal`] without edits
+    pub fn edit_kind(&self) -> ChangeKind {
+        let mut kinds = self.changes().map(|span| span.kind);
+        let Some(first) = kinds.next() else {
+            return ChangeKind::Equal;
+        };
+        if kinds.all(|kind| kind == first) {
+            first
+        } else {
+            ChangeKind::Replace
+        }
+    }
+
+    /// The `(old, new)` token pair if every edit in this change replaces the
+    /// same single token with the same other one (a rename), e.g.
+    /// `foo(foo)` → `bar(bar)`
+    pub fn token_replacement(&self) -> Option<(String, String)> {
+        let mut pairs: Vec<(String, String)> = Vec::new();
+        let mut current: Option<(String, String)> = None;
+        for span in &self.spans {
+            match span.kind {
+                ChangeKind::Equal => pairs.extend(current.take()),
+                ChangeKind::Delete => current.get_or_insert_default().0.push_str(&span.text),
+                ChangeKind::Insert => current.get_or_insert_default().1.push_str(&span.text),
+                ChangeKind::Replace => {
+                    let pair = current.get_or_insert_default();
+                    pair.0.push_str(&span.text);
+                    pair.1
+                        .push_str(span.new_text.as_deref().unwrap_or_default());
+                }
+            }
+        }
+        pairs.extend(current);
+
+        let is_token = |text: &str| !text.is_empty() && !text.chars().any(char::is_whitespace);
+        let (first, rest) = pairs.split_first()?;
+        (is_token(&first.0) && is_token(&first.1) && rest.iter().all(|pair| pair == first))
+            .then(|| first.clone())
+    }
 }
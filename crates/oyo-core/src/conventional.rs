@@ -0,0 +1,223 @@
+//! Conventional-commit subjects (`feat(parser)!: accept tabs`) for grouping
+//! the files of a commit range by the kind of change that touched them
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Group of breaking commits (`type!:` subjects)
+pub const BREAKING: &str = "breaking";
+/// Group of commits without a conventional subject
+pub const OTHER: &str = "other";
+
+/// Commit types in review order; breaking commits come first, others last
+pub const COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "perf", "refactor", "revert", "docs", "style", "test", "build", "ci", "chore",
+];
+
+/// A parsed commit subject
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subject<'a> {
+    /// Group label: the conventional type, [`BREAKING`], or [`OTHER`]
+    pub label: &'static str,
+    pub scope: Option<&'a str>,
+    /// Text after the `type(scope):` prefix (the whole subject for [`OTHER`])
+    pub description: &'a str,
+}
+
+/// Parse a conventional-commit subject
+pub fn parse_subject(subject: &str) -> Subject<'_> {
+    let other = Subject {
+        label: OTHER,
+        scope: None,
+        description: subject.trim(),
+    };
+    let Some((head, description)) = subject.split_once(':') else {
+        return other;
+    };
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, scope)) => match scope.strip_suffix(')') {
+            Some(scope) => (kind, Some(scope.trim())),
+            None => return other,
+        },
+        None => (head, None),
+    };
+    let kind = kind.trim().to_ascii_lowercase();
+    let label = match COMMIT_TYPES.iter().find(|&&known| known == kind) {
+        Some(_) if breaking => BREAKING,
+        Some(&known) => known,
+        None => return other,
+    };
+    Subject {
+        label,
+        scope: scope.filter(|scope| !scope.is_empty()),
+        description: description.trim(),
+    }
+}
+
+/// Group label of a commit subject: its conventional type, [`BREAKING`],
+/// or [`OTHER`]
+pub fn commit_type(subject: &str) -> &'static str {
+    parse_subject(subject).label
+}
+
+/// Changelog heading of a group label
+fn section_title(label: &str) -> &'static str {
+    match label {
+        BREAKING => "Breaking Changes",
+        "feat" => "Features",
+        "fix" => "Bug Fixes",
+        "perf" => "Performance",
+        "refactor" => "Refactoring",
+        "revert" => "Reverts",
+        "docs" => "Documentation",
+        "style" => "Style",
+        "test" => "Tests",
+        "build" => "Build",
+        "ci" => "CI",
+        "chore" => "Chores",
+        _ => "Other",
+    }
+}
+
+/// Markdown changelog scaffold for `version`: one section per commit type,
+/// entries sorted by scope
+pub fn changelog(version: &str, commits: &[RangeCommit]) -> String {
+    let mut subjects: Vec<Subject> = commits
+        .iter()
+        .map(|commit| parse_subject(&commit.subject))
+        .collect();
+    // Stable: commits of a scope keep their log order
+    subjects.sort_by_key(|subject| (type_rank(subject.label), subject.scope));
+
+    let mut out = format!("## {version}\n");
+    let mut section = None;
+    for subject in subjects {
+        if section != Some(subject.label) {
+            section = Some(subject.label);
+            out.push_str(&format!("\n### {}\n\n", section_title(subject.label)));
+        }
+        match subject.scope {
+            Some(scope) => out.push_str(&format!("- **{scope}**: {}\n", subject.description)),
+            None => out.push_str(&format!("- {}\n", subject.description)),
+        }
+    }
+    out
+}
+
+/// Review order of a group label (breaking first, other last)
+pub fn type_rank(label: &str) -> usize {
+    if label == BREAKING {
+        return 0;
+    }
+    COMMIT_TYPES
+        .iter()
+        .position(|&known| known == label)
+        .map_or(COMMIT_TYPES.len() + 1, |pos| pos + 1)
+}
+
+/// A commit of a range: its subject and the files it touched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeCommit {
+    pub subject: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Files of a commit range grouped by commit type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitTypeGroups {
+    /// Group of each file: the most significant type among the commits
+    /// touching it
+    by_path: HashMap<PathBuf, &'static str>,
+    /// Number of commits in each group
+    commits: HashMap<&'static str, usize>,
+}
+
+impl CommitTypeGroups {
+    pub fn new(commits: &[RangeCommit]) -> Self {
+        let mut groups = Self::default();
+        for commit in commits {
+            let label = commit_type(&commit.subject);
+            *groups.commits.entry(label).or_default() += 1;
+            for path in &commit.files {
+                let slot = groups.by_path.entry(path.clone()).or_insert(label);
+                if type_rank(label) < type_rank(slot) {
+                    *slot = label;
+                }
+            }
+        }
+        groups
+    }
+
+    /// Group of `path` ([`OTHER`] for files no commit touched)
+    pub fn label(&self, path: &Path) -> &'static str {
+        self.by_path.get(path).copied().unwrap_or(OTHER)
+    }
+
+    /// Number of commits of the range in group `label`
+    pub fn commit_count(&self, label: &str) -> usize {
+        self.commits.get(label).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_type() {
+        assert_eq!(commit_type("feat: add thing"), "feat");
+        assert_eq!(commit_type("Fix(parser): handle tabs"), "fix");
+        assert_eq!(commit_type("refactor(core)!: drop api"), BREAKING);
+        assert_eq!(commit_type("Update README"), OTHER);
+        assert_eq!(commit_type("wip: stuff"), OTHER);
+        assert_eq!(commit_type("docs(readme: typo"), OTHER);
+    }
+
+    #[test]
+    fn test_changelog_groups_by_type_and_scope() {
+        let commit = |subject: &str| RangeCommit {
+            subject: subject.to_string(),
+            files: Vec::new(),
+        };
+        let commits = [
+            commit("fix(ui): clip popup"),
+            commit("feat: add release command"),
+            commit("Update README"),
+            commit("fix(core): off by one"),
+            commit("feat(core)!: rename api"),
+        ];
+        assert_eq!(
+            changelog("v1.3.0", &commits),
+            "## v1.3.0\n\
+             \n### Breaking Changes\n\n- **core**: rename api\n\
+             \n### Features\n\n- add release command\n\
+             \n### Bug Fixes\n\n- **core**: off by one\n- **ui**: clip popup\n\
+             \n### Other\n\n- Update README\n"
+        );
+    }
+
+    #[test]
+    fn test_groups_pick_most_significant_type() {
+        let commits = [
+            RangeCommit {
+                subject: "chore: bump".to_string(),
+                files: vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")],
+            },
+            RangeCommit {
+                subject: "fix: a".to_string(),
+                files: vec![PathBuf::from("a.rs")],
+            },
+        ];
+        let groups = CommitTypeGroups::new(&commits);
+        assert_eq!(groups.label(Path::new("a.rs")), "fix");
+        assert_eq!(groups.label(Path::new("b.rs")), "chore");
+        assert_eq!(groups.label(Path::new("c.rs")), OTHER);
+        assert_eq!(groups.commit_count("chore"), 1);
+        assert!(type_rank(BREAKING) < type_rank("feat"));
+        assert!(type_rank("chore") < type_rank(OTHER));
+    }
+}
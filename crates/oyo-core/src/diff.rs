@@ -1,8 +1,14 @@
 //! Diff computation engine
 
 use crate::change::{Change, ChangeKind, ChangeSpan};
-use similar::{ChangeTag, TextDiff};
+use crate::provider::DiffProvider;
+use crate::text::Text;
+use crate::token::{CodeTokenizer, Tokenizer};
+use regex::Regex;
+use similar::{Algorithm, ChangeTag, TextDiff};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -55,6 +61,26 @@ pub struct DiffResult {
     pub insertions: usize,
     /// Total number of deletions
     pub deletions: usize,
+    /// Indentation-only changes (when reindent detection is enabled)
+    pub reindent_changes: Vec<usize>,
+    /// `(change, lead)`: changes stepped together with an earlier `lead`
+    /// change instead of on their own (the rest of a collapsed reindent run)
+    pub grouped_changes: Vec<(usize, usize)>,
+    /// Changed lines that differ only in trailing whitespace or line ending
+    pub whitespace_changes: Vec<usize>,
+    /// The final newline was added or removed
+    pub eof_newline: Option<EofNewline>,
+}
+
+/// A change to whether the file ends with a newline
+/// (`\ No newline at end of file` in unified diffs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EofNewline {
+    /// True if the new version ends with a newline and the old one did not
+    pub added: bool,
+    /// Change holding the last line whose newline changed (the old side's
+    /// last line when added, the new side's when removed)
+    pub change_id: usize,
 }
 
 impl DiffResult {
@@ -66,6 +92,60 @@ impl DiffResult {
             .collect()
     }
 
+    /// Approximate heap and inline bytes held by the result; a buffer
+    /// shared by several spans is counted once
+    pub fn memory_estimate(&self) -> usize {
+        self.memory_estimate_excluding(&mut std::collections::HashSet::new())
+    }
+
+    /// [`Self::memory_estimate`] without the buffers in `seen`, which it
+    /// adds to
+    pub(crate) fn memory_estimate_excluding(
+        &self,
+        seen: &mut std::collections::HashSet<*const u8>,
+    ) -> usize {
+        use std::mem::size_of;
+        let mut changes = 0;
+        for change in &self.changes {
+            changes += size_of::<Change>()
+                + change.description.as_ref().map_or(0, String::capacity)
+                + change.spans.capacity() * size_of::<ChangeSpan>();
+            for span in &change.spans {
+                for text in std::iter::once(&span.text).chain(&span.new_text) {
+                    if seen.insert(text.buffer_id()) {
+                        changes += text.buffer_size();
+                    }
+                }
+            }
+        }
+        let hunks: usize = self
+            .hunks
+            .iter()
+            .map(|hunk| size_of::<Hunk>() + hunk.change_ids.capacity() * size_of::<usize>())
+            .sum();
+        let ids = self.significant_changes.capacity()
+            + self.reindent_changes.capacity()
+            + self.whitespace_changes.capacity()
+            + self.grouped_changes.capacity() * 2;
+        size_of::<Self>() + changes + hunks + ids * size_of::<usize>()
+    }
+
+    /// Number of changes in each of `buckets` equal slices of the file, to
+    /// show whether changes are localized or scattered
+    pub fn churn(&self, buckets: usize) -> Vec<usize> {
+        let mut counts = vec![0; buckets];
+        let total = self.changes.len();
+        if buckets == 0 || total == 0 {
+            return counts;
+        }
+        for &id in &self.significant_changes {
+            if let Some(pos) = self.changes.iter().position(|change| change.id == id) {
+                counts[pos * buckets / total] += 1;
+            }
+        }
+        counts
+    }
+
     /// Get a hunk by ID
     pub fn get_hunk(&self, hunk_id: usize) -> Option<&Hunk> {
         self.hunks.iter().find(|h| h.id == hunk_id)
@@ -87,12 +167,86 @@ pub struct FileDiff {
     pub result: DiffResult,
 }
 
+/// Line diff algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffAlgorithm {
+    /// Myers' O(ND) algorithm
+    #[default]
+    Myers,
+    /// Patience diff (anchors on unique lines)
+    Patience,
+    /// Longest common subsequence
+    Lcs,
+}
+
+impl From<DiffAlgorithm> for Algorithm {
+    fn from(algorithm: DiffAlgorithm) -> Self {
+        match algorithm {
+            DiffAlgorithm::Myers => Algorithm::Myers,
+            DiffAlgorithm::Patience => Algorithm::Patience,
+            DiffAlgorithm::Lcs => Algorithm::Lcs,
+        }
+    }
+}
+
+/// How indentation-only line changes (tabs→spaces, 2→4 spaces) are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReindentMode {
+    /// Treat them like any other change
+    #[default]
+    Off,
+    /// Show them as already applied, but don't step through them
+    Skip,
+    /// Render them as unchanged context
+    Hide,
+    /// Step through each run of consecutive ones as a single step
+    Collapse,
+}
+
+/// Regexes whose matches are never split by word diffing
+/// (string literals, URLs, hex colors, ...)
+#[derive(Debug, Clone, Default)]
+pub struct WordStopPatterns {
+    /// Patterns applied to every file
+    pub global: Vec<Regex>,
+    /// Patterns keyed by lowercase file extension (e.g. "rs", "css")
+    pub by_extension: HashMap<String, Vec<Regex>>,
+}
+
+impl WordStopPatterns {
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty() && self.by_extension.values().all(|p| p.is_empty())
+    }
+
+    /// Patterns that apply to a file path
+    pub fn for_path(&self, path: &Path) -> Vec<Regex> {
+        let mut patterns = self.global.clone();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            if let Some(extra) = self.by_extension.get(&ext.to_ascii_lowercase()) {
+                patterns.extend(extra.iter().cloned());
+            }
+        }
+        patterns
+    }
+}
+
 /// The main diff engine
+#[derive(Debug, Clone)]
 pub struct DiffEngine {
     /// Number of context lines to include
     context_lines: usize,
     /// Whether to do word-level diffing within changed lines
     word_level: bool,
+    /// Line diff algorithm
+    algorithm: DiffAlgorithm,
+    /// Matches of these patterns are kept as single word-diff tokens
+    stop_patterns: Vec<Regex>,
+    /// Handling of indentation-only changes
+    reindent: ReindentMode,
+    /// Splits changed lines into word-diff tokens
+    tokenizer: Arc<dyn Tokenizer>,
+    /// Engine that computes the diffs instead of `similar`
+    provider: Option<Arc<dyn DiffProvider>>,
 }
 
 impl Default for DiffEngine {
@@ -100,6 +254,11 @@ impl Default for DiffEngine {
         Self {
             context_lines: 3,
             word_level: true,
+            algorithm: DiffAlgorithm::default(),
+            stop_patterns: Vec::new(),
+            reindent: ReindentMode::default(),
+            tokenizer: Arc::new(CodeTokenizer),
+            provider: None,
         }
     }
 }
@@ -119,11 +278,92 @@ impl DiffEngine {
         self
     }
 
+    pub fn with_algorithm(mut self, algorithm: DiffAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn with_stop_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.stop_patterns = patterns;
+        self
+    }
+
+    pub fn with_reindent(mut self, mode: ReindentMode) -> Self {
+        self.reindent = mode;
+        self
+    }
+
+    /// Use a custom tokenizer for word-level diffing
+    /// (e.g. [`crate::token::GraphemeTokenizer`] for character-level diffs)
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Compute diffs with another engine (see [`crate::provider`])
+    pub fn with_provider(mut self, provider: Arc<dyn DiffProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Name of the engine that computes the diffs
+    pub fn provider_name(&self) -> &'static str {
+        self.provider
+            .as_ref()
+            .map_or("similar", |provider| provider.name())
+    }
+
+    /// [`Self::diff_text`] of the file at `path`, whose name lets the
+    /// provider pick a language
+    pub fn diff_text_for(&self, path: &Path, old: &Text, new: &Text) -> DiffResult {
+        match &self.provider {
+            Some(provider) => provider.diff_file(path, old, new),
+            None => self.diff_text(old, new),
+        }
+    }
+
     /// Compute diff between two strings
     pub fn diff_strings(&self, old: &str, new: &str) -> DiffResult {
-        let text_diff = TextDiff::from_lines(old, new);
+        self.diff_text(&Text::from(old), &Text::from(new))
+    }
+
+    /// Compute diff between two shared texts; every span is a range of
+    /// `old` or `new`, so the result holds no copy of the contents
+    #[tracing::instrument(level = "debug", skip_all, fields(old_bytes = old.len(), new_bytes = new.len()))]
+    pub fn diff_text(&self, old: &Text, new: &Text) -> DiffResult {
+        if let Some(provider) = &self.provider {
+            return provider.diff(old, new);
+        }
+        let text_diff = TextDiff::configure()
+            .algorithm(self.algorithm.into())
+            .diff_lines(old.as_str(), new.as_str());
+        let ops: Vec<_> = text_diff
+            .iter_all_changes()
+            .map(|change| (change.tag(), change.value()))
+            .collect();
+        self.diff_line_ops(old, new, &ops)
+    }
+
+    /// Build the result from a line diff of `old` and `new`: the changed
+    /// lines are paired and diffed by word, then grouped into hunks. Line
+    /// values may keep their newline.
+    pub(crate) fn diff_line_ops(
+        &self,
+        old: &Text,
+        new: &Text,
+        ops: &[(ChangeTag, &str)],
+    ) -> DiffResult {
+        let line_text = |value: &str| {
+            let value = value.trim_end_matches('\n');
+            old.slice_of(value)
+                .or_else(|| new.slice_of(value))
+                .unwrap_or_else(|| Text::from(value))
+        };
         let mut changes = Vec::new();
         let mut significant_changes = Vec::new();
+        let mut reindent_changes = Vec::new();
+        let mut grouped_changes = Vec::new();
+        let mut whitespace_changes = Vec::new();
         let mut insertions = 0;
         let mut deletions = 0;
         let mut change_id = 0;
@@ -132,13 +372,11 @@ impl DiffEngine {
         let mut new_line_num = 1usize;
 
         // Group consecutive changes together for word-level diffing
-        let mut pending_deletes: Vec<(String, usize)> = Vec::new();
-        let mut pending_inserts: Vec<(String, usize)> = Vec::new();
+        let mut pending_deletes: Vec<(Text, usize)> = Vec::new();
+        let mut pending_inserts: Vec<(Text, usize)> = Vec::new();
 
-        let ops: Vec<_> = text_diff.iter_all_changes().collect();
-
-        for change in ops.iter() {
-            match change.tag() {
+        for &(tag, value) in ops {
+            match tag {
                 ChangeTag::Equal => {
                     // Flush any pending changes before processing equal
                     self.flush_pending_changes(
@@ -146,12 +384,15 @@ impl DiffEngine {
                         &mut pending_inserts,
                         &mut changes,
                         &mut significant_changes,
+                        &mut reindent_changes,
+                        &mut grouped_changes,
+                        &mut whitespace_changes,
                         &mut change_id,
                         &mut insertions,
                         &mut deletions,
                     );
 
-                    let span = ChangeSpan::equal(change.value().trim_end_matches('\n'))
+                    let span = ChangeSpan::equal(line_text(value))
                         .with_lines(Some(old_line_num), Some(new_line_num));
                     changes.push(Change::single(change_id, span));
                     change_id += 1;
@@ -159,17 +400,11 @@ impl DiffEngine {
                     new_line_num += 1;
                 }
                 ChangeTag::Delete => {
-                    pending_deletes.push((
-                        change.value().trim_end_matches('\n').to_string(),
-                        old_line_num,
-                    ));
+                    pending_deletes.push((line_text(value), old_line_num));
                     old_line_num += 1;
                 }
                 ChangeTag::Insert => {
-                    pending_inserts.push((
-                        change.value().trim_end_matches('\n').to_string(),
-                        new_line_num,
-                    ));
+                    pending_inserts.push((line_text(value), new_line_num));
                     new_line_num += 1;
                 }
             }
@@ -181,13 +416,40 @@ impl DiffEngine {
             &mut pending_inserts,
             &mut changes,
             &mut significant_changes,
+            &mut reindent_changes,
+            &mut grouped_changes,
+            &mut whitespace_changes,
             &mut change_id,
             &mut insertions,
             &mut deletions,
         );
 
+        let eof_newline = (!old.is_empty() && !new.is_empty())
+            .then(|| new.ends_with('\n'))
+            .filter(|&added| old.ends_with('\n') != added)
+            .and_then(|added| {
+                let (old_last, new_last) = (old_line_num - 1, new_line_num - 1);
+                let holds_last = |span: &ChangeSpan| {
+                    if added {
+                        span.old_line == Some(old_last)
+                    } else {
+                        span.new_line == Some(new_last)
+                    }
+                };
+                // A newline-only change has no changed spans; find it by id
+                let change = changes.iter().rev().find(|change| {
+                    (significant_changes.contains(&change.id)
+                        || reindent_changes.contains(&change.id))
+                        && change.spans.iter().any(holds_last)
+                })?;
+                Some(EofNewline {
+                    added,
+                    change_id: change.id,
+                })
+            });
+
         // Compute hunks by grouping nearby changes
-        let hunks = Self::compute_hunks(&significant_changes, &changes);
+        let hunks = Self::compute_hunks(&significant_changes, &changes, self.context_lines);
 
         DiffResult {
             changes,
@@ -195,14 +457,20 @@ impl DiffEngine {
             hunks,
             insertions,
             deletions,
+            reindent_changes,
+            grouped_changes,
+            whitespace_changes,
+            eof_newline,
         }
     }
 
     /// Compute hunks by grouping consecutive changes that are close together
-    /// Changes within PROXIMITY_THRESHOLD lines are grouped into the same hunk
-    fn compute_hunks(significant_changes: &[usize], changes: &[Change]) -> Vec<Hunk> {
-        const PROXIMITY_THRESHOLD: usize = 3;
-
+    /// Changes within `proximity` lines (the context size) are grouped into the same hunk
+    fn compute_hunks(
+        significant_changes: &[usize],
+        changes: &[Change],
+        proximity: usize,
+    ) -> Vec<Hunk> {
         let mut hunks = Vec::new();
         if significant_changes.is_empty() {
             return hunks;
@@ -232,8 +500,8 @@ impl DiffEngine {
 
             // Determine if this change is close to the previous one
             let is_close = match (last_old_line, last_new_line, old_line, new_line) {
-                (Some(lo), _, Some(co), _) => co.saturating_sub(lo) <= PROXIMITY_THRESHOLD,
-                (_, Some(ln), _, Some(cn)) => cn.saturating_sub(ln) <= PROXIMITY_THRESHOLD,
+                (Some(lo), _, Some(co), _) => co.saturating_sub(lo) <= proximity,
+                (_, Some(ln), _, Some(cn)) => cn.saturating_sub(ln) <= proximity,
                 _ => current_hunk_changes.is_empty(), // First change always starts a hunk
             };
 
@@ -308,10 +576,13 @@ impl DiffEngine {
     #[allow(clippy::too_many_arguments)]
     fn flush_pending_changes(
         &self,
-        pending_deletes: &mut Vec<(String, usize)>,
-        pending_inserts: &mut Vec<(String, usize)>,
+        pending_deletes: &mut Vec<(Text, usize)>,
+        pending_inserts: &mut Vec<(Text, usize)>,
         changes: &mut Vec<Change>,
         significant_changes: &mut Vec<usize>,
+        reindent_changes: &mut Vec<usize>,
+        grouped_changes: &mut Vec<(usize, usize)>,
+        whitespace_changes: &mut Vec<usize>,
         change_id: &mut usize,
         insertions: &mut usize,
         deletions: &mut usize,
@@ -322,12 +593,44 @@ impl DiffEngine {
 
         // Try to match deletes with inserts for replace operations
         if self.word_level && pending_deletes.len() == pending_inserts.len() {
+            // First change of the current run of reindents, when collapsing
+            let mut run_lead = None;
             for ((old_text, old_line), (new_text, new_line)) in
                 pending_deletes.iter().zip(pending_inserts.iter())
             {
+                let reindent =
+                    self.reindent != ReindentMode::Off && is_reindent(old_text, new_text);
+                if reindent && self.reindent == ReindentMode::Hide {
+                    let span = ChangeSpan::equal(new_text.clone())
+                        .with_lines(Some(*old_line), Some(*new_line));
+                    changes.push(Change::single(*change_id, span));
+                    *change_id += 1;
+                    continue;
+                }
+
                 let spans = self.compute_word_diff(old_text, new_text, *old_line, *new_line);
                 let change = Change::new(*change_id, spans);
-                significant_changes.push(*change_id);
+                if reindent {
+                    reindent_changes.push(*change_id);
+                }
+                match (reindent, self.reindent, run_lead) {
+                    (true, ReindentMode::Collapse, Some(lead)) => {
+                        grouped_changes.push((*change_id, lead));
+                    }
+                    (true, ReindentMode::Collapse, None) => {
+                        run_lead = Some(*change_id);
+                        significant_changes.push(*change_id);
+                    }
+                    (true, _, _) => {}
+                    (false, _, _) => {
+                        run_lead = None;
+                        significant_changes.push(*change_id);
+                    }
+                }
+                // Equal text here means only the newline changed
+                if old_text.trim_end() == new_text.trim_end() {
+                    whitespace_changes.push(*change_id);
+                }
                 changes.push(change);
                 *change_id += 1;
                 *insertions += 1;
@@ -356,69 +659,96 @@ impl DiffEngine {
     }
 }
 
-/// Tokenize code for word-level diffing
-/// Separates identifiers from punctuation for accurate diffs
-fn tokenize_code(line: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut buf = String::new();
-    let mut in_word = false;
-
-    for ch in line.chars() {
-        let is_word = ch.is_alphanumeric() || ch == '_';
-        if is_word {
-            if !in_word {
-                if !buf.is_empty() {
-                    tokens.push(std::mem::take(&mut buf));
-                }
-                in_word = true;
-            }
-            buf.push(ch);
-        } else {
-            if in_word {
-                if !buf.is_empty() {
-                    tokens.push(std::mem::take(&mut buf));
-                }
-                in_word = false;
-            }
-            if ch.is_whitespace() {
-                // Group consecutive whitespace
-                if !buf.is_empty() && !buf.chars().all(char::is_whitespace) {
-                    tokens.push(std::mem::take(&mut buf));
-                }
-                buf.push(ch);
-            } else {
-                // Each punctuation char is its own token
-                if !buf.is_empty() {
-                    tokens.push(std::mem::take(&mut buf));
-                }
-                tokens.push(ch.to_string());
-            }
+/// Lines of `text`, each with its ending, split where line diffs split:
+/// after `\n`, `\r\n` or a lone `\r`. Line numbers in a [`DiffResult`]
+/// index these lines.
+pub fn split_lines(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (index, &byte) in bytes.iter().enumerate() {
+        if byte == b'\n' || (byte == b'\r' && bytes.get(index + 1) != Some(&b'\n')) {
+            lines.push(&text[start..=index]);
+            start = index + 1;
         }
     }
-    if !buf.is_empty() {
-        tokens.push(buf);
+    if start < text.len() {
+        lines.push(&text[start..]);
     }
-    tokens
+    lines
+}
+
+/// Check if two lines differ only in leading whitespace
+fn is_reindent(old: &str, new: &str) -> bool {
+    old != new && old.trim_start() == new.trim_start()
 }
 
 impl DiffEngine {
-    /// Compute word-level diff within a line
+    /// Tokenize a line, keeping stop pattern matches as single tokens
+    fn tokenize(&self, line: &str) -> Vec<String> {
+        if self.stop_patterns.is_empty() {
+            return self.tokenizer.tokenize(line);
+        }
+
+        let mut ranges: Vec<(usize, usize)> = self
+            .stop_patterns
+            .iter()
+            .flat_map(|re| re.find_iter(line))
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        // Earliest match wins; ties go to the longest
+        ranges.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+
+        let mut tokens = Vec::new();
+        let mut pos = 0;
+        for (start, end) in ranges {
+            if start < pos {
+                continue;
+            }
+            tokens.extend(self.tokenizer.tokenize(&line[pos..start]));
+            tokens.push(line[start..end].to_string());
+            pos = end;
+        }
+        tokens.extend(self.tokenizer.tokenize(&line[pos..]));
+        tokens
+    }
+
+    /// Compute word-level diff within a line; spans are ranges of the lines
     fn compute_word_diff(
         &self,
-        old: &str,
-        new: &str,
+        old: &Text,
+        new: &Text,
         old_line: usize,
         new_line: usize,
     ) -> Vec<ChangeSpan> {
-        let old_tokens = tokenize_code(old);
-        let new_tokens = tokenize_code(new);
+        let old_tokens = self.tokenize(old);
+        let new_tokens = self.tokenize(new);
         let old_refs: Vec<&str> = old_tokens.iter().map(|s| s.as_str()).collect();
         let new_refs: Vec<&str> = new_tokens.iter().map(|s| s.as_str()).collect();
         let word_diff = TextDiff::from_slices(&old_refs, &new_refs);
         let mut spans = Vec::new();
+        let (mut old_pos, mut new_pos) = (0, 0);
+        // Tokens cover their line in order; a tokenizer that drops or
+        // rewrites text falls back to a copy
+        let take = |line: &Text, pos: &mut usize, value: &str| {
+            let start = *pos;
+            *pos += value.len();
+            line.slice(start..*pos)
+                .filter(|text| text == value)
+                .unwrap_or_else(|| Text::from(value))
+        };
 
         for change in word_diff.iter_all_changes() {
-            let text = change.value().to_string();
+            let value = change.value();
+            let text = match change.tag() {
+                ChangeTag::Equal => {
+                    old_pos += value.len();
+                    take(new, &mut new_pos, value)
+                }
+                ChangeTag::Delete => take(old, &mut old_pos, value),
+                ChangeTag::Insert => take(new, &mut new_pos, value),
+            };
             let span = match change.tag() {
                 ChangeTag::Equal => ChangeSpan::equal(text),
                 ChangeTag::Delete => ChangeSpan::delete(text),
@@ -463,6 +793,36 @@ mod tests {
         assert!(!result.significant_changes.is_empty());
     }
 
+    #[test]
+    fn test_spans_are_ranges_of_the_contents() {
+        let engine = DiffEngine::new().with_word_level(true);
+        let old = Text::from("fn a() {\n}\nfn b() {\n}\n");
+        let new = Text::from("fn a() {\n}\nfn c() {\n}\n");
+
+        let result = engine.diff_text(&old, &new);
+        let spans: Vec<&ChangeSpan> = result.changes.iter().flat_map(|c| &c.spans).collect();
+
+        assert!(spans
+            .iter()
+            .all(|span| span.text.shares_buffer(&old) || span.text.shares_buffer(&new)));
+        let renamed = spans
+            .iter()
+            .find(|span| span.kind == ChangeKind::Insert)
+            .unwrap();
+        assert_eq!(renamed.text, "c");
+        assert_eq!(renamed.text.range(), 14..15);
+        assert_eq!(&new[renamed.text.range()], "c");
+    }
+
+    #[test]
+    fn test_split_lines_matches_the_line_diff() {
+        assert_eq!(split_lines("a\rb\r\nc\nd"), ["a\r", "b\r\n", "c\n", "d"]);
+        assert!(split_lines("").is_empty());
+        let text = "a\rb\r\n\r\rc";
+        let diff = DiffEngine::new().diff_strings(text, "");
+        assert_eq!(diff.deletions, split_lines(text).len());
+    }
+
     #[test]
     fn test_no_changes() {
         let engine = DiffEngine::new();
@@ -487,9 +847,25 @@ mod tests {
         assert_eq!(result.significant_changes.len(), 1);
     }
 
+    #[test]
+    fn test_token_replacement() {
+        let engine = DiffEngine::new().with_word_level(true);
+        let replacement = |old: &str, new: &str| {
+            let result = engine.diff_strings(old, new);
+            result.changes[result.significant_changes[0]].token_replacement()
+        };
+
+        assert_eq!(
+            replacement("let foo = foo + 1;\n", "let bar = bar + 1;\n"),
+            Some(("foo".to_string(), "bar".to_string()))
+        );
+        assert_eq!(replacement("let foo = baz;\n", "let bar = qux;\n"), None);
+        assert_eq!(replacement("let foo = 1;\n", "let foo = 1 + 2;\n"), None);
+    }
+
     #[test]
     fn test_tokenize_code_basic() {
-        let tokens = tokenize_code("KeyModifiers, MouseEventKind}");
+        let tokens = CodeTokenizer.tokenize("KeyModifiers, MouseEventKind}");
         assert_eq!(
             tokens,
             vec!["KeyModifiers", ",", " ", "MouseEventKind", "}"]
@@ -498,13 +874,13 @@ mod tests {
 
     #[test]
     fn test_tokenize_code_identifiers() {
-        let tokens = tokenize_code("foo_bar baz123");
+        let tokens = CodeTokenizer.tokenize("foo_bar baz123");
         assert_eq!(tokens, vec!["foo_bar", " ", "baz123"]);
     }
 
     #[test]
     fn test_tokenize_code_punctuation() {
-        let tokens = tokenize_code("use foo::{A, B};");
+        let tokens = CodeTokenizer.tokenize("use foo::{A, B};");
         assert_eq!(
             tokens,
             vec!["use", " ", "foo", ":", ":", "{", "A", ",", " ", "B", "}", ";"]
@@ -564,4 +940,138 @@ mod tests {
             insert_content
         );
     }
+
+    #[test]
+    fn test_char_diff_keeps_grapheme_clusters() {
+        use crate::change::ChangeKind;
+        use crate::token::GraphemeTokenizer;
+
+        let engine = DiffEngine::new().with_tokenizer(Arc::new(GraphemeTokenizer));
+        // Only the combining accent differs
+        let result = engine.diff_strings("cafe\u{301} ok", "cafe\u{300} ok");
+        let change = &result.changes[result.significant_changes[0]];
+        let text_of = |kind: ChangeKind| -> Vec<&str> {
+            change
+                .spans
+                .iter()
+                .filter(|s| s.kind == kind)
+                .map(|s| s.text.as_str())
+                .collect()
+        };
+
+        assert_eq!(text_of(ChangeKind::Delete), vec!["e\u{301}"]);
+        assert_eq!(text_of(ChangeKind::Insert), vec!["e\u{300}"]);
+    }
+
+    #[test]
+    fn test_context_lines_controls_hunk_grouping() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "A\nb\nc\nd\ne\nF\ng\n";
+
+        let result = DiffEngine::new().diff_strings(old, new);
+        assert_eq!(result.hunks.len(), 2);
+
+        let result = DiffEngine::new().with_context(5).diff_strings(old, new);
+        assert_eq!(result.hunks.len(), 1);
+    }
+
+    #[test]
+    fn test_stop_patterns_keep_url_whole() {
+        use crate::change::ChangeKind;
+
+        let engine =
+            DiffEngine::new().with_stop_patterns(vec![Regex::new(r#"https?://[^\s"]+"#).unwrap()]);
+        let old = "let url = \"https://example.com/a/b\";";
+        let new = "let url = \"https://example.org/a/c\";";
+
+        let result = engine.diff_strings(old, new);
+        let change = &result.changes[result.significant_changes[0]];
+        let deleted: Vec<&str> = change
+            .spans
+            .iter()
+            .filter(|s| s.kind == ChangeKind::Delete)
+            .map(|s| s.text.as_str())
+            .collect();
+        let inserted: Vec<&str> = change
+            .spans
+            .iter()
+            .filter(|s| s.kind == ChangeKind::Insert)
+            .map(|s| s.text.as_str())
+            .collect();
+
+        assert_eq!(deleted, vec!["https://example.com/a/b"]);
+        assert_eq!(inserted, vec!["https://example.org/a/c"]);
+    }
+
+    #[test]
+    fn test_reindent_modes() {
+        let old = "fn main() {\n\tfoo();\n\tbar();\n}\n";
+        let new = "fn main() {\n    foo();\n    baz();\n}\n";
+
+        let result = DiffEngine::new().diff_strings(old, new);
+        assert_eq!(result.significant_changes.len(), 2);
+        assert!(result.reindent_changes.is_empty());
+
+        let result = DiffEngine::new()
+            .with_reindent(ReindentMode::Skip)
+            .diff_strings(old, new);
+        assert_eq!(result.significant_changes.len(), 1);
+        assert_eq!(result.reindent_changes.len(), 1);
+        assert_eq!(result.insertions, 2);
+
+        // Both reindented lines form one run: stepped once, together
+        let old = "fn main() {\n\tfoo();\n\tbar();\n\tqux();\n}\n";
+        let new = "fn main() {\n    foo();\n    bar();\n    quux();\n}\n";
+        let result = DiffEngine::new()
+            .with_reindent(ReindentMode::Collapse)
+            .diff_strings(old, new);
+        assert_eq!(result.significant_changes, [1, 3]);
+        assert_eq!(result.grouped_changes, [(2, 1)]);
+
+        let old = "fn main() {\n\tfoo();\n\tbar();\n}\n";
+        let new = "fn main() {\n    foo();\n    baz();\n}\n";
+        let result = DiffEngine::new()
+            .with_reindent(ReindentMode::Hide)
+            .diff_strings(old, new);
+        assert_eq!(result.significant_changes.len(), 1);
+        assert!(result.reindent_changes.is_empty());
+        assert_eq!(result.insertions, 1);
+        assert_eq!(result.deletions, 1);
+    }
+
+    #[test]
+    fn test_churn_buckets_changes_by_position() {
+        let old: String = (0..8).map(|i| format!("{i}\n")).collect();
+        let new = old.replace("0\n", "zero\n").replace("7\n", "seven\n");
+        let result = DiffEngine::new().diff_strings(&old, &new);
+        assert_eq!(result.churn(4), [1, 0, 0, 1]);
+        assert_eq!(
+            DiffEngine::new().diff_strings("a\n", "a\n").churn(2),
+            [0, 0]
+        );
+    }
+
+    #[test]
+    fn test_eof_newline_and_trailing_whitespace() {
+        let result = DiffEngine::new().diff_strings("a\nb", "a\nb\n");
+        assert_eq!(result.significant_changes.len(), 1);
+        let change_id = result.significant_changes[0];
+        assert_eq!(
+            result.eof_newline,
+            Some(EofNewline {
+                added: true,
+                change_id
+            })
+        );
+        assert_eq!(result.whitespace_changes, [change_id]);
+
+        let result = DiffEngine::new().diff_strings("a\nb\n", "a\nc");
+        let eof = result.eof_newline.expect("newline removed");
+        assert!(!eof.added);
+        assert!(result.whitespace_changes.is_empty());
+
+        let result = DiffEngine::new().diff_strings("x = 1;  \ny\n", "x = 1;\ny\n");
+        assert_eq!(result.whitespace_changes, result.significant_changes);
+        assert_eq!(result.eof_newline, None);
+    }
 }
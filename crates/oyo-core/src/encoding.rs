@@ -0,0 +1,105 @@
+//! Encoding and line-ending detection, for showing how a file is stored
+
+use std::fmt;
+
+/// Text encoding of a file's content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Ascii => "ASCII",
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 BOM",
+        })
+    }
+}
+
+/// Line endings used in a file's content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+    /// More than one kind
+    Mixed,
+    /// A single line without an ending
+    None,
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+            LineEnding::Mixed => "mixed",
+            LineEnding::None => "no EOL",
+        })
+    }
+}
+
+/// Encoding and line endings of one version of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+}
+
+impl TextFormat {
+    pub fn detect(text: &str) -> Self {
+        let encoding = if text.starts_with('\u{feff}') {
+            Encoding::Utf8Bom
+        } else if text.is_ascii() {
+            Encoding::Ascii
+        } else {
+            Encoding::Utf8
+        };
+        let (mut lf, mut crlf, mut cr) = (false, false, false);
+        let bytes = text.as_bytes();
+        for (i, &byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' if i > 0 && bytes[i - 1] == b'\r' => crlf = true,
+                b'\n' => lf = true,
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => cr = true,
+                _ => {}
+            }
+        }
+        let line_ending = match (lf, crlf, cr) {
+            (false, false, false) => LineEnding::None,
+            (true, false, false) => LineEnding::Lf,
+            (false, true, false) => LineEnding::CrLf,
+            (false, false, true) => LineEnding::Cr,
+            _ => LineEnding::Mixed,
+        };
+        TextFormat {
+            encoding,
+            line_ending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_encoding_and_line_endings() {
+        let detect = |text: &str| {
+            let format = TextFormat::detect(text);
+            (format.encoding.to_string(), format.line_ending.to_string())
+        };
+        assert_eq!(detect("a\nb\n"), ("ASCII".into(), "LF".into()));
+        assert_eq!(detect("é\r\nb\r\n"), ("UTF-8".into(), "CRLF".into()));
+        assert_eq!(detect("\u{feff}a\rb"), ("UTF-8 BOM".into(), "CR".into()));
+        assert_eq!(detect("a\r\nb\n"), ("ASCII".into(), "mixed".into()));
+        assert_eq!(detect("a"), ("ASCII".into(), "no EOL".into()));
+        assert_eq!(detect(""), ("ASCII".into(), "no EOL".into()));
+    }
+}
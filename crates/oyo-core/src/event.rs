@@ -0,0 +1,102 @@
+//! Navigation events
+//!
+//! Navigators and multi-file sessions publish typed events on an [`EventBus`]
+//! so frontends (TUI, exporters, remote control) can react to transitions
+//! instead of polling [`crate::StepState`].
+
+use crate::step::{AnimationFrame, StepDirection};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A navigation transition
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavEvent {
+    /// A step was entered (by stepping, seeking or hunk navigation)
+    StepEntered {
+        step: usize,
+        total_steps: usize,
+        /// Change highlighted at this step (if any)
+        change_id: Option<usize>,
+        direction: StepDirection,
+    },
+    /// The current hunk changed
+    HunkChanged { from: usize, to: usize },
+    /// A different file was selected
+    FileChanged {
+        from: usize,
+        to: usize,
+        path: PathBuf,
+    },
+    /// Animation progressed (emitted by the frontend driving the animation)
+    AnimationTick {
+        frame: AnimationFrame,
+        /// Progress of the current phase (0.0..=1.0)
+        progress: f32,
+    },
+}
+
+/// Fan-out of [`NavEvent`]s to any number of subscribers.
+///
+/// Clones share the same subscriber list. Subscribers whose receiver was
+/// dropped are removed on the next emit.
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<NavEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to all future events
+    pub fn subscribe(&self) -> Receiver<NavEvent> {
+        let (tx, rx) = channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// True if anyone is listening
+    pub fn has_subscribers(&self) -> bool {
+        self.subscribers
+            .lock()
+            .map(|subscribers| !subscribers.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Send an event to every subscriber
+    pub fn emit(&self, event: NavEvent) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus_fans_out_and_prunes() {
+        let bus = EventBus::new();
+        let first = bus.subscribe();
+        let second = bus.clone().subscribe();
+        let event = NavEvent::HunkChanged { from: 0, to: 1 };
+
+        bus.emit(event.clone());
+        assert_eq!(first.try_recv(), Ok(event.clone()));
+        assert_eq!(second.try_recv(), Ok(event.clone()));
+
+        drop(second);
+        bus.emit(event.clone());
+        assert_eq!(first.try_recv(), Ok(event));
+        assert!(bus.has_subscribers());
+        drop(first);
+        bus.emit(NavEvent::HunkChanged { from: 1, to: 2 });
+        assert!(!bus.has_subscribers());
+    }
+}
@@ -14,6 +14,9 @@ pub enum GitError {
     Io(#[from] std::io::Error),
 }
 
+/// Object ID of git's empty tree (diff base for root commits)
+pub const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 /// Status of a file in git
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileStatus {
@@ -24,6 +27,17 @@ pub enum FileStatus {
     Untracked,
 }
 
+impl FileStatus {
+    /// Status of the same change applied in reverse
+    pub fn reversed(self) -> Self {
+        match self {
+            FileStatus::Added | FileStatus::Untracked => FileStatus::Deleted,
+            FileStatus::Deleted => FileStatus::Added,
+            status => status,
+        }
+    }
+}
+
 /// A changed file in git
 #[derive(Debug, Clone)]
 pub struct ChangedFile {
@@ -53,6 +67,92 @@ pub struct CommitEntry {
     pub stats: Option<CommitStats>,
 }
 
+/// Full metadata for a single commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    pub author_email: String,
+    pub date: String,
+    /// Full commit message (subject and body)
+    pub message: String,
+    /// Signature verification result (`None` when not checked)
+    pub signature: Option<SignatureStatus>,
+}
+
+/// Result of verifying a commit signature (`git verify-commit`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Unsigned,
+    /// Verified; who signed, as reported by gpg or ssh
+    Good(String),
+    /// Signed, but not verified (unknown key, bad signature, ...)
+    Bad(String),
+}
+
+impl CommitInfo {
+    /// `Key: value` trailers of the message's last paragraph
+    /// (`Signed-off-by`, `Co-authored-by`, ...)
+    pub fn trailers(&self) -> Vec<(&str, &str)> {
+        self.trailer_block()
+            .map(|(_, block)| {
+                block
+                    .lines()
+                    .filter_map(|line| {
+                        let (key, value) = line.split_once(':')?;
+                        Some((key, value.trim()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The message without its trailers
+    pub fn body(&self) -> &str {
+        match self.trailer_block() {
+            Some((body, _)) => body.trim_end(),
+            None => &self.message,
+        }
+    }
+
+    /// Message split before its last paragraph, if that paragraph holds
+    /// only trailers (the subject never does)
+    fn trailer_block(&self) -> Option<(&str, &str)> {
+        let start = self.message.trim_end().rfind("\n\n")? + 2;
+        let block = self.message[start..].trim_end();
+        let is_trailer = |line: &str| {
+            line.split_once(": ").is_some_and(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        };
+        block
+            .lines()
+            .all(is_trailer)
+            .then(|| (&self.message[..start], block))
+    }
+}
+
+/// State of an in-progress `git bisect` session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BisectState {
+    /// Newest known-good commit that is an ancestor of the candidate
+    pub good: Option<String>,
+    /// Current bisect candidate (HEAD)
+    pub current: String,
+    /// Paths the bisect was restricted to (`git bisect start -- <paths>`)
+    pub paths: Vec<PathBuf>,
+}
+
+/// Ref names of the two sides of an in-progress merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeLabels {
+    /// The checked-out branch (HEAD)
+    pub current: String,
+    /// The branch being merged in (MERGE_HEAD)
+    pub incoming: String,
+}
+
 /// Check if a directory is a git repository
 pub fn is_git_repo(path: &Path) -> bool {
     Command::new("git")
@@ -99,7 +199,203 @@ pub fn get_repo_root(path: &Path) -> Result<PathBuf, GitError> {
     Ok(PathBuf::from(root))
 }
 
+/// Get the base that `git commit --amend` would diff against:
+/// HEAD's first parent, or the empty tree when HEAD is a root commit
+pub fn get_amend_base(repo_path: &Path) -> Result<String, GitError> {
+    let rev_parse = |rev: &str| -> Result<Option<String>, GitError> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_path)
+            .arg("rev-parse")
+            .arg("--verify")
+            .arg("--quiet")
+            .arg(rev)
+            .output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    };
+
+    if rev_parse("HEAD")?.is_none() {
+        return Err(GitError::CommandFailed("No commit to amend".to_string()));
+    }
+    Ok(rev_parse("HEAD^")?.unwrap_or_else(|| EMPTY_TREE.to_string()))
+}
+
+/// Get the commit ID of HEAD
+pub fn get_head_commit(repo_path: &Path) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-parse")
+        .arg("HEAD")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Full commit ID of `rev`
+pub fn resolve_commit(repo_path: &Path, rev: &str) -> Result<String, GitError> {
+    let spec = format!("{rev}^{{commit}}");
+    Ok(run_git(repo_path, &["rev-parse", "--verify", &spec], None)?
+        .trim()
+        .to_string())
+}
+
+/// Get the fetch URL of `remote` (e.g. "origin")
+pub fn get_remote_url(repo_path: &Path, remote: &str) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["remote", "get-url", remote])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the best common ancestor of two commits, which a pull request's
+/// changes are shown against
+pub fn get_merge_base(repo_path: &Path, a: &str, b: &str) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("merge-base")
+        .arg(a)
+        .arg(b)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the state of an in-progress bisect, or None if no bisect is running
+pub fn get_bisect_state(repo_path: &Path) -> Result<Option<BisectState>, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-parse")
+        .arg("--absolute-git-dir")
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::NotARepo);
+    }
+    let git_dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    if !git_dir.join("BISECT_START").exists() {
+        return Ok(None);
+    }
+
+    let current = get_head_commit(repo_path)?;
+    let paths = std::fs::read_to_string(git_dir.join("BISECT_NAMES"))
+        .map(|names| parse_bisect_names(&names))
+        .unwrap_or_default();
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("for-each-ref")
+        .arg("--format=%(objectname)")
+        .arg("refs/bisect/good-*")
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    // Pick the newest good commit on the candidate's history
+    let is_ancestor = |ancestor: &str, descendant: &str| -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_path)
+            .arg("merge-base")
+            .arg("--is-ancestor")
+            .arg(ancestor)
+            .arg(descendant)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    };
+    let mut good: Option<String> = None;
+    for candidate in String::from_utf8_lossy(&output.stdout).lines() {
+        let candidate = candidate.trim();
+        if candidate.is_empty() || !is_ancestor(candidate, &current) {
+            continue;
+        }
+        match &good {
+            Some(best) if !is_ancestor(best, candidate) => {}
+            _ => good = Some(candidate.to_string()),
+        }
+    }
+
+    Ok(Some(BisectState {
+        good,
+        current,
+        paths,
+    }))
+}
+
+/// Get the labels of an in-progress merge, or None if no merge is running
+pub fn get_merge_labels(repo_path: &Path) -> Result<Option<MergeLabels>, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-parse")
+        .arg("--absolute-git-dir")
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::NotARepo);
+    }
+    let git_dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let Ok(merge_head) = std::fs::read_to_string(git_dir.join("MERGE_HEAD")) else {
+        return Ok(None);
+    };
+
+    let incoming = std::fs::read_to_string(git_dir.join("MERGE_MSG"))
+        .ok()
+        .and_then(|msg| parse_merge_msg(&msg))
+        .unwrap_or_else(|| shorten(merge_head.trim()));
+    let current = match get_current_branch(repo_path)? {
+        branch if branch != "HEAD" => branch,
+        _ => shorten(&get_head_commit(repo_path)?),
+    };
+    Ok(Some(MergeLabels { current, incoming }))
+}
+
+fn shorten(id: &str) -> String {
+    id.chars().take(7).collect()
+}
+
+/// Name of the merged ref from the first line of MERGE_MSG, e.g.
+/// "Merge branch 'feature/x' into main" -> "feature/x"
+fn parse_merge_msg(msg: &str) -> Option<String> {
+    let subject = msg.lines().next()?.strip_prefix("Merge ")?;
+    let (_, rest) = subject.split_once('\'')?;
+    let (name, _) = rest.split_once('\'')?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
 /// Get list of uncommitted changed files (staged and unstaged)
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_uncommitted_changes(repo_path: &Path) -> Result<Vec<ChangedFile>, GitError> {
     let mut changes = Vec::new();
 
@@ -158,6 +454,7 @@ pub fn get_uncommitted_changes(repo_path: &Path) -> Result<Vec<ChangedFile>, Git
 }
 
 /// Get list of staged changed files (index vs HEAD)
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_staged_changes(repo_path: &Path) -> Result<Vec<ChangedFile>, GitError> {
     let output = Command::new("git")
         .arg("-C")
@@ -183,6 +480,17 @@ pub fn get_changes_between(
     repo_path: &Path,
     from: &str,
     to: &str,
+) -> Result<Vec<ChangedFile>, GitError> {
+    get_changes_between_paths(repo_path, from, to, &[])
+}
+
+/// Get changes between two commits or refs, limited to paths (all paths if empty)
+#[tracing::instrument(level = "debug", skip_all, fields(from = %from, to = %to))]
+pub fn get_changes_between_paths(
+    repo_path: &Path,
+    from: &str,
+    to: &str,
+    paths: &[PathBuf],
 ) -> Result<Vec<ChangedFile>, GitError> {
     let output = Command::new("git")
         .arg("-C")
@@ -190,6 +498,8 @@ pub fn get_changes_between(
         .arg("diff")
         .arg("--name-status")
         .arg(format!("{}..{}", from, to))
+        .arg("--")
+        .args(paths)
         .output()?;
 
     if !output.status.success() {
@@ -204,6 +514,7 @@ pub fn get_changes_between(
 }
 
 /// Get changes between a commit and the staged index (commit vs index)
+#[tracing::instrument(level = "debug", skip_all, fields(from = %from))]
 pub fn get_changes_between_index(
     repo_path: &Path,
     from: &str,
@@ -294,7 +605,90 @@ pub fn get_recent_commits(repo_path: &Path, limit: usize) -> Result<Vec<CommitEn
     Ok(commits)
 }
 
+/// Get metadata (message, author, date, parents) for a commit
+#[tracing::instrument(level = "debug", skip_all, fields(rev = %rev))]
+pub fn get_commit_info(repo_path: &Path, rev: &str) -> Result<CommitInfo, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("show")
+        .arg("-s")
+        .arg("--date=format:%Y-%m-%d %H:%M")
+        .arg("--pretty=format:%H%x1f%P%x1f%an%x1f%ae%x1f%ad%x1f%B")
+        .arg(rev)
+        .arg("--")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    parse_commit_info(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| GitError::CommandFailed(format!("Unexpected commit format for {rev}")))
+}
+
+/// Verify the signature of commit `rev` (gpg or ssh, per git config)
+#[tracing::instrument(level = "debug", skip_all, fields(rev = %rev))]
+pub fn verify_commit(repo_path: &Path, rev: &str) -> Result<SignatureStatus, GitError> {
+    let object = run_git(repo_path, &["cat-file", "commit", rev], None)?;
+    let headers = object.split("\n\n").next().unwrap_or_default();
+    if !headers
+        .lines()
+        .any(|line| line.starts_with("gpgsig ") || line.starts_with("gpgsig-sha256 "))
+    {
+        return Ok(SignatureStatus::Unsigned);
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("verify-commit")
+        .arg(rev)
+        .output()?;
+    let report = String::from_utf8_lossy(&output.stderr);
+    Ok(parse_verify_report(output.status.success(), &report))
+}
+
+fn parse_verify_report(success: bool, report: &str) -> SignatureStatus {
+    let lines = || {
+        report
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+    };
+    if success {
+        // gpg: `Good signature from "Name <email>"`, ssh: `Good "git"
+        // signature for name with ED25519 key SHA256:...`
+        let signer = lines()
+            .find_map(|line| {
+                let rest = &line[line.find("Good ")?..];
+                let (_, signer) = rest
+                    .split_once(" from ")
+                    .or_else(|| rest.split_once(" for "))?;
+                let signer = match signer.trim().strip_prefix('"') {
+                    Some(quoted) => quoted.split('"').next().unwrap_or(quoted),
+                    None => signer.split(" with ").next().unwrap_or(signer).trim(),
+                };
+                Some(signer.to_string())
+            })
+            .unwrap_or_default();
+        SignatureStatus::Good(signer)
+    } else {
+        let reason = lines()
+            .find(|line| {
+                let lower = line.to_ascii_lowercase();
+                lower.contains("bad") || lower.contains("can't") || lower.contains("no public key")
+            })
+            .or_else(|| lines().next_back())
+            .map(|line| line.trim_start_matches("gpg: ").to_string())
+            .unwrap_or_default();
+        SignatureStatus::Bad(reason)
+    }
+}
+
 /// Get the content of a file at a specific commit
+#[tracing::instrument(level = "debug", skip_all, fields(commit = %commit, file = %file.display()))]
 pub fn get_file_at_commit(repo_path: &Path, commit: &str, file: &Path) -> Result<String, GitError> {
     let output = Command::new("git")
         .arg("-C")
@@ -312,6 +706,243 @@ pub fn get_file_at_commit(repo_path: &Path, commit: &str, file: &Path) -> Result
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
+/// Outcome of prefetching a blob that was missing from a partial clone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobFetch {
+    Fetched,
+    Failed,
+}
+
+/// A blob named by revision and path
+pub type BlobSpec = (String, PathBuf);
+
+/// Remote that lazily serves missing objects, if this is a partial clone
+pub fn promisor_remote(repo_path: &Path) -> Option<String> {
+    if let Ok(remote) = run_git(
+        repo_path,
+        &["config", "--get", "extensions.partialClone"],
+        None,
+    ) {
+        if !remote.is_empty() {
+            return Some(remote);
+        }
+    }
+    let promisors = run_git(
+        repo_path,
+        &["config", "--get-regexp", r"^remote\..*\.promisor$"],
+        None,
+    )
+    .ok()?;
+    promisors.lines().find_map(|line| {
+        let (key, value) = line.split_once(' ')?;
+        let remote = key.strip_prefix("remote.")?.strip_suffix(".promisor")?;
+        (value == "true").then(|| remote.to_string())
+    })
+}
+
+/// Object ids (of `rev:path` blobs) that are not present locally
+fn missing_blobs(
+    repo_path: &Path,
+    wanted: &[BlobSpec],
+) -> Result<Vec<(String, BlobSpec)>, GitError> {
+    use std::collections::BTreeMap;
+
+    let mut by_rev: BTreeMap<&str, Vec<&PathBuf>> = BTreeMap::new();
+    for (rev, path) in wanted {
+        by_rev.entry(rev).or_default().push(path);
+    }
+    // Trees are present in blob-less clones, so listing them never fetches;
+    // `rev-list --missing=print` reports absent blobs as `?<oid>` without
+    // fetching them
+    let mut missing = Vec::new();
+    for (rev, paths) in by_rev {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        let with_paths = |args: &[&'static str]| {
+            let mut args: Vec<&str> = args.to_vec();
+            args.push(rev);
+            args.push("--");
+            args.extend(paths.iter().map(String::as_str));
+            args
+        };
+
+        let listed = run_git(
+            repo_path,
+            &with_paths(&["rev-list", "--objects", "--missing=print", "--no-walk"]),
+            None,
+        )?;
+        let absent: std::collections::HashSet<&str> = listed
+            .lines()
+            .filter_map(|line| line.strip_prefix('?'))
+            .collect();
+        if absent.is_empty() {
+            continue;
+        }
+
+        for line in run_git(repo_path, &with_paths(&["ls-tree"]), None)?.lines() {
+            let Some((meta, path)) = line.split_once('\t') else {
+                continue;
+            };
+            if let [_, "blob", oid] = meta.split_whitespace().collect::<Vec<_>>()[..] {
+                if absent.contains(oid) {
+                    missing.push((oid.to_string(), (rev.to_string(), PathBuf::from(path))));
+                }
+            }
+        }
+    }
+    Ok(missing)
+}
+
+/// In a partial clone, fetch the missing blobs of `wanted` (`(rev, path)`
+/// pairs) in one batch instead of one lazy fetch per file. Returns the
+/// outcome for each blob that was missing.
+#[tracing::instrument(level = "debug", skip_all, fields(blobs = wanted.len()))]
+pub fn prefetch_blobs(
+    repo_path: &Path,
+    wanted: &[BlobSpec],
+) -> Result<Vec<(BlobSpec, BlobFetch)>, GitError> {
+    let Some(remote) = promisor_remote(repo_path) else {
+        return Ok(Vec::new());
+    };
+    let missing = missing_blobs(repo_path, wanted)?;
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+    let oids: String = missing.iter().map(|(oid, _)| format!("{oid}\n")).collect();
+    let fetched = run_git(
+        repo_path,
+        &[
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            &remote,
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+            "--stdin",
+        ],
+        Some(&oids),
+    )
+    .is_ok();
+    let status = if fetched {
+        BlobFetch::Fetched
+    } else {
+        BlobFetch::Failed
+    };
+    Ok(missing
+        .into_iter()
+        .map(|(_, blob)| (blob, status))
+        .collect())
+}
+
+/// Get the content of a blob by (possibly abbreviated) object id
+#[tracing::instrument(level = "debug", skip_all, fields(id = %id))]
+pub fn get_blob(repo_path: &Path, id: &str) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("cat-file")
+        .arg("blob")
+        .arg(id)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub(crate) fn run_git(
+    repo_path: &Path,
+    args: &[&str],
+    stdin: Option<&str>,
+) -> Result<String, GitError> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// True if the index differs from HEAD
+pub fn has_staged_changes(repo_path: &Path) -> Result<bool, GitError> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("diff")
+        .arg("--cached")
+        .arg("--quiet")
+        .status()?;
+    match status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(GitError::NotARepo),
+    }
+}
+
+/// Stage `content` as the new content of `file` (or remove it from the index
+/// with `None`) without touching the working tree
+pub fn stage_content(repo_path: &Path, file: &Path, content: Option<&str>) -> Result<(), GitError> {
+    let path = file.to_string_lossy();
+    let Some(content) = content else {
+        run_git(
+            repo_path,
+            &["update-index", "--force-remove", "--", &path],
+            None,
+        )?;
+        return Ok(());
+    };
+    let blob = run_git(repo_path, &["hash-object", "-w", "--stdin"], Some(content))?;
+    let mode = run_git(repo_path, &["ls-files", "-s", "--", &path], None)?
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .unwrap_or_else(|| "100644".to_string());
+    run_git(
+        repo_path,
+        &[
+            "update-index",
+            "--add",
+            "--cacheinfo",
+            &format!("{mode},{blob},{path}"),
+        ],
+        None,
+    )?;
+    Ok(())
+}
+
+/// Commit the index; returns the new commit ID
+pub fn commit_index(repo_path: &Path, message: &str) -> Result<String, GitError> {
+    run_git(repo_path, &["commit", "-q", "-F", "-"], Some(message))?;
+    get_head_commit(repo_path)
+}
+
 /// Get the staged content of a file
 pub fn get_staged_content(repo_path: &Path, file: &Path) -> Result<String, GitError> {
     let output = Command::new("git")
@@ -334,6 +965,76 @@ pub fn get_head_content(repo_path: &Path, file: &Path) -> Result<String, GitErro
     get_file_at_commit(repo_path, "HEAD", file)
 }
 
+/// Subjects and touched files of the (non-merge) commits in `from..to`
+#[tracing::instrument(level = "debug", skip_all, fields(from = %from, to = %to))]
+pub fn get_range_commits(
+    repo_path: &Path,
+    from: &str,
+    to: &str,
+) -> Result<Vec<crate::conventional::RangeCommit>, GitError> {
+    let range = format!("{from}..{to}");
+    let output = run_git(
+        repo_path,
+        &[
+            "log",
+            "--no-merges",
+            "--format=%x1e%s",
+            "--name-only",
+            &range,
+        ],
+        None,
+    )?;
+    Ok(parse_range_commits(&output))
+}
+
+fn parse_range_commits(output: &str) -> Vec<crate::conventional::RangeCommit> {
+    output
+        .split('\x1e')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let mut lines = entry.lines();
+            let subject = lines.next().unwrap_or_default().to_string();
+            let files = lines
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect();
+            crate::conventional::RangeCommit { subject, files }
+        })
+        .collect()
+}
+
+/// Tracked and untracked (not ignored) files of the working tree, relative
+/// to the repository root
+pub fn list_files(repo_path: &Path) -> Result<Vec<String>, GitError> {
+    let output = run_git(
+        repo_path,
+        &["ls-files", "--cached", "--others", "--exclude-standard"],
+        None,
+    )?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// Commit time (unix seconds) of each line of `file` at `rev`, from `git blame`
+#[tracing::instrument(level = "debug", skip_all, fields(rev = %rev, file = %file.display()))]
+pub fn blame_commit_times(repo_path: &Path, rev: &str, file: &Path) -> Result<Vec<i64>, GitError> {
+    let file = file.to_string_lossy();
+    let output = run_git(
+        repo_path,
+        &["blame", "--line-porcelain", rev, "--", &file],
+        None,
+    )?;
+    Ok(parse_blame_times(&output))
+}
+
+fn parse_blame_times(output: &str) -> Vec<i64> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("committer-time "))
+        .filter_map(|time| time.trim().parse().ok())
+        .collect()
+}
+
 fn parse_name_status(output: &str, changes: &mut Vec<ChangedFile>) {
     for line in output.lines() {
         let line = line.trim();
@@ -372,6 +1073,61 @@ fn parse_name_status(output: &str, changes: &mut Vec<ChangedFile>) {
     }
 }
 
+/// Parse `.git/BISECT_NAMES` (shell-quoted pathspecs, e.g. `'--' 'src' 'it'\''s.rs'`)
+fn parse_bisect_names(names: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut current = String::new();
+    let mut in_quote = false;
+    let mut has_token = false;
+    let mut chars = names.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' => {
+                in_quote = !in_quote;
+                has_token = true;
+            }
+            '\\' if !in_quote => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    has_token = true;
+                }
+            }
+            c if c.is_whitespace() && !in_quote => {
+                if has_token {
+                    paths.push(PathBuf::from(std::mem::take(&mut current)));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        paths.push(PathBuf::from(current));
+    }
+    paths.retain(|path| path.as_os_str() != "--");
+    paths
+}
+
+fn parse_commit_info(output: &str) -> Option<CommitInfo> {
+    let parts: Vec<&str> = output.splitn(6, '\u{1f}').collect();
+    if parts.len() < 6 {
+        return None;
+    }
+    Some(CommitInfo {
+        id: parts[0].trim().to_string(),
+        parents: parts[1].split_whitespace().map(|s| s.to_string()).collect(),
+        author: parts[2].to_string(),
+        author_email: parts[3].to_string(),
+        date: parts[4].to_string(),
+        message: parts[5].trim_end().to_string(),
+        signature: None,
+    })
+}
+
 fn parse_shortstat(line: &str) -> Option<CommitStats> {
     if !line.contains("file changed") && !line.contains("files changed") {
         return None;
@@ -419,4 +1175,109 @@ mod tests {
         assert_eq!(changes[1].status, FileStatus::Added);
         assert_eq!(changes[2].status, FileStatus::Deleted);
     }
+
+    #[test]
+    fn test_parse_blame_times() {
+        let output = "abc 1 1 1\nauthor A\ncommitter-time 100\nfilename a\n\tcommitter-time 5\n\
+                      def 2 2 1\ncommitter-time 200\n\tline two\n";
+        assert_eq!(parse_blame_times(output), vec![100, 200]);
+    }
+
+    #[test]
+    fn test_parse_range_commits() {
+        let output = "\x1efeat: b\n\nsrc/b.rs\n\x1eMerge x\n\x1efix: a\n\nsrc/a.rs\nsrc/b.rs";
+        let commits = parse_range_commits(output);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].subject, "feat: b");
+        assert_eq!(commits[0].files, vec![PathBuf::from("src/b.rs")]);
+        assert!(commits[1].files.is_empty());
+        assert_eq!(commits[2].files.len(), 2);
+    }
+
+    #[test]
+    fn test_commit_trailers() {
+        let info = CommitInfo {
+            id: String::new(),
+            parents: Vec::new(),
+            author: String::new(),
+            author_email: String::new(),
+            date: String::new(),
+            message: "Subject\n\nBody: not a trailer here\nmore\n\nSigned-off-by: A <a@x>\nCo-authored-by: B <b@x>"
+                .to_string(),
+            signature: None,
+        };
+        assert_eq!(
+            info.trailers(),
+            vec![("Signed-off-by", "A <a@x>"), ("Co-authored-by", "B <b@x>")]
+        );
+        assert_eq!(info.body(), "Subject\n\nBody: not a trailer here\nmore");
+
+        let info = CommitInfo {
+            message: "fix: thing".to_string(),
+            ..info
+        };
+        assert!(info.trailers().is_empty());
+        assert_eq!(info.body(), "fix: thing");
+    }
+
+    #[test]
+    fn test_parse_verify_report() {
+        assert_eq!(
+            parse_verify_report(
+                true,
+                "gpg: Signature made Mon\ngpg: Good signature from \"Jane <j@x>\" [ultimate]\n"
+            ),
+            SignatureStatus::Good("Jane <j@x>".to_string())
+        );
+        assert_eq!(
+            parse_verify_report(
+                true,
+                "Good \"git\" signature for jane with ED25519 key SHA256:abc\n"
+            ),
+            SignatureStatus::Good("jane".to_string())
+        );
+        assert_eq!(
+            parse_verify_report(
+                false,
+                "gpg: Signature made Mon\ngpg: Can't check signature: No public key\n"
+            ),
+            SignatureStatus::Bad("Can't check signature: No public key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_commit_info() {
+        let output = "abc123\x1fp1 p2\x1fJane\x1fjane@example.com\x1f2024-01-02 03:04\x1fSubject\n\nBody line\n";
+        let info = parse_commit_info(output).unwrap();
+
+        assert_eq!(info.id, "abc123");
+        assert_eq!(info.parents, vec!["p1", "p2"]);
+        assert_eq!(info.author, "Jane");
+        assert_eq!(info.author_email, "jane@example.com");
+        assert_eq!(info.date, "2024-01-02 03:04");
+        assert_eq!(info.message, "Subject\n\nBody line");
+    }
+
+    #[test]
+    fn test_parse_bisect_names() {
+        let names = " '--' 'src/lib.rs' 'docs'\\''s dir'\n";
+        assert_eq!(
+            parse_bisect_names(names),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("docs's dir")]
+        );
+        assert!(parse_bisect_names("\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_merge_msg() {
+        assert_eq!(
+            parse_merge_msg("Merge branch 'feature/x' into main\n\n# Conflicts:\n"),
+            Some("feature/x".to_string())
+        );
+        assert_eq!(
+            parse_merge_msg("Merge remote-tracking branch 'origin/dev'\n"),
+            Some("origin/dev".to_string())
+        );
+        assert_eq!(parse_merge_msg("Revert \"x\"\n"), None);
+    }
 }
@@ -0,0 +1,308 @@
+//! Suggested logical groups of hunks across files
+//!
+//! A first pass toward splitting a change into commits: hunks that touch the
+//! same symbol, or import a name that another hunk uses, end up together;
+//! whatever is left is grouped by directory.
+
+use crate::change::{Change, ChangeKind};
+use crate::diff::{DiffResult, Hunk};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// A hunk of one file of a multi-file diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HunkRef {
+    /// Index of the file in the diff
+    pub file: usize,
+    /// Index of the hunk in the file's diff
+    pub hunk: usize,
+}
+
+/// Why hunks were grouped together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupReason {
+    /// Defined or changed the same symbol
+    Symbol(String),
+    /// One hunk imports a name another hunk uses
+    ImportUsage(String),
+    /// Nothing in common but the directory
+    Directory(PathBuf),
+    /// Review queue entry with this risk score (see [`crate::risk`])
+    Risk(u32),
+}
+
+impl GroupReason {
+    pub fn label(&self) -> String {
+        match self {
+            GroupReason::Symbol(name) => format!("symbol {name}"),
+            GroupReason::ImportUsage(name) => format!("import {name}"),
+            GroupReason::Directory(dir) if dir.as_os_str().is_empty() => "dir ./".to_string(),
+            GroupReason::Directory(dir) => format!("dir {}/", dir.display()),
+            GroupReason::Risk(score) => format!("risk {score}"),
+        }
+    }
+}
+
+/// A suggested logical group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkGroup {
+    pub reason: GroupReason,
+    /// Hunks in file order
+    pub hunks: Vec<HunkRef>,
+}
+
+/// Keywords that introduce a definition; the next identifier is its name
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "def",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "interface",
+    "type",
+    "function",
+    "func",
+    "impl",
+    "const",
+    "static",
+    "mod",
+];
+
+/// Line prefixes of import statements
+const IMPORT_PREFIXES: &[&str] = &[
+    "use ", "pub use ", "import ", "from ", "#include", "require", "const ",
+];
+
+/// Identifiers of import lines that never name the imported item
+const IMPORT_NOISE: &[&str] = &[
+    "use", "pub", "crate", "self", "super", "import", "from", "as", "include", "require", "const",
+    "std",
+];
+
+/// Names a hunk touches
+#[derive(Debug, Default)]
+struct HunkNames {
+    defined: Vec<String>,
+    imported: Vec<String>,
+    used: Vec<String>,
+}
+
+fn identifiers(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.len() >= 3 && word.starts_with(|c: char| c.is_alphabetic()))
+}
+
+fn changed_lines(hunk: &Hunk, changes: &[Change]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for change in hunk
+        .change_ids
+        .iter()
+        .filter_map(|id| changes.iter().find(|c| c.id == *id))
+    {
+        let mut old = String::new();
+        let mut new = String::new();
+        for span in &change.spans {
+            match span.kind {
+                ChangeKind::Equal => {
+                    old.push_str(&span.text);
+                    new.push_str(&span.text);
+                }
+                ChangeKind::Delete => old.push_str(&span.text),
+                ChangeKind::Insert => new.push_str(&span.text),
+                ChangeKind::Replace => {
+                    old.push_str(&span.text);
+                    new.push_str(span.new_text.as_deref().unwrap_or_default());
+                }
+            }
+        }
+        lines.extend(
+            [old, new]
+                .into_iter()
+                .filter(|line| !line.trim().is_empty()),
+        );
+    }
+    lines
+}
+
+fn hunk_names(hunk: &Hunk, changes: &[Change]) -> HunkNames {
+    let mut names = HunkNames::default();
+    for line in changed_lines(hunk, changes) {
+        let trimmed = line.trim_start();
+        let is_import = IMPORT_PREFIXES.iter().any(|p| trimmed.starts_with(p))
+            && (!trimmed.starts_with("const ") || trimmed.contains("require("));
+        if is_import {
+            names.imported.extend(
+                identifiers(trimmed)
+                    .filter(|word| !IMPORT_NOISE.contains(word))
+                    .map(str::to_string),
+            );
+            continue;
+        }
+        let words: Vec<&str> = trimmed
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        for pair in words.windows(2) {
+            if DEFINITION_KEYWORDS.contains(&pair[0]) && identifiers(pair[1]).next().is_some() {
+                names.defined.push(pair[1].to_string());
+            }
+        }
+        names.used.extend(identifiers(trimmed).map(str::to_string));
+    }
+    names
+}
+
+/// Union-find over hunk indices
+struct Groups {
+    parent: Vec<usize>,
+    reason: Vec<Option<GroupReason>>,
+}
+
+impl Groups {
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        self.parent[index] = root;
+        root
+    }
+
+    fn join(&mut self, a: usize, b: usize, reason: &GroupReason) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (root, child) = (a.min(b), a.max(b));
+        self.parent[child] = root;
+        if self.reason[root].is_none() {
+            self.reason[root] = self.reason[child].take().or(Some(reason.clone()));
+        }
+    }
+}
+
+/// Cluster the hunks of several files (`(path, diff)` per file) into
+/// suggested groups, largest first
+pub fn group_hunks(files: &[(&Path, &DiffResult)]) -> Vec<HunkGroup> {
+    let mut refs = Vec::new();
+    let mut names = Vec::new();
+    for (file, (_, diff)) in files.iter().enumerate() {
+        for (hunk_index, hunk) in diff.hunks.iter().enumerate() {
+            refs.push(HunkRef {
+                file,
+                hunk: hunk_index,
+            });
+            names.push(hunk_names(hunk, &diff.changes));
+        }
+    }
+
+    let mut groups = Groups {
+        parent: (0..refs.len()).collect(),
+        reason: vec![None; refs.len()],
+    };
+
+    // Hunks touching a symbol that one of them defines
+    let mut definers: HashMap<&str, usize> = HashMap::new();
+    for (index, hunk) in names.iter().enumerate() {
+        for name in &hunk.defined {
+            match definers.get(name.as_str()) {
+                Some(&first) => groups.join(first, index, &GroupReason::Symbol(name.clone())),
+                None => {
+                    definers.insert(name, index);
+                }
+            }
+        }
+    }
+    for (index, hunk) in names.iter().enumerate() {
+        for name in &hunk.used {
+            if let Some(&definer) = definers.get(name.as_str()) {
+                groups.join(definer, index, &GroupReason::Symbol(name.clone()));
+            }
+        }
+    }
+
+    // Imports paired with the hunks using the imported name
+    let mut users: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, hunk) in names.iter().enumerate() {
+        for name in &hunk.used {
+            users.entry(name).or_default().push(index);
+        }
+    }
+    for (index, hunk) in names.iter().enumerate() {
+        for name in &hunk.imported {
+            for &user in users.get(name.as_str()).into_iter().flatten() {
+                groups.join(index, user, &GroupReason::ImportUsage(name.clone()));
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..refs.len() {
+        let root = groups.find(index);
+        clusters.entry(root).or_default().push(index);
+    }
+
+    let mut result = Vec::new();
+    let mut by_dir: BTreeMap<PathBuf, Vec<HunkRef>> = BTreeMap::new();
+    for (root, members) in clusters {
+        match groups.reason[root].take() {
+            Some(reason) if members.len() > 1 => result.push(HunkGroup {
+                reason,
+                hunks: members.iter().map(|&index| refs[index]).collect(),
+            }),
+            _ => {
+                for index in members {
+                    let dir = files[refs[index].file]
+                        .0
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_default();
+                    by_dir.entry(dir).or_default().push(refs[index]);
+                }
+            }
+        }
+    }
+    result.extend(by_dir.into_iter().map(|(dir, hunks)| HunkGroup {
+        reason: GroupReason::Directory(dir),
+        hunks,
+    }));
+    result.sort_by_key(|group| std::cmp::Reverse(group.hunks.len()));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffEngine;
+
+    #[test]
+    fn test_group_hunks_by_symbol_and_import() {
+        let engine = DiffEngine::new();
+        let lib = engine.diff_strings("fn keep() {}\n", "fn keep() {}\nfn parse_config() {}\n");
+        let main = engine.diff_strings(
+            "fn main() {\n}\n",
+            "use lib::Loader;\nfn main() {\n    parse_config();\n}\n",
+        );
+        let loader = engine.diff_strings("", "pub struct Loader;\n");
+        let docs = engine.diff_strings("a\n", "b\n");
+        let files = [
+            (Path::new("src/lib.rs"), &lib),
+            (Path::new("src/main.rs"), &main),
+            (Path::new("src/loader.rs"), &loader),
+            (Path::new("docs/readme.md"), &docs),
+        ];
+
+        let groups = group_hunks(&files);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].hunks.len(), 3);
+        assert!(matches!(groups[0].reason, GroupReason::Symbol(_)));
+        assert_eq!(
+            groups[1],
+            HunkGroup {
+                reason: GroupReason::Directory(PathBuf::from("docs")),
+                hunks: vec![HunkRef { file: 3, hunk: 0 }],
+            }
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Invariants every [`DiffResult`] must hold, checked by the fuzz targets
+//! (`fuzz/`) and by anything that relies on them: materializing the steps
+//! reproduces both files, line numbers only move forward, and every
+//! significant change belongs to a hunk
+
+use crate::change::{Change, ChangeSpan};
+use crate::diff::DiffResult;
+use crate::step::DiffNavigator;
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    #[error("step {step} does not reproduce the {side} content")]
+    RoundTrip { step: usize, side: &'static str },
+    #[error("change {change_id}: {side} line {line} comes after line {previous}")]
+    LineOrder {
+        change_id: usize,
+        side: &'static str,
+        line: usize,
+        previous: usize,
+    },
+    #[error("significant change {0} is in no hunk")]
+    Uncovered(usize),
+}
+
+/// Check every invariant of `diff`, the diff of `old` and `new`. Diffs made
+/// with [`ReindentMode::Hide`](crate::diff::ReindentMode::Hide) show hidden
+/// reindents as context with their new text, so their first step is not
+/// `old`; check those with the individual functions instead.
+pub fn check(diff: &DiffResult, old: &str, new: &str) -> Result<(), InvariantViolation> {
+    check_line_order(diff)?;
+    check_hunk_coverage(diff)?;
+    check_round_trip(diff, old, new)
+}
+
+/// The last step materializes `new`, and the first step `old` unless some
+/// changes are never stepped through (skipped reindents stay applied)
+pub fn check_round_trip(diff: &DiffResult, old: &str, new: &str) -> Result<(), InvariantViolation> {
+    let nav = DiffNavigator::new(diff.clone(), old.to_string(), new.to_string());
+    let last = nav.state().total_steps.saturating_sub(1);
+    if nav.materialize(last) != new {
+        return Err(InvariantViolation::RoundTrip {
+            step: last,
+            side: "new",
+        });
+    }
+    let all_stepped = diff.reindent_changes.iter().all(|id| {
+        diff.significant_changes.contains(id)
+            || diff
+                .grouped_changes
+                .iter()
+                .any(|&(grouped, _)| grouped == *id)
+    });
+    if all_stepped && nav.materialize(0) != old {
+        return Err(InvariantViolation::RoundTrip {
+            step: 0,
+            side: "old",
+        });
+    }
+    Ok(())
+}
+
+/// Old and new line numbers never decrease within a change and strictly
+/// increase from one change to the next
+pub fn check_line_order(diff: &DiffResult) -> Result<(), InvariantViolation> {
+    fn check_side(
+        changes: &[Change],
+        side: &'static str,
+        line_of: fn(&ChangeSpan) -> Option<usize>,
+    ) -> Result<(), InvariantViolation> {
+        let mut previous = 0;
+        for change in changes {
+            let first = previous;
+            for line in change.spans.iter().filter_map(line_of) {
+                if line <= first || line < previous {
+                    return Err(InvariantViolation::LineOrder {
+                        change_id: change.id,
+                        side,
+                        line,
+                        previous,
+                    });
+                }
+                previous = line;
+            }
+        }
+        Ok(())
+    }
+    check_side(&diff.changes, "old", |span| span.old_line)?;
+    check_side(&diff.changes, "new", |span| span.new_line)
+}
+
+/// Every significant change is listed by a hunk
+pub fn check_hunk_coverage(diff: &DiffResult) -> Result<(), InvariantViolation> {
+    let covered: HashSet<usize> = diff
+        .hunks
+        .iter()
+        .flat_map(|hunk| hunk.change_ids.iter().copied())
+        .collect();
+    match diff
+        .significant_changes
+        .iter()
+        .find(|id| !covered.contains(id))
+    {
+        Some(&id) => Err(InvariantViolation::Uncovered(id)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{DiffEngine, ReindentMode};
+
+    const CASES: &[(&str, &str)] = &[
+        ("", ""),
+        ("", "a\nb\n"),
+        ("a\nb\n", ""),
+        ("a\nb\nc\n", "a\nB\nc\nd\n"),
+        ("a\nb", "a\nb\n"),
+        ("x\r\ny\r\n", "x\r\nz\r\n"),
+        ("a\r", "a\rb\n"),
+        ("a\rb\nc\n", "a\rb\nC\n"),
+        ("a\rb\rc\r", "a\rB\rc\rd\r"),
+        ("a\r\nb\rc\n", "a\nb\r\nc\r"),
+        ("x = 1\ry = 2\n", "x = 1\ry = 3\n"),
+        ("same\nsame\nsame\n", "same\nother\nsame\nsame\n"),
+        (
+            "fn f() {\n  let a = 1;\n}\n",
+            "fn f() {\n    let a = 2;\n}\n",
+        ),
+        (
+            "if x {\n\ta();\n\tb();\n\tc();\n}\n",
+            "if x {\n    a();\n    b();\n    c();\n}\n",
+        ),
+        ("ünï\ncödé\n", "ünï\ncode\n"),
+    ];
+
+    #[test]
+    fn test_invariants_hold() {
+        for &(old, new) in CASES {
+            for word_level in [false, true] {
+                for reindent in [
+                    ReindentMode::Off,
+                    ReindentMode::Skip,
+                    ReindentMode::Collapse,
+                ] {
+                    let diff = DiffEngine::new()
+                        .with_word_level(word_level)
+                        .with_reindent(reindent)
+                        .diff_strings(old, new);
+                    assert_eq!(
+                        check(&diff, old, new),
+                        Ok(()),
+                        "{old:?} -> {new:?} (word level {word_level}, {reindent:?})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invariants_catch_violations() {
+        let (old, new) = ("a\nb\nc\n", "a\nB\nc\nd\n");
+        let diff = DiffEngine::new().diff_strings(old, new);
+
+        let mut uncovered = diff.clone();
+        uncovered.hunks.clear();
+        assert!(matches!(
+            check_hunk_coverage(&uncovered),
+            Err(InvariantViolation::Uncovered(_))
+        ));
+
+        let mut reordered = diff.clone();
+        reordered.changes.swap(0, 2);
+        assert!(matches!(
+            check_line_order(&reordered),
+            Err(InvariantViolation::LineOrder { .. })
+        ));
+
+        let mut truncated = diff.clone();
+        truncated.changes.pop();
+        assert!(matches!(
+            check_round_trip(&truncated, old, new),
+            Err(InvariantViolation::RoundTrip { side: "new", .. })
+        ));
+    }
+}
@@ -3,17 +3,53 @@
 //! This library provides data structures and algorithms for computing
 //! and navigating through diffs in a step-by-step manner.
 
+pub mod bucket;
 pub mod change;
+pub mod conventional;
 pub mod diff;
+pub mod encoding;
+pub mod event;
 pub mod git;
+pub mod group;
+pub mod invariants;
+pub mod merge;
 pub mod multi;
+pub mod notes;
+pub mod origin;
+pub mod p4;
+pub mod patch;
+pub mod provider;
+pub mod risk;
 pub mod step;
+pub mod structural;
+pub mod svn;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod text;
+pub mod token;
+pub mod writeback;
 
+pub use bucket::{Bucket, PlannedCommit, PlannedFile};
 pub use change::{Change, ChangeKind, ChangeSpan};
-pub use diff::{DiffEngine, DiffResult, FileDiff, Hunk};
+pub use diff::{
+    DiffAlgorithm, DiffEngine, DiffResult, EofNewline, FileDiff, Hunk, ReindentMode,
+    WordStopPatterns,
+};
+pub use encoding::{Encoding, LineEnding, TextFormat};
+pub use event::{EventBus, NavEvent};
 pub use git::{ChangedFile, FileStatus};
-pub use multi::{FileEntry, MultiFileDiff};
+pub use group::{GroupReason, HunkGroup, HunkRef};
+pub use multi::{DiskStamp, FileEntry, FileRevision, MultiFileDiff};
+pub use origin::ChangeOrigin;
+pub use patch::{ApplyCheck, HunkFit, Patch, PatchError, RejectFile};
+#[cfg(feature = "imara")]
+pub use provider::ImaraProvider;
+pub use provider::{DiffProvider, DifftasticProvider};
+pub use risk::{HunkRisk, RiskFactor};
 pub use step::{
-    AnimationFrame, DiffNavigator, LineKind, StepDirection, StepState, ViewLine, ViewSpan,
-    ViewSpanKind,
+    AnimationEasing, AnimationFrame, AnimationTiming, DiffNavigator, HunkContext, LineKind,
+    StepDirection, StepOrder, StepState, ViewAnchor, ViewLine, ViewSpan, ViewSpanKind,
 };
+pub use text::Text;
+pub use token::{CodeTokenizer, GraphemeTokenizer, Tokenizer};
+pub use writeback::{WriteBackError, WriteOptions};
@@ -0,0 +1,131 @@
+//! Three-way merge of text
+//!
+//! Used to preview what a merge resolves to: regions changed on one side
+//! only take that side, regions changed the same way on both sides are
+//! taken once, and regions changed differently are written as conflicts
+//! between git-style markers.
+
+use similar::{DiffOp, TextDiff};
+
+/// Result of [`merge`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    /// Merged text, with conflict markers around unresolved regions
+    pub text: String,
+    /// Number of conflicting regions
+    pub conflicts: usize,
+}
+
+/// For each line of `base`, the line of `side` it is kept as, if any
+fn base_to_side(base: &str, side: &str) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.lines().count()];
+    for op in TextDiff::from_lines(base, side).ops() {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = *op
+        {
+            for offset in 0..len {
+                map[old_index + offset] = Some(new_index + offset);
+            }
+        }
+    }
+    map
+}
+
+/// Merge `ours` and `theirs`, both derived from `base`. Conflicts are
+/// marked with `<<<<<<< {ours_label}`, `=======` and `>>>>>>> {theirs_label}`.
+pub fn merge(base: &str, ours: &str, theirs: &str, labels: (&str, &str)) -> Merged {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let in_ours = base_to_side(base, ours);
+    let in_theirs = base_to_side(base, theirs);
+
+    let mut text = String::new();
+    let mut conflicts = 0;
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // Base lines kept in place by both sides are merged as they are
+        while b < base_lines.len() && in_ours[b] == Some(o) && in_theirs[b] == Some(t) {
+            text.push_str(base_lines[b]);
+            b += 1;
+            o += 1;
+            t += 1;
+        }
+        if b == base_lines.len() && o == ours_lines.len() && t == theirs_lines.len() {
+            break;
+        }
+        // The region changed on some side ends at the next base line both
+        // sides keep (or at the end)
+        let end = (b..base_lines.len())
+            .find(|&line| in_ours[line].is_some() && in_theirs[line].is_some())
+            .unwrap_or(base_lines.len());
+        let (o_end, t_end) = match (in_ours.get(end), in_theirs.get(end)) {
+            (Some(&Some(o_end)), Some(&Some(t_end))) => (o_end, t_end),
+            _ => (ours_lines.len(), theirs_lines.len()),
+        };
+        let (base_region, ours_region, theirs_region) = (
+            &base_lines[b..end],
+            &ours_lines[o..o_end],
+            &theirs_lines[t..t_end],
+        );
+        if ours_region == base_region || ours_region == theirs_region {
+            theirs_region.iter().for_each(|line| text.push_str(line));
+        } else if theirs_region == base_region {
+            ours_region.iter().for_each(|line| text.push_str(line));
+        } else {
+            conflicts += 1;
+            let mut push_region = |marker: String, region: &[&str]| {
+                text.push_str(&marker);
+                for line in region {
+                    text.push_str(line);
+                }
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            };
+            push_region(format!("<<<<<<< {}\n", labels.0), ours_region);
+            push_region("=======\n".to_string(), theirs_region);
+            text.push_str(&format!(">>>>>>> {}\n", labels.1));
+        }
+        (b, o, t) = (end, o_end, t_end);
+    }
+    Merged { text, conflicts }
+}
+
+/// Number of conflicts still marked in `text`
+pub fn count_conflicts(text: &str) -> usize {
+    text.lines()
+        .filter(|line| line.starts_with("<<<<<<<"))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_takes_one_sided_changes_and_marks_conflicts() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "a\nB\nc\nd\ne\nours\n";
+        let theirs = "a\nb\nc\nD\ne\n";
+        let merged = merge(base, ours, theirs, ("ours", "theirs"));
+        assert_eq!(merged.text, "a\nB\nc\nD\ne\nours\n");
+        assert_eq!(merged.conflicts, 0);
+
+        let theirs = "a\nb2\nc\nd\ne\n";
+        let merged = merge(base, ours, theirs, ("main", "feature"));
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< main\nB\n=======\nb2\n>>>>>>> feature\nc\nd\ne\nours\n"
+        );
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(count_conflicts(&merged.text), 1);
+
+        // The same change on both sides is taken once
+        let merged = merge(base, ours, ours, ("ours", "theirs"));
+        assert_eq!(merged.text, ours);
+    }
+}
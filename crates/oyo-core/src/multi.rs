@@ -1,9 +1,17 @@
 //! Multi-file diff support
 
-use crate::diff::DiffEngine;
-use crate::git::{ChangedFile, FileStatus};
-use crate::step::{DiffNavigator, StepDirection};
+use crate::diff::{DiffEngine, WordStopPatterns};
+use crate::event::{EventBus, NavEvent};
+use crate::git::{BlobFetch, ChangedFile, FileStatus};
+use crate::origin::change_origins;
+use crate::patch::Patch;
+use crate::step::{DiffNavigator, StepDirection, StepOrder};
+use crate::text::Text;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,6 +20,12 @@ pub enum MultiDiffError {
     Io(#[from] std::io::Error),
     #[error("Git error: {0}")]
     Git(#[from] crate::git::GitError),
+    #[error("SVN error: {0}")]
+    Svn(#[from] crate::svn::SvnError),
+    #[error("Perforce error: {0}")]
+    P4(#[from] crate::p4::P4Error),
+    #[error("{0}")]
+    Unsupported(String),
 }
 
 /// A file entry in a multi-file diff
@@ -23,6 +37,35 @@ pub struct FileEntry {
     pub status: FileStatus,
     pub insertions: usize,
     pub deletions: usize,
+    /// The new side as last seen on disk (recorded by [`MultiFileDiff::is_stale`])
+    pub disk_stamp: Option<DiskStamp>,
+}
+
+/// Modification time and content hash of a file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskStamp {
+    pub modified: Option<SystemTime>,
+    pub hash: u64,
+}
+
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A commit holding one side of a file (see
+/// [`MultiFileDiff::file_revision`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRevision {
+    /// Repository the commit is in
+    pub repo: PathBuf,
+    pub rev: String,
+    /// Path of the file in the repository
+    pub path: PathBuf,
+    /// The commit holds the old side as shown, so line numbers must be
+    /// taken from it; else the new side
+    pub old_side: bool,
 }
 
 /// Multi-file diff session
@@ -42,14 +85,175 @@ pub struct MultiFileDiff {
     old_contents: Vec<String>,
     /// New contents for each file
     new_contents: Vec<String>,
+    /// Diff engine used to (re)compute per-file diffs
+    engine: DiffEngine,
+    /// Word-diff stop patterns (applied when building navigators)
+    stop_patterns: WordStopPatterns,
+    /// Order in which navigators step through changes
+    step_order: StepOrder,
+    /// Step through lines with several edits one span group at a time
+    split_changes: bool,
+    /// Navigation events of this session and its navigators
+    events: EventBus,
+    /// Common ancestor of a three-way file pair (`--base`)
+    base_content: Option<String>,
+    /// Diff config files through their structural adapter
+    structural: bool,
+    /// Show the comparison new → old
+    reversed: bool,
+    /// Adapted (old, new) contents per file, when `structural` is on
+    adapted: Vec<Option<(String, String)>>,
+    /// Unsaved editor content that replaces the new side of a path
+    overlays: HashMap<PathBuf, String>,
+    /// Blobs that had to be fetched in a partial clone, per file path
+    fetch_status: HashMap<PathBuf, BlobFetch>,
+    /// Where the hunks of a stale patch applied, per file path
+    patch_checks: HashMap<PathBuf, crate::patch::ApplyCheck>,
+    /// Memory budget of the computed diffs and what it evicted
+    residency: Residency,
+    /// Hunk risks of files without a navigator, per path, with the content
+    /// hashes they were scored on
+    risk_cache: HashMap<PathBuf, ((u64, u64), Vec<crate::risk::HunkRisk>)>,
+}
+
+/// Bookkeeping for [`MultiFileDiff::set_memory_budget`]
+#[derive(Debug, Default)]
+struct Residency {
+    /// Bytes the loaded navigators may hold (`None` = unlimited)
+    budget: Option<usize>,
+    /// Incremented on every use, to order loaded files by last use
+    clock: u64,
+    /// Last use of each loaded file, by path
+    last_used: HashMap<PathBuf, u64>,
+    /// Step each evicted file was on, restored when it is loaded again
+    evicted: HashMap<PathBuf, usize>,
 }
 
 #[derive(Debug, Clone)]
 enum GitDiffMode {
     Uncommitted,
     Staged,
-    IndexRange { from: String, to_index: bool },
-    Range { from: String, to: String },
+    IndexRange {
+        from: String,
+        to_index: bool,
+    },
+    Range {
+        from: String,
+        to: String,
+    },
+    Bisect {
+        from: String,
+        to: String,
+        paths: Vec<PathBuf>,
+    },
+    /// SVN working copy (BASE vs working files)
+    Svn,
+    /// Perforce client (have-revision vs opened files)
+    P4,
+    /// In-memory contents (e.g. archive entries); nothing to re-read
+    Virtual,
+    /// Uncommitted changes of several git repositories below `repo_root`;
+    /// file paths start with the directory of their repository
+    Workspace {
+        repos: Vec<PathBuf>,
+    },
+}
+
+/// Fetch the blobs `changes` need at `old_rev` and `new_rev` (sides read
+/// from a commit) in one batch when the repository is a partial clone;
+/// returns the status per file path
+fn prefetch_changes(
+    repo_root: &Path,
+    changes: &[ChangedFile],
+    old_rev: Option<&str>,
+    new_rev: Option<&str>,
+) -> HashMap<PathBuf, BlobFetch> {
+    let mut wanted = Vec::new();
+    let mut owners = HashMap::new();
+    for change in changes {
+        if let Some(old_rev) =
+            old_rev.filter(|_| !matches!(change.status, FileStatus::Added | FileStatus::Untracked))
+        {
+            let old_path = change.old_path.as_ref().unwrap_or(&change.path);
+            wanted.push((old_rev.to_string(), old_path.clone()));
+            owners.insert((old_rev.to_string(), old_path.clone()), change.path.clone());
+        }
+        if let Some(new_rev) = new_rev.filter(|_| change.status != FileStatus::Deleted) {
+            wanted.push((new_rev.to_string(), change.path.clone()));
+            owners.insert(
+                (new_rev.to_string(), change.path.clone()),
+                change.path.clone(),
+            );
+        }
+    }
+    let mut status: HashMap<PathBuf, BlobFetch> = HashMap::new();
+    for (blob, fetch) in crate::git::prefetch_blobs(repo_root, &wanted).unwrap_or_default() {
+        let Some(path) = owners.get(&blob) else {
+            continue;
+        };
+        let entry = status.entry(path.clone()).or_insert(fetch);
+        if fetch == BlobFetch::Failed {
+            *entry = BlobFetch::Failed;
+        }
+    }
+    status
+}
+
+/// Repository directory of a workspace path and the path inside it
+fn workspace_split(repos: &[PathBuf], path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let repo = repos
+        .iter()
+        .filter(|repo| path.starts_with(repo))
+        .max_by_key(|repo| repo.components().count())?;
+    let inner = path.strip_prefix(repo).ok()?.to_path_buf();
+    Some((repo.clone(), inner))
+}
+
+/// Uncommitted changes of all workspace repositories, with workspace paths
+fn workspace_changes(
+    root: &Path,
+    repos: &[PathBuf],
+) -> Result<Vec<ChangedFile>, crate::git::GitError> {
+    let mut changes = Vec::new();
+    for repo in repos {
+        for change in crate::git::get_uncommitted_changes(&root.join(repo))? {
+            changes.push(ChangedFile {
+                path: repo.join(&change.path),
+                old_path: change.old_path.map(|path| repo.join(path)),
+                status: change.status,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+impl GitDiffMode {
+    /// Old and new content of a file in an svn/p4 workspace
+    fn local_contents(
+        &self,
+        root: &Path,
+        path: &Path,
+        old_path: Option<&Path>,
+        status: FileStatus,
+    ) -> Option<(String, String)> {
+        match self {
+            GitDiffMode::Svn => Some(crate::svn::get_file_contents(root, path, status)),
+            GitDiffMode::P4 => Some(crate::p4::get_file_contents(root, path, old_path, status)),
+            _ => None,
+        }
+    }
+
+    /// HEAD content of a working tree file, routed to its repository
+    fn head_content(&self, root: &Path, path: &Path) -> Result<String, crate::git::GitError> {
+        match self {
+            GitDiffMode::Workspace { repos } => {
+                let (repo, inner) =
+                    workspace_split(repos, path).ok_or(crate::git::GitError::NotARepo)?;
+                crate::git::get_head_content(&root.join(repo), &inner)
+            }
+            _ => crate::git::get_head_content(root, path),
+        }
+    }
 }
 
 impl MultiFileDiff {
@@ -57,17 +261,40 @@ impl MultiFileDiff {
     pub fn from_git_changes(
         repo_root: PathBuf,
         changes: Vec<ChangedFile>,
+    ) -> Result<Self, MultiDiffError> {
+        Self::from_working_tree(repo_root, changes, GitDiffMode::Uncommitted)
+    }
+
+    /// Create from the uncommitted changes of several git repositories below
+    /// `root` (`repos` are relative to it). Files are listed with their
+    /// repository directory as prefix.
+    pub fn from_git_workspace(root: PathBuf, repos: Vec<PathBuf>) -> Result<Self, MultiDiffError> {
+        let changes = workspace_changes(&root, &repos)?;
+        Self::from_working_tree(root, changes, GitDiffMode::Workspace { repos })
+    }
+
+    fn from_working_tree(
+        repo_root: PathBuf,
+        changes: Vec<ChangedFile>,
+        mode: GitDiffMode,
     ) -> Result<Self, MultiDiffError> {
         let mut files = Vec::new();
         let mut old_contents = Vec::new();
         let mut new_contents = Vec::new();
         let engine = DiffEngine::new().with_word_level(true);
 
+        let fetch_status = match mode {
+            GitDiffMode::Uncommitted => prefetch_changes(&repo_root, &changes, Some("HEAD"), None),
+            _ => HashMap::new(),
+        };
+
         for change in changes {
             // Get old and new content
             let old_content = match change.status {
                 FileStatus::Added | FileStatus::Untracked => String::new(),
-                _ => crate::git::get_head_content(&repo_root, &change.path).unwrap_or_default(),
+                _ => mode
+                    .head_content(&repo_root, &change.path)
+                    .unwrap_or_default(),
             };
 
             let new_content = match change.status {
@@ -88,6 +315,7 @@ impl MultiFileDiff {
                 status: change.status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -101,9 +329,23 @@ impl MultiFileDiff {
             selected_index: 0,
             navigators,
             repo_root: Some(repo_root),
-            git_mode: Some(GitDiffMode::Uncommitted),
+            git_mode: Some(mode),
             old_contents,
             new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status,
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
         })
     }
 
@@ -117,6 +359,8 @@ impl MultiFileDiff {
         let mut new_contents = Vec::new();
         let engine = DiffEngine::new().with_word_level(true);
 
+        let fetch_status = prefetch_changes(&repo_root, &changes, Some("HEAD"), None);
+
         for change in changes {
             let old_path = change
                 .old_path
@@ -141,6 +385,7 @@ impl MultiFileDiff {
                 status: change.status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -157,6 +402,20 @@ impl MultiFileDiff {
             git_mode: Some(GitDiffMode::Staged),
             old_contents,
             new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status,
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
         })
     }
 
@@ -172,6 +431,12 @@ impl MultiFileDiff {
         let mut new_contents = Vec::new();
         let engine = DiffEngine::new().with_word_level(true);
 
+        let fetch_status = if to_index {
+            prefetch_changes(&repo_root, &changes, Some(&from), None)
+        } else {
+            prefetch_changes(&repo_root, &changes, None, Some(&from))
+        };
+
         for change in changes {
             let old_path = change
                 .old_path
@@ -212,6 +477,7 @@ impl MultiFileDiff {
                 status: change.status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -228,6 +494,20 @@ impl MultiFileDiff {
             git_mode: Some(GitDiffMode::IndexRange { from, to_index }),
             old_contents,
             new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status,
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
         })
     }
 
@@ -243,6 +523,8 @@ impl MultiFileDiff {
         let mut new_contents = Vec::new();
         let engine = DiffEngine::new().with_word_level(true);
 
+        let fetch_status = prefetch_changes(&repo_root, &changes, Some(&from), Some(&to));
+
         for change in changes {
             let old_path = change
                 .old_path
@@ -270,6 +552,7 @@ impl MultiFileDiff {
                 status: change.status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -286,9 +569,101 @@ impl MultiFileDiff {
             git_mode: Some(GitDiffMode::Range { from, to }),
             old_contents,
             new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status,
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
         })
     }
 
+    /// Create from the local changes of an svn working copy
+    pub fn from_svn_changes(
+        wc_root: PathBuf,
+        changes: Vec<ChangedFile>,
+    ) -> Result<Self, MultiDiffError> {
+        Ok(Self::from_local_changes(wc_root, changes, GitDiffMode::Svn))
+    }
+
+    /// Create from the files opened in a Perforce client
+    pub fn from_p4_changes(
+        client_root: PathBuf,
+        changes: Vec<ChangedFile>,
+    ) -> Result<Self, MultiDiffError> {
+        Ok(Self::from_local_changes(
+            client_root,
+            changes,
+            GitDiffMode::P4,
+        ))
+    }
+
+    fn from_local_changes(root: PathBuf, changes: Vec<ChangedFile>, mode: GitDiffMode) -> Self {
+        let mut files = Vec::new();
+        let mut old_contents = Vec::new();
+        let mut new_contents = Vec::new();
+        let engine = DiffEngine::new().with_word_level(true);
+
+        for change in changes {
+            let (old_content, new_content) = mode
+                .local_contents(
+                    &root,
+                    &change.path,
+                    change.old_path.as_deref(),
+                    change.status,
+                )
+                .unwrap_or_default();
+            let diff = engine.diff_strings(&old_content, &new_content);
+
+            files.push(FileEntry {
+                display_name: change.path.display().to_string(),
+                path: change.path,
+                old_path: change.old_path,
+                status: change.status,
+                insertions: diff.insertions,
+                deletions: diff.deletions,
+                disk_stamp: None,
+            });
+
+            old_contents.push(old_content);
+            new_contents.push(new_content);
+        }
+
+        let navigators: Vec<Option<DiffNavigator>> = (0..files.len()).map(|_| None).collect();
+
+        Self {
+            files,
+            selected_index: 0,
+            navigators,
+            repo_root: Some(root),
+            git_mode: Some(mode),
+            old_contents,
+            new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
+        }
+    }
+
     /// Create from two directories
     pub fn from_directories(old_dir: &Path, new_dir: &Path) -> Result<Self, MultiDiffError> {
         let mut files = Vec::new();
@@ -350,6 +725,7 @@ impl MultiFileDiff {
                 status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -366,9 +742,95 @@ impl MultiFileDiff {
             git_mode: None,
             old_contents,
             new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
         })
     }
 
+    /// Create from two in-memory file trees (e.g. the entries of two
+    /// archives), paired by path. Unchanged entries are left out.
+    pub fn from_entries(
+        old_entries: BTreeMap<PathBuf, String>,
+        mut new_entries: BTreeMap<PathBuf, String>,
+    ) -> Self {
+        let engine = DiffEngine::new().with_word_level(true);
+        let mut paired: BTreeMap<PathBuf, (Option<String>, Option<String>)> = BTreeMap::new();
+        for (path, content) in old_entries {
+            let new_content = new_entries.remove(&path);
+            paired.insert(path, (Some(content), new_content));
+        }
+        for (path, content) in new_entries {
+            paired.insert(path, (None, Some(content)));
+        }
+
+        let mut files = Vec::new();
+        let mut old_contents = Vec::new();
+        let mut new_contents = Vec::new();
+        for (path, (old_content, new_content)) in paired {
+            let status = match (&old_content, &new_content) {
+                (None, _) => FileStatus::Added,
+                (_, None) => FileStatus::Deleted,
+                _ => FileStatus::Modified,
+            };
+            let old_content = old_content.unwrap_or_default();
+            let new_content = new_content.unwrap_or_default();
+            if status == FileStatus::Modified && old_content == new_content {
+                continue;
+            }
+            let diff = engine.diff_strings(&old_content, &new_content);
+
+            files.push(FileEntry {
+                display_name: path.display().to_string(),
+                path,
+                old_path: None,
+                status,
+                insertions: diff.insertions,
+                deletions: diff.deletions,
+                disk_stamp: None,
+            });
+            old_contents.push(old_content);
+            new_contents.push(new_content);
+        }
+
+        let navigators: Vec<Option<DiffNavigator>> = (0..files.len()).map(|_| None).collect();
+
+        Self {
+            files,
+            selected_index: 0,
+            navigators,
+            repo_root: None,
+            git_mode: Some(GitDiffMode::Virtual),
+            old_contents,
+            new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
+        }
+    }
+
     /// Create from a single file pair
     pub fn from_file_pair(
         _old_path: PathBuf,
@@ -386,6 +848,7 @@ impl MultiFileDiff {
             status: FileStatus::Modified,
             insertions: diff.insertions,
             deletions: diff.deletions,
+            disk_stamp: None,
         }];
 
         Self {
@@ -396,27 +859,506 @@ impl MultiFileDiff {
             git_mode: None,
             old_contents: vec![old_content],
             new_contents: vec![new_content],
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
+        }
+    }
+
+    /// Create from "ours" and "theirs" versions of a file and their common
+    /// ancestor; each change is attributed to the side that introduced it
+    pub fn from_file_pair_with_base(
+        old_path: PathBuf,
+        new_path: PathBuf,
+        base_content: String,
+        old_content: String,
+        new_content: String,
+    ) -> Self {
+        let mut multi = Self::from_file_pair(old_path, new_path, old_content, new_content);
+        multi.base_content = Some(base_content);
+        multi
+    }
+
+    /// Create from one patch of a series. Pre-images are looked up in
+    /// `repo_root` by the blob ids of the patch when available; otherwise
+    /// the hunks are applied to the working tree file, allowing offsets and
+    /// up to `max_fuzz` lines of mismatched context (see
+    /// [`Self::patch_check`]).
+    pub fn from_patch(patch: &Patch, repo_root: Option<&Path>, max_fuzz: usize) -> Self {
+        let engine = DiffEngine::new().with_word_level(true);
+        let mut files = Vec::new();
+        let mut old_contents = Vec::new();
+        let mut new_contents = Vec::new();
+        let mut patch_checks = HashMap::new();
+
+        for file in &patch.files {
+            let Some(path) = file.path() else {
+                continue;
+            };
+            let pre_image = match (repo_root, file.old_blob.as_deref()) {
+                (Some(root), Some(id)) => crate::git::get_blob(root, id).ok(),
+                _ => None,
+            };
+            let stale = pre_image.is_none() && file.old_path.is_some();
+            let working_tree = file
+                .old_path
+                .as_deref()
+                .filter(|_| stale)
+                .and_then(|old_path| {
+                    let old_path = match repo_root {
+                        Some(root) => root.join(old_path),
+                        None => old_path.to_path_buf(),
+                    };
+                    std::fs::read_to_string(old_path).ok()
+                });
+            let (old_content, new_content) = match working_tree {
+                Some(target) => {
+                    let check = crate::patch::check_applies(file, &target, max_fuzz);
+                    let applied = (check.rejected().len() < file.hunks.len())
+                        .then(|| file.apply_checked(&target, &check))
+                        .flatten();
+                    patch_checks.insert(path.to_path_buf(), check);
+                    match applied {
+                        Some(new_content) => (target, new_content),
+                        None => file.contents(None),
+                    }
+                }
+                None => file.contents(pre_image.as_deref()),
+            };
+            let status = match (&file.old_path, &file.new_path) {
+                (None, _) => FileStatus::Added,
+                (_, None) => FileStatus::Deleted,
+                (Some(old), Some(new)) if old != new => FileStatus::Renamed,
+                _ => FileStatus::Modified,
+            };
+            let diff = engine.diff_strings(&old_content, &new_content);
+
+            files.push(FileEntry {
+                display_name: path.display().to_string(),
+                path: path.to_path_buf(),
+                old_path: match status {
+                    FileStatus::Renamed => file.old_path.clone(),
+                    _ => None,
+                },
+                status,
+                insertions: diff.insertions,
+                deletions: diff.deletions,
+                disk_stamp: None,
+            });
+            old_contents.push(old_content);
+            new_contents.push(new_content);
+        }
+
+        let navigators: Vec<Option<DiffNavigator>> = (0..files.len()).map(|_| None).collect();
+
+        Self {
+            files,
+            selected_index: 0,
+            navigators,
+            repo_root: None,
+            git_mode: None,
+            old_contents,
+            new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks,
+            residency: Residency::default(),
+            risk_cache: HashMap::new(),
+        }
+    }
+
+    /// Review rejected hunks: one entry per hunk (named by
+    /// [`crate::patch::RejectFile::label`]) showing its target file before
+    /// and after placing the hunk at its likeliest line
+    pub fn from_rejects(rejects: &[crate::patch::RejectFile]) -> Self {
+        let engine = DiffEngine::new().with_word_level(true);
+        let mut files = Vec::new();
+        let mut old_contents = Vec::new();
+        let mut new_contents = Vec::new();
+
+        for reject in rejects {
+            let target = std::fs::read_to_string(&reject.target).unwrap_or_default();
+            for (idx, hunk) in reject.hunks.iter().enumerate() {
+                let at = crate::patch::guess_placement(&target, hunk);
+                let placed = crate::patch::place_hunk(&target, hunk, at);
+                let diff = engine.diff_strings(&target, &placed);
+                files.push(FileEntry {
+                    display_name: reject.label(idx),
+                    path: reject.target.clone(),
+                    old_path: None,
+                    status: FileStatus::Modified,
+                    insertions: diff.insertions,
+                    deletions: diff.deletions,
+                    disk_stamp: None,
+                });
+                old_contents.push(target.clone());
+                new_contents.push(placed);
+            }
+        }
+
+        let navigators: Vec<Option<DiffNavigator>> = (0..files.len()).map(|_| None).collect();
+
+        Self {
+            files,
+            selected_index: 0,
+            navigators,
+            repo_root: None,
+            git_mode: None,
+            old_contents,
+            new_contents,
+            engine,
+            stop_patterns: WordStopPatterns::default(),
+            step_order: StepOrder::default(),
+            split_changes: false,
+            reversed: false,
+            events: EventBus::new(),
+            base_content: None,
+            structural: false,
+            adapted: Vec::new(),
+            overlays: HashMap::new(),
+            fetch_status: HashMap::new(),
+            patch_checks: HashMap::new(),
+            risk_cache: HashMap::new(),
+            residency: Residency::default(),
+        }
+    }
+
+    /// Replace the new side of file `idx`, e.g. after moving a rejected
+    /// hunk to another line
+    pub fn set_new_content(&mut self, idx: usize, new_content: String) {
+        if idx >= self.files.len() {
+            return;
+        }
+        let old_content = self.old_contents[idx].clone();
+        self.set_contents(idx, old_content, new_content);
+    }
+
+    /// Where the hunks of file `path` applied to its working tree copy, for
+    /// patches whose pre-image is not in the repository
+    pub fn patch_check(&self, path: &Path) -> Option<&crate::patch::ApplyCheck> {
+        self.patch_checks.get(path)
+    }
+
+    /// Take over the engine, stop patterns, step order and event bus of `other`, so a
+    /// replacement session (e.g. the next patch of a series) behaves the same
+    pub fn inherit_settings(&mut self, other: &MultiFileDiff) {
+        self.stop_patterns = other.stop_patterns.clone();
+        self.step_order = other.step_order;
+        self.split_changes = other.split_changes;
+        self.reversed = other.reversed;
+        self.events = other.events.clone();
+        self.engine = other.engine.clone();
+        self.residency.budget = other.residency.budget;
+        self.set_structural(other.structural);
+    }
+
+    /// True for three-way comparisons against a common ancestor
+    pub fn has_base(&self) -> bool {
+        self.base_content.is_some()
+    }
+
+    /// Base, ours and theirs contents of a three-way comparison (`--base`)
+    pub fn three_way_contents(&self) -> Option<(&str, &str, &str)> {
+        let base = self.base_content.as_deref()?;
+        Some((base, self.old_contents.first()?, self.new_contents.first()?))
+    }
+
+    /// Diff config files by structure (sorted keys per section) instead of
+    /// by line; see [`crate::structural`]
+    pub fn set_structural(&mut self, structural: bool) {
+        self.structural = structural;
+        self.adapted = (0..self.files.len()).map(|idx| self.adapt(idx)).collect();
+        self.set_engine(self.engine.clone());
+    }
+
+    pub fn is_structural(&self) -> bool {
+        self.structural
+    }
+
+    /// Name of the structural adapter applied to the current file
+    pub fn current_adapter(&self) -> Option<&'static str> {
+        self.adapted.get(self.selected_index)?.as_ref()?;
+        crate::structural::adapter_for(&self.files[self.selected_index].path)
+            .map(|adapter| adapter.name())
+    }
+
+    fn adapt(&self, idx: usize) -> Option<(String, String)> {
+        if !self.structural {
+            return None;
+        }
+        let adapter = crate::structural::adapter_for(&self.files[idx].path)?;
+        let adapt_side = |content: &str| {
+            if content.is_empty() {
+                Some(String::new())
+            } else {
+                adapter.normalize(content)
+            }
+        };
+        Some((
+            adapt_side(&self.old_contents[idx])?,
+            adapt_side(&self.new_contents[idx])?,
+        ))
+    }
+
+    /// Contents that are diffed and shown (adapted when structural,
+    /// swapped when reversed)
+    fn diff_contents(&self, idx: usize) -> (&str, &str) {
+        let (old, new) = match self.adapted.get(idx) {
+            Some(Some((old, new))) => (old, new),
+            _ => (&self.old_contents[idx], &self.new_contents[idx]),
+        };
+        if self.reversed {
+            (new, old)
+        } else {
+            (old, new)
+        }
+    }
+
+    /// Invert the comparison (new → old): insertions become deletions and
+    /// vice versa, e.g. to see what reverting the change would look like
+    pub fn set_reversed(&mut self, reversed: bool) {
+        self.reversed = reversed;
+        self.set_engine(self.engine.clone());
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Commit time of each old-side line of a file (from `git blame`), when
+    /// the old side was read from a git revision
+    pub fn old_line_times(&self, idx: usize) -> Option<Vec<i64>> {
+        let file = self.files.get(idx)?;
+        if self.reversed
+            || self.old_contents[idx].is_empty()
+            || self.adapted.get(idx).is_some_and(Option::is_some)
+        {
+            return None;
+        }
+        let root = self.repo_root.as_deref()?;
+        let path = file.old_path.as_deref().unwrap_or(&file.path);
+        let (repo, rev, path) = match self.git_mode.as_ref()? {
+            GitDiffMode::Uncommitted | GitDiffMode::Staged => {
+                (root.to_path_buf(), "HEAD", path.to_path_buf())
+            }
+            GitDiffMode::Workspace { repos } => {
+                let (repo, inner) = workspace_split(repos, path)?;
+                (root.join(repo), "HEAD", inner)
+            }
+            GitDiffMode::IndexRange { from, .. }
+            | GitDiffMode::Range { from, .. }
+            | GitDiffMode::Bisect { from, .. } => {
+                (root.to_path_buf(), from.as_str(), path.to_path_buf())
+            }
+            _ => return None,
+        };
+        crate::git::blame_commit_times(&repo, rev, &path).ok()
+    }
+
+    /// Status of a file as shown (flipped when reversed)
+    pub fn shown_status(&self, file: &FileEntry) -> FileStatus {
+        if self.reversed {
+            file.status.reversed()
+        } else {
+            file.status
         }
     }
 
     /// Get the navigator for the currently selected file
     pub fn current_navigator(&mut self) -> &mut DiffNavigator {
         if self.navigators[self.selected_index].is_none() {
-            let engine = DiffEngine::new().with_word_level(true);
-            let diff = engine.diff_strings(
-                &self.old_contents[self.selected_index],
-                &self.new_contents[self.selected_index],
-            );
-            let navigator = DiffNavigator::new(
-                diff,
-                self.old_contents[self.selected_index].clone(),
-                self.new_contents[self.selected_index].clone(),
+            let _span = tracing::debug_span!(
+                "open_file",
+                path = %self.files[self.selected_index].display_name
+            )
+            .entered();
+            let engine = self.engine.clone().with_stop_patterns(
+                self.stop_patterns
+                    .for_path(&self.files[self.selected_index].path),
             );
+            let (old_content, new_content) = self.diff_contents(self.selected_index);
+            // The navigator and every span of the diff share these buffers
+            let (old_content, new_content) = (Text::from(old_content), Text::from(new_content));
+            let started = std::time::Instant::now();
+            let path = &self.files[self.selected_index].path;
+            let diff = engine.diff_text_for(path, &old_content, &new_content);
+            let diff_time = started.elapsed();
+            let mut navigator = DiffNavigator::new(diff, old_content, new_content);
+            navigator.set_diff_time(diff_time);
+            navigator.set_step_order(self.step_order);
+            if self.split_changes {
+                navigator.set_split_changes(true);
+            }
+            // Before subscribing, so resuming an evicted file is not a step
+            let path = &self.files[self.selected_index].path;
+            if let Some(step) = self.residency.evicted.remove(path) {
+                navigator.goto(step);
+            }
+            navigator.set_event_bus(self.events.clone());
+            if let Some(base) = self.base_content.as_deref() {
+                let mut sides = (
+                    self.old_contents[self.selected_index].as_str(),
+                    self.new_contents[self.selected_index].as_str(),
+                );
+                if self.reversed {
+                    sides = (sides.1, sides.0);
+                }
+                navigator.set_change_origins(change_origins(
+                    base,
+                    sides.0,
+                    sides.1,
+                    navigator.diff(),
+                ));
+            }
             self.navigators[self.selected_index] = Some(navigator);
+            self.touch(self.selected_index);
+            self.enforce_memory_budget();
         }
         self.navigators[self.selected_index].as_mut().unwrap()
     }
 
+    /// Keep the estimated memory of the computed diffs under `bytes`
+    /// (`None` = unlimited) by dropping the least recently used ones other
+    /// than the selected file's; a dropped diff is recomputed, at the step
+    /// it was on, when its file is opened again. File contents stay loaded,
+    /// as diffs are recomputed from them.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) {
+        self.residency.budget = bytes;
+        self.enforce_memory_budget();
+    }
+
+    /// Whether the diff of file `index` was dropped to stay in the memory
+    /// budget (and not recomputed since)
+    pub fn is_evicted(&self, index: usize) -> bool {
+        self.files
+            .get(index)
+            .is_some_and(|file| self.residency.evicted.contains_key(&file.path))
+    }
+
+    /// Whether any diff was dropped to stay in the memory budget
+    pub fn has_evictions(&self) -> bool {
+        !self.residency.evicted.is_empty()
+    }
+
+    fn touch(&mut self, index: usize) {
+        self.residency.clock += 1;
+        let path = self.files[index].path.clone();
+        self.residency.last_used.insert(path, self.residency.clock);
+    }
+
+    fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.residency.budget else {
+            return;
+        };
+        let mut total: usize = self
+            .navigators
+            .iter()
+            .flatten()
+            .map(DiffNavigator::memory_estimate)
+            .sum();
+        while total > budget {
+            let victim = (0..self.files.len())
+                .filter(|&index| index != self.selected_index && self.is_loaded(index))
+                .min_by_key(|&index| {
+                    let path = &self.files[index].path;
+                    self.residency.last_used.get(path).copied().unwrap_or(0)
+                });
+            let Some(victim) = victim else {
+                break;
+            };
+            let Some(navigator) = self.navigators[victim].take() else {
+                break;
+            };
+            total -= navigator.memory_estimate();
+            let path = self.files[victim].path.clone();
+            tracing::debug!(path = %path.display(), "evicted diff");
+            self.residency.last_used.remove(&path);
+            self.residency
+                .evicted
+                .insert(path, navigator.state().current_step);
+        }
+    }
+
+    /// Replace the diff engine, recomputing file stats and dropping cached navigators
+    pub fn set_engine(&mut self, engine: DiffEngine) {
+        self.engine = engine;
+        for idx in 0..self.files.len() {
+            let (old_content, new_content) = self.diff_contents(idx);
+            let (old_content, new_content) = (Text::from(old_content), Text::from(new_content));
+            let diff = self
+                .engine
+                .diff_text_for(&self.files[idx].path, &old_content, &new_content);
+            self.files[idx].insertions = diff.insertions;
+            self.files[idx].deletions = diff.deletions;
+        }
+        self.navigators = (0..self.files.len()).map(|_| None).collect();
+    }
+
+    /// Set word-diff stop patterns, dropping cached navigators
+    pub fn set_stop_patterns(&mut self, patterns: WordStopPatterns) {
+        self.stop_patterns = patterns;
+        self.navigators = (0..self.files.len()).map(|_| None).collect();
+    }
+
+    /// Set the step order, dropping cached navigators
+    pub fn set_step_order(&mut self, order: StepOrder) {
+        self.step_order = order;
+        self.navigators = (0..self.files.len()).map(|_| None).collect();
+    }
+
+    /// Step through a line's separate edits one at a time, dropping cached
+    /// navigators
+    pub fn set_split_changes(&mut self, enabled: bool) {
+        self.split_changes = enabled;
+        self.navigators = (0..self.files.len()).map(|_| None).collect();
+    }
+
+    /// Whether the diff of file `index` has been computed and is held
+    pub fn is_loaded(&self, index: usize) -> bool {
+        self.navigators.get(index).is_some_and(Option::is_some)
+    }
+
+    /// Navigator of file `index`, if its diff has been computed
+    pub fn navigator(&self, index: usize) -> Option<&DiffNavigator> {
+        self.navigators.get(index)?.as_ref()
+    }
+
+    /// Approximate bytes held for file `index`: its contents and, when
+    /// loaded, its navigator
+    pub fn memory_estimate(&self, index: usize) -> usize {
+        let contents = self.old_contents.get(index).map_or(0, String::capacity)
+            + self.new_contents.get(index).map_or(0, String::capacity)
+            + self
+                .adapted
+                .get(index)
+                .and_then(Option::as_ref)
+                .map_or(0, |(old, new)| old.capacity() + new.capacity());
+        let navigator = self
+            .navigator(index)
+            .map_or(0, DiffNavigator::memory_estimate);
+        std::mem::size_of::<FileEntry>() + contents + navigator
+    }
+
     /// Get the current file entry
     pub fn current_file(&self) -> Option<&FileEntry> {
         self.files.get(self.selected_index)
@@ -425,7 +1367,7 @@ impl MultiFileDiff {
     /// Select next file
     pub fn next_file(&mut self) -> bool {
         if self.selected_index < self.files.len().saturating_sub(1) {
-            self.selected_index += 1;
+            self.set_selected(self.selected_index + 1);
             true
         } else {
             false
@@ -435,7 +1377,7 @@ impl MultiFileDiff {
     /// Select previous file
     pub fn prev_file(&mut self) -> bool {
         if self.selected_index > 0 {
-            self.selected_index -= 1;
+            self.set_selected(self.selected_index - 1);
             true
         } else {
             false
@@ -445,30 +1387,458 @@ impl MultiFileDiff {
     /// Select file by index
     pub fn select_file(&mut self, index: usize) {
         if index < self.files.len() {
-            self.selected_index = index;
+            self.set_selected(index);
         }
     }
 
+    fn set_selected(&mut self, index: usize) {
+        let from = self.selected_index;
+        self.selected_index = index;
+        if self.is_loaded(index) {
+            self.touch(index);
+        }
+        if from != index {
+            self.events.emit(NavEvent::FileChanged {
+                from,
+                to: index,
+                path: self.files[index].path.clone(),
+            });
+        }
+    }
+
+    /// Subscribe to file, step and hunk transitions of this session
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<NavEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish an event on this session's bus (e.g. animation ticks from a frontend)
+    pub fn emit(&self, event: NavEvent) {
+        self.events.emit(event);
+    }
+
+    /// True if anyone subscribed to this session's events
+    pub fn has_subscribers(&self) -> bool {
+        self.events.has_subscribers()
+    }
+
+    /// Old and new contents of a file
+    pub fn file_contents(&self, index: usize) -> Option<(&str, &str)> {
+        (index < self.files.len()).then(|| self.diff_contents(index))
+    }
+
     /// Total number of files
     pub fn file_count(&self) -> usize {
         self.files.len()
     }
 
-    /// Repository root path (git mode only)
+    /// Risk scores of the hunks of file `idx`, riskiest first. Loaded
+    /// files are scored from their navigator; others are diffed once and
+    /// cached until their contents change.
+    pub fn hunk_risks(&mut self, idx: usize) -> Vec<crate::risk::HunkRisk> {
+        let path = self.files[idx].path.clone();
+        if let Some(nav) = self.navigators[idx].as_ref() {
+            return crate::risk::rank_hunks(nav.diff(), &path);
+        }
+        let (old_content, new_content) = self.diff_contents(idx);
+        let hashes = (
+            content_hash(old_content.as_bytes()),
+            content_hash(new_content.as_bytes()),
+        );
+        if let Some((cached, risks)) = self.risk_cache.get(&path) {
+            if *cached == hashes {
+                return risks.clone();
+            }
+        }
+        let risks = crate::risk::rank_hunks(&self.file_diff(idx), &path);
+        self.risk_cache.insert(path, (hashes, risks.clone()));
+        risks
+    }
+
+    /// Risk score of each file: the sum of its hunk scores (see
+    /// [`crate::risk`])
+    pub fn file_risks(&mut self) -> Vec<u32> {
+        (0..self.files.len())
+            .map(|idx| self.hunk_risks(idx).iter().map(|risk| risk.score).sum())
+            .collect()
+    }
+
+    /// Every hunk of every file, riskiest first (file order for equal
+    /// scores), as single-hunk groups for the group review
+    pub fn risk_queue(&mut self) -> Vec<crate::group::HunkGroup> {
+        use crate::group::{GroupReason, HunkGroup, HunkRef};
+        let mut queue: Vec<(u32, HunkRef)> = (0..self.files.len())
+            .flat_map(|file| {
+                self.hunk_risks(file).into_iter().map(move |risk| {
+                    (
+                        risk.score,
+                        HunkRef {
+                            file,
+                            hunk: risk.hunk_id,
+                        },
+                    )
+                })
+            })
+            .collect();
+        queue.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        queue
+            .into_iter()
+            .map(|(score, hunk)| HunkGroup {
+                reason: GroupReason::Risk(score),
+                hunks: vec![hunk],
+            })
+            .collect()
+    }
+
+    /// Diff of a file as its navigator computes it
+    fn file_diff(&self, idx: usize) -> crate::diff::DiffResult {
+        let engine = self
+            .engine
+            .clone()
+            .with_stop_patterns(self.stop_patterns.for_path(&self.files[idx].path));
+        let (old_content, new_content) = self.diff_contents(idx);
+        let (old_content, new_content) = (Text::from(old_content), Text::from(new_content));
+        engine.diff_text_for(&self.files[idx].path, &old_content, &new_content)
+    }
+
+    /// Number of changes in each file that replace token `old` with `new`
+    /// (see [`crate::Change::token_replacement`])
+    pub fn replacement_counts(&self, old: &str, new: &str) -> Vec<usize> {
+        (0..self.files.len())
+            .map(|idx| {
+                self.file_diff(idx)
+                    .get_significant_changes()
+                    .into_iter()
+                    .filter(|change| {
+                        change
+                            .token_replacement()
+                            .is_some_and(|(from, to)| from == old && to == new)
+                    })
+                    .count()
+            })
+            .collect()
+    }
+
+    /// Suggested logical groups of the hunks of all files
+    pub fn hunk_groups(&self) -> Vec<crate::group::HunkGroup> {
+        let diffs: Vec<_> = (0..self.files.len())
+            .map(|idx| self.file_diff(idx))
+            .collect();
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .zip(&diffs)
+            .map(|(file, diff)| (file.path.as_path(), diff))
+            .collect();
+        crate::group::group_hunks(&files)
+    }
+
+    /// Plan one commit per bucket (in order) from the uncommitted changes.
+    /// Each commit applies its bucket's hunks on top of the previous ones;
+    /// hunks in no bucket stay uncommitted.
+    pub fn plan_commits(
+        &self,
+        buckets: &[crate::bucket::Bucket],
+    ) -> Result<Vec<crate::bucket::PlannedCommit>, MultiDiffError> {
+        use crate::bucket::{partial_content, PlannedCommit, PlannedFile};
+        use std::collections::BTreeSet;
+
+        if self.is_workspace() {
+            return Err(MultiDiffError::Unsupported(
+                "commits can only be split within one repository".to_string(),
+            ));
+        }
+        if !matches!(self.git_mode, Some(GitDiffMode::Uncommitted)) {
+            return Err(MultiDiffError::Unsupported(
+                "commits can only be split from uncommitted changes".to_string(),
+            ));
+        }
+        if self.structural {
+            return Err(MultiDiffError::Unsupported(
+                "turn off structural diff to split commits".to_string(),
+            ));
+        }
+        if self.reversed {
+            return Err(MultiDiffError::Unsupported(
+                "turn off the reversed view to split commits".to_string(),
+            ));
+        }
+
+        let diffs: Vec<_> = (0..self.files.len())
+            .map(|idx| {
+                self.engine
+                    .clone()
+                    .with_stop_patterns(self.stop_patterns.for_path(&self.files[idx].path))
+                    .diff_strings(&self.old_contents[idx], &self.new_contents[idx])
+            })
+            .collect();
+        let mut applied: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); self.files.len()];
+        let mut current: Vec<String> = self.old_contents.clone();
+
+        let mut plan = Vec::new();
+        for bucket in buckets {
+            let mut files = Vec::new();
+            for (idx, file) in self.files.iter().enumerate() {
+                let before_len = applied[idx].len();
+                applied[idx].extend(
+                    bucket
+                        .hunks
+                        .iter()
+                        .filter(|(path, hunk)| *path == file.path && *hunk < diffs[idx].hunks.len())
+                        .map(|(_, hunk)| *hunk),
+                );
+                if applied[idx].len() == before_len {
+                    continue;
+                }
+                let complete = applied[idx].len() == diffs[idx].hunks.len();
+                let after = if complete && file.status == FileStatus::Deleted {
+                    None
+                } else if complete {
+                    Some(self.new_contents[idx].clone())
+                } else {
+                    Some(partial_content(
+                        &self.old_contents[idx],
+                        &self.new_contents[idx],
+                        &diffs[idx],
+                        &applied[idx],
+                    ))
+                };
+                let before =
+                    std::mem::replace(&mut current[idx], after.clone().unwrap_or_default());
+                files.push(PlannedFile {
+                    path: file.path.clone(),
+                    before,
+                    after,
+                });
+            }
+            if !files.is_empty() {
+                plan.push(PlannedCommit {
+                    message: bucket.name.clone(),
+                    files,
+                });
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Order files riskiest first (stable for equal scores)
+    pub fn sort_files_by_risk(&mut self) {
+        let risks = self.file_risks();
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        order.sort_by(|a, b| risks[*b].cmp(&risks[*a]));
+        self.reorder_files(&order);
+    }
+
+    /// True if this diff shows a range of commits (including bisect)
+    pub fn is_commit_range(&self) -> bool {
+        matches!(
+            self.git_mode,
+            Some(GitDiffMode::Range { .. } | GitDiffMode::Bisect { .. })
+        )
+    }
+
+    /// Files grouped by the conventional-commit type of the commits of the
+    /// range touching them; `None` outside a commit range
+    pub fn commit_type_groups(&self) -> Option<crate::conventional::CommitTypeGroups> {
+        let (GitDiffMode::Range { from, to } | GitDiffMode::Bisect { from, to, .. }) =
+            self.git_mode.as_ref()?
+        else {
+            return None;
+        };
+        let commits = crate::git::get_range_commits(self.repo_root.as_ref()?, from, to).ok()?;
+        Some(crate::conventional::CommitTypeGroups::new(&commits))
+    }
+
+    /// Order files by commit type group (see [`Self::commit_type_groups`]),
+    /// then path
+    pub fn sort_files_by_commit_type(&mut self, groups: &crate::conventional::CommitTypeGroups) {
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        order.sort_by_key(|&idx| {
+            let path = &self.files[idx].path;
+            (crate::conventional::type_rank(groups.label(path)), path)
+        });
+        self.reorder_files(&order);
+    }
+
+    /// Order directories by churn (lines changed in their files), most
+    /// first, and each directory's files the same way; ties keep path order
+    pub fn sort_files_by_churn(&mut self) {
+        let dir = |file: &FileEntry| {
+            file.display_name
+                .rsplit_once('/')
+                .map_or("", |(dir, _)| dir)
+                .to_string()
+        };
+        let churn = |file: &FileEntry| file.insertions + file.deletions;
+        let mut dir_churn: HashMap<String, usize> = HashMap::new();
+        for file in &self.files {
+            *dir_churn.entry(dir(file)).or_default() += churn(file);
+        }
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        order.sort_by_cached_key(|&idx| {
+            let file = &self.files[idx];
+            let dir = dir(file);
+            (
+                std::cmp::Reverse(dir_churn[&dir]),
+                dir,
+                std::cmp::Reverse(churn(file)),
+                file.path.clone(),
+            )
+        });
+        self.reorder_files(&order);
+    }
+
+    /// Order files by path
+    pub fn sort_files_by_path(&mut self) {
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        order.sort_by(|a, b| self.files[*a].path.cmp(&self.files[*b].path));
+        self.reorder_files(&order);
+    }
+
+    /// Apply a permutation to all per-file state, keeping the selected file selected
+    fn reorder_files(&mut self, order: &[usize]) {
+        fn take_in_order<T>(items: &mut Vec<T>, order: &[usize]) {
+            let mut slots: Vec<Option<T>> = items.drain(..).map(Some).collect();
+            items.extend(order.iter().filter_map(|&idx| slots[idx].take()));
+        }
+        let selected = self.selected_index;
+        take_in_order(&mut self.files, order);
+        take_in_order(&mut self.navigators, order);
+        take_in_order(&mut self.old_contents, order);
+        take_in_order(&mut self.new_contents, order);
+        if !self.adapted.is_empty() {
+            take_in_order(&mut self.adapted, order);
+        }
+        self.selected_index = order.iter().position(|&idx| idx == selected).unwrap_or(0);
+    }
+
+    /// Create from an in-progress bisect (known-good commit..candidate)
+    pub fn from_git_bisect(
+        repo_root: PathBuf,
+        changes: Vec<ChangedFile>,
+        good: String,
+        bisect: &crate::git::BisectState,
+    ) -> Result<Self, MultiDiffError> {
+        let mut diff =
+            Self::from_git_range(repo_root, changes, good.clone(), bisect.current.clone())?;
+        diff.git_mode = Some(GitDiffMode::Bisect {
+            from: good,
+            to: bisect.current.clone(),
+            paths: bisect.paths.clone(),
+        });
+        Ok(diff)
+    }
+
+    /// True if this diff follows a git bisect session
+    pub fn is_bisect(&self) -> bool {
+        matches!(self.git_mode, Some(GitDiffMode::Bisect { .. }))
+    }
+
+    /// Bisect candidate currently shown (bisect mode only)
+    pub fn bisect_candidate(&self) -> Option<&str> {
+        match &self.git_mode {
+            Some(GitDiffMode::Bisect { to, .. }) => Some(to),
+            _ => None,
+        }
+    }
+
+    /// Repository root path (git mode only; the common directory in
+    /// workspace mode)
     pub fn repo_root(&self) -> Option<&Path> {
         self.repo_root.as_deref()
     }
 
+    /// Whether the blobs of file `idx` had to be fetched (partial clones)
+    pub fn fetch_status(&self, idx: usize) -> Option<BlobFetch> {
+        self.fetch_status.get(&self.files.get(idx)?.path).copied()
+    }
+
+    /// True if this diff spans several repositories
+    pub fn is_workspace(&self) -> bool {
+        matches!(self.git_mode, Some(GitDiffMode::Workspace { .. }))
+    }
+
+    /// Root of the repository that file `idx` belongs to
+    pub fn file_repo_root(&self, idx: usize) -> Option<PathBuf> {
+        let root = self.repo_root.as_ref()?;
+        match &self.git_mode {
+            Some(GitDiffMode::Workspace { repos }) => {
+                let (repo, _) = workspace_split(repos, &self.files.get(idx)?.path)?;
+                Some(root.join(repo))
+            }
+            _ => Some(root.clone()),
+        }
+    }
+
+    /// The commit holding one side of file `idx`: the new side of a commit
+    /// range, else the old side (HEAD for local and staged changes, the
+    /// commit of an index range), as the working tree and the index have
+    /// no commit of their own
+    pub fn file_revision(&self, idx: usize) -> Option<FileRevision> {
+        let file = self.files.get(idx)?;
+        let root = self.repo_root.as_deref()?;
+        // Which side of the diff as computed (before `reversed`) is at `rev`
+        let (rev, old) = match self.git_mode.as_ref()? {
+            GitDiffMode::Workspace { .. } | GitDiffMode::Uncommitted | GitDiffMode::Staged => {
+                ("HEAD", true)
+            }
+            GitDiffMode::Range { from, to } | GitDiffMode::Bisect { from, to, .. } => {
+                if self.reversed {
+                    (from.as_str(), true)
+                } else {
+                    (to.as_str(), false)
+                }
+            }
+            GitDiffMode::IndexRange {
+                from,
+                to_index: true,
+            } => (from.as_str(), true),
+            GitDiffMode::IndexRange {
+                from,
+                to_index: false,
+            } => (from.as_str(), false),
+            _ => return None,
+        };
+        let path = match (old, &file.old_path) {
+            (true, Some(old_path)) => old_path,
+            _ => &file.path,
+        };
+        let (repo, path) = match self.git_mode.as_ref()? {
+            GitDiffMode::Workspace { repos } => {
+                let (repo, inner) = workspace_split(repos, path)?;
+                (root.join(repo), inner)
+            }
+            _ => (root.to_path_buf(), path.to_path_buf()),
+        };
+        Some(FileRevision {
+            repo,
+            rev: rev.to_string(),
+            path,
+            old_side: old != self.reversed,
+        })
+    }
+
     /// True if this diff was created from git changes
     pub fn is_git_mode(&self) -> bool {
-        self.repo_root.is_some()
+        self.repo_root.is_some() && !self.is_svn() && !self.is_p4()
+    }
+
+    /// True if this diff shows the local changes of an svn working copy
+    pub fn is_svn(&self) -> bool {
+        matches!(self.git_mode, Some(GitDiffMode::Svn))
+    }
+
+    /// True if this diff shows the opened files of a Perforce client
+    pub fn is_p4(&self) -> bool {
+        matches!(self.git_mode, Some(GitDiffMode::P4))
     }
 
     /// Return a display-friendly git range for header usage (if applicable).
     pub fn git_range_display(&self) -> Option<(String, String)> {
         let mode = self.git_mode.as_ref()?;
         match mode {
-            GitDiffMode::Range { from, to } => Some((format_ref(from), format_ref(to))),
+            GitDiffMode::Range { from, to } | GitDiffMode::Bisect { from, to, .. } => {
+                Some((format_ref(from), format_ref(to)))
+            }
             GitDiffMode::IndexRange { from, to_index } => {
                 let staged = "STAGED".to_string();
                 if *to_index {
@@ -502,17 +1872,27 @@ impl MultiFileDiff {
         })
     }
 
-    /// Check if current file's old content is empty
+    /// Check if current file's old content (as shown) is empty
     pub fn current_old_is_empty(&self) -> bool {
-        self.old_contents
+        let contents = if self.reversed {
+            &self.new_contents
+        } else {
+            &self.old_contents
+        };
+        contents
             .get(self.selected_index)
             .map(|s| s.is_empty())
             .unwrap_or(true)
     }
 
-    /// Check if current file's new content is empty
+    /// Check if current file's new content (as shown) is empty
     pub fn current_new_is_empty(&self) -> bool {
-        self.new_contents
+        let contents = if self.reversed {
+            &self.old_contents
+        } else {
+            &self.new_contents
+        };
+        contents
             .get(self.selected_index)
             .map(|s| s.is_empty())
             .unwrap_or(true)
@@ -525,14 +1905,31 @@ impl MultiFileDiff {
             Some(root) => root.clone(),
             None => return false,
         };
-        let mode = match &self.git_mode {
+        let mut mode = match &self.git_mode {
             Some(mode) => mode.clone(),
             None => return false,
         };
 
+        // Follow the bisect session to its current candidate
+        if let GitDiffMode::Bisect { .. } = mode {
+            let bisect = match crate::git::get_bisect_state(&repo_root) {
+                Ok(Some(bisect)) => bisect,
+                _ => return false,
+            };
+            let Some(good) = bisect.good else {
+                return false;
+            };
+            mode = GitDiffMode::Bisect {
+                from: good,
+                to: bisect.current,
+                paths: bisect.paths,
+            };
+        }
+
         // Get fresh list of changes
         let changes = match mode {
             GitDiffMode::Uncommitted => crate::git::get_uncommitted_changes(&repo_root),
+            GitDiffMode::Workspace { ref repos } => workspace_changes(&repo_root, repos),
             GitDiffMode::Staged => crate::git::get_staged_changes(&repo_root),
             GitDiffMode::Range { ref from, ref to } => {
                 crate::git::get_changes_between(&repo_root, from, to)
@@ -540,44 +1937,101 @@ impl MultiFileDiff {
             GitDiffMode::IndexRange { ref from, to_index } => {
                 crate::git::get_changes_between_index(&repo_root, from, !to_index)
             }
+            GitDiffMode::Bisect {
+                ref from,
+                ref to,
+                ref paths,
+            } => crate::git::get_changes_between_paths(&repo_root, from, to, paths),
+            GitDiffMode::Svn => crate::svn::get_working_changes(&repo_root)
+                .map_err(|err| crate::git::GitError::CommandFailed(err.to_string())),
+            GitDiffMode::P4 => crate::p4::get_opened_files(&repo_root)
+                .map_err(|err| crate::git::GitError::CommandFailed(err.to_string())),
+            GitDiffMode::Virtual => return false,
         };
         let changes = match changes {
             Ok(c) => c,
             Err(_) => return false,
         };
+        // Keep the previous candidate on screen if the new one has nothing to show
+        if changes.is_empty() && matches!(mode, GitDiffMode::Bisect { .. }) {
+            return false;
+        }
+        self.fetch_status = match &mode {
+            GitDiffMode::Uncommitted | GitDiffMode::Staged => {
+                prefetch_changes(&repo_root, &changes, Some("HEAD"), None)
+            }
+            GitDiffMode::Range { from, to } | GitDiffMode::Bisect { from, to, .. } => {
+                prefetch_changes(&repo_root, &changes, Some(from), Some(to))
+            }
+            GitDiffMode::IndexRange { from, to_index } if *to_index => {
+                prefetch_changes(&repo_root, &changes, Some(from), None)
+            }
+            GitDiffMode::IndexRange { from, .. } => {
+                prefetch_changes(&repo_root, &changes, None, Some(from))
+            }
+            _ => HashMap::new(),
+        };
 
         // Rebuild the entire diff state
         let mut files = Vec::new();
         let mut old_contents = Vec::new();
         let mut new_contents = Vec::new();
-        let engine = DiffEngine::new().with_word_level(true);
 
         for change in changes {
             let old_path = change
                 .old_path
                 .clone()
                 .unwrap_or_else(|| change.path.clone());
-            let (old_content, new_content) =
-                match mode {
-                    GitDiffMode::Uncommitted => {
-                        let old_content = match change.status {
-                            FileStatus::Added | FileStatus::Untracked => String::new(),
-                            _ => crate::git::get_head_content(&repo_root, &old_path)
-                                .unwrap_or_default(),
-                        };
-                        let new_content = match change.status {
-                            FileStatus::Deleted => String::new(),
-                            _ => {
-                                let full_path = repo_root.join(&change.path);
-                                std::fs::read_to_string(&full_path).unwrap_or_default()
-                            }
-                        };
-                        (old_content, new_content)
-                    }
-                    GitDiffMode::Staged => {
+            let (old_content, new_content) = match mode {
+                GitDiffMode::Uncommitted | GitDiffMode::Workspace { .. } => {
+                    let old_content = match change.status {
+                        FileStatus::Added | FileStatus::Untracked => String::new(),
+                        _ => mode.head_content(&repo_root, &old_path).unwrap_or_default(),
+                    };
+                    let new_content = match change.status {
+                        FileStatus::Deleted => String::new(),
+                        _ => {
+                            let full_path = repo_root.join(&change.path);
+                            std::fs::read_to_string(&full_path).unwrap_or_default()
+                        }
+                    };
+                    (old_content, new_content)
+                }
+                GitDiffMode::Staged => {
+                    let old_content = match change.status {
+                        FileStatus::Added | FileStatus::Untracked => String::new(),
+                        _ => {
+                            crate::git::get_head_content(&repo_root, &old_path).unwrap_or_default()
+                        }
+                    };
+                    let new_content = match change.status {
+                        FileStatus::Deleted => String::new(),
+                        _ => crate::git::get_staged_content(&repo_root, &change.path)
+                            .unwrap_or_default(),
+                    };
+                    (old_content, new_content)
+                }
+                GitDiffMode::Range { ref from, ref to }
+                | GitDiffMode::Bisect {
+                    ref from, ref to, ..
+                } => {
+                    let old_content = match change.status {
+                        FileStatus::Added | FileStatus::Untracked => String::new(),
+                        _ => crate::git::get_file_at_commit(&repo_root, from, &old_path)
+                            .unwrap_or_default(),
+                    };
+                    let new_content = match change.status {
+                        FileStatus::Deleted => String::new(),
+                        _ => crate::git::get_file_at_commit(&repo_root, to, &change.path)
+                            .unwrap_or_default(),
+                    };
+                    (old_content, new_content)
+                }
+                GitDiffMode::IndexRange { ref from, to_index } => {
+                    if to_index {
                         let old_content = match change.status {
                             FileStatus::Added | FileStatus::Untracked => String::new(),
-                            _ => crate::git::get_head_content(&repo_root, &old_path)
+                            _ => crate::git::get_file_at_commit(&repo_root, from, &old_path)
                                 .unwrap_or_default(),
                         };
                         let new_content = match change.status {
@@ -586,50 +2040,32 @@ impl MultiFileDiff {
                                 .unwrap_or_default(),
                         };
                         (old_content, new_content)
-                    }
-                    GitDiffMode::Range { ref from, ref to } => {
+                    } else {
                         let old_content = match change.status {
                             FileStatus::Added | FileStatus::Untracked => String::new(),
-                            _ => crate::git::get_file_at_commit(&repo_root, from, &old_path)
+                            _ => crate::git::get_staged_content(&repo_root, &old_path)
                                 .unwrap_or_default(),
                         };
                         let new_content = match change.status {
                             FileStatus::Deleted => String::new(),
-                            _ => crate::git::get_file_at_commit(&repo_root, to, &change.path)
+                            _ => crate::git::get_file_at_commit(&repo_root, from, &change.path)
                                 .unwrap_or_default(),
                         };
                         (old_content, new_content)
                     }
-                    GitDiffMode::IndexRange { ref from, to_index } => {
-                        if to_index {
-                            let old_content = match change.status {
-                                FileStatus::Added | FileStatus::Untracked => String::new(),
-                                _ => crate::git::get_file_at_commit(&repo_root, from, &old_path)
-                                    .unwrap_or_default(),
-                            };
-                            let new_content = match change.status {
-                                FileStatus::Deleted => String::new(),
-                                _ => crate::git::get_staged_content(&repo_root, &change.path)
-                                    .unwrap_or_default(),
-                            };
-                            (old_content, new_content)
-                        } else {
-                            let old_content = match change.status {
-                                FileStatus::Added | FileStatus::Untracked => String::new(),
-                                _ => crate::git::get_staged_content(&repo_root, &old_path)
-                                    .unwrap_or_default(),
-                            };
-                            let new_content = match change.status {
-                                FileStatus::Deleted => String::new(),
-                                _ => crate::git::get_file_at_commit(&repo_root, from, &change.path)
-                                    .unwrap_or_default(),
-                            };
-                            (old_content, new_content)
-                        }
-                    }
-                };
+                }
+                GitDiffMode::Svn | GitDiffMode::P4 => mode
+                    .local_contents(
+                        &repo_root,
+                        &change.path,
+                        change.old_path.as_deref(),
+                        change.status,
+                    )
+                    .unwrap_or_default(),
+                GitDiffMode::Virtual => return false,
+            };
 
-            let diff = engine.diff_strings(&old_content, &new_content);
+            let diff = self.engine.diff_strings(&old_content, &new_content);
 
             files.push(FileEntry {
                 display_name: change.path.display().to_string(),
@@ -638,6 +2074,7 @@ impl MultiFileDiff {
                 status: change.status,
                 insertions: diff.insertions,
                 deletions: diff.deletions,
+                disk_stamp: None,
             });
 
             old_contents.push(old_content);
@@ -646,6 +2083,7 @@ impl MultiFileDiff {
 
         // Update state
         let navigators: Vec<Option<DiffNavigator>> = (0..files.len()).map(|_| None).collect();
+        self.git_mode = Some(mode);
         self.files = files;
         self.old_contents = old_contents;
         self.new_contents = new_contents;
@@ -655,6 +2093,11 @@ impl MultiFileDiff {
         if self.selected_index >= self.files.len() {
             self.selected_index = self.files.len().saturating_sub(1);
         }
+        // Stats above were computed old → new
+        if self.structural || self.reversed {
+            self.set_structural(self.structural);
+        }
+        self.reapply_overlays();
 
         true
     }
@@ -667,10 +2110,13 @@ impl MultiFileDiff {
 
         // Get fresh content based on mode
         let (old_content, new_content) = match (&self.repo_root, &self.git_mode) {
-            (Some(repo_root), Some(GitDiffMode::Uncommitted)) => {
+            (
+                Some(repo_root),
+                Some(mode @ (GitDiffMode::Uncommitted | GitDiffMode::Workspace { .. })),
+            ) => {
                 let old_content = match file.status {
                     FileStatus::Added | FileStatus::Untracked => String::new(),
-                    _ => crate::git::get_head_content(repo_root, &old_path).unwrap_or_default(),
+                    _ => mode.head_content(repo_root, &old_path).unwrap_or_default(),
                 };
                 let new_content = match file.status {
                     FileStatus::Deleted => String::new(),
@@ -692,7 +2138,10 @@ impl MultiFileDiff {
                 };
                 (old_content, new_content)
             }
-            (Some(repo_root), Some(GitDiffMode::Range { from, to })) => {
+            (
+                Some(repo_root),
+                Some(GitDiffMode::Range { from, to } | GitDiffMode::Bisect { from, to, .. }),
+            ) => {
                 let old_content = match file.status {
                     FileStatus::Added | FileStatus::Untracked => String::new(),
                     _ => crate::git::get_file_at_commit(repo_root, from, &old_path)
@@ -733,19 +2182,177 @@ impl MultiFileDiff {
                     (old_content, new_content)
                 }
             }
+            (Some(root), Some(mode @ (GitDiffMode::Svn | GitDiffMode::P4))) => mode
+                .local_contents(root, &file.path, file.old_path.as_deref(), file.status)
+                .unwrap_or_default(),
+            (_, Some(GitDiffMode::Virtual)) => return,
             _ => {
-                let new_content = std::fs::read_to_string(&file.path).unwrap_or_default();
+                // Keep the loaded content if the path cannot be re-read (e.g. remote files)
+                let new_content = std::fs::read_to_string(&file.path)
+                    .unwrap_or_else(|_| self.new_contents[idx].clone());
                 (self.old_contents[idx].clone(), new_content)
             }
         };
 
-        // Update stored content
+        let new_content = self
+            .overlays
+            .get(&self.files[idx].path)
+            .cloned()
+            .unwrap_or(new_content);
+        self.set_contents(idx, old_content, new_content);
+    }
+
+    /// Path relative to the repository root, as files are listed
+    fn overlay_key(&self, path: &Path) -> PathBuf {
+        match &self.repo_root {
+            Some(root) if path.is_absolute() => path
+                .strip_prefix(root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.to_path_buf()),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Show `content` (e.g. an unsaved editor buffer) as the new side of
+    /// `path` instead of the file on disk, until [`Self::clear_overlay`].
+    /// Outside of the current file list, a file with uncommitted changes is
+    /// added (diffed against HEAD). Returns the index of the affected file.
+    pub fn set_overlay(&mut self, path: &Path, content: String) -> Option<usize> {
+        let key = self.overlay_key(path);
+        self.overlays.insert(key.clone(), content);
+        self.apply_overlay(&key)
+    }
+
+    /// Go back to the on-disk content of `path`. Returns the index of the
+    /// affected file.
+    pub fn clear_overlay(&mut self, path: &Path) -> Option<usize> {
+        let key = self.overlay_key(path);
+        self.overlays.remove(&key)?;
+        let idx = self.files.iter().position(|file| file.path == key)?;
+        let selected = std::mem::replace(&mut self.selected_index, idx);
+        self.refresh_current_file();
+        self.selected_index = selected;
+        Some(idx)
+    }
+
+    /// True if file `idx` shows overlay content instead of the disk
+    pub fn has_overlay(&self, idx: usize) -> bool {
+        self.files
+            .get(idx)
+            .is_some_and(|file| self.overlays.contains_key(&file.path))
+    }
+
+    fn apply_overlay(&mut self, key: &Path) -> Option<usize> {
+        let content = self.overlays.get(key)?.clone();
+        if let Some(idx) = self.files.iter().position(|file| file.path == key) {
+            let old_content = std::mem::take(&mut self.old_contents[idx]);
+            self.set_contents(idx, old_content, content);
+            return Some(idx);
+        }
+
+        let repo_root = self.repo_root.as_ref()?;
+        let mode = self.git_mode.as_ref().filter(|mode| {
+            matches!(
+                mode,
+                GitDiffMode::Uncommitted | GitDiffMode::Workspace { .. }
+            )
+        })?;
+        let (status, old_content) = match mode.head_content(repo_root, key) {
+            Ok(old_content) => (FileStatus::Modified, old_content),
+            Err(_) => (FileStatus::Added, String::new()),
+        };
+        if old_content == content {
+            return None;
+        }
+        self.files.push(FileEntry {
+            path: key.to_path_buf(),
+            old_path: None,
+            display_name: key.display().to_string(),
+            status,
+            insertions: 0,
+            deletions: 0,
+            disk_stamp: None,
+        });
+        self.old_contents.push(String::new());
+        self.new_contents.push(String::new());
+        self.navigators.push(None);
+        let idx = self.files.len() - 1;
+        if !self.adapted.is_empty() {
+            self.adapted.push(None);
+        }
+        self.set_contents(idx, old_content, content);
+        Some(idx)
+    }
+
+    fn reapply_overlays(&mut self) {
+        let keys: Vec<PathBuf> = self.overlays.keys().cloned().collect();
+        for key in keys {
+            self.apply_overlay(&key);
+        }
+    }
+
+    /// Working tree path of a file, if the new side of the diff is on disk
+    pub fn working_tree_path(&self, idx: usize) -> Option<PathBuf> {
+        let file = self.files.get(idx)?;
+        if self.overlays.contains_key(&file.path) {
+            return None;
+        }
+        match (&self.repo_root, &self.git_mode) {
+            (
+                Some(root),
+                Some(
+                    GitDiffMode::Uncommitted
+                    | GitDiffMode::Workspace { .. }
+                    | GitDiffMode::Svn
+                    | GitDiffMode::P4,
+                ),
+            ) => Some(root.join(&file.path)),
+            (_, None) if file.path.is_file() => Some(file.path.clone()),
+            _ => None,
+        }
+    }
+
+    /// True if the working tree file behind `idx` no longer holds the
+    /// content that is shown. The content is only re-read (and hashed) when
+    /// the modification time moved.
+    pub fn is_stale(&mut self, idx: usize) -> bool {
+        let Some(path) = self.working_tree_path(idx) else {
+            return false;
+        };
+        let modified = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if self.files[idx]
+            .disk_stamp
+            .is_some_and(|stamp| stamp.modified == modified)
+        {
+            return false;
+        }
+        // Read as when loading, so unreadable files compare as empty
+        let hash = content_hash(
+            std::fs::read_to_string(&path)
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+        if hash != content_hash(self.new_contents[idx].as_bytes()) {
+            return true;
+        }
+        self.files[idx].disk_stamp = Some(DiskStamp { modified, hash });
+        false
+    }
+
+    /// Replace the contents of file `idx` and recompute its stats
+    fn set_contents(&mut self, idx: usize, old_content: String, new_content: String) {
+        self.files[idx].disk_stamp = None;
         self.old_contents[idx] = old_content;
         self.new_contents[idx] = new_content;
+        if idx < self.adapted.len() {
+            self.adapted[idx] = self.adapt(idx);
+        }
 
         // Recompute diff stats
-        let engine = DiffEngine::new().with_word_level(true);
-        let diff = engine.diff_strings(&self.old_contents[idx], &self.new_contents[idx]);
+        let (old_content, new_content) = self.diff_contents(idx);
+        let diff = self.engine.diff_strings(old_content, new_content);
 
         // Update file entry stats
         self.files[idx].insertions = diff.insertions;
@@ -794,3 +2401,90 @@ fn format_ref(reference: &str) -> String {
 fn shorten_hash(hash: &str) -> String {
     hash.chars().take(7).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget_evicts_least_recently_used_and_resumes() {
+        let side = |text: &str| -> BTreeMap<PathBuf, String> {
+            ["a", "b", "c"]
+                .iter()
+                .map(|name| (PathBuf::from(name), text.repeat(50)))
+                .collect()
+        };
+        let mut multi = MultiFileDiff::from_entries(side("x\ny\n"), side("x\nz\n"));
+        multi.current_navigator().next();
+        multi.current_navigator().next();
+        let step = multi.current_navigator().state().current_step;
+        multi.next_file();
+        multi.current_navigator();
+        let budget = multi.navigator(1).unwrap().memory_estimate();
+
+        multi.set_memory_budget(Some(budget));
+        assert!(multi.is_evicted(0));
+        assert!(!multi.is_loaded(0));
+        assert!(multi.is_loaded(1));
+
+        multi.prev_file();
+        assert_eq!(multi.current_navigator().state().current_step, step);
+        assert!(!multi.is_evicted(0));
+        assert!(multi.is_evicted(1));
+        assert!(multi.has_evictions());
+    }
+
+    #[test]
+    fn test_sort_by_churn_orders_directories_then_their_files() {
+        let old: BTreeMap<PathBuf, String> = ["a/x", "a/y", "b/z", "top"]
+            .iter()
+            .map(|name| (PathBuf::from(name), "1\n2\n3\n".to_string()))
+            .collect();
+        let mut new = old.clone();
+        new.insert(PathBuf::from("a/x"), "1\n2\nthree\n".to_string());
+        new.insert(PathBuf::from("a/y"), "one\ntwo\nthree\n".to_string());
+        new.insert(
+            PathBuf::from("b/z"),
+            "one\ntwo\n3\nfour\nfive\n".to_string(),
+        );
+        new.insert(PathBuf::from("top"), "1\n2\n3\n4\n".to_string());
+        let mut multi = MultiFileDiff::from_entries(old, new);
+        multi.sort_files_by_churn();
+        let order: Vec<&str> = multi
+            .files
+            .iter()
+            .map(|file| file.display_name.as_str())
+            .collect();
+        // a: 2 + 6 lines, b: 6, root: 1
+        assert_eq!(order, ["a/y", "a/x", "b/z", "top"]);
+    }
+
+    #[test]
+    fn test_risk_queue_orders_hunks_across_files_and_reuses_scores() {
+        let old: BTreeMap<PathBuf, String> = ["plain.rs", "risky.rs"]
+            .iter()
+            .map(|name| (PathBuf::from(name), "a\nb\nc\nd\ne\nf\ng\nh\n".to_string()))
+            .collect();
+        let mut new = old.clone();
+        new.insert(
+            PathBuf::from("plain.rs"),
+            "A\nb\nc\nd\ne\nf\ng\nh\n".to_string(),
+        );
+        new.insert(
+            PathBuf::from("risky.rs"),
+            "a\nb\nc\nd\ne\nf\ng\nunsafe { x.unwrap() }\n".to_string(),
+        );
+        let mut multi = MultiFileDiff::from_entries(old, new);
+        let queue = multi.risk_queue();
+        let files: Vec<usize> = queue.iter().map(|group| group.hunks[0].file).collect();
+        assert_eq!(files, [1, 0]);
+        assert!(!multi.is_loaded(0) && !multi.is_loaded(1));
+        assert_eq!(multi.risk_cache.len(), 2);
+
+        let risks = multi.file_risks();
+        assert!(risks[1] > risks[0]);
+        multi.sort_files_by_risk();
+        assert_eq!(multi.files[0].display_name, "risky.rs");
+        assert_eq!(multi.file_risks(), [risks[1], risks[0]]);
+    }
+}
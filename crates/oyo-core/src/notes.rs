@@ -0,0 +1,222 @@
+//! Review verdicts kept as git notes on the reviewed commits
+//! (`refs/notes/oyo`), so they travel with the repository when the notes
+//! ref is pushed and fetched
+//!
+//! Each review is a block of the commit's note; reviews are appended, so
+//! several reviewers (or several rounds) keep their own:
+//!
+//! ```text
+//! Verdict: changes requested
+//! Reviewer: Ada <ada@example.com>
+//! Date: 2026-10-16
+//!
+//! The retry loop never gives up.
+//! ```
+
+use crate::git::{run_git, GitError};
+use std::path::Path;
+
+/// Notes ref the reviews are kept in
+pub const NOTES_REF: &str = "refs/notes/oyo";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Approved,
+    ChangesRequested,
+    Commented,
+}
+
+impl Verdict {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Approved => "approved",
+            Verdict::ChangesRequested => "changes requested",
+            Verdict::Commented => "commented",
+        }
+    }
+
+    /// Parse a label, or a short name (`approve`, `changes`, `comment`)
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "approved" | "approve" => Some(Verdict::Approved),
+            "changes requested" | "changes" => Some(Verdict::ChangesRequested),
+            "commented" | "comment" => Some(Verdict::Commented),
+            _ => None,
+        }
+    }
+}
+
+/// One review of a commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewNote {
+    pub verdict: Verdict,
+    /// `Name <email>`
+    pub reviewer: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub comment: String,
+}
+
+impl ReviewNote {
+    /// The note block of this review
+    pub fn format(&self) -> String {
+        let mut text = format!(
+            "Verdict: {}\nReviewer: {}\nDate: {}\n",
+            self.verdict.label(),
+            self.reviewer,
+            self.date
+        );
+        if !self.comment.trim().is_empty() {
+            text.push('\n');
+            text.push_str(self.comment.trim());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Reviews in a note; text before the first `Verdict:` line (a note
+/// written by hand) is ignored
+pub fn parse(note: &str) -> Vec<ReviewNote> {
+    let mut reviews: Vec<ReviewNote> = Vec::new();
+    let mut in_header = false;
+    for line in note.lines() {
+        if let Some(verdict) = line.strip_prefix("Verdict:") {
+            if let Some(verdict) = Verdict::parse(verdict) {
+                reviews.push(ReviewNote {
+                    verdict,
+                    reviewer: String::new(),
+                    date: String::new(),
+                    comment: String::new(),
+                });
+                in_header = true;
+                continue;
+            }
+        }
+        let Some(review) = reviews.last_mut() else {
+            continue;
+        };
+        if in_header {
+            if let Some(reviewer) = line.strip_prefix("Reviewer:") {
+                review.reviewer = reviewer.trim().to_string();
+                continue;
+            }
+            if let Some(date) = line.strip_prefix("Date:") {
+                review.date = date.trim().to_string();
+                continue;
+            }
+            in_header = false;
+            if line.trim().is_empty() {
+                continue;
+            }
+        }
+        review.comment.push_str(line);
+        review.comment.push('\n');
+    }
+    for review in &mut reviews {
+        review.comment = review.comment.trim_end().to_string();
+    }
+    reviews
+}
+
+/// Reviews of `commit`, oldest first
+pub fn read(repo_path: &Path, commit: &str) -> Result<Vec<ReviewNote>, GitError> {
+    let listed = run_git(
+        repo_path,
+        &["notes", "--ref", NOTES_REF, "list", commit],
+        None,
+    );
+    if listed.is_err() {
+        // No note on the commit (or no notes ref yet)
+        return Ok(Vec::new());
+    }
+    let note = run_git(
+        repo_path,
+        &["notes", "--ref", NOTES_REF, "show", commit],
+        None,
+    )?;
+    Ok(parse(&note))
+}
+
+/// Append a review to `commit`'s note
+pub fn append(repo_path: &Path, commit: &str, review: &ReviewNote) -> Result<(), GitError> {
+    run_git(
+        repo_path,
+        &["notes", "--ref", NOTES_REF, "append", "-F", "-", commit],
+        Some(&review.format()),
+    )?;
+    Ok(())
+}
+
+/// `Name <email>` of the configured git user, and today's date in their
+/// time zone
+pub fn reviewer(repo_path: &Path) -> Result<(String, String), GitError> {
+    let ident = run_git(repo_path, &["var", "GIT_COMMITTER_IDENT"], None)?;
+    // "Name <email> 1760000000 +0200"
+    let (who, when) = ident
+        .rsplit_once('>')
+        .ok_or_else(|| GitError::CommandFailed(format!("unexpected ident: {ident}")))?;
+    let mut when = when.split_whitespace();
+    let seconds: i64 = when.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let offset = when.next().and_then(parse_tz_offset).unwrap_or(0);
+    Ok((format!("{who}>"), civil_date(seconds + offset)))
+}
+
+/// Seconds east of UTC of a `+hhmm` / `-hhmm` offset
+fn parse_tz_offset(tz: &str) -> Option<i64> {
+    let (sign, digits) = match tz.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    let value: i64 = digits.parse().ok()?;
+    Some(sign * ((value / 100) * 3600 + (value % 100) * 60))
+}
+
+/// `YYYY-MM-DD` of a Unix time (proleptic Gregorian calendar)
+fn civil_date(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reviews_round_trip_through_a_note() {
+        let first = ReviewNote {
+            verdict: Verdict::ChangesRequested,
+            reviewer: "Ada <ada@example.com>".to_string(),
+            date: "2026-10-16".to_string(),
+            comment: "The retry loop\nnever gives up.".to_string(),
+        };
+        let second = ReviewNote {
+            verdict: Verdict::Approved,
+            reviewer: "Bo <bo@example.com>".to_string(),
+            date: "2026-10-17".to_string(),
+            comment: String::new(),
+        };
+        // `git notes append` separates blocks with a blank line
+        let note = format!("written by hand\n\n{}\n{}", first.format(), second.format());
+        assert_eq!(parse(&note), [first, second]);
+        assert_eq!(Verdict::parse("changes"), Some(Verdict::ChangesRequested));
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(1_792_108_800), "2026-10-16");
+        assert_eq!(civil_date(951_782_400), "2000-02-29");
+        assert_eq!(parse_tz_offset("-0130"), Some(-5400));
+    }
+}
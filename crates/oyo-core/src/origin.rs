@@ -0,0 +1,103 @@
+//! Change origins for three-way comparisons
+//!
+//! When "ours" and "theirs" share a common ancestor (base), each change
+//! between them can be attributed to the side that introduced it.
+
+use crate::change::Change;
+use crate::diff::DiffResult;
+use similar::{ChangeTag, TextDiff};
+use std::collections::{HashMap, HashSet};
+
+/// Which side of a three-way comparison introduced a change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOrigin {
+    /// Only "ours" (the old side) differs from base here
+    Ours,
+    /// Only "theirs" (the new side) differs from base here
+    Theirs,
+    /// Both sides changed these lines (a likely merge conflict)
+    Both,
+}
+
+/// 1-based line numbers of `side` that differ from `base`
+fn lines_changed_from_base(base: &str, side: &str) -> HashSet<usize> {
+    TextDiff::from_lines(base, side)
+        .iter_all_changes()
+        .filter(|change| change.tag() == ChangeTag::Insert)
+        .filter_map(|change| change.new_index())
+        .map(|index| index + 1)
+        .collect()
+}
+
+fn origin_of(
+    change: &Change,
+    ours_changed: &HashSet<usize>,
+    theirs_changed: &HashSet<usize>,
+) -> ChangeOrigin {
+    let old_line = change.spans.iter().find_map(|span| span.old_line);
+    let new_line = change.spans.iter().find_map(|span| span.new_line);
+    let in_ours = old_line.is_some_and(|line| ours_changed.contains(&line));
+    let in_theirs = new_line.is_some_and(|line| theirs_changed.contains(&line));
+    match (old_line, new_line) {
+        // Only in ours: either ours added it, or theirs deleted a base line
+        (Some(_), None) if in_ours => ChangeOrigin::Ours,
+        (Some(_), None) => ChangeOrigin::Theirs,
+        // Only in theirs: either theirs added it, or ours deleted a base line
+        (None, Some(_)) if in_theirs => ChangeOrigin::Theirs,
+        (None, Some(_)) => ChangeOrigin::Ours,
+        _ => match (in_ours, in_theirs) {
+            (true, false) => ChangeOrigin::Ours,
+            (false, true) => ChangeOrigin::Theirs,
+            _ => ChangeOrigin::Both,
+        },
+    }
+}
+
+/// Attribute each significant change of `diff` (ours → theirs) to the side
+/// that introduced it relative to `base`
+pub fn change_origins(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    diff: &DiffResult,
+) -> HashMap<usize, ChangeOrigin> {
+    let ours_changed = lines_changed_from_base(base, ours);
+    let theirs_changed = lines_changed_from_base(base, theirs);
+    diff.significant_changes
+        .iter()
+        .filter_map(|&id| diff.changes.get(id))
+        .map(|change| (change.id, origin_of(change, &ours_changed, &theirs_changed)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffEngine;
+
+    #[test]
+    fn test_change_origins() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "a\nB\nc\nd\ne\nours\n";
+        let theirs = "a\nb\nc\nD\n";
+        let diff = DiffEngine::new().diff_strings(ours, theirs);
+        let origins = change_origins(base, ours, theirs, &diff);
+
+        let origin_at = |text: &str| {
+            let change = diff
+                .changes
+                .iter()
+                .find(|change| change.spans.iter().any(|span| span.text == text))
+                .unwrap();
+            origins[&change.id]
+        };
+        // Ours renamed b -> B; theirs sees the base line
+        assert_eq!(origin_at("B"), ChangeOrigin::Ours);
+        // Theirs changed d -> D
+        assert_eq!(origin_at("d"), ChangeOrigin::Theirs);
+        // Theirs deleted e
+        assert_eq!(origin_at("e"), ChangeOrigin::Theirs);
+        // Ours added a line
+        assert_eq!(origin_at("ours"), ChangeOrigin::Ours);
+    }
+}
@@ -0,0 +1,198 @@
+//! Perforce support: files opened in the current client vs their have-revision
+
+use crate::git::{ChangedFile, FileStatus};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum P4Error {
+    #[error("Not in a Perforce client workspace")]
+    NotAClient,
+    #[error("p4 command failed: {0}")]
+    CommandFailed(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Run `p4 -ztag <args>` in `dir` and parse its tagged records
+fn run_ztag<I, S>(dir: &Path, args: I) -> Result<Vec<HashMap<String, String>>, P4Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = Command::new("p4")
+        .current_dir(dir)
+        .arg("-ztag")
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(P4Error::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(parse_ztag(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `-ztag` output: `... key value` lines, records separated by blank lines
+fn parse_ztag(output: &str) -> Vec<HashMap<String, String>> {
+    let mut records = Vec::new();
+    let mut record = HashMap::new();
+    for line in output.lines() {
+        let Some(field) = line.strip_prefix("... ") else {
+            if line.trim().is_empty() && !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+            continue;
+        };
+        let (key, value) = field.split_once(' ').unwrap_or((field, ""));
+        record.insert(key.to_string(), value.to_string());
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+    records
+}
+
+/// Root directory of the client workspace `path` belongs to
+pub fn get_client_root(path: &Path) -> Result<PathBuf, P4Error> {
+    let info = run_ztag(path, ["info"])?;
+    let root = info
+        .iter()
+        .find_map(|record| record.get("clientRoot"))
+        .filter(|root| !root.is_empty() && root.as_str() != "*unknown*")
+        .ok_or(P4Error::NotAClient)?;
+    Ok(PathBuf::from(root))
+}
+
+/// Map a `p4 opened` action to a file status; `None` for the source side
+/// of a move (shown as the rename's old path)
+fn action_status(action: &str) -> Option<FileStatus> {
+    match action {
+        "edit" | "integrate" => Some(FileStatus::Modified),
+        "add" | "branch" | "import" => Some(FileStatus::Added),
+        "delete" => Some(FileStatus::Deleted),
+        "move/add" => Some(FileStatus::Renamed),
+        _ => None,
+    }
+}
+
+/// List files opened in the client, relative to its root
+pub fn get_opened_files(client_root: &Path) -> Result<Vec<ChangedFile>, P4Error> {
+    let opened = run_ztag(client_root, ["opened"])?;
+    let depot_files: Vec<&str> = opened
+        .iter()
+        .filter_map(|record| record.get("depotFile"))
+        .chain(opened.iter().filter_map(|record| record.get("movedFile")))
+        .map(String::as_str)
+        .collect();
+    if depot_files.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Depot path -> local path
+    let mut args = vec!["where"];
+    args.extend(&depot_files);
+    let local: HashMap<String, PathBuf> = run_ztag(client_root, args)?
+        .into_iter()
+        .filter_map(|mut record| {
+            let depot = record.remove("depotFile")?;
+            let path = PathBuf::from(record.remove("path")?);
+            let path = path
+                .strip_prefix(client_root)
+                .map(Path::to_path_buf)
+                .unwrap_or(path);
+            Some((depot, path))
+        })
+        .collect();
+
+    let mut changes: Vec<ChangedFile> = opened
+        .iter()
+        .filter_map(|record| {
+            let status = action_status(record.get("action")?)?;
+            let path = local.get(record.get("depotFile")?)?.clone();
+            let old_path = match status {
+                FileStatus::Renamed => record
+                    .get("movedFile")
+                    .and_then(|moved| local.get(moved))
+                    .cloned(),
+                _ => None,
+            };
+            Some(ChangedFile {
+                path,
+                status,
+                old_path,
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// Get the have-revision (last synced) content of a file
+pub fn get_have_content(client_root: &Path, file: &Path) -> Result<String, P4Error> {
+    let output = Command::new("p4")
+        .current_dir(client_root)
+        .arg("print")
+        .arg("-q")
+        .arg(format!("{}#have", file.display()))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(P4Error::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Old (have-revision) and new (workspace) content of an opened file
+pub fn get_file_contents(
+    client_root: &Path,
+    file: &Path,
+    old_path: Option<&Path>,
+    status: FileStatus,
+) -> (String, String) {
+    let old_content = match status {
+        FileStatus::Added | FileStatus::Untracked => String::new(),
+        _ => get_have_content(client_root, old_path.unwrap_or(file)).unwrap_or_default(),
+    };
+    let new_content = match status {
+        FileStatus::Deleted => String::new(),
+        _ => std::fs::read_to_string(client_root.join(file)).unwrap_or_default(),
+    };
+    (old_content, new_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ztag_records() {
+        let output = "\
+... depotFile //depot/main/a.c
+... clientFile //ws/main/a.c
+... action edit
+... haveRev 3
+
+... depotFile //depot/main/b.c
+... action move/add
+... movedFile //depot/main/old b.c
+
+";
+        let records = parse_ztag(output);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["action"], "edit");
+        assert_eq!(records[1]["movedFile"], "//depot/main/old b.c");
+        assert_eq!(
+            action_status(&records[1]["action"]),
+            Some(FileStatus::Renamed)
+        );
+        assert_eq!(action_status("move/delete"), None);
+    }
+}
@@ -0,0 +1,1096 @@
+//! Patch series: `git format-patch` output (a directory of `.patch` files or
+//! an mbox) and plain unified diffs
+//!
+//! Each patch becomes its own [`crate::MultiFileDiff`]. File contents are
+//! rebuilt by applying the hunks to the pre-image blob when it is available
+//! in the repository; otherwise only the hunks (with their headers as
+//! context) are shown.
+
+use crate::git::CommitInfo;
+use crate::writeback::{WriteBackError, WriteOptions};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PatchError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No patches found in {0}")]
+    Empty(PathBuf),
+    #[error("{}: hunk(s) {} do not apply", .path.display(), hunk_list(.hunks))]
+    Rejected { path: PathBuf, hunks: Vec<usize> },
+    #[error(transparent)]
+    WriteBack(#[from] WriteBackError),
+}
+
+/// 1-based hunk numbers, comma separated
+fn hunk_list(hunks: &[usize]) -> String {
+    hunks
+        .iter()
+        .map(|idx| (idx + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchLine {
+    Context(String),
+    Delete(String),
+    Insert(String),
+}
+
+/// A `@@ -a,b +c,d @@` hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchHunk {
+    /// 1-based first line in the old file
+    pub old_start: usize,
+    /// 1-based first line in the new file
+    pub new_start: usize,
+    /// The full `@@ ... @@` header line
+    pub header: String,
+    pub lines: Vec<PatchLine>,
+    /// The old side ends without a newline (`\ No newline at end of file`)
+    pub old_no_newline: bool,
+    /// The new side ends without a newline
+    pub new_no_newline: bool,
+}
+
+/// Changes to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchFile {
+    /// `None` for added files
+    pub old_path: Option<PathBuf>,
+    /// `None` for deleted files
+    pub new_path: Option<PathBuf>,
+    /// Pre-image blob id from the `index` line (may be abbreviated)
+    pub old_blob: Option<String>,
+    pub hunks: Vec<PatchHunk>,
+}
+
+/// One patch (mail) of a series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// Original commit id (from the mbox `From <sha>` line)
+    pub commit: Option<String>,
+    /// Subject, including any `[PATCH n/m]` prefix
+    pub subject: String,
+    /// `Name <email>`
+    pub author: Option<String>,
+    pub date: Option<String>,
+    /// Commit message body (without the subject)
+    pub message: String,
+    pub files: Vec<PatchFile>,
+}
+
+impl PatchFile {
+    /// Path shown for this file
+    pub fn path(&self) -> Option<&Path> {
+        self.new_path.as_deref().or(self.old_path.as_deref())
+    }
+
+    /// Rebuild old and new contents.
+    ///
+    /// With the full pre-image the hunks are applied to it; if that is not
+    /// available (or does not match), only the hunks are reconstructed.
+    pub fn contents(&self, pre_image: Option<&str>) -> (String, String) {
+        let pre_image = if self.old_path.is_none() {
+            Some("")
+        } else {
+            pre_image
+        };
+        if let Some(new) = pre_image.and_then(|old| self.apply(old)) {
+            return (pre_image.unwrap_or_default().to_string(), new);
+        }
+
+        let mut old = String::new();
+        let mut new = String::new();
+        for hunk in &self.hunks {
+            push_line(&mut old, &hunk.header);
+            push_line(&mut new, &hunk.header);
+            for line in &hunk.lines {
+                match line {
+                    PatchLine::Context(text) => {
+                        push_line(&mut old, text);
+                        push_line(&mut new, text);
+                    }
+                    PatchLine::Delete(text) => push_line(&mut old, text),
+                    PatchLine::Insert(text) => push_line(&mut new, text),
+                }
+            }
+        }
+        (old, new)
+    }
+
+    /// Apply the hunks to `old`; `None` if the context does not match
+    fn apply(&self, old: &str) -> Option<String> {
+        let old_lines: Vec<&str> = old.lines().collect();
+        let mut new = String::new();
+        let mut pos = 0usize;
+        for hunk in &self.hunks {
+            // Pure insertions into an empty range use the line before them
+            let start = if hunk.lines.iter().all(|l| matches!(l, PatchLine::Insert(_))) {
+                hunk.old_start
+            } else {
+                hunk.old_start.checked_sub(1)?
+            };
+            if start < pos || start > old_lines.len() {
+                return None;
+            }
+            for line in &old_lines[pos..start] {
+                push_line(&mut new, line);
+            }
+            pos = start;
+            for line in &hunk.lines {
+                match line {
+                    PatchLine::Context(text) | PatchLine::Delete(text) => {
+                        if old_lines.get(pos) != Some(&text.as_str()) {
+                            return None;
+                        }
+                        if matches!(line, PatchLine::Context(_)) {
+                            push_line(&mut new, text);
+                        }
+                        pos += 1;
+                    }
+                    PatchLine::Insert(text) => push_line(&mut new, text),
+                }
+            }
+        }
+        for line in &old_lines[pos..] {
+            push_line(&mut new, line);
+        }
+        Some(new)
+    }
+
+    /// Apply the hunks to `target` where `check` (of the same target)
+    /// located them; rejected hunks are left out
+    pub fn apply_checked(&self, target: &str, check: &ApplyCheck) -> Option<String> {
+        let mut new = Rebuild::new(target);
+        let mut pos = 0;
+        let mut last = None;
+        for (hunk, hunk_check) in self.hunks.iter().zip(&check.hunks) {
+            let Some(at) = hunk_check.at else {
+                continue;
+            };
+            new.copy(pos..at)?;
+            pos = at;
+            let (front, back) = hunk_check.skip;
+            for line in &hunk.lines[front..hunk.lines.len() - back] {
+                match line {
+                    PatchLine::Context(_) => {
+                        new.copy(pos..pos + 1)?;
+                        pos += 1;
+                    }
+                    PatchLine::Delete(_) => pos += 1,
+                    PatchLine::Insert(text) => new.insert(text),
+                }
+            }
+            // Dropped trailing context is not at the end of the file
+            last = Some((hunk, pos, back == 0));
+        }
+        let ends = last.filter(|&(_, end, whole)| whole && end == new.lines.len());
+        new.copy(pos..new.lines.len())?;
+        Some(new.finish(ends.map(|(hunk, _, _)| hunk)))
+    }
+}
+
+/// How a hunk lines up with the content it is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkFit {
+    /// Applies at its recorded line with all of its context
+    Clean,
+    /// Applies `offset` lines away from its recorded line, ignoring up to
+    /// `fuzz` context lines at each end
+    Offset { offset: isize, fuzz: usize },
+    /// No line matches the hunk's context
+    Rejected,
+}
+
+/// Where one hunk applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HunkCheck {
+    pub fit: HunkFit,
+    /// 1-based line of the target where the hunk applies
+    pub line: Option<usize>,
+    /// 0-based target line where the (trimmed) old side starts
+    at: Option<usize>,
+    /// Context lines dropped at the start and end of the hunk
+    skip: (usize, usize),
+}
+
+/// Where the hunks of a file apply, in hunk order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyCheck {
+    pub hunks: Vec<HunkCheck>,
+}
+
+impl ApplyCheck {
+    /// True if every hunk applies, possibly with offset or fuzz
+    pub fn applies(&self) -> bool {
+        self.hunks.iter().all(|hunk| hunk.fit != HunkFit::Rejected)
+    }
+
+    /// True if every hunk applies at its recorded line with full context
+    pub fn is_clean(&self) -> bool {
+        self.hunks.iter().all(|hunk| hunk.fit == HunkFit::Clean)
+    }
+
+    /// Indices of the hunks that do not apply
+    pub fn rejected(&self) -> Vec<usize> {
+        self.hunks
+            .iter()
+            .enumerate()
+            .filter(|(_, hunk)| hunk.fit == HunkFit::Rejected)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
+
+/// Old side (context and deleted lines) of a hunk
+fn old_side(hunk: &PatchHunk) -> Vec<&str> {
+    hunk.lines
+        .iter()
+        .filter_map(|line| match line {
+            PatchLine::Context(text) | PatchLine::Delete(text) => Some(text.as_str()),
+            PatchLine::Insert(_) => None,
+        })
+        .collect()
+}
+
+/// Check where the hunks of `file` apply to `target` without changing
+/// anything. Like GNU patch, a hunk is searched for away from its recorded
+/// line, then with up to `max_fuzz` context lines dropped at each end.
+pub fn check_applies(file: &PatchFile, target: &str, max_fuzz: usize) -> ApplyCheck {
+    let lines: Vec<&str> = target.lines().collect();
+    let mut min = 0;
+    let mut shift = 0isize;
+    let mut hunks = Vec::with_capacity(file.hunks.len());
+    for hunk in &file.hunks {
+        let check = locate(&lines, hunk, min, shift, max_fuzz);
+        if let (Some(at), HunkFit::Clean | HunkFit::Offset { .. }) = (check.at, check.fit) {
+            let (front, back) = check.skip;
+            min = at + old_side(hunk).len() - front - back;
+            if let HunkFit::Offset { offset, .. } = check.fit {
+                shift = offset;
+            }
+        }
+        hunks.push(check);
+    }
+    ApplyCheck { hunks }
+}
+
+/// Find the first position at or after `min` where `hunk` applies, trying
+/// positions nearest to its recorded line (moved by `shift`) first
+fn locate(
+    lines: &[&str],
+    hunk: &PatchHunk,
+    min: usize,
+    shift: isize,
+    max_fuzz: usize,
+) -> HunkCheck {
+    let old = old_side(hunk);
+    let is_context = |line: &&PatchLine| matches!(line, PatchLine::Context(_));
+    let leading = hunk.lines.iter().take_while(is_context).count();
+    let trailing = hunk.lines.iter().rev().take_while(is_context).count();
+    // Pure insertions into an empty range use the line before them
+    let expected = if old.is_empty() {
+        hunk.old_start
+    } else {
+        hunk.old_start.saturating_sub(1)
+    } as isize;
+
+    let mut tried = None;
+    for fuzz in 0..=max_fuzz {
+        let (front, back) = (fuzz.min(leading), fuzz.min(trailing));
+        if tried == Some((front, back)) || front + back > old.len() {
+            break;
+        }
+        tried = Some((front, back));
+        let needle = &old[front..old.len() - back];
+        // Less context may still fit
+        let Some(last) = lines.len().checked_sub(needle.len()) else {
+            continue;
+        };
+        let base = expected + front as isize + shift;
+        let fits = |at: isize| {
+            at >= min as isize
+                && at <= last as isize
+                && lines[at as usize..at as usize + needle.len()] == *needle
+        };
+        let reach = lines.len() as isize + base.abs();
+        let found = (0..=reach)
+            .flat_map(|distance| [base + distance, base - distance])
+            .find(|&at| fits(at));
+        if let Some(at) = found {
+            let offset = at - front as isize - expected;
+            let fit = if fuzz == 0 && offset == 0 {
+                HunkFit::Clean
+            } else {
+                HunkFit::Offset { offset, fuzz }
+            };
+            return HunkCheck {
+                fit,
+                line: Some(at as usize - front + 1),
+                at: Some(at as usize),
+                skip: (front, back),
+            };
+        }
+    }
+    HunkCheck {
+        fit: HunkFit::Rejected,
+        line: None,
+        at: None,
+        skip: (0, 0),
+    }
+}
+
+/// Apply `file` to the file at `path` (atomically, see
+/// [`crate::writeback::write_file`]). Nothing is written unless every hunk
+/// applies; the check tells where each one went.
+pub fn apply_to_path(
+    file: &PatchFile,
+    path: &Path,
+    max_fuzz: usize,
+    options: WriteOptions,
+) -> Result<ApplyCheck, PatchError> {
+    let target = match std::fs::read_to_string(path) {
+        Ok(target) => target,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && file.old_path.is_none() => {
+            String::new()
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let check = check_applies(file, &target, max_fuzz);
+    let content = match file.apply_checked(&target, &check) {
+        Some(content) if check.applies() => content,
+        _ => {
+            return Err(PatchError::Rejected {
+                path: path.to_path_buf(),
+                hunks: check.rejected(),
+            })
+        }
+    };
+    crate::writeback::write_file(path, &content, Some(&target), options)?;
+    Ok(check)
+}
+
+/// Hunks left in a `.rej` file by a failed patch application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectFile {
+    /// The `.rej` file
+    pub path: PathBuf,
+    /// File the hunks were meant for (the `.rej` path without its extension)
+    pub target: PathBuf,
+    pub hunks: Vec<PatchHunk>,
+}
+
+impl RejectFile {
+    pub fn load(path: &Path) -> Result<Self, PatchError> {
+        let text = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = text.lines().collect();
+        let hunks: Vec<PatchHunk> = parse_diff(&lines)
+            .into_iter()
+            .flat_map(|file| file.hunks)
+            .collect();
+        if hunks.is_empty() {
+            return Err(PatchError::Empty(path.to_path_buf()));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            target: path.with_extension(""),
+            hunks,
+        })
+    }
+
+    /// Name of hunk `idx` in a list of rejected hunks
+    pub fn label(&self, idx: usize) -> String {
+        format!("{} · hunk {}", self.target.display(), idx + 1)
+    }
+
+    /// Write the remaining hunks back (see [`crate::writeback::write_file`]),
+    /// removing the file once none are left
+    pub fn save(&self) -> Result<(), WriteBackError> {
+        if self.hunks.is_empty() {
+            return Ok(std::fs::remove_file(&self.path)?);
+        }
+        let target = self.target.display();
+        let mut out = format!("--- {target}\n+++ {target}\n");
+        for hunk in &self.hunks {
+            push_line(&mut out, &hunk.header);
+            let last_old = hunk
+                .lines
+                .iter()
+                .rposition(|line| matches!(line, PatchLine::Context(_) | PatchLine::Delete(_)));
+            let last_new = hunk
+                .lines
+                .iter()
+                .rposition(|line| matches!(line, PatchLine::Context(_) | PatchLine::Insert(_)));
+            for (idx, line) in hunk.lines.iter().enumerate() {
+                match line {
+                    PatchLine::Context(text) => push_line(&mut out, &format!(" {text}")),
+                    PatchLine::Delete(text) => push_line(&mut out, &format!("-{text}")),
+                    PatchLine::Insert(text) => push_line(&mut out, &format!("+{text}")),
+                }
+                if (hunk.old_no_newline && Some(idx) == last_old)
+                    || (hunk.new_no_newline && Some(idx) == last_new)
+                {
+                    push_line(&mut out, "\\ No newline at end of file");
+                }
+            }
+        }
+        crate::writeback::write_file(&self.path, &out, None, WriteOptions::default())
+    }
+}
+
+/// `target` with the old side of `hunk` replaced by its new side at 0-based
+/// line `at`, whether or not the lines there match. Context lines keep the
+/// target's text.
+pub fn place_hunk(target: &str, hunk: &PatchHunk, at: usize) -> String {
+    let mut new = Rebuild::new(target);
+    let len = new.lines.len();
+    let at = at.min(len);
+    new.copy(0..at);
+    let mut pos = at;
+    for line in &hunk.lines {
+        match line {
+            PatchLine::Context(text) => {
+                if new.copy(pos..pos + 1).is_none() {
+                    new.insert(text);
+                }
+                pos += 1;
+            }
+            PatchLine::Delete(_) => pos += 1,
+            PatchLine::Insert(text) => new.insert(text),
+        }
+    }
+    let ends = pos >= len;
+    new.copy(pos.min(len)..len);
+    new.finish(ends.then_some(hunk))
+}
+
+/// Content rebuilt from a target's lines and inserted lines, keeping the
+/// target's line endings (inserted lines take those of its first line)
+struct Rebuild<'a> {
+    /// Lines of the target, each with its line ending (the last may have
+    /// none)
+    lines: Vec<&'a str>,
+    eol: &'static str,
+    out: String,
+}
+
+impl<'a> Rebuild<'a> {
+    fn new(target: &'a str) -> Self {
+        let lines: Vec<&str> = target.split_inclusive('\n').collect();
+        let eol = match lines.first() {
+            Some(line) if line.ends_with("\r\n") => "\r\n",
+            _ => "\n",
+        };
+        Self {
+            lines,
+            eol,
+            out: String::new(),
+        }
+    }
+
+    /// End the last line if it has no ending, before more follow
+    fn terminate(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push_str(self.eol);
+        }
+    }
+
+    /// Copy target lines `range` as they are; `None` if out of range
+    fn copy(&mut self, range: std::ops::Range<usize>) -> Option<()> {
+        self.lines.get(range.clone())?;
+        for idx in range {
+            let line = self.lines[idx];
+            self.terminate();
+            self.out.push_str(line);
+        }
+        Some(())
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.terminate();
+        self.out.push_str(text);
+        self.out.push_str(self.eol);
+    }
+
+    /// The content; when `last` (the hunk the content ends with) marks a
+    /// side as having no final newline, its new side decides whether the
+    /// file ends with one, else the target's ending is kept
+    fn finish(mut self, last: Option<&PatchHunk>) -> String {
+        match last {
+            Some(hunk) if hunk.new_no_newline => {
+                if let Some(out) = self.out.strip_suffix('\n') {
+                    let len = out.strip_suffix('\r').unwrap_or(out).len();
+                    self.out.truncate(len);
+                }
+            }
+            Some(hunk) if hunk.old_no_newline => self.terminate(),
+            _ => {}
+        }
+        self.out
+    }
+}
+
+/// Likely 0-based line for the old side of `hunk` in `target`: where it
+/// applies when ignoring as much context as needed, else its recorded line
+pub fn guess_placement(target: &str, hunk: &PatchHunk) -> usize {
+    let lines: Vec<&str> = target.lines().collect();
+    match locate(&lines, hunk, 0, 0, usize::MAX).line {
+        Some(line) => line - 1,
+        None => hunk.old_start.saturating_sub(1).min(lines.len()),
+    }
+}
+
+impl Patch {
+    /// Metadata for the commit panel
+    pub fn commit_info(&self) -> CommitInfo {
+        let (author, author_email) = match self.author.as_deref() {
+            Some(author) => match author.split_once('<') {
+                Some((name, email)) => (
+                    name.trim().to_string(),
+                    email.trim_end_matches('>').trim().to_string(),
+                ),
+                None => (author.trim().to_string(), String::new()),
+            },
+            None => (String::new(), String::new()),
+        };
+        let message = if self.message.is_empty() {
+            self.subject.clone()
+        } else {
+            format!("{}\n\n{}", self.subject, self.message)
+        };
+        CommitInfo {
+            id: self.commit.clone().unwrap_or_default(),
+            parents: Vec::new(),
+            author,
+            author_email,
+            date: self.date.clone().unwrap_or_default(),
+            message,
+            signature: None,
+        }
+    }
+}
+
+fn push_line(out: &mut String, line: &str) {
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Load a series from a directory of patch files (in name order) or from a
+/// single patch/mbox file
+pub fn load_series(path: &Path) -> Result<Vec<Patch>, PatchError> {
+    let mut patches = Vec::new();
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| matches!(ext, "patch" | "diff" | "mbox" | "eml"))
+            })
+            .collect();
+        entries.sort();
+        for entry in entries {
+            let text = std::fs::read_to_string(&entry)?;
+            patches.extend(parse_series(&text, &file_stem(&entry)));
+        }
+    } else {
+        let text = std::fs::read_to_string(path)?;
+        patches.extend(parse_series(&text, &file_stem(path)));
+    }
+    if patches.is_empty() {
+        return Err(PatchError::Empty(path.to_path_buf()));
+    }
+    Ok(patches)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn is_mbox_separator(line: &str) -> bool {
+    line.starts_with("From ") && !line.starts_with("From: ")
+}
+
+/// Parse an mbox (one or more mails) or a plain unified diff.
+/// `fallback_subject` names patches without a `Subject:` header.
+pub fn parse_series(text: &str, fallback_subject: &str) -> Vec<Patch> {
+    let mut messages: Vec<Vec<&str>> = Vec::new();
+    for line in text.lines() {
+        if is_mbox_separator(line) || messages.is_empty() {
+            messages.push(Vec::new());
+        }
+        if let Some(message) = messages.last_mut() {
+            message.push(line);
+        }
+    }
+    messages
+        .iter()
+        .map(|lines| parse_message(lines, fallback_subject))
+        .filter(|patch| !patch.files.is_empty())
+        .collect()
+}
+
+fn parse_message(lines: &[&str], fallback_subject: &str) -> Patch {
+    let mut patch = Patch {
+        commit: None,
+        subject: fallback_subject.to_string(),
+        author: None,
+        date: None,
+        message: String::new(),
+        files: Vec::new(),
+    };
+
+    let mut idx = 0;
+    if let Some(first) = lines.first().filter(|line| is_mbox_separator(line)) {
+        patch.commit = first.split_whitespace().nth(1).map(str::to_string);
+        idx = 1;
+    }
+
+    // Mail headers (with folded continuation lines)
+    let has_headers = lines
+        .get(idx)
+        .is_some_and(|line| line.contains(':') && !line.starts_with("diff "));
+    if has_headers {
+        let mut last_header: Option<&mut String> = None;
+        while idx < lines.len() && !lines[idx].is_empty() {
+            let line = lines[idx];
+            idx += 1;
+            if line.starts_with([' ', '\t']) {
+                if let Some(value) = last_header.as_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            last_header = match line.split_once(':') {
+                Some(("From", value)) => Some(patch.author.insert(value.trim().to_string())),
+                Some(("Date", value)) => Some(patch.date.insert(value.trim().to_string())),
+                Some(("Subject", value)) => {
+                    patch.subject = value.trim().to_string();
+                    Some(&mut patch.subject)
+                }
+                _ => None,
+            };
+        }
+    }
+
+    // Message body up to the `---` before the diffstat (or the first diff)
+    let mut body = Vec::new();
+    while idx < lines.len() && lines[idx] != "---" && !lines[idx].starts_with("diff --git ") {
+        body.push(lines[idx]);
+        idx += 1;
+    }
+    patch.message = body.join("\n").trim().to_string();
+
+    patch.files = parse_diff(&lines[idx..]);
+    patch
+}
+
+fn strip_prefix_path(path: &str) -> Option<PathBuf> {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(PathBuf::from(path))
+}
+
+/// Parse `@@ -a,b +c,d @@` into (a, b, c, d)
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let inner = line.strip_prefix("@@ -")?;
+    let (ranges, _) = inner.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |text: &str| -> Option<(usize, usize)> {
+        match text.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((text.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old)?;
+    let (new_start, new_count) = range(new)?;
+    Some((old_start, old_count, new_start, new_count))
+}
+
+fn parse_diff(lines: &[&str]) -> Vec<PatchFile> {
+    let mut files: Vec<PatchFile> = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        idx += 1;
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old, new) = rest.split_once(" b/").unwrap_or((rest, rest));
+            files.push(PatchFile {
+                old_path: strip_prefix_path(old),
+                new_path: strip_prefix_path(new),
+                old_blob: None,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("index ") {
+            if let Some(file) = files.last_mut() {
+                file.old_blob = rest
+                    .split("..")
+                    .next()
+                    .filter(|id| id.chars().any(|c| c != '0'))
+                    .map(str::to_string);
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("--- ") {
+            // Plain unified diffs have no `diff --git` line
+            let needs_file = files.last().is_none_or(|file| !file.hunks.is_empty());
+            if needs_file {
+                files.push(PatchFile {
+                    old_path: None,
+                    new_path: None,
+                    old_blob: None,
+                    hunks: Vec::new(),
+                });
+            }
+            if let Some(file) = files.last_mut() {
+                file.old_path = strip_prefix_path(path);
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = strip_prefix_path(path);
+            }
+            continue;
+        }
+        let Some((old_start, old_count, new_start, new_count)) = parse_hunk_header(line) else {
+            continue;
+        };
+        let mut hunk = PatchHunk {
+            old_start,
+            new_start,
+            header: line.to_string(),
+            lines: Vec::new(),
+            old_no_newline: false,
+            new_no_newline: false,
+        };
+        let (mut old_left, mut new_left) = (old_count, new_count);
+        // A no-newline marker may follow the hunk's last line
+        while idx < lines.len() && (old_left > 0 || new_left > 0 || lines[idx].starts_with('\\')) {
+            let body = lines[idx];
+            idx += 1;
+            if body.starts_with('\\') {
+                // "\ No newline at end of file", about the line before it
+                match hunk.lines.last() {
+                    Some(PatchLine::Context(_)) => {
+                        hunk.old_no_newline = true;
+                        hunk.new_no_newline = true;
+                    }
+                    Some(PatchLine::Delete(_)) => hunk.old_no_newline = true,
+                    Some(PatchLine::Insert(_)) => hunk.new_no_newline = true,
+                    None => {}
+                }
+                continue;
+            }
+            let (marker, text) = body.split_at(body.len().min(1));
+            match marker {
+                "+" => {
+                    hunk.lines.push(PatchLine::Insert(text.to_string()));
+                    new_left = new_left.saturating_sub(1);
+                }
+                "-" => {
+                    hunk.lines.push(PatchLine::Delete(text.to_string()));
+                    old_left = old_left.saturating_sub(1);
+                }
+                _ => {
+                    hunk.lines.push(PatchLine::Context(text.to_string()));
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+        }
+        if let Some(file) = files.last_mut() {
+            file.hunks.push(hunk);
+        }
+    }
+    files.retain(|file| file.path().is_some());
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIES: &str = "\
+From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Date: Mon, 1 Jan 2024 10:00:00 +0000
+Subject: [PATCH 1/2] Rename helper
+ across modules
+
+Body line.
+---
+ src/a.rs | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/src/a.rs b/src/a.rs
+index 3333333..4444444 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+--
+2.40.0
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 2/2] Add notes
+
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+index 0000000..5555555
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1,2 @@
++one
++two
+--
+2.40.0
+";
+
+    #[test]
+    fn test_parse_format_patch_series() {
+        let patches = parse_series(SERIES, "series");
+        assert_eq!(patches.len(), 2);
+
+        let first = &patches[0];
+        assert_eq!(
+            first.commit.as_deref(),
+            Some("1111111111111111111111111111111111111111")
+        );
+        assert_eq!(first.subject, "[PATCH 1/2] Rename helper across modules");
+        assert_eq!(first.message, "Body line.");
+        let info = first.commit_info();
+        assert_eq!(info.author, "Ada Lovelace");
+        assert_eq!(info.author_email, "ada@example.com");
+
+        let file = &first.files[0];
+        assert_eq!(file.path(), Some(Path::new("src/a.rs")));
+        assert_eq!(file.old_blob.as_deref(), Some("3333333"));
+        assert_eq!(file.hunks[0].lines.len(), 4);
+
+        // With the pre-image, hunks apply to the full file
+        let pre_image = "fn main() {\n    old();\n}\n// tail\n";
+        let (old, new) = file.contents(Some(pre_image));
+        assert_eq!(old, pre_image);
+        assert_eq!(new, "fn main() {\n    new();\n}\n// tail\n");
+
+        // Without it, only the hunk is rebuilt
+        let (old, new) = file.contents(None);
+        assert_eq!(old, "@@ -1,3 +1,3 @@\nfn main() {\n    old();\n}\n");
+        assert_eq!(new, "@@ -1,3 +1,3 @@\nfn main() {\n    new();\n}\n");
+
+        // Added file: empty pre-image, no blob lookup needed
+        let added = &patches[1].files[0];
+        assert_eq!(added.old_path, None);
+        assert_eq!(added.old_blob, None);
+        assert_eq!(added.contents(None), (String::new(), "one\ntwo\n".into()));
+    }
+
+    const STALE: &str = "\
+diff --git a/list.txt b/list.txt
+--- a/list.txt
++++ b/list.txt
+@@ -2,3 +2,3 @@
+ b
+-c
++C
+ d
+@@ -8,3 +8,3 @@
+ h
+-i
++I
+ j
+";
+
+    #[test]
+    fn test_check_applies_reports_offset_and_fuzz() {
+        let file = &parse_series(STALE, "stale")[0].files[0];
+        let fit = |target: &str, fuzz| {
+            let check = check_applies(file, target, fuzz);
+            check.hunks.iter().map(|hunk| hunk.fit).collect::<Vec<_>>()
+        };
+
+        let exact = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        assert_eq!(fit(exact, 0), [HunkFit::Clean, HunkFit::Clean]);
+
+        // Two lines were added at the top since the patch was made
+        let moved = format!("0\n00\n{exact}");
+        let check = check_applies(file, &moved, 0);
+        assert_eq!(check.hunks[0].fit, HunkFit::Offset { offset: 2, fuzz: 0 });
+        assert_eq!(check.hunks[1].line, Some(10));
+        assert_eq!(
+            file.apply_checked(&moved, &check).as_deref(),
+            Some("0\n00\na\nb\nC\nd\ne\nf\ng\nh\nI\nj\n")
+        );
+
+        // The context of the second hunk changed: it only applies with fuzz
+        let edited = exact.replace('h', "H");
+        assert_eq!(fit(&edited, 0), [HunkFit::Clean, HunkFit::Rejected]);
+        assert_eq!(
+            fit(&edited, 1),
+            [HunkFit::Clean, HunkFit::Offset { offset: 0, fuzz: 1 }]
+        );
+        let check = check_applies(file, &edited, 1);
+        assert_eq!(
+            file.apply_checked(&edited, &check).as_deref(),
+            Some("a\nb\nC\nd\ne\nf\ng\nH\nI\nj\n")
+        );
+
+        // Without fuzz the rejected hunk is left out
+        let check = check_applies(file, &edited, 0);
+        assert_eq!(check.rejected(), [1]);
+        assert_eq!(
+            file.apply_checked(&edited, &check).as_deref(),
+            Some("a\nb\nC\nd\ne\nf\ng\nH\ni\nj\n")
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_line_endings_and_missing_final_newline() {
+        let file = &parse_series(STALE, "stale")[0].files[0];
+        let crlf = "a\r\nb\r\nc\r\nd\r\ne\r\nf\r\ng\r\nh\r\ni\r\nj";
+        let check = check_applies(file, crlf, 0);
+        assert!(check.is_clean());
+        assert_eq!(
+            file.apply_checked(crlf, &check).as_deref(),
+            Some("a\r\nb\r\nC\r\nd\r\ne\r\nf\r\ng\r\nh\r\nI\r\nj")
+        );
+
+        let patch = "\
+diff --git a/last.txt b/last.txt
+--- a/last.txt
++++ b/last.txt
+@@ -1,2 +1,2 @@
+ a
+-b
+\\ No newline at end of file
++B
+";
+        let file = &parse_series(patch, "last")[0].files[0];
+        assert!(file.hunks[0].old_no_newline && !file.hunks[0].new_no_newline);
+        let check = check_applies(file, "a\nb", 0);
+        assert_eq!(
+            file.apply_checked("a\nb", &check).as_deref(),
+            Some("a\nB\n")
+        );
+        let reversed = PatchHunk {
+            lines: vec![
+                PatchLine::Context("a".into()),
+                PatchLine::Delete("B".into()),
+                PatchLine::Insert("b".into()),
+            ],
+            old_no_newline: false,
+            new_no_newline: true,
+            ..file.hunks[0].clone()
+        };
+        assert_eq!(place_hunk("a\r\nB\r\n", &reversed, 0), "a\r\nb");
+
+        // The marker survives a `.rej` round trip
+        let dir = std::env::temp_dir().join(format!("oyo-patch-eol-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rejects = RejectFile {
+            path: dir.join("last.txt.rej"),
+            target: dir.join("last.txt"),
+            hunks: vec![reversed],
+        };
+        rejects.save().unwrap();
+        assert_eq!(
+            RejectFile::load(&rejects.path).unwrap().hunks,
+            rejects.hunks
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_to_path_refuses_mismatch() {
+        let dir = std::env::temp_dir().join(format!("oyo-patch-apply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("list.txt");
+        let file = &parse_series(STALE, "stale")[0].files[0];
+
+        let mismatched = "a\nb\nX\nd\ne\nf\ng\nh\ni\nj\n";
+        std::fs::write(&path, mismatched).unwrap();
+        let err = apply_to_path(file, &path, 2, WriteOptions::default()).unwrap_err();
+        assert!(matches!(err, PatchError::Rejected { ref hunks, .. } if hunks == &[0]));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), mismatched);
+
+        std::fs::write(&path, "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n").unwrap();
+        let check = apply_to_path(file, &path, 0, WriteOptions::default()).unwrap();
+        assert!(check.is_clean());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a\nb\nC\nd\ne\nf\ng\nh\nI\nj\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reject_file_placement() {
+        let dir = std::env::temp_dir().join(format!("oyo-patch-rej-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rej = dir.join("list.txt.rej");
+        std::fs::write(&rej, STALE.split_once('\n').unwrap().1).unwrap();
+
+        let mut rejects = RejectFile::load(&rej).unwrap();
+        assert_eq!(rejects.target, dir.join("list.txt"));
+        assert_eq!(rejects.hunks.len(), 2);
+
+        // Nothing around the hunk matches: it goes to its recorded line
+        let target = "1\n2\n3\n4\n5\n";
+        let hunk = &rejects.hunks[0];
+        assert_eq!(guess_placement(target, hunk), 1);
+        assert_eq!(place_hunk(target, hunk, 2), "1\n2\n3\nC\n5\n");
+        // The changed line is enough to find it elsewhere
+        assert_eq!(guess_placement("x\nc\n", hunk), 0);
+
+        rejects.hunks.remove(0);
+        rejects.save().unwrap();
+        assert_eq!(RejectFile::load(&rej).unwrap().hunks, rejects.hunks);
+        rejects.hunks.clear();
+        rejects.save().unwrap();
+        assert!(!rej.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_patch_applies_to_working_tree() {
+        let dir = std::env::temp_dir().join(format!("oyo-patch-stale-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let edited = "a\nb\nc\nd\ne\nf\ng\nH\ni\nj\n";
+        std::fs::write(dir.join("list.txt"), edited).unwrap();
+        let patch = &parse_series(STALE, "stale")[0];
+
+        let mut diff = crate::MultiFileDiff::from_patch(patch, Some(&dir), 0);
+        let check = diff.patch_check(Path::new("list.txt")).unwrap();
+        assert_eq!(check.rejected(), [1]);
+        let nav = diff.current_navigator();
+        assert_eq!(nav.old_content(), edited);
+        assert_eq!(nav.new_content(), "a\nb\nC\nd\ne\nf\ng\nH\ni\nj\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
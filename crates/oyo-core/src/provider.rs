@@ -0,0 +1,379 @@
+//! Pluggable diff engines
+//!
+//! A [`DiffProvider`] turns an old and a new text into a [`DiffResult`].
+//! [`DiffEngine`] (line diffs from `similar`) is the default provider; another
+//! one is plugged in with [`DiffEngine::with_provider`], so everything that
+//! diffs through an engine uses it.
+//!
+//! - [`DifftasticProvider`]: structural diffs from an installed `difft`
+//! - `imara` feature: [`ImaraProvider`], line diffs from `imara-diff`
+
+use crate::diff::{split_lines, DiffEngine, DiffResult};
+use crate::text::Text;
+use serde::Deserialize;
+use similar::ChangeTag;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Computes the diff of two texts.
+///
+/// Every span of the result should be a range of `old` or `new` (see
+/// [`Text::slice`]) so diffs hold no copy of the contents.
+pub trait DiffProvider: Debug + Send + Sync {
+    /// Short name for logs and `oy config show`
+    fn name(&self) -> &'static str;
+
+    fn diff(&self, old: &Text, new: &Text) -> DiffResult;
+
+    /// Diff of the file at `path`; providers that parse the contents pick
+    /// the language from its name
+    fn diff_file(&self, path: &Path, old: &Text, new: &Text) -> DiffResult {
+        let _ = path;
+        self.diff(old, new)
+    }
+}
+
+impl DiffProvider for DiffEngine {
+    fn name(&self) -> &'static str {
+        self.provider_name()
+    }
+
+    fn diff(&self, old: &Text, new: &Text) -> DiffResult {
+        self.diff_text(old, new)
+    }
+}
+
+/// Structural diffs from difftastic (`difft --display json`): lines holding
+/// syntax changes are changed, the others are kept, so reformatting and
+/// moved punctuation are not steps. Changed lines are diffed by word and
+/// grouped into hunks by the wrapped engine, which also diffs the file when
+/// `difft` is missing or fails.
+#[derive(Debug, Clone)]
+pub struct DifftasticProvider {
+    engine: DiffEngine,
+    command: String,
+}
+
+impl DifftasticProvider {
+    /// `engine` supplies the word-level, reindent and context settings
+    pub fn new(engine: DiffEngine) -> Self {
+        Self {
+            engine,
+            command: "difft".to_string(),
+        }
+    }
+
+    /// Run `command` instead of `difft`
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = command.into();
+        self
+    }
+
+    /// Novel (changed) lines of each side, 0-based, as difftastic reports
+    /// them for `old` and `new` written under the file name of `path`
+    fn novel_lines(
+        &self,
+        path: &Path,
+        old: &str,
+        new: &str,
+    ) -> Result<(HashSet<usize>, HashSet<usize>), String> {
+        static RUN: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "oyo-difft-{}-{}",
+            std::process::id(),
+            RUN.fetch_add(1, Ordering::Relaxed)
+        ));
+        let name = path.file_name().unwrap_or("file".as_ref());
+        let (old_path, new_path) = (dir.join("old").join(name), dir.join("new").join(name));
+        let run = || -> Result<std::process::Output, String> {
+            for (path, content) in [(&old_path, old), (&new_path, new)] {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                }
+                std::fs::write(path, content).map_err(|err| err.to_string())?;
+            }
+            Command::new(&self.command)
+                .env("DFT_UNSTABLE", "yes")
+                .args(["--display", "json", "--color", "never"])
+                .arg(&old_path)
+                .arg(&new_path)
+                .output()
+                .map_err(|err| format!("{}: {err}", self.command))
+        };
+        let output = run();
+        let _ = std::fs::remove_dir_all(&dir);
+        let output = output?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        parse_difftastic(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+impl DiffProvider for DifftasticProvider {
+    fn name(&self) -> &'static str {
+        "difftastic"
+    }
+
+    fn diff(&self, old: &Text, new: &Text) -> DiffResult {
+        self.diff_file(Path::new("file"), old, new)
+    }
+
+    fn diff_file(&self, path: &Path, old: &Text, new: &Text) -> DiffResult {
+        match self.novel_lines(path, old.as_str(), new.as_str()) {
+            Ok((old_novel, new_novel)) => {
+                // difftastic numbers lines by `\n` alone; a line holding a
+                // lone `\r` becomes one op per line of the diff
+                let old_lines: Vec<&str> = old.as_str().split_inclusive('\n').collect();
+                let new_lines: Vec<&str> = new.as_str().split_inclusive('\n').collect();
+                let ops: Vec<_> = novel_line_ops(&old_lines, &new_lines, &old_novel, &new_novel)
+                    .into_iter()
+                    .flat_map(|(tag, line)| {
+                        split_lines(line).into_iter().map(move |line| (tag, line))
+                    })
+                    .collect();
+                self.engine.diff_line_ops(old, new, &ops)
+            }
+            Err(err) => {
+                tracing::debug!(path = %path.display(), "difftastic failed: {err}");
+                self.engine.diff_text(old, new)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DifftFile {
+    #[serde(default)]
+    chunks: Vec<Vec<DifftLine>>,
+}
+
+#[derive(Deserialize)]
+struct DifftLine {
+    lhs: Option<DifftSide>,
+    rhs: Option<DifftSide>,
+}
+
+#[derive(Deserialize)]
+struct DifftSide {
+    line_number: usize,
+    #[serde(default)]
+    changes: Vec<serde::de::IgnoredAny>,
+}
+
+/// Lines with changes on each side of a difftastic JSON report
+fn parse_difftastic(json: &str) -> Result<(HashSet<usize>, HashSet<usize>), String> {
+    let file: DifftFile = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let (mut old, mut new) = (HashSet::new(), HashSet::new());
+    for line in file.chunks.iter().flatten() {
+        for (side, novel) in [(&line.lhs, &mut old), (&line.rhs, &mut new)] {
+            if let Some(side) = side.as_ref().filter(|side| !side.changes.is_empty()) {
+                novel.insert(side.line_number);
+            }
+        }
+    }
+    Ok((old, new))
+}
+
+/// Line diff from the novel lines of each side: the other lines are paired
+/// in order, and kept unless their text differs (e.g. reindented)
+fn novel_line_ops<'a>(
+    old: &[&'a str],
+    new: &[&'a str],
+    old_novel: &HashSet<usize>,
+    new_novel: &HashSet<usize>,
+) -> Vec<(ChangeTag, &'a str)> {
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && (old_novel.contains(&i) || j == new.len()) {
+            ops.push((ChangeTag::Delete, old[i]));
+            i += 1;
+        } else if j < new.len() && (new_novel.contains(&j) || i == old.len()) {
+            ops.push((ChangeTag::Insert, new[j]));
+            j += 1;
+        } else {
+            if old[i] == new[j] {
+                ops.push((ChangeTag::Equal, old[i]));
+            } else {
+                ops.push((ChangeTag::Delete, old[i]));
+                ops.push((ChangeTag::Insert, new[j]));
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Line diffs from imara-diff's histogram algorithm, which is faster than
+/// `similar` on large files; changed lines are diffed by word and grouped
+/// into hunks by the wrapped engine
+#[cfg(feature = "imara")]
+#[derive(Debug, Clone, Default)]
+pub struct ImaraProvider {
+    engine: DiffEngine,
+}
+
+#[cfg(feature = "imara")]
+impl ImaraProvider {
+    /// `engine` supplies the word-level, reindent and context settings
+    pub fn new(engine: DiffEngine) -> Self {
+        Self { engine }
+    }
+}
+
+#[cfg(feature = "imara")]
+impl DiffProvider for ImaraProvider {
+    fn name(&self) -> &'static str {
+        "imara"
+    }
+
+    fn diff(&self, old: &Text, new: &Text) -> DiffResult {
+        use imara_diff::intern::InternedInput;
+        use imara_diff::Algorithm;
+
+        // Lines as `similar` splits them, so line numbers match the other
+        // engines even for a lone `\r`
+        let old_lines = split_lines(old.as_str());
+        let new_lines = split_lines(new.as_str());
+        let mut input = InternedInput::default();
+        input.update_before(old_lines.iter().copied());
+        input.update_after(new_lines.iter().copied());
+        let mut ops = Vec::new();
+        let (mut old_pos, mut new_pos) = (0, 0);
+        imara_diff::diff(
+            Algorithm::Histogram,
+            &input,
+            |before: std::ops::Range<u32>, after: std::ops::Range<u32>| {
+                let (before, after) = (
+                    before.start as usize..before.end as usize,
+                    after.start as usize..after.end as usize,
+                );
+                for &line in &old_lines[old_pos..before.start] {
+                    ops.push((ChangeTag::Equal, line));
+                }
+                for &line in &old_lines[before.clone()] {
+                    ops.push((ChangeTag::Delete, line));
+                }
+                for &line in &new_lines[after.clone()] {
+                    ops.push((ChangeTag::Insert, line));
+                }
+                old_pos = before.end;
+                new_pos = after.end;
+            },
+        );
+        debug_assert_eq!(old_lines.len() - old_pos, new_lines.len() - new_pos);
+        for &line in &old_lines[old_pos..] {
+            ops.push((ChangeTag::Equal, line));
+        }
+        self.engine.diff_line_ops(old, new, &ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change::ChangeKind;
+    use std::sync::Arc;
+
+    /// Reports every line as changed
+    #[derive(Debug)]
+    struct Rewrite;
+
+    impl DiffProvider for Rewrite {
+        fn name(&self) -> &'static str {
+            "rewrite"
+        }
+
+        fn diff(&self, old: &Text, new: &Text) -> DiffResult {
+            let ops: Vec<_> = split_lines(old.as_str())
+                .into_iter()
+                .map(|line| (ChangeTag::Delete, line))
+                .chain(
+                    split_lines(new.as_str())
+                        .into_iter()
+                        .map(|line| (ChangeTag::Insert, line)),
+                )
+                .collect();
+            DiffEngine::new()
+                .with_word_level(false)
+                .diff_line_ops(old, new, &ops)
+        }
+    }
+
+    #[test]
+    fn test_engine_diffs_with_its_provider() {
+        let old = "a\nb\nc\n";
+        let new = "a\nB\nc\n";
+        let default = DiffEngine::new();
+        assert_eq!(default.provider_name(), "similar");
+        assert_eq!(default.diff_strings(old, new).significant_changes.len(), 1);
+
+        let engine = DiffEngine::new().with_provider(Arc::new(Rewrite));
+        assert_eq!(engine.provider_name(), "rewrite");
+        let result = engine.diff_strings(old, new);
+        assert_eq!(result.deletions, 3);
+        assert_eq!(result.insertions, 3);
+        assert!(result
+            .changes
+            .iter()
+            .all(|change| change.spans[0].kind != ChangeKind::Equal));
+    }
+
+    #[test]
+    fn test_difftastic_novel_lines_become_changes() {
+        let json = r#"{"chunks":[[
+            {"lhs":{"line_number":1,"changes":[{"start":4,"end":7,"content":"bar","highlight":"normal"}]},
+             "rhs":{"line_number":1,"changes":[{"start":4,"end":7,"content":"baz","highlight":"normal"}]}},
+            {"rhs":{"line_number":3,"changes":[{"start":0,"end":1,"content":"}","highlight":"delimiter"}]}}
+        ]],"language":"Rust","path":"a.rs","status":"changed"}"#;
+        let (old_novel, new_novel) = parse_difftastic(json).unwrap();
+        assert_eq!(old_novel, HashSet::from([1]));
+        assert_eq!(new_novel, HashSet::from([1, 3]));
+
+        // Line 2 is reindented but not novel: kept as a (reindent) change
+        let old = ["fn f() {\n", "  bar();\n", "  x\n"];
+        let new = ["fn f() {\n", "    baz();\n", "    x\n", "}\n"];
+        let ops = novel_line_ops(&old, &new, &old_novel, &new_novel);
+        let tags: Vec<ChangeTag> = ops.iter().map(|(tag, _)| *tag).collect();
+        use ChangeTag::{Delete, Equal, Insert};
+        assert_eq!(tags, [Equal, Delete, Insert, Delete, Insert, Insert]);
+
+        let unchanged = r#"{"language":"Rust","path":"a.rs","status":"unchanged"}"#;
+        assert_eq!(parse_difftastic(unchanged).unwrap(), Default::default());
+    }
+
+    #[test]
+    fn test_difftastic_falls_back_without_difft() {
+        let provider =
+            DifftasticProvider::new(DiffEngine::new()).with_command("oyo-test-missing-difft");
+        let engine = DiffEngine::new().with_provider(Arc::new(provider));
+        let result = engine.diff_text_for(Path::new("a.rs"), &"a\nb\n".into(), &"a\nc\n".into());
+        assert_eq!(engine.provider_name(), "difftastic");
+        assert_eq!((result.insertions, result.deletions), (1, 1));
+    }
+
+    #[cfg(feature = "imara")]
+    #[test]
+    fn test_imara_matches_the_default_engine_on_simple_edits() {
+        let engine = DiffEngine::new().with_provider(Arc::new(ImaraProvider::default()));
+        for (old, new) in [
+            (
+                "fn main() {\n    foo();\n    bar();\n}\n",
+                "fn main() {\n    foo(1);\n    bar();\n    baz();\n}\n",
+            ),
+            ("a\rb\nc\n", "a\rb\nC\n"),
+        ] {
+            let expected = DiffEngine::new().diff_strings(old, new);
+            let result = engine.diff_strings(old, new);
+            assert_eq!(result.insertions, expected.insertions);
+            assert_eq!(result.deletions, expected.deletions);
+            assert_eq!(result.hunks.len(), expected.hunks.len());
+            assert_eq!(crate::invariants::check(&result, old, new), Ok(()));
+        }
+    }
+}
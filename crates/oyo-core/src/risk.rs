@@ -0,0 +1,216 @@
+//! Risk heuristics for deciding what to review first
+//!
+//! Scores are relative, not absolute: they only make sense when compared
+//! against other hunks/files of the same diff.
+
+use crate::change::{Change, ChangeKind};
+use crate::diff::{DiffResult, Hunk};
+use std::path::Path;
+
+/// Why a hunk was considered risky
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskFactor {
+    /// Many changed lines
+    Size,
+    /// Many branches/conditions among the changed lines
+    Branching,
+    /// Touches error handling (unwrap, panic, catch, ...)
+    ErrorHandling,
+    /// Touches unsafe code
+    Unsafe,
+    /// Test code (scored lower than source)
+    Test,
+}
+
+/// Risk score of a single hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkRisk {
+    pub hunk_id: usize,
+    pub score: u32,
+    pub factors: Vec<RiskFactor>,
+}
+
+const SIZE_CAP: u32 = 40;
+const LARGE_HUNK_LINES: u32 = 20;
+const BRANCH_WEIGHT: u32 = 2;
+const BRANCHY_THRESHOLD: u32 = 3;
+const ERROR_HANDLING_WEIGHT: u32 = 10;
+const UNSAFE_WEIGHT: u32 = 20;
+
+const BRANCH_KEYWORDS: &[&str] = &[
+    "if", "else", "match", "for", "while", "loop", "switch", "case", "when", "elif", "?",
+];
+const BRANCH_OPERATORS: &[&str] = &["&&", "||", "=>"];
+const ERROR_MARKERS: &[&str] = &[
+    "unwrap(",
+    "expect(",
+    "panic!",
+    "unreachable!",
+    "Err(",
+    "catch",
+    "throw",
+    "except",
+    "raise",
+    "rescue",
+];
+
+/// Score one hunk of a diff
+pub fn score_hunk(hunk: &Hunk, changes: &[Change], path: &Path) -> HunkRisk {
+    let mut factors = Vec::new();
+
+    let lines = (hunk.insertions + hunk.deletions) as u32;
+    let mut score = lines.min(SIZE_CAP);
+    if lines >= LARGE_HUNK_LINES {
+        factors.push(RiskFactor::Size);
+    }
+
+    let mut branches = 0;
+    let mut error_handling = false;
+    let mut unsafe_code = false;
+    for change in hunk
+        .change_ids
+        .iter()
+        .filter_map(|id| changes.iter().find(|c| c.id == *id))
+    {
+        let (old_text, new_text) = change_texts(change);
+        for text in [old_text, new_text] {
+            branches += count_branches(&text);
+            error_handling |= ERROR_MARKERS.iter().any(|m| text.contains(m));
+            unsafe_code |= has_word(&text, "unsafe");
+        }
+    }
+
+    score += branches * BRANCH_WEIGHT;
+    if branches >= BRANCHY_THRESHOLD {
+        factors.push(RiskFactor::Branching);
+    }
+    if error_handling {
+        score += ERROR_HANDLING_WEIGHT;
+        factors.push(RiskFactor::ErrorHandling);
+    }
+    if unsafe_code {
+        score += UNSAFE_WEIGHT;
+        factors.push(RiskFactor::Unsafe);
+    }
+    if is_test_path(path) {
+        score /= 2;
+        factors.push(RiskFactor::Test);
+    }
+
+    HunkRisk {
+        hunk_id: hunk.id,
+        score,
+        factors,
+    }
+}
+
+/// Score every hunk of a diff, riskiest first
+pub fn rank_hunks(diff: &DiffResult, path: &Path) -> Vec<HunkRisk> {
+    let mut ranked: Vec<HunkRisk> = diff
+        .hunks
+        .iter()
+        .map(|hunk| score_hunk(hunk, &diff.changes, path))
+        .collect();
+    ranked.sort_by(|a, b| b.score.cmp(&a.score).then(a.hunk_id.cmp(&b.hunk_id)));
+    ranked
+}
+
+/// Risk of a whole file (sum of its hunk scores)
+pub fn file_risk(diff: &DiffResult, path: &Path) -> u32 {
+    diff.hunks
+        .iter()
+        .map(|hunk| score_hunk(hunk, &diff.changes, path).score)
+        .sum()
+}
+
+/// Heuristic: tests live under test dirs or in *_test / *.test / *_spec files
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            matches!(
+                c.as_os_str().to_str(),
+                Some("test" | "tests" | "spec" | "__tests__")
+            )
+        })
+    });
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_tests")
+        || stem.ends_with(".test")
+        || stem.ends_with(".spec")
+        || stem.ends_with("_spec")
+}
+
+/// Reassemble the old and new line text of a (possibly word-level) change
+fn change_texts(change: &Change) -> (String, String) {
+    let mut old_text = String::new();
+    let mut new_text = String::new();
+    for span in &change.spans {
+        match span.kind {
+            ChangeKind::Equal => {
+                old_text.push_str(&span.text);
+                new_text.push_str(&span.text);
+            }
+            ChangeKind::Delete => old_text.push_str(&span.text),
+            ChangeKind::Insert => new_text.push_str(&span.text),
+            ChangeKind::Replace => {
+                old_text.push_str(&span.text);
+                new_text.push_str(span.new_text.as_deref().unwrap_or_default());
+            }
+        }
+    }
+    (old_text, new_text)
+}
+
+fn count_branches(text: &str) -> u32 {
+    let keywords = text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '?'))
+        .filter(|word| BRANCH_KEYWORDS.contains(word) || (word.len() > 1 && word.ends_with('?')))
+        .count();
+    let operators: usize = BRANCH_OPERATORS
+        .iter()
+        .map(|op| text.matches(op).count())
+        .sum();
+    (keywords + operators) as u32
+}
+
+fn has_word(text: &str, word: &str) -> bool {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|w| w == word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffEngine;
+
+    #[test]
+    fn test_risky_hunk_ranks_first() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\nunsafe { if x.unwrap() && y { z } }\nj\n";
+        let diff = DiffEngine::new().diff_strings(old, new);
+        assert_eq!(diff.hunks.len(), 2);
+
+        let ranked = rank_hunks(&diff, Path::new("src/lib.rs"));
+        assert_eq!(ranked[0].hunk_id, diff.hunks[1].id);
+        assert!(ranked[0].factors.contains(&RiskFactor::Unsafe));
+        assert!(ranked[0].factors.contains(&RiskFactor::ErrorHandling));
+        assert!(ranked[0].score > ranked[1].score);
+    }
+
+    #[test]
+    fn test_test_code_scores_lower() {
+        let diff = DiffEngine::new().diff_strings("a\n", "if x.unwrap() { panic!() }\n");
+        let src = file_risk(&diff, Path::new("src/parser.rs"));
+        let test = file_risk(&diff, Path::new("tests/parser.rs"));
+        assert!(test < src);
+        assert!(is_test_path(Path::new("src/parser_test.go")));
+        assert!(is_test_path(Path::new("web/app.spec.ts")));
+        assert!(!is_test_path(Path::new("src/contest.rs")));
+    }
+}
@@ -1,11 +1,16 @@
 //! Step-through navigation for diffs
 
 use crate::change::{Change, ChangeKind, ChangeSpan};
-use crate::diff::DiffResult;
+use crate::diff::{split_lines, DiffResult};
+use crate::event::{EventBus, NavEvent};
+use crate::origin::ChangeOrigin;
+use crate::text::Text;
 use serde::{Deserialize, Serialize};
+use std::ops::{Range, RangeInclusive};
+use std::time::Duration;
 
 /// Direction of the last step action
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum StepDirection {
     #[default]
     None,
@@ -22,8 +27,132 @@ pub enum AnimationFrame {
     FadeIn,
 }
 
+/// Easing curve applied to the progress of each animation phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationEasing {
+    #[default]
+    Linear,
+    /// Slow start and end (cubic)
+    EaseInOut,
+    /// Fast rise that overshoots past 1.0 and settles with a damped bounce
+    Spring,
+}
+
+impl AnimationEasing {
+    /// Map linear progress (0.0..=1.0) to eased progress: 0.0 at the start
+    /// and 1.0 at the end; only `Spring` leaves 0.0..=1.0 in between
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            AnimationEasing::Linear => t,
+            AnimationEasing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            AnimationEasing::Spring => {
+                if t >= 1.0 {
+                    1.0
+                } else {
+                    1.0 - (-6.0 * t).exp() * (12.0 * t).cos()
+                }
+            }
+        }
+    }
+}
+
+/// Phase durations and easing of the step animation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationTiming {
+    pub fade_out: Duration,
+    pub fade_in: Duration,
+    pub easing: AnimationEasing,
+}
+
+impl AnimationTiming {
+    /// Frame `elapsed` into a step animation and the eased progress of its
+    /// phase; `Idle` (at 1.0) once both phases are over
+    pub fn frame_at(&self, elapsed: Duration) -> (AnimationFrame, f32) {
+        let progress = |elapsed: Duration, duration: Duration| {
+            let duration = duration.max(Duration::from_millis(1));
+            self.easing
+                .apply(elapsed.as_secs_f32() / duration.as_secs_f32())
+        };
+        if elapsed < self.fade_out {
+            return (AnimationFrame::FadeOut, progress(elapsed, self.fade_out));
+        }
+        let elapsed = elapsed - self.fade_out;
+        if elapsed < self.fade_in {
+            (AnimationFrame::FadeIn, progress(elapsed, self.fade_in))
+        } else {
+            (AnimationFrame::Idle, 1.0)
+        }
+    }
+}
+
+/// Order in which the changes of a diff are stepped through. Across hunks
+/// the order is always top to bottom; the orders differ in how the deletions
+/// and insertions of one changed block interleave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepOrder {
+    /// Hunk by hunk, all deletions of a block before its insertions
+    #[default]
+    Hunk,
+    /// Old-file line order: each deleted line is followed by its replacement
+    OldLine,
+    /// New-file line order: each inserted line is followed by the line it replaces
+    NewLine,
+}
+
+/// Change ids of `diff` in step order
+fn step_sequence(diff: &DiffResult, order: StepOrder) -> Vec<usize> {
+    let (delete_rank, insert_rank) = match order {
+        StepOrder::Hunk => return diff.significant_changes.clone(),
+        StepOrder::OldLine => (0, 1),
+        StepOrder::NewLine => (1, 0),
+    };
+    let significant: std::collections::HashSet<usize> =
+        diff.significant_changes.iter().copied().collect();
+
+    let mut sequence = Vec::with_capacity(significant.len());
+    // (offset within the block, rank, change id)
+    let mut block: Vec<(usize, u8, usize)> = Vec::new();
+    let (mut deletes, mut inserts) = (0, 0);
+    for change in &diff.changes {
+        if !significant.contains(&change.id) {
+            block.sort();
+            sequence.extend(block.drain(..).map(|(_, _, id)| id));
+            (deletes, inserts) = (0, 0);
+            continue;
+        }
+        let has_old = change.spans.iter().any(|span| span.old_line.is_some());
+        let has_new = change.spans.iter().any(|span| span.new_line.is_some());
+        let key = match (has_old, has_new) {
+            (true, false) => {
+                deletes += 1;
+                (deletes - 1, delete_rank)
+            }
+            (false, true) => {
+                inserts += 1;
+                (inserts - 1, insert_rank)
+            }
+            _ => {
+                deletes += 1;
+                inserts += 1;
+                (deletes.max(inserts) - 1, 0)
+            }
+        };
+        block.push((key.0, key.1, change.id));
+    }
+    block.sort();
+    sequence.extend(block.into_iter().map(|(_, _, id)| id));
+    sequence
+}
+
 /// The current state of stepping through a diff
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct StepState {
     /// Current step index (0 = initial state, 1 = after first change applied, etc.)
     pub current_step: usize,
@@ -95,6 +224,67 @@ impl StepState {
         }
         (self.current_step as f64 / (self.total_steps - 1) as f64) * 100.0
     }
+
+    /// Apply the remaining `steps` of a change; returns how many were applied
+    fn apply_fully(&mut self, change_id: usize, steps: usize) -> usize {
+        let applied = self
+            .applied_changes
+            .iter()
+            .filter(|&&id| id == change_id)
+            .count();
+        let missing = steps.saturating_sub(applied);
+        self.applied_changes
+            .extend(std::iter::repeat_n(change_id, missing));
+        self.current_step += missing;
+        missing
+    }
+
+    /// Unapply every step of a change; returns how many were unapplied
+    fn unapply_fully(&mut self, change_id: usize) -> usize {
+        let before = self.applied_changes.len();
+        self.applied_changes.retain(|&id| id != change_id);
+        let removed = before - self.applied_changes.len();
+        self.current_step = self.current_step.saturating_sub(removed);
+        removed
+    }
+}
+
+/// Unchanged lines shown with a hunk beyond its own context, such as the
+/// rest of the function the hunk is in
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HunkContext {
+    /// First and last change of the context, the hunk's own included
+    pub changes: RangeInclusive<usize>,
+    /// Runs of unchanged changes shown as one row while the context is folded
+    pub folds: Vec<RangeInclusive<usize>>,
+    /// What the context is (a function name), for the folded rows
+    pub label: String,
+}
+
+/// How a change is shown at the current step
+#[derive(Debug, Clone, Copy)]
+struct LineFlags {
+    is_applied: bool,
+    is_active: bool,
+    is_active_change: bool,
+    is_primary_active: bool,
+    show_hunk_extent: bool,
+}
+
+/// A line of the cached view
+enum CachedLine {
+    /// Same on every frame
+    Static(ViewLine),
+    /// Rebuilt for each frame: index of the change and how it is shown
+    Animated(usize, LineFlags),
+}
+
+/// The view at one step, reused by every animation frame of the step
+#[derive(Default)]
+struct ViewCache {
+    /// [`DiffNavigator::view_key`] the view was built for
+    key: Option<u64>,
+    lines: Vec<CachedLine>,
 }
 
 /// Navigator for stepping through diff changes
@@ -104,15 +294,44 @@ pub struct DiffNavigator {
     /// Current step state
     state: StepState,
     /// Original content (for reconstructing views)
-    old_content: String,
+    old_content: Text,
     /// New content (for reconstructing views)
-    new_content: String,
+    new_content: Text,
     /// Mapping from change ID to hunk index
     change_to_hunk: std::collections::HashMap<usize, usize>,
+    /// Changes that are never stepped through (always shown applied)
+    skipped_changes: std::collections::HashSet<usize>,
+    /// Changes applied in the same step as an earlier one, mapped to it
+    /// (see [`DiffResult::grouped_changes`])
+    step_leads: std::collections::HashMap<usize, usize>,
+    /// Where step/hunk transitions are published
+    events: EventBus,
+    /// Nesting of transitions (only the outermost one emits events)
+    transition_depth: usize,
+    /// Side that introduced each change (three-way comparisons only)
+    origins: std::collections::HashMap<usize, ChangeOrigin>,
+    /// Hunks shown as a single summary row and skipped while stepping
+    collapsed_hunks: std::collections::BTreeSet<usize>,
+    /// Extended context of hunks, once set (see
+    /// [`DiffNavigator::set_hunk_contexts`])
+    hunk_contexts: Option<std::collections::BTreeMap<usize, HunkContext>>,
+    /// Hunks whose context has its folds folded
+    folded_contexts: std::collections::BTreeSet<usize>,
+    /// Steps of changes split into one step per span group (see
+    /// [`DiffNavigator::set_split_changes`]); other changes take one
+    change_steps: std::collections::HashMap<usize, usize>,
+    /// How long computing the diff took, if measured
+    diff_time: Option<std::time::Duration>,
+    /// View of the current step, rebuilt when the step state changes
+    view_cache: std::cell::RefCell<ViewCache>,
 }
 
 impl DiffNavigator {
-    pub fn new(diff: DiffResult, old_content: String, new_content: String) -> Self {
+    pub fn new(
+        diff: DiffResult,
+        old_content: impl Into<Text>,
+        new_content: impl Into<Text>,
+    ) -> Self {
         let total_changes = diff.significant_changes.len();
         let total_hunks = diff.hunks.len();
 
@@ -124,15 +343,227 @@ impl DiffNavigator {
             }
         }
 
+        let step_leads: std::collections::HashMap<usize, usize> =
+            diff.grouped_changes.iter().copied().collect();
+        // Reindent-only changes excluded from stepping stay applied
+        let skipped_changes = diff
+            .reindent_changes
+            .iter()
+            .copied()
+            .filter(|id| !diff.significant_changes.contains(id) && !step_leads.contains_key(id))
+            .collect();
+
         Self {
             diff,
             state: StepState::new(total_changes, total_hunks),
-            old_content,
-            new_content,
+            old_content: old_content.into(),
+            new_content: new_content.into(),
             change_to_hunk,
+            skipped_changes,
+            step_leads,
+            events: EventBus::new(),
+            transition_depth: 0,
+            origins: std::collections::HashMap::new(),
+            collapsed_hunks: std::collections::BTreeSet::new(),
+            hunk_contexts: None,
+            folded_contexts: std::collections::BTreeSet::new(),
+            change_steps: std::collections::HashMap::new(),
+            diff_time: None,
+            view_cache: std::cell::RefCell::default(),
+        }
+    }
+
+    /// How long computing the diff took, if the caller measured it
+    pub fn diff_time(&self) -> Option<std::time::Duration> {
+        self.diff_time
+    }
+
+    pub fn set_diff_time(&mut self, time: std::time::Duration) {
+        self.diff_time = Some(time);
+    }
+
+    /// Approximate bytes held by the navigator: both contents, the diff and
+    /// the step bookkeeping
+    pub fn memory_estimate(&self) -> usize {
+        let entry = 2 * std::mem::size_of::<usize>();
+        let mut seen = std::collections::HashSet::new();
+        let contents: usize = [&self.old_content, &self.new_content]
+            .into_iter()
+            .filter(|content| seen.insert(content.buffer_id()))
+            .map(Text::buffer_size)
+            .sum();
+        contents
+            + self.diff.memory_estimate_excluding(&mut seen)
+            + self.state.applied_changes.capacity() * std::mem::size_of::<usize>()
+            + (self.change_to_hunk.capacity() + self.change_steps.capacity()) * entry
+            + self.skipped_changes.capacity() * std::mem::size_of::<usize>()
+            + self.step_leads.capacity() * entry
+            + self.view_cache.borrow().lines.capacity() * std::mem::size_of::<CachedLine>()
+    }
+
+    /// Step through changes in `order`; call before the first step
+    pub fn set_step_order(&mut self, order: StepOrder) {
+        let sequence = step_sequence(&self.diff, order);
+        let position: std::collections::HashMap<usize, usize> = sequence
+            .iter()
+            .enumerate()
+            .map(|(position, &id)| (id, position))
+            .collect();
+        for hunk in &mut self.diff.hunks {
+            hunk.change_ids
+                .sort_by_key(|id| position.get(id).copied().unwrap_or(usize::MAX));
+        }
+        self.diff.significant_changes = sequence;
+    }
+
+    /// Step through a line with several separate edits one span group at a
+    /// time instead of all at once; call before the first step, after
+    /// [`Self::set_step_order`]
+    pub fn set_split_changes(&mut self, enabled: bool) {
+        let mut sequence = std::mem::take(&mut self.diff.significant_changes);
+        sequence.dedup();
+        self.change_steps.clear();
+        if enabled {
+            let stepped: std::collections::HashSet<usize> = sequence.iter().copied().collect();
+            for change in &self.diff.changes {
+                let groups = change
+                    .span_groups()
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .map_or(0, |g| g + 1);
+                if groups > 1 && stepped.contains(&change.id) {
+                    self.change_steps.insert(change.id, groups);
+                }
+            }
+            sequence = sequence
+                .into_iter()
+                .flat_map(|id| std::iter::repeat_n(id, self.steps_of(id)))
+                .collect();
+        }
+        self.state.total_steps = sequence.len() + 1;
+        self.diff.significant_changes = sequence;
+    }
+
+    /// Change whose step applies `change_id` (itself unless grouped)
+    fn step_lead(&self, change_id: usize) -> usize {
+        self.step_leads
+            .get(&change_id)
+            .copied()
+            .unwrap_or(change_id)
+    }
+
+    /// Number of steps `change_id` takes
+    fn steps_of(&self, change_id: usize) -> usize {
+        self.change_steps.get(&change_id).copied().unwrap_or(1)
+    }
+
+    /// Span groups of a split change applied so far (`None` for changes
+    /// applied as a whole)
+    fn revealed_groups(&self, change_id: usize) -> Option<usize> {
+        self.change_steps.contains_key(&change_id).then(|| {
+            self.state
+                .applied_changes
+                .iter()
+                .filter(|&&id| id == change_id)
+                .count()
+        })
+    }
+
+    /// Collapse or expand a hunk
+    pub fn set_hunk_collapsed(&mut self, hunk_idx: usize, collapsed: bool) {
+        if hunk_idx >= self.diff.hunks.len() {
+            return;
+        }
+        if collapsed {
+            self.collapsed_hunks.insert(hunk_idx);
+        } else {
+            self.collapsed_hunks.remove(&hunk_idx);
+        }
+    }
+
+    /// Collapse or expand every hunk; expanding also unfolds their contexts
+    pub fn set_all_hunks_collapsed(&mut self, collapsed: bool) {
+        self.collapsed_hunks = if collapsed {
+            (0..self.diff.hunks.len()).collect()
+        } else {
+            self.folded_contexts.clear();
+            std::collections::BTreeSet::new()
+        };
+    }
+
+    pub fn is_hunk_collapsed(&self, hunk_idx: usize) -> bool {
+        self.collapsed_hunks.contains(&hunk_idx)
+    }
+
+    /// Extend hunks' context (the extent marked while on the hunk) past
+    /// their own lines; contexts with folds start folded
+    pub fn set_hunk_contexts(&mut self, contexts: std::collections::BTreeMap<usize, HunkContext>) {
+        self.folded_contexts = contexts
+            .iter()
+            .filter(|(_, context)| !context.folds.is_empty())
+            .map(|(&hunk_idx, _)| hunk_idx)
+            .collect();
+        self.hunk_contexts = Some(contexts);
+    }
+
+    /// True once [`DiffNavigator::set_hunk_contexts`] was called
+    pub fn has_hunk_contexts(&self) -> bool {
+        self.hunk_contexts.is_some()
+    }
+
+    pub fn hunk_context(&self, hunk_idx: usize) -> Option<&HunkContext> {
+        self.hunk_contexts.as_ref()?.get(&hunk_idx)
+    }
+
+    /// Fold or unfold a hunk's context, and that of the other hunks sharing it
+    pub fn set_context_folded(&mut self, hunk_idx: usize, folded: bool) {
+        let Some(contexts) = &self.hunk_contexts else {
+            return;
+        };
+        let Some(context) = contexts.get(&hunk_idx) else {
+            return;
+        };
+        for (&idx, other) in contexts {
+            if other.changes != context.changes || other.folds.is_empty() {
+                continue;
+            }
+            if folded {
+                self.folded_contexts.insert(idx);
+            } else {
+                self.folded_contexts.remove(&idx);
+            }
         }
     }
 
+    pub fn is_context_folded(&self, hunk_idx: usize) -> bool {
+        self.folded_contexts.contains(&hunk_idx)
+    }
+
+    /// Folded run of unchanged changes `change_id` is in, with its label
+    fn fold_at(&self, change_id: usize) -> Option<(&RangeInclusive<usize>, &str)> {
+        let contexts = self.hunk_contexts.as_ref()?;
+        self.folded_contexts.iter().find_map(|hunk_idx| {
+            let context = contexts.get(hunk_idx)?;
+            let fold = context
+                .folds
+                .iter()
+                .find(|fold| fold.contains(&change_id))?;
+            Some((fold, context.label.as_str()))
+        })
+    }
+
+    /// True if some rows of the view stand for several lines
+    fn has_folds(&self) -> bool {
+        !self.collapsed_hunks.is_empty() || !self.folded_contexts.is_empty()
+    }
+
+    fn in_collapsed_hunk(&self, change_id: usize) -> bool {
+        self.change_to_hunk
+            .get(&change_id)
+            .is_some_and(|hunk_idx| self.collapsed_hunks.contains(hunk_idx))
+    }
+
     /// Get the current step state
     pub fn state(&self) -> &StepState {
         &self.state
@@ -160,12 +591,63 @@ impl DiffNavigator {
         &self.diff
     }
 
+    /// Subscribe to step and hunk transitions
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<NavEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish transitions on a shared bus (e.g. the multi-file session's)
+    pub fn set_event_bus(&mut self, events: EventBus) {
+        self.events = events;
+    }
+
+    /// Attribute changes to the side that introduced them (see [`crate::origin`])
+    pub fn set_change_origins(&mut self, origins: std::collections::HashMap<usize, ChangeOrigin>) {
+        self.origins = origins;
+    }
+
+    /// Side that introduced a change, in three-way comparisons
+    pub fn change_origin(&self, change_id: usize) -> Option<ChangeOrigin> {
+        self.origins.get(&change_id).copied()
+    }
+
+    /// Run a navigation transition and publish the resulting events
+    fn transition<R>(&mut self, apply: impl FnOnce(&mut Self) -> R) -> R {
+        let before_step = self.state.current_step;
+        let before_change = self.state.active_change;
+        let before_hunk = self.state.current_hunk;
+
+        self.transition_depth += 1;
+        let result = apply(self);
+        self.transition_depth -= 1;
+
+        if self.transition_depth == 0 && self.events.has_subscribers() {
+            if self.state.current_hunk != before_hunk {
+                self.events.emit(NavEvent::HunkChanged {
+                    from: before_hunk,
+                    to: self.state.current_hunk,
+                });
+            }
+            if self.state.current_step != before_step || self.state.active_change != before_change {
+                self.events.emit(NavEvent::StepEntered {
+                    step: self.state.current_step,
+                    total_steps: self.state.total_steps,
+                    change_id: self.state.active_change,
+                    direction: self.state.step_direction,
+                });
+            }
+        }
+        result
+    }
+
     /// Set a non-animated cursor for classic (no-step) navigation.
     pub fn set_cursor_hunk(&mut self, hunk_idx: usize, change_id: Option<usize>) {
-        if self.state.total_hunks > 0 {
-            self.state.current_hunk = hunk_idx.min(self.state.total_hunks - 1);
-        }
-        self.state.cursor_change = change_id;
+        self.transition(|nav| {
+            if nav.state.total_hunks > 0 {
+                nav.state.current_hunk = hunk_idx.min(nav.state.total_hunks - 1);
+            }
+            nav.state.cursor_change = change_id;
+        })
     }
 
     /// Clear the non-animated cursor.
@@ -181,17 +663,28 @@ impl DiffNavigator {
     /// Move to the next step
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> bool {
+        self.transition(|nav| nav.step_forward())
+    }
+
+    fn step_forward(&mut self) -> bool {
         // Handle preview mode dissolution on first step
         if self.state.hunk_preview_mode {
             return self.dissolve_preview_for_step_down();
         }
 
+        let prev_hunk = self.state.current_hunk;
+
+        // Collapsed hunks are applied as a whole, without stopping
+        let skipped = self.skip_collapsed_forward();
         if self.state.is_at_end() {
-            return false;
+            if skipped {
+                self.state.step_direction = StepDirection::Forward;
+                self.state.active_change = None;
+                self.state.last_nav_was_hunk = false;
+            }
+            return skipped;
         }
 
-        let prev_hunk = self.state.current_hunk;
-
         self.state.step_direction = StepDirection::Forward;
         self.state.animating_hunk = None; // Clear hunk animation for single-step
 
@@ -218,6 +711,37 @@ impl DiffNavigator {
         true
     }
 
+    /// Apply the changes of collapsed hunks at the current step
+    fn skip_collapsed_forward(&mut self) -> bool {
+        let mut skipped = false;
+        while let Some(&change_id) = self.diff.significant_changes.get(self.state.current_step) {
+            if !self.in_collapsed_hunk(change_id) {
+                break;
+            }
+            self.state.applied_changes.push(change_id);
+            self.state.current_step += 1;
+            if let Some(hunk) = self.diff.hunk_for_change(change_id) {
+                self.state.current_hunk = hunk.id;
+            }
+            skipped = true;
+        }
+        skipped
+    }
+
+    /// Unapply the changes of collapsed hunks at the end of the applied ones
+    fn skip_collapsed_backward(&mut self) -> bool {
+        let mut skipped = false;
+        while let Some(&change_id) = self.state.applied_changes.last() {
+            if !self.in_collapsed_hunk(change_id) {
+                break;
+            }
+            self.state.applied_changes.pop();
+            self.state.current_step -= 1;
+            skipped = true;
+        }
+        skipped
+    }
+
     /// Dissolve preview mode on step down: keep first change, apply second
     fn dissolve_preview_for_step_down(&mut self) -> bool {
         if self.state.preview_from_backward {
@@ -228,8 +752,8 @@ impl DiffNavigator {
 
         let hunk = &self.diff.hunks[self.state.current_hunk];
 
-        // If hunk has only one change, stepping down exits the hunk
-        if hunk.change_ids.len() <= 1 {
+        // If hunk has only one change (or is collapsed), stepping down exits the hunk
+        if hunk.change_ids.len() <= 1 || self.collapsed_hunks.contains(&self.state.current_hunk) {
             self.state.hunk_preview_mode = false;
             // Let normal next() handle moving to next change/hunk
             return self.next();
@@ -263,6 +787,10 @@ impl DiffNavigator {
 
     /// Move to the previous step
     pub fn prev(&mut self) -> bool {
+        self.transition(|nav| nav.step_backward())
+    }
+
+    fn step_backward(&mut self) -> bool {
         // Handle preview mode dissolution on first step up: exit hunk entirely
         if self.state.hunk_preview_mode {
             return self.dissolve_preview_for_step_up();
@@ -276,11 +804,21 @@ impl DiffNavigator {
 
         self.state.step_direction = StepDirection::Backward;
         self.state.animating_hunk = None; // Clear hunk animation for single-step
+
+        // Collapsed hunks are unapplied as a whole, without stopping
+        let skipped = self.skip_collapsed_backward();
+        if self.state.is_at_start() {
+            self.state.active_change = None;
+            self.state.current_hunk = 0;
+            self.state.last_nav_was_hunk = false;
+            return skipped;
+        }
         self.state.current_step -= 1;
 
         // Pop the change and set it as active for backward animation
         if let Some(unapplied_change_id) = self.state.applied_changes.pop() {
             self.state.active_change = Some(unapplied_change_id);
+            self.skip_collapsed_backward();
 
             // Update current hunk based on last applied change
             if let Some(&last_applied) = self.state.applied_changes.last() {
@@ -358,6 +896,10 @@ impl DiffNavigator {
 
     /// Jump to a specific step
     pub fn goto(&mut self, step: usize) {
+        self.transition(|nav| nav.seek(step))
+    }
+
+    fn seek(&mut self, step: usize) {
         let target_step = step.min(self.state.total_steps - 1);
 
         // Reset to start
@@ -371,10 +913,8 @@ impl DiffNavigator {
         self.state.hunk_preview_mode = false; // Clear preview mode on goto
         self.state.preview_from_backward = false;
 
-        // Apply changes up to target step
-        for _ in 0..target_step {
-            self.next();
-        }
+        // Apply changes up to target step (a step may skip collapsed hunks)
+        while self.state.current_step < target_step && self.next() {}
 
         // Update which hunk we're in
         self.update_current_hunk();
@@ -397,6 +937,10 @@ impl DiffNavigator {
     /// If current hunk is partially/fully applied, completes it and moves to next hunk.
     /// Returns true if moved, false if no movement possible
     pub fn next_hunk(&mut self) -> bool {
+        self.transition(|nav| nav.hunk_forward())
+    }
+
+    fn hunk_forward(&mut self) -> bool {
         if self.diff.hunks.is_empty() {
             return false;
         }
@@ -418,11 +962,7 @@ impl DiffNavigator {
         if !has_applied_in_current {
             let mut moved = false;
             for &change_id in &current_hunk.change_ids {
-                if !self.state.applied_changes.contains(&change_id) {
-                    self.state.applied_changes.push(change_id);
-                    self.state.current_step += 1;
-                    moved = true;
-                }
+                moved |= self.state.apply_fully(change_id, self.steps_of(change_id)) > 0;
             }
 
             self.state.animating_hunk = Some(self.state.current_hunk);
@@ -442,11 +982,7 @@ impl DiffNavigator {
         self.state.preview_from_backward = false;
         let mut completed_any = false;
         for &change_id in &current_hunk.change_ids {
-            if !self.state.applied_changes.contains(&change_id) {
-                self.state.applied_changes.push(change_id);
-                self.state.current_step += 1;
-                completed_any = true;
-            }
+            completed_any |= self.state.apply_fully(change_id, self.steps_of(change_id)) > 0;
         }
 
         // Move to next hunk
@@ -469,11 +1005,7 @@ impl DiffNavigator {
         // Apply ALL changes of next hunk (full preview)
         let mut moved = false;
         for &change_id in &hunk.change_ids {
-            if !self.state.applied_changes.contains(&change_id) {
-                self.state.applied_changes.push(change_id);
-                self.state.current_step += 1;
-                moved = true;
-            }
+            moved |= self.state.apply_fully(change_id, self.steps_of(change_id)) > 0;
         }
 
         self.state.animating_hunk = Some(next_hunk_idx);
@@ -492,6 +1024,10 @@ impl DiffNavigator {
     /// Move to the previous hunk, unapplying changes
     /// Returns true if moved, false if nothing to unapply
     pub fn prev_hunk(&mut self) -> bool {
+        self.transition(|nav| nav.hunk_backward())
+    }
+
+    fn hunk_backward(&mut self) -> bool {
         if self.diff.hunks.is_empty() {
             return false;
         }
@@ -526,16 +1062,7 @@ impl DiffNavigator {
 
         // Unapply changes from current hunk that are applied
         for &change_id in current_hunk.change_ids.iter().rev() {
-            if let Some(pos) = self
-                .state
-                .applied_changes
-                .iter()
-                .position(|&id| id == change_id)
-            {
-                self.state.applied_changes.remove(pos);
-                self.state.current_step = self.state.current_step.saturating_sub(1);
-                moved = true;
-            }
+            moved |= self.state.unapply_fully(change_id) > 0;
         }
 
         // Set animating hunk for whole-hunk animation (keep pointing at the hunk
@@ -585,6 +1112,10 @@ impl DiffNavigator {
     /// Applies all changes through target hunk (full preview mode).
     /// Cursor lands at top of target hunk.
     pub fn goto_hunk(&mut self, hunk_idx: usize) {
+        self.transition(|nav| nav.seek_hunk(hunk_idx))
+    }
+
+    fn seek_hunk(&mut self, hunk_idx: usize) {
         if hunk_idx >= self.diff.hunks.len() {
             return;
         }
@@ -596,16 +1127,14 @@ impl DiffNavigator {
         for idx in 0..hunk_idx {
             let hunk = &self.diff.hunks[idx];
             for &change_id in &hunk.change_ids {
-                self.state.applied_changes.push(change_id);
-                self.state.current_step += 1;
+                self.state.apply_fully(change_id, self.steps_of(change_id));
             }
         }
 
         // Apply ALL changes of target hunk (full preview)
         let hunk = &self.diff.hunks[hunk_idx];
         for &change_id in &hunk.change_ids {
-            self.state.applied_changes.push(change_id);
-            self.state.current_step += 1;
+            self.state.apply_fully(change_id, self.steps_of(change_id));
         }
 
         self.state.current_hunk = hunk_idx;
@@ -620,6 +1149,10 @@ impl DiffNavigator {
     /// Jump to first change of current hunk, unapplying all but first
     /// Returns true if moved, false if not inside a hunk or already at start
     pub fn goto_hunk_start(&mut self) -> bool {
+        self.transition(|nav| nav.seek_hunk_start())
+    }
+
+    fn seek_hunk_start(&mut self) -> bool {
         if self.diff.hunks.is_empty() {
             return false;
         }
@@ -638,16 +1171,7 @@ impl DiffNavigator {
         // Unapply all changes in this hunk except the first
         let mut unapplied_any = false;
         for &change_id in &hunk.change_ids[1..] {
-            if let Some(pos) = self
-                .state
-                .applied_changes
-                .iter()
-                .position(|&id| id == change_id)
-            {
-                self.state.applied_changes.remove(pos);
-                self.state.current_step = self.state.current_step.saturating_sub(1);
-                unapplied_any = true;
-            }
+            unapplied_any |= self.state.unapply_fully(change_id) > 0;
         }
 
         // No-op if already at start (nothing unapplied and cursor on first)
@@ -665,6 +1189,10 @@ impl DiffNavigator {
     /// Jump to last change of current hunk, applying all changes in hunk
     /// Returns true if moved, false if not inside a hunk or already at end
     pub fn goto_hunk_end(&mut self) -> bool {
+        self.transition(|nav| nav.seek_hunk_end())
+    }
+
+    fn seek_hunk_end(&mut self) -> bool {
         if self.diff.hunks.is_empty() {
             return false;
         }
@@ -682,10 +1210,7 @@ impl DiffNavigator {
 
         // Apply all unapplied changes in this hunk
         for &change_id in &hunk.change_ids {
-            if !self.state.applied_changes.contains(&change_id) {
-                self.state.applied_changes.push(change_id);
-                self.state.current_step += 1;
-            }
+            self.state.apply_fully(change_id, self.steps_of(change_id));
         }
 
         // No-op if already at end (cursor on last)
@@ -746,8 +1271,12 @@ impl DiffNavigator {
         false
     }
 
-    /// Check if a change belongs to the current hunk (for persistent extent markers)
+    /// Check if a change belongs to the current hunk or its extended
+    /// context (for persistent extent markers)
     fn is_change_in_current_hunk(&self, change_id: usize) -> bool {
+        if let Some(context) = self.hunk_context(self.state.current_hunk) {
+            return context.changes.contains(&change_id);
+        }
         self.diff
             .hunks
             .get(self.state.current_hunk)
@@ -769,8 +1298,9 @@ impl DiffNavigator {
             .iter()
             .filter(|c| c.has_changes())
             .map(|c| {
-                let applied = self.state.applied_changes.contains(&c.id);
-                let active = self.state.active_change == Some(c.id);
+                let lead = self.step_lead(c.id);
+                let applied = self.state.applied_changes.contains(&lead);
+                let active = self.state.active_change == Some(lead);
                 (c, applied, active)
             })
             .collect()
@@ -785,8 +1315,105 @@ impl DiffNavigator {
     /// Phase-aware view for word-level animation
     /// CLI should pass its current animation phase for proper fade animations
     pub fn current_view_with_frame(&self, frame: AnimationFrame) -> Vec<ViewLine> {
-        let mut lines = Vec::new();
+        let cache = self.cached_view();
+
+        // Only lines of the animating change or hunk differ between frames
+        let mut lines = Vec::with_capacity(cache.lines.len());
+        for line in &cache.lines {
+            match line {
+                CachedLine::Static(line) => lines.push(line.clone()),
+                CachedLine::Animated(index, flags) => {
+                    lines.extend(self.build_line(&self.diff.changes[*index], *flags, frame));
+                }
+            }
+        }
+
+        if self.has_folds() {
+            self.collapse_view(lines)
+        } else {
+            lines
+        }
+    }
+
+    /// Number of lines in the view at `frame`
+    pub fn view_len(&self, frame: AnimationFrame) -> usize {
+        if self.has_folds() {
+            return self.current_view_with_frame(frame).len();
+        }
+        let cache = self.cached_view();
+        cache
+            .lines
+            .iter()
+            .filter(|line| match line {
+                CachedLine::Static(_) => true,
+                CachedLine::Animated(index, flags) => self
+                    .build_line(&self.diff.changes[*index], *flags, frame)
+                    .is_some(),
+            })
+            .count()
+    }
+
+    /// Lines `range` of the view at `frame` (as far as the view goes),
+    /// copying only those, so drawing a viewport of a huge file costs the
+    /// viewport rather than the file
+    pub fn view_window(&self, frame: AnimationFrame, range: Range<usize>) -> Vec<ViewLine> {
+        if self.has_folds() {
+            let mut lines = self.current_view_with_frame(frame);
+            lines.truncate(range.end);
+            lines.drain(..range.start.min(lines.len()));
+            return lines;
+        }
+        let cache = self.cached_view();
+        let mut lines = Vec::with_capacity(range.len());
+        let mut index = 0;
+        for line in &cache.lines {
+            if index >= range.end {
+                break;
+            }
+            match line {
+                CachedLine::Static(line) => {
+                    if range.contains(&index) {
+                        lines.push(line.clone());
+                    }
+                    index += 1;
+                }
+                CachedLine::Animated(change, flags) => {
+                    if let Some(line) = self.build_line(&self.diff.changes[*change], *flags, frame)
+                    {
+                        if range.contains(&index) {
+                            lines.push(line);
+                        }
+                        index += 1;
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// The view at the current step, built if the step state changed
+    fn cached_view(&self) -> std::cell::Ref<'_, ViewCache> {
+        let key = self.view_key();
+        if self.view_cache.borrow().key != Some(key) {
+            *self.view_cache.borrow_mut() = self.build_view_cache(key);
+        }
+        self.view_cache.borrow()
+    }
 
+    /// Fingerprint of everything the view depends on besides the frame
+    fn view_key(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.state.hash(&mut hasher);
+        self.diff.significant_changes.hash(&mut hasher);
+        self.change_steps.len().hash(&mut hasher);
+        self.hunk_contexts.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Lines of the view at the current step: built once, except for the
+    /// animated ones, which depend on the frame
+    fn build_view_cache(&self, key: u64) -> ViewCache {
         // Primary cursor destination: last applied change on backward, active_change on forward
         // Fallback to active_change at step 0 so cursor stays on fading line
         let primary_change_id = if self.state.cursor_change.is_some()
@@ -803,19 +1430,23 @@ impl DiffNavigator {
         } else {
             self.state.active_change
         };
+        let applied: std::collections::HashSet<usize> =
+            self.state.applied_changes.iter().copied().collect();
 
         // Track if we've assigned a primary active line (for fallback when primary_change_id is None)
         let mut primary_assigned = false;
+        let mut lines = Vec::with_capacity(self.diff.changes.len());
 
-        for change in &self.diff.changes {
-            let is_applied = self.state.applied_changes.contains(&change.id);
+        for (index, change) in self.diff.changes.iter().enumerate() {
+            let lead = self.step_lead(change.id);
+            let is_applied = applied.contains(&lead) || self.skipped_changes.contains(&change.id);
 
             // Primary active: cursor destination (decoupled from animation target on backward)
             let is_primary_active = primary_change_id == Some(change.id);
 
             // Active: part of the animating hunk (for animation styling)
             let is_in_hunk = self.is_change_in_animating_hunk(change.id);
-            let is_active_change = self.state.active_change == Some(change.id);
+            let is_active_change = self.state.active_change == Some(lead);
             // Active if: (1) the active_change, or (2) in animating hunk (lights up whole hunk during animation)
             let is_active = is_active_change || is_in_hunk;
             // Show extent marker if animating hunk OR (last nav was hunk AND change in current hunk)
@@ -833,43 +1464,127 @@ impl DiffNavigator {
                 primary_assigned = true;
             }
 
-            // Check if this is a word-level diff (multiple spans in one change that represents a line)
-            let is_word_level = change.spans.len() > 1;
-
-            if is_word_level {
-                // Combine all spans into a single line
-                let line = self.build_word_level_line(
-                    change,
-                    is_applied,
-                    is_active,
-                    is_active_change,
-                    is_primary_active,
-                    show_hunk_extent,
-                    frame,
-                );
-                if let Some(l) = line {
-                    lines.push(l);
-                }
-            } else {
-                // Single span - handle as before
-                if let Some(span) = change.spans.first() {
-                    if let Some(line) = self.build_single_span_line(
-                        span,
-                        change.id,
-                        is_applied,
-                        is_active,
-                        is_active_change,
-                        is_primary_active,
-                        show_hunk_extent,
-                        frame,
-                    ) {
-                        lines.push(line);
-                    }
-                }
+            let flags = LineFlags {
+                is_applied,
+                is_active,
+                is_active_change,
+                is_primary_active,
+                show_hunk_extent,
+            };
+            if is_active {
+                lines.push(CachedLine::Animated(index, flags));
+            } else if let Some(line) = self.build_line(change, flags, AnimationFrame::Idle) {
+                lines.push(CachedLine::Static(line));
             }
         }
 
-        lines
+        ViewCache {
+            key: Some(key),
+            lines,
+        }
+    }
+
+    /// View line of a change, if it shows one
+    fn build_line(
+        &self,
+        change: &Change,
+        flags: LineFlags,
+        frame: AnimationFrame,
+    ) -> Option<ViewLine> {
+        // Check if this is a word-level diff (multiple spans in one change that represents a line)
+        if change.spans.len() > 1 {
+            // Combine all spans into a single line
+            self.build_word_level_line(
+                change,
+                flags.is_applied,
+                flags.is_active,
+                flags.is_active_change,
+                flags.is_primary_active,
+                flags.show_hunk_extent,
+                frame,
+            )
+        } else {
+            // Single span - handle as before
+            self.build_single_span_line(
+                change.spans.first()?,
+                change.id,
+                flags.is_applied,
+                flags.is_active,
+                flags.is_active_change,
+                flags.is_primary_active,
+                flags.show_hunk_extent,
+                frame,
+            )
+        }
+    }
+
+    /// Replace the lines of each collapsed hunk, and of each fold of a
+    /// folded context, with one summary line
+    fn collapse_view(&self, lines: Vec<ViewLine>) -> Vec<ViewLine> {
+        let mut out = Vec::with_capacity(lines.len());
+        let mut lines = lines.into_iter().peekable();
+        while let Some(line) = lines.next() {
+            if let Some((fold, label)) = self.fold_at(line.change_id) {
+                let mut summary = line;
+                let mut count = 1;
+                while let Some(next) = lines.next_if(|next| fold.contains(&next.change_id)) {
+                    summary.old_line = summary.old_line.or(next.old_line);
+                    summary.new_line = summary.new_line.or(next.new_line);
+                    summary.show_hunk_extent |= next.show_hunk_extent;
+                    count += 1;
+                }
+                summary.content = format!("⋯ {count} unchanged lines of {label}").into();
+                summary.spans = vec![ViewSpan {
+                    text: summary.content.clone(),
+                    kind: ViewSpanKind::Equal,
+                }];
+                summary.kind = LineKind::Collapsed;
+                summary.has_changes = false;
+                out.push(summary);
+                continue;
+            }
+            let Some(hunk_idx) = line
+                .hunk_index
+                .filter(|idx| self.collapsed_hunks.contains(idx))
+            else {
+                out.push(line);
+                continue;
+            };
+            let hunk = &self.diff.hunks[hunk_idx];
+            let last_change = hunk
+                .change_ids
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or(line.change_id);
+            let mut summary = line;
+            // Absorb the rest of the hunk, including context between its changes
+            while let Some(next) = lines.next_if(|next| next.change_id <= last_change) {
+                summary.old_line = summary.old_line.or(next.old_line);
+                summary.new_line = summary.new_line.or(next.new_line);
+                summary.is_active |= next.is_active;
+                summary.is_active_change |= next.is_active_change;
+                summary.is_primary_active |= next.is_primary_active;
+                summary.show_hunk_extent |= next.show_hunk_extent;
+            }
+            let at_line = hunk.new_start.or(hunk.old_start).unwrap_or(0);
+            summary.content = format!(
+                "⋯ hunk {}: +{} −{} at line {}",
+                hunk_idx + 1,
+                hunk.insertions,
+                hunk.deletions,
+                at_line
+            )
+            .into();
+            summary.spans = vec![ViewSpan {
+                text: summary.content.clone(),
+                kind: ViewSpanKind::Equal,
+            }];
+            summary.kind = LineKind::Collapsed;
+            summary.has_changes = false;
+            out.push(summary);
+        }
+        out
     }
 
     /// Compute whether to show new content based on animation frame and direction.
@@ -918,7 +1633,27 @@ impl DiffNavigator {
         let mut view_spans = Vec::new();
         let mut content = String::new();
 
-        for span in &change.spans {
+        // Split changes apply one span group per step: only the group being
+        // stepped animates, groups before it show applied
+        let revealed = self.revealed_groups(change.id);
+        let span_groups = change.span_groups();
+        let stepping_group = revealed.map(|revealed| {
+            if self.state.step_direction == StepDirection::Backward {
+                revealed
+            } else {
+                revealed.saturating_sub(1)
+            }
+        });
+
+        for (span, group) in change.spans.iter().zip(span_groups) {
+            let (is_active, is_applied) = match (revealed, stepping_group, group) {
+                (Some(revealed), Some(stepping), Some(group)) => (
+                    is_active && (self.state.animating_hunk.is_some() || group == stepping),
+                    group < revealed,
+                ),
+                _ => (is_active, is_applied),
+            };
+
             // Phase-aware content and styling for active changes
             let (span_kind, text) = if is_active {
                 // Determine show_new based on frame and change type:
@@ -1022,7 +1757,7 @@ impl DiffNavigator {
         let has_changes = change.has_changes();
 
         Some(ViewLine {
-            content,
+            content: content.into(),
             spans: view_spans,
             kind: line_kind,
             old_line,
@@ -1145,12 +1880,82 @@ impl DiffNavigator {
     pub fn new_content(&self) -> &str {
         &self.new_content
     }
+
+    /// Exact text of the file after the first `step` steps (0 = before any
+    /// change, `total_steps - 1` = the new content), independent of the
+    /// current step. Changes never stepped through (skipped reindents) are
+    /// always applied, and a split change applies one span group per step.
+    pub fn materialize(&self, step: usize) -> String {
+        let mut revealed: std::collections::HashMap<usize, usize> =
+            std::collections::HashMap::new();
+        for &id in self.diff.significant_changes.iter().take(step) {
+            *revealed.entry(id).or_default() += 1;
+        }
+        let stepped: std::collections::HashSet<usize> =
+            self.diff.significant_changes.iter().copied().collect();
+
+        // Whole lines come from the contents (span text has no line ending)
+        let old_lines = split_lines(&self.old_content);
+        let new_lines = split_lines(&self.new_content);
+        fn line_of<'a>(
+            lines: &[&'a str],
+            side: fn(&ChangeSpan) -> Option<usize>,
+            change: &Change,
+        ) -> Option<&'a str> {
+            change
+                .spans
+                .iter()
+                .find_map(side)
+                .and_then(|line| lines.get(line.checked_sub(1)?).copied())
+        }
+
+        let mut out = String::with_capacity(self.new_content.len());
+        for change in &self.diff.changes {
+            let lead = self.step_lead(change.id);
+            let steps = self.steps_of(lead);
+            // Newline-only changes have no changed spans but are still stepped
+            let applied = if self.skipped_changes.contains(&change.id) || !stepped.contains(&lead) {
+                steps
+            } else {
+                revealed.get(&lead).copied().unwrap_or(0)
+            };
+            // A side's line is missing for pure insertions and deletions
+            if applied == 0 {
+                out.extend(line_of(&old_lines, |span| span.old_line, change));
+                continue;
+            }
+            if applied >= steps {
+                out.extend(line_of(&new_lines, |span| span.new_line, change));
+                continue;
+            }
+
+            // A split change part way through: the first `applied` groups
+            for (span, group) in change.spans.iter().zip(change.span_groups()) {
+                let is_applied = group.is_some_and(|group| group < applied);
+                match span.kind {
+                    ChangeKind::Equal => out.push_str(&span.text),
+                    ChangeKind::Delete if !is_applied => out.push_str(&span.text),
+                    ChangeKind::Insert if is_applied => out.push_str(&span.text),
+                    ChangeKind::Replace if is_applied => {
+                        out.push_str(span.new_text.as_deref().unwrap_or_default())
+                    }
+                    ChangeKind::Replace => out.push_str(&span.text),
+                    ChangeKind::Delete | ChangeKind::Insert => {}
+                }
+            }
+            let ending = line_of(&new_lines, |span| span.new_line, change)
+                .map(|line| &line[line.trim_end_matches(['\r', '\n']).len()..]);
+            out.push_str(ending.unwrap_or_default());
+        }
+        out
+    }
 }
 
 /// A styled span within a view line
 #[derive(Debug, Clone)]
 pub struct ViewSpan {
-    pub text: String,
+    /// A range of the diff's text, not a copy
+    pub text: Text,
     pub kind: ViewSpanKind,
 }
 
@@ -1167,8 +1972,8 @@ pub enum ViewSpanKind {
 /// A line in the current view with its status
 #[derive(Debug, Clone)]
 pub struct ViewLine {
-    /// Full content of the line
-    pub content: String,
+    /// Full content of the line (shared with the diff for whole-line spans)
+    pub content: Text,
     /// Individual styled spans (for word-level highlighting)
     pub spans: Vec<ViewSpan>,
     /// Overall line kind
@@ -1191,6 +1996,57 @@ pub struct ViewLine {
     pub has_changes: bool,
 }
 
+/// A row of the view named by the line it shows rather than its index, so
+/// the row can be found again after the view is rebuilt (a recomputed
+/// diff, a refreshed file, collapsed hunks)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewAnchor {
+    /// Change of the anchor line
+    pub change_id: usize,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    /// Rows from the anchor line to the anchored row
+    pub offset: usize,
+}
+
+impl ViewAnchor {
+    /// Anchor for `row` of `lines`, on the nearest line at or above it
+    pub fn at(lines: &[ViewLine], row: usize) -> Option<Self> {
+        let index = row.min(lines.len().checked_sub(1)?);
+        let line = &lines[index];
+        Some(Self {
+            change_id: line.change_id,
+            old_line: line.old_line,
+            new_line: line.new_line,
+            offset: row - index,
+        })
+    }
+
+    /// Row of `lines` the anchor names: its change if that still shows the
+    /// same lines, otherwise the first line at or after the anchored place
+    /// in the file (change IDs are renumbered when the diff is recomputed).
+    /// The old side goes first, as edits to the new file shift its lines.
+    pub fn resolve(&self, lines: &[ViewLine]) -> Option<usize> {
+        let index = lines
+            .iter()
+            .position(|line| {
+                line.change_id == self.change_id
+                    && line.old_line == self.old_line
+                    && line.new_line == self.new_line
+            })
+            .or_else(|| match (self.old_line, self.new_line) {
+                (Some(old), _) => lines
+                    .iter()
+                    .position(|line| line.old_line.is_some_and(|line| line >= old)),
+                (None, Some(new)) => lines
+                    .iter()
+                    .position(|line| line.new_line.is_some_and(|line| line >= new)),
+                (None, None) => None,
+            })?;
+        Some(index + self.offset)
+    }
+}
+
 /// The kind of line in the view
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineKind {
@@ -1208,6 +2064,8 @@ pub enum LineKind {
     PendingInsert,
     /// Line is about to be modified (active animation)
     PendingModify,
+    /// Summary of a collapsed hunk
+    Collapsed,
 }
 
 #[cfg(test)]
@@ -1215,6 +2073,98 @@ mod tests {
     use super::*;
     use crate::diff::DiffEngine;
 
+    #[test]
+    fn test_memory_estimate_grows_with_the_diff() {
+        let engine = DiffEngine::new();
+        let estimate = |old: &str, new: &str| {
+            let diff = engine.diff_strings(old, new);
+            DiffNavigator::new(diff, old.to_string(), new.to_string()).memory_estimate()
+        };
+        let small = estimate("a\n", "b\n");
+        let old = "line\n".repeat(1000);
+        let new = "changed line\n".repeat(1000);
+        let large = estimate(&old, &new);
+        assert!(small > 4);
+        assert!(large > old.len() + new.len() + 1000 * std::mem::size_of::<Change>());
+    }
+
+    #[test]
+    fn test_cached_view_animates_and_follows_steps() {
+        let old = "foo\nbar\nbaz\n";
+        let new = "foo\nqux\nbaz\n";
+        let diff = DiffEngine::new().diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        let contents = |nav: &DiffNavigator, frame| -> Vec<String> {
+            nav.current_view_with_frame(frame)
+                .iter()
+                .map(|line| line.content.to_string())
+                .collect()
+        };
+        let start = contents(&nav, AnimationFrame::Idle);
+
+        nav.next();
+        assert_eq!(contents(&nav, AnimationFrame::FadeOut), start);
+        assert_eq!(
+            contents(&nav, AnimationFrame::FadeIn),
+            ["foo", "qux", "baz"]
+        );
+        assert_eq!(
+            contents(&nav, AnimationFrame::FadeOut),
+            contents(&nav, AnimationFrame::FadeOut)
+        );
+
+        nav.prev();
+        nav.clear_active_change();
+        assert_eq!(contents(&nav, AnimationFrame::Idle), start);
+    }
+
+    #[test]
+    fn test_view_window_matches_the_full_view() {
+        let old: String = (0..40).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 5\n", "")
+            .replace("line 30", "line thirty");
+        let diff = DiffEngine::new().diff_strings(&old, &new);
+        let mut nav = DiffNavigator::new(diff, old.clone(), new.clone());
+        nav.next();
+        let contents = |lines: Vec<ViewLine>| -> Vec<String> {
+            lines.iter().map(|line| line.content.to_string()).collect()
+        };
+
+        for frame in [AnimationFrame::FadeOut, AnimationFrame::FadeIn] {
+            let full = contents(nav.current_view_with_frame(frame));
+            assert_eq!(nav.view_len(frame), full.len());
+            assert_eq!(contents(nav.view_window(frame, 3..8)), full[3..8]);
+            assert_eq!(contents(nav.view_window(frame, 38..60)), full[38..]);
+        }
+    }
+
+    #[test]
+    fn test_view_anchor_survives_a_recomputed_diff() {
+        let old = "a\nb\nc\nd\ne\nf\n";
+        let engine = DiffEngine::new();
+        let view = |new: &str| {
+            let diff = engine.diff_strings(old, new);
+            let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+            nav.goto_end();
+            nav.current_view()
+        };
+
+        let before = view("a\nb\nc\nD\ne\nf\n");
+        let row = before.iter().position(|line| line.content == "e").unwrap();
+        let anchor = ViewAnchor::at(&before, row).unwrap();
+        assert_eq!(anchor.resolve(&before), Some(row));
+
+        // Two lines inserted above: the anchor follows "e" down
+        let after = view("0\n1\na\nb\nc\nD\ne\nf\n");
+        let row = anchor.resolve(&after).unwrap();
+        assert_eq!(after[row].content, "e");
+
+        let past_end = ViewAnchor::at(&before, 20).unwrap();
+        assert_eq!(past_end.offset, 20 - (before.len() - 1));
+        assert_eq!(ViewAnchor::at(&[], 0), None);
+    }
+
     #[test]
     fn test_navigation() {
         let old = "foo\nbar\nbaz";
@@ -1240,6 +2190,208 @@ mod tests {
         assert!(nav.state().is_at_start());
     }
 
+    #[test]
+    fn test_skipped_reindent_shown_applied() {
+        use crate::diff::ReindentMode;
+
+        let old = "a\n\tb\nc\n";
+        let new = "a\n    b\nC\n";
+
+        let engine = DiffEngine::new().with_reindent(ReindentMode::Skip);
+        let diff = engine.diff_strings(old, new);
+        let nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+
+        // Only the real change is a step
+        assert_eq!(nav.state().total_steps, 2);
+
+        let view = nav.current_view();
+        let reindented = view.iter().find(|l| l.content.contains('b')).unwrap();
+        assert_eq!(reindented.content, "    b");
+        assert_eq!(reindented.kind, LineKind::Modified);
+        assert!(view.iter().any(|l| l.content == "c"));
+    }
+
+    #[test]
+    fn test_reindent_run_is_one_step() {
+        use crate::diff::ReindentMode;
+
+        let old = "\ta\n\tb\nc\n";
+        let new = "    a\n    b\nC\n";
+        let engine = DiffEngine::new().with_reindent(ReindentMode::Collapse);
+        let diff = engine.diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        assert_eq!(nav.state().total_steps, 3);
+        assert_eq!(nav.materialize(1), "    a\n    b\nc\n");
+
+        nav.next();
+        let view = nav.current_view();
+        let reindented: Vec<&ViewLine> =
+            view.iter().filter(|l| l.content.starts_with(' ')).collect();
+        assert_eq!(reindented.len(), 2);
+        assert!(reindented.iter().all(|l| l.is_active));
+    }
+
+    #[test]
+    fn test_collapsed_hunk_is_skipped() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "A\nB\nc\nd\ne\nf\ng\nH\n";
+        let diff = DiffEngine::new().with_context(1).diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        assert_eq!(nav.hunks().len(), 2);
+        nav.set_hunk_collapsed(0, true);
+
+        let view = nav.current_view();
+        let summary: Vec<_> = view
+            .iter()
+            .filter(|line| line.kind == LineKind::Collapsed)
+            .collect();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].content, "⋯ hunk 1: +2 −2 at line 1");
+        assert_eq!(view.len(), 7);
+
+        // The first step applies the collapsed hunk and the next change
+        assert!(nav.next());
+        assert_eq!(nav.state().current_step, 3);
+        assert_eq!(nav.state().current_hunk, 1);
+        assert!(nav.prev());
+        assert!(nav.state().is_at_start());
+
+        nav.goto(3);
+        assert_eq!(nav.state().current_step, 3);
+        nav.set_hunk_collapsed(0, false);
+        assert!(nav
+            .current_view()
+            .iter()
+            .all(|l| l.kind != LineKind::Collapsed));
+    }
+
+    #[test]
+    fn test_folded_context_shows_one_row_per_fold() {
+        let old = "fn f() {\n    a\n    b\n    c\n    d\n    e\n}\n";
+        let new = "fn f() {\n    a\n    b\n    c\n    d\n    E\n}\n";
+        let diff = DiffEngine::new().diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        let last = nav.diff().changes.len() - 1;
+        let context = HunkContext {
+            changes: 0..=last,
+            folds: vec![1..=3],
+            label: "f".to_string(),
+        };
+        nav.set_hunk_contexts([(0, context)].into_iter().collect());
+        assert!(nav.is_context_folded(0));
+
+        let view = nav.current_view();
+        let folded: Vec<_> = view
+            .iter()
+            .filter(|line| line.kind == LineKind::Collapsed)
+            .collect();
+        assert_eq!(folded.len(), 1);
+        assert_eq!(folded[0].content, "⋯ 3 unchanged lines of f");
+        assert_eq!(folded[0].new_line, Some(2));
+        assert_eq!(nav.view_len(AnimationFrame::Idle), view.len());
+
+        // The hunk's extent covers the whole function
+        nav.set_hunk_scope(true);
+        assert!(nav.current_view().iter().all(|line| line.show_hunk_extent));
+
+        nav.set_all_hunks_collapsed(false);
+        assert!(!nav.is_context_folded(0));
+        assert!(nav
+            .current_view()
+            .iter()
+            .all(|l| l.kind != LineKind::Collapsed));
+    }
+
+    #[test]
+    fn test_step_order_interleaves_block() {
+        let old = "ctx\na\nb\nc\n";
+        let new = "ctx\nx\ny\n";
+        let kinds = |order: StepOrder| {
+            let diff = DiffEngine::new().diff_strings(old, new);
+            let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+            nav.set_step_order(order);
+            let mut kinds = String::new();
+            while nav.next() {
+                let change = nav.active_change().unwrap();
+                kinds.push(match change.spans[0].kind {
+                    ChangeKind::Delete => 'd',
+                    _ => 'i',
+                });
+            }
+            kinds
+        };
+
+        assert_eq!(kinds(StepOrder::Hunk), "dddii");
+        assert_eq!(kinds(StepOrder::OldLine), "didid");
+        assert_eq!(kinds(StepOrder::NewLine), "ididd");
+    }
+
+    #[test]
+    fn test_transitions_emit_events() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\nI\nj\n";
+
+        let diff = DiffEngine::new().diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        let events = nav.subscribe();
+
+        nav.next();
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NavEvent::StepEntered {
+                step: 1,
+                direction: StepDirection::Forward,
+                ..
+            })
+        ));
+        assert!(events.try_recv().is_err());
+
+        // goto replays steps internally but publishes a single transition
+        nav.goto_end();
+        let received: Vec<NavEvent> = events.try_iter().collect();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], NavEvent::HunkChanged { from: 0, to: 1 });
+        assert!(matches!(received[1], NavEvent::StepEntered { step: 2, .. }));
+
+        // No-op navigation is silent
+        assert!(!nav.next());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_animation_easing() {
+        for easing in [
+            AnimationEasing::Linear,
+            AnimationEasing::EaseInOut,
+            AnimationEasing::Spring,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert!(AnimationEasing::EaseInOut.apply(0.25) < 0.25);
+        assert!(AnimationEasing::EaseInOut.apply(0.75) > 0.75);
+        assert!(
+            AnimationEasing::Spring.apply(0.25) > 1.0,
+            "spring overshoots"
+        );
+    }
+
+    #[test]
+    fn test_animation_timing_frames() {
+        let timing = AnimationTiming {
+            fade_out: Duration::from_millis(100),
+            fade_in: Duration::from_millis(300),
+            easing: AnimationEasing::Linear,
+        };
+        let at = |ms| timing.frame_at(Duration::from_millis(ms));
+        assert_eq!(at(0), (AnimationFrame::FadeOut, 0.0));
+        assert_eq!(at(50), (AnimationFrame::FadeOut, 0.5));
+        assert_eq!(at(100), (AnimationFrame::FadeIn, 0.0));
+        assert_eq!(at(250), (AnimationFrame::FadeIn, 0.5));
+        assert_eq!(at(400), (AnimationFrame::Idle, 1.0));
+    }
+
     #[test]
     fn test_progress() {
         let old = "a\nb\nc\nd";
@@ -1276,6 +2428,75 @@ mod tests {
         assert_eq!(view[0].content, "const bar = 5");
     }
 
+    #[test]
+    fn test_split_changes_step_per_span_group() {
+        let old = "const foo = 4";
+        let new = "const bar = 5";
+
+        let engine = DiffEngine::new().with_word_level(true);
+        let diff = engine.diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        nav.set_split_changes(true);
+        assert_eq!(nav.state().total_steps, 3);
+
+        nav.next();
+        nav.clear_active_change();
+        assert_eq!(nav.current_view()[0].content, "const bar = 4");
+        nav.next();
+        nav.clear_active_change();
+        assert_eq!(nav.current_view()[0].content, "const bar = 5");
+        assert!(nav.state().is_at_end());
+
+        nav.prev();
+        nav.clear_active_change();
+        assert_eq!(nav.current_view()[0].content, "const bar = 4");
+
+        // Hunk navigation applies every group at once
+        nav.goto_start();
+        nav.next_hunk();
+        assert_eq!(nav.state().current_step, 2);
+        assert_eq!(nav.current_view()[0].content, "const bar = 5");
+    }
+
+    #[test]
+    fn test_materialize_any_step() {
+        let old = "fn main() {\n    let x = 1;\n    drop(x);\n}\n";
+        let new = "fn main() {\n    let y = 2;\n}\nfn extra() {}\n";
+        for word_level in [false, true] {
+            let diff = DiffEngine::new()
+                .with_word_level(word_level)
+                .diff_strings(old, new);
+            let nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+            let last = nav.state().total_steps - 1;
+            assert_eq!(nav.materialize(0), old);
+            assert_eq!(nav.materialize(last), new);
+            assert_eq!(nav.materialize(last + 5), new);
+        }
+
+        let (old, new) = ("const foo = 4\n", "const bar = 5\n");
+        let diff = DiffEngine::new()
+            .with_word_level(true)
+            .diff_strings(old, new);
+        let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+        nav.set_split_changes(true);
+        assert_eq!(nav.materialize(1), "const bar = 4\n");
+        // Independent of the current step
+        nav.goto_end();
+        assert_eq!(nav.materialize(0), old);
+
+        // A lone `\r` ends a line, as in the diff
+        for (old, new) in [
+            ("a\rb\nc\n", "a\rb\nC\n"),
+            ("a\rb\rc\r", "a\rB\rc\rd\r"),
+            ("a\r", "a\rb\n"),
+        ] {
+            let diff = DiffEngine::new().diff_strings(old, new);
+            let nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+            assert_eq!(nav.materialize(0), old);
+            assert_eq!(nav.materialize(nav.state().total_steps - 1), new);
+        }
+    }
+
     #[test]
     fn test_prev_hunk_animation_state() {
         // Setup: file with 2 hunks (changes separated by >3 unchanged lines)
@@ -1412,6 +2633,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn test_backward_primary_marker_on_destination() {
         // Two hunks with >3 lines separation to ensure distinct hunks
         // Stepping back from hunk 1 should put primary on hunk 0's change (destination)
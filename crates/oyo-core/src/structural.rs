@@ -0,0 +1,422 @@
+//! Structural adapters: rewrite config files into a canonical form before
+//! diffing, so reordered keys are not reported as changes
+//!
+//! Each key is printed as `key = value`, grouped by section and sorted, so a
+//! changed value shows up as a modified line and a removed key as a deletion.
+//! SQL schema dumps are regrouped by object (table, index, ...) instead.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Rewrites file content into a canonical, diff-friendly form
+pub trait Adapter: Send + Sync {
+    /// Short name shown in the UI
+    fn name(&self) -> &'static str;
+    /// True if this adapter handles `path`
+    fn matches(&self, path: &Path) -> bool;
+    /// Canonical form of `content`, or `None` if it cannot be parsed
+    fn normalize(&self, content: &str) -> Option<String>;
+}
+
+/// Keys grouped by section (the unnamed section holds top-level keys)
+type Sections = BTreeMap<String, BTreeMap<String, String>>;
+
+fn render(sections: &Sections) -> String {
+    let mut out = String::new();
+    for (section, keys) in sections {
+        if keys.is_empty() {
+            continue;
+        }
+        if !section.is_empty() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{section}]\n"));
+        }
+        for (key, value) in keys {
+            out.push_str(&format!("{key} = {value}\n"));
+        }
+    }
+    out
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// `.env` files: `KEY=VALUE` lines, optionally prefixed with `export`
+pub struct EnvAdapter;
+
+impl Adapter for EnvAdapter {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        name == ".env" || name.starts_with(".env.") || extension(path).as_deref() == Some("env")
+    }
+
+    fn normalize(&self, content: &str) -> Option<String> {
+        let mut sections = Sections::new();
+        let keys = sections.entry(String::new()).or_default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            keys.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Some(render(&sections))
+    }
+}
+
+/// `.ini` / `.cfg` files: `[section]` headers with `key = value` or `key: value`
+pub struct IniAdapter;
+
+impl Adapter for IniAdapter {
+    fn name(&self) -> &'static str {
+        "ini"
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        matches!(extension(path).as_deref(), Some("ini" | "cfg"))
+    }
+
+    fn normalize(&self, content: &str) -> Option<String> {
+        let mut sections = Sections::new();
+        let mut section = String::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with([';', '#']) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let split = line.find(['=', ':']);
+            let (key, value) = match split {
+                Some(idx) => (&line[..idx], &line[idx + 1..]),
+                None => (line, ""),
+            };
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Some(render(&sections))
+    }
+}
+
+/// `.toml` files: tables become sections named by their dotted path
+pub struct TomlAdapter;
+
+impl TomlAdapter {
+    fn flatten(table: &toml::Table, path: &str, sections: &mut Sections) {
+        let keys = sections.entry(path.to_string()).or_default();
+        let mut nested = Vec::new();
+        for (key, value) in table {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            match value {
+                toml::Value::Table(table) => nested.push((child, table)),
+                toml::Value::Array(items)
+                    if !items.is_empty() && items.iter().all(toml::Value::is_table) =>
+                {
+                    for (idx, item) in items.iter().enumerate() {
+                        if let toml::Value::Table(table) = item {
+                            nested.push((format!("{child}[{idx}]"), table));
+                        }
+                    }
+                }
+                value => {
+                    keys.insert(key.clone(), value.to_string());
+                }
+            }
+        }
+        for (child, table) in nested {
+            Self::flatten(table, &child, sections);
+        }
+    }
+}
+
+impl Adapter for TomlAdapter {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        extension(path).as_deref() == Some("toml")
+    }
+
+    fn normalize(&self, content: &str) -> Option<String> {
+        let table: toml::Table = content.parse().ok()?;
+        let mut sections = Sections::new();
+        Self::flatten(&table, "", &mut sections);
+        Some(render(&sections))
+    }
+}
+
+/// SQL schema dumps (`pg_dump --schema-only`, `mysqldump --no-data`, ...):
+/// statements are grouped by the object they define and sorted by object
+pub struct SqlAdapter;
+
+/// DDL object kinds recognized after `CREATE` / `ALTER` / `COMMENT ON`
+const SQL_OBJECT_KINDS: &[&str] = &[
+    "TABLE",
+    "INDEX",
+    "VIEW",
+    "SEQUENCE",
+    "FUNCTION",
+    "PROCEDURE",
+    "TRIGGER",
+    "TYPE",
+    "SCHEMA",
+    "EXTENSION",
+    "DOMAIN",
+];
+
+/// Words that may sit between the verb/kind and the object name
+const SQL_MODIFIERS: &[&str] = &[
+    "OR",
+    "REPLACE",
+    "UNIQUE",
+    "TEMP",
+    "TEMPORARY",
+    "UNLOGGED",
+    "MATERIALIZED",
+    "IF",
+    "NOT",
+    "EXISTS",
+    "ONLY",
+    "CONCURRENTLY",
+    "COLUMN",
+];
+
+impl SqlAdapter {
+    /// Split into statements, dropping comments (quotes and `$tag$` bodies
+    /// are kept intact)
+    fn statements(sql: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        let mut current = String::new();
+        let mut rest = sql;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("--") {
+                rest = rest.find('\n').map_or("", |end| &rest[end..]);
+                continue;
+            }
+            if rest.starts_with("/*") {
+                rest = rest.find("*/").map_or("", |end| &rest[end + 2..]);
+                continue;
+            }
+            let quoted_end = match c {
+                '\'' | '"' | '`' => rest[1..].find(c).map(|end| end + 2),
+                '$' => rest[1..].find('$').and_then(|tag_end| {
+                    let tag = &rest[..tag_end + 2];
+                    let valid = tag[1..tag.len() - 1]
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_');
+                    valid
+                        .then(|| rest[tag.len()..].find(tag))
+                        .flatten()
+                        .map(|body| tag.len() + body + tag.len())
+                }),
+                _ => None,
+            };
+            if let Some(end) = quoted_end {
+                current.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+            if c == ';' {
+                statements.push(std::mem::take(&mut current));
+            } else {
+                current.push(c);
+            }
+        }
+        statements.push(current);
+        statements
+            .into_iter()
+            .map(|statement| {
+                statement
+                    .lines()
+                    .map(str::trim_end)
+                    .filter(|line| !line.trim().is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .filter(|statement| !statement.is_empty())
+            .collect()
+    }
+
+    /// `KIND name` of the object a statement defines or alters
+    fn object_key(statement: &str) -> Option<String> {
+        let words: Vec<&str> = statement.split_whitespace().collect();
+        let verb = words.first()?.to_ascii_uppercase();
+        let mut idx = match verb.as_str() {
+            "CREATE" | "ALTER" | "DROP" => 1,
+            "COMMENT" if words.get(1)?.eq_ignore_ascii_case("ON") => 2,
+            _ => return None,
+        };
+        let mut kind = None;
+        while let Some(word) = words.get(idx) {
+            let upper = word.to_ascii_uppercase();
+            if kind.is_none() && SQL_OBJECT_KINDS.contains(&upper.as_str()) {
+                kind = Some(upper);
+            } else if !SQL_MODIFIERS.contains(&upper.as_str()) {
+                break;
+            }
+            idx += 1;
+        }
+        let name = words.get(idx)?;
+        let name = name
+            .split('(')
+            .next()
+            .unwrap_or(name)
+            .replace(['"', '`'], "");
+        Some(format!("{} {}", kind?, name))
+    }
+}
+
+impl Adapter for SqlAdapter {
+    fn name(&self) -> &'static str {
+        "sql"
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        extension(path).as_deref() == Some("sql")
+    }
+
+    fn normalize(&self, content: &str) -> Option<String> {
+        // Statements that belong to no object (SET, SELECT, ...) keep their order
+        let mut other = Vec::new();
+        let mut objects: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for statement in Self::statements(content) {
+            match Self::object_key(&statement) {
+                Some(key) => objects.entry(key).or_default().push(statement),
+                None => other.push(statement),
+            }
+        }
+
+        let mut out = String::new();
+        let other = (!other.is_empty()).then(|| ("other".to_string(), other));
+        for (key, statements) in other.into_iter().chain(objects) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("-- {key}\n"));
+            for statement in statements {
+                out.push_str(&statement);
+                out.push_str(";\n");
+            }
+        }
+        Some(out)
+    }
+}
+
+static ADAPTERS: &[&dyn Adapter] = &[&EnvAdapter, &IniAdapter, &TomlAdapter, &SqlAdapter];
+
+/// The adapter handling `path`, if any
+pub fn adapter_for(path: &Path) -> Option<&'static dyn Adapter> {
+    ADAPTERS
+        .iter()
+        .copied()
+        .find(|adapter| adapter.matches(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(path: &str, content: &str) -> String {
+        adapter_for(Path::new(path))
+            .and_then(|adapter| adapter.normalize(content))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_adapters_ignore_key_order() {
+        assert_eq!(
+            normalize(".env.local", "# db\nexport B=2\nA = 1\n"),
+            normalize(".env.local", "A=1\nB=2\n")
+        );
+
+        let ini = normalize("app.ini", "top=1\n[db]\nport: 5432\nhost = x\n[a]\nk=v\n");
+        assert_eq!(
+            ini,
+            "top = 1\n\n[a]\nk = v\n\n[db]\nhost = x\nport = 5432\n"
+        );
+
+        let toml = normalize(
+            "Cargo.toml",
+            "[package]\nversion = \"1\"\nname = \"oyo\"\n[[bin]]\nname = \"oy\"\n",
+        );
+        assert_eq!(
+            toml,
+            "[bin[0]]\nname = \"oy\"\n\n[package]\nname = \"oyo\"\nversion = \"1\"\n"
+        );
+        assert!(adapter_for(Path::new("main.rs")).is_none());
+    }
+
+    #[test]
+    fn test_sql_adapter_groups_by_object() {
+        let old = "\
+-- Name: users; Type: TABLE
+SET statement_timeout = 0;
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    note text DEFAULT 'a;b'
+);
+CREATE INDEX users_id_idx ON public.users USING btree (id);
+CREATE FUNCTION public.f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;
+ALTER TABLE ONLY public.users
+    ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+";
+        let new = "\
+SET statement_timeout = 0;
+CREATE INDEX users_id_idx ON public.users USING btree (id);
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    note text DEFAULT 'a;b'
+);
+ALTER TABLE ONLY public.users
+    ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+CREATE FUNCTION public.f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;
+";
+        let normalized = normalize("schema.sql", old);
+        assert_eq!(normalized, normalize("schema.sql", new));
+        assert_eq!(
+            normalized,
+            "\
+-- other
+SET statement_timeout = 0;
+
+-- FUNCTION public.f
+CREATE FUNCTION public.f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;
+
+-- INDEX users_id_idx
+CREATE INDEX users_id_idx ON public.users USING btree (id);
+
+-- TABLE public.users
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    note text DEFAULT 'a;b'
+);
+ALTER TABLE ONLY public.users
+    ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+"
+        );
+    }
+}
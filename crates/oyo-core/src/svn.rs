@@ -0,0 +1,156 @@
+//! Subversion working-copy support (BASE vs working files)
+
+use crate::git::{ChangedFile, FileStatus};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SvnError {
+    #[error("Not an svn working copy")]
+    NotAWorkingCopy,
+    #[error("svn command failed: {0}")]
+    CommandFailed(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Find the root of the working copy containing `path` (the directory
+/// holding `.svn`)
+pub fn find_wc_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".svn").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Check if a directory is inside an svn working copy
+pub fn is_svn_working_copy(path: &Path) -> bool {
+    find_wc_root(path).is_some()
+}
+
+/// List modified, added, deleted and unversioned files of the working copy
+pub fn get_working_changes(wc_root: &Path) -> Result<Vec<ChangedFile>, SvnError> {
+    if !is_svn_working_copy(wc_root) {
+        return Err(SvnError::NotAWorkingCopy);
+    }
+    let output = Command::new("svn")
+        .current_dir(wc_root)
+        .arg("status")
+        .arg("--non-interactive")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(SvnError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    let mut changes = parse_status(&String::from_utf8_lossy(&output.stdout));
+    // Added or unversioned directories are listed as a single entry
+    changes.retain(|change| !wc_root.join(&change.path).is_dir());
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// Parse `svn status` output: seven status columns, a space, then the path
+fn parse_status(output: &str) -> Vec<ChangedFile> {
+    let mut changes = Vec::new();
+    for line in output.lines() {
+        // Changelist headers and tree-conflict details
+        if line.starts_with("--- ") || line.starts_with("      >") {
+            continue;
+        }
+        let (Some(item), Some(path)) = (line.chars().next(), line.get(8..)) else {
+            continue;
+        };
+        let status = match item {
+            'M' | 'R' | 'C' => FileStatus::Modified,
+            'A' => FileStatus::Added,
+            'D' | '!' => FileStatus::Deleted,
+            '?' => FileStatus::Untracked,
+            _ => continue,
+        };
+        let path = path.trim();
+        if path.is_empty() {
+            continue;
+        }
+        changes.push(ChangedFile {
+            path: PathBuf::from(path),
+            status,
+            old_path: None,
+        });
+    }
+    changes
+}
+
+/// Get the BASE (last checked out) content of a file
+pub fn get_base_content(wc_root: &Path, file: &Path) -> Result<String, SvnError> {
+    let output = Command::new("svn")
+        .current_dir(wc_root)
+        .arg("cat")
+        .arg("--non-interactive")
+        .arg("-r")
+        .arg("BASE")
+        .arg(file)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(SvnError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Old (BASE) and new (working) content of a changed file
+pub fn get_file_contents(wc_root: &Path, file: &Path, status: FileStatus) -> (String, String) {
+    let old_content = match status {
+        FileStatus::Added | FileStatus::Untracked => String::new(),
+        _ => get_base_content(wc_root, file).unwrap_or_default(),
+    };
+    let new_content = match status {
+        FileStatus::Deleted => String::new(),
+        _ => std::fs::read_to_string(wc_root.join(file)).unwrap_or_default(),
+    };
+    (old_content, new_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let output = "\
+M       src/main.c
+ M      docs
+A  +    src/new.c
+D       old.txt
+!       missing.txt
+?       scratch.txt
+C       conflict.c
+      >   local edit, incoming delete upon update
+X       vendor/lib
+--- Changelist 'wip':
+M       notes with space.txt
+";
+        let changes = parse_status(output);
+        let summary: Vec<(&str, FileStatus)> = changes
+            .iter()
+            .map(|change| (change.path.to_str().unwrap(), change.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/main.c", FileStatus::Modified),
+                ("src/new.c", FileStatus::Added),
+                ("old.txt", FileStatus::Deleted),
+                ("missing.txt", FileStatus::Deleted),
+                ("scratch.txt", FileStatus::Untracked),
+                ("conflict.c", FileStatus::Modified),
+                ("notes with space.txt", FileStatus::Modified),
+            ]
+        );
+    }
+}
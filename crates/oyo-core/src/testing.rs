@@ -0,0 +1,158 @@
+//! Golden-file helpers for snapshot-testing step sequences without a
+//! terminal (`test-util` feature)
+//!
+//! Frames are plain text: one row per view line with the old and new line
+//! numbers, a kind sign, and the shown edits marked `[-old-]` and `{+new+}`.
+//! The primary active line is marked with `>`.
+//!
+//! ```text
+//! ── step 1/1 ──
+//!     1    1   fn main() {
+//! >   2    2 ~     let {+y+} = 1;
+//!     3    3   }
+//! ```
+
+use crate::diff::DiffResult;
+use crate::step::{AnimationFrame, DiffNavigator, LineKind, ViewLine, ViewSpanKind};
+use std::path::Path;
+
+/// Environment variable that makes [`assert_golden`] (re)write golden files
+/// instead of comparing against them
+pub const UPDATE_ENV: &str = "OYO_UPDATE_GOLDEN";
+
+/// The navigator's current view as a plain-text frame (settled, i.e. with
+/// no animation in progress)
+pub fn render_frame(nav: &DiffNavigator) -> String {
+    nav.current_view_with_frame(AnimationFrame::Idle)
+        .iter()
+        .map(render_line)
+        .collect()
+}
+
+fn render_line(line: &ViewLine) -> String {
+    let number = |n: Option<usize>| n.map_or_else(|| "    ".to_string(), |n| format!("{n:>4}"));
+    let sign = match line.kind {
+        LineKind::Context => ' ',
+        LineKind::Inserted | LineKind::PendingInsert => '+',
+        LineKind::Deleted | LineKind::PendingDelete => '-',
+        LineKind::Modified | LineKind::PendingModify => '~',
+        LineKind::Collapsed => '⋯',
+    };
+    let mut text = String::new();
+    for span in &line.spans {
+        match span.kind {
+            ViewSpanKind::Equal => text.push_str(&span.text),
+            ViewSpanKind::Inserted | ViewSpanKind::PendingInsert => {
+                text.push_str(&format!("{{+{}+}}", span.text))
+            }
+            ViewSpanKind::Deleted | ViewSpanKind::PendingDelete => {
+                text.push_str(&format!("[-{}-]", span.text))
+            }
+        }
+    }
+    if line.spans.is_empty() {
+        text.push_str(&line.content);
+    }
+    let marker = if line.is_primary_active { '>' } else { ' ' };
+    let row = format!(
+        "{marker}{} {} {sign} {text}",
+        number(line.old_line),
+        number(line.new_line)
+    );
+    format!("{}\n", row.trim_end())
+}
+
+/// Every step of the navigator from the start, one frame per step under a
+/// `── step N/M ──` header; leaves the navigator at the end
+pub fn render_steps(nav: &mut DiffNavigator) -> String {
+    nav.goto_start();
+    nav.clear_active_change();
+    let last = nav.state().total_steps.saturating_sub(1);
+    let mut out = String::new();
+    loop {
+        let step = nav.state().current_step;
+        out.push_str(&format!("── step {step}/{last} ──\n"));
+        out.push_str(&render_frame(nav));
+        if !nav.next() {
+            break;
+        }
+    }
+    out
+}
+
+/// [`render_steps`] for a diff of `old` and `new`
+pub fn render_diff_steps(diff: DiffResult, old: &str, new: &str) -> String {
+    let mut nav = DiffNavigator::new(diff, old.to_string(), new.to_string());
+    render_steps(&mut nav)
+}
+
+/// Compare `actual` with the golden file at `path`, panicking with the
+/// first differing line on a mismatch. With [`UPDATE_ENV`] set (or when the
+/// file does not exist yet) the file is written instead.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_ENV).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("create golden file directory");
+        }
+        std::fs::write(path, actual).expect("write golden file");
+        return;
+    }
+    let expected = std::fs::read_to_string(path).expect("read golden file");
+    if expected == actual {
+        return;
+    }
+    let mismatch = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+    panic!(
+        "{} differs from the rendered output at line {} (set {UPDATE_ENV}=1 to update)\n\
+         expected: {:?}\n  actual: {:?}",
+        path.display(),
+        mismatch + 1,
+        expected.lines().nth(mismatch).unwrap_or("<end of file>"),
+        actual.lines().nth(mismatch).unwrap_or("<end of output>"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffEngine;
+
+    #[test]
+    fn test_render_steps() {
+        let (old, new) = (
+            "fn main() {\n    let x = 1;\n}\n",
+            "fn main() {\n    let y = 1;\n}\n",
+        );
+        let diff = DiffEngine::new()
+            .with_word_level(true)
+            .diff_strings(old, new);
+        assert_eq!(
+            render_diff_steps(diff, old, new),
+            "── step 0/1 ──\n\
+             \x20   1    1   fn main() {\n\
+             \x20   2    2       let x = 1;\n\
+             \x20   3    3   }\n\
+             ── step 1/1 ──\n\
+             \x20   1    1   fn main() {\n\
+             >   2    2 ~     let {+y+} = 1;\n\
+             \x20   3    3   }\n"
+        );
+    }
+
+    #[test]
+    fn test_assert_golden_writes_then_compares() {
+        let path = std::env::temp_dir()
+            .join(format!("oyo-golden-{}", std::process::id()))
+            .join("frame.txt");
+        assert_golden(&path, "a\nb\n");
+        assert_golden(&path, "a\nb\n");
+        let mismatch = std::panic::catch_unwind(|| assert_golden(&path, "a\nc\n"));
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+        assert!(mismatch.is_err());
+    }
+}
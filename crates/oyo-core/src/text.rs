@@ -0,0 +1,238 @@
+//! Shared immutable text for change spans and view lines
+//!
+//! A diff of a big file holds one span per line, most of them unchanged
+//! context. [`Text`] is a byte range into a reference-counted buffer, so a
+//! span refers to its line inside the file contents rather than owning a
+//! copy, and cloning a span, or a view line built from it, copies nothing.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+/// Immutable, cheaply cloned text: a range of a shared buffer that reads
+/// like a `&str`
+#[derive(Clone)]
+pub struct Text {
+    buffer: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl Text {
+    /// Resolve the range against the buffer
+    pub fn as_str(&self) -> &str {
+        &self.buffer[self.start..self.end]
+    }
+
+    /// Byte range of the text within its buffer
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Sub-range of this text, sharing its buffer; `None` unless `range`
+    /// falls on character boundaries within the text
+    pub fn slice(&self, range: Range<usize>) -> Option<Text> {
+        self.as_str().get(range.clone())?;
+        Some(Text {
+            buffer: Arc::clone(&self.buffer),
+            start: self.start + range.start,
+            end: self.start + range.end,
+        })
+    }
+
+    /// `part`, a `&str` borrowed from this text, as a range of the same
+    /// buffer; `None` if `part` points elsewhere
+    pub fn slice_of(&self, part: &str) -> Option<Text> {
+        let offset = (part.as_ptr() as usize).wrapping_sub(self.as_str().as_ptr() as usize);
+        if offset > self.len() || part.len() > self.len() - offset {
+            return None;
+        }
+        self.slice(offset..offset + part.len())
+    }
+
+    /// Whether `self` and `other` are ranges of one buffer
+    pub fn shares_buffer(&self, other: &Text) -> bool {
+        Arc::ptr_eq(&self.buffer, &other.buffer)
+    }
+
+    /// Identifies the buffer, to count shared buffers once
+    pub(crate) fn buffer_id(&self) -> *const u8 {
+        self.buffer.as_ptr()
+    }
+
+    /// Bytes held by the buffer, shared with every range of it
+    pub fn buffer_size(&self) -> usize {
+        self.buffer.len() + 2 * std::mem::size_of::<usize>()
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Text::from("")
+    }
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Text {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Text {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for Text {
+    fn eq(&self, other: &Text) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Text {}
+
+impl Hash for Text {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialOrd for Text {
+    fn partial_cmp(&self, other: &Text) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Text {
+    fn cmp(&self, other: &Text) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl fmt::Debug for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl From<Arc<str>> for Text {
+    fn from(buffer: Arc<str>) -> Self {
+        let end = buffer.len();
+        Text {
+            buffer,
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Text::from(Arc::<str>::from(text))
+    }
+}
+
+impl From<&String> for Text {
+    fn from(text: &String) -> Self {
+        Text::from(text.as_str())
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Text::from(Arc::<str>::from(text))
+    }
+}
+
+impl From<&Text> for Text {
+    fn from(text: &Text) -> Self {
+        text.clone()
+    }
+}
+
+impl From<Text> for String {
+    fn from(text: Text) -> Self {
+        text.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for Text {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Text {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<Text> for str {
+    fn eq(&self, other: &Text) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Text> for &str {
+    fn eq(&self, other: &Text) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl Serialize for Text {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Text {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Text::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slices_share_the_buffer() {
+        let file = Text::from("fn main() {\n}\n");
+        let line = file.slice_of(file.lines().next().unwrap()).unwrap();
+        let name = line.slice(3..7).unwrap();
+
+        assert_eq!(line, "fn main() {");
+        assert_eq!(name, "main");
+        assert_eq!(name.range(), 3..7);
+        assert!(name.shares_buffer(&file));
+        assert!(file.slice_of("main").is_none());
+        assert!(line.slice(0..100).is_none());
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"main\"");
+    }
+}
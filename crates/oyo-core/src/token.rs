@@ -0,0 +1,87 @@
+//! Tokenizers for word-level (intra-line) diffing
+//!
+//! Tokens never split a grapheme cluster, so combining marks and emoji ZWJ
+//! sequences are always diffed as a whole.
+
+use std::fmt::Debug;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits a line into tokens for intra-line diffing.
+///
+/// Concatenating the tokens must give back the original line.
+pub trait Tokenizer: Debug + Send + Sync {
+    fn tokenize(&self, line: &str) -> Vec<String>;
+}
+
+/// Code-aware tokens: identifiers, runs of whitespace, and single punctuation
+/// graphemes (the default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodeTokenizer;
+
+/// One token per grapheme cluster (character-level diffing)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GraphemeTokenizer;
+
+#[derive(PartialEq, Eq)]
+enum GraphemeClass {
+    Word,
+    Whitespace,
+    Punct,
+}
+
+fn classify(grapheme: &str) -> GraphemeClass {
+    match grapheme.chars().next() {
+        Some(ch) if ch.is_alphanumeric() || ch == '_' => GraphemeClass::Word,
+        Some(_) if grapheme.chars().all(char::is_whitespace) => GraphemeClass::Whitespace,
+        _ => GraphemeClass::Punct,
+    }
+}
+
+impl Tokenizer for CodeTokenizer {
+    fn tokenize(&self, line: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut buf = String::new();
+        let mut buf_class = GraphemeClass::Punct;
+
+        for grapheme in line.graphemes(true) {
+            let class = classify(grapheme);
+            // Each punctuation grapheme is its own token; words and whitespace group
+            if (class == GraphemeClass::Punct || class != buf_class) && !buf.is_empty() {
+                tokens.push(std::mem::take(&mut buf));
+            }
+            buf.push_str(grapheme);
+            buf_class = class;
+        }
+        if !buf.is_empty() {
+            tokens.push(buf);
+        }
+        tokens
+    }
+}
+
+impl Tokenizer for GraphemeTokenizer {
+    fn tokenize(&self, line: &str) -> Vec<String> {
+        line.graphemes(true).map(str::to_string).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizers_keep_grapheme_clusters() {
+        // "e" + combining acute accent, and a ZWJ family emoji
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let line = format!("cafe\u{301} {family}!");
+
+        let code = CodeTokenizer.tokenize(&line);
+        assert_eq!(code, vec!["cafe\u{301}", " ", family, "!"]);
+
+        let chars = GraphemeTokenizer.tokenize(&line);
+        assert_eq!(chars.len(), 7);
+        assert_eq!(chars[3], "e\u{301}");
+        assert_eq!(chars[5], family);
+        assert_eq!(chars.concat(), line);
+    }
+}
@@ -0,0 +1,172 @@
+//! Writes to the working tree: every change goes through an atomic
+//! temp-file + rename, with an optional `.orig` backup, and is refused if the
+//! file changed on disk since its content was loaded
+//!
+//! A symlinked file is written through the link: the rename replaces the
+//! file it points to, so the link itself stays in place.
+
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum WriteBackError {
+    #[error("{0} changed on disk since it was loaded")]
+    Conflict(PathBuf),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// How a file is written back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Keep the previous content as `<file>.orig`
+    pub backup: bool,
+}
+
+/// Path of the backup kept for `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".orig");
+    path.with_file_name(name)
+}
+
+/// Fail with [`WriteBackError::Conflict`] unless `path` still holds
+/// `expected` (a missing file counts as empty)
+pub fn check_unchanged(path: &Path, expected: &str) -> Result<(), WriteBackError> {
+    let current = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    if current != expected.as_bytes() {
+        return Err(WriteBackError::Conflict(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// Create a new temp file next to `name` in `dir`. The name is not
+/// predictable and the file must not exist yet, so nothing planted there
+/// (such as a symlink) is ever followed.
+fn create_temp(dir: &Path, name: &OsStr) -> std::io::Result<(PathBuf, fs::File)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    loop {
+        let mut hasher = DefaultHasher::new();
+        (
+            std::process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        )
+            .hash(&mut hasher);
+        let mut temp_name = OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".oyo-{:016x}.tmp", hasher.finish()));
+        let temp = dir.join(temp_name);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+        {
+            Ok(file) => return Ok((temp, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Replace the content of `path` atomically. With `expected`, the write is
+/// refused if the file no longer holds that content.
+pub fn write_file(
+    path: &Path,
+    content: &str,
+    expected: Option<&str>,
+    options: WriteOptions,
+) -> Result<(), WriteBackError> {
+    if let Some(expected) = expected {
+        check_unchanged(path, expected)?;
+    }
+
+    let resolved;
+    let path = if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        resolved = fs::canonicalize(path)?;
+        resolved.as_path()
+    } else {
+        path
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (temp, mut file) = create_temp(dir, path.file_name().unwrap_or_default())?;
+
+    let result = (|| -> std::io::Result<()> {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+            if options.backup {
+                fs::copy(path, backup_path(path))?;
+            }
+        }
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_file_backup_and_conflict() {
+        let dir = std::env::temp_dir().join(format!("oyo-writeback-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.txt");
+        fs::write(&path, "one\n").unwrap();
+
+        let backup = WriteOptions { backup: true };
+        write_file(&path, "two\n", Some("one\n"), backup).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "one\n");
+
+        let err = write_file(&path, "three\n", Some("one\n"), WriteOptions::default());
+        assert!(matches!(err, Err(WriteBackError::Conflict(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_writes_through_symlinks() {
+        let dir = std::env::temp_dir().join(format!("oyo-writeback-link-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("target.txt");
+        let link = dir.join("link.txt");
+        fs::write(&target, "one\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_file(&link, "two\n", Some("one\n"), WriteOptions::default()).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "two\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
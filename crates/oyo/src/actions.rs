@@ -0,0 +1,340 @@
+//! Scripted actions (`[actions]`): functions of a Rhai script, bound to keys
+//! or run with `:action <name>`
+//!
+//! Scripts see a snapshot of the session taken when the action starts and
+//! change it only through effects (copy, notify, open a file, go to a line
+//! or step) applied once it returns, so an action cannot corrupt the
+//! navigator. Scripts have no file, process or network access (`import`
+//! finds no modules), are stopped after [`MAX_OPERATIONS`], and fail when a
+//! string, array or map outgrows its limit, so a runaway script cannot
+//! exhaust memory.
+//!
+//! ```rhai
+//! fn skip_vendored() {
+//!     let files = files();
+//!     let i = file_index() + 1;
+//!     while i < files.len() && files[i].path.starts_with("vendor/") {
+//!         i += 1;
+//!     }
+//!     if i < files.len() { open_file(i) } else { notify("no more files") }
+//! }
+//! ```
+
+use crate::config::ActionsConfig;
+use rhai::{Array, Dynamic, Engine, ImmutableString, Map, Scope, AST};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Script file used when `actions.script` is not set, next to the config
+pub const DEFAULT_SCRIPT: &str = "actions.rhai";
+
+/// Operations an action may run before it is stopped
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A change requested by an action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    Copy(String),
+    Notify(String),
+    OpenFile(usize),
+    OpenPath(String),
+    NextFile,
+    PrevFile,
+    /// 1-based line of the new version
+    GotoLine(usize),
+    /// 1-based step
+    GotoStep(usize),
+}
+
+/// What an action can read
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub file: String,
+    pub file_index: usize,
+    /// Path and status of every file
+    pub files: Vec<(String, String)>,
+    /// 1-based
+    pub step: usize,
+    pub steps: usize,
+    /// New-side line of the active change (0 if none)
+    pub line: usize,
+    pub root: Option<PathBuf>,
+    pub branch: Option<String>,
+}
+
+pub struct Actions {
+    engine: Engine,
+    ast: AST,
+    /// Key label (as in the key echo: `x`, `C-y`, `M-p`) to action name
+    keys: BTreeMap<String, String>,
+    snapshot: Rc<RefCell<Snapshot>>,
+    effects: Rc<RefCell<Vec<Effect>>>,
+}
+
+impl Actions {
+    /// Compile the configured script, if there is one; `config_dir`
+    /// resolves a relative `actions.script`
+    pub fn load(config: &ActionsConfig, config_dir: Option<&Path>) -> Result<Option<Self>, String> {
+        let path = match &config.script {
+            Some(path) if path.is_relative() => config_dir.map(|dir| dir.join(path)),
+            Some(path) => Some(path.clone()),
+            None => config_dir
+                .map(|dir| dir.join(DEFAULT_SCRIPT))
+                .filter(|path| path.is_file()),
+        };
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let source =
+            std::fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        Self::compile(&source, config.keys.clone())
+            .map(Some)
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn compile(source: &str, keys: BTreeMap<String, String>) -> Result<Self, String> {
+        let snapshot = Rc::new(RefCell::new(Snapshot::default()));
+        let effects = Rc::new(RefCell::new(Vec::new()));
+        let engine = engine(&snapshot, &effects);
+        let ast = engine.compile(source).map_err(|err| err.to_string())?;
+        let actions = Actions {
+            engine,
+            ast,
+            keys,
+            snapshot,
+            effects,
+        };
+        if let Some(name) = actions
+            .keys
+            .values()
+            .find(|name| !actions.names().contains(name))
+        {
+            return Err(format!("no action `{name}` to bind"));
+        }
+        Ok(actions)
+    }
+
+    /// Actions (script functions without parameters), by name; the AST
+    /// does not keep functions in script order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .ast
+            .iter_functions()
+            .filter(|function| function.params.is_empty())
+            .map(|function| function.name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn keys(&self) -> &BTreeMap<String, String> {
+        &self.keys
+    }
+
+    pub fn for_key(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).map(String::as_str)
+    }
+
+    /// Run action `name`; a string it returns is shown like `notify`
+    pub fn run(&self, name: &str, snapshot: Snapshot) -> Result<Vec<Effect>, String> {
+        *self.snapshot.borrow_mut() = snapshot;
+        self.effects.borrow_mut().clear();
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, ())
+            .map_err(|err| err.to_string())?;
+        let mut effects = std::mem::take(&mut *self.effects.borrow_mut());
+        if let Some(text) = result.try_cast::<ImmutableString>() {
+            effects.push(Effect::Notify(text.to_string()));
+        }
+        Ok(effects)
+    }
+}
+
+fn engine(snapshot: &Rc<RefCell<Snapshot>>, effects: &Rc<RefCell<Vec<Effect>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1024 * 1024);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+
+    let push = |effects: &Rc<RefCell<Vec<Effect>>>| {
+        let effects = Rc::clone(effects);
+        move |effect: Effect| effects.borrow_mut().push(effect)
+    };
+    let emit = push(effects);
+    engine.on_print(move |text| emit(Effect::Notify(text.to_string())));
+    let emit = push(effects);
+    engine.register_fn("copy", move |text: &str| {
+        emit(Effect::Copy(text.to_string()))
+    });
+    let emit = push(effects);
+    engine.register_fn("notify", move |text: &str| {
+        emit(Effect::Notify(text.to_string()))
+    });
+    let emit = push(effects);
+    engine.register_fn("open_file", move |index: i64| {
+        if let Ok(index) = usize::try_from(index) {
+            emit(Effect::OpenFile(index));
+        }
+    });
+    let emit = push(effects);
+    engine.register_fn("open_file", move |path: &str| {
+        emit(Effect::OpenPath(path.to_string()))
+    });
+    let emit = push(effects);
+    engine.register_fn("next_file", move || emit(Effect::NextFile));
+    let emit = push(effects);
+    engine.register_fn("prev_file", move || emit(Effect::PrevFile));
+    let emit = push(effects);
+    engine.register_fn("goto_line", move |line: i64| {
+        if let Ok(line) = usize::try_from(line) {
+            emit(Effect::GotoLine(line));
+        }
+    });
+    let emit = push(effects);
+    engine.register_fn("goto_step", move |step: i64| {
+        if let Ok(step) = usize::try_from(step) {
+            emit(Effect::GotoStep(step));
+        }
+    });
+
+    let state = Rc::clone(snapshot);
+    engine.register_fn("file", move || state.borrow().file.clone());
+    let state = Rc::clone(snapshot);
+    engine.register_fn("file_index", move || state.borrow().file_index as i64);
+    let state = Rc::clone(snapshot);
+    engine.register_fn("files", move || -> Array {
+        state
+            .borrow()
+            .files
+            .iter()
+            .map(|(path, status)| {
+                let mut file = Map::new();
+                file.insert("path".into(), path.clone().into());
+                file.insert("status".into(), status.clone().into());
+                Dynamic::from_map(file)
+            })
+            .collect()
+    });
+    let state = Rc::clone(snapshot);
+    engine.register_fn("step", move || state.borrow().step as i64);
+    let state = Rc::clone(snapshot);
+    engine.register_fn("steps", move || state.borrow().steps as i64);
+    let state = Rc::clone(snapshot);
+    engine.register_fn("line", move || state.borrow().line as i64);
+    let state = Rc::clone(snapshot);
+    engine.register_fn("repo_root", move || {
+        state
+            .borrow()
+            .root
+            .as_ref()
+            .map(|root| root.display().to_string())
+            .unwrap_or_default()
+    });
+    let state = Rc::clone(snapshot);
+    engine.register_fn("branch", move || {
+        state.borrow().branch.clone().unwrap_or_default()
+    });
+    // Git metadata is read when asked for, not for every action
+    let state = Rc::clone(snapshot);
+    engine.register_fn("commit", move || {
+        state
+            .borrow()
+            .root
+            .as_ref()
+            .and_then(|root| oyo_core::git::get_head_commit(root).ok())
+            .unwrap_or_default()
+    });
+    let state = Rc::clone(snapshot);
+    engine.register_fn("remote_url", move |remote: &str| {
+        state
+            .borrow()
+            .root
+            .as_ref()
+            .and_then(|root| oyo_core::git::get_remote_url(root, remote).ok())
+            .unwrap_or_default()
+    });
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            file: "src/lib.rs".to_string(),
+            file_index: 0,
+            files: vec![
+                ("src/lib.rs".to_string(), "modified".to_string()),
+                ("vendor/a.rs".to_string(), "added".to_string()),
+                ("vendor/b.rs".to_string(), "added".to_string()),
+                ("src/main.rs".to_string(), "modified".to_string()),
+            ],
+            step: 2,
+            steps: 5,
+            line: 14,
+            ..Snapshot::default()
+        }
+    }
+
+    #[test]
+    fn test_actions_read_the_snapshot_and_return_effects() {
+        let source = r#"
+            fn skip_vendored() {
+                let files = files();
+                let i = file_index() + 1;
+                while i < files.len() && files[i].path.starts_with("vendor/") {
+                    i += 1;
+                }
+                open_file(i);
+            }
+            fn where_am_i() {
+                copy(`${file()}#L${line()}`);
+                `step ${step()}/${steps()}`
+            }
+            fn helper(x) { x }
+        "#;
+        let keys = BTreeMap::from([("C-y".to_string(), "where_am_i".to_string())]);
+        let actions = Actions::compile(source, keys).unwrap();
+
+        assert_eq!(actions.names(), ["skip_vendored", "where_am_i"]);
+        assert_eq!(actions.for_key("C-y"), Some("where_am_i"));
+        assert_eq!(
+            actions.run("skip_vendored", snapshot()),
+            Ok(vec![Effect::OpenFile(3)])
+        );
+        assert_eq!(
+            actions.run("where_am_i", snapshot()),
+            Ok(vec![
+                Effect::Copy("src/lib.rs#L14".to_string()),
+                Effect::Notify("step 2/5".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_actions_are_checked_and_limited() {
+        let unbound = BTreeMap::from([("x".to_string(), "missing".to_string())]);
+        assert!(Actions::compile("fn present() {}", unbound).is_err());
+        assert!(Actions::compile("fn broken( {", BTreeMap::new()).is_err());
+
+        let actions = Actions::compile("fn spin() { loop {} }", BTreeMap::new()).unwrap();
+        assert!(actions.run("spin", snapshot()).is_err());
+        assert!(actions.run("missing", snapshot()).is_err());
+
+        let sneaky = Actions::compile("fn sneaky() { eval(\"1\") }", BTreeMap::new());
+        assert!(sneaky.map_or(true, |actions| actions.run("sneaky", snapshot()).is_err()));
+
+        let greedy = "fn grow() { let s = \"x\"; for i in 0..40 { s += s } s }";
+        let actions = Actions::compile(greedy, BTreeMap::new()).unwrap();
+        assert!(actions.run("grow", snapshot()).is_err());
+    }
+}
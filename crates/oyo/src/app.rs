@@ -1,23 +1,106 @@
 //! Application state and logic
 
+use crate::actions::{self, Actions, Effect};
 use crate::color;
 use crate::config::{
-    DiffBackgroundMode, DiffForegroundMode, FileCountMode, ModifiedStepMode, ResolvedTheme,
-    SyntaxMode,
+    DiffBackgroundMode, DiffForegroundMode, EngineConfig, FileCountMode, FileSortMode, ForgeRemote,
+    ModifiedStepMode, PresetConfig, ResolvedTheme, SearchCase, SyntaxMode,
 };
-use crate::syntax::{SyntaxCache, SyntaxEngine, SyntaxSide};
+use crate::crash;
+use crate::diagnostics::Diagnostic;
+use crate::forge::{self, Review, Side, Thread};
+use crate::frame::RowCache;
+use crate::history::{History, Jump};
+use crate::impact::{ImpactHint, ImpactRule, TestRun};
+use crate::overlay::OverlayMessage;
+use crate::plugin::PluginHost;
+use crate::popup::PopupKind;
+use crate::prompt::{Confirm, ConfirmAction, Input, PromptKey, Validator};
+use crate::redact::Redactor;
+use crate::settings;
+use crate::syntax::{FunctionSpan, SyntaxCache, SyntaxEngine, SyntaxSide};
+use crate::toast::{Level, Toasts};
+use oyo_core::conventional::CommitTypeGroups;
+use oyo_core::git::{CommitInfo, MergeLabels};
+use oyo_core::notes::{ReviewNote, Verdict};
+use oyo_core::patch::{PatchHunk, RejectFile};
 use oyo_core::{
-    AnimationFrame, Change, ChangeKind, LineKind, MultiFileDiff, StepDirection, StepState, ViewLine,
+    AnimationEasing, AnimationFrame, AnimationTiming, Bucket, Change, ChangeKind, ChangeOrigin,
+    FileStatus, HunkContext, HunkGroup, LineKind, MultiFileDiff, NavEvent, Patch, PlannedCommit,
+    StepDirection, StepState, TextFormat, ViewAnchor, ViewLine,
 };
 use ratatui::style::Color;
-use ratatui::text::Span;
+use ratatui::text::{Line, Span};
 use regex::{Regex, RegexBuilder};
 use std::time::{Duration, Instant};
 use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    hash::{Hash, Hasher},
     io::Write,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
+    sync::mpsc::Receiver,
 };
 
+/// Suggested logical hunk groups and the reviewed (group, hunk)
+#[derive(Debug, Clone)]
+struct GroupReview {
+    groups: Vec<HunkGroup>,
+    cursor: Option<(usize, usize)>,
+}
+
+/// Level of the breadcrumb bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrumbLevel {
+    Dir,
+    File,
+    Hunk,
+    Change,
+}
+
+/// Changes and review progress of the files in one directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirRollup {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Files marked reviewed
+    pub reviewed: usize,
+}
+
+impl DirRollup {
+    /// Share of the files marked reviewed, in percent (rounded down)
+    pub fn reviewed_percent(&self) -> usize {
+        (self.reviewed * 100).checked_div(self.files).unwrap_or(0)
+    }
+}
+
+/// Dry-run preview of the commits the hunk buckets would create
+struct SplitPreview {
+    /// The working tree diff, restored when the preview closes
+    original: MultiFileDiff,
+    plan: Vec<PlannedCommit>,
+    index: usize,
+}
+
+/// A/B compare of one file at two steps (`:compare`)
+struct StepCompare {
+    /// The session being compared, restored when the compare closes
+    original: MultiFileDiff,
+    /// View mode to restore
+    view_mode: ViewMode,
+    /// The compared steps (left, right)
+    steps: (usize, usize),
+}
+
+/// Preview of the merge result against both sides (`:merged`)
+struct MergePreview {
+    /// The three-way comparison, restored when the preview closes
+    original: MultiFileDiff,
+    /// Conflicts left in the merge result
+    conflicts: usize,
+}
+
 /// Animation phase for smooth transitions
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnimationPhase {
@@ -64,6 +147,17 @@ struct HunkBounds {
     end: HunkStart,
 }
 
+/// Scroll eased from one offset to another after a step moves the view
+#[derive(Clone, Copy, Debug)]
+struct ScrollAnimation {
+    from: usize,
+    to: usize,
+    started_at: Instant,
+    /// Offset last set by the animation; any other offset means the view
+    /// was scrolled by hand, which ends the animation
+    current: usize,
+}
+
 #[derive(Clone, Copy, Debug)]
 struct NoStepState {
     current_hunk: usize,
@@ -95,12 +189,14 @@ pub struct App {
     files_visited: Vec<bool>,
     /// Whether to quit
     pub should_quit: bool,
+    /// Quit without asking about unsaved work (`--force`)
+    pub force_quit: bool,
     /// Current animation phase
     pub animation_phase: AnimationPhase,
-    /// Animation progress (0.0 to 1.0)
+    /// Eased progress of the current phase (0.0 to 1.0; "spring" overshoots)
     pub animation_progress: f32,
-    /// Last animation tick time
-    last_animation_tick: Instant,
+    /// When the current step animation started
+    animation_started_at: Instant,
     /// Last autoplay tick time
     last_autoplay_tick: Instant,
     /// Whether the file list is focused (for multi-file mode)
@@ -115,16 +211,42 @@ pub struct App {
     pub file_list_rows: Vec<Option<usize>>,
     /// File list filter input area (x, y, width, height)
     pub file_filter_area: Option<(u16, u16, u16, u16)>,
+    /// Show the step scrub bar under the status bar
+    pub show_scrub_bar: bool,
+    /// Scrub bar area (x, y, width, height)
+    pub scrub_bar_area: Option<(u16, u16, u16, u16)>,
+    /// Show the file header bar above the diff
+    pub show_file_header: bool,
+    /// Show the breadcrumb bar (repo ▸ dir ▸ file ▸ hunk ▸ change)
+    pub show_breadcrumbs: bool,
+    /// Breadcrumb level `,` / `.` move between siblings of
+    pub crumb_level: CrumbLevel,
     /// When to show per-file +/- counts in the file panel
     pub file_count_mode: FileCountMode,
+    /// File list ordering
+    pub file_sort: FileSortMode,
+    /// Files grouped by conventional-commit type, while sorted that way
+    pub commit_type_groups: Option<CommitTypeGroups>,
+    /// Files marked reviewed in the file list
+    reviewed_files: HashSet<PathBuf>,
     /// File list filter text
-    pub file_filter: String,
+    pub file_filter: Input,
     /// True when filter input is active
     pub file_filter_active: bool,
     /// Whether animations are enabled (false = instant transitions)
     pub animation_enabled: bool,
+    /// Reduced motion: every transition is instant, regardless of animation_enabled
+    pub reduced_motion: bool,
     /// Zen mode - hide UI chrome (top bar, progress bar, help bar)
     pub zen_mode: bool,
+    /// Presentation mode: no gutters or chrome, padded content, the active
+    /// change kept centered and pressed keys shown on screen
+    pub presentation: bool,
+    /// `auto_center` to restore when leaving presentation mode
+    presentation_auto_center: bool,
+    /// Recently pressed keys shown in presentation mode: label, repeat
+    /// count, time of the last press
+    pub key_echo: Vec<(String, usize, Instant)>,
     /// Flag to scroll to active change on next render (after stepping)
     pub needs_scroll_to_active: bool,
     /// Whether to show the help popover
@@ -135,10 +257,22 @@ pub struct App {
     pub help_max_scroll: usize,
     /// Git branch name (if in a git repo)
     pub git_branch: Option<String>,
+    /// Branch per repository root (workspace mode, looked up on demand)
+    repo_branches: HashMap<PathBuf, Option<String>>,
     /// Auto-center on active change after stepping (like vim's zz)
     pub auto_center: bool,
     /// Animation duration in milliseconds (how long fade effects take)
     pub animation_duration: u64,
+    /// Fade-out phase duration override in milliseconds
+    pub fade_out_duration: Option<u64>,
+    /// Fade-in phase duration override in milliseconds
+    pub fade_in_duration: Option<u64>,
+    /// Easing curve applied to animation progress
+    pub animation_easing: AnimationEasing,
+    /// Scroll animation duration in milliseconds when a step moves the
+    /// view (0 = jump)
+    pub scroll_duration: u64,
+    scroll_animation: Option<ScrollAnimation>,
     /// Pending count for vim-style commands (e.g., 10j = scroll down 10 lines)
     pub pending_count: Option<usize>,
     /// Pending "g" prefix for vim-style commands (e.g., gg)
@@ -163,6 +297,8 @@ pub struct App {
     pub scrollbar_visible: bool,
     /// Show strikethrough on deleted text
     pub strikethrough_deletions: bool,
+    /// Mark change kinds with underline/strikethrough/italic besides color
+    pub kind_patterns: bool,
     /// Whether user has manually toggled the file panel (overrides auto-hide)
     pub file_panel_manually_set: bool,
     /// Whether to show the file path popup (Ctrl+G)
@@ -197,6 +333,8 @@ pub struct App {
     pub diff_fg: DiffForegroundMode,
     /// Single-pane modified line render mode while stepping
     pub single_modified_step_mode: ModifiedStepMode,
+    /// Pad modified lines in split view so their unchanged tails line up
+    pub split_align_gaps: bool,
     /// Syntax scope in evolution view
     pub evo_syntax: crate::config::EvoSyntaxMode,
     /// Syntax highlighting mode
@@ -211,10 +349,15 @@ pub struct App {
     show_syntax_scopes: bool,
     /// Cached syntax scope label for the active line
     syntax_scope_cache: Option<SyntaxScopeCache>,
+    /// File header of the current file: file index, whether the language
+    /// came from the syntax engine, header
+    file_header_cache: Option<(usize, bool, FileHeader)>,
     /// Peek old/new state (stepping-only)
     peek_state: Option<PeekState>,
     /// Saved peek state for stepping mode (when toggled off)
     step_peek_state: Option<PeekState>,
+    /// Opposite side of the active line, shown in a popup (split view)
+    pub split_peek: Option<SplitPeek>,
     /// Saved step state per file (to restore after toggling off)
     step_state_snapshots: Vec<Option<StepState>>,
     /// Saved no-step cursor/marker state per file
@@ -222,7 +365,7 @@ pub struct App {
     /// View mode to restore when stepping is enabled
     step_view_mode: ViewMode,
     /// Search query (diff pane)
-    search_query: String,
+    search_input: Input,
     /// True when search input is active
     search_active: bool,
     /// Last matched display index for search navigation
@@ -231,21 +374,196 @@ pub struct App {
     needs_scroll_to_search: bool,
     /// Target display index for search scrolling
     search_target: Option<usize>,
-    /// Cached search regex (case-insensitive)
+    /// Cached search regex (built from the query and search options)
     search_regex: Option<Regex>,
+    /// Treat the search query as a regular expression
+    pub search_regex_mode: bool,
+    /// Letter case matching for search
+    pub search_case: SearchCase,
+    /// Only match whole words
+    pub search_whole_word: bool,
+    /// 1-based index of the current search target among the file's matches
+    search_position: Option<usize>,
+    /// Number of search matches in the current file
+    search_total: usize,
+    /// With a search active, step only through changes matching it
+    pub search_filter_steps: bool,
+    /// Highlight other occurrences of the words changed by the active change
+    pub show_occurrences: bool,
+    /// Words changed by the active change (refreshed once per frame)
+    occurrences: Option<Occurrences>,
+    /// Per-file counts of changes doing the last queried token replacement
+    rename_counts: Option<RenameCounts>,
+    /// Step only through changes doing this token replacement
+    pub rename_filter: Option<(String, String)>,
+    /// Step only through changes of this kind (`Insert`, `Delete` or
+    /// `Replace` for modifications); other changes are dimmed
+    pub kind_filter: Option<ChangeKind>,
+    /// Masks secrets in rendered lines and yanked text (`--redact`)
+    pub redactor: Option<Redactor>,
     /// Goto query (":" command)
-    goto_query: String,
+    goto_input: Input,
     /// True when goto input is active
     goto_active: bool,
+    /// Review of the suggested logical hunk groups (`(` / `)`)
+    hunk_groups: Option<GroupReview>,
+    /// Hunk buckets of the commit split, in commit order (`x`, `:bucket`)
+    buckets: Vec<Bucket>,
+    /// Bucket that `x` assigns hunks to
+    active_bucket: usize,
+    /// Commit split preview (`:preview`)
+    split_preview: Option<SplitPreview>,
+    /// A/B compare of the current file at two steps, if open
+    step_compare: Option<StepCompare>,
+    /// Transient notifications, and their history (`:messages`)
+    pub toasts: Toasts,
+    /// Show the message history (`:messages`)
+    pub show_messages: bool,
+    /// Messages the history is scrolled up from its newest
+    pub messages_scroll: usize,
+    /// Ref names of the merge being resolved (three-way mode)
+    pub merge_labels: Option<MergeLabels>,
+    /// File the merge is resolved in (`--merged`, git mergetool's `$MERGED`)
+    pub merged_path: Option<PathBuf>,
+    /// Merge result diffed against ours and theirs, if open
+    merge_preview: Option<MergePreview>,
+    /// Persisted search and jump history of the project
+    history: History,
+    /// Destructive action waiting for a yes or no
+    pub confirm: Option<Confirm>,
+    /// Popups by when they were last opened, latest last; open popups
+    /// stack in this order
+    popup_order: Vec<PopupKind>,
+    /// Show the jump list (`:jumps`)
+    pub show_jump_list: bool,
+    /// Selected row of the jump list
+    jump_list_index: usize,
+    /// Show the debug log panel (`:log`)
+    pub show_log: bool,
+    /// Lines the log panel is scrolled up from its newest line
+    log_scroll: usize,
+    /// Show the settings panel (`:settings`)
+    pub show_settings: bool,
+    /// Selected row of the settings panel
+    settings_index: usize,
+    /// Compiler/CI diagnostics shown on the new side (`--diagnostics`)
+    pub diagnostics: Vec<Diagnostic>,
+    /// Review threads of the pull request being reviewed (`oy pr`)
+    pub review: Option<Review>,
+    pub show_review_panel: bool,
+    /// Thread and text of a reply waiting for confirmation
+    pending_reply: Option<(String, String)>,
+    /// Rules mapping changed files to related tests (`[[tests.rules]]`)
+    pub test_rules: Vec<ImpactRule>,
+    /// Show the test impact panel
+    pub show_test_impact: bool,
+    /// Tests likely affected by the changed files (computed on open)
+    test_hints: Vec<ImpactHint>,
+    /// Results of the last run of the hinted test commands
+    test_runs: Vec<TestRun>,
+    /// Commands of the run in progress, and where results arrive
+    test_run_pending: Option<(usize, Receiver<TestRun>)>,
+    /// Show a churn thumbnail per file in the file list
+    pub churn_thumbnails: bool,
+    /// Changes per slice of each file (see [`CHURN_BUCKETS`])
+    churn: HashMap<PathBuf, Vec<usize>>,
+    /// Thumbnails still being computed in the background
+    churn_pending: Option<Receiver<(PathBuf, Vec<usize>)>>,
     /// Snap animation frame when animations are disabled
     snap_frame: Option<AnimationFrame>,
     /// Start time of the current snap frame
     snap_frame_started_at: Option<Instant>,
     /// Last known viewport height for the diff area
     pub last_viewport_height: usize,
+    /// Base diff engine settings (presets are layered over these)
+    pub engine_config: EngineConfig,
+    /// Named presets from config (sorted by name)
+    pub presets: Vec<(String, PresetConfig)>,
+    /// Index of the active preset
+    active_preset: Option<usize>,
+    /// Metadata of the commit being reviewed (range mode)
+    pub commit_info: Option<CommitInfo>,
+    /// Earlier reviews of the commit, from git notes (`:verdict`)
+    pub commit_reviews: Vec<ReviewNote>,
+    /// Whether the commit message panel is visible
+    pub show_commit_panel: bool,
+    /// Last time HEAD was checked for bisect progress
+    last_bisect_check: Instant,
+    /// Last time the current file was checked for changes on disk
+    last_stale_check: Instant,
+    /// File whose content changed on disk since it was loaded
+    stale_file: Option<usize>,
+    /// Unsaved editor buffers (`--overlay-stdin`)
+    overlay_rx: Option<Receiver<OverlayMessage>>,
+    /// External-process plugins (`[[plugins]]`)
+    pub plugins: PluginHost,
+    /// File and step last sent to the plugins
+    plugin_position: Option<(usize, usize)>,
+    /// The plugin leader key (`\`) was pressed; the next key goes to the
+    /// plugins
+    pub plugin_key_pending: bool,
+    /// Scripted actions (`[actions]`)
+    pub actions: Option<Actions>,
+    /// Self-hosted forges (`[[forge.remotes]]`), for permalinks
+    pub forge_remotes: Vec<ForgeRemote>,
+    /// Show the frame-rate debug overlay
+    pub show_fps: bool,
+    /// Show the timings HUD (`:timings`): frame, diff and highlight times
+    /// and memory estimates
+    pub show_timings: bool,
+    /// Frames drawn during the last second (set by the render loop)
+    pub fps: usize,
+    /// Time spent on the last frame in milliseconds (set by the render loop)
+    pub frame_time_ms: f32,
+    /// Rows of the single-pane view kept between animation frames: gutter
+    /// line, content line and content width
+    pub row_cache: RowCache<(Line<'static>, Line<'static>, usize)>,
+    /// Bumped whenever something other than the animation may have changed
+    /// how rows look (see [`Self::row_cache_key`])
+    render_epoch: u64,
+    /// Set when state changed outside of input handling (e.g. background refresh)
+    pub redraw_requested: bool,
+    /// Reorder right-to-left text into visual order before rendering
+    pub bidi: bool,
+    /// Dim context lines by age (from `git blame` of the old side)
+    pub heat: bool,
+    /// Extend a hunk inside a function to the whole function
+    pub function_context: bool,
+    /// Longest function shown whole; longer ones fold their unchanged lines
+    /// away from the hunks
+    pub function_context_lines: usize,
+    /// Relative age of each old-side line (0 = newest, 1 = oldest), per file
+    heat_ages: HashMap<usize, Option<Vec<f32>>>,
+    /// Patches of a series (`oy series`); each one is its own multi-file diff
+    patch_series: Vec<Patch>,
+    /// Index of the patch being reviewed
+    patch_index: usize,
+    /// Repository used to look up patch pre-images
+    patch_repo: Option<PathBuf>,
+    /// Context lines a stale patch hunk may ignore at each end (`--fuzz`)
+    patch_fuzz: usize,
+    /// Whether the rejected hunks of the current patch are shown
+    pub show_patch_rejects: bool,
+    /// Scroll offset of the rejected hunks view
+    pub patch_rejects_scroll: usize,
+    /// `.rej` files under review (`oy rejects`); each hunk is its own entry
+    reject_files: Vec<RejectFile>,
+    /// 0-based target line each rejected hunk is placed at, by entry name
+    reject_lines: HashMap<String, usize>,
+    /// Rejected hunks moved with `:place` and not written yet
+    placed_rejects: HashSet<String>,
 }
 
+/// Slices of a file in its churn thumbnail
+pub const CHURN_BUCKETS: usize = 8;
+
 const SNAP_PHASE_MS: u64 = 50;
+const BISECT_POLL_MS: u64 = 1000;
+const STALE_POLL_MS: u64 = 1000;
+/// Keys shown at once in presentation mode
+const KEY_ECHO_MAX: usize = 6;
+/// How long a pressed key stays on screen in presentation mode
+const KEY_ECHO_DURATION: Duration = Duration::from_millis(1500);
 
 /// Pure helper: determine if overscroll should be allowed
 fn allow_overscroll_state(
@@ -281,12 +599,36 @@ pub enum PeekMode {
     Mixed,
 }
 
+/// Side whose version of the active line the split view peek shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitPeek {
+    /// The old line, floating over the new pane
+    Old,
+    /// The new line, floating over the old pane
+    New,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PeekState {
     pub scope: PeekScope,
     pub mode: PeekMode,
 }
 
+/// What the file header bar shows about the current file
+#[derive(Debug, Clone)]
+pub struct FileHeader {
+    pub path: String,
+    pub status: FileStatus,
+    /// Syntax name, if the file has one other than plain text
+    pub language: Option<String>,
+    /// Format and size in bytes of the old version (`None` if added)
+    pub old: Option<(TextFormat, usize)>,
+    /// Format and size in bytes of the new version (`None` if deleted)
+    pub new: Option<(TextFormat, usize)>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
 #[derive(Debug, Clone)]
 struct SyntaxScopeCache {
     file_index: usize,
@@ -295,6 +637,80 @@ struct SyntaxScopeCache {
     label: String,
 }
 
+/// A token replacement and how many changes of each file perform it
+type RenameCounts = ((String, String), Vec<usize>);
+
+/// Sites of the focused change's token replacement (a rename)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameSites {
+    pub old: String,
+    pub new: String,
+    /// Changes doing the replacement in the selected file
+    pub in_file: usize,
+    /// Changes doing the replacement across the changeset
+    pub total: usize,
+    /// Files with at least one such change
+    pub files: usize,
+}
+
+/// Most words a single change contributes to occurrence highlighting
+const MAX_OCCURRENCE_WORDS: usize = 16;
+
+/// Whole-word patterns for the words a change removed and added
+#[derive(Debug, Clone)]
+struct Occurrences {
+    file: usize,
+    change_id: usize,
+    old: Option<Regex>,
+    new: Option<Regex>,
+}
+
+impl Occurrences {
+    fn for_change(file: usize, change: &Change) -> Self {
+        let mut old_words: Vec<&str> = Vec::new();
+        let mut new_words: Vec<&str> = Vec::new();
+        for span in &change.spans {
+            match span.kind {
+                ChangeKind::Equal => {}
+                ChangeKind::Delete => old_words.extend(words(&span.text)),
+                ChangeKind::Insert => new_words.extend(words(&span.text)),
+                ChangeKind::Replace => {
+                    old_words.extend(words(&span.text));
+                    new_words.extend(words(span.new_text.as_deref().unwrap_or_default()));
+                }
+            }
+        }
+        // Only in-line edits (a rename, a changed call) have counterparts to
+        // look for; whole inserted or deleted lines would light up every word
+        let in_line_edit = !old_words.is_empty() && !new_words.is_empty();
+        Self {
+            file,
+            change_id: change.id,
+            old: in_line_edit.then(|| word_regex(&old_words)).flatten(),
+            new: in_line_edit.then(|| word_regex(&new_words)).flatten(),
+        }
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+}
+
+fn word_regex(words: &[&str]) -> Option<Regex> {
+    let mut unique: Vec<&str> = Vec::new();
+    for word in words {
+        if !unique.contains(word) && unique.len() < MAX_OCCURRENCE_WORDS {
+            unique.push(word);
+        }
+    }
+    if unique.is_empty() {
+        return None;
+    }
+    let alternation: Vec<String> = unique.iter().map(|word| regex::escape(word)).collect();
+    Regex::new(&format!(r"\b(?:{})\b", alternation.join("|"))).ok()
+}
+
 impl App {
     pub fn new(
         multi_diff: MultiFileDiff,
@@ -316,28 +732,47 @@ impl App {
             no_step_visited: vec![false; file_count],
             files_visited: vec![false; file_count],
             should_quit: false,
+            force_quit: false,
             animation_phase: AnimationPhase::Idle,
             animation_progress: 1.0,
-            last_animation_tick: Instant::now(),
+            animation_started_at: Instant::now(),
             last_autoplay_tick: Instant::now(),
             file_list_focused: false,
             file_panel_visible: true,
             file_list_scroll: 0,
             file_list_area: None,
+            show_scrub_bar: false,
+            scrub_bar_area: None,
+            show_file_header: true,
+            show_breadcrumbs: false,
+            crumb_level: CrumbLevel::Change,
             file_list_rows: Vec::new(),
             file_filter_area: None,
             file_count_mode: FileCountMode::Active,
-            file_filter: String::new(),
+            file_sort: FileSortMode::Path,
+            reviewed_files: HashSet::new(),
+            commit_type_groups: None,
+            file_filter: Input::default(),
             file_filter_active: false,
             animation_enabled: false,
+            reduced_motion: false,
             zen_mode: false,
+            presentation: false,
+            presentation_auto_center: true,
+            key_echo: Vec::new(),
             needs_scroll_to_active: true, // Scroll to first change on startup
             show_help: false,
             help_scroll: 0,
             help_max_scroll: 0,
             git_branch,
+            repo_branches: HashMap::new(),
             auto_center: true,
             animation_duration: 150,
+            fade_out_duration: None,
+            fade_in_duration: None,
+            animation_easing: AnimationEasing::Linear,
+            scroll_duration: 150,
+            scroll_animation: None,
             pending_count: None,
             pending_g_prefix: false,
             horizontal_scroll: 0,
@@ -350,6 +785,7 @@ impl App {
             last_wrap_active_idx: None,
             scrollbar_visible: false,
             strikethrough_deletions: false,
+            kind_patterns: false,
             file_panel_manually_set: false,
             show_path_popup: false,
             file_panel_auto_hidden: false,
@@ -367,6 +803,7 @@ impl App {
             diff_bg: DiffBackgroundMode::None,
             diff_fg: DiffForegroundMode::Theme,
             single_modified_step_mode: ModifiedStepMode::Mixed,
+            split_align_gaps: false,
             evo_syntax: crate::config::EvoSyntaxMode::Context,
             syntax_mode: SyntaxMode::On,
             syntax_theme: "ansi".to_string(),
@@ -374,22 +811,104 @@ impl App {
             syntax_caches: vec![None; file_count],
             show_syntax_scopes: false,
             syntax_scope_cache: None,
+            file_header_cache: None,
             peek_state: None,
             step_peek_state: None,
+            split_peek: None,
             step_state_snapshots: vec![None; file_count],
             no_step_state_snapshots: vec![None; file_count],
             step_view_mode: view_mode,
-            search_query: String::new(),
+            search_input: Input::default(),
             search_active: false,
             search_last_target: None,
             needs_scroll_to_search: false,
             search_target: None,
             search_regex: None,
-            goto_query: String::new(),
+            search_regex_mode: false,
+            search_case: SearchCase::default(),
+            search_whole_word: false,
+            search_position: None,
+            search_total: 0,
+            search_filter_steps: false,
+            show_occurrences: false,
+            occurrences: None,
+            rename_counts: None,
+            rename_filter: None,
+            kind_filter: None,
+            redactor: None,
+            goto_input: Input::with_validator(validate_goto),
             goto_active: false,
+            hunk_groups: None,
+            buckets: Vec::new(),
+            active_bucket: 0,
+            split_preview: None,
+            step_compare: None,
+            toasts: Toasts::default(),
+            show_messages: false,
+            messages_scroll: 0,
+            merge_labels: None,
+            merged_path: None,
+            merge_preview: None,
+            history: History::default(),
+            confirm: None,
+            popup_order: Vec::new(),
+            show_jump_list: false,
+            jump_list_index: 0,
+            show_log: false,
+            log_scroll: 0,
+            show_settings: false,
+            settings_index: 0,
+            diagnostics: Vec::new(),
+            review: None,
+            show_review_panel: false,
+            pending_reply: None,
+            test_rules: Vec::new(),
+            show_test_impact: false,
+            test_hints: Vec::new(),
+            test_runs: Vec::new(),
+            test_run_pending: None,
+            churn_thumbnails: true,
+            churn: HashMap::new(),
+            churn_pending: None,
             snap_frame: None,
             snap_frame_started_at: None,
             last_viewport_height: 0,
+            engine_config: EngineConfig::default(),
+            presets: Vec::new(),
+            active_preset: None,
+            commit_info: None,
+            commit_reviews: Vec::new(),
+            show_commit_panel: false,
+            last_bisect_check: Instant::now(),
+            last_stale_check: Instant::now(),
+            stale_file: None,
+            overlay_rx: None,
+            plugins: PluginHost::default(),
+            plugin_position: None,
+            plugin_key_pending: false,
+            actions: None,
+            forge_remotes: Vec::new(),
+            show_fps: false,
+            show_timings: false,
+            fps: 0,
+            frame_time_ms: 0.0,
+            row_cache: RowCache::default(),
+            render_epoch: 0,
+            redraw_requested: false,
+            bidi: true,
+            heat: false,
+            function_context: false,
+            function_context_lines: 40,
+            heat_ages: HashMap::new(),
+            patch_series: Vec::new(),
+            patch_index: 0,
+            patch_repo: None,
+            patch_fuzz: 0,
+            show_patch_rejects: false,
+            patch_rejects_scroll: 0,
+            reject_files: Vec::new(),
+            reject_lines: HashMap::new(),
+            placed_rejects: HashSet::new(),
         }
     }
 
@@ -412,14 +931,20 @@ impl App {
     }
 
     pub fn toggle_help(&mut self) {
-        self.show_help = !self.show_help;
         if self.show_help {
+            self.close_popup(PopupKind::Help);
+        } else {
             self.help_scroll = 0;
+            self.open_popup(PopupKind::Help);
         }
     }
 
     pub fn toggle_path_popup(&mut self) {
-        self.show_path_popup = !self.show_path_popup;
+        if self.show_path_popup {
+            self.close_popup(PopupKind::Path);
+        } else {
+            self.open_popup(PopupKind::Path);
+        }
     }
 
     pub fn toggle_syntax(&mut self) {
@@ -450,6 +975,17 @@ impl App {
 
     fn clear_peek(&mut self) {
         self.peek_state = None;
+        self.split_peek = None;
+    }
+
+    /// Show the other side's version of the active line over the pane
+    /// that lacks it (split view)
+    pub fn toggle_split_peek(&mut self, side: SplitPeek) {
+        self.split_peek = if self.split_peek == Some(side) {
+            None
+        } else {
+            Some(side)
+        };
     }
 
     fn cycle_peek_change(&mut self) {
@@ -548,2625 +1084,5352 @@ impl App {
 
     pub fn start_search(&mut self) {
         self.search_active = true;
-        self.search_query.clear();
+        self.search_input.clear();
         self.search_last_target = None;
         self.search_target = None;
         self.needs_scroll_to_search = false;
         self.search_regex = None;
+        self.search_position = None;
+        self.search_total = 0;
     }
 
     pub fn stop_search(&mut self) {
         self.search_active = false;
+        self.history.record_search(self.search_input.text());
     }
 
     pub fn clear_search(&mut self) {
         self.search_active = false;
-        self.search_query.clear();
+        self.search_input.clear();
         self.search_last_target = None;
         self.search_target = None;
         self.needs_scroll_to_search = false;
         self.search_regex = None;
+        self.search_position = None;
+        self.search_total = 0;
     }
 
     pub fn clear_search_text(&mut self) {
-        self.search_query.clear();
+        self.search_input.clear();
         self.search_last_target = None;
         self.search_target = None;
         self.needs_scroll_to_search = false;
         self.search_regex = None;
+        self.search_position = None;
+        self.search_total = 0;
     }
 
     pub fn start_goto(&mut self) {
         self.goto_active = true;
-        self.goto_query.clear();
+        self.goto_input.clear();
     }
 
     pub fn clear_goto(&mut self) {
         self.goto_active = false;
-        self.goto_query.clear();
+        self.goto_input.clear();
     }
 
-    pub fn clear_goto_text(&mut self) {
-        self.goto_query.clear();
+    pub fn goto_active(&self) -> bool {
+        self.goto_active
     }
 
-    pub fn push_goto_char(&mut self, ch: char) {
-        self.goto_query.push(ch);
+    pub fn goto_query(&self) -> &str {
+        self.goto_input.text()
     }
 
-    pub fn pop_goto_char(&mut self) {
-        self.goto_query.pop();
+    pub fn goto_input(&self) -> &Input {
+        &self.goto_input
     }
 
-    pub fn goto_active(&self) -> bool {
-        self.goto_active
+    /// Edit the `:` prompt; Enter runs the command unless it is invalid,
+    /// Up/Down recall earlier commands
+    pub fn goto_key(&mut self, key: PromptKey) {
+        match key {
+            PromptKey::Cancel => self.clear_goto(),
+            PromptKey::Submit => {
+                if let Some(err) = self.goto_input.error() {
+                    self.notify(Level::Error, err);
+                    return;
+                }
+                self.apply_goto();
+                self.clear_goto();
+            }
+            PromptKey::Backspace if self.goto_input.is_empty() => self.clear_goto(),
+            key => {
+                let queries: Vec<&str> = self
+                    .history
+                    .jumps
+                    .iter()
+                    .map(|jump| jump.query.as_str())
+                    .collect();
+                self.goto_input.edit(key, &queries);
+            }
+        }
     }
 
-    pub fn goto_query(&self) -> &str {
-        &self.goto_query
+    pub fn search_input(&self) -> &Input {
+        &self.search_input
     }
 
-    pub fn push_search_char(&mut self, ch: char) {
-        self.search_query.push(ch);
-        self.search_last_target = None;
-        self.update_search_regex();
+    /// Edit the `/` prompt; Up/Down recall earlier searches
+    pub fn search_key(&mut self, key: PromptKey) {
+        match key {
+            PromptKey::Cancel => self.clear_search(),
+            PromptKey::Submit => {
+                self.stop_search();
+                self.search_next();
+            }
+            PromptKey::Backspace if self.search_input.is_empty() => self.clear_search(),
+            PromptKey::Clear => self.clear_search_text(),
+            key => {
+                if self.search_input.edit(key, &self.history.searches) {
+                    self.search_last_target = None;
+                    self.update_search_regex();
+                }
+            }
+        }
     }
 
-    pub fn pop_search_char(&mut self) {
-        self.search_query.pop();
-        self.search_last_target = None;
-        self.update_search_regex();
+    /// Load the search and jump history persisted for `project`
+    pub fn load_history(&mut self, project: &Path) {
+        self.history = History::load(project);
     }
 
-    fn reset_search_for_file_switch(&mut self) {
-        self.search_last_target = None;
-        self.search_target = None;
-        self.needs_scroll_to_search = false;
+    pub fn jumps(&self) -> &[Jump] {
+        &self.history.jumps
     }
 
-    pub fn search_active(&self) -> bool {
-        self.search_active
+    pub fn jump_list_index(&self) -> usize {
+        self.jump_list_index
     }
 
-    pub fn search_query(&self) -> &str {
-        &self.search_query
+    /// Whether popup `kind` is open
+    pub fn popup_open(&self, kind: PopupKind) -> bool {
+        match kind {
+            PopupKind::TestImpact => self.show_test_impact,
+            PopupKind::PatchRejects => self.show_patch_rejects,
+            PopupKind::Settings => self.show_settings,
+            PopupKind::Log => self.show_log,
+            PopupKind::Jumps => self.show_jump_list,
+            PopupKind::Messages => self.show_messages,
+            PopupKind::Path => self.show_path_popup,
+            PopupKind::Help => self.show_help,
+            PopupKind::Confirm => self.confirm.is_some(),
+        }
     }
 
-    fn update_search_regex(&mut self) {
-        let query = self.search_query.trim();
-        if query.is_empty() {
-            self.search_regex = None;
-            return;
+    pub fn close_popup(&mut self, kind: PopupKind) {
+        match kind {
+            PopupKind::TestImpact => self.show_test_impact = false,
+            PopupKind::PatchRejects => self.show_patch_rejects = false,
+            PopupKind::Settings => self.show_settings = false,
+            PopupKind::Log => self.show_log = false,
+            PopupKind::Jumps => self.show_jump_list = false,
+            PopupKind::Messages => self.show_messages = false,
+            PopupKind::Path => self.show_path_popup = false,
+            PopupKind::Help => self.show_help = false,
+            PopupKind::Confirm => self.confirm = None,
         }
-        let regex = RegexBuilder::new(query)
-            .case_insensitive(true)
-            .build()
-            .or_else(|_| {
-                RegexBuilder::new(&regex::escape(query))
-                    .case_insensitive(true)
-                    .build()
-            })
-            .ok();
-        self.search_regex = regex;
     }
 
-    pub fn search_target(&self) -> Option<usize> {
-        self.search_target
+    /// Ask before running a destructive action
+    pub fn ask(&mut self, action: ConfirmAction, question: String) {
+        self.confirm = Some(Confirm { question, action });
+        self.open_popup(PopupKind::Confirm);
     }
 
-    pub fn search_next(&mut self) {
-        let matches = self.collect_search_matches();
-        if matches.is_empty() {
+    /// Run the action asked about, or drop it
+    pub fn answer_confirm(&mut self, yes: bool) {
+        let Some(confirm) = self.confirm.take() else {
+            return;
+        };
+        if !yes {
             return;
         }
-        let start = self.search_last_target.unwrap_or(self.scroll_offset);
-        let target = matches
-            .iter()
-            .copied()
-            .find(|idx| *idx > start)
-            .unwrap_or(matches[0]);
-        self.search_last_target = Some(target);
-        self.search_target = Some(target);
-        self.needs_scroll_to_search = true;
+        match confirm.action {
+            ConfirmAction::ApplyReject => self.apply_reject(),
+            ConfirmAction::CommitBuckets => self.commit_buckets(),
+            ConfirmAction::Quit => self.should_quit = true,
+            ConfirmAction::PostReply => self.post_reply(),
+        }
     }
 
-    pub fn search_prev(&mut self) {
-        let matches = self.collect_search_matches();
-        if matches.is_empty() {
-            return;
+    /// Work that quitting would lose: hunks in buckets not committed yet and
+    /// rejected hunks moved but not written
+    pub fn unsaved_work(&self) -> Vec<String> {
+        let mut unsaved = Vec::new();
+        let bucketed: usize = self.buckets.iter().map(|bucket| bucket.hunks.len()).sum();
+        let hunks = |count: usize| if count == 1 { "hunk" } else { "hunks" };
+        if bucketed > 0 {
+            unsaved.push(format!(
+                "{bucketed} {} in uncommitted buckets",
+                hunks(bucketed)
+            ));
         }
-        let start = self.search_last_target.unwrap_or(self.scroll_offset);
-        let target = matches
-            .iter()
-            .copied()
-            .rev()
-            .find(|idx| *idx < start)
-            .unwrap_or(*matches.last().unwrap());
-        self.search_last_target = Some(target);
-        self.search_target = Some(target);
-        self.needs_scroll_to_search = true;
+        let placed = self.placed_rejects.len();
+        if placed > 0 {
+            unsaved.push(format!(
+                "{placed} moved rejected {} not applied",
+                hunks(placed)
+            ));
+        }
+        unsaved
     }
 
-    pub fn apply_goto(&mut self) {
-        let query = self.goto_query.trim();
-        if query.is_empty() {
-            return;
+    /// True if the app may quit now; otherwise asks first, unless started
+    /// with `--force`
+    pub fn request_quit(&mut self) -> bool {
+        let unsaved = self.unsaved_work();
+        if self.force_quit || unsaved.is_empty() {
+            return true;
         }
+        self.ask(
+            ConfirmAction::Quit,
+            format!("Quit with {}?", unsaved.join(" and ")),
+        );
+        false
+    }
 
-        let mut chars = query.chars();
-        let first = match chars.next() {
-            Some(ch) => ch,
-            None => return,
-        };
+    /// Whether the question asked offers to commit the buckets first
+    pub fn confirm_can_save(&self) -> bool {
+        self.confirm
+            .as_ref()
+            .is_some_and(|confirm| confirm.action == ConfirmAction::Quit)
+            && self.buckets.iter().any(|bucket| !bucket.hunks.is_empty())
+    }
 
-        match first {
-            'h' | 'H' => {
-                let rest = chars
-                    .as_str()
-                    .trim_start_matches(|c: char| c == ':' || c.is_whitespace());
-                if let Ok(num) = rest.parse::<usize>() {
-                    self.goto_hunk_number(num);
-                }
-            }
-            's' | 'S' => {
-                let rest = chars
-                    .as_str()
-                    .trim_start_matches(|c: char| c == ':' || c.is_whitespace());
-                if let Ok(num) = rest.parse::<usize>() {
-                    self.goto_step_number(num);
-                }
-            }
-            _ => {
-                if query.chars().all(|c| c.is_ascii_digit()) {
-                    if let Ok(num) = query.parse::<usize>() {
-                        self.goto_line_number(num);
-                    }
-                }
-            }
+    /// Commit the buckets, then quit unless something is still unsaved
+    pub fn save_and_quit(&mut self) {
+        self.confirm = None;
+        self.commit_buckets();
+        let unsaved = self.unsaved_work();
+        if unsaved.is_empty() {
+            self.should_quit = true;
+        } else {
+            self.notify(
+                Level::Warning,
+                format!("not quitting: {}", unsaved.join(", ")),
+            );
+        }
+    }
+
+    /// Open popup `kind` on top of the others. A confirm popup opens from
+    /// [`App::ask`], which sets its question first.
+    fn open_popup(&mut self, kind: PopupKind) {
+        match kind {
+            PopupKind::TestImpact => self.show_test_impact = true,
+            PopupKind::PatchRejects => self.show_patch_rejects = true,
+            PopupKind::Settings => self.show_settings = true,
+            PopupKind::Log => self.show_log = true,
+            PopupKind::Jumps => self.show_jump_list = true,
+            PopupKind::Messages => self.show_messages = true,
+            PopupKind::Path => self.show_path_popup = true,
+            PopupKind::Help => self.show_help = true,
+            PopupKind::Confirm => debug_assert!(self.confirm.is_some()),
+        }
+        self.popup_order.retain(|&raised| raised != kind);
+        self.popup_order.push(kind);
+    }
+
+    /// Open popups, bottom first
+    pub fn open_popups(&self) -> Vec<PopupKind> {
+        let mut open: Vec<PopupKind> = PopupKind::ALL
+            .into_iter()
+            .filter(|&kind| self.popup_open(kind))
+            .collect();
+        open.sort_by_key(|kind| self.popup_order.iter().position(|raised| raised == kind));
+        open
+    }
+
+    /// The popup on top
+    pub fn focused_popup(&self) -> Option<PopupKind> {
+        self.open_popups().last().copied()
+    }
+
+    /// The highest open popup that takes keys, which may sit under the path
+    /// popup
+    pub fn input_popup(&self) -> Option<PopupKind> {
+        self.open_popups()
+            .into_iter()
+            .rev()
+            .find(|kind| kind.takes_input())
+    }
+
+    /// Show a transient notification, kept in the `:messages` history
+    pub fn notify(&mut self, level: Level, text: impl Into<String>) {
+        self.toasts.push(level, text);
+        self.redraw_requested = true;
+    }
+
+    pub fn open_messages(&mut self) {
+        self.messages_scroll = 0;
+        self.open_popup(PopupKind::Messages);
+    }
+
+    pub fn open_jump_list(&mut self) {
+        self.open_popup(PopupKind::Jumps);
+        self.jump_list_index = 0;
+    }
+
+    pub fn jump_list_down(&mut self) {
+        if self.jump_list_index + 1 < self.history.jumps.len() {
+            self.jump_list_index += 1;
         }
     }
 
-    pub fn highlight_search_spans(
-        &self,
-        spans: Vec<Span<'static>>,
-        text: &str,
-        is_active: bool,
-    ) -> Vec<Span<'static>> {
-        let Some(regex) = self.search_regex.as_ref() else {
-            return spans;
-        };
-        let ranges = match_ranges(text, regex);
-        if ranges.is_empty() {
-            return spans;
+    pub fn jump_list_up(&mut self) {
+        self.jump_list_index = self.jump_list_index.saturating_sub(1);
+    }
+
+    pub fn log_scroll(&self) -> usize {
+        self.log_scroll
+    }
+
+    pub fn open_log(&mut self) {
+        if crate::logging::path().is_none() {
+            self.notify(Level::Warning, "Start oy with --debug to log timings");
+            return;
         }
-        let highlight_bg = if is_active {
-            self.theme.accent
-        } else {
-            color::dim_color(self.theme.accent)
-        };
-        let highlight_fg = if is_active {
-            self.search_highlight_fg(highlight_bg)
+        self.open_popup(PopupKind::Log);
+        self.log_scroll = 0;
+    }
+
+    /// Scroll the log panel toward older (`up`) or newer lines
+    pub fn scroll_log(&mut self, up: bool, lines: usize) {
+        self.log_scroll = if up {
+            self.log_scroll.saturating_add(lines)
         } else {
-            None
+            self.log_scroll.saturating_sub(lines)
         };
-        apply_highlight_spans(spans, &ranges, highlight_bg, highlight_fg)
     }
 
-    pub fn yank_current_change(&mut self) {
-        let frame = self.animation_frame();
-        let view_lines = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame);
-        let Some(line) = view_lines.iter().find(|line| line.is_primary_active) else {
-            return;
-        };
-        if let Some(text) = self.text_for_yank(line) {
-            copy_to_clipboard(&text);
-        }
+    pub fn settings_index(&self) -> usize {
+        self.settings_index
     }
 
-    fn search_highlight_fg(&self, bg: Color) -> Option<Color> {
-        let text = self.theme.text;
-        let mut best_color = text;
-        let mut best_ratio = color::contrast_ratio(bg, text).unwrap_or(0.0);
-        if let Some(bg_color) = self.theme.background {
-            let ratio = color::contrast_ratio(bg, bg_color).unwrap_or(0.0);
-            if ratio > best_ratio {
-                best_ratio = ratio;
-                best_color = bg_color;
-            }
+    pub fn open_settings(&mut self) {
+        self.open_popup(PopupKind::Settings);
+        self.settings_index = 0;
+    }
+
+    pub fn settings_down(&mut self) {
+        if self.settings_index + 1 < settings::SETTINGS.len() {
+            self.settings_index += 1;
         }
-        if best_ratio == 0.0 {
-            None
-        } else {
-            Some(best_color)
+    }
+
+    pub fn settings_up(&mut self) {
+        self.settings_index = self.settings_index.saturating_sub(1);
+    }
+
+    /// Cycle the selected setting and save it to the config file
+    pub fn change_setting(&mut self, forward: bool) {
+        let setting = &settings::SETTINGS[self.settings_index];
+        let value = setting.cycle(self, forward);
+        let saved = match crate::config::Config::write_path() {
+            Some(path) => settings::write(&path, setting, value),
+            None => Err("no config directory".to_string()),
+        };
+        match saved {
+            Ok(()) => self.notify(Level::Success, format!("{} = {value} (saved)", setting.key)),
+            Err(err) => self.notify(
+                Level::Warning,
+                format!("{} = {value} (not saved: {err})", setting.key),
+            ),
         }
     }
 
-    pub fn yank_current_hunk(&mut self) {
-        let frame = self.animation_frame();
-        let view_lines = self
+    /// Open the test impact panel with hints for the changed files
+    pub fn open_test_impact(&mut self) {
+        self.open_popup(PopupKind::TestImpact);
+        let changed: Vec<String> = self
             .multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame);
-        let current_hunk = self.multi_diff.current_navigator().state().current_hunk;
-        let mut lines: Vec<String> = Vec::new();
-        for line in view_lines
+            .files
             .iter()
-            .filter(|line| line.hunk_index == Some(current_hunk))
+            .map(|file| file.path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        let files = self
+            .multi_diff
+            .repo_root()
+            .and_then(|root| oyo_core::git::list_files(root).ok())
+            .unwrap_or_else(|| changed.clone());
+        self.test_hints = crate::impact::hints(&self.test_rules, &changed, &files);
+    }
+
+    pub fn test_hints(&self) -> &[ImpactHint] {
+        &self.test_hints
+    }
+
+    pub fn test_runs(&self) -> &[TestRun] {
+        &self.test_runs
+    }
+
+    /// Number of hinted commands still running
+    pub fn test_runs_pending(&self) -> usize {
+        self.test_run_pending
+            .as_ref()
+            .map_or(0, |(total, _)| total.saturating_sub(self.test_runs.len()))
+    }
+
+    /// Run the commands of the test hints (each once) in the background
+    pub fn run_impacted_tests(&mut self) {
+        if self.test_run_pending.is_some() {
+            return;
+        }
+        let mut commands: Vec<String> = Vec::new();
+        for command in self
+            .test_hints
+            .iter()
+            .filter_map(|hint| hint.command.clone())
         {
-            if let Some(text) = self.text_for_yank(line) {
-                lines.push(text);
+            if !commands.contains(&command) {
+                commands.push(command);
             }
         }
-        if lines.is_empty() {
+        if commands.is_empty() {
             return;
         }
-        copy_to_clipboard(&lines.join("\n"));
+        let dir = self
+            .multi_diff
+            .repo_root()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+        self.test_runs.clear();
+        let total = commands.len();
+        self.test_run_pending = Some((total, crate::impact::spawn_runs(commands, dir)));
     }
 
-    fn text_for_yank(&mut self, view_line: &ViewLine) -> Option<String> {
-        if let Some(mode) = self.peek_mode_for_line(view_line) {
-            match mode {
-                PeekMode::Old => {
-                    if let Some(text) = self.peek_text_for_line(view_line) {
-                        return Some(text);
-                    }
-                }
-                PeekMode::Modified => {
-                    if let Some(text) = self.modified_only_text_for_line(view_line) {
-                        return Some(text);
-                    }
-                }
-                PeekMode::Mixed => {
-                    let change = self
-                        .multi_diff
-                        .current_navigator()
-                        .diff()
-                        .changes
-                        .get(view_line.change_id);
-                    if let Some(change) = change {
-                        let text = inline_text_for_change(change);
-                        if !text.is_empty() {
-                            return Some(text);
-                        }
-                    }
-                }
-            }
+    fn collect_test_runs(&mut self) {
+        let Some((total, rx)) = &self.test_run_pending else {
+            return;
+        };
+        let runs: Vec<TestRun> = rx.try_iter().collect();
+        let done = self.test_runs.len() + runs.len() >= *total;
+        if !runs.is_empty() {
+            self.test_runs.extend(runs);
+            self.redraw_requested = true;
+        }
+        if done {
+            self.test_run_pending = None;
         }
-        Some(view_line.content.clone())
     }
 
-    fn peek_text_for_line(&mut self, view_line: &ViewLine) -> Option<String> {
-        if !matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
-            return None;
-        }
-        let change = self
-            .multi_diff
-            .current_navigator()
-            .diff()
-            .changes
-            .get(view_line.change_id)?;
-        let text = old_text_for_change(change);
-        if text.is_empty() {
-            None
-        } else {
-            Some(text)
-        }
+    /// Changes per slice of file `path`, once computed
+    pub fn file_churn(&self, path: &Path) -> Option<&[usize]> {
+        self.churn.get(path).map(Vec::as_slice)
     }
 
-    fn modified_only_text_for_line(&mut self, view_line: &ViewLine) -> Option<String> {
-        if !matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
-            return None;
-        }
-        let change = self
-            .multi_diff
-            .current_navigator()
-            .diff()
-            .changes
-            .get(view_line.change_id)?;
-        let text = modified_only_text_for_change(change);
-        if text.is_empty() {
-            None
-        } else {
-            Some(text)
+    /// Compute the churn thumbnails of all files on a background thread
+    fn start_churn(&mut self) {
+        if !self.churn_thumbnails
+            || !self.multi_diff.is_multi_file()
+            || self.churn_pending.is_some()
+            || !self.churn.is_empty()
+        {
+            return;
         }
+        let files: Vec<(PathBuf, String, String)> = (0..self.multi_diff.file_count())
+            .filter_map(|idx| {
+                let (old, new) = self.multi_diff.file_contents(idx)?;
+                let path = self.multi_diff.files[idx].path.clone();
+                Some((path, old.to_string(), new.to_string()))
+            })
+            .collect();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let engine = oyo_core::DiffEngine::new().with_word_level(false);
+            for (path, old, new) in files {
+                let churn = engine.diff_strings(&old, &new).churn(CHURN_BUCKETS);
+                if sender.send((path, churn)).is_err() {
+                    break;
+                }
+            }
+        });
+        self.churn_pending = Some(receiver);
     }
 
-    fn collect_search_matches(&mut self) -> Vec<usize> {
-        let regex = match self.search_regex.as_ref() {
-            Some(regex) => regex.clone(),
-            None => return Vec::new(),
+    fn collect_churn(&mut self) {
+        let Some(rx) = &self.churn_pending else {
+            return;
         };
-        let frame = self.animation_frame();
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame);
-        let mut matches = Vec::new();
-
-        match self.view_mode {
-            ViewMode::SinglePane => {
-                for (display_idx, line) in view.iter().enumerate() {
-                    let text = self.search_text_single(line);
-                    if line_has_query(&text, &regex) {
-                        matches.push(display_idx);
-                    }
-                }
-            }
-            ViewMode::Evolution => {
-                let mut display_idx = 0usize;
-                for line in &view {
-                    let visible = match line.kind {
-                        LineKind::Deleted => false,
-                        LineKind::PendingDelete => {
-                            line.is_active && self.animation_phase != AnimationPhase::Idle
-                        }
-                        _ => true,
-                    };
-                    if !visible {
-                        continue;
-                    }
-                    let text = self.search_text_single(line);
-                    if line_has_query(&text, &regex) {
-                        matches.push(display_idx);
-                    }
-                    display_idx += 1;
+        let mut received = false;
+        loop {
+            match rx.try_recv() {
+                Ok((path, churn)) => {
+                    self.churn.insert(path, churn);
+                    received = true;
                 }
-            }
-            ViewMode::Split => {
-                let mut old_idx = 0usize;
-                let mut new_idx = 0usize;
-                for line in &view {
-                    if line.old_line.is_some() {
-                        if let Some(text) = self.search_text_split_old(line) {
-                            if line_has_query(&text, &regex) {
-                                matches.push(old_idx);
-                            }
-                        }
-                        old_idx += 1;
-                    }
-                    if line.new_line.is_some()
-                        && !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
-                    {
-                        if let Some(text) = self.search_text_split_new(line) {
-                            if line_has_query(&text, &regex) {
-                                matches.push(new_idx);
-                            }
-                        }
-                        new_idx += 1;
-                    }
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.churn_pending = None;
+                    break;
                 }
             }
         }
-
-        matches.sort_unstable();
-        matches
+        if received {
+            self.redraw_requested = true;
+        }
     }
 
-    fn search_text_single(&mut self, view_line: &ViewLine) -> String {
-        if let Some(mode) = self.peek_mode_for_line(view_line) {
-            match mode {
-                PeekMode::Old => {
-                    if let Some(text) = self.peek_text_for_line(view_line) {
-                        return text;
-                    }
-                }
-                PeekMode::Modified => {
-                    if let Some(text) = self.modified_only_text_for_line(view_line) {
-                        return text;
-                    }
-                }
-                PeekMode::Mixed => {
-                    if let Some(change) = self
-                        .multi_diff
-                        .current_navigator()
-                        .diff()
-                        .changes
-                        .get(view_line.change_id)
-                    {
-                        let text = inline_text_for_change(change);
-                        if !text.is_empty() {
-                            return text;
-                        }
-                    }
-                }
-            }
-        }
-        if !self.stepping && matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify)
+    /// Go to the selected jump of the jump list and close it
+    pub fn apply_selected_jump(&mut self) {
+        self.show_jump_list = false;
+        let Some(jump) = self.history.jumps.get(self.jump_list_index).cloned() else {
+            return;
+        };
+        if let Some(index) = self
+            .multi_diff
+            .files
+            .iter()
+            .position(|file| file.display_name == jump.file)
         {
-            if let Some(change) = self
-                .multi_diff
-                .current_navigator()
-                .diff()
-                .changes
-                .get(view_line.change_id)
-            {
-                let text = inline_text_for_change(change);
-                if !text.is_empty() {
-                    return text;
-                }
+            if index != self.multi_diff.selected_index {
+                self.select_file(index);
             }
         }
-        view_line.content.clone()
+        self.goto_input.set(jump.query);
+        self.apply_goto();
+        self.goto_input.clear();
     }
 
-    fn search_text_split_old(&mut self, view_line: &ViewLine) -> Option<String> {
-        view_line.old_line?;
-        if matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
-            if let Some(change) = self
-                .multi_diff
-                .current_navigator()
-                .diff()
-                .changes
-                .get(view_line.change_id)
-            {
-                let text = old_text_for_change(change);
-                if !text.is_empty() {
-                    return Some(text);
-                }
-            }
-        }
-        Some(view_line.content.clone())
+    fn reset_search_for_file_switch(&mut self) {
+        self.search_last_target = None;
+        self.search_target = None;
+        self.needs_scroll_to_search = false;
+        self.refresh_search_count();
     }
 
-    fn search_text_split_new(&mut self, view_line: &ViewLine) -> Option<String> {
-        view_line.new_line?;
-        Some(view_line.content.clone())
+    pub fn search_active(&self) -> bool {
+        self.search_active
     }
 
-    pub fn state(&mut self) -> StepState {
-        self.multi_diff.current_navigator().state().clone()
+    pub fn search_query(&self) -> &str {
+        self.search_input.text()
     }
 
-    pub fn syntax_enabled(&self) -> bool {
-        match self.syntax_mode {
-            SyntaxMode::On => true,
-            SyntaxMode::Off => false,
-        }
+    pub fn toggle_search_regex_mode(&mut self) {
+        self.search_regex_mode = !self.search_regex_mode;
+        self.update_search_regex();
     }
 
-    pub fn syntax_spans_for_line(
-        &mut self,
-        side: SyntaxSide,
-        line_num: Option<usize>,
-    ) -> Option<Vec<Span<'static>>> {
-        if !self.syntax_enabled() {
-            return None;
-        }
-        let line_num = line_num?;
-        if line_num == 0 {
-            return None;
-        }
-        let cache = self.ensure_syntax_cache()?;
-        let spans = cache.spans(side, line_num - 1)?;
-        Some(
-            spans
-                .iter()
-                .map(|span| Span::styled(span.text.clone(), span.style))
-                .collect(),
-        )
+    pub fn cycle_search_case(&mut self) {
+        self.search_case = self.search_case.next();
+        self.update_search_regex();
     }
 
-    pub fn syntax_scope_target(&mut self, view: &[ViewLine]) -> Option<(usize, String)> {
-        if !self.show_syntax_scopes {
-            return None;
-        }
-        let step_direction = self.multi_diff.current_step_direction();
-        let (display_len, _) = display_metrics(
-            view,
-            self.view_mode,
-            self.animation_phase,
-            self.scroll_offset,
-            step_direction,
-        );
-        if display_len == 0 {
-            return None;
-        }
-        let viewport_height = self.last_viewport_height.max(1);
-        let target_idx = self.scroll_offset.saturating_add(viewport_height / 2);
-        let display_idx = target_idx.min(display_len.saturating_sub(1));
+    pub fn toggle_search_whole_word(&mut self) {
+        self.search_whole_word = !self.search_whole_word;
+        self.update_search_regex();
+    }
 
-        let (side, line_num) = self.syntax_line_for_display(view, display_idx)?;
-        let file_index = self.multi_diff.selected_index;
-        if let Some(cache) = &self.syntax_scope_cache {
-            if cache.file_index == file_index && cache.side == side && cache.line_num == line_num {
-                return Some((display_idx, cache.label.clone()));
-            }
-        }
-        let file_name = self.current_file_path();
-        let nav = self.multi_diff.current_navigator();
-        let content = match side {
-            SyntaxSide::Old => nav.old_content(),
-            SyntaxSide::New => nav.new_content(),
-        };
-        if self.syntax_engine.is_none() {
-            self.syntax_engine = Some(SyntaxEngine::new(&self.syntax_theme, self.theme_is_light));
-        }
-        let engine = self.syntax_engine.as_ref()?;
-        let scopes = engine.scopes_for_line(content, &file_name, line_num - 1);
-        let label = if scopes.is_empty() {
-            "scopes: (none)".to_string()
+    /// Current match and match count, for the "3/17" counter
+    pub fn search_counter(&self) -> Option<(Option<usize>, usize)> {
+        self.search_regex
+            .as_ref()
+            .map(|_| (self.search_position, self.search_total))
+    }
+
+    fn update_search_regex(&mut self) {
+        self.search_input.set_validator(
+            self.search_regex_mode
+                .then_some(validate_regex as Validator),
+        );
+        let query = self.search_input.text().trim();
+        self.search_regex = if query.is_empty() {
+            None
         } else {
-            format!("scopes: {}", scopes.join(" | "))
+            let case_insensitive = match self.search_case {
+                SearchCase::Smart => !query.chars().any(char::is_uppercase),
+                SearchCase::Sensitive => false,
+                SearchCase::Insensitive => true,
+            };
+            let whole_word = self.search_whole_word;
+            let build = |pattern: &str| {
+                let pattern = if whole_word {
+                    format!(r"\b(?:{pattern})\b")
+                } else {
+                    pattern.to_string()
+                };
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+            };
+            let literal = regex::escape(query);
+            // An incomplete regex (while typing) falls back to a literal match
+            let regex = if self.search_regex_mode {
+                build(query).or_else(|_| build(&literal))
+            } else {
+                build(&literal)
+            };
+            regex.ok()
         };
-        self.syntax_scope_cache = Some(SyntaxScopeCache {
-            file_index,
-            side,
-            line_num,
-            label: label.clone(),
-        });
-        Some((display_idx, label))
+        self.refresh_search_count();
     }
 
-    fn ensure_syntax_cache(&mut self) -> Option<&SyntaxCache> {
-        if !self.syntax_enabled() {
-            return None;
-        }
-        let idx = self.multi_diff.selected_index;
-        if idx >= self.syntax_caches.len() {
-            self.syntax_caches = vec![None; self.multi_diff.file_count()];
-        }
-        if self.syntax_caches[idx].is_none() {
-            let file_name = self.current_file_path();
-            let (old_content, new_content) = {
-                let nav = self.multi_diff.current_navigator();
-                (nav.old_content().to_string(), nav.new_content().to_string())
-            };
-            if self.syntax_engine.is_none() {
-                self.syntax_engine =
-                    Some(SyntaxEngine::new(&self.syntax_theme, self.theme_is_light));
-            }
-            let engine = self.syntax_engine.as_ref()?;
-            self.syntax_caches[idx] = Some(SyntaxCache::new(
-                engine,
-                &old_content,
-                &new_content,
-                &file_name,
-            ));
-        }
-        self.syntax_caches[idx].as_ref()
+    fn refresh_search_count(&mut self) {
+        self.search_position = None;
+        self.search_total = if self.search_regex.is_some() {
+            self.collect_search_matches().len()
+        } else {
+            0
+        };
     }
 
-    fn syntax_line_for_display(
-        &self,
-        view: &[ViewLine],
-        display_idx: usize,
-    ) -> Option<(SyntaxSide, usize)> {
-        match self.view_mode {
-            ViewMode::SinglePane => view.get(display_idx).and_then(|line| {
-                line.new_line.or(line.old_line).map(|line_num| {
-                    let side = if line.new_line.is_some() {
-                        SyntaxSide::New
-                    } else {
-                        SyntaxSide::Old
-                    };
-                    (side, line_num)
-                })
-            }),
-            ViewMode::Evolution => {
-                let mut display_count = 0usize;
-                for line in view {
-                    let visible = match line.kind {
-                        LineKind::Deleted => false,
-                        LineKind::PendingDelete => {
-                            line.is_active && self.animation_phase != AnimationPhase::Idle
-                        }
-                        _ => true,
-                    };
-                    if visible {
-                        if display_count == display_idx {
-                            let line_num = line.new_line.or(line.old_line)?;
-                            let side = if line.new_line.is_some() {
-                                SyntaxSide::New
-                            } else {
-                                SyntaxSide::Old
-                            };
-                            return Some((side, line_num));
-                        }
-                        display_count += 1;
-                    }
-                }
-                None
-            }
-            ViewMode::Split => {
-                let mut old_count = 0usize;
-                let mut new_count = 0usize;
-                let mut old_line = None;
-                let mut new_line = None;
+    fn set_search_target(&mut self, target: usize, matches: &[usize]) {
+        self.search_last_target = Some(target);
+        self.search_target = Some(target);
+        self.needs_scroll_to_search = true;
+        self.search_position = matches.iter().position(|&idx| idx == target).map(|p| p + 1);
+        self.search_total = matches.len();
+    }
 
-                for line in view {
-                    if line.old_line.is_some() {
-                        if old_count == display_idx {
-                            old_line = line.old_line;
-                        }
-                        old_count += 1;
-                    }
-                    if line.new_line.is_some() {
-                        if new_count == display_idx {
-                            new_line = line.new_line;
-                        }
-                        new_count += 1;
-                    }
-                    if new_line.is_some() || (old_count > display_idx && new_count > display_idx) {
-                        break;
-                    }
-                }
+    /// Jump to the first (or last) match in the next (or previous) file that has one
+    fn search_other_file(&mut self, forward: bool) -> bool {
+        let Some(regex) = self.search_regex.clone() else {
+            return false;
+        };
+        let current = self.multi_diff.selected_index;
+        let mut indices = self.filtered_file_indices();
+        let split = indices.partition_point(|&idx| idx <= current);
+        indices.rotate_left(split);
+        indices.retain(|&idx| idx != current);
+        if !forward {
+            indices.reverse();
+        }
 
-                if let Some(line_num) = new_line {
-                    Some((SyntaxSide::New, line_num))
-                } else {
-                    old_line.map(|line_num| (SyntaxSide::Old, line_num))
-                }
+        for idx in indices {
+            let has_match = self
+                .multi_diff
+                .file_contents(idx)
+                .is_some_and(|(old, new)| regex.is_match(old) || regex.is_match(new));
+            if !has_match {
+                continue;
+            }
+            self.select_file(idx);
+            let matches = self.collect_search_matches();
+            let target = if forward {
+                matches.first()
+            } else {
+                matches.last()
+            };
+            if let Some(&target) = target {
+                self.set_search_target(target, &matches);
+                return true;
             }
         }
+        if self.multi_diff.selected_index != current {
+            self.select_file(current);
+        }
+        false
     }
 
-    pub fn next_step(&mut self) {
-        self.step_forward();
+    pub fn search_target(&self) -> Option<usize> {
+        self.search_target
     }
 
-    pub fn prev_step(&mut self) {
-        self.step_backward();
+    /// Jump to the next match, continuing into the next file with a match
+    /// before wrapping around
+    pub fn search_next(&mut self) {
+        let matches = self.collect_search_matches();
+        let start = self.search_last_target.unwrap_or(self.scroll_offset);
+        if let Some(target) = matches.iter().copied().find(|idx| *idx > start) {
+            self.set_search_target(target, &matches);
+            return;
+        }
+        if self.search_other_file(true) {
+            return;
+        }
+        if let Some(&target) = matches.first() {
+            self.set_search_target(target, &matches);
+        }
     }
 
-    fn step_forward(&mut self) -> bool {
-        self.clear_peek();
-        self.snap_frame = None;
-        self.snap_frame_started_at = None;
-        if self.multi_diff.current_navigator().next() {
-            if self.animation_enabled {
-                self.start_animation();
-            }
-            self.needs_scroll_to_active = true;
-            true
-        } else {
-            false
+    /// Jump to the previous match, continuing into the previous file with a
+    /// match before wrapping around
+    pub fn search_prev(&mut self) {
+        let matches = self.collect_search_matches();
+        let start = self.search_last_target.unwrap_or(self.scroll_offset);
+        if let Some(target) = matches.iter().copied().rev().find(|idx| *idx < start) {
+            self.set_search_target(target, &matches);
+            return;
+        }
+        if self.search_other_file(false) {
+            return;
+        }
+        if let Some(&target) = matches.last() {
+            self.set_search_target(target, &matches);
         }
     }
 
-    fn step_backward(&mut self) -> bool {
-        self.clear_peek();
-        self.snap_frame = None;
-        self.snap_frame_started_at = None;
-        if !self.animation_enabled {
-            self.snap_frame = Some(AnimationFrame::FadeOut);
-            self.snap_frame_started_at = Some(Instant::now());
-            self.clear_active_on_next_render = false;
+    pub fn apply_goto(&mut self) {
+        let query = self.goto_input.text().trim();
+        if query.is_empty() {
+            return;
         }
-        if self.multi_diff.current_navigator().prev() {
-            if self.animation_enabled {
-                self.start_animation();
-            } else if self.snap_frame.is_none() {
-                self.clear_active_on_next_render = true;
+        if query == "jumps" {
+            self.open_jump_list();
+            return;
+        }
+        if query == "settings" {
+            self.open_settings();
+            return;
+        }
+        if query == "log" {
+            self.open_log();
+            return;
+        }
+        if query == "messages" {
+            self.open_messages();
+            return;
+        }
+        if query == "timings" {
+            self.show_timings = !self.show_timings;
+            return;
+        }
+        if let Some(line) = query.strip_prefix("place ") {
+            match line.trim().parse::<usize>() {
+                Ok(line) if line > 0 => self.place_reject(line - 1),
+                _ => self.notify(Level::Error, format!("invalid line: {}", line.trim())),
             }
-            self.needs_scroll_to_active = true;
-            true
-        } else {
-            false
+            return;
         }
-    }
-
-    /// Compute hunk starts for single/evolution view (display index + change id).
-    fn compute_hunk_starts_single(&mut self) -> Vec<Option<HunkStart>> {
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(AnimationFrame::Idle);
-        let (_, total_hunks) = self.hunk_info();
-
-        let mut hunk_starts = vec![None; total_hunks];
-        let mut display_idx = 0;
-
-        for line in &view {
-            let is_visible = match self.view_mode {
-                ViewMode::Evolution => {
-                    !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+        if let Some(steps) = query.strip_prefix("compare ") {
+            let steps: Vec<Option<usize>> = steps
+                .split_whitespace()
+                .map(|step| step.parse().ok())
+                .collect();
+            match steps[..] {
+                [Some(left)] => {
+                    let current = self.multi_diff.current_navigator().state().current_step;
+                    self.open_step_compare(left, current);
                 }
-                _ => true,
-            };
-            if !is_visible {
-                continue;
+                [Some(left), Some(right)] => self.open_step_compare(left, right),
+                _ => self.notify(Level::Error, "usage: :compare <step> [<step>]"),
             }
-            if let Some(hidx) = line.hunk_index {
-                if hidx < total_hunks && hunk_starts[hidx].is_none() {
-                    hunk_starts[hidx] = Some(HunkStart {
-                        idx: display_idx,
-                        change_id: Some(line.change_id),
-                    });
-                }
+            return;
+        }
+        if let Some(name) = query.strip_prefix("bucket ") {
+            let name = name.trim().to_string();
+            self.select_bucket(&name);
+            return;
+        }
+        if let Some(args) = query.strip_prefix("verdict ") {
+            let args = args.trim().to_string();
+            self.record_verdict(&args);
+            return;
+        }
+        if let Some(text) = query.strip_prefix("reply ") {
+            let text = text.trim().to_string();
+            self.request_reply(text);
+            return;
+        }
+        match query {
+            "preview" => return self.open_split_preview(),
+            "merged" => return self.open_merge_preview(),
+            "threads" => return self.toggle_review_panel(),
+            "commit" => {
+                let question = format!("Create {} commits from the buckets?", self.buckets.len());
+                return self.ask(ConfirmAction::CommitBuckets, question);
             }
-            display_idx += 1;
+            "collapse" => return self.set_all_hunks_collapsed(true),
+            "expand" => return self.set_all_hunks_collapsed(false),
+            "rejects" => return self.open_patch_rejects(),
+            "apply" => {
+                let Some((file_idx, idx)) = self.current_reject() else {
+                    return self.apply_reject();
+                };
+                let reject = &self.reject_files[file_idx];
+                let question = format!("Write hunk {} into {}?", idx + 1, reject.target.display());
+                return self.ask(ConfirmAction::ApplyReject, question);
+            }
+            "scrub" => {
+                self.show_scrub_bar = !self.show_scrub_bar;
+                return;
+            }
+            "header" => {
+                self.show_file_header = !self.show_file_header;
+                return;
+            }
+            "breadcrumbs" => {
+                self.show_breadcrumbs = !self.show_breadcrumbs;
+                return;
+            }
+            "permalink" => return self.copy_permalink(),
+            "browse" => return self.open_in_forge(),
+            "present" => return self.toggle_presentation(),
+            _ => {}
+        }
+        if let Some(name) = query.strip_prefix("action ") {
+            let name = name.trim().to_string();
+            self.run_action(&name);
+            return;
         }
-        hunk_starts
-    }
-
-    /// Compute hunk bounds for single/evolution view (display start/end + change id).
-    fn compute_hunk_bounds_single(&mut self) -> Vec<Option<HunkBounds>> {
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(AnimationFrame::Idle);
-        let (_, total_hunks) = self.hunk_info();
+        let (name, args) = query.split_once(' ').unwrap_or((query, ""));
+        if self.plugins.run_command(name, args.trim()) {
+            return;
+        }
+        self.history.record_jump(Jump {
+            file: self.current_file_path(),
+            query: query.to_string(),
+        });
+        let query = self.goto_input.text().trim();
 
-        let mut bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
-        let mut display_idx = 0;
+        let mut chars = query.chars();
+        let first = match chars.next() {
+            Some(ch) => ch,
+            None => return,
+        };
 
-        for line in &view {
-            let is_visible = match self.view_mode {
-                ViewMode::Evolution => {
-                    !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+        match first {
+            'h' | 'H' => {
+                let rest = chars
+                    .as_str()
+                    .trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+                if let Ok(num) = rest.parse::<usize>() {
+                    self.goto_hunk_number(num);
                 }
-                _ => true,
-            };
-            if !is_visible {
-                continue;
             }
-            if let Some(hidx) = line.hunk_index {
-                if hidx < total_hunks {
-                    let start = HunkStart {
-                        idx: display_idx,
-                        change_id: Some(line.change_id),
-                    };
-                    if let Some(existing) = bounds[hidx] {
-                        bounds[hidx] = Some(HunkBounds {
-                            start: existing.start,
-                            end: start,
-                        });
-                    } else {
-                        bounds[hidx] = Some(HunkBounds { start, end: start });
+            's' | 'S' => {
+                let rest = chars
+                    .as_str()
+                    .trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+                if let Ok(num) = rest.parse::<usize>() {
+                    self.goto_step_number(num);
+                }
+            }
+            _ => {
+                if query.chars().all(|c| c.is_ascii_digit()) {
+                    if let Ok(num) = query.parse::<usize>() {
+                        self.goto_line_number(num);
                     }
                 }
             }
-            display_idx += 1;
         }
-        bounds
     }
 
-    /// Compute hunk starts for split view (per-pane display index + change id).
-    fn compute_hunk_starts_split(&mut self) -> (Vec<Option<HunkStart>>, Vec<Option<HunkStart>>) {
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(AnimationFrame::Idle);
-        let (_, total_hunks) = self.hunk_info();
+    pub fn highlight_search_spans(
+        &self,
+        spans: Vec<Span<'static>>,
+        text: &str,
+        is_active: bool,
+    ) -> Vec<Span<'static>> {
+        let Some(regex) = self.search_regex.as_ref() else {
+            return spans;
+        };
+        let ranges = match_ranges(text, regex);
+        if ranges.is_empty() {
+            return spans;
+        }
+        let highlight_bg = if is_active {
+            self.theme.accent
+        } else {
+            color::dim_color(self.theme.accent)
+        };
+        let highlight_fg = if is_active {
+            self.search_highlight_fg(highlight_bg)
+        } else {
+            None
+        };
+        apply_highlight_spans(spans, &ranges, highlight_bg, highlight_fg)
+    }
 
-        let mut old_starts = vec![None; total_hunks];
-        let mut new_starts = vec![None; total_hunks];
-        let mut old_idx = 0usize;
-        let mut new_idx = 0usize;
+    /// Mask secrets in a rendered line when redaction is on
+    pub fn redact_spans(&self, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+        match &self.redactor {
+            Some(redactor) => redactor.redact_spans(spans),
+            None => spans,
+        }
+    }
 
-        for line in &view {
-            if line.old_line.is_some() {
-                if let Some(hidx) = line.hunk_index {
-                    if hidx < total_hunks && old_starts[hidx].is_none() {
-                        old_starts[hidx] = Some(HunkStart {
-                            idx: old_idx,
-                            change_id: Some(line.change_id),
-                        });
+    /// Mask secrets in exported text when redaction is on
+    fn redact_text(&self, text: String) -> String {
+        match &self.redactor {
+            Some(redactor) => redactor.redact(&text),
+            None => text,
+        }
+    }
+
+    /// New-side line of the active change (old-side if it has none)
+    pub fn active_line(&mut self) -> Option<usize> {
+        let frame = self.animation_frame();
+        self.multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame)
+            .iter()
+            .find(|line| line.is_primary_active)
+            .and_then(|line| line.new_line.or(line.old_line))
+    }
+
+    /// Run scripted action `name` and apply what it asked for
+    pub fn run_action(&mut self, name: &str) {
+        if self.actions.is_none() {
+            self.notify(Level::Warning, "No actions configured");
+            return;
+        }
+        let state = self.multi_diff.current_navigator().state();
+        let (step, steps) = (state.current_step + 1, state.total_steps);
+        let snapshot = actions::Snapshot {
+            file: self.current_file_path(),
+            file_index: self.multi_diff.selected_index,
+            files: self
+                .multi_diff
+                .files
+                .iter()
+                .map(|file| {
+                    let status = format!("{:?}", file.status).to_ascii_lowercase();
+                    (file.path.display().to_string(), status)
+                })
+                .collect(),
+            step,
+            steps,
+            line: self.active_line().unwrap_or(0),
+            root: self.multi_diff.repo_root().map(Path::to_path_buf),
+            branch: self.git_branch.clone(),
+        };
+        let effects = match self
+            .actions
+            .as_ref()
+            .map(|actions| actions.run(name, snapshot))
+        {
+            Some(Ok(effects)) => effects,
+            Some(Err(err)) => {
+                self.notify(Level::Error, format!("action {name}: {err}"));
+                return;
+            }
+            None => return,
+        };
+        for effect in effects {
+            match effect {
+                Effect::Copy(text) => {
+                    if copy_to_clipboard(&self.redact_text(text)) {
+                        self.notify(Level::Success, "Copied");
                     }
                 }
-                old_idx += 1;
-            }
-            if line.new_line.is_some() {
-                if let Some(hidx) = line.hunk_index {
-                    if hidx < total_hunks && new_starts[hidx].is_none() {
-                        new_starts[hidx] = Some(HunkStart {
-                            idx: new_idx,
-                            change_id: Some(line.change_id),
-                        });
+                Effect::Notify(text) => self.notify(Level::Info, text),
+                Effect::OpenFile(index) if index < self.multi_diff.file_count() => {
+                    self.select_file(index);
+                }
+                Effect::OpenFile(_) => {}
+                Effect::OpenPath(path) => {
+                    if let Some(index) = self
+                        .multi_diff
+                        .files
+                        .iter()
+                        .position(|file| file.path == Path::new(&path))
+                    {
+                        self.select_file(index);
                     }
                 }
-                new_idx += 1;
+                Effect::NextFile => self.next_file(),
+                Effect::PrevFile => self.prev_file(),
+                Effect::GotoLine(line) => self.goto_line_number(line),
+                Effect::GotoStep(step) => self.goto_step_number(step),
             }
         }
-
-        (old_starts, new_starts)
     }
 
-    /// Compute hunk bounds for split view (per-pane display start/end + change id).
-    fn compute_hunk_bounds_split(&mut self) -> (Vec<Option<HunkBounds>>, Vec<Option<HunkBounds>>) {
-        let view = self
+    pub fn yank_current_change(&mut self) {
+        let frame = self.animation_frame();
+        let view_lines = self
             .multi_diff
             .current_navigator()
-            .current_view_with_frame(AnimationFrame::Idle);
-        let (_, total_hunks) = self.hunk_info();
-
-        let mut old_bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
-        let mut new_bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
-        let mut old_idx = 0usize;
-        let mut new_idx = 0usize;
+            .current_view_with_frame(frame);
+        let Some(line) = view_lines.iter().find(|line| line.is_primary_active) else {
+            return;
+        };
+        if let Some(text) = self.text_for_yank(line) {
+            copy_to_clipboard(&self.redact_text(text));
+        }
+    }
 
-        for line in &view {
-            if line.old_line.is_some() {
-                if let Some(hidx) = line.hunk_index {
-                    if hidx < total_hunks {
-                        let start = HunkStart {
-                            idx: old_idx,
-                            change_id: Some(line.change_id),
-                        };
-                        if let Some(existing) = old_bounds[hidx] {
-                            old_bounds[hidx] = Some(HunkBounds {
-                                start: existing.start,
-                                end: start,
-                            });
-                        } else {
-                            old_bounds[hidx] = Some(HunkBounds { start, end: start });
-                        }
-                    }
-                }
-                old_idx += 1;
-            }
-            if line.new_line.is_some() {
-                if let Some(hidx) = line.hunk_index {
-                    if hidx < total_hunks {
-                        let start = HunkStart {
-                            idx: new_idx,
-                            change_id: Some(line.change_id),
-                        };
-                        if let Some(existing) = new_bounds[hidx] {
-                            new_bounds[hidx] = Some(HunkBounds {
-                                start: existing.start,
-                                end: start,
-                            });
-                        } else {
-                            new_bounds[hidx] = Some(HunkBounds { start, end: start });
-                        }
-                    }
-                }
-                new_idx += 1;
+    fn search_highlight_fg(&self, bg: Color) -> Option<Color> {
+        let text = self.theme.text;
+        let mut best_color = text;
+        let mut best_ratio = color::contrast_ratio(bg, text).unwrap_or(0.0);
+        if let Some(bg_color) = self.theme.background {
+            let ratio = color::contrast_ratio(bg, bg_color).unwrap_or(0.0);
+            if ratio > best_ratio {
+                best_ratio = ratio;
+                best_color = bg_color;
             }
         }
+        if best_ratio == 0.0 {
+            None
+        } else {
+            Some(best_color)
+        }
+    }
 
-        (old_bounds, new_bounds)
+    pub fn yank_current_hunk(&mut self) {
+        let frame = self.animation_frame();
+        let view_lines = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame);
+        let current_hunk = self.multi_diff.current_navigator().state().current_hunk;
+        let mut lines: Vec<String> = Vec::new();
+        for line in view_lines
+            .iter()
+            .filter(|line| line.hunk_index == Some(current_hunk))
+        {
+            if let Some(text) = self.text_for_yank(line) {
+                lines.push(text);
+            }
+        }
+        if lines.is_empty() {
+            return;
+        }
+        copy_to_clipboard(&self.redact_text(lines.join("\n")));
     }
 
-    fn pick_split_start(
-        &self,
-        old: Option<HunkStart>,
-        new: Option<HunkStart>,
-    ) -> Option<HunkStart> {
-        match (old, new) {
-            (Some(o), Some(n)) => {
-                let old_dist = (o.idx as isize - self.scroll_offset as isize).abs();
-                let new_dist = (n.idx as isize - self.scroll_offset as isize).abs();
-                if old_dist < new_dist {
-                    Some(o)
+    /// Forge URL of the active line of the current file at the commit of
+    /// the new side (its nearest new-side line for a deletion)
+    fn permalink(&mut self) -> Result<String, String> {
+        let revision = self
+            .multi_diff
+            .file_revision(self.multi_diff.selected_index)
+            .ok_or("Permalinks need a git repository")?;
+        let frame = self.animation_frame();
+        let view_lines = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame);
+        let active = view_lines
+            .iter()
+            .position(|line| line.is_primary_active)
+            .unwrap_or(0);
+        // Lines of the side that is in the commit, so that uncommitted
+        // edits don't shift the link
+        let line = view_lines
+            .iter()
+            .take(active + 1)
+            .rev()
+            .find_map(|line| {
+                if revision.old_side {
+                    line.old_line
                 } else {
-                    Some(n)
+                    line.new_line
+                }
+            })
+            .unwrap_or(1);
+        let repo = &revision.repo;
+        let url = oyo_core::git::get_remote_url(repo, "origin").map_err(|err| err.to_string())?;
+        let commit =
+            oyo_core::git::resolve_commit(repo, &revision.rev).map_err(|err| err.to_string())?;
+        forge::permalink(&url, &self.forge_remotes, &commit, &revision.path, line)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Copy the forge permalink of the active line
+    pub fn copy_permalink(&mut self) {
+        match self.permalink() {
+            Ok(url) => {
+                if copy_to_clipboard(&url) {
+                    self.notify(Level::Success, format!("Copied {url}"));
+                } else {
+                    self.notify(Level::Warning, format!("No clipboard; {url}"));
                 }
             }
-            (Some(o), None) => Some(o),
-            (None, Some(n)) => Some(n),
-            (None, None) => None,
+            Err(err) => self.notify(Level::Error, format!("permalink: {}", err.trim())),
         }
     }
 
-    fn pick_split_index(&self, old: Option<usize>, new: Option<usize>) -> Option<usize> {
-        match (old, new) {
-            (Some(o), Some(n)) => {
-                let old_dist = (o as isize - self.scroll_offset as isize).abs();
-                let new_dist = (n as isize - self.scroll_offset as isize).abs();
-                if old_dist < new_dist {
-                    Some(o)
+    /// Open the forge permalink of the active line in the browser
+    pub fn open_in_forge(&mut self) {
+        match self.permalink() {
+            Ok(url) => {
+                if open_in_browser(&url) {
+                    self.notify(Level::Info, format!("Opened {url}"));
                 } else {
-                    Some(n)
+                    self.notify(Level::Warning, format!("No browser opener; {url}"));
                 }
             }
-            (Some(o), None) => Some(o),
-            (None, Some(n)) => Some(n),
-            (None, None) => None,
+            Err(err) => self.notify(Level::Error, format!("browse: {}", err.trim())),
         }
     }
 
-    fn pick_split_bounds(
-        &self,
-        old: Option<HunkBounds>,
-        new: Option<HunkBounds>,
-    ) -> Option<HunkBounds> {
-        match (old, new) {
-            (Some(o), Some(n)) => {
-                let old_dist = (o.start.idx as isize - self.scroll_offset as isize).abs();
-                let new_dist = (n.start.idx as isize - self.scroll_offset as isize).abs();
-                if old_dist < new_dist {
-                    Some(o)
-                } else {
-                    Some(n)
+    fn text_for_yank(&mut self, view_line: &ViewLine) -> Option<String> {
+        if let Some(mode) = self.peek_mode_for_line(view_line) {
+            match mode {
+                PeekMode::Old => {
+                    if let Some(text) = self.peek_text_for_line(view_line) {
+                        return Some(text);
+                    }
+                }
+                PeekMode::Modified => {
+                    if let Some(text) = self.modified_only_text_for_line(view_line) {
+                        return Some(text);
+                    }
+                }
+                PeekMode::Mixed => {
+                    let change = self
+                        .multi_diff
+                        .current_navigator()
+                        .diff()
+                        .changes
+                        .get(view_line.change_id);
+                    if let Some(change) = change {
+                        let text = inline_text_for_change(change);
+                        if !text.is_empty() {
+                            return Some(text);
+                        }
+                    }
                 }
             }
-            (Some(o), None) => Some(o),
-            (None, Some(n)) => Some(n),
-            (None, None) => None,
         }
+        Some(view_line.content.to_string())
     }
 
-    fn next_hunk_from_starts(
-        &self,
-        starts: &[Option<HunkStart>],
-        inclusive: bool,
-    ) -> Option<(usize, HunkStart)> {
-        let current_hunk_idx = starts
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, start)| start.map(|s| (idx, s.idx)))
-            .filter(|&(_, start)| {
-                if inclusive {
-                    start <= self.scroll_offset
-                } else {
-                    start < self.scroll_offset
-                }
-            })
-            .map(|(idx, _)| idx)
-            .last();
+    fn peek_text_for_line(&mut self, view_line: &ViewLine) -> Option<String> {
+        if !matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
+            return None;
+        }
+        let change = self
+            .multi_diff
+            .current_navigator()
+            .diff()
+            .changes
+            .get(view_line.change_id)?;
+        let text = old_text_for_change(change);
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
 
-        let mut target_idx = match current_hunk_idx {
-            Some(curr) => curr + 1,
-            None => 0,
+    fn modified_only_text_for_line(&mut self, view_line: &ViewLine) -> Option<String> {
+        if !matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
+            return None;
+        }
+        let change = self
+            .multi_diff
+            .current_navigator()
+            .diff()
+            .changes
+            .get(view_line.change_id)?;
+        let text = modified_only_text_for_change(change);
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
+    fn collect_search_matches(&mut self) -> Vec<usize> {
+        let regex = match self.search_regex.as_ref() {
+            Some(regex) => regex.clone(),
+            None => return Vec::new(),
         };
+        let frame = self.animation_frame();
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame);
+        let mut matches = Vec::new();
 
-        while target_idx < starts.len() {
-            if let Some(start) = starts[target_idx] {
-                return Some((target_idx, start));
+        match self.view_mode {
+            ViewMode::SinglePane => {
+                for (display_idx, line) in view.iter().enumerate() {
+                    let text = self.search_text_single(line);
+                    if line_has_query(&text, &regex) {
+                        matches.push(display_idx);
+                    }
+                }
+            }
+            ViewMode::Evolution => {
+                let mut display_idx = 0usize;
+                for line in &view {
+                    let visible = match line.kind {
+                        LineKind::Deleted => false,
+                        LineKind::PendingDelete => {
+                            line.is_active && self.animation_phase != AnimationPhase::Idle
+                        }
+                        _ => true,
+                    };
+                    if !visible {
+                        continue;
+                    }
+                    let text = self.search_text_single(line);
+                    if line_has_query(&text, &regex) {
+                        matches.push(display_idx);
+                    }
+                    display_idx += 1;
+                }
+            }
+            ViewMode::Split => {
+                let mut old_idx = 0usize;
+                let mut new_idx = 0usize;
+                for line in &view {
+                    if line.old_line.is_some() {
+                        if let Some(text) = self.search_text_split_old(line) {
+                            if line_has_query(&text, &regex) {
+                                matches.push(old_idx);
+                            }
+                        }
+                        old_idx += 1;
+                    }
+                    if line.new_line.is_some()
+                        && !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+                    {
+                        if let Some(text) = self.search_text_split_new(line) {
+                            if line_has_query(&text, &regex) {
+                                matches.push(new_idx);
+                            }
+                        }
+                        new_idx += 1;
+                    }
+                }
             }
-            target_idx += 1;
         }
-        None
+
+        matches.sort_unstable();
+        matches
     }
 
-    fn next_hunk_from_index(
-        &self,
-        starts: &[Option<HunkStart>],
-        current_hunk: usize,
-    ) -> Option<(usize, HunkStart)> {
-        let mut target_idx = current_hunk.saturating_add(1);
-        while target_idx < starts.len() {
-            if let Some(start) = starts[target_idx] {
-                return Some((target_idx, start));
+    fn search_text_single(&mut self, view_line: &ViewLine) -> String {
+        if let Some(mode) = self.peek_mode_for_line(view_line) {
+            match mode {
+                PeekMode::Old => {
+                    if let Some(text) = self.peek_text_for_line(view_line) {
+                        return text;
+                    }
+                }
+                PeekMode::Modified => {
+                    if let Some(text) = self.modified_only_text_for_line(view_line) {
+                        return text;
+                    }
+                }
+                PeekMode::Mixed => {
+                    if let Some(change) = self
+                        .multi_diff
+                        .current_navigator()
+                        .diff()
+                        .changes
+                        .get(view_line.change_id)
+                    {
+                        let text = inline_text_for_change(change);
+                        if !text.is_empty() {
+                            return text;
+                        }
+                    }
+                }
             }
-            target_idx += 1;
         }
-        None
+        if !self.stepping && matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify)
+        {
+            if let Some(change) = self
+                .multi_diff
+                .current_navigator()
+                .diff()
+                .changes
+                .get(view_line.change_id)
+            {
+                let text = inline_text_for_change(change);
+                if !text.is_empty() {
+                    return text;
+                }
+            }
+        }
+        view_line.content.to_string()
     }
 
-    fn single_hunk_fallback(&self, starts: &[Option<HunkStart>]) -> Option<(usize, HunkStart)> {
-        let mut only: Option<(usize, HunkStart)> = None;
-        for (idx, start) in starts.iter().enumerate() {
-            if let Some(start) = start {
-                if only.is_some() {
-                    return None;
+    fn search_text_split_old(&mut self, view_line: &ViewLine) -> Option<String> {
+        view_line.old_line?;
+        if matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify) {
+            if let Some(change) = self
+                .multi_diff
+                .current_navigator()
+                .diff()
+                .changes
+                .get(view_line.change_id)
+            {
+                let text = old_text_for_change(change);
+                if !text.is_empty() {
+                    return Some(text);
                 }
-                only = Some((idx, *start));
             }
         }
-        only
+        Some(view_line.content.to_string())
     }
 
-    fn prev_hunk_from_starts(&self, starts: &[Option<HunkStart>]) -> Option<(usize, HunkStart)> {
-        starts
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, start)| start.map(|s| (idx, s)))
-            .filter(|&(_, start)| start.idx < self.scroll_offset)
-            .last()
+    fn search_text_split_new(&mut self, view_line: &ViewLine) -> Option<String> {
+        view_line.new_line?;
+        Some(view_line.content.to_string())
     }
 
-    fn prev_hunk_from_index(
-        &self,
-        starts: &[Option<HunkStart>],
-        current_hunk: usize,
-    ) -> Option<(usize, HunkStart)> {
-        if starts.is_empty() {
-            return None;
-        }
-        let mut idx = current_hunk.min(starts.len() - 1);
-        while idx > 0 {
+    pub fn state(&mut self) -> StepState {
+        self.multi_diff.current_navigator().state().clone()
+    }
+
+    pub fn syntax_enabled(&self) -> bool {
+        match self.syntax_mode {
+            SyntaxMode::On => true,
+            SyntaxMode::Off => false,
+        }
+    }
+
+    /// Line `line_num` (1-based) of one side, highlighted when syntax
+    /// highlighting is on
+    pub fn side_line_spans(
+        &mut self,
+        side: SyntaxSide,
+        line_num: usize,
+    ) -> Option<Vec<Span<'static>>> {
+        let spans = match self.syntax_spans_for_line(side, Some(line_num)) {
+            Some(spans) => spans,
+            None => {
+                let navigator = self.multi_diff.current_navigator();
+                let content = match side {
+                    SyntaxSide::Old => navigator.old_content(),
+                    SyntaxSide::New => navigator.new_content(),
+                };
+                let text = content.lines().nth(line_num.checked_sub(1)?)?;
+                vec![Span::raw(text.to_string())]
+            }
+        };
+        Some(self.redact_spans(spans))
+    }
+
+    pub fn syntax_spans_for_line(
+        &mut self,
+        side: SyntaxSide,
+        line_num: Option<usize>,
+    ) -> Option<Vec<Span<'static>>> {
+        if !self.syntax_enabled() {
+            return None;
+        }
+        let line_num = line_num?;
+        if line_num == 0 {
+            return None;
+        }
+        let cache = self.ensure_syntax_cache(side)?;
+        let spans = cache.spans(side, line_num - 1)?;
+        Some(
+            spans
+                .iter()
+                .map(|span| Span::styled(span.text.clone(), span.style))
+                .collect(),
+        )
+    }
+
+    /// Whether the scopes of the middle line are shown (`:scopes`)
+    pub fn syntax_scopes_shown(&self) -> bool {
+        self.show_syntax_scopes
+    }
+
+    pub fn syntax_scope_target(&mut self, view: &[ViewLine]) -> Option<(usize, String)> {
+        if !self.show_syntax_scopes {
+            return None;
+        }
+        let step_direction = self.multi_diff.current_step_direction();
+        let (display_len, _) = display_metrics(
+            view,
+            self.view_mode,
+            self.animation_phase,
+            self.scroll_offset,
+            step_direction,
+        );
+        if display_len == 0 {
+            return None;
+        }
+        let viewport_height = self.last_viewport_height.max(1);
+        let target_idx = self.scroll_offset.saturating_add(viewport_height / 2);
+        let display_idx = target_idx.min(display_len.saturating_sub(1));
+
+        let (side, line_num) = self.syntax_line_for_display(view, display_idx)?;
+        let file_index = self.multi_diff.selected_index;
+        if let Some(cache) = &self.syntax_scope_cache {
+            if cache.file_index == file_index && cache.side == side && cache.line_num == line_num {
+                return Some((display_idx, cache.label.clone()));
+            }
+        }
+        let file_name = self.current_file_path();
+        let nav = self.multi_diff.current_navigator();
+        let content = match side {
+            SyntaxSide::Old => nav.old_content(),
+            SyntaxSide::New => nav.new_content(),
+        };
+        if self.syntax_engine.is_none() {
+            self.syntax_engine = Some(SyntaxEngine::new(&self.syntax_theme, self.theme_is_light));
+        }
+        let engine = self.syntax_engine.as_ref()?;
+        let scopes = engine.scopes_for_line(content, &file_name, line_num - 1);
+        let label = if scopes.is_empty() {
+            "scopes: (none)".to_string()
+        } else {
+            format!("scopes: {}", scopes.join(" | "))
+        };
+        self.syntax_scope_cache = Some(SyntaxScopeCache {
+            file_index,
+            side,
+            line_num,
+            label: label.clone(),
+        });
+        Some((display_idx, label))
+    }
+
+    /// Syntax cache of the current file with `side` highlighted
+    fn ensure_syntax_cache(&mut self, side: SyntaxSide) -> Option<&SyntaxCache> {
+        if !self.syntax_enabled() {
+            return None;
+        }
+        let idx = self.multi_diff.selected_index;
+        if idx >= self.syntax_caches.len() {
+            self.syntax_caches = vec![None; self.multi_diff.file_count()];
+        }
+        if self.syntax_engine.is_none() {
+            self.syntax_engine = Some(SyntaxEngine::new(&self.syntax_theme, self.theme_is_light));
+        }
+        let file_name = self.current_file_path();
+        let nav = self.multi_diff.current_navigator();
+        let content = match side {
+            SyntaxSide::Old => nav.old_content(),
+            SyntaxSide::New => nav.new_content(),
+        };
+        let engine = self.syntax_engine.as_ref()?;
+        let cache = self.syntax_caches[idx].get_or_insert_with(SyntaxCache::default);
+        cache.ensure(engine, side, content, &file_name);
+        Some(cache)
+    }
+
+    /// Re-highlight one side (or both with `None`) of the current file on
+    /// next render
+    pub fn invalidate_syntax(&mut self, side: Option<SyntaxSide>) {
+        let idx = self.multi_diff.selected_index;
+        let Some(Some(cache)) = self.syntax_caches.get_mut(idx) else {
+            return;
+        };
+        match side {
+            Some(side) => cache.invalidate(side),
+            None => self.syntax_caches[idx] = None,
+        }
+    }
+
+    fn syntax_line_for_display(
+        &self,
+        view: &[ViewLine],
+        display_idx: usize,
+    ) -> Option<(SyntaxSide, usize)> {
+        match self.view_mode {
+            ViewMode::SinglePane => view.get(display_idx).and_then(|line| {
+                line.new_line.or(line.old_line).map(|line_num| {
+                    let side = if line.new_line.is_some() {
+                        SyntaxSide::New
+                    } else {
+                        SyntaxSide::Old
+                    };
+                    (side, line_num)
+                })
+            }),
+            ViewMode::Evolution => {
+                let mut display_count = 0usize;
+                for line in view {
+                    let visible = match line.kind {
+                        LineKind::Deleted => false,
+                        LineKind::PendingDelete => {
+                            line.is_active && self.animation_phase != AnimationPhase::Idle
+                        }
+                        _ => true,
+                    };
+                    if visible {
+                        if display_count == display_idx {
+                            let line_num = line.new_line.or(line.old_line)?;
+                            let side = if line.new_line.is_some() {
+                                SyntaxSide::New
+                            } else {
+                                SyntaxSide::Old
+                            };
+                            return Some((side, line_num));
+                        }
+                        display_count += 1;
+                    }
+                }
+                None
+            }
+            ViewMode::Split => {
+                let mut old_count = 0usize;
+                let mut new_count = 0usize;
+                let mut old_line = None;
+                let mut new_line = None;
+
+                for line in view {
+                    if line.old_line.is_some() {
+                        if old_count == display_idx {
+                            old_line = line.old_line;
+                        }
+                        old_count += 1;
+                    }
+                    if line.new_line.is_some() {
+                        if new_count == display_idx {
+                            new_line = line.new_line;
+                        }
+                        new_count += 1;
+                    }
+                    if new_line.is_some() || (old_count > display_idx && new_count > display_idx) {
+                        break;
+                    }
+                }
+
+                if let Some(line_num) = new_line {
+                    Some((SyntaxSide::New, line_num))
+                } else {
+                    old_line.map(|line_num| (SyntaxSide::Old, line_num))
+                }
+            }
+        }
+    }
+
+    pub fn next_step(&mut self) {
+        self.step_filtered(true);
+    }
+
+    pub fn prev_step(&mut self) {
+        self.step_filtered(false);
+    }
+
+    /// Side that introduced a change (three-way `--base` mode)
+    pub fn change_origin(&mut self, view_line: &ViewLine) -> Option<ChangeOrigin> {
+        if view_line.kind == LineKind::Context || !self.multi_diff.has_base() {
+            return None;
+        }
+        self.multi_diff
+            .current_navigator()
+            .change_origin(view_line.change_id)
+    }
+
+    /// Line-number color marking which side introduced a change (three-way
+    /// `--base` mode): ours, theirs, or both
+    pub fn origin_color(&mut self, view_line: &ViewLine) -> Option<Color> {
+        self.change_origin(view_line)
+            .map(|origin| self.origin_theme_color(origin))
+    }
+
+    /// Most severe diagnostic on a line of the new version, and how many
+    /// diagnostics the line has
+    pub fn line_diagnostic(&self, view_line: &ViewLine) -> Option<(&Diagnostic, usize)> {
+        if (self.diagnostics.is_empty() && self.plugins.is_empty()) || self.multi_diff.is_reversed()
+        {
+            return None;
+        }
+        let line = view_line.new_line?;
+        let path = &self
+            .multi_diff
+            .files
+            .get(self.multi_diff.selected_index)?
+            .path;
+        let mut on_line = self
+            .diagnostics
+            .iter()
+            .chain(self.plugins.annotations())
+            .filter(|diag| diag.line == line && diag.is_for(path));
+        let first = on_line.next()?;
+        let (worst, count) = on_line.fold((first, 1), |(worst, count), diag| {
+            let worst = if diag.severity > worst.severity {
+                diag
+            } else {
+                worst
+            };
+            (worst, count + 1)
+        });
+        Some((worst, count))
+    }
+
+    pub fn origin_theme_color(&self, origin: ChangeOrigin) -> Color {
+        match origin {
+            ChangeOrigin::Ours => self.theme.info,
+            ChangeOrigin::Theirs => self.theme.accent,
+            ChangeOrigin::Both => self.theme.warning,
+        }
+    }
+
+    /// Name of a side, using the ref names of an in-progress merge if known
+    pub fn origin_label(&self, origin: ChangeOrigin) -> String {
+        match (&self.merge_labels, origin) {
+            (Some(labels), ChangeOrigin::Ours) => format!("current: {}", labels.current),
+            (Some(labels), ChangeOrigin::Theirs) => format!("incoming: {}", labels.incoming),
+            (None, ChangeOrigin::Ours) => "ours".to_string(),
+            (None, ChangeOrigin::Theirs) => "theirs".to_string(),
+            (_, ChangeOrigin::Both) => "both".to_string(),
+        }
+    }
+
+    pub fn toggle_occurrences(&mut self) {
+        self.show_occurrences = !self.show_occurrences;
+        self.occurrences = None;
+    }
+
+    /// Recompute the changed-word patterns if the active change moved
+    pub fn refresh_occurrences(&mut self) {
+        if !self.show_occurrences {
+            self.occurrences = None;
+            return;
+        }
+        let file = self.multi_diff.selected_index;
+        let nav = self.multi_diff.current_navigator();
+        let state = nav.state();
+        let change_id = state
+            .active_change
+            .or(state.cursor_change)
+            .or_else(|| state.applied_changes.last().copied());
+        let Some(change_id) = change_id else {
+            self.occurrences = None;
+            return;
+        };
+        if self
+            .occurrences
+            .as_ref()
+            .is_some_and(|occ| occ.file == file && occ.change_id == change_id)
+        {
+            return;
+        }
+        self.occurrences = nav
+            .diff()
+            .changes
+            .get(change_id)
+            .map(|change| Occurrences::for_change(file, change));
+    }
+
+    /// Highlight occurrences of the active change's old words (`Old` side) and
+    /// new words (`New` side); `None` highlights both
+    pub fn highlight_occurrence_spans(
+        &self,
+        spans: Vec<Span<'static>>,
+        text: &str,
+        side: Option<SyntaxSide>,
+    ) -> Vec<Span<'static>> {
+        let Some(occ) = self.occurrences.as_ref() else {
+            return spans;
+        };
+        let mut spans = spans;
+        if side != Some(SyntaxSide::New) {
+            if let Some(regex) = occ.old.as_ref() {
+                let ranges = match_ranges(text, regex);
+                spans =
+                    apply_highlight_spans(spans, &ranges, color::dim_color(self.theme.error), None);
+            }
+        }
+        if side != Some(SyntaxSide::Old) {
+            if let Some(regex) = occ.new.as_ref() {
+                let ranges = match_ranges(text, regex);
+                spans = apply_highlight_spans(
+                    spans,
+                    &ranges,
+                    color::dim_color(self.theme.success),
+                    None,
+                );
+            }
+        }
+        spans
+    }
+
+    /// Token replacement done by the focused change, if it is a rename
+    pub fn focused_rename(&mut self) -> Option<(String, String)> {
+        let nav = self.multi_diff.current_navigator();
+        let state = nav.state();
+        let change_id = state
+            .active_change
+            .or(state.cursor_change)
+            .or_else(|| state.applied_changes.last().copied())?;
+        nav.diff().changes.get(change_id)?.token_replacement()
+    }
+
+    /// How many changes across the changeset do the focused change's rename
+    pub fn rename_sites(&mut self) -> Option<RenameSites> {
+        let rename = self.focused_rename()?;
+        if self
+            .rename_counts
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != rename)
+        {
+            let counts = self.multi_diff.replacement_counts(&rename.0, &rename.1);
+            self.rename_counts = Some((rename.clone(), counts));
+        }
+        let (_, counts) = self.rename_counts.as_ref()?;
+        let (old, new) = rename;
+        Some(RenameSites {
+            old,
+            new,
+            in_file: counts
+                .get(self.multi_diff.selected_index)
+                .copied()
+                .unwrap_or(0),
+            total: counts.iter().sum(),
+            files: counts.iter().filter(|&&count| count > 0).count(),
+        })
+    }
+
+    /// Step only through changes doing the focused change's rename, or
+    /// stop doing so
+    pub fn toggle_rename_filter(&mut self) {
+        if self.rename_filter.take().is_some() {
+            return;
+        }
+        self.rename_filter = self.focused_rename();
+    }
+
+    /// Cycle the kind filter: deletions, insertions, modifications, off
+    pub fn cycle_kind_filter(&mut self) {
+        self.kind_filter = match self.kind_filter {
+            None => Some(ChangeKind::Delete),
+            Some(ChangeKind::Delete) => Some(ChangeKind::Insert),
+            Some(ChangeKind::Insert) => Some(ChangeKind::Replace),
+            Some(_) => None,
+        };
+        let shown = match self.kind_filter {
+            Some(ChangeKind::Delete) => "only deletions",
+            Some(ChangeKind::Insert) => "only insertions",
+            Some(_) => "only modifications",
+            None => "all changes",
+        };
+        self.notify(Level::Info, shown);
+    }
+
+    /// True if the change shown on `view_line` is hidden by the kind filter
+    pub fn filtered_out(&mut self, view_line: &ViewLine) -> bool {
+        let Some(kind) = self.kind_filter else {
+            return false;
+        };
+        if matches!(view_line.kind, LineKind::Context | LineKind::Collapsed) {
+            return false;
+        }
+        self.multi_diff
+            .current_navigator()
+            .diff()
+            .changes
+            .get(view_line.change_id)
+            .is_some_and(|change| change.has_changes() && change.edit_kind() != kind)
+    }
+
+    pub fn toggle_search_filter_steps(&mut self) {
+        self.search_filter_steps = !self.search_filter_steps;
+    }
+
+    /// True if stepping is currently restricted to search matches
+    pub fn steps_filtered(&self) -> bool {
+        self.search_filter_steps && self.search_regex.is_some()
+    }
+
+    /// Step once, or with filtered stepping on, jump to the next change
+    /// (or back past the previous change) of the filtered kind, doing the
+    /// filtered rename or whose text matches the search
+    fn step_filtered(&mut self, forward: bool) -> bool {
+        let regex = self
+            .search_regex
+            .clone()
+            .filter(|_| self.search_filter_steps);
+        let kind = self.kind_filter;
+        let rename = self.rename_filter.clone();
+        if kind.is_none() && rename.is_none() && regex.is_none() {
+            return if forward {
+                self.step_forward()
+            } else {
+                self.step_backward()
+            };
+        }
+        let step = self.matching_step(
+            |change| {
+                kind.is_none_or(|kind| change.edit_kind() == kind)
+                    && match (&rename, &regex) {
+                        (Some((old, new)), _) => change
+                            .token_replacement()
+                            .is_some_and(|(from, to)| &from == old && &to == new),
+                        (None, Some(regex)) => {
+                            regex.is_match(&old_text_for_change(change))
+                                || regex.is_match(&modified_only_text_for_change(change))
+                        }
+                        (None, None) => true,
+                    }
+            },
+            forward,
+        );
+        let Some(step) = step else {
+            return false;
+        };
+        if forward {
+            self.multi_diff.current_navigator().goto(step - 1);
+            self.step_forward()
+        } else {
+            self.multi_diff.current_navigator().goto(step);
+            self.step_backward()
+        }
+    }
+
+    /// Nearest step (after the current one, or at/before it going backward)
+    /// whose change satisfies `matches`
+    fn matching_step(&mut self, matches: impl Fn(&Change) -> bool, forward: bool) -> Option<usize> {
+        let nav = self.multi_diff.current_navigator();
+        let current = nav.state().current_step;
+        let diff = nav.diff();
+        let step_matches = |step: usize| {
+            diff.significant_changes
+                .get(step - 1)
+                .and_then(|&id| diff.changes.get(id))
+                .is_some_and(&matches)
+        };
+        if forward {
+            (current + 1..=diff.significant_changes.len()).find(|&step| step_matches(step))
+        } else {
+            (1..=current).rev().find(|&step| step_matches(step))
+        }
+    }
+
+    fn step_forward(&mut self) -> bool {
+        self.clear_peek();
+        self.snap_frame = None;
+        self.snap_frame_started_at = None;
+        if self.multi_diff.current_navigator().next() {
+            if self.animations_active() {
+                self.start_animation();
+            }
+            self.needs_scroll_to_active = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn step_backward(&mut self) -> bool {
+        self.clear_peek();
+        self.snap_frame = None;
+        self.snap_frame_started_at = None;
+        if !self.animation_enabled && !self.reduced_motion {
+            self.snap_frame = Some(AnimationFrame::FadeOut);
+            self.snap_frame_started_at = Some(Instant::now());
+            self.clear_active_on_next_render = false;
+        }
+        if self.multi_diff.current_navigator().prev() {
+            if self.animations_active() {
+                self.start_animation();
+            } else if self.snap_frame.is_none() {
+                self.clear_active_on_next_render = true;
+            }
+            self.needs_scroll_to_active = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Compute hunk starts for single/evolution view (display index + change id).
+    fn compute_hunk_starts_single(&mut self) -> Vec<Option<HunkStart>> {
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(AnimationFrame::Idle);
+        let (_, total_hunks) = self.hunk_info();
+
+        let mut hunk_starts = vec![None; total_hunks];
+        let mut display_idx = 0;
+
+        for line in &view {
+            let is_visible = match self.view_mode {
+                ViewMode::Evolution => {
+                    !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+                }
+                _ => true,
+            };
+            if !is_visible {
+                continue;
+            }
+            if let Some(hidx) = line.hunk_index {
+                if hidx < total_hunks && hunk_starts[hidx].is_none() {
+                    hunk_starts[hidx] = Some(HunkStart {
+                        idx: display_idx,
+                        change_id: Some(line.change_id),
+                    });
+                }
+            }
+            display_idx += 1;
+        }
+        hunk_starts
+    }
+
+    /// Compute hunk bounds for single/evolution view (display start/end + change id).
+    fn compute_hunk_bounds_single(&mut self) -> Vec<Option<HunkBounds>> {
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(AnimationFrame::Idle);
+        let (_, total_hunks) = self.hunk_info();
+
+        let mut bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
+        let mut display_idx = 0;
+
+        for line in &view {
+            let is_visible = match self.view_mode {
+                ViewMode::Evolution => {
+                    !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+                }
+                _ => true,
+            };
+            if !is_visible {
+                continue;
+            }
+            if let Some(hidx) = line.hunk_index {
+                if hidx < total_hunks {
+                    let start = HunkStart {
+                        idx: display_idx,
+                        change_id: Some(line.change_id),
+                    };
+                    if let Some(existing) = bounds[hidx] {
+                        bounds[hidx] = Some(HunkBounds {
+                            start: existing.start,
+                            end: start,
+                        });
+                    } else {
+                        bounds[hidx] = Some(HunkBounds { start, end: start });
+                    }
+                }
+            }
+            display_idx += 1;
+        }
+        bounds
+    }
+
+    /// Compute hunk starts for split view (per-pane display index + change id).
+    fn compute_hunk_starts_split(&mut self) -> (Vec<Option<HunkStart>>, Vec<Option<HunkStart>>) {
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(AnimationFrame::Idle);
+        let (_, total_hunks) = self.hunk_info();
+
+        let mut old_starts = vec![None; total_hunks];
+        let mut new_starts = vec![None; total_hunks];
+        let mut old_idx = 0usize;
+        let mut new_idx = 0usize;
+
+        for line in &view {
+            if line.old_line.is_some() {
+                if let Some(hidx) = line.hunk_index {
+                    if hidx < total_hunks && old_starts[hidx].is_none() {
+                        old_starts[hidx] = Some(HunkStart {
+                            idx: old_idx,
+                            change_id: Some(line.change_id),
+                        });
+                    }
+                }
+                old_idx += 1;
+            }
+            if line.new_line.is_some() {
+                if let Some(hidx) = line.hunk_index {
+                    if hidx < total_hunks && new_starts[hidx].is_none() {
+                        new_starts[hidx] = Some(HunkStart {
+                            idx: new_idx,
+                            change_id: Some(line.change_id),
+                        });
+                    }
+                }
+                new_idx += 1;
+            }
+        }
+
+        (old_starts, new_starts)
+    }
+
+    /// Compute hunk bounds for split view (per-pane display start/end + change id).
+    fn compute_hunk_bounds_split(&mut self) -> (Vec<Option<HunkBounds>>, Vec<Option<HunkBounds>>) {
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(AnimationFrame::Idle);
+        let (_, total_hunks) = self.hunk_info();
+
+        let mut old_bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
+        let mut new_bounds: Vec<Option<HunkBounds>> = vec![None; total_hunks];
+        let mut old_idx = 0usize;
+        let mut new_idx = 0usize;
+
+        for line in &view {
+            if line.old_line.is_some() {
+                if let Some(hidx) = line.hunk_index {
+                    if hidx < total_hunks {
+                        let start = HunkStart {
+                            idx: old_idx,
+                            change_id: Some(line.change_id),
+                        };
+                        if let Some(existing) = old_bounds[hidx] {
+                            old_bounds[hidx] = Some(HunkBounds {
+                                start: existing.start,
+                                end: start,
+                            });
+                        } else {
+                            old_bounds[hidx] = Some(HunkBounds { start, end: start });
+                        }
+                    }
+                }
+                old_idx += 1;
+            }
+            if line.new_line.is_some() {
+                if let Some(hidx) = line.hunk_index {
+                    if hidx < total_hunks {
+                        let start = HunkStart {
+                            idx: new_idx,
+                            change_id: Some(line.change_id),
+                        };
+                        if let Some(existing) = new_bounds[hidx] {
+                            new_bounds[hidx] = Some(HunkBounds {
+                                start: existing.start,
+                                end: start,
+                            });
+                        } else {
+                            new_bounds[hidx] = Some(HunkBounds { start, end: start });
+                        }
+                    }
+                }
+                new_idx += 1;
+            }
+        }
+
+        (old_bounds, new_bounds)
+    }
+
+    fn pick_split_start(
+        &self,
+        old: Option<HunkStart>,
+        new: Option<HunkStart>,
+    ) -> Option<HunkStart> {
+        match (old, new) {
+            (Some(o), Some(n)) => {
+                let old_dist = (o.idx as isize - self.scroll_offset as isize).abs();
+                let new_dist = (n.idx as isize - self.scroll_offset as isize).abs();
+                if old_dist < new_dist {
+                    Some(o)
+                } else {
+                    Some(n)
+                }
+            }
+            (Some(o), None) => Some(o),
+            (None, Some(n)) => Some(n),
+            (None, None) => None,
+        }
+    }
+
+    fn pick_split_index(&self, old: Option<usize>, new: Option<usize>) -> Option<usize> {
+        match (old, new) {
+            (Some(o), Some(n)) => {
+                let old_dist = (o as isize - self.scroll_offset as isize).abs();
+                let new_dist = (n as isize - self.scroll_offset as isize).abs();
+                if old_dist < new_dist {
+                    Some(o)
+                } else {
+                    Some(n)
+                }
+            }
+            (Some(o), None) => Some(o),
+            (None, Some(n)) => Some(n),
+            (None, None) => None,
+        }
+    }
+
+    fn pick_split_bounds(
+        &self,
+        old: Option<HunkBounds>,
+        new: Option<HunkBounds>,
+    ) -> Option<HunkBounds> {
+        match (old, new) {
+            (Some(o), Some(n)) => {
+                let old_dist = (o.start.idx as isize - self.scroll_offset as isize).abs();
+                let new_dist = (n.start.idx as isize - self.scroll_offset as isize).abs();
+                if old_dist < new_dist {
+                    Some(o)
+                } else {
+                    Some(n)
+                }
+            }
+            (Some(o), None) => Some(o),
+            (None, Some(n)) => Some(n),
+            (None, None) => None,
+        }
+    }
+
+    fn next_hunk_from_starts(
+        &self,
+        starts: &[Option<HunkStart>],
+        inclusive: bool,
+    ) -> Option<(usize, HunkStart)> {
+        let current_hunk_idx = starts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, start)| start.map(|s| (idx, s.idx)))
+            .filter(|&(_, start)| {
+                if inclusive {
+                    start <= self.scroll_offset
+                } else {
+                    start < self.scroll_offset
+                }
+            })
+            .map(|(idx, _)| idx)
+            .next_back();
+
+        let mut target_idx = match current_hunk_idx {
+            Some(curr) => curr + 1,
+            None => 0,
+        };
+
+        while target_idx < starts.len() {
+            if let Some(start) = starts[target_idx] {
+                return Some((target_idx, start));
+            }
+            target_idx += 1;
+        }
+        None
+    }
+
+    fn next_hunk_from_index(
+        &self,
+        starts: &[Option<HunkStart>],
+        current_hunk: usize,
+    ) -> Option<(usize, HunkStart)> {
+        let mut target_idx = current_hunk.saturating_add(1);
+        while target_idx < starts.len() {
+            if let Some(start) = starts[target_idx] {
+                return Some((target_idx, start));
+            }
+            target_idx += 1;
+        }
+        None
+    }
+
+    fn single_hunk_fallback(&self, starts: &[Option<HunkStart>]) -> Option<(usize, HunkStart)> {
+        let mut only: Option<(usize, HunkStart)> = None;
+        for (idx, start) in starts.iter().enumerate() {
+            if let Some(start) = start {
+                if only.is_some() {
+                    return None;
+                }
+                only = Some((idx, *start));
+            }
+        }
+        only
+    }
+
+    fn prev_hunk_from_starts(&self, starts: &[Option<HunkStart>]) -> Option<(usize, HunkStart)> {
+        starts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, start)| start.map(|s| (idx, s)))
+            .rfind(|&(_, start)| start.idx < self.scroll_offset)
+    }
+
+    fn prev_hunk_from_index(
+        &self,
+        starts: &[Option<HunkStart>],
+        current_hunk: usize,
+    ) -> Option<(usize, HunkStart)> {
+        if starts.is_empty() {
+            return None;
+        }
+        let mut idx = current_hunk.min(starts.len() - 1);
+        while idx > 0 {
             idx -= 1;
             if let Some(start) = starts[idx] {
                 return Some((idx, start));
             }
         }
-        None
+        None
+    }
+
+    fn current_hunk_from_bounds(&self, bounds: &[Option<HunkBounds>]) -> Option<usize> {
+        bounds.iter().enumerate().rev().find_map(|(idx, bound)| {
+            bound.and_then(|b| (b.start.idx <= self.scroll_offset).then_some(idx))
+        })
+    }
+
+    fn first_available_hunk(bounds: &[Option<HunkBounds>]) -> Option<(usize, HunkBounds)> {
+        bounds
+            .iter()
+            .enumerate()
+            .find_map(|(idx, bound)| bound.map(|b| (idx, b)))
+    }
+
+    fn set_cursor_for_current_scroll(&mut self) {
+        let target = match self.view_mode {
+            ViewMode::Split => {
+                let (old_starts, new_starts) = self.compute_hunk_starts_split();
+                let effective: Vec<Option<HunkStart>> = old_starts
+                    .into_iter()
+                    .zip(new_starts)
+                    .map(|(old, new)| self.pick_split_start(old, new))
+                    .collect();
+                effective.iter().enumerate().rev().find_map(|(idx, start)| {
+                    start.and_then(|s| (s.idx <= self.scroll_offset).then_some((idx, s)))
+                })
+            }
+            _ => {
+                let hunk_starts = self.compute_hunk_starts_single();
+                hunk_starts
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find_map(|(idx, start)| {
+                        start.and_then(|s| (s.idx <= self.scroll_offset).then_some((idx, s)))
+                    })
+            }
+        };
+
+        if let Some((hidx, start)) = target {
+            self.multi_diff
+                .current_navigator()
+                .set_cursor_hunk(hidx, start.change_id);
+        } else {
+            self.multi_diff.current_navigator().clear_cursor_change();
+        }
+    }
+
+    /// Scroll to the next hunk (no-step mode)
+    pub fn next_hunk_scroll(&mut self) {
+        let auto_center = self.auto_center;
+        let (current_hunk, cursor_set) = {
+            let state = self.multi_diff.current_navigator().state();
+            (state.current_hunk, state.cursor_change.is_some())
+        };
+        let in_hunk_scope = self
+            .multi_diff
+            .current_navigator()
+            .state()
+            .last_nav_was_hunk;
+        let use_cursor = auto_center && cursor_set && in_hunk_scope;
+        let inclusive = in_hunk_scope;
+        let target = match self.view_mode {
+            ViewMode::Split => {
+                let (old_starts, new_starts) = self.compute_hunk_starts_split();
+                let effective: Vec<Option<HunkStart>> = old_starts
+                    .into_iter()
+                    .zip(new_starts)
+                    .map(|(old, new)| self.pick_split_start(old, new))
+                    .collect();
+                let mut target = if use_cursor && current_hunk < effective.len() {
+                    self.next_hunk_from_index(&effective, current_hunk)
+                } else {
+                    self.next_hunk_from_starts(&effective, inclusive)
+                };
+                if target.is_none() {
+                    target = self.single_hunk_fallback(&effective);
+                }
+                target
+            }
+            _ => {
+                let hunk_starts = self.compute_hunk_starts_single();
+                let mut target = if use_cursor && current_hunk < hunk_starts.len() {
+                    self.next_hunk_from_index(&hunk_starts, current_hunk)
+                } else {
+                    self.next_hunk_from_starts(&hunk_starts, inclusive)
+                };
+                if target.is_none() {
+                    target = self.single_hunk_fallback(&hunk_starts);
+                }
+                target
+            }
+        };
+
+        if let Some((hidx, start)) = target {
+            self.scroll_offset = start.idx;
+            self.centered_once = false;
+            self.multi_diff
+                .current_navigator()
+                .set_cursor_hunk(hidx, start.change_id);
+            self.multi_diff.current_navigator().set_hunk_scope(true);
+            if self.auto_center {
+                self.needs_scroll_to_active = true;
+            }
+        }
+    }
+
+    /// Scroll to the previous hunk (no-step mode)
+    pub fn prev_hunk_scroll(&mut self) {
+        let auto_center = self.auto_center;
+        let (current_hunk, cursor_set) = {
+            let state = self.multi_diff.current_navigator().state();
+            (state.current_hunk, state.cursor_change.is_some())
+        };
+        let in_hunk_scope = self
+            .multi_diff
+            .current_navigator()
+            .state()
+            .last_nav_was_hunk;
+        let use_cursor = auto_center && cursor_set && in_hunk_scope;
+        let target = match self.view_mode {
+            ViewMode::Split => {
+                let (old_starts, new_starts) = self.compute_hunk_starts_split();
+                let effective: Vec<Option<HunkStart>> = old_starts
+                    .into_iter()
+                    .zip(new_starts)
+                    .map(|(old, new)| self.pick_split_start(old, new))
+                    .collect();
+                let mut target = if use_cursor && current_hunk < effective.len() {
+                    self.prev_hunk_from_index(&effective, current_hunk)
+                } else {
+                    self.prev_hunk_from_starts(&effective)
+                };
+                if target.is_none() {
+                    target = self.single_hunk_fallback(&effective);
+                }
+                target
+            }
+            _ => {
+                let hunk_starts = self.compute_hunk_starts_single();
+                let mut target = if use_cursor && current_hunk < hunk_starts.len() {
+                    self.prev_hunk_from_index(&hunk_starts, current_hunk)
+                } else {
+                    self.prev_hunk_from_starts(&hunk_starts)
+                };
+                if target.is_none() {
+                    target = self.single_hunk_fallback(&hunk_starts);
+                }
+                target
+            }
+        };
+
+        if let Some((hidx, start)) = target {
+            self.scroll_offset = start.idx;
+            self.centered_once = false;
+            self.multi_diff
+                .current_navigator()
+                .set_cursor_hunk(hidx, start.change_id);
+            self.multi_diff.current_navigator().set_hunk_scope(true);
+            if self.auto_center {
+                self.needs_scroll_to_active = true;
+            }
+        }
+    }
+
+    /// Move to the next hunk (group of related changes)
+    pub fn next_hunk(&mut self) {
+        self.clear_peek();
+        if self.multi_diff.current_navigator().next_hunk() {
+            if self.animations_active() {
+                self.start_animation();
+            }
+            self.needs_scroll_to_active = true;
+        }
+    }
+
+    /// Move to the previous hunk (group of related changes)
+    pub fn prev_hunk(&mut self) {
+        self.clear_peek();
+        if self.multi_diff.current_navigator().prev_hunk() {
+            if self.animations_active() {
+                self.start_animation();
+            } else {
+                self.clear_active_on_next_render = true;
+            }
+            self.needs_scroll_to_active = true;
+        }
+    }
+
+    /// Get current hunk info (current hunk index, total hunks)
+    pub fn hunk_info(&mut self) -> (usize, usize) {
+        let state = self.multi_diff.current_navigator().state();
+        (state.current_hunk + 1, state.total_hunks) // 1-indexed for display
+    }
+
+    pub fn hunk_step_info(&mut self) -> Option<(usize, usize)> {
+        let nav = self.multi_diff.current_navigator();
+        let state = nav.state();
+        let hunk = nav.current_hunk()?;
+        let total = hunk.change_ids.len();
+        if total == 0 {
+            return None;
+        }
+        let mut applied = 0usize;
+        for id in &hunk.change_ids {
+            if state.applied_changes.contains(id) {
+                applied += 1;
+            }
+        }
+        Some((applied, total))
+    }
+
+    pub fn pending_insert_only_in_current_hunk(&mut self) -> usize {
+        let nav = self.multi_diff.current_navigator();
+        let state = nav.state();
+        let hunk = match nav.current_hunk() {
+            Some(hunk) => hunk,
+            None => return 0,
+        };
+
+        let mut pending = 0usize;
+        for change_id in &hunk.change_ids {
+            if state.applied_changes.contains(change_id) {
+                continue;
+            }
+            if let Some(change) = nav.diff().changes.iter().find(|c| c.id == *change_id) {
+                let is_insert_only = change
+                    .spans
+                    .iter()
+                    .all(|span| span.kind == ChangeKind::Insert);
+                if is_insert_only {
+                    pending += 1;
+                }
+            }
+        }
+
+        pending
+    }
+
+    /// Jump to first change of current hunk
+    pub fn goto_hunk_start(&mut self) {
+        self.clear_peek();
+        if self.multi_diff.current_navigator().goto_hunk_start() {
+            if self.animations_active() {
+                self.start_animation();
+            }
+            self.needs_scroll_to_active = true;
+        }
+    }
+
+    /// Jump to the start of the current hunk (no-step mode)
+    pub fn goto_hunk_start_scroll(&mut self) {
+        let (current_hunk, in_hunk_scope) = {
+            let state = self.multi_diff.current_navigator().state();
+            (
+                state.current_hunk,
+                state.last_nav_was_hunk && state.cursor_change.is_some(),
+            )
+        };
+        let target = match self.view_mode {
+            ViewMode::Split => {
+                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
+                let effective: Vec<Option<HunkBounds>> = old_bounds
+                    .into_iter()
+                    .zip(new_bounds)
+                    .map(|(old, new)| self.pick_split_bounds(old, new))
+                    .collect();
+                let hidx = if in_hunk_scope {
+                    Some(current_hunk)
+                } else {
+                    self.current_hunk_from_bounds(&effective)
+                };
+                hidx.and_then(|idx| effective.get(idx).copied().flatten().map(|b| (idx, b)))
+                    .or_else(|| Self::first_available_hunk(&effective))
+            }
+            _ => {
+                let bounds = self.compute_hunk_bounds_single();
+                let hidx = if in_hunk_scope {
+                    Some(current_hunk)
+                } else {
+                    self.current_hunk_from_bounds(&bounds)
+                };
+                hidx.and_then(|idx| bounds.get(idx).copied().flatten().map(|b| (idx, b)))
+                    .or_else(|| Self::first_available_hunk(&bounds))
+            }
+        };
+
+        if let Some((hidx, bound)) = target {
+            self.scroll_offset = bound.start.idx;
+            self.centered_once = false;
+            self.multi_diff
+                .current_navigator()
+                .set_cursor_hunk(hidx, bound.start.change_id);
+            self.multi_diff.current_navigator().set_hunk_scope(true);
+            if self.auto_center {
+                self.needs_scroll_to_active = true;
+            }
+        }
+    }
+
+    /// Jump to last change of current hunk
+    pub fn goto_hunk_end(&mut self) {
+        self.clear_peek();
+        if self.multi_diff.current_navigator().goto_hunk_end() {
+            if self.animations_active() {
+                self.start_animation();
+            }
+            self.needs_scroll_to_active = true;
+        }
+    }
+
+    /// Jump to the end of the current hunk (no-step mode)
+    pub fn goto_hunk_end_scroll(&mut self) {
+        let (current_hunk, in_hunk_scope) = {
+            let state = self.multi_diff.current_navigator().state();
+            (
+                state.current_hunk,
+                state.last_nav_was_hunk && state.cursor_change.is_some(),
+            )
+        };
+        let target = match self.view_mode {
+            ViewMode::Split => {
+                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
+                let effective: Vec<Option<HunkBounds>> = old_bounds
+                    .into_iter()
+                    .zip(new_bounds)
+                    .map(|(old, new)| self.pick_split_bounds(old, new))
+                    .collect();
+                let hidx = if in_hunk_scope {
+                    Some(current_hunk)
+                } else {
+                    self.current_hunk_from_bounds(&effective)
+                };
+                hidx.and_then(|idx| effective.get(idx).copied().flatten().map(|b| (idx, b)))
+                    .or_else(|| Self::first_available_hunk(&effective))
+            }
+            _ => {
+                let bounds = self.compute_hunk_bounds_single();
+                let hidx = if in_hunk_scope {
+                    Some(current_hunk)
+                } else {
+                    self.current_hunk_from_bounds(&bounds)
+                };
+                hidx.and_then(|idx| bounds.get(idx).copied().flatten().map(|b| (idx, b)))
+                    .or_else(|| Self::first_available_hunk(&bounds))
+            }
+        };
+
+        if let Some((hidx, bound)) = target {
+            self.scroll_offset = bound.end.idx;
+            self.centered_once = false;
+            self.multi_diff
+                .current_navigator()
+                .set_cursor_hunk(hidx, bound.end.change_id);
+            self.multi_diff.current_navigator().set_hunk_scope(true);
+            if self.auto_center {
+                self.needs_scroll_to_active = true;
+            }
+        }
+    }
+
+    /// Enter no-step mode without changing scroll position.
+    pub fn enter_no_step_mode(&mut self) {
+        // Evolution mode requires stepping, so switch to Single view
+        if self.view_mode == ViewMode::Evolution {
+            self.view_mode = ViewMode::SinglePane;
+        }
+
+        self.peek_state = None;
+        self.multi_diff.current_navigator().goto_end();
+        self.multi_diff.current_navigator().clear_active_change();
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.needs_scroll_to_active = false;
+        let index = self.multi_diff.selected_index;
+        if !self.restore_no_step_state_snapshot(index) {
+            self.set_cursor_for_current_scroll();
+            self.multi_diff.current_navigator().set_hunk_scope(false);
+        }
+    }
+
+    pub fn toggle_stepping(&mut self) {
+        let current_index = self.multi_diff.selected_index;
+        if self.stepping {
+            // Turning OFF stepping: snapshot state and scroll, then enter no-step.
+            self.save_scroll_position_for(current_index);
+            self.save_step_state_snapshot(current_index);
+            self.step_peek_state = self.peek_state.take();
+            self.step_view_mode = self.view_mode;
+            self.stepping = false;
+            if !self.no_step_visited[current_index] {
+                self.scroll_offsets_no_step[current_index] = self.scroll_offset;
+                self.horizontal_scrolls_no_step[current_index] = self.horizontal_scroll;
+                self.no_step_visited[current_index] = true;
+            }
+            self.restore_scroll_position_for(current_index);
+            self.enter_no_step_mode();
+        } else {
+            // Turning ON stepping: restore snapshot and scroll.
+            self.save_no_step_state_snapshot(current_index);
+            self.save_scroll_position_for(current_index);
+            self.stepping = true;
+            self.peek_state = self.step_peek_state.take();
+            self.view_mode = self.step_view_mode;
+            if !self.restore_step_state_snapshot(current_index) {
+                self.goto_start();
+            }
+            self.restore_scroll_position_for(current_index);
+            self.animation_phase = AnimationPhase::Idle;
+            self.animation_progress = 1.0;
+            self.needs_scroll_to_active = false;
+        }
+    }
+
+    /// Whether step transitions animate (off in reduced-motion mode)
+    pub fn animations_active(&self) -> bool {
+        self.animation_enabled && !self.reduced_motion
+    }
+
+    pub fn toggle_animation(&mut self) {
+        self.animation_enabled = !self.animation_enabled;
+    }
+
+    pub fn toggle_fps_overlay(&mut self) {
+        self.show_fps = !self.show_fps;
+    }
+
+    /// Drop the rows cached for animation frames (on input and any change
+    /// made outside of the animation)
+    pub fn invalidate_rows(&mut self) {
+        self.render_epoch += 1;
+    }
+
+    /// Render state of the rows drawn `width` columns wide in `frame`:
+    /// rows are only cached during step animations, where they change no
+    /// more than the animation does
+    pub fn row_cache_key(&self, frame: AnimationFrame, width: usize) -> Option<u64> {
+        if self.animation_phase == AnimationPhase::Idle || self.line_wrap {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        (
+            self.render_epoch,
+            self.multi_diff.selected_index,
+            frame as u8,
+            self.animation_phase as u8,
+            self.is_backward_animation(),
+            self.horizontal_scroll,
+            width,
+        )
+            .hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Time the last syntax highlighting of the current file took
+    pub fn highlight_time(&self) -> Option<Duration> {
+        self.syntax_caches
+            .get(self.multi_diff.selected_index)?
+            .as_ref()?
+            .highlight_time
+    }
+
+    /// Estimated bytes held for the current file, for all files, and the
+    /// number of files whose diff is loaded
+    pub fn memory_estimates(&self) -> (usize, usize, usize) {
+        let files = self.multi_diff.file_count();
+        let current = self
+            .multi_diff
+            .memory_estimate(self.multi_diff.selected_index);
+        let total = (0..files)
+            .map(|index| self.multi_diff.memory_estimate(index))
+            .sum();
+        let loaded = (0..files)
+            .filter(|&index| self.multi_diff.is_loaded(index))
+            .count();
+        (current, total, loaded)
+    }
+
+    /// True while something changes on screen without input (animations, autoplay)
+    pub fn is_animating(&self) -> bool {
+        self.animation_phase != AnimationPhase::Idle
+            || self.snap_frame.is_some()
+            || self.clear_active_on_next_render
+            || self.scroll_animation.is_some()
+            || (self.stepping && self.autoplay)
+    }
+
+    pub fn toggle_zen(&mut self) {
+        self.zen_mode = !self.zen_mode;
+    }
+
+    /// Enter or leave presentation mode (`:present`)
+    pub fn toggle_presentation(&mut self) {
+        self.presentation = !self.presentation;
+        if self.presentation {
+            self.presentation_auto_center = self.auto_center;
+            self.auto_center = true;
+            self.needs_scroll_to_active = true;
+        } else {
+            self.auto_center = self.presentation_auto_center;
+            self.key_echo.clear();
+        }
+    }
+
+    /// Show a pressed key in presentation mode; repeats of the last key are
+    /// counted instead of listed
+    pub fn echo_key(&mut self, label: String) {
+        if !self.presentation {
+            return;
+        }
+        let now = Instant::now();
+        match self.key_echo.last_mut() {
+            Some((last, count, at)) if *last == label => {
+                *count += 1;
+                *at = now;
+            }
+            _ => {
+                self.key_echo.push((label, 1, now));
+                if self.key_echo.len() > KEY_ECHO_MAX {
+                    self.key_echo.remove(0);
+                }
+            }
+        }
+    }
+
+    /// Cycle the file list between path order, riskiest-first, (for
+    /// commit ranges) commit type groups and churn
+    pub fn toggle_file_sort(&mut self) {
+        let sort = match self.file_sort {
+            FileSortMode::Path => FileSortMode::Risk,
+            FileSortMode::Risk if self.multi_diff.is_commit_range() => FileSortMode::Commit,
+            FileSortMode::Risk | FileSortMode::Commit => FileSortMode::Churn,
+            FileSortMode::Churn => FileSortMode::Path,
+        };
+        self.set_file_sort(sort);
+    }
+
+    pub fn set_file_sort(&mut self, sort: FileSortMode) {
+        self.file_sort = sort;
+        self.apply_file_sort();
+        self.reset_file_states();
+        self.handle_file_enter();
+    }
+
+    fn apply_file_sort(&mut self) {
+        self.hunk_groups = None;
+        self.commit_type_groups = None;
+        match self.file_sort {
+            FileSortMode::Path => self.multi_diff.sort_files_by_path(),
+            FileSortMode::Risk => self.multi_diff.sort_files_by_risk(),
+            FileSortMode::Churn => self.multi_diff.sort_files_by_churn(),
+            FileSortMode::Commit => match self.multi_diff.commit_type_groups() {
+                Some(groups) => {
+                    self.multi_diff.sort_files_by_commit_type(&groups);
+                    self.commit_type_groups = Some(groups);
+                }
+                None => self.multi_diff.sort_files_by_path(),
+            },
+        }
+    }
+
+    pub fn toggle_review_panel(&mut self) {
+        if self.review.is_some() {
+            self.show_review_panel = !self.show_review_panel;
+        } else {
+            self.notify(Level::Warning, "no pull request to show threads of (oy pr)");
+        }
+    }
+
+    /// Review threads on the current file, each with the index of the hunk
+    /// it is anchored to: the hunk its line is in or within the context of
+    pub fn review_threads(&self) -> Vec<(&Thread, Option<usize>)> {
+        let (Some(review), Some(file)) = (&self.review, self.multi_diff.current_file()) else {
+            return Vec::new();
+        };
+        let diff = self
+            .multi_diff
+            .navigator(self.multi_diff.selected_index)
+            .map(|navigator| navigator.diff());
+        review
+            .threads
+            .iter()
+            .filter(|thread| thread.is_for(&file.path))
+            .map(|thread| {
+                let hunk = diff.and_then(|diff| thread_hunk(diff, thread));
+                (thread, hunk)
+            })
+            .collect()
+    }
+
+    /// Ask to post `text` as a reply to the first review thread on the
+    /// current hunk
+    fn request_reply(&mut self, text: String) {
+        let problem = match &self.review {
+            None => Some("no pull request to reply on (oy pr)".to_string()),
+            Some(review) if !review.replies => {
+                Some("replies are off; set `replies = true` under [forge]".to_string())
+            }
+            Some(review) if !review.forge.has_token() => Some(format!(
+                "replying needs a token in {}",
+                review.forge.api().token_hint()
+            )),
+            Some(_) => None,
+        };
+        if let Some(problem) = problem {
+            return self.notify(Level::Warning, problem);
+        }
+        let current_hunk = self.multi_diff.current_navigator().state().current_hunk;
+        let Some(thread) = self
+            .review_threads()
+            .into_iter()
+            .find(|(_, hunk)| *hunk == Some(current_hunk))
+            .map(|(thread, _)| thread)
+        else {
+            return self.notify(Level::Warning, "no review thread on this hunk");
+        };
+        let question = format!(
+            "Post reply to @{} on {}:{}?",
+            thread.comments[0].author,
+            thread.path.display(),
+            thread.line
+        );
+        self.pending_reply = Some((thread.id.clone(), text));
+        self.ask(ConfirmAction::PostReply, question);
+    }
+
+    fn post_reply(&mut self) {
+        let (Some((thread_id, text)), Some(review)) = (self.pending_reply.take(), &mut self.review)
+        else {
+            return;
+        };
+        let Some(thread) = review.threads.iter_mut().find(|t| t.id == thread_id) else {
+            return;
+        };
+        match review.forge.reply(&review.pr, thread, &text) {
+            Ok(comment) => {
+                thread.comments.push(comment);
+                self.notify(Level::Success, "reply posted");
+            }
+            Err(err) => self.notify(Level::Error, format!("reply failed: {err:#}")),
+        }
+    }
+
+    /// Save a review verdict (`approve`, `changes` or `comment`, then an
+    /// optional comment) on the reviewed commit as a git note
+    fn record_verdict(&mut self, args: &str) {
+        let (verdict, comment) = args.split_once(' ').unwrap_or((args, ""));
+        let Some(verdict) = Verdict::parse(verdict) else {
+            return self.notify(Level::Error, VERDICT_USAGE);
+        };
+        let (Some(info), Some(repo_root)) = (&self.commit_info, self.multi_diff.repo_root()) else {
+            return self.notify(Level::Warning, "no commit to review (open a commit range)");
+        };
+        let commit = info.id.clone();
+        let result = oyo_core::notes::reviewer(repo_root).and_then(|(reviewer, date)| {
+            let review = ReviewNote {
+                verdict,
+                reviewer,
+                date,
+                comment: comment.trim().to_string(),
+            };
+            oyo_core::notes::append(repo_root, &commit, &review).map(|()| review)
+        });
+        match result {
+            Ok(review) => {
+                self.commit_reviews.push(review);
+                self.show_commit_panel = true;
+                let message = format!(
+                    "{} saved to {}",
+                    verdict.label(),
+                    oyo_core::notes::NOTES_REF
+                );
+                self.notify(Level::Success, message);
+            }
+            Err(err) => self.notify(Level::Error, format!("saving the verdict failed: {err}")),
+        }
+    }
+
+    pub fn toggle_commit_panel(&mut self) {
+        if self.commit_info.is_some() {
+            self.show_commit_panel = !self.show_commit_panel;
+        }
+    }
+
+    pub fn help_scroll_up(&mut self) {
+        self.help_scroll = self.help_scroll.saturating_sub(1);
+    }
+
+    pub fn help_scroll_down(&mut self) {
+        if self.help_scroll < self.help_max_scroll {
+            self.help_scroll += 1;
+        }
+    }
+
+    pub fn handle_file_list_click(&mut self, column: u16, row: u16) -> bool {
+        if let Some((x, y, width, height)) = self.file_filter_area {
+            let end_x = x.saturating_add(width);
+            let end_y = y.saturating_add(height);
+            if column >= x && column < end_x && row >= y && row < end_y {
+                self.file_list_focused = true;
+                self.start_file_filter();
+                return true;
+            }
+        }
+
+        let (x, y, width, height) = match self.file_list_area {
+            Some(area) => area,
+            None => return false,
+        };
+        let end_x = x.saturating_add(width);
+        let end_y = y.saturating_add(height);
+        if column < x || column >= end_x || row < y || row >= end_y {
+            return false;
+        }
+
+        let item_start = y.saturating_add(1);
+        if row < item_start {
+            self.file_list_focused = true;
+            return true;
+        }
+
+        let row_idx = (row - item_start) as usize;
+        if let Some(Some(file_idx)) = self.file_list_rows.get(row_idx) {
+            self.file_list_focused = true;
+            self.select_file(*file_idx);
+            return true;
+        }
+
+        self.file_list_focused = true;
+        true
+    }
+
+    pub fn toggle_file_panel(&mut self) {
+        if self.file_panel_manually_set {
+            // Already manually controlled, just toggle
+            self.file_panel_visible = !self.file_panel_visible;
+        } else {
+            // First manual toggle
+            self.file_panel_manually_set = true;
+            if self.file_panel_auto_hidden {
+                // Panel was auto-hidden, show it
+                self.file_panel_visible = true;
+            } else {
+                // Panel was visible, hide it
+                self.file_panel_visible = false;
+            }
+        }
+        if !self.file_panel_visible {
+            self.file_list_focused = false;
+        }
+    }
+
+    /// Check if current animation is backward (un-applying a change)
+    pub fn is_backward_animation(&self) -> bool {
+        self.animation_phase != AnimationPhase::Idle
+            && self.multi_diff.current_step_direction() == StepDirection::Backward
+    }
+
+    /// Convert CLI animation phase to core AnimationFrame for phase-aware rendering
+    pub fn animation_frame(&self) -> AnimationFrame {
+        if let Some(frame) = self.snap_frame {
+            return frame;
+        }
+        // Force FadeOut for one-frame render when animation disabled,
+        // so backward insert-only changes produce ViewLines for extent markers
+        if self.clear_active_on_next_render {
+            return AnimationFrame::FadeOut;
+        }
+        match self.animation_phase {
+            AnimationPhase::Idle => AnimationFrame::Idle,
+            AnimationPhase::FadeOut => AnimationFrame::FadeOut,
+            AnimationPhase::FadeIn => AnimationFrame::FadeIn,
+        }
+    }
+
+    pub fn goto_start(&mut self) {
+        self.clear_peek();
+        if !self.stepping {
+            self.scroll_offset = 0;
+            self.centered_once = false;
+            self.needs_scroll_to_active = false;
+            self.multi_diff.current_navigator().clear_cursor_change();
+            self.multi_diff.current_navigator().set_hunk_scope(false);
+            return;
+        }
+        self.multi_diff.current_navigator().goto_start();
+        self.scroll_offset = 0;
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.centered_once = false;
+        self.needs_scroll_to_active = true;
+    }
+
+    pub fn goto_end(&mut self) {
+        self.clear_peek();
+        if !self.stepping {
+            self.scroll_offset = usize::MAX;
+            self.centered_once = false;
+            self.needs_scroll_to_active = false;
+            self.multi_diff.current_navigator().clear_cursor_change();
+            self.multi_diff.current_navigator().set_hunk_scope(false);
+            return;
+        }
+        self.multi_diff.current_navigator().goto_end();
+        self.scroll_offset = usize::MAX; // Will be clamped to bottom
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.centered_once = false;
+        // Don't set needs_scroll_to_active - we want to stay at bottom
     }
 
-    fn current_hunk_from_bounds(&self, bounds: &[Option<HunkBounds>]) -> Option<usize> {
-        bounds.iter().enumerate().rev().find_map(|(idx, bound)| {
-            bound.and_then(|b| (b.start.idx <= self.scroll_offset).then_some(idx))
-        })
+    pub fn goto_first_step(&mut self) {
+        self.clear_peek();
+        self.multi_diff.current_navigator().goto(1);
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.centered_once = false;
+        self.needs_scroll_to_active = true;
     }
 
-    fn first_available_hunk(bounds: &[Option<HunkBounds>]) -> Option<(usize, HunkBounds)> {
-        bounds
-            .iter()
-            .enumerate()
-            .find_map(|(idx, bound)| bound.map(|b| (idx, b)))
+    pub fn goto_last_step(&mut self) {
+        self.clear_peek();
+        self.multi_diff.current_navigator().goto_end();
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.centered_once = false;
+        self.needs_scroll_to_active = true;
     }
 
-    fn set_cursor_for_current_scroll(&mut self) {
+    fn goto_step_number(&mut self, step_number: usize) {
+        if !self.stepping {
+            return;
+        }
+        let total_steps = self.multi_diff.current_navigator().state().total_steps;
+        if total_steps == 0 {
+            return;
+        }
+        self.clear_peek();
+        let clamped = step_number.max(1).min(total_steps);
+        let target_step = clamped.saturating_sub(1);
+        self.multi_diff.current_navigator().goto(target_step);
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.centered_once = false;
+        self.needs_scroll_to_active = true;
+    }
+
+    /// Jump `percent` of the way through the steps (`{N}%`)
+    pub fn goto_step_percent(&mut self, percent: usize) {
+        let last_step = self
+            .multi_diff
+            .current_navigator()
+            .state()
+            .total_steps
+            .saturating_sub(1);
+        let target_step = (last_step * percent.min(100) + 50) / 100;
+        self.goto_step_number(target_step + 1);
+    }
+
+    /// Jump to the step under `column` if it is on the scrub bar
+    pub fn handle_scrub_click(&mut self, column: u16, row: u16) -> bool {
+        let Some((x, y, width, _)) = self.scrub_bar_area else {
+            return false;
+        };
+        if row != y || column < x || column >= x.saturating_add(width) || !self.stepping {
+            return false;
+        }
+        let last_step = self
+            .multi_diff
+            .current_navigator()
+            .state()
+            .total_steps
+            .saturating_sub(1);
+        let span = usize::from(width.saturating_sub(1)).max(1);
+        let target_step = (usize::from(column - x) * last_step + span / 2) / span;
+        self.goto_step_number(target_step + 1);
+        true
+    }
+
+    /// Move `cells` scrub bar cells forward (negative: backward)
+    pub fn scrub_by(&mut self, cells: isize) {
+        let Some((_, _, width, _)) = self.scrub_bar_area else {
+            return;
+        };
+        let state = self.multi_diff.current_navigator().state();
+        let last_step = state.total_steps.saturating_sub(1);
+        let per_cell = (last_step / usize::from(width.max(1))).max(1);
+        let target_step = state
+            .current_step
+            .saturating_add_signed(cells * per_cell as isize)
+            .min(last_step);
+        self.goto_step_number(target_step + 1);
+    }
+
+    /// Jump to the next of the 0/25/50/75/100% checkpoints, wrapping to the start
+    pub fn goto_next_checkpoint(&mut self) {
+        let state = self.multi_diff.current_navigator().state();
+        let last_step = state.total_steps.saturating_sub(1);
+        let current = state.current_step;
+        let percent = [25, 50, 75, 100]
+            .into_iter()
+            .find(|percent| (last_step * percent + 50) / 100 > current)
+            .unwrap_or(0);
+        self.goto_step_percent(percent);
+    }
+
+    pub fn goto_hunk_number(&mut self, hunk_number: usize) {
+        let total_hunks = self.multi_diff.current_navigator().state().total_hunks;
+        if total_hunks == 0 {
+            return;
+        }
+        let clamped = hunk_number.max(1).min(total_hunks);
+        let hunk_idx = clamped - 1;
+        if self.stepping {
+            self.goto_hunk_index(hunk_idx);
+        } else {
+            self.goto_hunk_index_scroll(hunk_idx);
+        }
+    }
+
+    fn goto_hunk_index(&mut self, hunk_idx: usize) {
+        self.clear_peek();
+        self.multi_diff.current_navigator().goto_hunk(hunk_idx);
+        if self.animations_active() {
+            self.start_animation();
+        } else {
+            self.clear_active_on_next_render = true;
+        }
+        self.needs_scroll_to_active = true;
+    }
+
+    fn goto_hunk_index_scroll(&mut self, hunk_idx: usize) {
         let target = match self.view_mode {
             ViewMode::Split => {
-                let (old_starts, new_starts) = self.compute_hunk_starts_split();
-                let effective: Vec<Option<HunkStart>> = old_starts
-                    .into_iter()
-                    .zip(new_starts)
-                    .map(|(old, new)| self.pick_split_start(old, new))
-                    .collect();
-                effective.iter().enumerate().rev().find_map(|(idx, start)| {
-                    start.and_then(|s| (s.idx <= self.scroll_offset).then_some((idx, s)))
-                })
+                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
+                let old = old_bounds.get(hunk_idx).copied().flatten();
+                let new = new_bounds.get(hunk_idx).copied().flatten();
+                self.pick_split_bounds(old, new).map(|b| (hunk_idx, b))
             }
             _ => {
-                let hunk_starts = self.compute_hunk_starts_single();
-                hunk_starts
-                    .iter()
-                    .enumerate()
-                    .rev()
-                    .find_map(|(idx, start)| {
-                        start.and_then(|s| (s.idx <= self.scroll_offset).then_some((idx, s)))
-                    })
+                let bounds = self.compute_hunk_bounds_single();
+                bounds
+                    .get(hunk_idx)
+                    .copied()
+                    .flatten()
+                    .map(|b| (hunk_idx, b))
             }
         };
 
-        if let Some((hidx, start)) = target {
+        if let Some((hidx, bound)) = target {
+            self.scroll_offset = bound.start.idx;
+            self.centered_once = false;
             self.multi_diff
                 .current_navigator()
-                .set_cursor_hunk(hidx, start.change_id);
-        } else {
-            self.multi_diff.current_navigator().clear_cursor_change();
+                .set_cursor_hunk(hidx, bound.start.change_id);
+            self.multi_diff.current_navigator().set_hunk_scope(true);
+            if self.auto_center {
+                self.needs_scroll_to_active = true;
+            }
         }
     }
 
-    /// Scroll to the next hunk (no-step mode)
-    pub fn next_hunk_scroll(&mut self) {
-        let auto_center = self.auto_center;
-        let (current_hunk, cursor_set) = {
-            let state = self.multi_diff.current_navigator().state();
-            (state.current_hunk, state.cursor_change.is_some())
-        };
-        let in_hunk_scope = self
+    fn goto_line_number(&mut self, line_number: usize) {
+        self.clear_peek();
+        let view = self
             .multi_diff
             .current_navigator()
-            .state()
-            .last_nav_was_hunk;
-        let use_cursor = auto_center && cursor_set && in_hunk_scope;
-        let inclusive = in_hunk_scope;
-        let target = match self.view_mode {
+            .current_view_with_frame(AnimationFrame::Idle);
+        let target_idx = match self.view_mode {
             ViewMode::Split => {
-                let (old_starts, new_starts) = self.compute_hunk_starts_split();
-                let effective: Vec<Option<HunkStart>> = old_starts
-                    .into_iter()
-                    .zip(new_starts)
-                    .map(|(old, new)| self.pick_split_start(old, new))
-                    .collect();
-                let mut target = if use_cursor && current_hunk < effective.len() {
-                    self.next_hunk_from_index(&effective, current_hunk)
+                let mut old_idx = 0usize;
+                let mut new_idx = 0usize;
+                let mut old_last = None;
+                let mut new_last = None;
+                let mut old_max_line = 0usize;
+                let mut new_max_line = 0usize;
+                let mut old_match = None;
+                let mut new_match = None;
+                for line in &view {
+                    if let Some(old_line) = line.old_line {
+                        old_max_line = old_max_line.max(old_line);
+                        if old_line == line_number {
+                            old_match = Some(old_idx);
+                        }
+                        old_idx += 1;
+                        old_last = Some(old_idx - 1);
+                    }
+                    if let Some(new_line) = line.new_line {
+                        new_max_line = new_max_line.max(new_line);
+                        if new_line == line_number {
+                            new_match = Some(new_idx);
+                        }
+                        new_idx += 1;
+                        new_last = Some(new_idx - 1);
+                    }
+                }
+                if line_number == 0 {
+                    let first_old = if old_idx > 0 { Some(0) } else { None };
+                    let first_new = if new_idx > 0 { Some(0) } else { None };
+                    self.pick_split_index(first_old, first_new)
                 } else {
-                    self.next_hunk_from_starts(&effective, inclusive)
-                };
-                if target.is_none() {
-                    target = self.single_hunk_fallback(&effective);
+                    let max_line = old_max_line.max(new_max_line);
+                    if max_line > 0 && line_number > max_line {
+                        if old_max_line > new_max_line {
+                            old_last
+                        } else if new_max_line > old_max_line {
+                            new_last
+                        } else {
+                            self.pick_split_index(old_last, new_last)
+                        }
+                    } else {
+                        self.pick_split_index(old_match, new_match)
+                    }
+                }
+            }
+            ViewMode::Evolution => {
+                let mut target = None;
+                let mut last_idx = None;
+                let mut max_line = 0usize;
+                for (display_idx, line) in view
+                    .iter()
+                    .filter(|line| {
+                        !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
+                    })
+                    .enumerate()
+                {
+                    let line_num = line.new_line.or(line.old_line);
+                    if let Some(num) = line_num {
+                        max_line = max_line.max(num);
+                    }
+                    if line_num == Some(line_number) {
+                        target = Some(display_idx);
+                        break;
+                    }
+                    last_idx = Some(display_idx);
+                }
+                if line_number == 0 {
+                    last_idx.map(|_| 0)
+                } else if max_line > 0 && line_number > max_line {
+                    last_idx
+                } else {
+                    target
+                }
+            }
+            _ => {
+                let mut target = None;
+                let mut last_idx = None;
+                let mut max_line = 0usize;
+                for (display_idx, line) in view.iter().enumerate() {
+                    let line_num = line.old_line.or(line.new_line);
+                    if let Some(num) = line_num {
+                        max_line = max_line.max(num);
+                    }
+                    if line_num == Some(line_number) {
+                        target = Some(display_idx);
+                        break;
+                    }
+                    last_idx = Some(display_idx);
                 }
-                target
-            }
-            _ => {
-                let hunk_starts = self.compute_hunk_starts_single();
-                let mut target = if use_cursor && current_hunk < hunk_starts.len() {
-                    self.next_hunk_from_index(&hunk_starts, current_hunk)
+                if line_number == 0 {
+                    last_idx.map(|_| 0)
+                } else if max_line > 0 && line_number > max_line {
+                    last_idx
                 } else {
-                    self.next_hunk_from_starts(&hunk_starts, inclusive)
-                };
-                if target.is_none() {
-                    target = self.single_hunk_fallback(&hunk_starts);
+                    target
                 }
-                target
             }
         };
 
-        if let Some((hidx, start)) = target {
-            self.scroll_offset = start.idx;
-            self.centered_once = false;
-            self.multi_diff
-                .current_navigator()
-                .set_cursor_hunk(hidx, start.change_id);
-            self.multi_diff.current_navigator().set_hunk_scope(true);
+        if let Some(idx) = target_idx {
+            let viewport_height = self.last_viewport_height.max(1);
             if self.auto_center {
-                self.needs_scroll_to_active = true;
+                let half_viewport = viewport_height / 2;
+                self.scroll_offset = idx.saturating_sub(half_viewport);
+                self.centered_once = true;
+            } else {
+                self.scroll_offset = idx;
+                self.centered_once = false;
+            }
+            self.needs_scroll_to_active = false;
+            self.multi_diff.current_navigator().set_hunk_scope(false);
+            if !self.stepping {
+                self.set_cursor_for_current_scroll();
             }
         }
     }
 
-    /// Scroll to the previous hunk (no-step mode)
-    pub fn prev_hunk_scroll(&mut self) {
-        let auto_center = self.auto_center;
-        let (current_hunk, cursor_set) = {
-            let state = self.multi_diff.current_navigator().state();
-            (state.current_hunk, state.cursor_change.is_some())
-        };
-        let in_hunk_scope = self
-            .multi_diff
-            .current_navigator()
-            .state()
-            .last_nav_was_hunk;
-        let use_cursor = auto_center && cursor_set && in_hunk_scope;
-        let target = match self.view_mode {
-            ViewMode::Split => {
-                let (old_starts, new_starts) = self.compute_hunk_starts_split();
-                let effective: Vec<Option<HunkStart>> = old_starts
-                    .into_iter()
-                    .zip(new_starts)
-                    .map(|(old, new)| self.pick_split_start(old, new))
-                    .collect();
-                let mut target = if use_cursor && current_hunk < effective.len() {
-                    self.prev_hunk_from_index(&effective, current_hunk)
-                } else {
-                    self.prev_hunk_from_starts(&effective)
-                };
-                if target.is_none() {
-                    target = self.single_hunk_fallback(&effective);
-                }
-                target
-            }
-            _ => {
-                let hunk_starts = self.compute_hunk_starts_single();
-                let mut target = if use_cursor && current_hunk < hunk_starts.len() {
-                    self.prev_hunk_from_index(&hunk_starts, current_hunk)
-                } else {
-                    self.prev_hunk_from_starts(&hunk_starts)
-                };
-                if target.is_none() {
-                    target = self.single_hunk_fallback(&hunk_starts);
-                }
-                target
-            }
-        };
+    pub fn toggle_autoplay(&mut self) {
+        if self.autoplay && !self.autoplay_reverse {
+            self.autoplay = false;
+        } else {
+            self.autoplay = true;
+            self.autoplay_reverse = false;
+        }
+        self.last_autoplay_tick = Instant::now();
+    }
 
-        if let Some((hidx, start)) = target {
-            self.scroll_offset = start.idx;
-            self.centered_once = false;
-            self.multi_diff
-                .current_navigator()
-                .set_cursor_hunk(hidx, start.change_id);
-            self.multi_diff.current_navigator().set_hunk_scope(true);
-            if self.auto_center {
-                self.needs_scroll_to_active = true;
-            }
+    pub fn toggle_autoplay_reverse(&mut self) {
+        if self.autoplay && self.autoplay_reverse {
+            self.autoplay = false;
+        } else {
+            self.autoplay = true;
+            self.autoplay_reverse = true;
         }
+        self.last_autoplay_tick = Instant::now();
     }
 
-    /// Move to the next hunk (group of related changes)
-    pub fn next_hunk(&mut self) {
-        self.clear_peek();
-        if self.multi_diff.current_navigator().next_hunk() {
-            if self.animation_enabled {
-                self.start_animation();
-            }
-            self.needs_scroll_to_active = true;
+    pub fn toggle_view_mode(&mut self) {
+        if !self.stepping {
+            // In no-step mode, skip Evolution view as it requires stepping
+            self.view_mode = match self.view_mode {
+                ViewMode::SinglePane => ViewMode::Split,
+                ViewMode::Split => ViewMode::SinglePane,
+                _ => ViewMode::SinglePane,
+            };
+        } else {
+            self.view_mode = self.view_mode.next();
         }
     }
 
-    /// Move to the previous hunk (group of related changes)
-    pub fn prev_hunk(&mut self) {
-        self.clear_peek();
-        if self.multi_diff.current_navigator().prev_hunk() {
-            if self.animation_enabled {
-                self.start_animation();
-            } else {
-                self.clear_active_on_next_render = true;
-            }
-            self.needs_scroll_to_active = true;
+    pub fn scroll_up(&mut self) {
+        self.centered_once = false;
+        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.centered_once = false;
+        self.scroll_offset += 1;
+    }
+
+    pub fn scroll_half_page_up(&mut self, viewport_height: usize) {
+        self.centered_once = false;
+        let half = viewport_height / 2;
+        self.scroll_offset = self.scroll_offset.saturating_sub(half);
+    }
+
+    /// Clamp scroll offset so we don't scroll past content
+    /// When allow_overscroll is true, permits enough scroll for the last line to be centered
+    pub fn clamp_scroll(
+        &mut self,
+        total_lines: usize,
+        viewport_height: usize,
+        allow_overscroll: bool,
+    ) {
+        self.scroll_offset =
+            self.scroll_offset
+                .min(max_scroll(total_lines, viewport_height, allow_overscroll));
+    }
+
+    /// Whether overscroll is allowed (centering is about to happen or manual zz was used)
+    pub fn allow_overscroll(&self) -> bool {
+        allow_overscroll_state(
+            self.auto_center,
+            self.needs_scroll_to_active,
+            self.centered_once,
+        )
+    }
+
+    pub fn scroll_half_page_down(&mut self, viewport_height: usize) {
+        self.centered_once = false;
+        let half = viewport_height / 2;
+        self.scroll_offset += half;
+    }
+
+    pub fn scroll_left(&mut self) {
+        if !self.line_wrap {
+            self.horizontal_scroll = self.horizontal_scroll.saturating_sub(4);
         }
     }
 
-    /// Get current hunk info (current hunk index, total hunks)
-    pub fn hunk_info(&mut self) -> (usize, usize) {
-        let state = self.multi_diff.current_navigator().state();
-        (state.current_hunk + 1, state.total_hunks) // 1-indexed for display
+    pub fn scroll_right(&mut self) {
+        if !self.line_wrap {
+            self.horizontal_scroll += 4;
+        }
     }
 
-    pub fn hunk_step_info(&mut self) -> Option<(usize, usize)> {
-        let nav = self.multi_diff.current_navigator();
-        let state = nav.state();
-        let hunk = nav.current_hunk()?;
-        let total = hunk.change_ids.len();
-        if total == 0 {
-            return None;
+    /// Go to start of line (horizontal scroll = 0), like vim's 0
+    pub fn scroll_to_line_start(&mut self) {
+        self.horizontal_scroll = 0;
+    }
+
+    /// Go to end of line (max horizontal scroll), like vim's $
+    pub fn scroll_to_line_end(&mut self) {
+        if !self.line_wrap {
+            // Set to max, will be clamped during render
+            self.horizontal_scroll = usize::MAX / 2;
         }
-        let mut applied = 0usize;
-        for id in &hunk.change_ids {
-            if state.applied_changes.contains(id) {
-                applied += 1;
-            }
+    }
+
+    /// Clamp horizontal scroll so we don't scroll too far right
+    pub fn clamp_horizontal_scroll(&mut self, max_line_width: usize, viewport_width: usize) {
+        if !self.line_wrap {
+            let max_scroll = max_line_width.saturating_sub(viewport_width);
+            self.horizontal_scroll = self.horizontal_scroll.min(max_scroll);
         }
-        Some((applied, total))
     }
 
-    pub fn pending_insert_only_in_current_hunk(&mut self) -> usize {
-        let nav = self.multi_diff.current_navigator();
-        let state = nav.state();
-        let hunk = match nav.current_hunk() {
-            Some(hunk) => hunk,
-            None => return 0,
-        };
+    pub fn clamp_horizontal_scroll_cached(&mut self, viewport_width: usize) {
+        if self.line_wrap {
+            return;
+        }
+        let max_line_width = self.current_max_line_width();
+        if max_line_width == 0 {
+            return;
+        }
+        let max_scroll = max_line_width.saturating_sub(viewport_width);
+        self.horizontal_scroll = self.horizontal_scroll.min(max_scroll);
+    }
 
-        let mut pending = 0usize;
-        for change_id in &hunk.change_ids {
-            if state.applied_changes.contains(change_id) {
-                continue;
-            }
-            if let Some(change) = nav.diff().changes.iter().find(|c| c.id == *change_id) {
-                let is_insert_only = change
-                    .spans
-                    .iter()
-                    .all(|span| span.kind == ChangeKind::Insert);
-                if is_insert_only {
-                    pending += 1;
-                }
+    pub fn reset_current_max_line_width(&mut self) {
+        let idx = self.multi_diff.selected_index;
+        if self.stepping {
+            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
+                *slot = 0;
             }
+        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
+            *slot = 0;
         }
-
-        pending
     }
 
-    /// Jump to first change of current hunk
-    pub fn goto_hunk_start(&mut self) {
-        self.clear_peek();
-        if self.multi_diff.current_navigator().goto_hunk_start() {
-            if self.animation_enabled {
-                self.start_animation();
+    pub fn set_current_max_line_width(&mut self, max_line_width: usize) {
+        let idx = self.multi_diff.selected_index;
+        if self.stepping {
+            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
+                *slot = max_line_width;
             }
-            self.needs_scroll_to_active = true;
+        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
+            *slot = max_line_width;
         }
     }
 
-    /// Jump to the start of the current hunk (no-step mode)
-    pub fn goto_hunk_start_scroll(&mut self) {
-        let (current_hunk, in_hunk_scope) = {
-            let state = self.multi_diff.current_navigator().state();
-            (
-                state.current_hunk,
-                state.last_nav_was_hunk && state.cursor_change.is_some(),
-            )
-        };
-        let target = match self.view_mode {
-            ViewMode::Split => {
-                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
-                let effective: Vec<Option<HunkBounds>> = old_bounds
-                    .into_iter()
-                    .zip(new_bounds)
-                    .map(|(old, new)| self.pick_split_bounds(old, new))
-                    .collect();
-                let hidx = if in_hunk_scope {
-                    Some(current_hunk)
-                } else {
-                    self.current_hunk_from_bounds(&effective)
-                };
-                hidx.and_then(|idx| effective.get(idx).copied().flatten().map(|b| (idx, b)))
-                    .or_else(|| Self::first_available_hunk(&effective))
-            }
-            _ => {
-                let bounds = self.compute_hunk_bounds_single();
-                let hidx = if in_hunk_scope {
-                    Some(current_hunk)
-                } else {
-                    self.current_hunk_from_bounds(&bounds)
-                };
-                hidx.and_then(|idx| bounds.get(idx).copied().flatten().map(|b| (idx, b)))
-                    .or_else(|| Self::first_available_hunk(&bounds))
+    pub fn update_current_max_line_width(&mut self, max_line_width: usize) {
+        let idx = self.multi_diff.selected_index;
+        if self.stepping {
+            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
+                *slot = (*slot).max(max_line_width);
             }
-        };
+        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
+            *slot = (*slot).max(max_line_width);
+        }
+    }
 
-        if let Some((hidx, bound)) = target {
-            self.scroll_offset = bound.start.idx;
-            self.centered_once = false;
-            self.multi_diff
-                .current_navigator()
-                .set_cursor_hunk(hidx, bound.start.change_id);
-            self.multi_diff.current_navigator().set_hunk_scope(true);
-            if self.auto_center {
-                self.needs_scroll_to_active = true;
-            }
+    fn current_max_line_width(&self) -> usize {
+        let idx = self.multi_diff.selected_index;
+        if self.stepping {
+            self.max_line_widths_step.get(idx).copied().unwrap_or(0)
+        } else {
+            self.max_line_widths_no_step.get(idx).copied().unwrap_or(0)
         }
     }
 
-    /// Jump to last change of current hunk
-    pub fn goto_hunk_end(&mut self) {
-        self.clear_peek();
-        if self.multi_diff.current_navigator().goto_hunk_end() {
-            if self.animation_enabled {
-                self.start_animation();
-            }
-            self.needs_scroll_to_active = true;
+    pub fn toggle_line_wrap(&mut self) {
+        self.line_wrap = !self.line_wrap;
+        // Reset horizontal scroll when enabling wrap
+        if self.line_wrap {
+            self.horizontal_scroll = 0;
         }
+        self.last_wrap_display_len = None;
+        self.last_wrap_active_idx = None;
     }
 
-    /// Jump to the end of the current hunk (no-step mode)
-    pub fn goto_hunk_end_scroll(&mut self) {
-        let (current_hunk, in_hunk_scope) = {
-            let state = self.multi_diff.current_navigator().state();
-            (
-                state.current_hunk,
-                state.last_nav_was_hunk && state.cursor_change.is_some(),
-            )
-        };
-        let target = match self.view_mode {
-            ViewMode::Split => {
-                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
-                let effective: Vec<Option<HunkBounds>> = old_bounds
-                    .into_iter()
-                    .zip(new_bounds)
-                    .map(|(old, new)| self.pick_split_bounds(old, new))
-                    .collect();
-                let hidx = if in_hunk_scope {
-                    Some(current_hunk)
-                } else {
-                    self.current_hunk_from_bounds(&effective)
-                };
-                hidx.and_then(|idx| effective.get(idx).copied().flatten().map(|b| (idx, b)))
-                    .or_else(|| Self::first_available_hunk(&effective))
-            }
-            _ => {
-                let bounds = self.compute_hunk_bounds_single();
-                let hidx = if in_hunk_scope {
-                    Some(current_hunk)
-                } else {
-                    self.current_hunk_from_bounds(&bounds)
-                };
-                hidx.and_then(|idx| bounds.get(idx).copied().flatten().map(|b| (idx, b)))
-                    .or_else(|| Self::first_available_hunk(&bounds))
-            }
-        };
+    pub fn toggle_strikethrough_deletions(&mut self) {
+        self.strikethrough_deletions = !self.strikethrough_deletions;
+    }
 
-        if let Some((hidx, bound)) = target {
-            self.scroll_offset = bound.end.idx;
-            self.centered_once = false;
-            self.multi_diff
-                .current_navigator()
-                .set_cursor_hunk(hidx, bound.end.change_id);
-            self.multi_diff.current_navigator().set_hunk_scope(true);
-            if self.auto_center {
-                self.needs_scroll_to_active = true;
-            }
-        }
+    pub fn increase_speed(&mut self) {
+        self.animation_speed = (self.animation_speed + 50).min(2000);
     }
 
-    /// Enter no-step mode without changing scroll position.
-    pub fn enter_no_step_mode(&mut self) {
-        // Evolution mode requires stepping, so switch to Single view
-        if self.view_mode == ViewMode::Evolution {
-            self.view_mode = ViewMode::SinglePane;
+    pub fn decrease_speed(&mut self) {
+        self.animation_speed = self.animation_speed.saturating_sub(50).max(50);
+    }
+
+    // File navigation methods
+    pub fn next_file(&mut self) {
+        if !self.file_filter.is_empty() {
+            let indices = self.filtered_file_indices();
+            if indices.is_empty() {
+                return;
+            }
+            let current = self.multi_diff.selected_index;
+            let pos = indices.iter().position(|&i| i == current);
+            let next_index = match pos {
+                Some(p) if p + 1 < indices.len() => indices[p + 1],
+                None => indices[0],
+                _ => return,
+            };
+            self.select_file(next_index);
+            return;
         }
 
-        self.peek_state = None;
-        self.multi_diff.current_navigator().goto_end();
-        self.multi_diff.current_navigator().clear_active_change();
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.needs_scroll_to_active = false;
-        let index = self.multi_diff.selected_index;
-        if !self.restore_no_step_state_snapshot(index) {
-            self.set_cursor_for_current_scroll();
-            self.multi_diff.current_navigator().set_hunk_scope(false);
+        // Save current scroll positions
+        let old_index = self.multi_diff.selected_index;
+        if self.multi_diff.next_file() {
+            if !self.stepping {
+                self.save_no_step_state_snapshot(old_index);
+            }
+            self.save_scroll_position_for(old_index);
+            // Restore scroll positions for new file
+            self.restore_scroll_position_for(self.multi_diff.selected_index);
+            self.animation_phase = AnimationPhase::Idle;
+            self.animation_progress = 1.0;
+            self.reset_search_for_file_switch();
+            self.update_file_list_scroll();
+            self.centered_once = false;
+            self.handle_file_enter();
         }
     }
 
-    pub fn toggle_stepping(&mut self) {
-        let current_index = self.multi_diff.selected_index;
-        if self.stepping {
-            // Turning OFF stepping: snapshot state and scroll, then enter no-step.
-            self.save_scroll_position_for(current_index);
-            self.save_step_state_snapshot(current_index);
-            self.step_peek_state = self.peek_state.take();
-            self.step_view_mode = self.view_mode;
-            self.stepping = false;
-            if !self.no_step_visited[current_index] {
-                self.scroll_offsets_no_step[current_index] = self.scroll_offset;
-                self.horizontal_scrolls_no_step[current_index] = self.horizontal_scroll;
-                self.no_step_visited[current_index] = true;
+    pub fn prev_file(&mut self) {
+        if !self.file_filter.is_empty() {
+            let indices = self.filtered_file_indices();
+            if indices.is_empty() {
+                return;
             }
-            self.restore_scroll_position_for(current_index);
-            self.enter_no_step_mode();
-        } else {
-            // Turning ON stepping: restore snapshot and scroll.
-            self.save_no_step_state_snapshot(current_index);
-            self.save_scroll_position_for(current_index);
-            self.stepping = true;
-            self.peek_state = self.step_peek_state.take();
-            self.view_mode = self.step_view_mode;
-            if !self.restore_step_state_snapshot(current_index) {
-                self.goto_start();
+            let current = self.multi_diff.selected_index;
+            let pos = indices.iter().position(|&i| i == current);
+            let prev_index = match pos {
+                Some(p) if p > 0 => indices[p - 1],
+                None => indices[indices.len().saturating_sub(1)],
+                _ => return,
+            };
+            self.select_file(prev_index);
+            return;
+        }
+
+        // Save current scroll positions
+        let old_index = self.multi_diff.selected_index;
+        if self.multi_diff.prev_file() {
+            if !self.stepping {
+                self.save_no_step_state_snapshot(old_index);
             }
-            self.restore_scroll_position_for(current_index);
+            self.save_scroll_position_for(old_index);
+            // Restore scroll positions for new file
+            self.restore_scroll_position_for(self.multi_diff.selected_index);
             self.animation_phase = AnimationPhase::Idle;
             self.animation_progress = 1.0;
-            self.needs_scroll_to_active = false;
+            self.reset_search_for_file_switch();
+            self.update_file_list_scroll();
+            self.centered_once = false;
+            self.handle_file_enter();
+        }
+    }
+
+    pub fn select_file(&mut self, index: usize) {
+        let old_index = self.multi_diff.selected_index;
+        if !self.stepping {
+            self.save_no_step_state_snapshot(old_index);
         }
+        self.save_scroll_position_for(old_index);
+        self.multi_diff.select_file(index);
+        self.restore_scroll_position_for(self.multi_diff.selected_index);
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.reset_search_for_file_switch();
+        self.centered_once = false;
+        self.update_file_list_scroll();
+        self.handle_file_enter();
     }
 
-    pub fn toggle_animation(&mut self) {
-        self.animation_enabled = !self.animation_enabled;
+    pub fn start_file_filter(&mut self) {
+        self.file_filter_active = true;
+        self.file_filter.clear();
+        self.file_list_scroll = 0;
+        self.ensure_selection_matches_filter();
+        self.update_file_list_scroll();
     }
 
-    pub fn toggle_zen(&mut self) {
-        self.zen_mode = !self.zen_mode;
+    pub fn stop_file_filter(&mut self) {
+        self.file_filter_active = false;
     }
 
-    pub fn help_scroll_up(&mut self) {
-        self.help_scroll = self.help_scroll.saturating_sub(1);
+    /// Edit the file filter; Enter and Esc leave it
+    pub fn file_filter_key(&mut self, key: PromptKey) {
+        match key {
+            PromptKey::Cancel | PromptKey::Submit => self.stop_file_filter(),
+            key => {
+                if self.file_filter.edit(key, &[] as &[&str]) {
+                    self.on_filter_changed();
+                }
+            }
+        }
     }
 
-    pub fn help_scroll_down(&mut self) {
-        if self.help_scroll < self.help_max_scroll {
-            self.help_scroll += 1;
-        }
+    /// Check if current file would be blank at step 0 (new file: empty old, non-empty new)
+    fn is_blank_at_step0(&self) -> bool {
+        self.multi_diff.current_old_is_empty() && !self.multi_diff.current_new_is_empty()
     }
 
-    pub fn handle_file_list_click(&mut self, column: u16, row: u16) -> bool {
-        if let Some((x, y, width, height)) = self.file_filter_area {
-            let end_x = x.saturating_add(width);
-            let end_y = y.saturating_add(height);
-            if column >= x && column < end_x && row >= y && row < end_y {
-                self.file_list_focused = true;
-                self.start_file_filter();
-                return true;
+    /// Handle entering a file (marks visited, optionally auto-steps to first change)
+    /// Called on initial file and when switching files.
+    pub fn handle_file_enter(&mut self) {
+        let idx = self.multi_diff.selected_index;
+
+        if !self.stepping {
+            if !self.files_visited[idx] {
+                self.files_visited[idx] = true;
+            }
+            // If in no-step mode, ensure full content is shown immediately
+            self.ensure_step_state_snapshot(idx);
+            self.multi_diff.current_navigator().goto_end();
+            self.multi_diff.current_navigator().clear_active_change();
+            self.animation_phase = AnimationPhase::Idle;
+            self.animation_progress = 1.0;
+            if !self.restore_no_step_state_snapshot(idx) {
+                self.set_cursor_for_current_scroll();
+                self.multi_diff.current_navigator().set_hunk_scope(false);
             }
+            self.no_step_visited[idx] = true;
+            // Don't mess with scroll_offset here; it might have been restored by next_file/prev_file
+            return;
         }
 
-        let (x, y, width, height) = match self.file_list_area {
-            Some(area) => area,
-            None => return false,
-        };
-        let end_x = x.saturating_add(width);
-        let end_y = y.saturating_add(height);
-        if column < x || column >= end_x || row < y || row >= end_y {
-            return false;
+        // Only process on first visit to this file
+        if self.files_visited[idx] {
+            return;
         }
 
-        let item_start = y.saturating_add(1);
-        if row < item_start {
-            self.file_list_focused = true;
-            return true;
+        // Mark as visited
+        self.files_visited[idx] = true;
+
+        let state = self.multi_diff.current_navigator().state();
+        let at_step_0 = state.current_step == 0;
+        let has_steps = state.total_steps > 1;
+
+        if !at_step_0 || !has_steps {
+            return;
         }
 
-        let row_idx = (row - item_start) as usize;
-        if let Some(Some(file_idx)) = self.file_list_rows.get(row_idx) {
-            self.file_list_focused = true;
-            self.select_file(*file_idx);
-            return true;
+        // Auto-step for blank files (new files) regardless of view mode
+        if self.auto_step_blank_files && self.is_blank_at_step0() {
+            self.next_step();
+            return;
         }
 
-        self.file_list_focused = true;
-        true
+        // Regular auto-step on enter (not for Evolution mode)
+        if self.auto_step_on_enter && self.view_mode != ViewMode::Evolution {
+            self.next_step();
+        }
     }
 
-    pub fn toggle_file_panel(&mut self) {
-        if self.file_panel_manually_set {
-            // Already manually controlled, just toggle
-            self.file_panel_visible = !self.file_panel_visible;
+    fn active_scroll_buffers(&self) -> (&Vec<usize>, &Vec<usize>) {
+        if self.stepping {
+            (&self.scroll_offsets_step, &self.horizontal_scrolls_step)
         } else {
-            // First manual toggle
-            self.file_panel_manually_set = true;
-            if self.file_panel_auto_hidden {
-                // Panel was auto-hidden, show it
-                self.file_panel_visible = true;
-            } else {
-                // Panel was visible, hide it
-                self.file_panel_visible = false;
-            }
-        }
-        if !self.file_panel_visible {
-            self.file_list_focused = false;
+            (
+                &self.scroll_offsets_no_step,
+                &self.horizontal_scrolls_no_step,
+            )
         }
     }
 
-    /// Check if current animation is backward (un-applying a change)
-    pub fn is_backward_animation(&self) -> bool {
-        self.animation_phase != AnimationPhase::Idle
-            && self.multi_diff.current_step_direction() == StepDirection::Backward
+    fn active_scroll_buffers_mut(&mut self) -> (&mut Vec<usize>, &mut Vec<usize>) {
+        if self.stepping {
+            (
+                &mut self.scroll_offsets_step,
+                &mut self.horizontal_scrolls_step,
+            )
+        } else {
+            (
+                &mut self.scroll_offsets_no_step,
+                &mut self.horizontal_scrolls_no_step,
+            )
+        }
     }
 
-    /// Convert CLI animation phase to core AnimationFrame for phase-aware rendering
-    pub fn animation_frame(&self) -> AnimationFrame {
-        if let Some(frame) = self.snap_frame {
-            return frame;
+    fn save_scroll_position_for(&mut self, index: usize) {
+        let scroll_offset = self.scroll_offset;
+        let horizontal_scroll = self.horizontal_scroll;
+        let (scrolls, horizontals) = self.active_scroll_buffers_mut();
+        if let Some(slot) = scrolls.get_mut(index) {
+            *slot = scroll_offset;
         }
-        // Force FadeOut for one-frame render when animation disabled,
-        // so backward insert-only changes produce ViewLines for extent markers
-        if self.clear_active_on_next_render {
-            return AnimationFrame::FadeOut;
+        if let Some(slot) = horizontals.get_mut(index) {
+            *slot = horizontal_scroll;
         }
-        match self.animation_phase {
-            AnimationPhase::Idle => AnimationFrame::Idle,
-            AnimationPhase::FadeOut => AnimationFrame::FadeOut,
-            AnimationPhase::FadeIn => AnimationFrame::FadeIn,
+    }
+
+    fn restore_scroll_position_for(&mut self, index: usize) {
+        let (scrolls, horizontals) = self.active_scroll_buffers();
+        let scroll_value = scrolls.get(index).copied();
+        let horizontal_value = horizontals.get(index).copied();
+        if let Some(value) = scroll_value {
+            self.scroll_offset = value;
+        }
+        if let Some(value) = horizontal_value {
+            self.horizontal_scroll = value;
         }
     }
 
-    pub fn goto_start(&mut self) {
-        self.clear_peek();
-        if !self.stepping {
-            self.scroll_offset = 0;
-            self.centered_once = false;
-            self.needs_scroll_to_active = false;
-            self.multi_diff.current_navigator().clear_cursor_change();
-            self.multi_diff.current_navigator().set_hunk_scope(false);
-            return;
+    fn save_step_state_snapshot(&mut self, index: usize) {
+        let state = self.multi_diff.current_navigator().state().clone();
+        if let Some(slot) = self.step_state_snapshots.get_mut(index) {
+            *slot = Some(state);
         }
-        self.multi_diff.current_navigator().goto_start();
-        self.scroll_offset = 0;
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.centered_once = false;
-        self.needs_scroll_to_active = true;
     }
 
-    pub fn goto_end(&mut self) {
-        self.clear_peek();
-        if !self.stepping {
-            self.scroll_offset = usize::MAX;
-            self.centered_once = false;
-            self.needs_scroll_to_active = false;
-            self.multi_diff.current_navigator().clear_cursor_change();
-            self.multi_diff.current_navigator().set_hunk_scope(false);
+    fn restore_step_state_snapshot(&mut self, index: usize) -> bool {
+        let Some(snapshot) = self.step_state_snapshots.get(index).and_then(|s| s.clone()) else {
+            return false;
+        };
+        self.multi_diff.current_navigator().set_state(snapshot)
+    }
+
+    fn ensure_step_state_snapshot(&mut self, index: usize) {
+        let needs_snapshot = self
+            .step_state_snapshots
+            .get(index)
+            .map(|slot| slot.is_none())
+            .unwrap_or(false);
+        if !needs_snapshot {
             return;
         }
-        self.multi_diff.current_navigator().goto_end();
-        self.scroll_offset = usize::MAX; // Will be clamped to bottom
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.centered_once = false;
-        // Don't set needs_scroll_to_active - we want to stay at bottom
+        let state = self.multi_diff.current_navigator().state().clone();
+        if let Some(slot) = self.step_state_snapshots.get_mut(index) {
+            *slot = Some(state);
+        }
     }
 
-    pub fn goto_first_step(&mut self) {
-        self.clear_peek();
-        self.multi_diff.current_navigator().goto(1);
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.centered_once = false;
-        self.needs_scroll_to_active = true;
+    fn save_no_step_state_snapshot(&mut self, index: usize) {
+        let state = self.multi_diff.current_navigator().state();
+        if let Some(slot) = self.no_step_state_snapshots.get_mut(index) {
+            *slot = Some(NoStepState {
+                current_hunk: state.current_hunk,
+                cursor_change: state.cursor_change,
+                last_nav_was_hunk: state.last_nav_was_hunk,
+            });
+        }
     }
 
-    pub fn goto_last_step(&mut self) {
-        self.clear_peek();
-        self.multi_diff.current_navigator().goto_end();
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.centered_once = false;
-        self.needs_scroll_to_active = true;
+    fn restore_no_step_state_snapshot(&mut self, index: usize) -> bool {
+        let Some(snapshot) = self.no_step_state_snapshots.get(index).and_then(|s| *s) else {
+            return false;
+        };
+        self.multi_diff
+            .current_navigator()
+            .set_cursor_hunk(snapshot.current_hunk, snapshot.cursor_change);
+        self.multi_diff
+            .current_navigator()
+            .set_hunk_scope(snapshot.last_nav_was_hunk);
+        true
     }
 
-    fn goto_step_number(&mut self, step_number: usize) {
-        if !self.stepping {
-            return;
-        }
-        let total_steps = self.multi_diff.current_navigator().state().total_steps;
-        if total_steps == 0 {
-            return;
-        }
-        self.clear_peek();
-        let clamped = step_number.max(1).min(total_steps);
-        let target_step = clamped.saturating_sub(1);
-        self.multi_diff.current_navigator().goto(target_step);
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.centered_once = false;
-        self.needs_scroll_to_active = true;
+    pub fn is_multi_file(&self) -> bool {
+        self.multi_diff.is_multi_file()
     }
 
-    fn goto_hunk_number(&mut self, hunk_number: usize) {
-        let total_hunks = self.multi_diff.current_navigator().state().total_hunks;
-        if total_hunks == 0 {
+    fn update_file_list_scroll(&mut self) {
+        let indices = self.filtered_file_indices();
+        if indices.is_empty() {
+            self.file_list_scroll = 0;
             return;
         }
-        let clamped = hunk_number.max(1).min(total_hunks);
-        let hunk_idx = clamped - 1;
-        if self.stepping {
-            self.goto_hunk_index(hunk_idx);
-        } else {
-            self.goto_hunk_index_scroll(hunk_idx);
-        }
-    }
 
-    fn goto_hunk_index(&mut self, hunk_idx: usize) {
-        self.clear_peek();
-        self.multi_diff.current_navigator().goto_hunk(hunk_idx);
-        if self.animation_enabled {
-            self.start_animation();
-        } else {
-            self.clear_active_on_next_render = true;
+        // Keep selected file visible in the file list
+        let selected = self.multi_diff.selected_index;
+        let selected_pos = indices.iter().position(|&i| i == selected).unwrap_or(0);
+        if selected_pos < self.file_list_scroll {
+            self.file_list_scroll = selected_pos;
+        }
+        // Assume roughly 20 visible files
+        let visible_files = 20;
+        if selected_pos >= self.file_list_scroll + visible_files {
+            self.file_list_scroll = selected_pos.saturating_sub(visible_files - 1);
         }
-        self.needs_scroll_to_active = true;
     }
 
-    fn goto_hunk_index_scroll(&mut self, hunk_idx: usize) {
-        let target = match self.view_mode {
-            ViewMode::Split => {
-                let (old_bounds, new_bounds) = self.compute_hunk_bounds_split();
-                let old = old_bounds.get(hunk_idx).copied().flatten();
-                let new = new_bounds.get(hunk_idx).copied().flatten();
-                self.pick_split_bounds(old, new).map(|b| (hunk_idx, b))
-            }
-            _ => {
-                let bounds = self.compute_hunk_bounds_single();
-                bounds
-                    .get(hunk_idx)
-                    .copied()
-                    .flatten()
-                    .map(|b| (hunk_idx, b))
-            }
-        };
+    fn on_filter_changed(&mut self) {
+        self.file_list_scroll = 0;
+        self.ensure_selection_matches_filter();
+        self.update_file_list_scroll();
+    }
 
-        if let Some((hidx, bound)) = target {
-            self.scroll_offset = bound.start.idx;
-            self.centered_once = false;
-            self.multi_diff
-                .current_navigator()
-                .set_cursor_hunk(hidx, bound.start.change_id);
-            self.multi_diff.current_navigator().set_hunk_scope(true);
-            if self.auto_center {
-                self.needs_scroll_to_active = true;
-            }
+    fn ensure_selection_matches_filter(&mut self) {
+        if self.file_filter.is_empty() {
+            return;
+        }
+        let indices = self.filtered_file_indices();
+        if indices.is_empty() {
+            return;
+        }
+        if !indices.contains(&self.multi_diff.selected_index) {
+            self.select_file(indices[0]);
         }
     }
 
-    fn goto_line_number(&mut self, line_number: usize) {
-        self.clear_peek();
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(AnimationFrame::Idle);
-        let target_idx = match self.view_mode {
-            ViewMode::Split => {
-                let mut old_idx = 0usize;
-                let mut new_idx = 0usize;
-                let mut old_last = None;
-                let mut new_last = None;
-                let mut old_max_line = 0usize;
-                let mut new_max_line = 0usize;
-                let mut old_match = None;
-                let mut new_match = None;
-                for line in &view {
-                    if let Some(old_line) = line.old_line {
-                        old_max_line = old_max_line.max(old_line);
-                        if old_line == line_number {
-                            old_match = Some(old_idx);
-                        }
-                        old_idx += 1;
-                        old_last = Some(old_idx - 1);
-                    }
-                    if let Some(new_line) = line.new_line {
-                        new_max_line = new_max_line.max(new_line);
-                        if new_line == line_number {
-                            new_match = Some(new_idx);
-                        }
-                        new_idx += 1;
-                        new_last = Some(new_idx - 1);
-                    }
-                }
-                if line_number == 0 {
-                    let first_old = if old_idx > 0 { Some(0) } else { None };
-                    let first_new = if new_idx > 0 { Some(0) } else { None };
-                    self.pick_split_index(first_old, first_new)
-                } else {
-                    let max_line = old_max_line.max(new_max_line);
-                    if max_line > 0 && line_number > max_line {
-                        if old_max_line > new_max_line {
-                            old_last
-                        } else if new_max_line > old_max_line {
-                            new_last
-                        } else {
-                            self.pick_split_index(old_last, new_last)
-                        }
-                    } else {
-                        self.pick_split_index(old_match, new_match)
-                    }
-                }
-            }
-            ViewMode::Evolution => {
-                let mut target = None;
-                let mut last_idx = None;
-                let mut max_line = 0usize;
-                for (display_idx, line) in view
-                    .iter()
-                    .filter(|line| {
-                        !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)
-                    })
-                    .enumerate()
-                {
-                    let line_num = line.new_line.or(line.old_line);
-                    if let Some(num) = line_num {
-                        max_line = max_line.max(num);
-                    }
-                    if line_num == Some(line_number) {
-                        target = Some(display_idx);
-                        break;
-                    }
-                    last_idx = Some(display_idx);
-                }
-                if line_number == 0 {
-                    last_idx.map(|_| 0)
-                } else if max_line > 0 && line_number > max_line {
-                    last_idx
-                } else {
-                    target
-                }
-            }
-            _ => {
-                let mut target = None;
-                let mut last_idx = None;
-                let mut max_line = 0usize;
-                for (display_idx, line) in view.iter().enumerate() {
-                    let line_num = line.old_line.or(line.new_line);
-                    if let Some(num) = line_num {
-                        max_line = max_line.max(num);
-                    }
-                    if line_num == Some(line_number) {
-                        target = Some(display_idx);
-                        break;
-                    }
-                    last_idx = Some(display_idx);
-                }
-                if line_number == 0 {
-                    last_idx.map(|_| 0)
-                } else if max_line > 0 && line_number > max_line {
-                    last_idx
-                } else {
-                    target
-                }
-            }
+    /// Segments of the breadcrumb bar, each with its level (the repository
+    /// has none)
+    pub fn breadcrumbs(&mut self) -> Vec<(Option<CrumbLevel>, String)> {
+        let repo = match self.current_repo() {
+            Some((name, _)) => name,
+            None => self
+                .multi_diff
+                .repo_root()
+                .and_then(|root| root.file_name())
+                .map_or_else(
+                    || ".".to_string(),
+                    |name| name.to_string_lossy().to_string(),
+                ),
         };
-
-        if let Some(idx) = target_idx {
-            let viewport_height = self.last_viewport_height.max(1);
-            if self.auto_center {
-                let half_viewport = viewport_height / 2;
-                self.scroll_offset = idx.saturating_sub(half_viewport);
-                self.centered_once = true;
-            } else {
-                self.scroll_offset = idx;
-                self.centered_once = false;
-            }
-            self.needs_scroll_to_active = false;
-            self.multi_diff.current_navigator().set_hunk_scope(false);
-            if !self.stepping {
-                self.set_cursor_for_current_scroll();
-            }
+        let path = self.current_file_path();
+        let (dir, file) = match path.rsplit_once('/') {
+            Some((dir, file)) => (Some(dir.to_string()), file.to_string()),
+            None => (None, path.clone()),
+        };
+        let mut crumbs = vec![(None, repo)];
+        if let Some(dir) = dir {
+            crumbs.push((Some(CrumbLevel::Dir), dir));
         }
-    }
-
-    pub fn toggle_autoplay(&mut self) {
-        if self.autoplay && !self.autoplay_reverse {
-            self.autoplay = false;
-        } else {
-            self.autoplay = true;
-            self.autoplay_reverse = false;
+        crumbs.push((Some(CrumbLevel::File), file));
+        let stepping = self.stepping;
+        let state = self.multi_diff.current_navigator().state();
+        if state.total_hunks > 0 {
+            crumbs.push((
+                Some(CrumbLevel::Hunk),
+                format!("hunk {}/{}", state.current_hunk + 1, state.total_hunks),
+            ));
         }
-        self.last_autoplay_tick = Instant::now();
+        let changes = state.total_steps.saturating_sub(1);
+        let change = if stepping {
+            format!("change {}/{changes}", state.current_step)
+        } else {
+            format!("{changes} changes")
+        };
+        crumbs.push((Some(CrumbLevel::Change), change));
+        crumbs
+    }
+
+    /// Move the breadcrumb level up (change, hunk, file, directory), back
+    /// to the change after the directory; shows the bar
+    pub fn crumb_up(&mut self) {
+        self.show_breadcrumbs = true;
+        self.crumb_level = match self.crumb_level {
+            CrumbLevel::Change => CrumbLevel::Hunk,
+            CrumbLevel::Hunk if self.is_multi_file() => CrumbLevel::File,
+            CrumbLevel::File => CrumbLevel::Dir,
+            CrumbLevel::Hunk | CrumbLevel::Dir => CrumbLevel::Change,
+        };
     }
 
-    pub fn toggle_autoplay_reverse(&mut self) {
-        if self.autoplay && self.autoplay_reverse {
-            self.autoplay = false;
+    /// Go to the next (or previous) sibling at the breadcrumb level
+    pub fn crumb_sibling(&mut self, forward: bool) {
+        match (self.crumb_level, forward) {
+            (CrumbLevel::Dir, _) => self.select_sibling_dir(forward),
+            (CrumbLevel::File, true) => self.next_file(),
+            (CrumbLevel::File, false) => self.prev_file(),
+            (CrumbLevel::Change, true) if self.stepping => self.next_step(),
+            (CrumbLevel::Change, false) if self.stepping => self.prev_step(),
+            (CrumbLevel::Hunk, true) if self.stepping => self.next_hunk(),
+            (CrumbLevel::Hunk, false) if self.stepping => self.prev_hunk(),
+            (_, true) => self.next_hunk_scroll(),
+            (_, false) => self.prev_hunk_scroll(),
+        }
+    }
+
+    /// Select the first file of the next directory in the file list, or of
+    /// the previous one
+    fn select_sibling_dir(&mut self, forward: bool) {
+        let files = self.filtered_file_indices();
+        let dir_of = |idx: usize| {
+            let name = &self.multi_diff.files[idx].display_name;
+            name.rsplit_once('/').map_or("", |(dir, _)| dir).to_string()
+        };
+        let Some(pos) = files
+            .iter()
+            .position(|&idx| idx == self.multi_diff.selected_index)
+        else {
+            return;
+        };
+        let current = dir_of(files[pos]);
+        let target = if forward {
+            files[pos + 1..]
+                .iter()
+                .copied()
+                .find(|&idx| dir_of(idx) != current)
         } else {
-            self.autoplay = true;
-            self.autoplay_reverse = true;
+            files[..pos]
+                .iter()
+                .rposition(|&idx| dir_of(idx) != current)
+                .map(|last| {
+                    let previous = dir_of(files[last]);
+                    let first = files[..last]
+                        .iter()
+                        .rposition(|&idx| dir_of(idx) != previous)
+                        .map_or(0, |before| before + 1);
+                    files[first]
+                })
+        };
+        if let Some(target) = target {
+            self.select_file(target);
         }
-        self.last_autoplay_tick = Instant::now();
     }
-
-    pub fn toggle_view_mode(&mut self) {
-        if !self.stepping {
-            // In no-step mode, skip Evolution view as it requires stepping
-            self.view_mode = match self.view_mode {
-                ViewMode::SinglePane => ViewMode::Split,
-                ViewMode::Split => ViewMode::SinglePane,
-                _ => ViewMode::SinglePane,
-            };
-        } else {
-            self.view_mode = self.view_mode.next();
+
+    /// Mark the selected file reviewed, or unmark it
+    pub fn toggle_file_reviewed(&mut self) {
+        let Some(file) = self.multi_diff.current_file() else {
+            return;
+        };
+        let path = file.path.clone();
+        if !self.reviewed_files.remove(&path) {
+            self.reviewed_files.insert(path);
         }
     }
 
-    pub fn scroll_up(&mut self) {
-        self.centered_once = false;
-        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    pub fn is_file_reviewed(&self, path: &Path) -> bool {
+        self.reviewed_files.contains(path)
     }
 
-    pub fn scroll_down(&mut self) {
-        self.centered_once = false;
-        self.scroll_offset += 1;
+    /// Totals of the files directly in directory `dir` (`""` for the root)
+    pub fn dir_rollup(&self, dir: &str) -> DirRollup {
+        let mut rollup = DirRollup::default();
+        for file in &self.multi_diff.files {
+            let file_dir = file
+                .display_name
+                .rsplit_once('/')
+                .map_or("", |(dir, _)| dir);
+            if file_dir != dir {
+                continue;
+            }
+            rollup.files += 1;
+            rollup.insertions += file.insertions;
+            rollup.deletions += file.deletions;
+            if self.reviewed_files.contains(&file.path) {
+                rollup.reviewed += 1;
+            }
+        }
+        rollup
     }
 
-    pub fn scroll_half_page_up(&mut self, viewport_height: usize) {
-        self.centered_once = false;
-        let half = viewport_height / 2;
-        self.scroll_offset = self.scroll_offset.saturating_sub(half);
+    /// True once any file is marked reviewed
+    pub fn has_reviewed_files(&self) -> bool {
+        !self.reviewed_files.is_empty()
     }
 
-    /// Clamp scroll offset so we don't scroll past content
-    /// When allow_overscroll is true, permits enough scroll for the last line to be centered
-    pub fn clamp_scroll(
-        &mut self,
-        total_lines: usize,
-        viewport_height: usize,
-        allow_overscroll: bool,
-    ) {
-        self.scroll_offset =
-            self.scroll_offset
-                .min(max_scroll(total_lines, viewport_height, allow_overscroll));
+    pub fn filtered_file_indices(&self) -> Vec<usize> {
+        if self.file_filter.is_empty() {
+            return (0..self.multi_diff.files.len()).collect();
+        }
+        let query = self.file_filter.text().to_ascii_lowercase();
+        self.multi_diff
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.display_name.to_ascii_lowercase().contains(&query))
+            .map(|(idx, _)| idx)
+            .collect()
     }
 
-    /// Whether overscroll is allowed (centering is about to happen or manual zz was used)
-    pub fn allow_overscroll(&self) -> bool {
-        allow_overscroll_state(
-            self.auto_center,
-            self.needs_scroll_to_active,
-            self.centered_once,
-        )
+    /// Duration of the current animation phase in milliseconds
+    fn animation_timing(&self) -> AnimationTiming {
+        let duration = |phase: Option<u64>| {
+            Duration::from_millis(phase.unwrap_or(self.animation_duration).max(1))
+        };
+        AnimationTiming {
+            fade_out: duration(self.fade_out_duration),
+            fade_in: duration(self.fade_in_duration),
+            easing: self.animation_easing,
+        }
     }
 
-    pub fn scroll_half_page_down(&mut self, viewport_height: usize) {
-        self.centered_once = false;
-        let half = viewport_height / 2;
-        self.scroll_offset += half;
+    fn start_animation(&mut self) {
+        self.invalidate_rows();
+        self.animation_phase = AnimationPhase::FadeOut;
+        self.animation_progress = 0.0;
+        self.animation_started_at = Instant::now();
     }
 
-    pub fn scroll_left(&mut self) {
-        if !self.line_wrap {
-            self.horizontal_scroll = self.horizontal_scroll.saturating_sub(4);
+    /// Ensure active change is visible if needed (called from views after stepping)
+    pub fn ensure_active_visible_if_needed(&mut self, viewport_height: usize) {
+        if self.handle_search_scroll_if_needed(viewport_height) {
+            return;
+        }
+        if !self.needs_scroll_to_active {
+            return;
+        }
+        if self.auto_center && self.snap_frame.is_some() {
+            return;
         }
+        self.needs_scroll_to_active = false;
+
+        let from = self.scroll_offset;
+        self.scroll_to_active(viewport_height);
+        self.animate_scroll_from(from);
     }
 
-    pub fn scroll_right(&mut self) {
-        if !self.line_wrap {
-            self.horizontal_scroll += 4;
+    fn scroll_to_active(&mut self, viewport_height: usize) {
+        let step_direction = self.multi_diff.current_step_direction();
+        let auto_center = self.auto_center;
+        // If auto_center is enabled, always center on active change
+        if auto_center {
+            self.center_on_active(viewport_height);
+            return;
         }
-    }
 
-    /// Go to start of line (horizontal scroll = 0), like vim's 0
-    pub fn scroll_to_line_start(&mut self) {
-        self.horizontal_scroll = 0;
-    }
+        let frame = self.animation_frame();
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame);
 
-    /// Go to end of line (max horizontal scroll), like vim's $
-    pub fn scroll_to_line_end(&mut self) {
-        if !self.line_wrap {
-            // Set to max, will be clamped during render
-            self.horizontal_scroll = usize::MAX / 2;
+        let (display_len, display_idx) = display_metrics(
+            &view,
+            self.view_mode,
+            self.animation_phase,
+            self.scroll_offset,
+            step_direction,
+        );
+
+        if let Some(idx) = display_idx {
+            let margin = 3.min(viewport_height / 4);
+
+            // Check if active line is above viewport
+            if idx < self.scroll_offset.saturating_add(margin) {
+                self.scroll_offset = idx.saturating_sub(margin);
+            }
+            // Check if active line is below viewport
+            else if idx
+                >= self
+                    .scroll_offset
+                    .saturating_add(viewport_height.saturating_sub(margin))
+            {
+                self.scroll_offset = idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
+            }
+        } else if display_len > 0 {
+            let state = self.multi_diff.current_navigator().state();
+            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
+                return;
+            }
+            // No active line (step 0); snap to top so "first step" is visible.
+            self.scroll_offset = 0;
         }
     }
 
-    /// Clamp horizontal scroll so we don't scroll too far right
-    pub fn clamp_horizontal_scroll(&mut self, max_line_width: usize, viewport_width: usize) {
-        if !self.line_wrap {
-            let max_scroll = max_line_width.saturating_sub(viewport_width);
-            self.horizontal_scroll = self.horizontal_scroll.min(max_scroll);
+    pub fn handle_search_scroll_if_needed(&mut self, viewport_height: usize) -> bool {
+        if !self.needs_scroll_to_search {
+            return false;
+        }
+        self.needs_scroll_to_search = false;
+        if let Some(idx) = self.search_target {
+            if self.auto_center {
+                let half_viewport = viewport_height / 2;
+                self.scroll_offset = idx.saturating_sub(half_viewport);
+                self.centered_once = true;
+            } else {
+                let margin = 3.min(viewport_height / 4);
+                if idx < self.scroll_offset.saturating_add(margin) {
+                    self.scroll_offset = idx.saturating_sub(margin);
+                } else if idx
+                    >= self
+                        .scroll_offset
+                        .saturating_add(viewport_height.saturating_sub(margin))
+                {
+                    self.scroll_offset =
+                        idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
+                }
+            }
         }
+        true
     }
 
-    pub fn clamp_horizontal_scroll_cached(&mut self, viewport_width: usize) {
-        if self.line_wrap {
+    pub fn ensure_active_visible_if_needed_wrapped(
+        &mut self,
+        viewport_height: usize,
+        display_len: usize,
+        display_idx: Option<usize>,
+    ) {
+        self.last_wrap_display_len = Some(display_len);
+        self.last_wrap_active_idx = display_idx;
+
+        if !self.needs_scroll_to_active {
             return;
         }
-        let max_line_width = self.current_max_line_width();
-        if max_line_width == 0 {
+        if self.auto_center && self.snap_frame.is_some() {
             return;
         }
-        let max_scroll = max_line_width.saturating_sub(viewport_width);
-        self.horizontal_scroll = self.horizontal_scroll.min(max_scroll);
+        self.needs_scroll_to_active = false;
+
+        let from = self.scroll_offset;
+        self.scroll_to_active_wrapped(viewport_height, display_len, display_idx);
+        self.animate_scroll_from(from);
     }
 
-    pub fn reset_current_max_line_width(&mut self) {
-        let idx = self.multi_diff.selected_index;
-        if self.stepping {
-            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
-                *slot = 0;
+    fn scroll_to_active_wrapped(
+        &mut self,
+        viewport_height: usize,
+        display_len: usize,
+        display_idx: Option<usize>,
+    ) {
+        if self.auto_center {
+            self.center_with_display_idx(viewport_height, display_len, display_idx);
+            return;
+        }
+
+        if let Some(idx) = display_idx {
+            let margin = 3.min(viewport_height / 4);
+
+            if idx < self.scroll_offset.saturating_add(margin) {
+                self.scroll_offset = idx.saturating_sub(margin);
+            } else if idx
+                >= self
+                    .scroll_offset
+                    .saturating_add(viewport_height.saturating_sub(margin))
+            {
+                self.scroll_offset = idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
             }
-        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
-            *slot = 0;
+        } else if display_len > 0 {
+            let state = self.multi_diff.current_navigator().state();
+            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
+                return;
+            }
+            self.scroll_offset = 0;
         }
     }
 
-    pub fn set_current_max_line_width(&mut self, max_line_width: usize) {
-        let idx = self.multi_diff.selected_index;
-        if self.stepping {
-            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
-                *slot = max_line_width;
+    fn center_with_display_idx(
+        &mut self,
+        viewport_height: usize,
+        display_len: usize,
+        display_idx: Option<usize>,
+    ) {
+        if let Some(idx) = display_idx {
+            let half_viewport = viewport_height / 2;
+            self.scroll_offset = idx.saturating_sub(half_viewport);
+        } else if display_len > 0 {
+            let state = self.multi_diff.current_navigator().state();
+            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
+                return;
             }
-        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
-            *slot = max_line_width;
+            self.scroll_offset = 0;
         }
+
+        self.centered_once = true;
+        self.horizontal_scroll = 0;
     }
 
-    pub fn update_current_max_line_width(&mut self, max_line_width: usize) {
-        let idx = self.multi_diff.selected_index;
-        if self.stepping {
-            if let Some(slot) = self.max_line_widths_step.get_mut(idx) {
-                *slot = (*slot).max(max_line_width);
+    /// Ease from `from` to the scroll offset just set for a step, showing
+    /// `from` until the next tick; a jump of one row is not animated
+    fn animate_scroll_from(&mut self, from: usize) {
+        let to = self.scroll_offset;
+        if !self.animations_active() || self.scroll_duration == 0 || from.abs_diff(to) <= 1 {
+            self.scroll_animation = None;
+            return;
+        }
+        self.scroll_animation = Some(ScrollAnimation {
+            from,
+            to,
+            started_at: Instant::now(),
+            current: from,
+        });
+        self.scroll_offset = from;
+    }
+
+    fn tick_scroll_animation(&mut self, now: Instant) {
+        let Some(animation) = self.scroll_animation.as_mut() else {
+            return;
+        };
+        if self.scroll_offset != animation.current {
+            self.scroll_animation = None;
+            return;
+        }
+        let duration = Duration::from_millis(self.scroll_duration.max(1));
+        let linear = (now.duration_since(animation.started_at).as_secs_f32()
+            / duration.as_secs_f32())
+        .min(1.0);
+        let progress = self.animation_easing.apply(linear);
+        let distance = animation.to as f32 - animation.from as f32;
+        let offset = (animation.from as f32 + distance * progress)
+            .round()
+            .max(0.0) as usize;
+        animation.current = offset;
+        self.scroll_offset = offset;
+        if linear >= 1.0 {
+            self.scroll_offset = animation.to;
+            self.scroll_animation = None;
+        }
+    }
+
+    /// Center the viewport on the active change (like Vim's zz)
+    pub fn center_on_active(&mut self, viewport_height: usize) {
+        if self.line_wrap {
+            if let Some(display_len) = self.last_wrap_display_len {
+                let display_idx = self.last_wrap_active_idx;
+                self.center_with_display_idx(viewport_height, display_len, display_idx);
+                return;
             }
-        } else if let Some(slot) = self.max_line_widths_no_step.get_mut(idx) {
-            *slot = (*slot).max(max_line_width);
         }
+
+        let frame = self.animation_frame();
+        let view = self
+            .multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame);
+        let step_direction = self.multi_diff.current_step_direction();
+
+        let (display_len, display_idx) = display_metrics(
+            &view,
+            self.view_mode,
+            self.animation_phase,
+            self.scroll_offset,
+            step_direction,
+        );
+
+        self.center_with_display_idx(viewport_height, display_len, display_idx);
     }
 
-    fn current_max_line_width(&self) -> usize {
-        let idx = self.multi_diff.selected_index;
-        if self.stepping {
-            self.max_line_widths_step.get(idx).copied().unwrap_or(0)
-        } else {
-            self.max_line_widths_no_step.get(idx).copied().unwrap_or(0)
+    /// Called every frame to update animations and autoplay
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+
+        if self.toasts.expire(now) {
+            self.redraw_requested = true;
         }
-    }
 
-    pub fn toggle_line_wrap(&mut self) {
-        self.line_wrap = !self.line_wrap;
-        // Reset horizontal scroll when enabling wrap
-        if self.line_wrap {
-            self.horizontal_scroll = 0;
+        let echoed = self.key_echo.len();
+        self.key_echo
+            .retain(|(_, _, at)| now.duration_since(*at) < KEY_ECHO_DURATION);
+        if self.key_echo.len() != echoed {
+            self.redraw_requested = true;
         }
-        self.last_wrap_display_len = None;
-        self.last_wrap_active_idx = None;
-    }
 
-    pub fn toggle_strikethrough_deletions(&mut self) {
-        self.strikethrough_deletions = !self.strikethrough_deletions;
-    }
+        if let Some(frame) = self.snap_frame {
+            let started_at = self.snap_frame_started_at.get_or_insert(now);
+            let phase_duration = Duration::from_millis(SNAP_PHASE_MS);
+            if now.duration_since(*started_at) >= phase_duration {
+                match frame {
+                    AnimationFrame::FadeOut => {
+                        self.snap_frame = Some(AnimationFrame::FadeIn);
+                        self.snap_frame_started_at = Some(now);
+                    }
+                    AnimationFrame::FadeIn | AnimationFrame::Idle => {
+                        self.snap_frame = None;
+                        self.snap_frame_started_at = None;
+                        let step_dir = self.multi_diff.current_navigator().state().step_direction;
+                        if step_dir == StepDirection::Backward {
+                            self.multi_diff.current_navigator().clear_active_change();
+                        }
+                    }
+                }
+            }
+        }
 
-    pub fn increase_speed(&mut self) {
-        self.animation_speed = (self.animation_speed + 50).min(2000);
-    }
+        self.tick_scroll_animation(now);
 
-    pub fn decrease_speed(&mut self) {
-        self.animation_speed = self.animation_speed.saturating_sub(50).max(50);
-    }
+        // Update animation
+        if self.animation_phase != AnimationPhase::Idle {
+            let elapsed = now.duration_since(self.animation_started_at);
+            let (frame, progress) = self.animation_timing().frame_at(elapsed);
+            self.animation_progress = progress;
+            match frame {
+                AnimationFrame::FadeOut => self.animation_phase = AnimationPhase::FadeOut,
+                AnimationFrame::FadeIn => self.animation_phase = AnimationPhase::FadeIn,
+                AnimationFrame::Idle => {
+                    self.animation_phase = AnimationPhase::Idle;
+
+                    // If this was a backward animation, clear the active change
+                    // so un-applied insertions properly disappear
+                    let step_dir = self.multi_diff.current_navigator().state().step_direction;
+                    if step_dir == StepDirection::Backward {
+                        self.multi_diff.current_navigator().clear_active_change();
+                    }
+                }
+            }
 
-    // File navigation methods
-    pub fn next_file(&mut self) {
-        if !self.file_filter.is_empty() {
-            let indices = self.filtered_file_indices();
-            if indices.is_empty() {
-                return;
+            if self.multi_diff.has_subscribers() {
+                self.multi_diff.emit(NavEvent::AnimationTick {
+                    frame: self.animation_frame(),
+                    progress: self.animation_progress,
+                });
             }
-            let current = self.multi_diff.selected_index;
-            let pos = indices.iter().position(|&i| i == current);
-            let next_index = match pos {
-                Some(p) if p + 1 < indices.len() => indices[p + 1],
-                None => indices[0],
-                _ => return,
-            };
-            self.select_file(next_index);
-            return;
         }
 
-        // Save current scroll positions
-        let old_index = self.multi_diff.selected_index;
-        if self.multi_diff.next_file() {
-            if !self.stepping {
-                self.save_no_step_state_snapshot(old_index);
+        // Handle autoplay
+        if self.stepping && self.autoplay && self.animation_phase == AnimationPhase::Idle {
+            let autoplay_interval = Duration::from_millis(self.animation_speed * 2);
+            if now.duration_since(self.last_autoplay_tick) >= autoplay_interval {
+                let moved = self.step_filtered(!self.autoplay_reverse);
+                if !moved {
+                    self.autoplay = false;
+                }
+                self.last_autoplay_tick = now;
             }
-            self.save_scroll_position_for(old_index);
-            // Restore scroll positions for new file
-            self.restore_scroll_position_for(self.multi_diff.selected_index);
-            self.animation_phase = AnimationPhase::Idle;
-            self.animation_progress = 1.0;
-            self.reset_search_for_file_switch();
-            self.update_file_list_scroll();
-            self.centered_once = false;
-            self.handle_file_enter();
         }
-    }
 
-    pub fn prev_file(&mut self) {
-        if !self.file_filter.is_empty() {
-            let indices = self.filtered_file_indices();
-            if indices.is_empty() {
-                return;
+        // Follow bisect: reload when the candidate (HEAD) moves
+        if self.multi_diff.is_bisect()
+            && now.duration_since(self.last_bisect_check) >= Duration::from_millis(BISECT_POLL_MS)
+        {
+            self.last_bisect_check = now;
+            let head = self
+                .multi_diff
+                .repo_root()
+                .and_then(|root| oyo_core::git::get_head_commit(root).ok());
+            if head.is_some() && head.as_deref() != self.multi_diff.bisect_candidate() {
+                self.refresh_all_files();
             }
-            let current = self.multi_diff.selected_index;
-            let pos = indices.iter().position(|&i| i == current);
-            let prev_index = match pos {
-                Some(p) if p > 0 => indices[p - 1],
-                None => indices[indices.len().saturating_sub(1)],
-                _ => return,
-            };
-            self.select_file(prev_index);
-            return;
         }
 
-        // Save current scroll positions
-        let old_index = self.multi_diff.selected_index;
-        if self.multi_diff.prev_file() {
-            if !self.stepping {
-                self.save_no_step_state_snapshot(old_index);
+        self.apply_overlay_messages();
+        self.exchange_plugin_messages();
+        self.collect_test_runs();
+        self.start_churn();
+        self.collect_churn();
+
+        // Warn when the shown file changed on disk
+        if now.duration_since(self.last_stale_check) >= Duration::from_millis(STALE_POLL_MS) {
+            self.last_stale_check = now;
+            let idx = self.multi_diff.selected_index;
+            let stale = (idx < self.multi_diff.file_count() && self.multi_diff.is_stale(idx))
+                .then_some(idx);
+            if stale != self.stale_file {
+                self.stale_file = stale;
+                self.redraw_requested = true;
+                if stale.is_some() {
+                    let path = self.current_file_path();
+                    self.notify(Level::Warning, format!("{path} changed on disk"));
+                }
             }
-            self.save_scroll_position_for(old_index);
-            // Restore scroll positions for new file
-            self.restore_scroll_position_for(self.multi_diff.selected_index);
-            self.animation_phase = AnimationPhase::Idle;
-            self.animation_progress = 1.0;
-            self.reset_search_for_file_switch();
-            self.update_file_list_scroll();
-            self.centered_once = false;
-            self.handle_file_enter();
         }
     }
 
-    pub fn select_file(&mut self, index: usize) {
-        let old_index = self.multi_diff.selected_index;
-        if !self.stepping {
-            self.save_no_step_state_snapshot(old_index);
+    /// Apply what the plugins sent, and tell them about a new file or step
+    fn exchange_plugin_messages(&mut self) {
+        if self.plugins.is_empty() {
+            return;
         }
-        self.save_scroll_position_for(old_index);
-        self.multi_diff.select_file(index);
-        self.restore_scroll_position_for(self.multi_diff.selected_index);
-        self.animation_phase = AnimationPhase::Idle;
-        self.animation_progress = 1.0;
-        self.reset_search_for_file_switch();
-        self.centered_once = false;
-        self.update_file_list_scroll();
-        self.handle_file_enter();
+        if self.plugins.poll() {
+            self.redraw_requested = true;
+        }
+        if let Some(notice) = self.plugins.take_notice() {
+            self.notify(Level::Info, notice);
+        }
+        let idx = self.multi_diff.selected_index;
+        let Some(file) = self.multi_diff.files.get(idx) else {
+            return;
+        };
+        let path = file.path.display().to_string();
+        let status = format!("{:?}", file.status).to_ascii_lowercase();
+        let state = self.multi_diff.current_navigator().state();
+        let position = (idx, state.current_step);
+        let total = state.total_steps;
+        if self.plugin_position == Some(position) {
+            return;
+        }
+        if self.plugin_position.map(|(file, _)| file) != Some(idx) {
+            self.plugins.notify(
+                "fileOpened",
+                serde_json::json!({ "path": path, "index": idx, "status": status }),
+            );
+        }
+        self.plugins.notify(
+            "stepChanged",
+            serde_json::json!({ "path": path, "step": position.1, "total": total }),
+        );
+        self.plugin_position = Some(position);
     }
 
-    pub fn start_file_filter(&mut self) {
-        self.file_filter_active = true;
-        self.file_filter.clear();
-        self.file_list_scroll = 0;
-        self.ensure_selection_matches_filter();
-        self.update_file_list_scroll();
+    /// Apply unsaved editor buffers received from `rx`
+    pub fn listen_for_overlays(&mut self, rx: Receiver<OverlayMessage>) {
+        self.overlay_rx = Some(rx);
     }
 
-    pub fn stop_file_filter(&mut self) {
-        self.file_filter_active = false;
+    fn apply_overlay_messages(&mut self) {
+        let Some(rx) = &self.overlay_rx else {
+            return;
+        };
+        let messages: Vec<OverlayMessage> = rx.try_iter().collect();
+        for message in messages {
+            let file_count = self.multi_diff.file_count();
+            let anchor = self.scroll_anchor();
+            let idx = match message {
+                OverlayMessage::Overlay { path, content } => {
+                    self.multi_diff.set_overlay(&path, content)
+                }
+                OverlayMessage::Clear { path } => self.multi_diff.clear_overlay(&path),
+            };
+            let Some(idx) = idx else {
+                continue;
+            };
+            self.redraw_requested = true;
+            if self.multi_diff.file_count() != file_count {
+                self.apply_file_sort();
+                self.reset_file_states();
+                self.handle_file_enter();
+            } else if idx == self.multi_diff.selected_index {
+                self.invalidate_syntax(Some(SyntaxSide::New));
+                self.needs_scroll_to_active = true;
+                self.restore_scroll_anchor(anchor);
+            } else if let Some(cache) = self.syntax_caches.get_mut(idx) {
+                *cache = None;
+            }
+        }
     }
 
-    pub fn push_file_filter_char(&mut self, ch: char) {
-        self.file_filter.push(ch);
-        self.on_filter_changed();
+    /// Directory name and branch of the current file's repository, when
+    /// several repositories are open
+    pub fn current_repo(&mut self) -> Option<(String, Option<String>)> {
+        if !self.multi_diff.is_workspace() {
+            return None;
+        }
+        let root = self
+            .multi_diff
+            .file_repo_root(self.multi_diff.selected_index)?;
+        let name = root.file_name()?.to_string_lossy().to_string();
+        let branch = self
+            .repo_branches
+            .entry(root)
+            .or_insert_with_key(|root| oyo_core::git::get_current_branch(root).ok())
+            .clone();
+        Some((name, branch))
     }
 
-    pub fn pop_file_filter_char(&mut self) {
-        self.file_filter.pop();
-        self.on_filter_changed();
+    /// True if the current file changed on disk since it was loaded
+    pub fn current_file_stale(&self) -> bool {
+        self.stale_file == Some(self.multi_diff.selected_index)
     }
 
-    pub fn clear_file_filter(&mut self) {
-        self.file_filter.clear();
-        self.on_filter_changed();
+    /// Get the total number of lines in the current view
+    #[allow(dead_code)]
+    pub fn total_lines(&mut self) -> usize {
+        let frame = self.animation_frame();
+        self.multi_diff
+            .current_navigator()
+            .current_view_with_frame(frame)
+            .len()
     }
 
-    /// Check if current file would be blank at step 0 (new file: empty old, non-empty new)
-    fn is_blank_at_step0(&self) -> bool {
-        self.multi_diff.current_old_is_empty() && !self.multi_diff.current_new_is_empty()
+    /// Get statistics about the current file's diff
+    pub fn stats(&mut self) -> (usize, usize) {
+        let diff = self.multi_diff.current_navigator().diff();
+        (diff.insertions, diff.deletions)
     }
 
-    /// Handle entering a file (marks visited, optionally auto-steps to first change)
-    /// Called on initial file and when switching files.
-    pub fn handle_file_enter(&mut self) {
-        let idx = self.multi_diff.selected_index;
-
-        if !self.stepping {
-            if !self.files_visited[idx] {
-                self.files_visited[idx] = true;
-            }
-            // If in no-step mode, ensure full content is shown immediately
-            self.ensure_step_state_snapshot(idx);
-            self.multi_diff.current_navigator().goto_end();
-            self.multi_diff.current_navigator().clear_active_change();
-            self.animation_phase = AnimationPhase::Idle;
-            self.animation_progress = 1.0;
-            if !self.restore_no_step_state_snapshot(idx) {
-                self.set_cursor_for_current_scroll();
-                self.multi_diff.current_navigator().set_hunk_scope(false);
+    /// Header bar facts of the current file; formats are detected once per
+    /// file and again when its content changes
+    pub fn file_header(&mut self) -> FileHeader {
+        let file_index = self.multi_diff.selected_index;
+        let (insertions, deletions) = self.stats();
+        let status = self
+            .multi_diff
+            .current_file()
+            .map(|file| self.multi_diff.shown_status(file))
+            .unwrap_or(FileStatus::Modified);
+        let nav = self.multi_diff.current_navigator();
+        let sizes = (nav.old_content().len(), nav.new_content().len());
+        let highlighting = self.syntax_engine.is_some();
+        if let Some((index, named, header)) = &mut self.file_header_cache {
+            let cached = (
+                header.old.map_or(0, |(_, size)| size),
+                header.new.map_or(0, |(_, size)| size),
+            );
+            // The language is named by extension until syntax highlighting
+            // is loaded
+            if *index == file_index
+                && *named == highlighting
+                && header.status == status
+                && cached == sizes
+            {
+                header.insertions = insertions;
+                header.deletions = deletions;
+                return header.clone();
             }
-            self.no_step_visited[idx] = true;
-            // Don't mess with scroll_offset here; it might have been restored by next_file/prev_file
-            return;
-        }
-
-        // Only process on first visit to this file
-        if self.files_visited[idx] {
-            return;
         }
+        let path = self.current_file_path();
+        let language = match &self.syntax_engine {
+            Some(engine) => Some(engine.syntax_name_for_file(&path).to_string())
+                .filter(|name| name != "Plain Text"),
+            None => Path::new(&path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_uppercase()),
+        };
+        let nav = self.multi_diff.current_navigator();
+        let side = |content: &str| (TextFormat::detect(content), content.len());
+        let old = side(nav.old_content());
+        let new = side(nav.new_content());
+        let header = FileHeader {
+            path,
+            status,
+            language,
+            old: (!matches!(status, FileStatus::Added | FileStatus::Untracked)).then_some(old),
+            new: (status != FileStatus::Deleted).then_some(new),
+            insertions,
+            deletions,
+        };
+        self.file_header_cache = Some((file_index, highlighting, header.clone()));
+        header
+    }
 
-        // Mark as visited
-        self.files_visited[idx] = true;
-
-        let state = self.multi_diff.current_navigator().state();
-        let at_step_0 = state.current_step == 0;
-        let has_steps = state.total_steps > 1;
-
-        if !at_step_0 || !has_steps {
-            return;
+    /// Current position, saved if oy crashes
+    pub fn session(&mut self) -> crash::Session {
+        crash::Session {
+            file: self.current_file_path(),
+            step: self.multi_diff.current_navigator().state().current_step,
         }
+    }
 
-        // Auto-step for blank files (new files) regardless of view mode
-        if self.auto_step_blank_files && self.is_blank_at_step0() {
-            self.next_step();
-            return;
-        }
+    /// Go back to a saved position; false if its file is not in this diff
+    pub fn restore_session(&mut self, session: &crash::Session) -> bool {
+        let Some(index) = self
+            .multi_diff
+            .files
+            .iter()
+            .position(|file| file.display_name == session.file)
+        else {
+            return false;
+        };
+        self.select_file(index);
+        self.goto_step_number(session.step + 1);
+        true
+    }
 
-        // Regular auto-step on enter (not for Evolution mode)
-        if self.auto_step_on_enter && self.view_mode != ViewMode::Evolution {
-            self.next_step();
-        }
+    /// Get current file path for display
+    pub fn current_file_path(&self) -> String {
+        self.multi_diff
+            .current_file()
+            .map(|f| f.display_name.clone())
+            .unwrap_or_default()
     }
 
-    fn active_scroll_buffers(&self) -> (&Vec<usize>, &Vec<usize>) {
-        if self.stepping {
-            (&self.scroll_offsets_step, &self.horizontal_scrolls_step)
+    /// Refresh current file from disk
+    pub fn refresh_current_file(&mut self) {
+        let anchor = self.scroll_anchor();
+        let idx = self.multi_diff.selected_index;
+        let old_before = self
+            .multi_diff
+            .file_contents(idx)
+            .map(|(old, _)| old.to_string());
+        self.multi_diff.refresh_current_file();
+        self.stale_file = None;
+        self.heat_ages.remove(&idx);
+        self.rename_counts = None;
+        // The old side rarely changes on refresh; keep its highlighting then
+        let old_changed =
+            self.multi_diff.file_contents(idx).map(|(old, _)| old) != old_before.as_deref();
+        self.invalidate_syntax(if old_changed {
+            None
         } else {
-            (
-                &self.scroll_offsets_no_step,
-                &self.horizontal_scrolls_no_step,
-            )
-        }
+            Some(SyntaxSide::New)
+        });
+        self.scroll_offset = 0;
+        self.horizontal_scroll = 0;
+        self.centered_once = false;
+        self.needs_scroll_to_active = true;
+        self.restore_scroll_anchor(anchor);
     }
 
-    fn active_scroll_buffers_mut(&mut self) -> (&mut Vec<usize>, &mut Vec<usize>) {
-        if self.stepping {
-            (
-                &mut self.scroll_offsets_step,
-                &mut self.horizontal_scrolls_step,
-            )
-        } else {
-            (
-                &mut self.scroll_offsets_no_step,
-                &mut self.horizontal_scrolls_no_step,
-            )
+    /// Where the top row of the viewport is, by the line it shows, to keep
+    /// it there when the view is rebuilt; `None` when wrapping, where rows
+    /// are not view lines
+    fn scroll_anchor(&mut self) -> Option<ViewAnchor> {
+        if self.line_wrap {
+            return None;
         }
+        ViewAnchor::at(&self.display_view(), self.scroll_offset)
     }
 
-    fn save_scroll_position_for(&mut self, index: usize) {
-        let scroll_offset = self.scroll_offset;
-        let horizontal_scroll = self.horizontal_scroll;
-        let (scrolls, horizontals) = self.active_scroll_buffers_mut();
-        if let Some(slot) = scrolls.get_mut(index) {
-            *slot = scroll_offset;
-        }
-        if let Some(slot) = horizontals.get_mut(index) {
-            *slot = horizontal_scroll;
+    /// Scroll back to `anchor` in the rebuilt view instead of jumping to
+    /// the active change
+    fn restore_scroll_anchor(&mut self, anchor: Option<ViewAnchor>) {
+        let Some(row) = anchor.and_then(|anchor| anchor.resolve(&self.display_view())) else {
+            return;
+        };
+        self.scroll_offset = row;
+        self.needs_scroll_to_active = false;
+        if !self.stepping {
+            self.set_cursor_for_current_scroll();
         }
     }
 
-    fn restore_scroll_position_for(&mut self, index: usize) {
-        let (scrolls, horizontals) = self.active_scroll_buffers();
-        let scroll_value = scrolls.get(index).copied();
-        let horizontal_value = horizontals.get(index).copied();
-        if let Some(value) = scroll_value {
-            self.scroll_offset = value;
-        }
-        if let Some(value) = horizontal_value {
-            self.horizontal_scroll = value;
+    /// Lines of the current view, one per row of the view mode (the new
+    /// pane's rows in split view)
+    fn display_view(&mut self) -> Vec<ViewLine> {
+        let mut view = self.multi_diff.current_navigator().current_view();
+        match self.view_mode {
+            ViewMode::SinglePane => {}
+            ViewMode::Evolution => view
+                .retain(|line| !matches!(line.kind, LineKind::Deleted | LineKind::PendingDelete)),
+            ViewMode::Split => view.retain(|line| line.new_line.is_some()),
         }
+        view
     }
 
-    fn save_step_state_snapshot(&mut self, index: usize) {
-        let state = self.multi_diff.current_navigator().state().clone();
-        if let Some(slot) = self.step_state_snapshots.get_mut(index) {
-            *slot = Some(state);
+    /// Refresh all files from git (re-scan for uncommitted changes)
+    pub fn refresh_all_files(&mut self) {
+        if self.multi_diff.refresh_all_from_git() {
+            self.redraw_requested = true;
+            if self.file_sort != FileSortMode::Path {
+                self.apply_file_sort();
+            }
+            self.stale_file = None;
+            self.reset_file_states();
+            self.handle_file_enter();
         }
     }
 
-    fn restore_step_state_snapshot(&mut self, index: usize) -> bool {
-        let Some(snapshot) = self.step_state_snapshots.get(index).and_then(|s| s.clone()) else {
-            return false;
+    /// Review a patch series; the current diff must show its first patch
+    pub fn set_patch_series(
+        &mut self,
+        patches: Vec<Patch>,
+        repo_root: Option<PathBuf>,
+        fuzz: usize,
+    ) {
+        self.commit_info = patches.first().map(Patch::commit_info);
+        self.patch_series = patches;
+        self.patch_index = 0;
+        self.patch_repo = repo_root;
+        self.patch_fuzz = fuzz;
+    }
+
+    /// Hunks of the current patch that did not apply to the working tree,
+    /// with their file and 0-based hunk index
+    pub fn patch_rejects(&self) -> Vec<(&Path, usize, &PatchHunk)> {
+        let Some(patch) = self.patch_series.get(self.patch_index) else {
+            return Vec::new();
         };
-        self.multi_diff.current_navigator().set_state(snapshot)
+        patch
+            .files
+            .iter()
+            .filter_map(|file| {
+                let path = file.path()?;
+                let check = self.multi_diff.patch_check(path)?;
+                Some(
+                    check
+                        .rejected()
+                        .into_iter()
+                        .filter_map(move |idx| Some((path, idx, file.hunks.get(idx)?))),
+                )
+            })
+            .flatten()
+            .collect()
     }
 
-    fn ensure_step_state_snapshot(&mut self, index: usize) {
-        let needs_snapshot = self
-            .step_state_snapshots
-            .get(index)
-            .map(|slot| slot.is_none())
-            .unwrap_or(false);
-        if !needs_snapshot {
-            return;
-        }
-        let state = self.multi_diff.current_navigator().state().clone();
-        if let Some(slot) = self.step_state_snapshots.get_mut(index) {
-            *slot = Some(state);
-        }
+    pub fn open_patch_rejects(&mut self) {
+        self.open_popup(PopupKind::PatchRejects);
+        self.patch_rejects_scroll = 0;
     }
 
-    fn save_no_step_state_snapshot(&mut self, index: usize) {
-        let state = self.multi_diff.current_navigator().state();
-        if let Some(slot) = self.no_step_state_snapshots.get_mut(index) {
-            *slot = Some(NoStepState {
-                current_hunk: state.current_hunk,
-                cursor_change: state.cursor_change,
-                last_nav_was_hunk: state.last_nav_was_hunk,
-            });
+    /// Review `.rej` files; the current diff must come from
+    /// [`MultiFileDiff::from_rejects`] of the same files
+    pub fn set_reject_files(&mut self, rejects: Vec<RejectFile>) {
+        self.reject_lines.clear();
+        self.placed_rejects.clear();
+        for reject in &rejects {
+            let target = std::fs::read_to_string(&reject.target).unwrap_or_default();
+            for (idx, hunk) in reject.hunks.iter().enumerate() {
+                let line = oyo_core::patch::guess_placement(&target, hunk);
+                self.reject_lines.insert(reject.label(idx), line);
+            }
         }
+        self.reject_files = rejects;
     }
 
-    fn restore_no_step_state_snapshot(&mut self, index: usize) -> bool {
-        let Some(snapshot) = self.no_step_state_snapshots.get(index).and_then(|s| *s) else {
-            return false;
-        };
-        self.multi_diff
-            .current_navigator()
-            .set_cursor_hunk(snapshot.current_hunk, snapshot.cursor_change);
-        self.multi_diff
-            .current_navigator()
-            .set_hunk_scope(snapshot.last_nav_was_hunk);
-        true
+    /// `.rej` file and hunk index of the current entry, if reviewing rejects
+    fn current_reject(&self) -> Option<(usize, usize)> {
+        let name = &self.multi_diff.current_file()?.display_name;
+        self.reject_files
+            .iter()
+            .enumerate()
+            .find_map(|(file_idx, reject)| {
+                (0..reject.hunks.len())
+                    .find(|&idx| reject.label(idx) == *name)
+                    .map(|idx| (file_idx, idx))
+            })
     }
 
-    pub fn is_multi_file(&self) -> bool {
-        self.multi_diff.is_multi_file()
+    /// 1-based target line the current rejected hunk is placed at
+    pub fn reject_placement(&self) -> Option<usize> {
+        let name = &self.multi_diff.current_file()?.display_name;
+        self.current_reject()?;
+        self.reject_lines.get(name).map(|line| line + 1)
     }
 
-    fn update_file_list_scroll(&mut self) {
-        let indices = self.filtered_file_indices();
-        if indices.is_empty() {
-            self.file_list_scroll = 0;
+    /// Move the current rejected hunk to 0-based target line `line`
+    pub fn place_reject(&mut self, line: usize) {
+        let Some((file_idx, idx)) = self.current_reject() else {
+            self.notify(Level::Warning, "no rejected hunk selected");
             return;
-        }
-
-        // Keep selected file visible in the file list
+        };
+        let hunk = &self.reject_files[file_idx].hunks[idx];
+        let target = self
+            .multi_diff
+            .current_navigator()
+            .old_content()
+            .to_string();
+        let line = line.min(target.lines().count());
+        let placed = oyo_core::patch::place_hunk(&target, hunk, line);
+        let label = self.reject_files[file_idx].label(idx);
         let selected = self.multi_diff.selected_index;
-        let selected_pos = indices.iter().position(|&i| i == selected).unwrap_or(0);
-        if selected_pos < self.file_list_scroll {
-            self.file_list_scroll = selected_pos;
-        }
-        // Assume roughly 20 visible files
-        let visible_files = 20;
-        if selected_pos >= self.file_list_scroll + visible_files {
-            self.file_list_scroll = selected_pos.saturating_sub(visible_files - 1);
-        }
-    }
-
-    fn on_filter_changed(&mut self) {
-        self.file_list_scroll = 0;
-        self.ensure_selection_matches_filter();
-        self.update_file_list_scroll();
+        self.multi_diff.set_new_content(selected, placed);
+        self.reject_lines.insert(label.clone(), line);
+        self.placed_rejects.insert(label);
+        self.reset_file_states();
+        self.handle_file_enter();
     }
 
-    fn ensure_selection_matches_filter(&mut self) {
-        if self.file_filter.is_empty() {
+    /// Write the current rejected hunk into its target at its placement and
+    /// drop it from the `.rej` file
+    pub fn apply_reject(&mut self) {
+        let Some((file_idx, idx)) = self.current_reject() else {
+            self.notify(Level::Warning, "no rejected hunk selected");
+            return;
+        };
+        let nav = self.multi_diff.current_navigator();
+        let (old, new) = (nav.old_content().to_string(), nav.new_content().to_string());
+        let reject = &mut self.reject_files[file_idx];
+        if let Err(err) =
+            oyo_core::writeback::write_file(&reject.target, &new, Some(&old), Default::default())
+        {
+            self.notify(Level::Error, err.to_string());
             return;
         }
-        let indices = self.filtered_file_indices();
-        if indices.is_empty() {
+        let target = reject.target.display().to_string();
+        reject.hunks.remove(idx);
+        // The remaining hunks are numbered and placed anew
+        self.placed_rejects.clear();
+        if let Err(err) = reject.save() {
+            let message = format!("{}: {err}", reject.path.display());
+            self.notify(Level::Error, message);
             return;
         }
-        if !indices.contains(&self.multi_diff.selected_index) {
-            self.select_file(indices[0]);
+        self.reject_files.retain(|reject| !reject.hunks.is_empty());
+        if self.reject_files.is_empty() {
+            self.notify(
+                Level::Success,
+                format!("applied to {target} · no rejected hunks left"),
+            );
+            return;
         }
-    }
 
-    pub fn filtered_file_indices(&self) -> Vec<usize> {
-        if self.file_filter.is_empty() {
-            return (0..self.multi_diff.files.len()).collect();
+        let selected = self.multi_diff.selected_index;
+        let mut multi_diff = MultiFileDiff::from_rejects(&self.reject_files);
+        multi_diff.inherit_settings(&self.multi_diff);
+        self.multi_diff = multi_diff;
+        let rejects = std::mem::take(&mut self.reject_files);
+        self.set_reject_files(rejects);
+        self.notify(Level::Success, format!("applied to {target}"));
+        self.apply_file_sort();
+        self.reset_file_states();
+        let last = self.multi_diff.file_count().saturating_sub(1);
+        self.select_file(selected.min(last));
+    }
+
+    /// Current patch and series length (1-based), if reviewing a series
+    pub fn patch_position(&self) -> Option<(usize, usize)> {
+        (!self.patch_series.is_empty()).then(|| (self.patch_index + 1, self.patch_series.len()))
+    }
+
+    pub fn next_patch(&mut self) {
+        if let Some(preview) = &self.split_preview {
+            if preview.index + 1 < preview.plan.len() {
+                self.show_split_commit(preview.index + 1);
+            }
+            return;
+        }
+        if self.patch_index + 1 < self.patch_series.len() {
+            self.select_patch(self.patch_index + 1);
         }
-        let query = self.file_filter.to_ascii_lowercase();
-        self.multi_diff
-            .files
-            .iter()
-            .enumerate()
-            .filter(|(_, file)| file.display_name.to_ascii_lowercase().contains(&query))
-            .map(|(idx, _)| idx)
-            .collect()
     }
 
-    fn start_animation(&mut self) {
-        self.animation_phase = AnimationPhase::FadeOut;
-        self.animation_progress = 0.0;
-        self.last_animation_tick = Instant::now();
+    pub fn prev_patch(&mut self) {
+        if let Some(preview) = &self.split_preview {
+            if preview.index > 0 {
+                self.show_split_commit(preview.index - 1);
+            }
+            return;
+        }
+        if self.patch_index > 0 {
+            self.select_patch(self.patch_index - 1);
+        }
     }
 
-    /// Ensure active change is visible if needed (called from views after stepping)
-    pub fn ensure_active_visible_if_needed(&mut self, viewport_height: usize) {
-        if self.handle_search_scroll_if_needed(viewport_height) {
+    fn select_patch(&mut self, index: usize) {
+        let Some(patch) = self.patch_series.get(index) else {
             return;
+        };
+        let mut multi_diff =
+            MultiFileDiff::from_patch(patch, self.patch_repo.as_deref(), self.patch_fuzz);
+        multi_diff.inherit_settings(&self.multi_diff);
+        self.multi_diff = multi_diff;
+        self.commit_info = Some(patch.commit_info());
+        self.patch_index = index;
+        self.apply_file_sort();
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
+
+    /// Reset per-file scroll and step state after the diffs were rebuilt
+    fn reset_file_states(&mut self) {
+        let file_count = self.multi_diff.file_count();
+        self.scroll_offsets_step = vec![0; file_count];
+        self.scroll_offsets_no_step = vec![0; file_count];
+        self.horizontal_scrolls_step = vec![0; file_count];
+        self.horizontal_scrolls_no_step = vec![0; file_count];
+        self.max_line_widths_step = vec![0; file_count];
+        self.max_line_widths_no_step = vec![0; file_count];
+        self.no_step_visited = vec![false; file_count];
+        self.files_visited = vec![false; file_count];
+        self.syntax_caches = vec![None; file_count];
+        self.step_state_snapshots = vec![None; file_count];
+        self.no_step_state_snapshots = vec![None; file_count];
+        self.scroll_offset = 0;
+        self.horizontal_scroll = 0;
+        self.needs_scroll_to_active = true;
+        self.centered_once = false;
+        self.hunk_groups = None;
+        self.heat_ages.clear();
+        self.rename_counts = None;
+        self.churn.clear();
+        self.churn_pending = None;
+    }
+
+    /// Step to the next hunk of the suggested logical groups, continuing
+    /// into the next group (starts the group review on first use). With
+    /// the riskiest-first file sort, the review walks every hunk riskiest
+    /// first instead.
+    pub fn next_group_hunk(&mut self) {
+        if self.hunk_groups.is_none() {
+            let groups = match self.file_sort {
+                FileSortMode::Risk => self.multi_diff.risk_queue(),
+                _ => self.multi_diff.hunk_groups(),
+            };
+            self.hunk_groups = Some(GroupReview {
+                groups,
+                cursor: None,
+            });
         }
-        if !self.needs_scroll_to_active {
+        let Some(review) = &mut self.hunk_groups else {
             return;
+        };
+        let next = match review.cursor {
+            None => (!review.groups.is_empty()).then_some((0, 0)),
+            Some((group, hunk)) if hunk + 1 < review.groups[group].hunks.len() => {
+                Some((group, hunk + 1))
+            }
+            Some((group, _)) => (group + 1 < review.groups.len()).then_some((group + 1, 0)),
+        };
+        if let Some(next) = next {
+            self.select_group_hunk(next);
         }
-        if self.auto_center && self.snap_frame.is_some() {
+    }
+
+    pub fn prev_group_hunk(&mut self) {
+        let Some(GroupReview {
+            groups,
+            cursor: Some((group, hunk)),
+        }) = &self.hunk_groups
+        else {
+            self.next_group_hunk();
             return;
+        };
+        let prev = if *hunk > 0 {
+            Some((*group, hunk - 1))
+        } else if *group > 0 {
+            Some((group - 1, groups[group - 1].hunks.len().saturating_sub(1)))
+        } else {
+            None
+        };
+        if let Some(prev) = prev {
+            self.select_group_hunk(prev);
         }
-        self.needs_scroll_to_active = false;
+    }
 
-        let step_direction = self.multi_diff.current_step_direction();
-        let auto_center = self.auto_center;
-        // If auto_center is enabled, always center on active change
-        if auto_center {
-            self.center_on_active(viewport_height);
+    fn select_group_hunk(&mut self, (group, hunk): (usize, usize)) {
+        let Some(review) = &mut self.hunk_groups else {
+            return;
+        };
+        let Some(target) = review
+            .groups
+            .get(group)
+            .and_then(|g| g.hunks.get(hunk))
+            .copied()
+        else {
             return;
+        };
+        review.cursor = Some((group, hunk));
+        if target.file != self.multi_diff.selected_index {
+            self.select_file(target.file);
         }
+        if self.stepping {
+            self.goto_hunk_index(target.hunk);
+        } else {
+            self.goto_hunk_index_scroll(target.hunk);
+        }
+    }
 
-        let frame = self.animation_frame();
-        let view = self
-            .multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame);
-
-        let (display_len, display_idx) = display_metrics(
-            &view,
-            self.view_mode,
-            self.animation_phase,
-            self.scroll_offset,
-            step_direction,
-        );
-
-        if let Some(idx) = display_idx {
-            let margin = 3.min(viewport_height / 4);
+    /// Leave the group review
+    pub fn clear_group_review(&mut self) {
+        self.hunk_groups = None;
+    }
 
-            // Check if active line is above viewport
-            if idx < self.scroll_offset.saturating_add(margin) {
-                self.scroll_offset = idx.saturating_sub(margin);
-            }
-            // Check if active line is below viewport
-            else if idx
-                >= self
-                    .scroll_offset
-                    .saturating_add(viewport_height.saturating_sub(margin))
-            {
-                self.scroll_offset = idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
-            }
-        } else if display_len > 0 {
-            let state = self.multi_diff.current_navigator().state();
-            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
-                return;
+    pub fn group_review_active(&self) -> bool {
+        self.hunk_groups.is_some()
+    }
+
+    /// Group review position: (group, groups, hunk in group, hunks in group,
+    /// reason), all 1-based
+    pub fn group_position(&self) -> Option<(usize, usize, usize, usize, String)> {
+        let review = self.hunk_groups.as_ref()?;
+        let (groups, (group, hunk)) = (&review.groups, review.cursor?);
+        let current = groups.get(group)?;
+        Some((
+            group + 1,
+            groups.len(),
+            hunk + 1,
+            current.hunks.len(),
+            current.reason.label(),
+        ))
+    }
+
+    /// Switch `x` to the bucket named `name`, appending it if new
+    pub fn select_bucket(&mut self, name: &str) {
+        if name.is_empty() {
+            return;
+        }
+        self.active_bucket = match self.buckets.iter().position(|b| b.name == name) {
+            Some(index) => index,
+            None => {
+                self.buckets.push(Bucket::new(name));
+                self.buckets.len() - 1
             }
-            // No active line (step 0); snap to top so "first step" is visible.
-            self.scroll_offset = 0;
+        };
+    }
+
+    /// Collapse the current hunk into a summary row, or expand it again.
+    /// A hunk whose function context is folded unfolds it first.
+    pub fn toggle_hunk_collapsed(&mut self) {
+        let navigator = self.multi_diff.current_navigator();
+        if navigator.state().total_hunks == 0 {
+            return;
         }
+        let hunk = navigator.state().current_hunk;
+        if navigator.is_context_folded(hunk) {
+            navigator.set_context_folded(hunk, false);
+            self.needs_scroll_to_active = true;
+            return;
+        }
+        let collapsed = navigator.is_hunk_collapsed(hunk);
+        navigator.set_hunk_collapsed(hunk, !collapsed);
+        self.needs_scroll_to_active = true;
     }
 
-    pub fn handle_search_scroll_if_needed(&mut self, viewport_height: usize) -> bool {
-        if !self.needs_scroll_to_search {
-            return false;
+    /// Find the functions the current file's hunks are in, once per diff,
+    /// when function context is on
+    pub fn ensure_function_context(&mut self) {
+        if !self.function_context || self.multi_diff.current_navigator().has_hunk_contexts() {
+            return;
         }
-        self.needs_scroll_to_search = false;
-        if let Some(idx) = self.search_target {
-            if self.auto_center {
-                let half_viewport = viewport_height / 2;
-                self.scroll_offset = idx.saturating_sub(half_viewport);
-                self.centered_once = true;
-            } else {
-                let margin = 3.min(viewport_height / 4);
-                if idx < self.scroll_offset.saturating_add(margin) {
-                    self.scroll_offset = idx.saturating_sub(margin);
-                } else if idx
-                    >= self
-                        .scroll_offset
-                        .saturating_add(viewport_height.saturating_sub(margin))
-                {
-                    self.scroll_offset =
-                        idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
-                }
-            }
+        if self.syntax_engine.is_none() {
+            self.syntax_engine = Some(SyntaxEngine::new(&self.syntax_theme, self.theme_is_light));
         }
-        true
+        let file_name = self.current_file_path();
+        let max_lines = self.function_context_lines;
+        let Some(engine) = self.syntax_engine.as_ref() else {
+            return;
+        };
+        let navigator = self.multi_diff.current_navigator();
+        let functions = engine.function_spans(navigator.new_content(), &file_name);
+        let contexts = function_contexts(navigator.diff(), &functions, max_lines);
+        navigator.set_hunk_contexts(contexts);
     }
 
-    pub fn ensure_active_visible_if_needed_wrapped(
-        &mut self,
-        viewport_height: usize,
-        display_len: usize,
-        display_idx: Option<usize>,
-    ) {
-        self.last_wrap_display_len = Some(display_len);
-        self.last_wrap_active_idx = display_idx;
+    /// Collapse or expand all hunks of the current file
+    pub fn set_all_hunks_collapsed(&mut self, collapsed: bool) {
+        self.multi_diff
+            .current_navigator()
+            .set_all_hunks_collapsed(collapsed);
+        self.needs_scroll_to_active = true;
+    }
 
-        if !self.needs_scroll_to_active {
+    /// Add the current hunk to the active bucket, or take it out again.
+    /// A hunk belongs to one bucket at a time.
+    pub fn toggle_bucket_hunk(&mut self) {
+        if self.split_preview.is_some() {
             return;
         }
-        if self.auto_center && self.snap_frame.is_some() {
+        let state = self.multi_diff.current_navigator().state();
+        if state.total_hunks == 0 {
             return;
         }
-        self.needs_scroll_to_active = false;
-
-        if self.auto_center {
-            self.center_with_display_idx(viewport_height, display_len, display_idx);
-            return;
+        let hunk = state.current_hunk;
+        let key = (self.current_file_path_buf(), hunk);
+        if self.buckets.is_empty() {
+            self.buckets.push(Bucket::new("Commit 1"));
+            self.active_bucket = 0;
+        }
+        let active = self.active_bucket;
+        let was_active = self.buckets[active].hunks.contains(&key);
+        for bucket in &mut self.buckets {
+            bucket.hunks.remove(&key);
         }
+        if !was_active {
+            self.buckets[active].hunks.insert(key);
+        }
+    }
 
-        if let Some(idx) = display_idx {
-            let margin = 3.min(viewport_height / 4);
+    fn current_file_path_buf(&self) -> PathBuf {
+        self.multi_diff
+            .current_file()
+            .map(|file| file.path.clone())
+            .unwrap_or_default()
+    }
 
-            if idx < self.scroll_offset.saturating_add(margin) {
-                self.scroll_offset = idx.saturating_sub(margin);
-            } else if idx
-                >= self
-                    .scroll_offset
-                    .saturating_add(viewport_height.saturating_sub(margin))
-            {
-                self.scroll_offset = idx.saturating_sub(viewport_height.saturating_sub(margin + 1));
+    /// Bucket of the current hunk, if any
+    pub fn current_hunk_bucket(&mut self) -> Option<&str> {
+        let hunk = self.multi_diff.current_navigator().state().current_hunk;
+        let key = (self.current_file_path_buf(), hunk);
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.hunks.contains(&key))
+            .map(|bucket| bucket.name.as_str())
+    }
+
+    /// Active bucket name, its hunk count, and its position (1-based)
+    pub fn bucket_position(&self) -> Option<(&str, usize, usize, usize)> {
+        let bucket = self.buckets.get(self.active_bucket)?;
+        Some((
+            &bucket.name,
+            bucket.hunks.len(),
+            self.active_bucket + 1,
+            self.buckets.len(),
+        ))
+    }
+
+    /// Previewed commit (1-based), commit count and message
+    pub fn split_preview_position(&self) -> Option<(usize, usize, &str)> {
+        let preview = self.split_preview.as_ref()?;
+        Some((
+            preview.index + 1,
+            preview.plan.len(),
+            &preview.plan[preview.index].message,
+        ))
+    }
+
+    /// Show the diff of each commit the buckets would create (`{` / `}`)
+    pub fn open_split_preview(&mut self) {
+        if self.split_preview.is_some() {
+            return;
+        }
+        match self.multi_diff.plan_commits(&self.buckets) {
+            Ok(plan) if plan.is_empty() => {
+                self.notify(Level::Warning, "no hunks in buckets");
             }
-        } else if display_len > 0 {
-            let state = self.multi_diff.current_navigator().state();
-            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
-                return;
+            Ok(plan) => {
+                let original = std::mem::replace(
+                    &mut self.multi_diff,
+                    MultiFileDiff::from_entries(Default::default(), Default::default()),
+                );
+                self.split_preview = Some(SplitPreview {
+                    original,
+                    plan,
+                    index: 0,
+                });
+                self.show_split_commit(0);
             }
-            self.scroll_offset = 0;
+            Err(err) => self.notify(Level::Error, err.to_string()),
         }
     }
 
-    fn center_with_display_idx(
-        &mut self,
-        viewport_height: usize,
-        display_len: usize,
-        display_idx: Option<usize>,
-    ) {
-        if let Some(idx) = display_idx {
-            let half_viewport = viewport_height / 2;
-            self.scroll_offset = idx.saturating_sub(half_viewport);
-        } else if display_len > 0 {
-            let state = self.multi_diff.current_navigator().state();
-            if self.view_mode == ViewMode::Evolution && self.stepping && state.current_step > 0 {
-                return;
-            }
-            self.scroll_offset = 0;
+    fn show_split_commit(&mut self, index: usize) {
+        let Some(preview) = &mut self.split_preview else {
+            return;
+        };
+        let Some(commit) = preview.plan.get(index) else {
+            return;
+        };
+        let old = commit
+            .files
+            .iter()
+            .filter(|file| !file.before.is_empty())
+            .map(|file| (file.path.clone(), file.before.clone()))
+            .collect();
+        let new = commit
+            .files
+            .iter()
+            .filter_map(|file| Some((file.path.clone(), file.after.clone()?)))
+            .collect();
+        let mut multi_diff = MultiFileDiff::from_entries(old, new);
+        multi_diff.inherit_settings(&preview.original);
+        preview.index = index;
+        self.multi_diff = multi_diff;
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
+
+    pub fn split_preview_active(&self) -> bool {
+        self.split_preview.is_some()
+    }
+
+    /// Content of the current file at `step` (changes of later steps not
+    /// yet applied)
+    fn content_at_step(&mut self, step: usize) -> String {
+        self.multi_diff.current_navigator().materialize(step)
+    }
+
+    /// Show the current file at step `left` against itself at step `right`,
+    /// side by side (`:compare`)
+    pub fn open_step_compare(&mut self, left: usize, right: usize) {
+        if self.step_compare.is_some() || self.split_preview.is_some() {
+            return;
         }
+        let Some(path) = self.multi_diff.current_file().map(|file| file.path.clone()) else {
+            return;
+        };
+        let total = self.multi_diff.current_navigator().state().total_steps - 1;
+        let (left, right) = (left.min(total), right.min(total));
+        let old = self.content_at_step(left);
+        let new = self.content_at_step(right);
+        let mut multi_diff = MultiFileDiff::from_file_pair(path.clone(), path, old, new);
+        multi_diff.inherit_settings(&self.multi_diff);
+        let original = std::mem::replace(&mut self.multi_diff, multi_diff);
+        self.step_compare = Some(StepCompare {
+            original,
+            view_mode: self.view_mode,
+            steps: (left, right),
+        });
+        self.view_mode = ViewMode::Split;
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
 
-        self.centered_once = true;
-        self.horizontal_scroll = 0;
+    pub fn step_compare_active(&self) -> bool {
+        self.step_compare.is_some()
     }
 
-    /// Center the viewport on the active change (like Vim's zz)
-    pub fn center_on_active(&mut self, viewport_height: usize) {
-        if self.line_wrap {
-            if let Some(display_len) = self.last_wrap_display_len {
-                let display_idx = self.last_wrap_active_idx;
-                self.center_with_display_idx(viewport_height, display_len, display_idx);
-                return;
-            }
+    /// Steps shown by the open A/B compare (left, right)
+    pub fn step_compare_steps(&self) -> Option<(usize, usize)> {
+        self.step_compare.as_ref().map(|compare| compare.steps)
+    }
+
+    /// Leave the A/B compare and return to the compared session
+    pub fn close_step_compare(&mut self) {
+        let Some(compare) = self.step_compare.take() else {
+            return;
+        };
+        self.multi_diff = compare.original;
+        self.view_mode = compare.view_mode;
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
+
+    /// Show the merge result against ours and against theirs, as two
+    /// files: the `--merged` file as resolved so far, or else the automatic
+    /// merge with conflict markers
+    pub fn open_merge_preview(&mut self) {
+        if self.merge_preview.is_some() {
+            return;
         }
+        let Some((base, ours, theirs)) = self.multi_diff.three_way_contents() else {
+            self.notify(Level::Warning, "the merge preview needs a base (--base)");
+            return;
+        };
+        let (base, ours, theirs) = (base.to_string(), ours.to_string(), theirs.to_string());
+        let merged = match &self.merged_path {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(merged) => merged,
+                Err(err) => {
+                    self.notify(Level::Error, format!("{}: {err}", path.display()));
+                    return;
+                }
+            },
+            None => {
+                let (current, incoming) = match &self.merge_labels {
+                    Some(labels) => (labels.current.as_str(), labels.incoming.as_str()),
+                    None => ("ours", "theirs"),
+                };
+                oyo_core::merge::merge(&base, &ours, &theirs, (current, incoming)).text
+            }
+        };
+        let conflicts = oyo_core::merge::count_conflicts(&merged);
 
-        let frame = self.animation_frame();
-        let view = self
+        let name = self
             .multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame);
-        let step_direction = self.multi_diff.current_step_direction();
+            .current_file()
+            .and_then(|file| file.path.file_name())
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let sides = [(Path::new("ours"), ours), (Path::new("theirs"), theirs)];
+        let old = sides
+            .iter()
+            .map(|(side, content)| (side.join(&name), content.clone()))
+            .collect();
+        let new = sides
+            .iter()
+            .map(|(side, _)| (side.join(&name), merged.clone()))
+            .collect();
+        let mut multi_diff = MultiFileDiff::from_entries(old, new);
+        multi_diff.inherit_settings(&self.multi_diff);
+        if multi_diff.file_count() == 0 {
+            self.notify(Level::Info, "the merge result matches both sides");
+            return;
+        }
+        let original = std::mem::replace(&mut self.multi_diff, multi_diff);
+        self.merge_preview = Some(MergePreview {
+            original,
+            conflicts,
+        });
+        if conflicts > 0 {
+            let message = format!("{conflicts} conflicts left in the merge result");
+            self.notify(Level::Warning, message);
+        }
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
 
-        let (display_len, display_idx) = display_metrics(
-            &view,
-            self.view_mode,
-            self.animation_phase,
-            self.scroll_offset,
-            step_direction,
-        );
+    pub fn merge_preview_active(&self) -> bool {
+        self.merge_preview.is_some()
+    }
 
-        self.center_with_display_idx(viewport_height, display_len, display_idx);
+    /// Conflicts left in the previewed merge result
+    pub fn merge_preview_conflicts(&self) -> Option<usize> {
+        self.merge_preview.as_ref().map(|preview| preview.conflicts)
     }
 
-    /// Called every frame to update animations and autoplay
-    pub fn tick(&mut self) {
-        let now = Instant::now();
+    /// Leave the merge preview and return to the three-way comparison
+    pub fn close_merge_preview(&mut self) {
+        let Some(preview) = self.merge_preview.take() else {
+            return;
+        };
+        self.multi_diff = preview.original;
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
 
-        if let Some(frame) = self.snap_frame {
-            let started_at = self.snap_frame_started_at.get_or_insert(now);
-            let phase_duration = Duration::from_millis(SNAP_PHASE_MS);
-            if now.duration_since(*started_at) >= phase_duration {
-                match frame {
-                    AnimationFrame::FadeOut => {
-                        self.snap_frame = Some(AnimationFrame::FadeIn);
-                        self.snap_frame_started_at = Some(now);
-                    }
-                    AnimationFrame::FadeIn | AnimationFrame::Idle => {
-                        self.snap_frame = None;
-                        self.snap_frame_started_at = None;
-                        let step_dir = self.multi_diff.current_navigator().state().step_direction;
-                        if step_dir == StepDirection::Backward {
-                            self.multi_diff.current_navigator().clear_active_change();
-                        }
-                    }
-                }
+    /// Leave the preview and return to the working tree diff
+    pub fn close_split_preview(&mut self) {
+        let Some(preview) = self.split_preview.take() else {
+            return;
+        };
+        self.multi_diff = preview.original;
+        self.reset_file_states();
+        self.reset_search_for_file_switch();
+        self.handle_file_enter();
+    }
+
+    /// Create one commit per bucket, then reload the remaining changes
+    pub fn commit_buckets(&mut self) {
+        self.close_split_preview();
+        let Some(repo_root) = self.multi_diff.repo_root().map(Path::to_path_buf) else {
+            self.notify(Level::Warning, "commit splitting needs a git repository");
+            return;
+        };
+        let result = self
+            .multi_diff
+            .plan_commits(&self.buckets)
+            .map_err(|err| err.to_string())
+            .and_then(|plan| {
+                oyo_core::bucket::create_commits(&repo_root, &plan).map_err(|err| err.to_string())
+            });
+        match result {
+            Ok(commits) if commits.is_empty() => {
+                self.notify(Level::Warning, "no hunks in buckets");
+            }
+            Ok(commits) => {
+                self.notify(Level::Success, format!("created {} commits", commits.len()));
+                self.buckets.clear();
+                self.active_bucket = 0;
+                self.refresh_all_files();
             }
+            Err(err) => self.notify(Level::Error, err),
         }
+    }
 
-        // Update animation
-        if self.animation_phase != AnimationPhase::Idle {
-            let elapsed = now.duration_since(self.last_animation_tick);
-            let phase_duration = Duration::from_millis(self.animation_duration);
-
-            self.animation_progress =
-                (elapsed.as_secs_f32() / phase_duration.as_secs_f32()).min(1.0);
-
-            if self.animation_progress >= 1.0 {
-                match self.animation_phase {
-                    AnimationPhase::FadeOut => {
-                        self.animation_phase = AnimationPhase::FadeIn;
-                        self.animation_progress = 0.0;
-                        self.last_animation_tick = now;
-                    }
-                    AnimationPhase::FadeIn => {
-                        self.animation_phase = AnimationPhase::Idle;
-                        self.animation_progress = 1.0;
+    /// Name of the active preset
+    pub fn active_preset_name(&self) -> Option<&str> {
+        self.active_preset
+            .and_then(|idx| self.presets.get(idx))
+            .map(|(name, _)| name.as_str())
+    }
 
-                        // If this was a backward animation, clear the active change
-                        // so un-applied insertions properly disappear
-                        let step_dir = self.multi_diff.current_navigator().state().step_direction;
-                        if step_dir == StepDirection::Backward {
-                            self.multi_diff.current_navigator().clear_active_change();
-                        }
-                    }
-                    AnimationPhase::Idle => {}
-                }
+    /// Apply a preset by name. Returns false if no such preset exists.
+    pub fn apply_preset(&mut self, name: &str) -> bool {
+        match self.presets.iter().position(|(n, _)| n == name) {
+            Some(idx) => {
+                self.apply_preset_index(idx);
+                true
             }
+            None => false,
         }
+    }
 
-        // Handle autoplay
-        if self.stepping && self.autoplay && self.animation_phase == AnimationPhase::Idle {
-            let autoplay_interval = Duration::from_millis(self.animation_speed * 2);
-            if now.duration_since(self.last_autoplay_tick) >= autoplay_interval {
-                let moved = if self.autoplay_reverse {
-                    self.step_backward()
-                } else {
-                    self.step_forward()
-                };
-                if !moved {
-                    self.autoplay = false;
-                }
-                self.last_autoplay_tick = now;
-            }
+    /// Cycle to the next preset (wraps around)
+    pub fn cycle_preset(&mut self) {
+        if self.presets.is_empty() {
+            return;
         }
+        let next = match self.active_preset {
+            Some(idx) => (idx + 1) % self.presets.len(),
+            None => 0,
+        };
+        self.apply_preset_index(next);
     }
 
-    /// Get the total number of lines in the current view
-    #[allow(dead_code)]
-    pub fn total_lines(&mut self) -> usize {
-        let frame = self.animation_frame();
-        self.multi_diff
-            .current_navigator()
-            .current_view_with_frame(frame)
-            .len()
+    fn apply_preset_index(&mut self, idx: usize) {
+        let preset = self.presets[idx].1.clone();
+        self.active_preset = Some(idx);
+
+        if let Some(stepping) = preset.stepping {
+            if stepping != self.stepping {
+                self.toggle_stepping();
+            }
+        }
+        if let Some(mode) = preset
+            .view_mode
+            .as_deref()
+            .and_then(crate::config::parse_view_mode)
+        {
+            // Evolution requires stepping; it is restored when stepping is enabled
+            self.step_view_mode = mode;
+            if self.stepping || mode != ViewMode::Evolution {
+                self.view_mode = mode;
+            }
+        }
+        if let Some(speed) = preset.speed {
+            self.animation_speed = speed;
+        }
+        if let Some(autoplay) = preset.autoplay {
+            self.autoplay = autoplay && self.stepping;
+            self.autoplay_reverse = false;
+            self.last_autoplay_tick = Instant::now();
+        }
+        if let Some(animation) = preset.animation {
+            self.animation_enabled = animation;
+        }
+        if let Some(duration) = preset.animation_duration {
+            self.animation_duration = duration;
+        }
+        if let Some(line_wrap) = preset.line_wrap {
+            self.line_wrap = line_wrap;
+            self.last_wrap_display_len = None;
+            self.last_wrap_active_idx = None;
+        }
+        if let Some(zen) = preset.zen {
+            self.zen_mode = zen;
+        }
+
+        let engine_config = preset.engine_config(&self.engine_config);
+        self.multi_diff.set_engine(engine_config.diff_engine());
+        if engine_config.structural != self.multi_diff.is_structural() {
+            self.multi_diff.set_structural(engine_config.structural);
+        }
+        self.rebuild_diffs();
     }
 
-    /// Get statistics about the current file's diff
-    pub fn stats(&mut self) -> (usize, usize) {
-        let diff = self.multi_diff.current_navigator().diff();
-        (diff.insertions, diff.deletions)
+    /// Toggle structural diffing of config files (.env, .ini, .toml)
+    pub fn toggle_structural(&mut self) {
+        let structural = !self.multi_diff.is_structural();
+        self.multi_diff.set_structural(structural);
+        self.rebuild_diffs();
     }
 
-    /// Get current file path for display
-    pub fn current_file_path(&self) -> String {
-        self.multi_diff
-            .current_file()
-            .map(|f| f.display_name.clone())
-            .unwrap_or_default()
+    pub fn toggle_heat(&mut self) {
+        self.heat = !self.heat;
     }
 
-    /// Refresh current file from disk
-    pub fn refresh_current_file(&mut self) {
-        self.multi_diff.refresh_current_file();
-        let idx = self.multi_diff.selected_index;
-        if idx < self.syntax_caches.len() {
-            self.syntax_caches[idx] = None;
+    /// Relative age of an old-side line of the current file (0 = newest
+    /// line of the file, 1 = oldest), when heat is on and blame is available
+    pub fn line_age(&mut self, old_line: usize) -> Option<f32> {
+        if !self.heat {
+            return None;
         }
-        self.scroll_offset = 0;
-        self.horizontal_scroll = 0;
-        self.centered_once = false;
-        self.needs_scroll_to_active = true;
+        let idx = self.multi_diff.selected_index;
+        let multi_diff = &self.multi_diff;
+        let ages = self.heat_ages.entry(idx).or_insert_with(|| {
+            let times = multi_diff.old_line_times(idx)?;
+            let newest = *times.iter().max()?;
+            let oldest = *times.iter().min()?;
+            let span = (newest - oldest).max(1) as f32;
+            Some(
+                times
+                    .iter()
+                    .map(|&time| (newest - time) as f32 / span)
+                    .collect(),
+            )
+        });
+        ages.as_ref()?.get(old_line.checked_sub(1)?).copied()
     }
 
-    /// Refresh all files from git (re-scan for uncommitted changes)
-    pub fn refresh_all_files(&mut self) {
-        if self.multi_diff.refresh_all_from_git() {
-            // Reset scroll states for all files
-            let file_count = self.multi_diff.file_count();
-            self.scroll_offsets_step = vec![0; file_count];
-            self.scroll_offsets_no_step = vec![0; file_count];
-            self.horizontal_scrolls_step = vec![0; file_count];
-            self.horizontal_scrolls_no_step = vec![0; file_count];
-            self.max_line_widths_step = vec![0; file_count];
-            self.max_line_widths_no_step = vec![0; file_count];
-            self.no_step_visited = vec![false; file_count];
-            self.files_visited = vec![false; file_count];
-            self.syntax_caches = vec![None; file_count];
-            self.step_state_snapshots = vec![None; file_count];
-            self.no_step_state_snapshots = vec![None; file_count];
-            self.scroll_offset = 0;
-            self.horizontal_scroll = 0;
-            self.needs_scroll_to_active = true;
-            self.centered_once = false;
-            self.handle_file_enter();
-        }
+    /// Swap old and new (view the change as its revert)
+    pub fn toggle_reversed(&mut self) {
+        let reversed = !self.multi_diff.is_reversed();
+        self.multi_diff.set_reversed(reversed);
+        self.rebuild_diffs();
+    }
+
+    /// Reset view state after the diffs were recomputed, keeping the top
+    /// row in place
+    fn rebuild_diffs(&mut self) {
+        let anchor = self.scroll_anchor();
+        self.peek_state = None;
+        self.step_peek_state = None;
+        self.animation_phase = AnimationPhase::Idle;
+        self.animation_progress = 1.0;
+        self.reset_file_states();
+        self.handle_file_enter();
+        self.restore_scroll_anchor(anchor);
     }
 }
 
@@ -3198,6 +6461,32 @@ fn copy_to_clipboard(text: &str) -> bool {
     }
 }
 
+/// Open `url` with the platform's default handler, without waiting for it;
+/// no shell is involved, so the URL is never parsed as commands
+fn open_in_browser(url: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    let (cmd, args): (&str, &[&str]) = ("open", &[]);
+    #[cfg(target_os = "windows")]
+    let (cmd, args): (&str, &[&str]) = ("rundll32", &["url.dll,FileProtocolHandler"]);
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let (cmd, args): (&str, &[&str]) = ("xdg-open", &[]);
+    let child = Command::new(cmd)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            // Reap it in the background so it does not linger as a zombie
+            std::thread::spawn(move || child.wait());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
 fn write_to_clipboard_cmd(cmd: &str, args: &[&str], text: &str) -> bool {
     let mut child = match Command::new(cmd).args(args).stdin(Stdio::piped()).spawn() {
         Ok(child) => child,
@@ -3232,7 +6521,7 @@ fn inline_text_for_change(change: &Change) -> String {
             ChangeKind::Insert => text.push_str(&span.text),
             ChangeKind::Replace => {
                 text.push_str(&span.text);
-                text.push_str(&span.new_text.clone().unwrap_or_else(|| span.text.clone()));
+                text.push_str(span.new_text.as_deref().unwrap_or(&span.text));
             }
         }
     }
@@ -3247,7 +6536,7 @@ fn modified_only_text_for_change(change: &Change) -> String {
             ChangeKind::Delete => {}
             ChangeKind::Insert => text.push_str(&span.text),
             ChangeKind::Replace => {
-                text.push_str(&span.new_text.clone().unwrap_or_else(|| span.text.clone()));
+                text.push_str(span.new_text.as_deref().unwrap_or(&span.text));
             }
         }
     }
@@ -3457,6 +6746,156 @@ fn split_display_metrics(
     (display_len, active_idx)
 }
 
+/// Usage shown for a malformed `:verdict`
+const VERDICT_USAGE: &str = "usage: :verdict approve|changes|comment [<comment>]";
+
+/// Check the arguments of `:` commands that take them
+fn validate_goto(query: &str) -> Result<(), String> {
+    let query = query.trim();
+    if let Some(args) = query.strip_prefix("verdict ") {
+        let verdict = args.split_whitespace().next().unwrap_or_default();
+        if Verdict::parse(verdict).is_none() {
+            return Err(VERDICT_USAGE.to_string());
+        }
+    }
+    if let Some(line) = query.strip_prefix("place ") {
+        return match line.trim().parse::<usize>() {
+            Ok(line) if line > 0 => Ok(()),
+            _ => Err(format!("invalid line: {}", line.trim())),
+        };
+    }
+    if let Some(steps) = query.strip_prefix("compare ") {
+        let steps: Vec<&str> = steps.split_whitespace().collect();
+        if steps.is_empty()
+            || steps.len() > 2
+            || steps.iter().any(|step| step.parse::<usize>().is_err())
+        {
+            return Err("usage: :compare <step> [<step>]".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Hunk a review thread's line is in, or within the context lines GitHub
+/// shows around it
+fn thread_hunk(diff: &oyo_core::DiffResult, thread: &Thread) -> Option<usize> {
+    const CONTEXT: usize = 3;
+    diff.hunks.iter().position(|hunk| {
+        let lines = hunk
+            .change_ids
+            .iter()
+            .filter_map(|id| diff.changes.iter().find(|change| change.id == *id))
+            .flat_map(|change| &change.spans)
+            .filter_map(|span| match thread.side {
+                Side::Old => span.old_line,
+                Side::New => span.new_line,
+            });
+        let (first, last) = lines.fold((usize::MAX, 0), |(first, last), line| {
+            (first.min(line), last.max(line))
+        });
+        first.saturating_sub(CONTEXT) <= thread.line && thread.line <= last + CONTEXT
+    })
+}
+
+/// Context of each hunk inside a function (the innermost): the whole
+/// function. Functions longer than `max_lines` fold the unchanged lines
+/// more than a few lines away from their hunks, keeping the first and last.
+fn function_contexts(
+    diff: &oyo_core::DiffResult,
+    functions: &[FunctionSpan],
+    max_lines: usize,
+) -> BTreeMap<usize, HunkContext> {
+    const CONTEXT: usize = 3;
+    let mut line_changes: HashMap<usize, usize> = HashMap::new();
+    for change in &diff.changes {
+        if let Some(line) = change.spans.iter().find_map(|span| span.new_line) {
+            line_changes.entry(line).or_insert(change.id);
+        }
+    }
+    // Functions as ranges of changes (new-side lines are 1-based)
+    let ranges: Vec<Option<(usize, usize)>> = functions
+        .iter()
+        .map(|function| {
+            let first = line_changes.get(&(function.lines.start() + 1))?;
+            let last = line_changes.get(&(function.lines.end() + 1))?;
+            Some((*first, *last))
+        })
+        .collect();
+
+    let mut hunks_by_function: BTreeMap<usize, Vec<(usize, usize, usize)>> = BTreeMap::new();
+    for (hunk_idx, hunk) in diff.hunks.iter().enumerate() {
+        let (Some(&lo), Some(&hi)) = (hunk.change_ids.iter().min(), hunk.change_ids.iter().max())
+        else {
+            continue;
+        };
+        let innermost = ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| range.is_some_and(|(first, last)| first <= lo && hi <= last))
+            .max_by_key(|(_, range)| range.map(|(first, _)| first));
+        if let Some((function_idx, _)) = innermost {
+            hunks_by_function
+                .entry(function_idx)
+                .or_default()
+                .push((hunk_idx, lo, hi));
+        }
+    }
+
+    let significant: HashSet<usize> = diff.significant_changes.iter().copied().collect();
+    let mut contexts = BTreeMap::new();
+    for (function_idx, hunks) in hunks_by_function {
+        let function = &functions[function_idx];
+        let Some((first, last)) = ranges[function_idx] else {
+            continue;
+        };
+        let mut folds = Vec::new();
+        if function.lines.end() - function.lines.start() + 1 > max_lines {
+            let lo = hunks.iter().map(|&(_, lo, _)| lo).min().unwrap_or(first);
+            let hi = hunks.iter().map(|&(_, _, hi)| hi).max().unwrap_or(last);
+            let above = first + 1..lo.saturating_sub(CONTEXT);
+            let below = hi + CONTEXT + 1..last;
+            // Runs of unchanged changes, at least two long
+            let mut fold = |start: usize, end: usize| {
+                if end > start {
+                    folds.push(start..=end);
+                }
+            };
+            for span in [above, below] {
+                let mut start = None;
+                for id in span.clone() {
+                    match (significant.contains(&id), start) {
+                        (false, None) => start = Some(id),
+                        (true, Some(run)) => {
+                            fold(run, id - 1);
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(run) = start {
+                    fold(run, span.end - 1);
+                }
+            }
+        }
+        let context = HunkContext {
+            changes: first..=last,
+            folds,
+            label: function.name.clone(),
+        };
+        for (hunk_idx, _, _) in hunks {
+            contexts.insert(hunk_idx, context.clone());
+        }
+    }
+    contexts
+}
+
+/// Check a search in regex mode; an invalid one is searched for literally
+fn validate_regex(query: &str) -> Result<(), String> {
+    regex::Regex::new(query.trim())
+        .map(|_| ())
+        .map_err(|_| "invalid regex, matching literally".to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3500,7 +6939,7 @@ mod tests {
         is_primary_active: bool,
     ) -> ViewLine {
         ViewLine {
-            content: String::new(),
+            content: Default::default(),
             spans: vec![],
             kind,
             old_line,
@@ -3653,6 +7092,26 @@ mod tests {
         App::new(multi_diff, ViewMode::SinglePane, 0, false, None)
     }
 
+    #[test]
+    fn test_toggle_reversed_swaps_sides() {
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.txt"),
+            std::path::PathBuf::from("a.txt"),
+            "one\n".to_string(),
+            "one\ntwo\n".to_string(),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        assert_eq!(app.multi_diff.total_stats(), (1, 0));
+
+        app.toggle_reversed();
+        assert_eq!(app.multi_diff.total_stats(), (0, 1));
+        let diff = app.multi_diff.current_navigator().diff().clone();
+        assert_eq!(diff.deletions, 1);
+
+        app.toggle_reversed();
+        assert_eq!(app.multi_diff.total_stats(), (1, 0));
+    }
+
     #[test]
     fn test_no_step_prev_hunk_from_bottom_advances() {
         let mut app = make_app_with_two_hunks();
@@ -3766,4 +7225,524 @@ mod tests {
         app.next_step();
         assert_eq!(app.hunk_step_info(), Some((2, 2)));
     }
+
+    #[test]
+    fn test_scroll_animation_eases_to_the_target() {
+        let mut app = make_app_with_single_hunk();
+        app.animation_enabled = true;
+        app.reduced_motion = false;
+        app.scroll_duration = 100;
+
+        app.scroll_offset = 40;
+        app.animate_scroll_from(0);
+        assert_eq!(app.scroll_offset, 0);
+        assert!(app.is_animating());
+
+        let started_at = app.scroll_animation.unwrap().started_at;
+        app.tick_scroll_animation(started_at + Duration::from_millis(50));
+        assert_eq!(app.scroll_offset, 20);
+        app.tick_scroll_animation(started_at + Duration::from_millis(100));
+        assert_eq!(app.scroll_offset, 40);
+        assert!(app.scroll_animation.is_none());
+
+        // Scrolling by hand ends the animation where the user left it
+        app.animate_scroll_from(10);
+        app.scroll_offset = 12;
+        app.tick_scroll_animation(started_at + Duration::from_millis(50));
+        assert_eq!(app.scroll_offset, 12);
+        assert!(app.scroll_animation.is_none());
+
+        app.scroll_duration = 0;
+        app.scroll_offset = 40;
+        app.animate_scroll_from(0);
+        assert_eq!(app.scroll_offset, 40);
+    }
+
+    #[test]
+    fn test_latest_popup_is_on_top_and_focused() {
+        let mut app = make_app_with_single_hunk();
+        assert_eq!(app.focused_popup(), None);
+
+        app.toggle_help();
+        app.open_settings();
+        assert_eq!(app.open_popups(), [PopupKind::Help, PopupKind::Settings]);
+        assert_eq!(app.focused_popup(), Some(PopupKind::Settings));
+
+        app.close_popup(PopupKind::Settings);
+        assert_eq!(app.focused_popup(), Some(PopupKind::Help));
+        app.open_settings();
+        app.toggle_help();
+        app.toggle_help();
+        assert_eq!(app.open_popups(), [PopupKind::Settings, PopupKind::Help]);
+    }
+
+    #[test]
+    fn test_keys_go_to_the_popup_under_the_path_popup() {
+        let mut app = make_app_with_single_hunk();
+        app.toggle_path_popup();
+        assert_eq!(app.focused_popup(), Some(PopupKind::Path));
+        assert_eq!(app.input_popup(), None);
+
+        app.open_settings();
+        app.toggle_path_popup();
+        app.toggle_path_popup();
+        assert_eq!(app.focused_popup(), Some(PopupKind::Path));
+        assert_eq!(app.input_popup(), Some(PopupKind::Settings));
+
+        app.toggle_help();
+        assert_eq!(app.input_popup(), Some(PopupKind::Help));
+    }
+
+    #[test]
+    fn test_quit_asks_when_buckets_are_not_committed() {
+        let mut app = make_app_with_single_hunk();
+        assert!(app.request_quit());
+
+        app.toggle_bucket_hunk();
+        assert_eq!(app.unsaved_work(), ["1 hunk in uncommitted buckets"]);
+        assert!(!app.request_quit());
+        assert_eq!(app.focused_popup(), Some(PopupKind::Confirm));
+        assert!(app.confirm_can_save());
+        app.answer_confirm(false);
+        assert!(!app.should_quit);
+
+        app.request_quit();
+        app.answer_confirm(true);
+        assert!(app.should_quit);
+
+        app.force_quit = true;
+        assert!(app.request_quit());
+    }
+
+    #[test]
+    fn test_review_threads_are_anchored_to_hunks() {
+        let mut app = make_app_with_two_hunks();
+        app.multi_diff.current_navigator();
+        let thread = |path: &str, line: usize| crate::forge::Thread {
+            id: line.to_string(),
+            path: std::path::PathBuf::from(path),
+            side: Side::New,
+            line,
+            outdated: false,
+            comments: Vec::new(),
+        };
+        app.review = Some(Review {
+            forge: crate::forge::open("https://github.com/o/r", &[]).unwrap(),
+            pr: crate::forge::PullRequest {
+                number: 1,
+                title: "t".into(),
+                base_ref: "main".into(),
+                base_sha: "a".into(),
+                head_sha: "b".into(),
+            },
+            threads: vec![
+                thread("a.txt", 4),
+                thread("a.txt", 11),
+                thread("a.txt", 20),
+                thread("b.txt", 2),
+            ],
+            replies: false,
+        });
+        let anchored: Vec<(usize, Option<usize>)> = app
+            .review_threads()
+            .into_iter()
+            .map(|(thread, hunk)| (thread.line, hunk))
+            .collect();
+        assert_eq!(anchored, [(4, Some(0)), (11, None), (20, Some(1))]);
+
+        app.request_reply("ok".into());
+        assert_eq!(app.focused_popup(), None);
+    }
+
+    #[test]
+    fn test_breadcrumb_levels_move_between_siblings() {
+        let old: std::collections::BTreeMap<PathBuf, String> = ["a/x", "a/y", "b/z"]
+            .iter()
+            .map(|name| (PathBuf::from(name), "1\n".to_string()))
+            .collect();
+        let new = old
+            .keys()
+            .map(|path| (path.clone(), "2\n".to_string()))
+            .collect();
+        let multi_diff = MultiFileDiff::from_entries(old, new);
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        let crumbs: Vec<String> = app.breadcrumbs().into_iter().map(|(_, c)| c).collect();
+        assert_eq!(crumbs[1..], ["a", "x", "hunk 1/1", "change 0/1"]);
+
+        app.crumb_up();
+        app.crumb_up();
+        app.crumb_up();
+        assert!(app.show_breadcrumbs);
+        assert_eq!(app.crumb_level, CrumbLevel::Dir);
+        app.crumb_sibling(true);
+        assert_eq!(app.current_file_path(), "b/z");
+        app.crumb_sibling(false);
+        assert_eq!(app.current_file_path(), "a/x");
+
+        app.crumb_up();
+        assert_eq!(app.crumb_level, CrumbLevel::Change);
+    }
+
+    #[test]
+    fn test_dir_rollup_counts_reviewed_files() {
+        let old: std::collections::BTreeMap<PathBuf, String> = ["src/a", "src/b", "top"]
+            .iter()
+            .map(|name| (PathBuf::from(name), "1\n2\n".to_string()))
+            .collect();
+        let mut new = old.clone();
+        new.insert(PathBuf::from("src/a"), "1\ntwo\n".to_string());
+        new.insert(PathBuf::from("src/b"), "1\n2\n3\n".to_string());
+        let multi_diff = MultiFileDiff::from_entries(old, new);
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        assert!(!app.has_reviewed_files());
+
+        app.toggle_file_reviewed();
+        let rollup = app.dir_rollup("src");
+        assert_eq!(
+            (rollup.files, rollup.insertions, rollup.deletions),
+            (2, 2, 1)
+        );
+        assert_eq!(rollup.reviewed_percent(), 50);
+        assert_eq!(app.dir_rollup("").reviewed, 0);
+
+        app.toggle_file_reviewed();
+        assert_eq!(app.dir_rollup("src").reviewed, 0);
+    }
+
+    #[test]
+    fn test_function_context_folds_long_functions() {
+        let body: String = (1..=12).map(|i| format!("    let v{i} = {i};\n")).collect();
+        let old = format!("use x;\n\nfn parse() {{\n{body}    old();\n}}\n\nfn other() {{}}\n");
+        let new = format!("use x;\n\nfn parse() {{\n{body}    new();\n}}\n\nfn other() {{}}\n");
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.rs"),
+            std::path::PathBuf::from("a.rs"),
+            old,
+            new,
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        app.function_context = true;
+        app.function_context_lines = 10;
+        app.ensure_function_context();
+
+        let nav = app.multi_diff.current_navigator();
+        let context = nav.hunk_context(0).unwrap().clone();
+        assert_eq!(context.label, "parse");
+        // `let v1` .. `let v9`: the signature and the three lines above
+        // the hunk stay
+        assert_eq!(context.folds.len(), 1);
+        let view = nav.current_view();
+        let folded: Vec<_> = view
+            .iter()
+            .filter(|line| line.kind == LineKind::Collapsed)
+            .collect();
+        assert_eq!(folded.len(), 1);
+        assert_eq!(folded[0].content, "⋯ 9 unchanged lines of parse");
+
+        app.toggle_hunk_collapsed();
+        let nav = app.multi_diff.current_navigator();
+        assert!(!nav.is_context_folded(0));
+        assert!(!nav.is_hunk_collapsed(0));
+        assert!(nav
+            .current_view()
+            .iter()
+            .all(|line| line.kind != LineKind::Collapsed));
+    }
+
+    #[test]
+    fn test_merge_preview_diffs_the_merge_against_both_sides() {
+        let multi_diff = MultiFileDiff::from_file_pair_with_base(
+            std::path::PathBuf::from("a.txt"),
+            std::path::PathBuf::from("a.txt"),
+            "one\ntwo\nthree\nfour\n".to_string(),
+            "one\nTWO\nthree\nfour\n".to_string(),
+            "one\ntwo\nthree\n4\n".to_string(),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        app.open_merge_preview();
+        assert!(app.merge_preview_active());
+        assert_eq!(app.merge_preview_conflicts(), Some(0));
+        let paths: Vec<_> = app
+            .multi_diff
+            .files
+            .iter()
+            .map(|f| f.path.clone())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                std::path::PathBuf::from("ours/a.txt"),
+                std::path::PathBuf::from("theirs/a.txt")
+            ]
+        );
+
+        app.close_merge_preview();
+        assert!(!app.merge_preview_active());
+        assert!(app.multi_diff.has_base());
+    }
+
+    #[test]
+    fn test_search_options_and_counter() {
+        let mut app = make_app_with_two_hunks();
+        for ch in "line2".chars() {
+            app.search_key(PromptKey::Insert(ch));
+        }
+        let (_, substring_total) = app.search_counter().unwrap();
+        assert!(substring_total > 2); // line2, line20..line25, line2-new
+
+        app.toggle_search_whole_word();
+        let (position, whole_word_total) = app.search_counter().unwrap();
+        assert_eq!(position, None);
+        assert!(whole_word_total < substring_total);
+
+        app.search_next();
+        assert_eq!(app.search_counter(), Some((Some(1), whole_word_total)));
+
+        // Smart case: an uppercase letter makes the search case-sensitive
+        app.search_key(PromptKey::Clear);
+        for ch in "LINE2".chars() {
+            app.search_key(PromptKey::Insert(ch));
+        }
+        assert_eq!(app.search_counter(), Some((None, 0)));
+        app.cycle_search_case(); // sensitive
+        app.cycle_search_case(); // insensitive
+        assert_eq!(app.search_counter(), Some((None, whole_word_total)));
+    }
+
+    #[test]
+    fn test_filtered_steps_skip_non_matching_changes() {
+        let mut app = make_app_with_single_hunk_two_changes();
+        for ch in "two".chars() {
+            app.search_key(PromptKey::Insert(ch));
+        }
+        app.toggle_search_filter_steps();
+        assert!(app.steps_filtered());
+
+        // Step 1 (one -> ONE) does not match and is skipped
+        app.next_step();
+        assert_eq!(app.multi_diff.current_navigator().state().current_step, 2);
+        // Nothing after the last match
+        app.next_step();
+        assert_eq!(app.multi_diff.current_navigator().state().current_step, 2);
+
+        // Backward un-applies the matching change
+        app.prev_step();
+        assert_eq!(app.multi_diff.current_navigator().state().current_step, 1);
+    }
+
+    #[test]
+    fn test_rename_sites_and_filter() {
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.rs"),
+            std::path::PathBuf::from("a.rs"),
+            "a(foo);\nx\nb(1);\ny\nc(foo);\n".to_string(),
+            "a(bar);\nx\nb(2);\ny\nc(bar);\n".to_string(),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        app.next_step();
+        let sites = app.rename_sites().expect("rename");
+        assert_eq!((sites.old.as_str(), sites.new.as_str()), ("foo", "bar"));
+        assert_eq!((sites.in_file, sites.total, sites.files), (2, 2, 1));
+
+        // Stepping skips b(1) -> b(2), which is not the same rename
+        app.toggle_rename_filter();
+        app.next_step();
+        assert_eq!(app.multi_diff.current_navigator().state().current_step, 3);
+        app.next_step();
+        assert_eq!(app.multi_diff.current_navigator().state().current_step, 3);
+
+        app.toggle_rename_filter();
+        assert!(app.rename_filter.is_none());
+    }
+
+    #[test]
+    fn test_kind_filter_steps_and_dims() {
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.rs"),
+            std::path::PathBuf::from("a.rs"),
+            "let a = 1;\nx\ngone();\ny\nz\n".to_string(),
+            "let a = 2;\nx\ny\nz\nadded();\n".to_string(),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        let active_kind = |app: &mut App| {
+            let nav = app.multi_diff.current_navigator();
+            let id = nav.state().active_change.expect("active change");
+            nav.diff().changes[id].edit_kind()
+        };
+
+        app.cycle_kind_filter();
+        assert_eq!(app.kind_filter, Some(ChangeKind::Delete));
+        app.next_step();
+        assert_eq!(active_kind(&mut app), ChangeKind::Delete);
+        let step = app.multi_diff.current_navigator().state().current_step;
+        app.next_step();
+        assert_eq!(
+            app.multi_diff.current_navigator().state().current_step,
+            step
+        );
+
+        let view = app.multi_diff.current_navigator().current_view();
+        let modified = view
+            .iter()
+            .find(|line| line.content.contains("let a"))
+            .expect("modified line");
+        assert!(app.filtered_out(modified));
+        let deleted = view
+            .iter()
+            .find(|line| line.content.contains("gone"))
+            .expect("deleted line");
+        assert!(!app.filtered_out(deleted));
+
+        app.cycle_kind_filter();
+        app.next_step();
+        assert_eq!(active_kind(&mut app), ChangeKind::Insert);
+
+        app.cycle_kind_filter();
+        app.cycle_kind_filter();
+        assert!(app.kind_filter.is_none());
+        assert!(!app.filtered_out(modified));
+    }
+
+    #[test]
+    fn test_step_compare_shows_file_at_two_steps() {
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.txt"),
+            std::path::PathBuf::from("a.txt"),
+            "a\nb\nc\nd\ne\n".to_string(),
+            "A\nb\nc\nd\nE\n".to_string(),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        let steps = app.multi_diff.current_navigator().state().total_steps - 1;
+        assert!(steps >= 2);
+
+        app.goto_input.set(format!("compare 0 {steps}"));
+        app.apply_goto();
+        assert!(app.step_compare_active());
+        assert_eq!(app.view_mode, ViewMode::Split);
+        assert_eq!(
+            app.multi_diff.file_contents(0),
+            Some(("a\nb\nc\nd\ne\n", "A\nb\nc\nd\nE\n"))
+        );
+
+        app.close_step_compare();
+        assert!(!app.step_compare_active());
+        assert_eq!(app.view_mode, ViewMode::SinglePane);
+
+        // Halfway through, only the first line has changed
+        let half = app.content_at_step(steps / 2);
+        assert!(half.starts_with("A\n") && half.ends_with("e\n"), "{half:?}");
+    }
+
+    #[test]
+    fn test_step_checkpoints() {
+        let old: String = (0..8).map(|i| format!("line {i}\n")).collect();
+        let new = old.replace("line", "LINE");
+        let multi_diff = MultiFileDiff::from_file_pair(
+            std::path::PathBuf::from("a.txt"),
+            std::path::PathBuf::from("a.txt"),
+            old,
+            new,
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        let step = |app: &mut App| app.multi_diff.current_navigator().state().current_step;
+        let total = app.multi_diff.current_navigator().state().total_steps;
+
+        app.goto_step_percent(50);
+        assert_eq!(step(&mut app), (total - 1) / 2);
+        app.goto_next_checkpoint();
+        assert_eq!(step(&mut app), ((total - 1) * 75 + 50) / 100);
+        app.goto_step_percent(100);
+        app.goto_next_checkpoint();
+        assert_eq!(step(&mut app), 0);
+
+        // The scrub bar maps its cells onto the whole step range
+        app.scrub_bar_area = Some((10, 20, 9, 1));
+        assert!(!app.handle_scrub_click(10, 19));
+        assert!(app.handle_scrub_click(18, 20));
+        assert_eq!(step(&mut app), total - 1);
+        assert!(app.handle_scrub_click(10, 20));
+        assert_eq!(step(&mut app), 0);
+        app.scrub_by(2);
+        assert_eq!(step(&mut app), 2);
+    }
+
+    #[test]
+    fn test_churn_computed_in_background() {
+        let old: String = (0..16).map(|i| format!("{i}\n")).collect();
+        let entries = |content: &str, b: &str| {
+            [("a.txt", content), ("b.txt", b)]
+                .into_iter()
+                .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+                .collect()
+        };
+        let multi_diff = MultiFileDiff::from_entries(
+            entries(&old, "b\n"),
+            entries(&old.replace("15\n", "end\n"), "B\n"),
+        );
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+
+        app.start_churn();
+        while app.churn_pending.is_some() {
+            app.collect_churn();
+        }
+        let churn = app.file_churn(Path::new("a.txt")).expect("computed");
+        assert_eq!(churn.len(), CHURN_BUCKETS);
+        // The removed and the added line, both at the end
+        assert_eq!(churn.iter().sum::<usize>(), 2);
+        assert_eq!(churn[CHURN_BUCKETS - 1], 2);
+        assert!(app.file_churn(Path::new("b.txt")).is_some());
+    }
+
+    #[test]
+    fn test_place_and_apply_reject() {
+        let dir = std::env::temp_dir().join(format!("oyo-app-rej-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "1\n2\n3\n").unwrap();
+        let rej = dir.join("a.txt.rej");
+        std::fs::write(&rej, "--- a.txt\n+++ a.txt\n@@ -5,1 +5,1 @@\n-x\n+y\n").unwrap();
+
+        let rejects = vec![RejectFile::load(&rej).unwrap()];
+        let multi_diff = MultiFileDiff::from_rejects(&rejects);
+        let mut app = App::new(multi_diff, ViewMode::SinglePane, 0, false, None);
+        app.set_reject_files(rejects);
+        assert_eq!(app.reject_placement(), Some(4));
+
+        app.goto_input.set("place 2");
+        app.apply_goto();
+        assert_eq!(app.reject_placement(), Some(2));
+        app.goto_input.set("apply");
+        app.apply_goto();
+        assert_eq!(app.focused_popup(), Some(PopupKind::Confirm));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.txt")).unwrap(),
+            "1\n2\n3\n"
+        );
+        app.answer_confirm(true);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.txt")).unwrap(),
+            "1\ny\n3\n"
+        );
+        assert!(!rej.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_occurrences_match_renamed_words() {
+        let diff = oyo_core::DiffEngine::new().diff_strings("call(foo);\n", "call(bar);\n");
+        let change = &diff.changes[diff.significant_changes[0]];
+        let occ = Occurrences::for_change(0, change);
+
+        let old = occ.old.expect("old words");
+        let new = occ.new.expect("new words");
+        assert!(old.is_match("let x = foo;"));
+        assert!(!old.is_match("let food = 1;"));
+        assert!(!old.is_match("call(x);"));
+        assert!(new.is_match("bar(1)"));
+
+        // A whole inserted line has no counterpart to look for
+        let diff = oyo_core::DiffEngine::new().diff_strings("a\n", "a\nnew_line()\n");
+        let change = &diff.changes[diff.significant_changes[0]];
+        assert!(Occurrences::for_change(0, change).new.is_none());
+    }
 }
@@ -0,0 +1,283 @@
+//! Zip and tar archive contents, compared entry by entry
+
+use crate::compress::{Compression, MAX_DECOMPRESSED_BYTES};
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Archive format, detected by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    Tar(Option<Compression>),
+}
+
+impl Format {
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        let format = match name.rsplit_once('.')?.1 {
+            "zip" | "jar" | "war" | "whl" => Format::Zip,
+            "tar" => Format::Tar(None),
+            "tgz" => Format::Tar(Some(Compression::Gzip)),
+            "gz" | "zst" | "xz" if name.contains(".tar.") => Format::Tar(Compression::detect(path)),
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
+/// True if `path` names a zip or (possibly compressed) tar archive
+pub fn is_archive(path: &Path) -> bool {
+    Format::detect(path).is_some()
+}
+
+/// Read the file entries of an archive as text, keyed by path. Binary
+/// entries are summarized by size and hash so changes still show up.
+pub fn read_entries(path: &Path, bytes: Vec<u8>) -> Result<BTreeMap<PathBuf, String>> {
+    let format = Format::detect(path).context(format!("Not an archive: {}", path.display()))?;
+    let files = match format {
+        Format::Zip => zip_files(&bytes),
+        Format::Tar(None) => Ok(owned(tar_files(&bytes))),
+        Format::Tar(Some(compression)) => compression
+            .decompress(&bytes)
+            .map(|tar| owned(tar_files(&tar))),
+    }
+    .context(format!("Failed to read archive: {}", path.display()))?;
+
+    Ok(files
+        .into_iter()
+        .map(|(name, data)| (PathBuf::from(name), entry_text(data)))
+        .collect())
+}
+
+fn owned(files: Vec<(String, &[u8])>) -> Vec<(String, Vec<u8>)> {
+    files
+        .into_iter()
+        .map(|(name, data)| (name, data.to_vec()))
+        .collect()
+}
+
+fn entry_text(data: Vec<u8>) -> String {
+    String::from_utf8(data).unwrap_or_else(|err| {
+        let data = err.into_bytes();
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        format!(
+            "Binary file ({} bytes, hash {:016x})\n",
+            data.len(),
+            hasher.finish()
+        )
+    })
+}
+
+fn clean_name(name: &str) -> String {
+    name.trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+fn field_str(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+/// Regular files of a tar stream, in order
+pub fn tar_files(tar: &[u8]) -> Vec<(String, &[u8])> {
+    let mut files = Vec::new();
+    let mut long_name: Option<String> = None;
+    let mut offset = 0;
+    while offset + 512 <= tar.len() {
+        let header = &tar[offset..offset + 512];
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        let Some(size) = std::str::from_utf8(&header[124..136])
+            .ok()
+            .and_then(|field| {
+                usize::from_str_radix(field.trim_matches(|c| c == '\0' || c == ' '), 8).ok()
+            })
+        else {
+            break;
+        };
+        let data_start = offset + 512;
+        let Some(data) = tar.get(data_start..data_start + size) else {
+            break;
+        };
+        match header[156] {
+            // GNU long name: the data is the name of the next entry
+            b'L' => long_name = Some(field_str(data)),
+            // pax extended header: may carry the path of the next entry
+            b'x' => {
+                long_name = String::from_utf8_lossy(data)
+                    .lines()
+                    .find_map(|record| record.split_once(" path=").map(|(_, path)| path))
+                    .map(str::to_string)
+            }
+            // '0' or NUL: regular file
+            b'0' | 0 => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let prefix = field_str(&header[345..500]);
+                    let name = field_str(&header[..100]);
+                    if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{prefix}/{name}")
+                    }
+                });
+                files.push((clean_name(&name), data));
+            }
+            _ => long_name = None,
+        }
+        offset = data_start + size.div_ceil(512) * 512;
+    }
+    files
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+fn le32(bytes: &[u8], at: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as usize)
+}
+
+/// Files of a zip archive, read through its central directory
+fn zip_files(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    const END_OF_DIRECTORY: &[u8] = &[0x50, 0x4b, 0x05, 0x06];
+    const DIRECTORY_ENTRY: &[u8] = &[0x50, 0x4b, 0x01, 0x02];
+    const LOCAL_HEADER: &[u8] = &[0x50, 0x4b, 0x03, 0x04];
+
+    let end = (0..zip.len().saturating_sub(21))
+        .rev()
+        .find(|&at| zip[at..].starts_with(END_OF_DIRECTORY))
+        .context("Missing zip end of central directory")?;
+    let count = le16(zip, end + 10).context("Truncated zip")?;
+    let mut offset = le32(zip, end + 16).context("Truncated zip")?;
+
+    let mut files = Vec::new();
+    let mut total = 0u64;
+    for _ in 0..count {
+        if !zip
+            .get(offset..)
+            .is_some_and(|rest| rest.starts_with(DIRECTORY_ENTRY))
+        {
+            anyhow::bail!("Corrupt zip central directory");
+        }
+        let entry = |at| le16(zip, offset + at).context("Truncated zip");
+        let method = entry(10)?;
+        let compressed_size = le32(zip, offset + 20).context("Truncated zip")?;
+        let (name_len, extra_len, comment_len) = (entry(28)?, entry(30)?, entry(32)?);
+        let local = le32(zip, offset + 42).context("Truncated zip")?;
+        let name = zip
+            .get(offset + 46..offset + 46 + name_len)
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .context("Truncated zip")?;
+        offset += 46 + name_len + extra_len + comment_len;
+        if name.ends_with('/') {
+            continue;
+        }
+        if compressed_size == u32::MAX as usize || local == u32::MAX as usize {
+            anyhow::bail!("ZIP64 archives are not supported");
+        }
+
+        if !zip
+            .get(local..)
+            .is_some_and(|rest| rest.starts_with(LOCAL_HEADER))
+        {
+            anyhow::bail!("Corrupt zip entry: {name}");
+        }
+        let data_start = local
+            + 30
+            + le16(zip, local + 26).context("Truncated zip")?
+            + le16(zip, local + 28).context("Truncated zip")?;
+        let raw = zip
+            .get(data_start..data_start + compressed_size)
+            .context(format!("Truncated zip entry: {name}"))?;
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut out = Vec::new();
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(MAX_DECOMPRESSED_BYTES + 1)
+                    .read_to_end(&mut out)
+                    .context(format!("Failed to inflate {name}"))?;
+                out
+            }
+            _ => anyhow::bail!("Unsupported zip compression method {method} ({name})"),
+        };
+        total += data.len() as u64;
+        if total > MAX_DECOMPRESSED_BYTES {
+            anyhow::bail!(
+                "decompressed size exceeds {} MiB",
+                MAX_DECOMPRESSED_BYTES / 1024 / 1024
+            );
+        }
+        files.push((clean_name(&name), data));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zip_with(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut zip = vec![0x50, 0x4b, 0x03, 0x04];
+        zip.extend([0u8; 22]);
+        zip.extend((name.len() as u16).to_le_bytes());
+        zip.extend([0u8; 2]);
+        zip.extend(name.as_bytes());
+        zip.extend(data);
+        let directory = zip.len() as u32;
+
+        zip.extend([0x50, 0x4b, 0x01, 0x02]);
+        zip.extend([0u8; 16]);
+        zip.extend((data.len() as u32).to_le_bytes());
+        zip.extend((data.len() as u32).to_le_bytes());
+        zip.extend((name.len() as u16).to_le_bytes());
+        zip.extend([0u8; 12]);
+        zip.extend(0u32.to_le_bytes());
+        zip.extend(name.as_bytes());
+        let directory_size = zip.len() as u32 - directory;
+
+        zip.extend([0x50, 0x4b, 0x05, 0x06]);
+        zip.extend([0u8; 4]);
+        zip.extend(1u16.to_le_bytes());
+        zip.extend(1u16.to_le_bytes());
+        zip.extend(directory_size.to_le_bytes());
+        zip.extend(directory.to_le_bytes());
+        zip.extend([0u8; 2]);
+        zip
+    }
+
+    #[test]
+    fn test_read_entries() {
+        assert!(is_archive(Path::new("release.tar.gz")));
+        assert!(is_archive(Path::new("app.jar")));
+        assert!(!is_archive(Path::new("app.log.gz")));
+
+        let zip = zip_with("conf/app.toml", b"port = 80\n");
+        let entries = read_entries(Path::new("old.zip"), zip).unwrap();
+        assert_eq!(
+            entries.get(Path::new("conf/app.toml")).map(String::as_str),
+            Some("port = 80\n")
+        );
+
+        let mut tar = vec![0u8; 512];
+        tar[..9].copy_from_slice(b"./bin/app");
+        tar[124..136].copy_from_slice(b"00000000002\0");
+        tar[156] = b'0';
+        let mut data = vec![0xff, 0xfe];
+        data.resize(512, 0);
+        tar.extend(data);
+        tar.extend([0u8; 1024]);
+        let entries = read_entries(Path::new("new.tar"), tar).unwrap();
+        assert!(entries[Path::new("bin/app")].starts_with("Binary file (2 bytes"));
+    }
+}
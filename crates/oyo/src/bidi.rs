@@ -0,0 +1,211 @@
+//! Bidirectional text: reorder right-to-left runs into visual order
+//!
+//! Terminals draw cells left to right, so Arabic/Hebrew text has to be
+//! reordered before rendering. This is a simplified form of the Unicode
+//! Bidirectional Algorithm for a left-to-right paragraph (which is what
+//! source code is): strong RTL characters get level 1, numbers inside RTL
+//! text level 2, neutrals take the level of the strong text around them,
+//! bracket pairs around RTL text are RTL, and runs are then reversed per
+//! level (rule L2) with brackets mirrored.
+
+use ratatui::style::Style;
+use ratatui::text::Span;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BidiClass {
+    Left,
+    Right,
+    Number,
+    Neutral,
+}
+
+fn is_rtl_char(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+    )
+}
+
+fn classify(grapheme: &str) -> BidiClass {
+    match grapheme.chars().next() {
+        Some(ch) if is_rtl_char(ch) => BidiClass::Right,
+        Some(ch) if ch.is_numeric() => BidiClass::Number,
+        Some(ch) if ch.is_alphabetic() => BidiClass::Left,
+        _ => BidiClass::Neutral,
+    }
+}
+
+fn mirror(grapheme: &str) -> &str {
+    match grapheme {
+        "(" => ")",
+        ")" => "(",
+        "[" => "]",
+        "]" => "[",
+        "{" => "}",
+        "}" => "{",
+        "<" => ">",
+        ">" => "<",
+        _ => grapheme,
+    }
+}
+
+/// True if the text contains any right-to-left characters
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl_char)
+}
+
+/// Resolve embedding levels for a left-to-right paragraph
+fn resolve_levels(graphemes: &[&str]) -> Vec<u8> {
+    let is_rtl_side = |class: BidiClass| matches!(class, BidiClass::Right | BidiClass::Number);
+
+    // Numbers take the direction of the preceding strong text
+    let mut last_strong = BidiClass::Left;
+    let mut resolved: Vec<BidiClass> = graphemes
+        .iter()
+        .map(|g| match classify(g) {
+            class @ (BidiClass::Left | BidiClass::Right) => {
+                last_strong = class;
+                class
+            }
+            BidiClass::Number if last_strong == BidiClass::Right => BidiClass::Number,
+            BidiClass::Number => BidiClass::Left,
+            BidiClass::Neutral => BidiClass::Neutral,
+        })
+        .collect();
+
+    // Bracket pairs enclosing only RTL text are RTL themselves
+    let mut open_brackets = Vec::new();
+    for (i, grapheme) in graphemes.iter().enumerate() {
+        match *grapheme {
+            "(" | "[" | "{" => open_brackets.push(i),
+            ")" | "]" | "}" => {
+                if let Some(open) = open_brackets.pop() {
+                    let inner = &resolved[open + 1..i];
+                    if inner.iter().any(|&c| is_rtl_side(c)) && !inner.contains(&BidiClass::Left) {
+                        resolved[open] = BidiClass::Right;
+                        resolved[i] = BidiClass::Right;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut levels = vec![0u8; resolved.len()];
+    let mut i = 0;
+    while i < resolved.len() {
+        match resolved[i] {
+            BidiClass::Left => i += 1,
+            BidiClass::Right => {
+                levels[i] = 1;
+                i += 1;
+            }
+            BidiClass::Number => {
+                levels[i] = 2;
+                i += 1;
+            }
+            BidiClass::Neutral => {
+                // Neutrals between two RTL sides join them; otherwise they stay LTR
+                let start = i;
+                while i < resolved.len() && resolved[i] == BidiClass::Neutral {
+                    i += 1;
+                }
+                let before = start.checked_sub(1).map(|j| resolved[j]);
+                let after = resolved.get(i).copied();
+                if before.is_some_and(is_rtl_side) && after.is_some_and(is_rtl_side) {
+                    levels[start..i].fill(1);
+                }
+            }
+        }
+    }
+    levels
+}
+
+/// Reorder spans into visual order, keeping each grapheme's style.
+///
+/// Lines without right-to-left characters are returned unchanged.
+pub fn reorder_spans(spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    if !spans.iter().any(|span| has_rtl(&span.content)) {
+        return spans;
+    }
+
+    let mut cells: Vec<(&str, Style)> = Vec::new();
+    for span in &spans {
+        for grapheme in span.content.graphemes(true) {
+            cells.push((grapheme, span.style));
+        }
+    }
+    let graphemes: Vec<&str> = cells.iter().map(|(g, _)| *g).collect();
+    let levels = resolve_levels(&graphemes);
+
+    // L2: from the highest level down, reverse every run at or above it
+    let mut order: Vec<usize> = (0..cells.len()).collect();
+    let max_level = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=max_level).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+
+    let mut out: Vec<Span<'static>> = Vec::new();
+    let mut buf = String::new();
+    let mut buf_style: Option<Style> = None;
+    for idx in order {
+        let (grapheme, style) = cells[idx];
+        let grapheme = if levels[idx] % 2 == 1 {
+            mirror(grapheme)
+        } else {
+            grapheme
+        };
+        if let Some(prev) = buf_style.filter(|&s| s != style) {
+            out.push(Span::styled(std::mem::take(&mut buf), prev));
+        }
+        buf.push_str(grapheme);
+        buf_style = Some(style);
+    }
+    if let Some(style) = buf_style {
+        out.push(Span::styled(buf, style));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_reorders_rtl_runs_and_keeps_styles() {
+        let ltr = vec![Span::raw("let x = 1;")];
+        assert_eq!(reorder_spans(ltr.clone()), ltr);
+
+        // "שלום עולם" inside a string literal
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![
+            Span::raw("s = \""),
+            Span::styled("שלום עולם", red),
+            Span::raw("\";"),
+        ];
+        let out = reorder_spans(spans);
+        assert_eq!(text(&out), "s = \"םלוע םולש\";");
+        assert_eq!(out[1].content, "םלוע םולש");
+        assert_eq!(out[1].style, red);
+
+        // Numbers inside RTL text stay left-to-right; brackets mirror
+        let out = reorder_spans(vec![Span::raw("אב 12 (גד)")]);
+        assert_eq!(text(&out), "(דג) 12 בא");
+    }
+}
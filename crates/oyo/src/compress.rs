@@ -0,0 +1,151 @@
+//! Transparent decompression of `.gz`, `.zst` and `.xz` inputs
+
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Largest decompressed size accepted (guards against decompression bombs)
+pub const MAX_DECOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Compression format of an input, detected by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    pub fn detect(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+
+    pub fn decompress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self {
+            Compression::Gzip => {
+                flate2::read::MultiGzDecoder::new(bytes)
+                    .take(MAX_DECOMPRESSED_BYTES + 1)
+                    .read_to_end(&mut out)?;
+            }
+            // No pure-Rust decoder available; use the system tools
+            Compression::Zstd | Compression::Xz => {
+                let tool = if self == Compression::Zstd {
+                    "zstd"
+                } else {
+                    "xz"
+                };
+                let mut child = Command::new(tool)
+                    .arg("-dc")
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .context(format!("Failed to run {tool}"))?;
+                let mut stdin = child.stdin.take().context("Failed to open stdin")?;
+                let input = bytes.to_vec();
+                let writer = std::thread::spawn(move || stdin.write_all(&input));
+                child
+                    .stdout
+                    .take()
+                    .context("Failed to open stdout")?
+                    .take(MAX_DECOMPRESSED_BYTES + 1)
+                    .read_to_end(&mut out)?;
+                // Past the limit the tool is stopped, so its status says
+                // nothing; otherwise a failure means a truncated or corrupt
+                // input, even when some bytes came out
+                let killed = out.len() as u64 > MAX_DECOMPRESSED_BYTES;
+                if killed {
+                    let _ = child.kill();
+                }
+                let output = child.wait_with_output()?;
+                let _ = writer.join();
+                if !killed && !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    match stderr.trim() {
+                        "" => anyhow::bail!("{tool} failed ({})", output.status),
+                        stderr => anyhow::bail!("{stderr}"),
+                    }
+                }
+            }
+        }
+        if out.len() as u64 > MAX_DECOMPRESSED_BYTES {
+            anyhow::bail!(
+                "decompressed size exceeds {} MiB",
+                MAX_DECOMPRESSED_BYTES / 1024 / 1024
+            );
+        }
+        Ok(out)
+    }
+}
+
+/// Decode file bytes as text, decompressing them first if `path` names a
+/// compressed file
+pub fn decode(path: &Path, bytes: Vec<u8>) -> Result<(String, Option<Compression>)> {
+    let Some(compression) = Compression::detect(path) else {
+        return Ok((String::from_utf8_lossy(&bytes).to_string(), None));
+    };
+    let bytes = compression
+        .decompress(&bytes)
+        .context(format!("Failed to decompress: {}", path.display()))?;
+    Ok((
+        String::from_utf8_lossy(&bytes).to_string(),
+        Some(compression),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(b"rotated log\n").unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        let (text, compression) = decode(Path::new("app.log.1.gz"), bytes).unwrap();
+        assert_eq!(text, "rotated log\n");
+        assert_eq!(compression, Some(Compression::Gzip));
+
+        let (text, compression) = decode(Path::new("app.log"), b"plain".to_vec()).unwrap();
+        assert_eq!(text, "plain");
+        assert_eq!(compression, None);
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_xz() {
+        let text = "rotated log line\n".repeat(4096);
+        let mut child = Command::new("xz")
+            .arg("-c")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(text.as_bytes())
+            .unwrap();
+        let bytes = child.wait_with_output().unwrap().stdout;
+
+        let (decoded, _) = decode(Path::new("app.log.xz"), bytes.clone()).unwrap();
+        assert_eq!(decoded, text);
+        assert!(decode(Path::new("app.log.xz"), bytes[..bytes.len() / 2].to_vec()).is_err());
+    }
+}
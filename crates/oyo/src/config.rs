@@ -11,10 +11,20 @@
 //! line_wrap = false
 //! scrollbar = false
 //! strikethrough_deletions = false
+//! patterns = false
 //! primary_marker = "▶"
 //! primary_marker_right = "◀"
 //! extent_marker = "▌"
 //! extent_marker_right = "▐"
+//! reduced_motion = false
+//! max_fps = 60
+//! bidi = true
+//! heat = false
+//! function_context = false
+//! function_context_lines = 40
+//! scrub_bar = false
+//! file_header = true
+//! breadcrumbs = false
 //!
 //! [ui.theme.defs]
 //! oyo14 = "#A3BE8C"
@@ -30,19 +40,74 @@
 //! speed = 200
 //! autoplay = false
 //! animation = true
+//! easing = "ease_in_out"
+//! fade_out_duration = 100
+//! fade_in_duration = 200
 //! auto_step_on_enter = true
 //! auto_step_blank_files = true
+//! step_order = "hunk"
+//! split_changes = false
 //!
 //! [files]
 //! panel_visible = true
 //! counts = "active"
+//!
+//! [engine]
+//! algorithm = "myers"
+//! reindent = "off"
+//! granularity = "word"
+//! structural = false
+//! provider = "similar"
+//!
+//! [search]
+//! regex = false
+//! case = "smart"
+//! whole_word = false
+//!
+//! [remote]
+//! cache_ttl = 300
+//!
+//! [forge]
+//! replies = false
+//!
+//! [[forge.remotes]]
+//! url = 'git\.example\.com'
+//! kind = "gitlab"
+//! token_env = "EXAMPLE_GITLAB_TOKEN"
+//! permalink = "https://{host}/{repo}/-/blob/{commit}/{path}#L{line}"
+//!
+//! [redact]
+//! enabled = false
+//! patterns = ['[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}']
+//!
+//! [word_diff]
+//! stop_patterns = ['https?://[^\s"]+']
+//!
+//! [word_diff.languages]
+//! css = ['#[0-9a-fA-F]{3,8}\b']
+//!
+//! [presets.review]
+//! algorithm = "patience"
+//! context = 5
+//! view_mode = "split"
+//!
+//! [presets.demo]
+//! view_mode = "single"
+//! speed = 600
+//! animation_duration = 300
 //! ```
 
 use crate::color::{self, AnimationGradient};
 use ratatui::style::Color;
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use unicode_width::UnicodeWidthStr;
+
+/// Every option with its default value and a comment, written by
+/// `oy config init`
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
 
 // ============================================================================
 // Theme Configuration
@@ -134,6 +199,13 @@ const BUILTIN_THEMES: &[(&str, &str)] = &[
         "cobalt2",
         include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/themes/cobalt2.json")),
     ),
+    (
+        "deuteranopia",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/themes/deuteranopia.json"
+        )),
+    ),
     (
         "dracula",
         include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/themes/dracula.json")),
@@ -188,6 +260,13 @@ const BUILTIN_THEMES: &[(&str, &str)] = &[
             "/themes/palenight.json"
         )),
     ),
+    (
+        "protanopia",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/themes/protanopia.json"
+        )),
+    ),
     (
         "rosepine",
         include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/themes/rosepine.json")),
@@ -535,10 +614,15 @@ pub struct UiConfig {
     pub scrollbar: bool,
     /// Show strikethrough on deleted text
     pub strikethrough_deletions: bool,
+    /// Mark change kinds with text attributes as well as color: inserts
+    /// underlined, deletions struck through, modifications italic
+    pub patterns: bool,
     /// Syntax highlighting configuration
     pub syntax: SyntaxConfig,
     /// Single-pane view settings
     pub single: SingleViewConfig,
+    /// Split view settings
+    pub split: SplitViewConfig,
     /// Evolution view settings
     pub evo: EvoViewConfig,
     /// Diff styling settings
@@ -555,6 +639,51 @@ pub struct UiConfig {
     pub extent_marker_right: Option<String>,
     /// Theme configuration
     pub theme: ThemeConfig,
+    /// Replace animations with instant transitions (also `OYO_REDUCED_MOTION=1`)
+    pub reduced_motion: bool,
+    /// Frame-rate cap for redraws (0 = uncapped)
+    pub max_fps: u32,
+    /// Show the frame-rate debug overlay
+    pub fps_overlay: bool,
+    /// Reorder right-to-left (Arabic/Hebrew) text for display; disable for speed
+    pub bidi: bool,
+    /// Dim unchanged lines by age, from `git blame` of the old side
+    pub heat: bool,
+    /// Extend a hunk inside a function to the whole function (detected by
+    /// the syntax definitions)
+    pub function_context: bool,
+    /// Longest function shown whole; longer ones start with their unchanged
+    /// lines away from the hunks folded
+    pub function_context_lines: usize,
+    /// Show the step scrub bar under the status bar
+    pub scrub_bar: bool,
+    /// Show the file header bar (path, language, encoding, line endings,
+    /// sizes) above the diff
+    pub file_header: bool,
+    /// Show the breadcrumb bar (repo ▸ dir ▸ file ▸ hunk ▸ change) above
+    /// the diff
+    pub breadcrumbs: bool,
+}
+
+impl UiConfig {
+    /// Gutter markers occupy exactly one column; fall back to defaults for
+    /// wide (CJK/emoji) or empty markers so the gutter stays aligned
+    pub fn normalize_markers(&mut self) {
+        fn single_column(marker: &mut String, fallback: &str, key: &str) {
+            if UnicodeWidthStr::width(marker.as_str()) != 1 {
+                eprintln!("Warning: ui.{key} must be one column wide, using \"{fallback}\"");
+                *marker = fallback.to_string();
+            }
+        }
+        single_column(&mut self.primary_marker, "▶", "primary_marker");
+        single_column(&mut self.extent_marker, "▌", "extent_marker");
+        if let Some(marker) = self.primary_marker_right.as_mut() {
+            single_column(marker, "◀", "primary_marker_right");
+        }
+        if let Some(marker) = self.extent_marker_right.as_mut() {
+            single_column(marker, "▐", "extent_marker_right");
+        }
+    }
 }
 
 impl Default for UiConfig {
@@ -566,8 +695,10 @@ impl Default for UiConfig {
             line_wrap: false,
             scrollbar: false,
             strikethrough_deletions: false,
+            patterns: false,
             syntax: SyntaxConfig::default(),
             single: SingleViewConfig::default(),
+            split: SplitViewConfig::default(),
             evo: EvoViewConfig::default(),
             diff: DiffConfig::default(),
             stepping: true,
@@ -576,6 +707,16 @@ impl Default for UiConfig {
             extent_marker: "▌".to_string(),
             extent_marker_right: None,
             theme: ThemeConfig::default(),
+            reduced_motion: false,
+            max_fps: 60,
+            fps_overlay: false,
+            bidi: true,
+            heat: false,
+            function_context: false,
+            function_context_lines: 40,
+            scrub_bar: false,
+            file_header: true,
+            breadcrumbs: false,
         }
     }
 }
@@ -596,6 +737,15 @@ impl Default for SingleViewConfig {
     }
 }
 
+/// Split view configuration
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SplitViewConfig {
+    /// Pad each side of a modified line opposite words only the other side
+    /// has, so the unchanged tails of both lines line up
+    pub align_gaps: bool,
+}
+
 /// Evolution view configuration
 #[derive(Debug, Deserialize)]
 #[serde(default)]
@@ -745,10 +895,23 @@ pub struct PlaybackConfig {
     pub animation: bool,
     /// Animation duration in milliseconds (how long fade effects take)
     pub animation_duration: u64,
+    /// Fade-out phase duration in milliseconds (defaults to animation_duration)
+    pub fade_out_duration: Option<u64>,
+    /// Fade-in phase duration in milliseconds (defaults to animation_duration)
+    pub fade_in_duration: Option<u64>,
+    /// Easing curve for fade animations
+    pub easing: AnimationEasing,
+    /// Scroll animation duration in milliseconds when a step moves the
+    /// view (0 = jump)
+    pub scroll_duration: u64,
     /// Auto-step to first change when entering a file at step 0
     pub auto_step_on_enter: bool,
     /// Auto-step when file would be blank at step 0 (new files)
     pub auto_step_blank_files: bool,
+    /// How deletions and insertions interleave: "hunk", "old_line", or "new_line"
+    pub step_order: StepOrder,
+    /// Give each separate edit of a modified line its own step
+    pub split_changes: bool,
 }
 
 impl Default for PlaybackConfig {
@@ -758,8 +921,59 @@ impl Default for PlaybackConfig {
             autoplay: false,
             animation: true,
             animation_duration: 120,
+            fade_out_duration: None,
+            fade_in_duration: None,
+            easing: AnimationEasing::Linear,
+            scroll_duration: 150,
             auto_step_on_enter: true,
             auto_step_blank_files: true,
+            step_order: StepOrder::Hunk,
+            split_changes: false,
+        }
+    }
+}
+
+/// Easing curve for fade animations
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationEasing {
+    #[default]
+    Linear,
+    EaseInOut,
+    Spring,
+}
+
+impl From<AnimationEasing> for oyo_core::AnimationEasing {
+    fn from(easing: AnimationEasing) -> Self {
+        match easing {
+            AnimationEasing::Linear => oyo_core::AnimationEasing::Linear,
+            AnimationEasing::EaseInOut => oyo_core::AnimationEasing::EaseInOut,
+            AnimationEasing::Spring => oyo_core::AnimationEasing::Spring,
+        }
+    }
+}
+
+/// Order in which changes are stepped through
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOrder {
+    /// All deletions of a block before its insertions
+    #[default]
+    Hunk,
+    /// Old-file line order (also accepted as "old")
+    #[serde(alias = "old")]
+    OldLine,
+    /// New-file line order (also accepted as "new")
+    #[serde(alias = "new")]
+    NewLine,
+}
+
+impl From<StepOrder> for oyo_core::StepOrder {
+    fn from(order: StepOrder) -> Self {
+        match order {
+            StepOrder::Hunk => oyo_core::StepOrder::Hunk,
+            StepOrder::OldLine => oyo_core::StepOrder::OldLine,
+            StepOrder::NewLine => oyo_core::StepOrder::NewLine,
         }
     }
 }
@@ -772,6 +986,13 @@ pub struct FilesConfig {
     pub panel_visible: bool,
     /// When to show per-file +/- counts in the file panel
     pub counts: FileCountMode,
+    /// File list order
+    pub sort: FileSortMode,
+    /// Show where in each file its changes are (a small churn thumbnail)
+    pub churn: bool,
+    /// Megabytes the computed diffs of all files may hold before the least
+    /// recently viewed are dropped and recomputed on return (0 = unlimited)
+    pub memory_budget: usize,
 }
 
 impl Default for FilesConfig {
@@ -779,10 +1000,30 @@ impl Default for FilesConfig {
         Self {
             panel_visible: true,
             counts: FileCountMode::Active,
+            sort: FileSortMode::Path,
+            churn: true,
+            memory_budget: 512,
         }
     }
 }
 
+/// File list ordering
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSortMode {
+    /// Alphabetical by path
+    #[default]
+    Path,
+    /// Riskiest files first (size, branching, error handling, unsafe; tests last)
+    Risk,
+    /// Grouped by the conventional-commit type (feat, fix, ...) of the
+    /// commits touching them (commit ranges only)
+    Commit,
+    /// Directories with the most lines changed first, and the same within
+    /// each directory
+    Churn,
+}
+
 /// File list counts display behavior
 #[derive(Debug, Deserialize, Clone, Copy, Default)]
 #[serde(rename_all = "snake_case")]
@@ -794,6 +1035,451 @@ pub enum FileCountMode {
     Off,
 }
 
+/// Letter case matching for search
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchCase {
+    /// Case-insensitive unless the query has an uppercase letter
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl SearchCase {
+    /// Next mode when cycling at runtime
+    pub fn next(self) -> Self {
+        match self {
+            SearchCase::Smart => SearchCase::Sensitive,
+            SearchCase::Sensitive => SearchCase::Insensitive,
+            SearchCase::Insensitive => SearchCase::Smart,
+        }
+    }
+}
+
+/// Search defaults (each can be toggled while typing a search)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Treat the query as a regular expression
+    pub regex: bool,
+    /// Letter case matching: "smart", "sensitive", or "insensitive"
+    pub case: SearchCase,
+    /// Only match whole words
+    pub whole_word: bool,
+}
+
+/// Remote file arguments (`user@host:path`, `https://...`)
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Seconds a fetched remote file is reused from the cache
+    pub cache_ttl: u64,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self { cache_ttl: 300 }
+    }
+}
+
+/// Pull/merge request review threads (`oy pr`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ForgeConfig {
+    /// Allow posting replies to review threads (`:reply`); also needs a
+    /// token (`GITHUB_TOKEN`, `GITLAB_TOKEN`, `GITEA_TOKEN`, ...)
+    pub replies: bool,
+    /// Self-hosted forges, matched against the remote URL in order
+    /// (`[[forge.remotes]]`); github.com, gitlab.com and codeberg.org are
+    /// known without one
+    pub remotes: Vec<ForgeRemote>,
+}
+
+/// Kind of forge a remote is on
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+    /// Gitea and Forgejo
+    Gitea,
+}
+
+/// A self-hosted forge (`[[forge.remotes]]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgeRemote {
+    /// Regex over the remote URL, e.g. `git\.example\.com`; compiled when
+    /// the config is loaded, so an invalid one is reported there
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub url: regex::Regex,
+    pub kind: ForgeKind,
+    /// API root; by default derived from the host (`/api/v3` for GitHub
+    /// Enterprise, `/api/v4` for GitLab, `/api/v1` for Gitea)
+    #[serde(default)]
+    pub api: Option<String>,
+    /// Variable holding the token, instead of the forge's usual ones
+    #[serde(default)]
+    pub token_env: Option<String>,
+    /// Web URL of a file line (`gy`, `gx`), with `{host}`, `{repo}`, `{commit}`,
+    /// `{path}` and `{line}`; by default the forge's usual one
+    #[serde(default)]
+    pub permalink: Option<String>,
+}
+
+fn deserialize_regex<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<regex::Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    regex::Regex::new(&pattern)
+        .map_err(|err| serde::de::Error::custom(format!("invalid regex {pattern:?}: {err}")))
+}
+
+/// Test impact hints (`[[tests.rules]]`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TestsConfig {
+    pub rules: Vec<TestRule>,
+}
+
+/// Scripted actions (`[actions]`), see [`actions`](crate::actions)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ActionsConfig {
+    /// Rhai script defining the actions, relative to the config directory
+    /// (default: `actions.rhai` there, if it exists)
+    pub script: Option<PathBuf>,
+    /// Key, as shown in the key echo (`x`, `C-y`, `M-p`), to action name
+    pub keys: BTreeMap<String, String>,
+}
+
+/// An external-process plugin (`[[plugins]]`), see [`plugin`](crate::plugin)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginConfig {
+    /// Name shown with the plugin's messages
+    pub name: String,
+    /// Executable, looked up on `PATH`
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Maps changed files to the tests they likely affect
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestRule {
+    /// Glob over changed paths relative to the repository root (`**`, `*`, `?`)
+    pub path: String,
+    /// Glob of related test files; `{stem}` and `{path}` expand to the changed file
+    #[serde(default)]
+    pub tests: Option<String>,
+    /// Shell command running the tests; also expands `{tests}` to the matched files
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl TestsConfig {
+    pub fn compile(&self) -> Vec<crate::impact::ImpactRule> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                crate::impact::ImpactRule::new(&rule.path, rule.tests.clone(), rule.command.clone())
+            })
+            .collect()
+    }
+}
+
+/// Masking of secrets for screen sharing (`[redact]`)
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RedactConfig {
+    /// Mask matches in every view and in yanked text (also `--redact`)
+    pub enabled: bool,
+    /// Regexes to mask (whole match, or the first capture group if any)
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            patterns: crate::redact::DEFAULT_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl RedactConfig {
+    /// Redactor to use if enabled (here or with `--redact`), warning about
+    /// (and skipping) invalid regexes
+    pub fn redactor(&self, enabled: bool) -> Option<crate::redact::Redactor> {
+        if !(self.enabled || enabled) {
+            return None;
+        }
+        let patterns = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| match regex::Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    eprintln!("Warning: Invalid redact pattern {pattern:?}: {e}");
+                    None
+                }
+            })
+            .collect();
+        Some(crate::redact::Redactor::new(patterns))
+    }
+}
+
+/// Word diff configuration
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WordDiffConfig {
+    /// Regexes whose matches are never split by word diffing (all files)
+    pub stop_patterns: Vec<String>,
+    /// Extra stop patterns keyed by file extension (e.g. `rs`, `css`)
+    pub languages: HashMap<String, Vec<String>>,
+}
+
+impl WordDiffConfig {
+    /// Compile stop patterns, warning about (and skipping) invalid regexes
+    pub fn compile(&self) -> oyo_core::WordStopPatterns {
+        fn compile_all(patterns: &[String]) -> Vec<regex::Regex> {
+            patterns
+                .iter()
+                .filter_map(|pattern| match regex::Regex::new(pattern) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        eprintln!("Warning: Invalid word_diff stop pattern {pattern:?}: {e}");
+                        None
+                    }
+                })
+                .collect()
+        }
+
+        oyo_core::WordStopPatterns {
+            global: compile_all(&self.stop_patterns),
+            by_extension: self
+                .languages
+                .iter()
+                .map(|(ext, patterns)| {
+                    (
+                        ext.trim_start_matches('.').to_ascii_lowercase(),
+                        compile_all(patterns),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Line diff algorithm of the `similar` provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Patience,
+    Lcs,
+}
+
+impl<'de> Deserialize<'de> for DiffAlgorithm {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "myers" => Ok(DiffAlgorithm::Myers),
+            "patience" => Ok(DiffAlgorithm::Patience),
+            "lcs" => Ok(DiffAlgorithm::Lcs),
+            // Not offered by `similar`; silently diffing with another
+            // algorithm would hide that
+            "histogram" => Err(D::Error::custom(
+                "algorithm `histogram` is not available; histogram diffs come from `provider = \"imara\"`",
+            )),
+            _ => Err(D::Error::unknown_variant(&name, &["myers", "patience", "lcs"])),
+        }
+    }
+}
+
+impl From<DiffAlgorithm> for oyo_core::DiffAlgorithm {
+    fn from(algorithm: DiffAlgorithm) -> Self {
+        match algorithm {
+            DiffAlgorithm::Myers => oyo_core::DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience => oyo_core::DiffAlgorithm::Patience,
+            DiffAlgorithm::Lcs => oyo_core::DiffAlgorithm::Lcs,
+        }
+    }
+}
+
+/// How indentation-only line changes are handled
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindentMode {
+    /// Treat them like any other change
+    #[default]
+    Off,
+    /// Show them already applied, without stepping through them
+    Skip,
+    /// Render them as unchanged context
+    Hide,
+    /// Step through each run of consecutive ones as a single step
+    Collapse,
+}
+
+impl From<ReindentMode> for oyo_core::ReindentMode {
+    fn from(mode: ReindentMode) -> Self {
+        match mode {
+            ReindentMode::Off => oyo_core::ReindentMode::Off,
+            ReindentMode::Skip => oyo_core::ReindentMode::Skip,
+            ReindentMode::Hide => oyo_core::ReindentMode::Hide,
+            ReindentMode::Collapse => oyo_core::ReindentMode::Collapse,
+        }
+    }
+}
+
+/// Unit of intra-line (word-level) diffing
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffGranularity {
+    /// Identifiers, whitespace runs and punctuation
+    #[default]
+    Word,
+    /// Single characters (grapheme clusters)
+    Char,
+}
+
+/// Engine that computes the line diffs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffProviderKind {
+    /// `similar`, with the configured algorithm
+    #[default]
+    Similar,
+    /// imara-diff's histogram algorithm (needs the `imara` cargo feature)
+    #[cfg(feature = "imara")]
+    Imara,
+    /// Structural diffs from difftastic (`difft` on the PATH)
+    Difftastic,
+}
+
+impl<'de> Deserialize<'de> for DiffProviderKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "similar" => Ok(DiffProviderKind::Similar),
+            #[cfg(feature = "imara")]
+            "imara" => Ok(DiffProviderKind::Imara),
+            // Silently diffing with `similar` instead would hide that
+            #[cfg(not(feature = "imara"))]
+            "imara" => Err(D::Error::custom(
+                "provider `imara` needs oy built with `--features imara`",
+            )),
+            "difftastic" => Ok(DiffProviderKind::Difftastic),
+            _ => Err(D::Error::unknown_variant(
+                &name,
+                &["similar", "imara", "difftastic"],
+            )),
+        }
+    }
+}
+
+/// Diff engine configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    /// Line diff algorithm: "myers", "patience", or "lcs" (for histogram
+    /// diffs, use the "imara" provider)
+    pub algorithm: DiffAlgorithm,
+    /// Context lines; changes closer than this are grouped into one hunk (default: 3)
+    pub context: Option<usize>,
+    /// Indentation-only changes: "off", "skip", or "hide"
+    pub reindent: ReindentMode,
+    /// Intra-line diff unit: "word" or "char"
+    pub granularity: DiffGranularity,
+    /// Diff .env/.ini/.toml files by key and .sql dumps by object instead of by line
+    pub structural: bool,
+    /// Line diff engine: "similar", "imara" or "difftastic"
+    pub provider: DiffProviderKind,
+}
+
+impl EngineConfig {
+    /// Build the diff engine described by this config
+    pub fn diff_engine(&self) -> oyo_core::DiffEngine {
+        let mut engine = oyo_core::DiffEngine::new()
+            .with_word_level(true)
+            .with_algorithm(self.algorithm.into())
+            .with_reindent(self.reindent.into());
+        if self.granularity == DiffGranularity::Char {
+            engine = engine.with_tokenizer(Arc::new(oyo_core::GraphemeTokenizer));
+        }
+        if let Some(context) = self.context {
+            engine = engine.with_context(context);
+        }
+        match self.provider {
+            DiffProviderKind::Similar => engine,
+            DiffProviderKind::Difftastic => {
+                let provider = oyo_core::DifftasticProvider::new(engine.clone());
+                engine.with_provider(Arc::new(provider))
+            }
+            #[cfg(feature = "imara")]
+            DiffProviderKind::Imara => {
+                let provider = oyo_core::ImaraProvider::new(engine.clone());
+                engine.with_provider(Arc::new(provider))
+            }
+        }
+    }
+}
+
+/// Named preset bundling diff engine and view options (`[presets.<name>]`)
+/// Unset fields leave the current setting untouched, except engine options,
+/// which fall back to the `[engine]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PresetConfig {
+    /// View mode: "single", "split", or "evolution"
+    pub view_mode: Option<String>,
+    /// Line diff algorithm: "myers", "patience", or "lcs"
+    pub algorithm: Option<DiffAlgorithm>,
+    /// Line diff engine: "similar", "imara" (histogram) or "difftastic"
+    pub provider: Option<DiffProviderKind>,
+    /// Context lines; changes closer than this are grouped into one hunk
+    pub context: Option<usize>,
+    /// Indentation-only changes: "off", "skip", or "hide"
+    pub reindent: Option<ReindentMode>,
+    /// Intra-line diff unit: "word" or "char"
+    pub granularity: Option<DiffGranularity>,
+    /// Override `[engine] structural`
+    pub structural: Option<bool>,
+    /// Autoplay speed in milliseconds
+    pub speed: Option<u64>,
+    /// Start autoplay when the preset is applied
+    pub autoplay: Option<bool>,
+    /// Enable step animations
+    pub animation: Option<bool>,
+    /// Animation duration in milliseconds
+    pub animation_duration: Option<u64>,
+    /// Enable stepping
+    pub stepping: Option<bool>,
+    /// Enable line wrapping
+    pub line_wrap: Option<bool>,
+    /// Zen mode
+    pub zen: Option<bool>,
+}
+
+impl PresetConfig {
+    /// Engine config for this preset, layered over the base `[engine]` section
+    pub fn engine_config(&self, base: &EngineConfig) -> EngineConfig {
+        EngineConfig {
+            algorithm: self.algorithm.unwrap_or(base.algorithm),
+            context: self.context.or(base.context),
+            reindent: self.reindent.unwrap_or(base.reindent),
+            granularity: self.granularity.unwrap_or(base.granularity),
+            structural: self.structural.unwrap_or(base.structural),
+            provider: self.provider.unwrap_or(base.provider),
+        }
+    }
+}
+
 /// Root configuration
 #[derive(Debug, Deserialize, Default)]
 #[serde(default)]
@@ -801,6 +1487,18 @@ pub struct Config {
     pub ui: UiConfig,
     pub playback: PlaybackConfig,
     pub files: FilesConfig,
+    pub engine: EngineConfig,
+    pub word_diff: WordDiffConfig,
+    pub search: SearchConfig,
+    pub remote: RemoteConfig,
+    pub forge: ForgeConfig,
+    pub tests: TestsConfig,
+    pub redact: RedactConfig,
+    /// External-process plugins, started in the repository root
+    pub plugins: Vec<PluginConfig>,
+    pub actions: ActionsConfig,
+    /// Named presets, selectable with `--preset <name>` or cycled at runtime
+    pub presets: BTreeMap<String, PresetConfig>,
 }
 
 impl Config {
@@ -835,29 +1533,49 @@ impl Config {
         Self::config_paths().into_iter().find(|p| p.exists())
     }
 
-    /// Load config from XDG config path
-    /// Returns default config if file doesn't exist or can't be parsed
+    /// Directory of the loaded config file, or where it would be
+    pub fn config_dir() -> Option<PathBuf> {
+        Self::write_path().and_then(|path| path.parent().map(Path::to_path_buf))
+    }
+
+    /// Where settings are saved: the loaded config file, or the first
+    /// candidate path if there is none yet
+    pub fn write_path() -> Option<PathBuf> {
+        Self::config_path().or_else(|| Self::config_paths().into_iter().next())
+    }
+
+    /// Load config from the XDG config path, the repo's `.oyo.toml` and
+    /// `OYO_*` variables (see [`layers`](crate::layers)), warning about each
+    /// problem found by [`validate::check`](crate::validate::check)
+    /// Layers that don't exist or can't be parsed are skipped
     pub fn load() -> Self {
-        Self::config_path()
-            .and_then(|path| std::fs::read_to_string(&path).ok())
-            .and_then(|content| {
-                toml::from_str(&content)
-                    .map_err(|e| {
-                        eprintln!("Warning: Failed to parse config: {}", e);
-                        e
-                    })
-                    .ok()
-            })
-            .unwrap_or_default()
+        crate::layers::load().config
+    }
+
+    /// Whether reduced motion is on; `OYO_REDUCED_MOTION` (1/0, true/false)
+    /// overrides `ui.reduced_motion`
+    pub fn reduced_motion(&self) -> bool {
+        match std::env::var("OYO_REDUCED_MOTION") {
+            Ok(value) if !value.trim().is_empty() => !matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "0" | "false" | "no" | "off"
+            ),
+            _ => self.ui.reduced_motion,
+        }
     }
 
     /// Parse view mode string to ViewMode enum
     pub fn parse_view_mode(&self) -> Option<crate::app::ViewMode> {
-        self.ui.view_mode.as_ref().and_then(|s| match s.as_str() {
-            "single" => Some(crate::app::ViewMode::SinglePane),
-            "split" | "sbs" => Some(crate::app::ViewMode::Split),
-            "evolution" | "evo" => Some(crate::app::ViewMode::Evolution),
-            _ => None,
-        })
+        self.ui.view_mode.as_deref().and_then(parse_view_mode)
+    }
+}
+
+/// Parse a view mode name ("single", "split", "evolution" and aliases)
+pub fn parse_view_mode(name: &str) -> Option<crate::app::ViewMode> {
+    match name {
+        "single" => Some(crate::app::ViewMode::SinglePane),
+        "split" | "sbs" => Some(crate::app::ViewMode::Split),
+        "evolution" | "evo" => Some(crate::app::ViewMode::Evolution),
+        _ => None,
     }
 }
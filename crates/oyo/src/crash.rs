@@ -0,0 +1,179 @@
+//! Crash safety: a panic restores the terminal, writes a crash report with
+//! the last actions, and saves the position so the next launch in the same
+//! project resumes there instead of losing review progress
+
+use crate::history;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+
+/// Actions kept for the crash report
+const RECENT_ACTIONS: usize = 50;
+
+/// Position in a review, saved when oy crashes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Display path of the open file
+    pub file: String,
+    /// 0-based step in that file
+    pub step: usize,
+}
+
+struct Recorder {
+    project: Option<PathBuf>,
+    session: Option<Session>,
+    actions: VecDeque<String>,
+}
+
+static RECORDER: Mutex<Recorder> = Mutex::new(Recorder {
+    project: None,
+    session: None,
+    actions: VecDeque::new(),
+});
+
+static INSTALL: Once = Once::new();
+
+/// Handle panics from now on: `restore_terminal` leaves raw mode and the
+/// alternate screen before anything is printed
+pub fn install(project: Option<PathBuf>, restore_terminal: fn()) {
+    lock().project = project;
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            on_panic(info);
+            previous(info);
+        }));
+    });
+}
+
+/// Note an action (a key, a click) taken at `session`
+pub fn record(action: String, session: Session) {
+    let mut recorder = lock();
+    if recorder.actions.len() == RECENT_ACTIONS {
+        recorder.actions.pop_front();
+    }
+    recorder.actions.push_back(format!(
+        "{action}  ({} step {})",
+        session.file,
+        session.step + 1
+    ));
+    recorder.session = Some(session);
+}
+
+/// The session saved by a crash in `project`, removed so it is only
+/// restored once
+pub fn take_session(project: &Path) -> Option<Session> {
+    let path = history::project_file(project, "sessions")?;
+    let text = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    toml::from_str(&text).ok()
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Recorder> {
+    // A panic elsewhere must not stop the report from being written
+    RECORDER.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn on_panic(info: &PanicHookInfo) {
+    let recorder = lock();
+    if let (Some(project), Some(session)) = (&recorder.project, &recorder.session) {
+        if let Some(path) = history::project_file(project, "sessions") {
+            if let Ok(text) = toml::to_string(session) {
+                if write_file(&path, &text).is_ok() {
+                    eprintln!(
+                        "oy crashed; your position ({} step {}) is restored on the next launch",
+                        session.file,
+                        session.step + 1
+                    );
+                }
+            }
+        }
+    }
+    let report = report(info, &recorder.actions);
+    let path = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("oyo").join("crashes").join(report_name()));
+    match path {
+        Some(path) if write_file(&path, &report).is_ok() => {
+            eprintln!("Crash report written to {}", path.display());
+        }
+        _ => eprintln!("{report}"),
+    }
+}
+
+fn report(info: &PanicHookInfo, actions: &VecDeque<String>) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let mut report = format!(
+        "oy {} ({} {})\npanic: {message}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    );
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "at: {location}");
+    }
+    let _ = writeln!(report, "\nlast actions (oldest first):");
+    for action in actions {
+        let _ = writeln!(report, "  {action}");
+    }
+    let _ = writeln!(
+        report,
+        "\nbacktrace:\n{}",
+        std::backtrace::Backtrace::force_capture()
+    );
+    report
+}
+
+/// `crash-<unix seconds>-<pid>.txt`
+fn report_name() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format!("crash-{secs}-{}.txt", std::process::id())
+}
+
+fn write_file(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_recent_actions_and_position() {
+        for step in 0..RECENT_ACTIONS + 10 {
+            let session = Session {
+                file: "src/lib.rs".to_string(),
+                step,
+            };
+            record(format!("j{step}"), session);
+        }
+        let recorder = lock();
+        assert_eq!(recorder.actions.len(), RECENT_ACTIONS);
+        assert_eq!(
+            recorder.actions.front().unwrap(),
+            "j10  (src/lib.rs step 11)"
+        );
+        assert_eq!(
+            recorder.session,
+            Some(Session {
+                file: "src/lib.rs".to_string(),
+                step: RECENT_ACTIONS + 9,
+            })
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Git range picker dashboard for oy view
 
 use crate::config::ResolvedTheme;
+use crate::views::truncate_text;
 use oyo_core::git::CommitEntry;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -784,30 +785,6 @@ impl DashboardEntry {
     }
 }
 
-fn truncate_text(text: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
-    }
-    if max_width <= 3 {
-        return ".".repeat(max_width);
-    }
-    if text_width(text) <= max_width {
-        return text.to_string();
-    }
-    let suffix_len = max_width.saturating_sub(3);
-    let mut acc = String::new();
-    let mut width = 0usize;
-    for ch in text.chars() {
-        let ch_width = UnicodeWidthStr::width(ch.to_string().as_str());
-        if width + ch_width > suffix_len {
-            break;
-        }
-        acc.push(ch);
-        width += ch_width;
-    }
-    format!("{acc}...")
-}
-
 fn shorten_hash(hash: &str) -> String {
     hash.chars().take(8).collect()
 }
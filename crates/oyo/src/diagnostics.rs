@@ -0,0 +1,139 @@
+//! Compiler/CI diagnostics shown on the lines they point at (`--diagnostics`)
+//!
+//! Accepts `cargo check --message-format=json` output and plain
+//! `path:line[:col]: [severity:] message` lines (gcc, clang, eslint's unix
+//! format, ...), mixed freely.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn parse(level: &str) -> Self {
+        match level.to_ascii_lowercase().as_str() {
+            "error" | "fatal" | "error: internal compiler error" => Severity::Error,
+            "warning" | "warn" => Severity::Warning,
+            _ => Severity::Note,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    /// 1-based line in the new version of the file
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// True if the diagnostic's path names `path` (either may be relative to
+    /// a different directory, so one only has to end with the other)
+    pub fn is_for(&self, path: &Path) -> bool {
+        self.path.ends_with(path) || path.ends_with(&self.path)
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    level: String,
+    spans: Vec<CompilerSpan>,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: PathBuf,
+    line_start: usize,
+    is_primary: bool,
+}
+
+/// Parse diagnostics from compiler/CI output; unrecognized lines are skipped
+pub fn parse(output: &str) -> Vec<Diagnostic> {
+    output.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        let cargo: CargoMessage = serde_json::from_str(line).ok()?;
+        if cargo.reason != "compiler-message" {
+            return None;
+        }
+        let message = cargo.message?;
+        let span = message.spans.iter().find(|span| span.is_primary)?;
+        return Some(Diagnostic {
+            path: span.file_name.clone(),
+            line: span.line_start,
+            severity: Severity::parse(&message.level),
+            message: message.message,
+        });
+    }
+
+    static TEXT: OnceLock<Regex> = OnceLock::new();
+    let text = TEXT.get_or_init(|| {
+        Regex::new(
+            r"^(?P<path>[^:\s][^:]*):(?P<line>\d+)(?::\d+)?:\s*(?:(?P<level>error|warning|warn|note|info|help)(?:\[[^\]]*\])?:\s*)?(?P<message>.+)$",
+        )
+        .expect("diagnostic pattern is valid")
+    });
+    let caps = text.captures(line)?;
+    Some(Diagnostic {
+        path: PathBuf::from(&caps["path"]),
+        line: caps["line"].parse().ok().filter(|&line| line > 0)?,
+        severity: caps
+            .name("level")
+            .map_or(Severity::Warning, |level| Severity::parse(level.as_str())),
+        message: caps["message"].trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_json() {
+        let output = r#"{"reason":"compiler-artifact","package_id":"x"}
+{"reason":"compiler-message","message":{"message":"unused variable: `x`","level":"warning","spans":[{"file_name":"src/main.rs","line_start":4,"is_primary":true}]}}"#;
+        assert_eq!(
+            parse(output),
+            vec![Diagnostic {
+                path: PathBuf::from("src/main.rs"),
+                line: 4,
+                severity: Severity::Warning,
+                message: "unused variable: `x`".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_lines() {
+        let diags = parse(
+            "src/a.c:12:5: error: expected ';'\n\
+             lib/b.py:3: missing docstring\n\
+             Compiling oyo v0.1.0\n",
+        );
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].message, "expected ';'");
+        assert_eq!(diags[1].line, 3);
+        assert_eq!(diags[1].severity, Severity::Warning);
+        assert!(diags[1].is_for(Path::new("repo/lib/b.py")));
+    }
+}
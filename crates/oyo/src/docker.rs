@@ -0,0 +1,81 @@
+//! Files inside docker containers and images (`oy docker`)
+
+use crate::archive;
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Split `<container-or-image>:<absolute path>`; image tags may contain ':'
+pub fn parse_source(source: &str) -> Option<(&str, &str)> {
+    let split = source.find(":/")?;
+    let (target, path) = (&source[..split], &source[split + 1..]);
+    (!target.is_empty() && path.len() > 1).then_some((target, path))
+}
+
+fn docker(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .context("Failed to run docker")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+/// Content of the first regular file in a tar stream (`docker cp ... -`)
+fn first_file_in_tar(tar: &[u8]) -> Option<&[u8]> {
+    archive::tar_files(tar)
+        .into_iter()
+        .next()
+        .map(|(_, data)| data)
+}
+
+fn copy_out(container: &str, path: &str) -> Result<String> {
+    let tar = docker(&["cp", "-L", &format!("{container}:{path}"), "-"])?;
+    let content = first_file_in_tar(&tar).context(format!("{path} is not a regular file"))?;
+    Ok(String::from_utf8_lossy(content).to_string())
+}
+
+/// Read a file from a container, or from an image via a temporary container
+pub fn read(source: &str) -> Result<String> {
+    let (target, path) = parse_source(source)
+        .context("Expected <container-or-image>:<absolute path>, e.g. web:/etc/nginx.conf")?;
+    if let Ok(content) = copy_out(target, path) {
+        return Ok(content);
+    }
+
+    let id =
+        docker(&["create", target]).context(format!("No container or image named {target}"))?;
+    let id = String::from_utf8_lossy(&id).trim().to_string();
+    let content = copy_out(&id, path);
+    let _ = docker(&["rm", &id]);
+    content.context(format!("Failed to read {path} from {target}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_source_and_tar() {
+        assert_eq!(
+            parse_source("web:/etc/nginx.conf"),
+            Some(("web", "/etc/nginx.conf"))
+        );
+        assert_eq!(
+            parse_source("nginx:1.27:/etc/nginx/nginx.conf"),
+            Some(("nginx:1.27", "/etc/nginx/nginx.conf"))
+        );
+        assert_eq!(parse_source("web:etc/nginx.conf"), None);
+
+        let mut tar = vec![0u8; 512];
+        tar[..8].copy_from_slice(b"app.conf");
+        tar[124..136].copy_from_slice(b"00000000005\0");
+        tar[156] = b'0';
+        let mut data = b"hello".to_vec();
+        data.resize(512, 0);
+        tar.extend(data);
+        tar.extend([0u8; 1024]);
+        assert_eq!(first_file_in_tar(&tar), Some(&b"hello"[..]));
+    }
+}
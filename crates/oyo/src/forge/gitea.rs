@@ -0,0 +1,203 @@
+//! Gitea and Forgejo pull requests
+//!
+//! Gitea has no reply endpoint: its review comments are grouped by line,
+//! so a thread is every comment on one line, and a reply is a new review
+//! comment on that line.
+
+use super::{Api, Comment, Forge, PullRequest, Side, Thread};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize)]
+struct ApiRef {
+    #[serde(rename = "ref")]
+    name: String,
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct ApiPullRequest {
+    number: u64,
+    title: String,
+    base: ApiRef,
+    head: ApiRef,
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct ApiReview {
+    id: u64,
+    user: Option<ApiUser>,
+    #[serde(default)]
+    submitted_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiComment {
+    id: u64,
+    path: PathBuf,
+    /// Line on the new side, 0 if on the old side
+    #[serde(default)]
+    position: usize,
+    /// Line on the old side, 0 if on the new side
+    #[serde(default)]
+    original_position: usize,
+    user: Option<ApiUser>,
+    body: String,
+    created_at: String,
+}
+
+fn login(user: Option<&ApiUser>) -> String {
+    user.map_or_else(|| "ghost".to_string(), |user| user.login.clone())
+}
+
+/// Group review comments by the line they are on, ordered by path and
+/// line; each group's comments are ordered by time
+fn threads_from_comments(mut comments: Vec<ApiComment>) -> Vec<Thread> {
+    comments.sort_by(|a, b| (&a.created_at, a.id).cmp(&(&b.created_at, b.id)));
+    let mut threads: Vec<Thread> = Vec::new();
+    for comment in comments {
+        let (side, line) = match (comment.position, comment.original_position) {
+            (0, 0) => continue,
+            (0, line) => (Side::Old, line),
+            (line, _) => (Side::New, line),
+        };
+        let reply = Comment {
+            author: login(comment.user.as_ref()),
+            body: comment.body,
+            created_at: comment.created_at,
+        };
+        let existing = threads.iter_mut().find(|thread| {
+            thread.path == comment.path && thread.side == side && thread.line == line
+        });
+        match existing {
+            Some(thread) => thread.comments.push(reply),
+            None => threads.push(Thread {
+                id: comment.id.to_string(),
+                path: comment.path,
+                side,
+                line,
+                outdated: false,
+                comments: vec![reply],
+            }),
+        }
+    }
+    threads.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    threads
+}
+
+/// A repository on Gitea or Forgejo
+#[derive(Debug, Clone)]
+pub struct Gitea {
+    api: Api,
+    /// `owner/name`
+    repo: String,
+}
+
+impl Gitea {
+    pub fn new(api: Api, repo: String) -> Self {
+        Self { api, repo }
+    }
+}
+
+impl Forge for Gitea {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    fn api(&self) -> &Api {
+        &self.api
+    }
+
+    fn pull_request(&self, number: u64) -> Result<PullRequest> {
+        let path = format!("repos/{}/pulls/{number}", self.repo);
+        let value = self.api.request("GET", &path, None)?;
+        let pr: ApiPullRequest =
+            serde_json::from_value(value).context("Unexpected pull request response")?;
+        Ok(PullRequest {
+            number: pr.number,
+            title: pr.title,
+            base_ref: pr.base.name,
+            base_sha: pr.base.sha,
+            head_sha: pr.head.sha,
+        })
+    }
+
+    fn review_threads(&self, pr: &PullRequest) -> Result<Vec<Thread>> {
+        let reviews_path = format!("repos/{}/pulls/{}/reviews", self.repo, pr.number);
+        let reviews: Vec<ApiReview> = self.api.list(&reviews_path)?;
+        let mut comments = Vec::new();
+        for review in reviews {
+            let path = format!("{reviews_path}/{}/comments", review.id);
+            let value = self.api.request("GET", &path, None)?;
+            let batch: Vec<ApiComment> =
+                serde_json::from_value(value).context("Unexpected review comments response")?;
+            comments.extend(batch);
+        }
+        Ok(threads_from_comments(comments))
+    }
+
+    fn reply(&self, pr: &PullRequest, thread: &Thread, body: &str) -> Result<Comment> {
+        self.api.require_token()?;
+        let position = match thread.side {
+            Side::Old => "old_position",
+            Side::New => "new_position",
+        };
+        let request = serde_json::json!({
+            "event": "COMMENT",
+            "body": "",
+            "comments": [{
+                "path": thread.path,
+                "body": body,
+                (position): thread.line,
+            }],
+        });
+        let path = format!("repos/{}/pulls/{}/reviews", self.repo, pr.number);
+        let value = self.api.request("POST", &path, Some(&request))?;
+        let review: ApiReview =
+            serde_json::from_value(value).context("Unexpected review response")?;
+        Ok(Comment {
+            author: login(review.user.as_ref()),
+            body: body.to_string(),
+            created_at: review.submitted_at.unwrap_or_default(),
+        })
+    }
+
+    fn head_ref(&self, number: u64) -> String {
+        format!("pull/{number}/head")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_on_a_line_form_a_thread() {
+        let comments: Vec<ApiComment> = serde_json::from_str(
+            r#"[
+                {"id": 5, "path": "a.rs", "position": 3, "original_position": 0,
+                 "user": {"login": "bo"}, "body": "agreed",
+                 "created_at": "2026-01-02T00:00:00Z"},
+                {"id": 4, "path": "a.rs", "position": 3, "original_position": 0,
+                 "user": {"login": "ana"}, "body": "rename?",
+                 "created_at": "2026-01-01T00:00:00Z"},
+                {"id": 6, "path": "a.rs", "position": 0, "original_position": 3,
+                 "user": null, "body": "old side",
+                 "created_at": "2026-01-03T00:00:00Z"}
+            ]"#,
+        )
+        .unwrap();
+        let threads = threads_from_comments(comments);
+        assert_eq!(threads.len(), 2);
+        let new = threads.iter().find(|t| t.side == Side::New).unwrap();
+        assert_eq!(new.id, "4");
+        let bodies: Vec<&str> = new.comments.iter().map(|c| c.body.as_str()).collect();
+        assert_eq!(bodies, ["rename?", "agreed"]);
+    }
+}
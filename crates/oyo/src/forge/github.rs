@@ -0,0 +1,180 @@
+//! GitHub (and GitHub Enterprise) pull requests
+
+use super::{Api, Comment, Forge, PullRequest, Side, Thread};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize)]
+struct ApiRef {
+    #[serde(rename = "ref")]
+    name: String,
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct ApiPullRequest {
+    number: u64,
+    title: String,
+    base: ApiRef,
+    head: ApiRef,
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct ApiComment {
+    id: u64,
+    in_reply_to_id: Option<u64>,
+    path: PathBuf,
+    line: Option<usize>,
+    original_line: Option<usize>,
+    side: Option<String>,
+    user: Option<ApiUser>,
+    body: String,
+    created_at: String,
+}
+
+impl ApiComment {
+    fn comment(&self) -> Comment {
+        Comment {
+            author: self
+                .user
+                .as_ref()
+                .map_or_else(|| "ghost".to_string(), |user| user.login.clone()),
+            body: self.body.clone(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+/// Group review comments into threads, ordered by path and line; replies
+/// to comments not in `comments` are dropped
+fn threads_from_comments(comments: &[ApiComment]) -> Vec<Thread> {
+    let mut threads: Vec<Thread> = comments
+        .iter()
+        .filter(|comment| comment.in_reply_to_id.is_none())
+        .filter_map(|comment| {
+            let line = comment.line.or(comment.original_line)?;
+            Some(Thread {
+                id: comment.id.to_string(),
+                path: comment.path.clone(),
+                side: match comment.side.as_deref() {
+                    Some("LEFT") => Side::Old,
+                    _ => Side::New,
+                },
+                line,
+                outdated: comment.line.is_none(),
+                comments: vec![comment.comment()],
+            })
+        })
+        .collect();
+    for reply in comments {
+        let Some(root) = reply.in_reply_to_id else {
+            continue;
+        };
+        let root = root.to_string();
+        if let Some(thread) = threads.iter_mut().find(|thread| thread.id == root) {
+            thread.comments.push(reply.comment());
+        }
+    }
+    threads.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    threads
+}
+
+/// A repository on GitHub
+#[derive(Debug, Clone)]
+pub struct GitHub {
+    api: Api,
+    /// `owner/name`
+    repo: String,
+}
+
+impl GitHub {
+    pub fn new(api: Api, repo: String) -> Self {
+        Self { api, repo }
+    }
+}
+
+impl Forge for GitHub {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn api(&self) -> &Api {
+        &self.api
+    }
+
+    fn pull_request(&self, number: u64) -> Result<PullRequest> {
+        let path = format!("repos/{}/pulls/{number}", self.repo);
+        let value = self.api.request("GET", &path, None)?;
+        let pr: ApiPullRequest =
+            serde_json::from_value(value).context("Unexpected pull request response")?;
+        Ok(PullRequest {
+            number: pr.number,
+            title: pr.title,
+            base_ref: pr.base.name,
+            base_sha: pr.base.sha,
+            head_sha: pr.head.sha,
+        })
+    }
+
+    fn review_threads(&self, pr: &PullRequest) -> Result<Vec<Thread>> {
+        let path = format!("repos/{}/pulls/{}/comments", self.repo, pr.number);
+        let comments: Vec<ApiComment> = self.api.list(&path)?;
+        Ok(threads_from_comments(&comments))
+    }
+
+    fn reply(&self, pr: &PullRequest, thread: &Thread, body: &str) -> Result<Comment> {
+        self.api.require_token()?;
+        let path = format!(
+            "repos/{}/pulls/{}/comments/{}/replies",
+            self.repo, pr.number, thread.id
+        );
+        let value = self
+            .api
+            .request("POST", &path, Some(&serde_json::json!({ "body": body })))?;
+        let comment: ApiComment =
+            serde_json::from_value(value).context("Unexpected reply response")?;
+        Ok(comment.comment())
+    }
+
+    fn head_ref(&self, number: u64) -> String {
+        format!("pull/{number}/head")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_comments_are_grouped_into_threads() {
+        let comments: Vec<ApiComment> = serde_json::from_str(
+            r#"[
+                {"id": 1, "path": "src/b.rs", "line": 9, "original_line": 9,
+                 "side": "RIGHT", "user": {"login": "ana"}, "body": "why?",
+                 "created_at": "2026-01-01T00:00:00Z"},
+                {"id": 2, "path": "src/a.rs", "line": null, "original_line": 4,
+                 "side": "LEFT", "user": {"login": "bo"}, "body": "nit",
+                 "created_at": "2026-01-01T00:00:00Z"},
+                {"id": 3, "in_reply_to_id": 1, "path": "src/b.rs", "line": 9,
+                 "side": "RIGHT", "user": null, "body": "because",
+                 "created_at": "2026-01-02T00:00:00Z"}
+            ]"#,
+        )
+        .unwrap();
+        let threads = threads_from_comments(&comments);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].path, PathBuf::from("src/a.rs"));
+        assert_eq!((threads[0].side, threads[0].line), (Side::Old, 4));
+        assert!(threads[0].outdated);
+        assert_eq!(threads[1].comments.len(), 2);
+        assert_eq!(threads[1].comments[1].author, "ghost");
+        assert!(threads[1].is_for(Path::new("b.rs")));
+    }
+}
@@ -0,0 +1,202 @@
+//! GitLab merge requests
+
+use super::{Api, Comment, Forge, PullRequest, Side, Thread};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize)]
+struct ApiDiffRefs {
+    base_sha: String,
+    head_sha: String,
+}
+
+#[derive(Deserialize)]
+struct ApiMergeRequest {
+    iid: u64,
+    title: String,
+    target_branch: String,
+    diff_refs: Option<ApiDiffRefs>,
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct ApiPosition {
+    old_path: Option<PathBuf>,
+    new_path: Option<PathBuf>,
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ApiNote {
+    body: String,
+    author: ApiUser,
+    created_at: String,
+    #[serde(default)]
+    system: bool,
+    position: Option<ApiPosition>,
+}
+
+impl ApiNote {
+    fn comment(&self) -> Comment {
+        Comment {
+            author: self.author.username.clone(),
+            body: self.body.clone(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiDiscussion {
+    id: String,
+    notes: Vec<ApiNote>,
+}
+
+/// Discussions on the diff as threads, ordered by path and line; general
+/// discussions and system notes are left out
+fn threads_from_discussions(discussions: &[ApiDiscussion]) -> Vec<Thread> {
+    let mut threads: Vec<Thread> = discussions
+        .iter()
+        .filter_map(|discussion| {
+            let first = discussion.notes.first()?;
+            let position = first.position.as_ref()?;
+            let (side, path, line) = match (position.new_line, position.old_line) {
+                (Some(line), _) => (Side::New, position.new_path.as_ref()?, line),
+                (None, Some(line)) => (Side::Old, position.old_path.as_ref()?, line),
+                (None, None) => return None,
+            };
+            Some(Thread {
+                id: discussion.id.clone(),
+                path: path.clone(),
+                side,
+                line,
+                outdated: false,
+                comments: discussion
+                    .notes
+                    .iter()
+                    .filter(|note| !note.system)
+                    .map(ApiNote::comment)
+                    .collect(),
+            })
+        })
+        .collect();
+    threads.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    threads
+}
+
+/// A project on GitLab
+#[derive(Debug, Clone)]
+pub struct GitLab {
+    api: Api,
+    /// URL-encoded `group/.../name`, as the API takes it
+    project: String,
+}
+
+impl GitLab {
+    pub fn new(api: Api, project: &str) -> Self {
+        Self {
+            api,
+            project: project.replace('/', "%2F"),
+        }
+    }
+}
+
+impl Forge for GitLab {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    fn api(&self) -> &Api {
+        &self.api
+    }
+
+    fn pull_request(&self, number: u64) -> Result<PullRequest> {
+        let path = format!("projects/{}/merge_requests/{number}", self.project);
+        let value = self.api.request("GET", &path, None)?;
+        let mr: ApiMergeRequest =
+            serde_json::from_value(value).context("Unexpected merge request response")?;
+        let refs = mr
+            .diff_refs
+            .context("The merge request has no diff (still preparing?)")?;
+        Ok(PullRequest {
+            number: mr.iid,
+            title: mr.title,
+            base_ref: mr.target_branch,
+            base_sha: refs.base_sha,
+            head_sha: refs.head_sha,
+        })
+    }
+
+    fn review_threads(&self, pr: &PullRequest) -> Result<Vec<Thread>> {
+        let path = format!(
+            "projects/{}/merge_requests/{}/discussions",
+            self.project, pr.number
+        );
+        let discussions: Vec<ApiDiscussion> = self.api.list(&path)?;
+        Ok(threads_from_discussions(&discussions))
+    }
+
+    fn reply(&self, pr: &PullRequest, thread: &Thread, body: &str) -> Result<Comment> {
+        self.api.require_token()?;
+        let path = format!(
+            "projects/{}/merge_requests/{}/discussions/{}/notes",
+            self.project, pr.number, thread.id
+        );
+        let value = self
+            .api
+            .request("POST", &path, Some(&serde_json::json!({ "body": body })))?;
+        let note: ApiNote = serde_json::from_value(value).context("Unexpected reply response")?;
+        Ok(note.comment())
+    }
+
+    fn head_ref(&self, number: u64) -> String {
+        format!("merge-requests/{number}/head")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_discussions_become_threads() {
+        let discussions: Vec<ApiDiscussion> = serde_json::from_str(
+            r#"[
+                {"id": "general", "notes": [
+                    {"body": "LGTM", "author": {"username": "ana"},
+                     "created_at": "2026-01-01T00:00:00Z", "position": null}]},
+                {"id": "d1", "notes": [
+                    {"body": "off by one?", "author": {"username": "bo"},
+                     "created_at": "2026-01-01T00:00:00Z",
+                     "position": {"old_path": "src/a.rs", "new_path": "src/a.rs",
+                                  "old_line": 7, "new_line": null}},
+                    {"body": "changed this line", "author": {"username": "bo"},
+                     "created_at": "2026-01-01T00:00:00Z", "system": true,
+                     "position": null},
+                    {"body": "no", "author": {"username": "cy"},
+                     "created_at": "2026-01-02T00:00:00Z", "position": null}]}
+            ]"#,
+        )
+        .unwrap();
+        let threads = threads_from_discussions(&discussions);
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].id, "d1");
+        assert_eq!((threads[0].side, threads[0].line), (Side::Old, 7));
+        let authors: Vec<&str> = threads[0]
+            .comments
+            .iter()
+            .map(|comment| comment.author.as_str())
+            .collect();
+        assert_eq!(authors, ["bo", "cy"]);
+        assert_eq!(
+            GitLab::new(Api::new(String::new(), Vec::new(), |t| t.into()), "g/s/p").project,
+            "g%2Fs%2Fp"
+        );
+    }
+}
@@ -0,0 +1,477 @@
+//! Pull/merge request review threads from a forge (`oy pr <number>`)
+//!
+//! Each forge is a [`Forge`]: GitHub, GitLab merge requests, and Gitea or
+//! Forgejo pull requests. The forge of a remote is picked by its host
+//! (github.com, gitlab.com, codeberg.org) or by a `[[forge.remotes]]` URL
+//! pattern for self-hosted ones. APIs are called with curl. Review comments
+//! are grouped into threads, each anchored to a line of the old or new side
+//! of a file. Reading works anonymously for public repositories; a token is
+//! sent when set, and is required for replies, which are also off unless
+//! `[forge] replies = true`.
+
+mod gitea;
+mod github;
+mod gitlab;
+
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+
+use crate::config::{ForgeKind, ForgeRemote};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Pages of 100 comments fetched at most
+const MAX_PAGES: usize = 10;
+
+/// Host and repository path of a remote URL (https, ssh or scp-style);
+/// the path may have several parts (GitLab subgroups)
+pub fn parse_remote_url(url: &str) -> Option<(String, String)> {
+    let url = url.trim().trim_end_matches('/');
+    let rest = ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .unwrap_or(url);
+    let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+    let (host, path) = rest.split_once([':', '/'])?;
+    // An ssh port (`host:2222/owner/repo`) is not part of the path
+    let path = match path.split_once('/') {
+        Some((port, path)) if port.chars().all(|c| c.is_ascii_digit()) => path,
+        _ => path,
+    };
+    let path = path.trim_start_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some((host.to_string(), path.to_string()))
+}
+
+/// The refs a pull/merge request compares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub base_ref: String,
+    pub base_sha: String,
+    pub head_sha: String,
+}
+
+/// Side of the diff a thread is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Old,
+    New,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+    /// RFC 3339 time the comment was made
+    pub created_at: String,
+}
+
+/// A review comment and its replies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thread {
+    /// Forge id of the thread (its first comment or discussion)
+    pub id: String,
+    pub path: PathBuf,
+    pub side: Side,
+    /// 1-based line on `side`; the line the thread was started on if the
+    /// code moved since
+    pub line: usize,
+    /// The commented code changed after the thread was started
+    pub outdated: bool,
+    pub comments: Vec<Comment>,
+}
+
+impl Thread {
+    /// True if the thread's path names `path` (as [`crate::diagnostics`] does)
+    pub fn is_for(&self, path: &Path) -> bool {
+        self.path.ends_with(path) || path.ends_with(&self.path)
+    }
+}
+
+/// A forge's pull/merge request API
+pub trait Forge: Debug {
+    /// Name of the forge, e.g. "GitHub"
+    fn name(&self) -> &'static str;
+
+    fn api(&self) -> &Api;
+
+    fn pull_request(&self, number: u64) -> Result<PullRequest>;
+
+    /// Review threads on the request's code, ordered by path and line
+    fn review_threads(&self, pr: &PullRequest) -> Result<Vec<Thread>>;
+
+    /// Post `body` as a reply to `thread`; returns the reply as posted
+    fn reply(&self, pr: &PullRequest, thread: &Thread, body: &str) -> Result<Comment>;
+
+    /// Ref the request's head commit can be fetched from
+    fn head_ref(&self, number: u64) -> String;
+
+    fn has_token(&self) -> bool {
+        self.api().token.is_some()
+    }
+}
+
+/// A remote on a forge: its kind, the `[[forge.remotes]]` entry matching
+/// it (if any), host and repository path
+struct Remote<'a> {
+    kind: ForgeKind,
+    configured: Option<&'a ForgeRemote>,
+    host: String,
+    path: String,
+}
+
+/// The forge `url` is on: the first `[[forge.remotes]]` entry matching it,
+/// else one of the well-known hosts
+fn remote<'a>(url: &str, remotes: &'a [ForgeRemote]) -> Result<Remote<'a>> {
+    let (host, path) = parse_remote_url(url).context(format!("Not a repository URL: {url}"))?;
+    let configured = remotes.iter().find(|remote| remote.url.is_match(url));
+    let kind = match (configured, host.as_str()) {
+        (Some(remote), _) => remote.kind,
+        (None, "github.com") => ForgeKind::Github,
+        (None, "gitlab.com") => ForgeKind::Gitlab,
+        (None, "codeberg.org") => ForgeKind::Gitea,
+        (None, _) => {
+            anyhow::bail!("Unknown forge for {url}; add a [[forge.remotes]] entry with its kind")
+        }
+    };
+    Ok(Remote {
+        kind,
+        configured,
+        host,
+        path,
+    })
+}
+
+/// Open the forge `url` is on
+pub fn open(url: &str, remotes: &[ForgeRemote]) -> Result<Box<dyn Forge>> {
+    let Remote {
+        kind,
+        configured,
+        host,
+        path,
+    } = remote(url, remotes)?;
+    let api = configured.and_then(|remote| remote.api.clone());
+    let token_env = configured.and_then(|remote| remote.token_env.clone());
+    let token_vars = |defaults: &[&str]| match &token_env {
+        Some(name) => vec![name.clone()],
+        None => defaults.iter().map(|name| name.to_string()).collect(),
+    };
+    Ok(match kind {
+        ForgeKind::Github => {
+            let base = api.unwrap_or_else(|| match host.as_str() {
+                "github.com" => "https://api.github.com".to_string(),
+                _ => format!("https://{host}/api/v3"),
+            });
+            Box::new(GitHub::new(
+                Api::new(base, token_vars(&["GITHUB_TOKEN", "GH_TOKEN"]), |token| {
+                    format!("Authorization: Bearer {token}")
+                }),
+                path,
+            ))
+        }
+        ForgeKind::Gitlab => {
+            let base = api.unwrap_or_else(|| format!("https://{host}/api/v4"));
+            Box::new(GitLab::new(
+                Api::new(base, token_vars(&["GITLAB_TOKEN"]), |token| {
+                    format!("PRIVATE-TOKEN: {token}")
+                }),
+                &path,
+            ))
+        }
+        ForgeKind::Gitea => {
+            let base = api.unwrap_or_else(|| format!("https://{host}/api/v1"));
+            Box::new(Gitea::new(
+                Api::new(
+                    base,
+                    token_vars(&["GITEA_TOKEN", "FORGEJO_TOKEN"]),
+                    |token| format!("Authorization: token {token}"),
+                ),
+                path,
+            ))
+        }
+    })
+}
+
+/// Web URL of `line` of `path` at `commit` in the repository at `url`,
+/// from the remote's `permalink` template or the forge's usual one;
+/// `{host}`, `{repo}`, `{commit}`, `{path}` and `{line}` are filled in
+pub fn permalink(
+    url: &str,
+    remotes: &[ForgeRemote],
+    commit: &str,
+    path: &Path,
+    line: usize,
+) -> Result<String> {
+    let remote = remote(url, remotes)?;
+    let template = match remote.configured.and_then(|r| r.permalink.as_deref()) {
+        Some(template) => template,
+        None => match remote.kind {
+            ForgeKind::Github => "https://{host}/{repo}/blob/{commit}/{path}#L{line}",
+            ForgeKind::Gitlab => "https://{host}/{repo}/-/blob/{commit}/{path}#L{line}",
+            ForgeKind::Gitea => "https://{host}/{repo}/src/commit/{commit}/{path}#L{line}",
+        },
+    };
+    let path = path
+        .components()
+        .map(|part| url_escape(&part.as_os_str().to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("/");
+    Ok(template
+        .replace("{host}", &remote.host)
+        .replace("{repo}", &remote.path)
+        .replace("{commit}", commit)
+        .replace("{path}", &path)
+        .replace("{line}", &line.to_string()))
+}
+
+/// Percent-encode a URL path segment
+fn url_escape(segment: &str) -> String {
+    let mut escaped = String::new();
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    escaped
+}
+
+/// Quote a value for a curl config file
+fn curl_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// A forge's REST API root and credentials
+#[derive(Debug, Clone)]
+pub struct Api {
+    base: String,
+    /// Variables the token is read from, first set one wins
+    token_vars: Vec<String>,
+    token: Option<String>,
+    /// Header line carrying the token
+    auth: fn(&str) -> String,
+}
+
+impl Api {
+    pub fn new(base: String, token_vars: Vec<String>, auth: fn(&str) -> String) -> Self {
+        let token = token_vars
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|token| !token.is_empty()));
+        Self {
+            base: base.trim_end_matches('/').to_string(),
+            token_vars,
+            token,
+            auth,
+        }
+    }
+
+    /// Where the token is looked for, e.g. "GITHUB_TOKEN or GH_TOKEN"
+    pub fn token_hint(&self) -> String {
+        self.token_vars.join(" or ")
+    }
+
+    /// Fail unless a token is set, as writing needs one
+    fn require_token(&self) -> Result<()> {
+        if self.token.is_none() {
+            anyhow::bail!("replying needs a token in {}", self.token_hint());
+        }
+        Ok(())
+    }
+
+    /// Call `path` under the API root; the token and body go to curl on
+    /// stdin, so they are not visible in the process list
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = format!("{}/{path}", self.base);
+        let mut config = vec![
+            format!("url = {}", curl_quote(&url)),
+            format!("request = {}", curl_quote(method)),
+            "header = \"Accept: application/json\"".to_string(),
+        ];
+        if let Some(token) = &self.token {
+            config.push(format!("header = {}", curl_quote(&(self.auth)(token))));
+        }
+        if let Some(body) = body {
+            config.push("header = \"Content-Type: application/json\"".to_string());
+            config.push(format!("data-binary = {}", curl_quote(&body.to_string())));
+        }
+
+        let mut child = Command::new("curl")
+            .args([
+                "-sSL",
+                "--max-time",
+                "30",
+                "-w",
+                "\n%{http_code}",
+                "-K",
+                "-",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            use std::io::Write;
+            stdin.write_all(config.join("\n").as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+        let value: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        if !status.starts_with('2') {
+            let message = value["message"]
+                .as_str()
+                .or(value["error"].as_str())
+                .unwrap_or("request failed");
+            anyhow::bail!("{message} (HTTP {status})");
+        }
+        Ok(value)
+    }
+
+    /// Every item of a paginated list (`page` and `per_page` parameters)
+    fn list<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let value = self.request(
+                "GET",
+                &format!("{path}{separator}per_page=100&page={page}"),
+                None,
+            )?;
+            let batch: Vec<T> =
+                serde_json::from_value(value).context(format!("Unexpected response: {path}"))?;
+            let last = batch.len() < 100;
+            items.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// Fetch the request's commits from `remote` unless they are already in
+/// the repository
+pub fn fetch_commits(
+    repo_root: &Path,
+    remote: &str,
+    forge: &dyn Forge,
+    pr: &PullRequest,
+) -> Result<()> {
+    let has_commit = |sha: &str| {
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["cat-file", "-e", &format!("{sha}^{{commit}}")])
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    };
+    if has_commit(&pr.base_sha) && has_commit(&pr.head_sha) {
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["fetch", "--quiet", remote])
+        .arg(forge.head_ref(pr.number))
+        .arg(&pr.base_ref)
+        .output()
+        .context("Failed to run git fetch")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Review threads of the request being reviewed
+#[derive(Debug)]
+pub struct Review {
+    pub forge: Box<dyn Forge>,
+    pub pr: PullRequest,
+    pub threads: Vec<Thread>,
+    /// Replies may be posted (`[forge] replies`)
+    pub replies: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_url() {
+        let repo = Some(("github.com".to_string(), "ahkohd/oyo".to_string()));
+        assert_eq!(parse_remote_url("https://github.com/ahkohd/oyo.git"), repo);
+        assert_eq!(parse_remote_url("git@github.com:ahkohd/oyo.git"), repo);
+        assert_eq!(parse_remote_url("ssh://git@github.com/ahkohd/oyo"), repo);
+        assert_eq!(
+            parse_remote_url("ssh://git@git.corp:2222/team/infra/oyo.git"),
+            Some(("git.corp".to_string(), "team/infra/oyo".to_string()))
+        );
+        assert_eq!(parse_remote_url("/srv/git/oyo"), None);
+    }
+
+    #[test]
+    fn test_forge_is_picked_by_host_or_configured_pattern() {
+        let remotes = [ForgeRemote {
+            url: regex::Regex::new(r"git\.corp\b").unwrap(),
+            kind: ForgeKind::Gitea,
+            api: None,
+            token_env: Some("CORP_TOKEN".to_string()),
+            permalink: None,
+        }];
+        let forge = open("git@gitlab.com:group/sub/oyo.git", &remotes).unwrap();
+        assert_eq!(forge.name(), "GitLab");
+        let forge = open("https://git.corp/team/oyo", &remotes).unwrap();
+        assert_eq!(forge.name(), "Gitea");
+        assert_eq!(forge.api().token_hint(), "CORP_TOKEN");
+        assert!(open("https://git.other/team/oyo", &remotes).is_err());
+    }
+
+    #[test]
+    fn test_permalink_uses_forge_or_configured_template() {
+        let remotes = [ForgeRemote {
+            url: regex::Regex::new(r"git\.corp\b").unwrap(),
+            kind: ForgeKind::Gitlab,
+            api: None,
+            token_env: None,
+            permalink: Some("https://code.corp/{repo}/{path}?at={commit}#{line}".to_string()),
+        }];
+        let path = Path::new("src/my file.rs");
+        assert_eq!(
+            permalink("git@github.com:ahkohd/oyo.git", &remotes, "abc", path, 7).unwrap(),
+            "https://github.com/ahkohd/oyo/blob/abc/src/my%20file.rs#L7"
+        );
+        assert_eq!(
+            permalink("https://gitlab.com/g/sub/oyo", &remotes, "abc", path, 7).unwrap(),
+            "https://gitlab.com/g/sub/oyo/-/blob/abc/src/my%20file.rs#L7"
+        );
+        assert_eq!(
+            permalink("ssh://git@git.corp/team/oyo.git", &remotes, "abc", path, 7).unwrap(),
+            "https://code.corp/team/oyo/src/my%20file.rs?at=abc#7"
+        );
+    }
+
+    #[test]
+    fn test_curl_quote_escapes_config_values() {
+        assert_eq!(curl_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(curl_quote("{\"body\":\"x\"}\n"), r#""{\"body\":\"x\"}\n""#);
+    }
+}
@@ -0,0 +1,199 @@
+//! Frame pacing: redraw only when something changed, at most `max_fps` times a second,
+//! and during step animations rebuild only the rows that animate
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How long to wait for input when nothing needs drawing
+const IDLE_POLL: Duration = Duration::from_millis(250);
+/// Window used to measure the frame rate
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+pub struct FrameLimiter {
+    min_interval: Duration,
+    dirty: bool,
+    last_draw: Option<Instant>,
+    recent_draws: VecDeque<Instant>,
+    last_draw_time: Duration,
+}
+
+impl FrameLimiter {
+    /// `max_fps` of 0 disables the cap
+    pub fn new(max_fps: u32) -> Self {
+        let min_interval = if max_fps == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(1) / max_fps
+        };
+        Self {
+            min_interval,
+            dirty: true,
+            last_draw: None,
+            recent_draws: VecDeque::new(),
+            last_draw_time: Duration::ZERO,
+        }
+    }
+
+    /// Request a redraw on the next allowed frame
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    fn next_frame_at(&self) -> Option<Instant> {
+        self.last_draw.map(|last| last + self.min_interval)
+    }
+
+    /// True if a frame is due: something changed and the frame budget allows it
+    pub fn should_draw(&self, now: Instant) -> bool {
+        self.dirty && self.next_frame_at().is_none_or(|at| now >= at)
+    }
+
+    /// Record a finished frame that started at `started`
+    pub fn record_draw(&mut self, started: Instant, now: Instant) {
+        self.dirty = false;
+        self.last_draw = Some(started);
+        self.last_draw_time = now.saturating_duration_since(started);
+        self.recent_draws.push_back(started);
+        while self
+            .recent_draws
+            .front()
+            .is_some_and(|&t| now.saturating_duration_since(t) > FPS_WINDOW)
+        {
+            self.recent_draws.pop_front();
+        }
+    }
+
+    /// How long to wait for input before the next tick
+    pub fn poll_timeout(&self, now: Instant, busy: bool) -> Duration {
+        if !self.dirty && !busy {
+            return IDLE_POLL;
+        }
+        let wait = self
+            .next_frame_at()
+            .map(|at| at.saturating_duration_since(now))
+            .unwrap_or(Duration::ZERO);
+        wait.max(Duration::from_millis(1))
+    }
+
+    /// Frames drawn during the last second
+    pub fn fps(&self) -> usize {
+        self.recent_draws.len()
+    }
+
+    /// Time spent building and flushing the last frame
+    pub fn last_draw_time(&self) -> Duration {
+        self.last_draw_time
+    }
+}
+
+/// Rows rendered during a step animation. While only the animation
+/// advances, rows that don't animate are drawn from here instead of being
+/// rebuilt every frame.
+#[derive(Debug)]
+pub struct RowCache<T> {
+    /// Render state the cached rows belong to; `None` while off
+    key: Option<u64>,
+    rows: HashMap<usize, T>,
+    reused: usize,
+    rebuilt: usize,
+}
+
+impl<T> Default for RowCache<T> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            rows: HashMap::new(),
+            reused: 0,
+            rebuilt: 0,
+        }
+    }
+}
+
+impl<T: Clone> RowCache<T> {
+    /// Start a frame drawn in render state `key`, dropping rows cached in
+    /// another state; `None` turns the cache off for this frame
+    pub fn begin(&mut self, key: Option<u64>) {
+        if key.is_none() || key != self.key {
+            self.rows.clear();
+        }
+        self.key = key;
+        self.reused = 0;
+        self.rebuilt = 0;
+    }
+
+    /// Row `row` as drawn by an earlier frame in the same state
+    pub fn get(&mut self, row: usize) -> Option<T> {
+        self.key?;
+        let cached = self.rows.get(&row).cloned();
+        if cached.is_some() {
+            self.reused += 1;
+        }
+        cached
+    }
+
+    /// Record a rebuilt row; `cacheable` rows are kept for the next frames
+    pub fn insert(&mut self, row: usize, value: T, cacheable: bool) {
+        self.rebuilt += 1;
+        if cacheable && self.key.is_some() {
+            self.rows.insert(row, value);
+        }
+    }
+
+    /// Rows drawn from the cache and rows rebuilt in the current frame
+    pub fn stats(&self) -> (usize, usize) {
+        (self.reused, self.rebuilt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_only_when_dirty_and_within_budget() {
+        let mut limiter = FrameLimiter::new(50); // 20ms per frame
+        let start = Instant::now();
+        assert!(limiter.should_draw(start));
+        limiter.record_draw(start, start + Duration::from_millis(2));
+
+        // Nothing changed: no frame
+        assert!(!limiter.should_draw(start + Duration::from_millis(30)));
+
+        // Changed, but the frame budget is not spent yet
+        limiter.mark_dirty();
+        assert!(!limiter.should_draw(start + Duration::from_millis(10)));
+        assert!(limiter.should_draw(start + Duration::from_millis(20)));
+        assert_eq!(limiter.fps(), 1);
+        assert_eq!(limiter.last_draw_time(), Duration::from_millis(2));
+    }
+
+    #[test]
+    fn test_idle_poll_timeout() {
+        let mut limiter = FrameLimiter::new(60);
+        let now = Instant::now();
+        limiter.record_draw(now, now);
+        assert_eq!(limiter.poll_timeout(now, false), IDLE_POLL);
+        assert!(limiter.poll_timeout(now, true) <= Duration::from_millis(17));
+    }
+
+    #[test]
+    fn test_row_cache_reuses_rows_of_the_same_state() {
+        let mut rows = RowCache::default();
+        rows.begin(Some(1));
+        assert_eq!(rows.get(0), None);
+        rows.insert(0, "context", true);
+        rows.insert(1, "animated", false);
+        assert_eq!(rows.stats(), (0, 2));
+
+        rows.begin(Some(1));
+        assert_eq!(rows.get(0), Some("context"));
+        assert_eq!(rows.get(1), None);
+        assert_eq!(rows.stats(), (1, 0));
+
+        rows.begin(Some(2));
+        assert_eq!(rows.get(0), None);
+        rows.begin(None);
+        rows.insert(0, "context", true);
+        assert_eq!(rows.get(0), None);
+    }
+}
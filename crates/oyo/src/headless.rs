@@ -0,0 +1,202 @@
+//! Headless rendering (`--render-frames DIR`): every step of every file is
+//! drawn into an off-screen buffer and written out as a plain-text or ANSI
+//! frame, for visual regression tests of the views in CI
+
+use crate::app::{AnimationPhase, App};
+use crate::ui;
+use anyhow::{Context, Result};
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::Terminal;
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+/// Encoding of a written frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// Characters only, trailing blanks trimmed (`.txt`)
+    Text,
+    /// Characters with SGR color and attribute escapes (`.ans`)
+    Ansi,
+}
+
+impl FrameFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            FrameFormat::Text => "txt",
+            FrameFormat::Ansi => "ans",
+        }
+    }
+
+    fn encode(self, buf: &Buffer) -> String {
+        match self {
+            FrameFormat::Text => text_frame(buf),
+            FrameFormat::Ansi => ansi_frame(buf),
+        }
+    }
+}
+
+/// Write one frame per step of each file to `dir` as
+/// `<file>-<step>.<ext>` (both zero-padded, 1-based); returns the number of
+/// frames written
+pub fn render_frames(
+    app: &mut App,
+    dir: &Path,
+    (width, height): (u16, u16),
+    format: FrameFormat,
+) -> Result<usize> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    // Anything that depends on the clock would make frames differ per run
+    app.animation_enabled = false;
+    app.autoplay = false;
+    app.show_fps = false;
+
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let mut written = 0;
+    for file in 0..app.multi_diff.file_count() {
+        app.select_file(file);
+        app.goto_start();
+        let mut step = 1;
+        loop {
+            app.animation_phase = AnimationPhase::Idle;
+            app.key_echo.clear();
+            // The second draw settles scrolling to the active change
+            terminal.draw(|frame| ui::draw(frame, app))?;
+            terminal.draw(|frame| ui::draw(frame, app))?;
+            let path = dir.join(format!(
+                "{:03}-{:04}.{}",
+                file + 1,
+                step,
+                format.extension()
+            ));
+            std::fs::write(&path, format.encode(terminal.backend().buffer()))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written += 1;
+
+            let before = app.multi_diff.current_navigator().state().current_step;
+            app.next_step();
+            if app.multi_diff.current_navigator().state().current_step == before {
+                break;
+            }
+            step += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Cells of each row with wide characters' continuation cells skipped
+fn rows(buf: &Buffer) -> impl Iterator<Item = Vec<&ratatui::buffer::Cell>> {
+    (0..buf.area.height).map(move |y| {
+        let mut cells = Vec::with_capacity(buf.area.width as usize);
+        let mut x = 0;
+        while x < buf.area.width {
+            let cell = &buf[(buf.area.x + x, buf.area.y + y)];
+            cells.push(cell);
+            x += cell.symbol().width().max(1) as u16;
+        }
+        cells
+    })
+}
+
+fn text_frame(buf: &Buffer) -> String {
+    rows(buf)
+        .map(|cells| {
+            let row: String = cells.iter().map(|cell| cell.symbol()).collect();
+            format!("{}\n", row.trim_end())
+        })
+        .collect()
+}
+
+fn ansi_frame(buf: &Buffer) -> String {
+    let reset = sgr(Style::default());
+    let mut out = String::new();
+    for cells in rows(buf) {
+        let mut current = reset.clone();
+        for cell in cells {
+            let code = sgr(cell.style());
+            if code != current {
+                out.push_str(&code);
+                current = code;
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// SGR sequence selecting `style` from scratch
+fn sgr(style: Style) -> String {
+    const ATTRIBUTES: [(Modifier, u8); 9] = [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ];
+    let mut codes = vec!["0".to_string()];
+    codes.extend(
+        ATTRIBUTES
+            .iter()
+            .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+            .map(|(_, code)| code.to_string()),
+    );
+    codes.extend(style.fg.and_then(|color| color_code(color, false)));
+    codes.extend(style.bg.and_then(|color| color_code(color, true)));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let base = if background { 40 } else { 30 };
+    let extended = if background { 48 } else { 38 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => base,
+        Color::Red => base + 1,
+        Color::Green => base + 2,
+        Color::Yellow => base + 3,
+        Color::Blue => base + 4,
+        Color::Magenta => base + 5,
+        Color::Cyan => base + 6,
+        Color::Gray => base + 7,
+        Color::DarkGray => base + 60,
+        Color::LightRed => base + 61,
+        Color::LightGreen => base + 62,
+        Color::LightYellow => base + 63,
+        Color::LightBlue => base + 64,
+        Color::LightMagenta => base + 65,
+        Color::LightCyan => base + 66,
+        Color::White => base + 67,
+        Color::Indexed(index) => return Some(format!("{extended};5;{index}")),
+        Color::Rgb(r, g, b) => return Some(format!("{extended};2;{r};{g};{b}")),
+    };
+    Some(code.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn test_frames_encode_text_and_styles() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buf.set_string(0, 0, "ab", Style::default().fg(Color::Red));
+        buf.set_string(0, 1, "界x", Style::default());
+        assert_eq!(text_frame(&buf), "ab\n界x\n");
+        assert_eq!(
+            ansi_frame(&buf).lines().next(),
+            Some("\x1b[0;31mab\x1b[0m    \x1b[0m")
+        );
+
+        let style = Style::default()
+            .bg(Color::Rgb(1, 2, 3))
+            .add_modifier(Modifier::BOLD);
+        assert_eq!(sgr(style), "\x1b[0;1;48;2;1;2;3m");
+    }
+}
@@ -0,0 +1,107 @@
+//! Search and jump history, persisted per project under the user state dir
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Entries kept per list
+const MAX_ENTRIES: usize = 100;
+
+/// A `:` goto command run in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jump {
+    pub file: String,
+    pub query: String,
+}
+
+/// Recent searches and jumps, most recent first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct History {
+    pub searches: Vec<String>,
+    pub jumps: Vec<Jump>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+/// File of `project` in `kind` under the user state dir, e.g.
+/// `~/.local/state/oyo/history/<hash>.toml`
+pub fn project_file(project: &Path, kind: &str) -> Option<PathBuf> {
+    let project = project
+        .canonicalize()
+        .unwrap_or_else(|_| project.to_path_buf());
+    let mut hasher = DefaultHasher::new();
+    project.hash(&mut hasher);
+    let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    Some(
+        dir.join("oyo")
+            .join(kind)
+            .join(format!("{:016x}.toml", hasher.finish())),
+    )
+}
+
+fn push_front<T: PartialEq>(list: &mut Vec<T>, entry: T) {
+    list.retain(|existing| *existing != entry);
+    list.insert(0, entry);
+    list.truncate(MAX_ENTRIES);
+}
+
+impl History {
+    /// Load the history of `project` (empty if none was saved yet)
+    pub fn load(project: &Path) -> Self {
+        let path = project_file(project, "history");
+        let mut history: History = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default();
+        history.path = path;
+        history
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let Ok(text) = toml::to_string(self) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(path, text);
+    }
+
+    pub fn record_search(&mut self, query: &str) {
+        if query.is_empty() {
+            return;
+        }
+        push_front(&mut self.searches, query.to_string());
+        self.save();
+    }
+
+    pub fn record_jump(&mut self, jump: Jump) {
+        push_front(&mut self.jumps, jump);
+        self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_dedups_most_recent_first() {
+        let mut history = History::default();
+        history.record_search("foo");
+        history.record_search("bar");
+        history.record_search("foo");
+        assert_eq!(history.searches, vec!["foo", "bar"]);
+
+        let text = toml::to_string(&history).unwrap();
+        let loaded: History = toml::from_str(&text).unwrap();
+        assert_eq!(loaded.searches, history.searches);
+        assert!(loaded.jumps.is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Test impact hints: `[[tests.rules]]` map changed paths to the tests they
+//! likely affect and to a command running them
+//!
+//! ```toml
+//! [[tests.rules]]
+//! path = "src/**/*.rs"
+//! tests = "tests/{stem}_*.rs"
+//! command = "cargo test --test {stem}"
+//! ```
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+/// A mapping from changed paths to related tests and a command
+#[derive(Debug, Clone)]
+pub struct ImpactRule {
+    path: Regex,
+    tests: Option<String>,
+    command: Option<String>,
+}
+
+/// Tests and command suggested for one changed file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpactHint {
+    /// The changed file
+    pub source: String,
+    /// Existing files matching the rule's test glob
+    pub tests: Vec<String>,
+    pub command: Option<String>,
+}
+
+/// Outcome of one test command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRun {
+    pub command: String,
+    pub success: bool,
+    /// Last line of output, to tell why it failed at a glance
+    pub summary: String,
+}
+
+impl ImpactRule {
+    /// `path` is a glob matched against paths relative to the repository
+    /// root; `{stem}` and `{path}` in `tests` and `command` expand to the
+    /// changed file, `{tests}` in `command` to the matched test files. In
+    /// `command` each file name is shell-quoted, and a name the shell
+    /// cannot be given safely drops the command.
+    pub fn new(path: &str, tests: Option<String>, command: Option<String>) -> Self {
+        Self {
+            path: glob_regex(path),
+            tests,
+            command,
+        }
+    }
+
+    fn hint(&self, changed: &str, files: &[String]) -> Option<ImpactHint> {
+        if !self.path.is_match(changed) {
+            return None;
+        }
+        let stem = Path::new(changed)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let expand = |template: &str| template.replace("{stem}", &stem).replace("{path}", changed);
+        let tests: Vec<String> = match &self.tests {
+            Some(glob) => {
+                let glob = glob_regex(&expand(glob));
+                files
+                    .iter()
+                    .filter(|file| glob.is_match(file))
+                    .cloned()
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let command = self.command.as_deref().and_then(|command| {
+            let quoted_tests = tests
+                .iter()
+                .map(|test| shell_quote(test))
+                .collect::<Option<Vec<_>>>()?;
+            Some(
+                command
+                    .replace("{stem}", &shell_quote(&stem)?)
+                    .replace("{path}", &shell_quote(changed)?)
+                    .replace("{tests}", &quoted_tests.join(" ")),
+            )
+        });
+        (!tests.is_empty() || command.is_some()).then(|| ImpactHint {
+            source: changed.to_string(),
+            tests,
+            command,
+        })
+    }
+}
+
+/// `value` as one word of a `sh -c` (or, on Windows, `cmd /C`) command;
+/// `None` if cmd cannot quote it (`"`, `%`, `!` or control characters)
+fn shell_quote(value: &str) -> Option<String> {
+    let plain = |ch: char| ch.is_ascii_alphanumeric() || "_-./+=:,@".contains(ch);
+    if !value.is_empty() && value.chars().all(plain) {
+        return Some(value.to_string());
+    }
+    if cfg!(windows) {
+        if value
+            .chars()
+            .any(|ch| "\"%!".contains(ch) || ch.is_control())
+        {
+            return None;
+        }
+        return Some(format!("\"{value}\""));
+    }
+    Some(format!("'{}'", value.replace('\'', r"'\''")))
+}
+
+/// Hints of every rule matching each of the `changed` paths; test globs are
+/// matched against `files`
+pub fn hints(rules: &[ImpactRule], changed: &[String], files: &[String]) -> Vec<ImpactHint> {
+    changed
+        .iter()
+        .flat_map(|path| rules.iter().filter_map(|rule| rule.hint(path, files)))
+        .collect()
+}
+
+/// Anchored regex for a path glob: `**` spans directories, `*` and `?` stay
+/// within one
+fn glob_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_some() {
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+/// Run `commands` one after another in `dir` on a background thread
+pub fn spawn_runs(commands: Vec<String>, dir: PathBuf) -> Receiver<TestRun> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for command in commands {
+            let run = run_command(&command, &dir);
+            if sender.send(run).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn run_command(command: &str, dir: &Path) -> TestRun {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = shell
+        .arg(command)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output();
+    let (success, summary) = match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let last_line = |text: &str| {
+                text.lines()
+                    .rev()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_string)
+            };
+            let summary = if output.status.success() {
+                last_line(&stdout).or_else(|| last_line(&stderr))
+            } else {
+                last_line(&stderr).or_else(|| last_line(&stdout))
+            };
+            (output.status.success(), summary.unwrap_or_default())
+        }
+        Err(err) => (false, err.to_string()),
+    };
+    TestRun {
+        command: command.to_string(),
+        success,
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_regex() {
+        let glob = glob_regex("src/**/*.rs");
+        assert!(glob.is_match("src/main.rs"));
+        assert!(glob.is_match("src/views/split.rs"));
+        assert!(!glob.is_match("tests/main.rs"));
+        assert!(!glob_regex("src/*.rs").is_match("src/views/split.rs"));
+        assert!(glob_regex("a?.txt").is_match("ab.txt"));
+    }
+
+    #[test]
+    fn test_hints_expand_changed_file() {
+        let rules = [ImpactRule::new(
+            "src/**/*.rs",
+            Some("tests/{stem}_*.rs".to_string()),
+            Some("cargo test {tests}".to_string()),
+        )];
+        let files = [
+            "tests/foo_basic.rs".to_string(),
+            "tests/foo_edge.rs".to_string(),
+            "tests/bar_basic.rs".to_string(),
+        ];
+        let hints = hints(
+            &rules,
+            &["src/foo.rs".to_string(), "README.md".to_string()],
+            &files,
+        );
+        assert_eq!(
+            hints,
+            vec![ImpactHint {
+                source: "src/foo.rs".to_string(),
+                tests: vec![
+                    "tests/foo_basic.rs".to_string(),
+                    "tests/foo_edge.rs".to_string()
+                ],
+                command: Some("cargo test tests/foo_basic.rs tests/foo_edge.rs".to_string()),
+            }]
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_command_quotes_file_names() {
+        let rules = [ImpactRule::new(
+            "src/*",
+            None,
+            Some("cargo test {stem} -- {path}".to_string()),
+        )];
+        let changed = ["src/a;curl x|sh;'.rs".to_string()];
+        let hint = &hints(&rules, &changed, &[])[0];
+        assert_eq!(
+            hint.command.as_deref(),
+            Some(r"cargo test 'a;curl x|sh;'\''' -- 'src/a;curl x|sh;'\''.rs'")
+        );
+    }
+}
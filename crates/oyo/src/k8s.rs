@@ -0,0 +1,189 @@
+//! Live Kubernetes objects vs local manifests (`oy k8s`)
+//!
+//! Both sides are normalized before diffing: server-managed fields are
+//! dropped and mapping keys are sorted, so only meaningful differences show.
+
+use anyhow::{Context, Result};
+use std::process::Command;
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+
+/// Metadata fields set by the API server
+const SERVER_METADATA: &[&str] = &[
+    "managedFields",
+    "resourceVersion",
+    "uid",
+    "creationTimestamp",
+    "generation",
+    "selfLink",
+];
+
+/// Annotations written by kubectl and controllers
+const SERVER_ANNOTATIONS: &[&str] = &[
+    "kubectl.kubernetes.io/last-applied-configuration",
+    "deployment.kubernetes.io/revision",
+];
+
+fn key(name: &str) -> Yaml {
+    Yaml::String(name.to_string())
+}
+
+/// Drop `status` and server-managed metadata
+fn strip_server_fields(doc: &mut Yaml) {
+    let Yaml::Hash(root) = doc else {
+        return;
+    };
+    root.remove(&key("status"));
+    let Some(Yaml::Hash(metadata)) = root.get_mut(&key("metadata")) else {
+        return;
+    };
+    for field in SERVER_METADATA {
+        metadata.remove(&key(field));
+    }
+    let annotations_empty = match metadata.get_mut(&key("annotations")) {
+        Some(Yaml::Hash(annotations)) => {
+            for annotation in SERVER_ANNOTATIONS {
+                annotations.remove(&key(annotation));
+            }
+            annotations.is_empty()
+        }
+        _ => false,
+    };
+    if annotations_empty {
+        metadata.remove(&key("annotations"));
+    }
+}
+
+/// Sort mapping keys recursively
+fn sorted(doc: Yaml) -> Yaml {
+    match doc {
+        Yaml::Hash(hash) => {
+            let mut entries: Vec<(Yaml, Yaml)> = hash.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Yaml::Hash(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sorted(value)))
+                    .collect(),
+            )
+        }
+        Yaml::Array(items) => Yaml::Array(items.into_iter().map(sorted).collect()),
+        other => other,
+    }
+}
+
+/// Normalized YAML text of a document
+pub fn normalize(mut doc: Yaml) -> Result<String> {
+    strip_server_fields(&mut doc);
+    let mut out = String::new();
+    YamlEmitter::new(&mut out)
+        .dump(&sorted(doc))
+        .map_err(|err| anyhow::anyhow!("Failed to write YAML: {err:?}"))?;
+    let out = out.strip_prefix("---\n").unwrap_or(&out);
+    Ok(format!("{out}\n"))
+}
+
+/// Pick the document describing the same object as `live` (same kind and
+/// name, and same namespace unless the document leaves it out) from a
+/// (possibly multi-document) manifest
+pub fn select_document(manifest: &str, live: &Yaml) -> Result<Yaml> {
+    let mut docs = YamlLoader::load_from_str(manifest).context("Invalid YAML manifest")?;
+    let kind = live["kind"].as_str().context("Live object has no kind")?;
+    let name = live["metadata"]["name"]
+        .as_str()
+        .context("Live object has no name")?;
+    let namespace = live["metadata"]["namespace"].as_str();
+    let index = docs
+        .iter()
+        .position(|doc| {
+            let doc_namespace = doc["metadata"]["namespace"].as_str();
+            doc["kind"].as_str() == Some(kind)
+                && doc["metadata"]["name"].as_str() == Some(name)
+                && (doc_namespace.is_none() || namespace.is_none() || doc_namespace == namespace)
+        })
+        .context(format!("No {kind} {name} in the manifest"))?;
+    Ok(docs.swap_remove(index))
+}
+
+/// Fetch the live object (`kind/name`) with kubectl
+pub fn fetch_live(object: &str, namespace: Option<&str>) -> Result<Yaml> {
+    let mut command = Command::new("kubectl");
+    command.arg("get").arg(object).arg("-o").arg("yaml");
+    if let Some(namespace) = namespace {
+        command.arg("--namespace").arg(namespace);
+    }
+    let output = command.output().context("Failed to run kubectl")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    YamlLoader::load_from_str(&text)
+        .context("kubectl returned invalid YAML")?
+        .into_iter()
+        .next()
+        .context(format!("{object} not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_strips_server_fields() {
+        let live = "\
+kind: Deployment
+metadata:
+  uid: 1234
+  name: web
+  annotations:
+    kubectl.kubernetes.io/last-applied-configuration: '{}'
+  managedFields:
+    - manager: kubectl
+spec:
+  replicas: 3
+apiVersion: apps/v1
+status:
+  readyReplicas: 3
+";
+        let local = "\
+apiVersion: v1
+kind: Service
+metadata:
+  name: other
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 2
+";
+        let live = YamlLoader::load_from_str(live).unwrap().remove(0);
+        let local = normalize(select_document(local, &live).unwrap()).unwrap();
+        let live = normalize(live).unwrap();
+        assert_eq!(
+            live,
+            "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\nspec:\n  replicas: 3\n"
+        );
+        assert_eq!(local, live.replace("replicas: 3", "replicas: 2"));
+    }
+
+    #[test]
+    fn test_select_document_matches_kind_name_and_namespace() {
+        let live = YamlLoader::load_from_str(
+            "kind: Deployment\nmetadata:\n  name: web\n  namespace: prod\n",
+        )
+        .unwrap()
+        .remove(0);
+        let select =
+            |manifest: &str| select_document(manifest, &live).map(|doc| doc["spec"].clone());
+        let service = "kind: Service\nmetadata:\n  name: web\nspec: service\n";
+        assert!(select(service).is_err());
+        let staging =
+            "kind: Deployment\nmetadata:\n  name: web\n  namespace: staging\nspec: staging\n";
+        assert!(select(staging).is_err());
+        let manifest = format!(
+            "{service}---\n{staging}---\nkind: Deployment\nmetadata:\n  name: web\nspec: any\n"
+        );
+        assert_eq!(select(&manifest).unwrap().as_str(), Some("any"));
+    }
+}
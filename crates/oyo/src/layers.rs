@@ -0,0 +1,332 @@
+//! Layered config: the global config file, then a per-repo `.oyo.toml` at
+//! the repository root, then `OYO_<SECTION>__<KEY>` environment variables,
+//! each overriding the keys it sets in the layers before it
+//!
+//! The repo layer cannot set `[[tests.rules]]` or `[[plugins]]`, whose
+//! commands oy runs, nor `[forge]`, which decides where forge tokens are
+//! sent, so cloning a repository never makes oy run its commands or hand
+//! out credentials.
+
+use crate::config::{Config, DEFAULT_CONFIG};
+use crate::validate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-repo config file at the repository root
+pub const REPO_FILE: &str = ".oyo.toml";
+
+/// Sections only read from the global config and the environment
+const REPO_RESTRICTED: [&str; 3] = ["tests", "plugins", "forge"];
+
+/// Separates the key path in an environment variable name, e.g.
+/// `OYO_UI__DIFF__BG`
+const ENV_SEPARATOR: &str = "__";
+
+/// Where a setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    Default,
+    Global(PathBuf),
+    Repo(PathBuf),
+    /// Environment variable name
+    Env(String),
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Default => write!(f, "default"),
+            Layer::Global(path) => write!(f, "global {}", path.display()),
+            Layer::Repo(path) => write!(f, "repo {}", path.display()),
+            Layer::Env(name) => write!(f, "env {name}"),
+        }
+    }
+}
+
+/// Config merged from every layer
+pub struct Layered {
+    pub config: Config,
+    /// Keys set by the layers, as merged
+    table: toml::Table,
+    /// Layer that set each key, by dotted path
+    origins: BTreeMap<String, Layer>,
+}
+
+impl Layered {
+    /// Every effective setting, including defaults, with the layer it came
+    /// from, in key order
+    pub fn settings(&self) -> Vec<(String, toml::Value, Layer)> {
+        let mut table: toml::Table = toml::from_str(DEFAULT_CONFIG).unwrap_or_default();
+        merge(
+            &mut table,
+            self.table.clone(),
+            "",
+            &Layer::Default,
+            &mut BTreeMap::new(),
+        );
+        let mut settings = Vec::new();
+        leaves(&toml::Value::Table(table), "", &mut |key, value| {
+            let layer = self.origins.get(key).cloned().unwrap_or(Layer::Default);
+            settings.push((key.to_string(), value.clone(), layer));
+        });
+        settings.sort_by(|a, b| a.0.cmp(&b.0));
+        settings
+    }
+}
+
+/// Load every layer, warning about problems in each; a layer that cannot be
+/// applied is ignored
+pub fn load() -> Layered {
+    let mut layers = Vec::new();
+    if let Some(path) = Config::config_path() {
+        if let Some(table) = read_file(&path) {
+            layers.push((Layer::Global(path), table));
+        }
+    }
+    if let Some(path) = repo_config_path() {
+        if let Some(mut table) = read_file(&path) {
+            for key in strip_restricted(&mut table) {
+                eprintln!(
+                    "Warning: {}: ignoring [{key}], it is only read from the global config",
+                    path.display()
+                );
+            }
+            layers.push((Layer::Repo(path), table));
+        }
+    }
+    for (name, table) in env_layers(std::env::vars()) {
+        let text = toml::to_string(&table).unwrap_or_default();
+        for mut problem in validate::check(&text) {
+            problem.line = None;
+            eprintln!("Warning: {name}: {problem}");
+        }
+        layers.push((Layer::Env(name), table));
+    }
+    merge_layers(layers)
+}
+
+/// Merge `layers` in order, skipping (with a warning) any layer that would
+/// make the config invalid
+fn merge_layers(layers: Vec<(Layer, toml::Table)>) -> Layered {
+    let mut table = toml::Table::new();
+    let mut origins = BTreeMap::new();
+    let mut config = Config::default();
+    for (layer, layer_table) in layers {
+        let mut merged = table.clone();
+        let mut merged_origins = origins.clone();
+        merge(&mut merged, layer_table, "", &layer, &mut merged_origins);
+        match Config::deserialize(toml::Value::Table(merged.clone())) {
+            Ok(merged_config) => {
+                config = merged_config;
+                table = merged;
+                origins = merged_origins;
+            }
+            Err(_) => eprintln!("Warning: Ignoring {layer}"),
+        }
+    }
+    config.ui.normalize_markers();
+    Layered {
+        config,
+        table,
+        origins,
+    }
+}
+
+/// Remove the sections a repo layer cannot set, returning those it had
+fn strip_restricted(table: &mut toml::Table) -> Vec<&'static str> {
+    REPO_RESTRICTED
+        .into_iter()
+        .filter(|key| table.remove(*key).is_some())
+        .collect()
+}
+
+fn read_file(path: &Path) -> Option<toml::Table> {
+    let text = std::fs::read_to_string(path).ok()?;
+    for problem in validate::check(&text) {
+        eprintln!("Warning: {}:{problem}", path.display());
+    }
+    toml::from_str(&text)
+        .map_err(|_| eprintln!("Warning: Ignoring {}", path.display()))
+        .ok()
+}
+
+/// `.oyo.toml` at the root of the repository containing the working
+/// directory, if there is one
+fn repo_config_path() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let root = oyo_core::git::get_repo_root(&cwd).ok()?;
+    Some(root.join(REPO_FILE)).filter(|path| path.is_file())
+}
+
+/// One single-key layer per `OYO_` variable naming a key path, e.g.
+/// `OYO_PLAYBACK__SPEED=100` sets `playback.speed`; other `OYO_` variables
+/// (`OYO_REDUCED_MOTION`) are not config keys. Values are read as TOML
+/// (`true`, `100`, `["a"]`) and otherwise taken as a string.
+fn env_layers(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, toml::Table)> {
+    let mut layers: Vec<(String, toml::Table)> = vars
+        .filter_map(|(name, raw)| {
+            let path = name.strip_prefix("OYO_")?;
+            if !path.contains(ENV_SEPARATOR) {
+                return None;
+            }
+            let value = format!("value = {raw}")
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut table| table.remove("value"))
+                .unwrap_or(toml::Value::String(raw));
+            let table = path.rsplit(ENV_SEPARATOR).fold(value, |value, segment| {
+                let mut table = toml::Table::new();
+                table.insert(segment.to_ascii_lowercase(), value);
+                toml::Value::Table(table)
+            });
+            match table {
+                toml::Value::Table(table) => Some((name, table)),
+                _ => None,
+            }
+        })
+        .collect();
+    // Deterministic order when variables overlap
+    layers.sort_by(|a, b| a.0.cmp(&b.0));
+    layers
+}
+
+/// Merge `from` into `into`, tables key by key and everything else by
+/// replacement, recording `layer` for every key `from` sets
+fn merge(
+    into: &mut toml::Table,
+    from: toml::Table,
+    prefix: &str,
+    layer: &Layer,
+    origins: &mut BTreeMap<String, Layer>,
+) {
+    for (key, value) in from {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table, &path, layer, origins);
+            }
+            (_, value) => {
+                let nested = format!("{path}.");
+                origins.retain(|key, _| key != &path && !key.starts_with(&nested));
+                leaves(&value, &path, &mut |key, _| {
+                    origins.insert(key.to_string(), layer.clone());
+                });
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Call `f` with the dotted path of every non-table value in `value`
+fn leaves(value: &toml::Value, path: &str, f: &mut dyn FnMut(&str, &toml::Value)) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                leaves(value, &path, f);
+            }
+        }
+        value => f(path, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_env_layers_parse_key_paths_and_values() {
+        let layers = env_layers(vars(&[
+            ("OYO_REDUCED_MOTION", "1"),
+            ("OYO_UI__LINE_WRAP", "true"),
+            ("OYO_UI__DIFF__BG", "line"),
+            ("HOME", "/root"),
+        ]));
+        let shown: Vec<String> = layers
+            .iter()
+            .map(|(name, table)| format!("{name}: {}", toml::to_string(table).unwrap().trim()))
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                "OYO_UI__DIFF__BG: [ui.diff]\nbg = \"line\"",
+                "OYO_UI__LINE_WRAP: [ui]\nline_wrap = true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repo_layer_cannot_set_commands_or_forges() {
+        let mut table: toml::Table = toml::from_str(
+            "[ui]\nzen = true\n[forge]\nreplies = true\n[[forge.remotes]]\nurl = '.*'\nkind = \"github\"\n[[plugins]]\ncommand = \"x\"\n",
+        )
+        .unwrap();
+        assert_eq!(strip_restricted(&mut table), ["plugins", "forge"]);
+        assert_eq!(table.keys().collect::<Vec<_>>(), ["ui"]);
+    }
+
+    #[test]
+    fn test_later_layers_override_and_record_origins() {
+        let global = PathBuf::from("/home/config.toml");
+        let repo = PathBuf::from("/repo/.oyo.toml");
+        let mut layers = vec![
+            (
+                Layer::Global(global.clone()),
+                toml::from_str("[ui]\nzen = true\nline_wrap = true\nsyntax = \"off\"\n").unwrap(),
+            ),
+            (
+                Layer::Repo(repo.clone()),
+                toml::from_str("[ui]\nline_wrap = false\n[ui.syntax]\nmode = \"on\"\n").unwrap(),
+            ),
+            // Invalid, so ignored
+            (
+                Layer::Repo(repo.clone()),
+                toml::from_str("[ui]\nzen = \"yes\"\n").unwrap(),
+            ),
+        ];
+        layers.extend(
+            env_layers(vars(&[("OYO_PLAYBACK__SPEED", "50")]))
+                .into_iter()
+                .map(|(name, table)| (Layer::Env(name), table)),
+        );
+        let layered = merge_layers(layers);
+
+        assert!(layered.config.ui.zen);
+        assert!(!layered.config.ui.line_wrap);
+        assert_eq!(layered.config.playback.speed, 50);
+
+        let settings = layered.settings();
+        let layer_of = |key: &str| {
+            settings
+                .iter()
+                .find(|(name, _, _)| name == key)
+                .map(|(_, _, layer)| layer.clone())
+        };
+        assert_eq!(layer_of("ui.zen"), Some(Layer::Global(global)));
+        assert_eq!(layer_of("ui.line_wrap"), Some(Layer::Repo(repo.clone())));
+        assert_eq!(layer_of("ui.syntax.mode"), Some(Layer::Repo(repo)));
+        assert_eq!(
+            layer_of("playback.speed"),
+            Some(Layer::Env("OYO_PLAYBACK__SPEED".to_string()))
+        );
+        assert_eq!(layer_of("playback.autoplay"), Some(Layer::Default));
+    }
+}
@@ -0,0 +1,115 @@
+//! Debug log (`--debug`): tracing spans from the core and the app (diffs,
+//! git commands, highlighting, renders) with their durations, written to a
+//! file and kept in memory for the log panel (`:log`)
+
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Lines kept for the log panel
+const RECENT_LINES: usize = 500;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Log file used by `--debug` without a path
+pub fn default_path() -> Option<PathBuf> {
+    let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    Some(dir.join("oyo").join("debug.log"))
+}
+
+/// Log debug spans and events to `path` (truncated) from now on
+pub fn init(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    tracing_subscriber::fmt()
+        .with_writer(LogWriter(Arc::new(Mutex::new(file))))
+        .with_ansi(false)
+        .with_max_level(tracing::Level::DEBUG)
+        // A span's closing line carries its duration (`time.busy`)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(|err| anyhow::anyhow!(err))?;
+    let _ = PATH.set(path.to_path_buf());
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "debug log started");
+    Ok(())
+}
+
+/// The log file, if logging
+pub fn path() -> Option<&'static Path> {
+    PATH.get().map(PathBuf::as_path)
+}
+
+/// The latest log lines, oldest first
+pub fn recent() -> Vec<String> {
+    lock().iter().cloned().collect()
+}
+
+fn lock() -> std::sync::MutexGuard<'static, VecDeque<String>> {
+    RECENT.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn keep(text: &str) {
+    let mut recent = lock();
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.to_string());
+    }
+}
+
+/// Writes each formatted line to the log file and keeps it for the panel
+#[derive(Clone)]
+struct LogWriter(Arc<Mutex<File>>);
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        keep(&String::from_utf8_lossy(buf));
+        self.0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogWriter {
+    type Writer = LogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_lines_are_capped() {
+        for index in 0..RECENT_LINES + 5 {
+            keep(&format!("line {index}\n"));
+        }
+        let recent = recent();
+        assert_eq!(recent.len(), RECENT_LINES);
+        assert_eq!(recent[0], "line 5");
+        assert_eq!(
+            recent.last().unwrap(),
+            &format!("line {}", RECENT_LINES + 4)
+        );
+    }
+}
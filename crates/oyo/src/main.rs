@@ -1,33 +1,62 @@
 //! Oyo CLI - Step-through diff viewer TUI
 
+mod actions;
 mod app;
+mod archive;
+mod bidi;
 mod color;
+mod compress;
 mod config;
+mod crash;
 mod dashboard;
+mod diagnostics;
+mod docker;
+mod forge;
+mod frame;
+mod headless;
+mod history;
+mod impact;
+mod k8s;
+mod layers;
+mod logging;
+mod overlay;
+mod plugin;
+mod popup;
+mod prompt;
+mod redact;
+mod remote;
+mod settings;
 mod syntax;
+mod toast;
 mod ui;
+mod validate;
 mod views;
 
+use crate::compress::Compression;
 use crate::dashboard::{Dashboard, DashboardConfig, DashboardSelection};
 use crate::syntax::{list_syntax_themes, SyntaxEngine};
 use anyhow::{Context, Result};
-use app::{App, ViewMode};
+use app::{App, SplitPeek, ViewMode};
 use clap::{Parser, Subcommand};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
-        MouseButton, MouseEventKind,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEventKind,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use oyo_core::MultiFileDiff;
+use oyo_core::{MultiFileDiff, Patch, RejectFile};
+use popup::PopupKind;
+use prompt::PromptKey;
 use ratatui::prelude::*;
 use std::fs::OpenOptions;
 use std::io::{self, IsTerminal};
+use std::path::Path;
 #[cfg(unix)]
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use toast::Level;
 
 const INDEX_REF: &str = "INDEX";
 
@@ -40,6 +69,7 @@ struct Args {
     command: Option<Command>,
 
     /// Files or directories to compare: old_file new_file
+    /// Either file may be remote: `user@host:path` or `ssh://host/path` (ssh) or an `https://` URL
     /// Also works as a git external diff tool (git config diff.external oy)
     #[arg(num_args = 0..)]
     paths: Vec<PathBuf>,
@@ -76,13 +106,88 @@ struct Args {
     #[arg(long)]
     no_step: bool,
 
-    /// Show staged changes (index vs HEAD)
-    #[arg(long, alias = "cached", conflicts_with = "range")]
+    /// Show staged changes (index vs HEAD); `--staged-only` shows exactly what
+    /// `git commit` would capture
+    #[arg(long, aliases = ["cached", "staged-only"], conflicts_with = "range")]
     staged: bool,
 
     /// Diff a git range (e.g. HEAD~1..HEAD)
     #[arg(long, value_name = "RANGE", conflicts_with = "staged")]
     range: Option<String>,
+
+    /// Show what `git commit --amend` would capture (HEAD's parent vs index)
+    #[arg(long, conflicts_with_all = ["staged", "range"])]
+    amend_preview: bool,
+
+    /// Apply a named preset from config ([presets.<name>])
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Common ancestor for a three-way comparison (`--base base ours theirs`);
+    /// changes are colored by the side that introduced them
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["staged", "range", "amend_preview"])]
+    base: Option<PathBuf>,
+
+    /// File the merge is being resolved in (git mergetool's `$MERGED`);
+    /// `:merged` diffs it against both sides
+    #[arg(long, value_name = "FILE", requires = "base")]
+    merged: Option<PathBuf>,
+
+    /// Diff .env/.ini/.toml files by key and .sql schema dumps by object,
+    /// instead of by line (ordering is ignored)
+    #[arg(long)]
+    structural: bool,
+
+    /// Mask secrets matching the `[redact]` patterns (API keys, emails) in
+    /// every view and in yanked text, e.g. for screen sharing
+    #[arg(long)]
+    redact: bool,
+
+    /// Start in presentation mode: no gutters or status bar, the active
+    /// change centered and pressed keys shown (toggle with `:present`)
+    #[arg(long)]
+    present: bool,
+
+    /// Quit without asking about uncommitted hunk buckets or moved
+    /// rejected hunks that were not applied
+    #[arg(long)]
+    force: bool,
+
+    /// Reverse the comparison (new → old), e.g. to review a revert
+    #[arg(short = 'R', long)]
+    reverse: bool,
+
+    /// Read unsaved editor buffers from stdin, one JSON message per line:
+    /// {"type":"overlay","path":...,"content":...} or {"type":"clear","path":...}
+    #[arg(long)]
+    overlay_stdin: bool,
+
+    /// Show compiler/CI diagnostics on the changed files: `cargo check
+    /// --message-format=json` output or `path:line[:col]: message` lines
+    #[arg(long, value_name = "FILE")]
+    diagnostics: Option<PathBuf>,
+
+    /// Version control system for local changes (default: git, then svn)
+    #[arg(long, value_enum, conflicts_with_all = ["staged", "range", "amend_preview", "base"])]
+    vcs: Option<CliVcs>,
+
+    /// Write a debug log of diff, git, highlighting and render timings to
+    /// FILE (default: `oyo/debug.log` in the user state dir); `:log` shows it
+    #[arg(long, value_name = "FILE", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    debug: Option<PathBuf>,
+
+    /// Draw every step of every file off-screen and write the frames to DIR
+    /// as `<file>-<step>.txt` (or `.ans`) instead of starting the TUI
+    #[arg(long, value_name = "DIR")]
+    render_frames: Option<PathBuf>,
+
+    /// Encoding of `--render-frames` output
+    #[arg(long, value_enum, default_value = "text", requires = "render_frames")]
+    frame_format: CliFrameFormat,
+
+    /// Size of `--render-frames` output, as COLUMNSxROWS
+    #[arg(long, value_name = "SIZE", default_value = "120x40", value_parser = parse_frame_size, requires = "render_frames")]
+    frame_size: (u16, u16),
 }
 
 #[derive(Debug, Subcommand)]
@@ -97,6 +202,107 @@ enum Command {
         #[arg(long, default_value = "200")]
         limit: usize,
     },
+    /// Follow a running `git bisect`: diff the known-good commit against the
+    /// current candidate (limited to the bisect paths), updating as bisect advances
+    Bisect,
+    /// Review the uncommitted changes of several git repositories at once
+    /// (default: the repositories directly below the current directory)
+    Workspace {
+        /// Repository directories
+        repos: Vec<PathBuf>,
+    },
+    /// Review a patch series one patch at a time: a `git format-patch`
+    /// directory, an mbox, or a single patch file
+    Series {
+        /// Directory of .patch files, mbox, or patch file
+        path: PathBuf,
+        /// Context lines a hunk may ignore at each end when a patch no longer
+        /// applies to the working tree as-is (like `patch -F`)
+        #[arg(short = 'F', long, default_value_t = 2)]
+        fuzz: usize,
+    },
+    /// Review the hunks left in `.rej` files by a failed patch application,
+    /// placing each one by hand and applying it (`:place <line>`, `:apply`)
+    Rejects {
+        /// .rej files (default: every .rej file below the current directory)
+        paths: Vec<PathBuf>,
+    },
+    /// Review a release: diff two tags with files grouped by conventional-commit
+    /// type, optionally exporting a changelog scaffold
+    Release {
+        /// Previous release tag (or any ref)
+        from: String,
+        /// New release tag (or any ref)
+        to: String,
+        /// Write a Markdown changelog scaffold to this file ("-" prints it and exits)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Compare a file inside a docker container or image with a local file
+    Docker {
+        /// <container-or-image>:<absolute path>, e.g. web:/etc/nginx.conf
+        source: String,
+        /// Local file to compare against
+        local: PathBuf,
+    },
+    /// Diff two refs of a repository that is not cloned: they are fetched
+    /// without history into a cache, and file contents as they are shown
+    Remote {
+        /// Repository URL, e.g. https://github.com/ahkohd/oyo
+        url: String,
+        /// Old ref (branch, tag or full commit id)
+        from: String,
+        /// New ref
+        to: String,
+    },
+    /// Compare a live Kubernetes object with a local manifest, ignoring
+    /// server-managed fields (status, managedFields, ...)
+    K8s {
+        /// Object to fetch with kubectl, e.g. deployment/web
+        object: String,
+        /// Local manifest (YAML; in multi-document files the document with the
+        /// live object's kind, name and namespace is used)
+        local: PathBuf,
+        /// Namespace of the object
+        #[arg(short, long)]
+        namespace: Option<String>,
+    },
+    /// Review a pull request (GitHub, Gitea, Forgejo) or merge request
+    /// (GitLab): diff its base against its head, with the review threads
+    /// beside the hunks they are on (`:threads`)
+    Pr {
+        /// Pull/merge request number
+        number: u64,
+        /// Remote the pull request is on
+        #[arg(long, default_value = "origin")]
+        remote: String,
+    },
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Report unknown keys, bad values and invalid colors with their line
+    /// and a suggested fix, without starting the TUI
+    Check {
+        /// Config file to check (default: the one oy loads)
+        path: Option<PathBuf>,
+    },
+    /// Print every effective setting with the layer it came from (default,
+    /// global config, repo `.oyo.toml` or `OYO_*` variable)
+    Show,
+    /// Write a commented config listing every option with its default
+    Init {
+        /// Where to write it (default: ~/.config/oyo/config.toml)
+        path: Option<PathBuf>,
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -105,6 +311,42 @@ enum CliThemeMode {
     Light,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CliVcs {
+    Git,
+    Svn,
+    /// Perforce: opened files vs their have-revision
+    P4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CliFrameFormat {
+    /// Plain text, trailing blanks trimmed
+    Text,
+    /// Text with ANSI color and attribute escapes
+    Ansi,
+}
+
+impl From<CliFrameFormat> for headless::FrameFormat {
+    fn from(format: CliFrameFormat) -> Self {
+        match format {
+            CliFrameFormat::Text => headless::FrameFormat::Text,
+            CliFrameFormat::Ansi => headless::FrameFormat::Ansi,
+        }
+    }
+}
+
+fn parse_frame_size(size: &str) -> Result<(u16, u16), String> {
+    let (cols, rows) = size
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected COLUMNSxROWS, got {size:?}"))?;
+    let parse = |n: &str| n.trim().parse::<u16>().ok().filter(|&n| n > 0);
+    match (parse(cols), parse(rows)) {
+        (Some(cols), Some(rows)) => Ok((cols, rows)),
+        _ => Err(format!("expected COLUMNSxROWS, got {size:?}")),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 enum CliViewMode {
     /// Single pane that morphs from old to new state
@@ -140,12 +382,47 @@ enum InputMode {
         old_path: PathBuf,
         new_path: PathBuf,
     },
+    /// Two already-loaded versions of a file (e.g. fetched from a container)
+    ContentPair {
+        old_path: PathBuf,
+        old_content: String,
+        new_path: PathBuf,
+        new_content: String,
+    },
+    /// Two versions of a file and their common ancestor
+    ThreeWay {
+        base_path: PathBuf,
+        ours_path: PathBuf,
+        theirs_path: PathBuf,
+    },
     /// No args - try git uncommitted changes in current directory
     GitUncommitted,
+    /// No args inside an svn working copy - BASE vs working files
+    SvnWorkingCopy,
+    /// Files opened in a Perforce client vs their have-revision (`--vcs p4`)
+    P4Opened,
     /// Staged changes (index vs HEAD)
     GitStaged,
     /// Git range
     GitRange { from: String, to: String },
+    /// Known-good commit vs current candidate of a running git bisect
+    GitBisect,
+    /// Uncommitted changes of several repositories below a common directory
+    GitWorkspace { root: PathBuf, repos: Vec<PathBuf> },
+    /// Two commits fetched from a repository that is not cloned (`oy remote`)
+    RemoteRange {
+        repo_root: PathBuf,
+        from: String,
+        to: String,
+    },
+    /// Patch series; the first patch is shown
+    PatchSeries {
+        patches: Vec<Patch>,
+        repo_root: Option<PathBuf>,
+        fuzz: usize,
+    },
+    /// Hunks of `.rej` files, one entry each
+    Rejects { rejects: Vec<RejectFile> },
     /// No valid input
     None,
 }
@@ -169,13 +446,89 @@ fn detect_input_mode(paths: &[PathBuf]) -> InputMode {
             new_path: paths[1].clone(),
         }
     } else if paths.is_empty() {
-        // No args - try git uncommitted changes
-        InputMode::GitUncommitted
+        // No args - try git uncommitted changes, then an svn working copy
+        let cwd = std::env::current_dir().unwrap_or_default();
+        if !oyo_core::git::is_git_repo(&cwd) && oyo_core::svn::is_svn_working_copy(&cwd) {
+            InputMode::SvnWorkingCopy
+        } else {
+            InputMode::GitUncommitted
+        }
     } else {
         InputMode::None
     }
 }
 
+/// Repository roots of `paths` (or of the directories directly below the
+/// current one), as a common root directory and repositories relative to it
+fn resolve_workspace(paths: &[PathBuf]) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let mut roots = Vec::new();
+    if paths.is_empty() {
+        let cwd = std::env::current_dir().context("Failed to read current directory")?;
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(&cwd)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            let Ok(root) = oyo_core::git::get_repo_root(&dir) else {
+                continue;
+            };
+            // Skip plain subdirectories of a repository around `cwd`
+            if dir.canonicalize().ok() == root.canonicalize().ok() {
+                roots.push(root);
+            }
+        }
+    } else {
+        for path in paths {
+            roots.push(
+                oyo_core::git::get_repo_root(path)
+                    .context(format!("Not a git repository: {}", path.display()))?,
+            );
+        }
+    }
+    let mut roots: Vec<PathBuf> = roots
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect();
+    roots.dedup();
+    let Some(first) = roots.first() else {
+        anyhow::bail!("No git repositories found below the current directory.");
+    };
+
+    let mut common = first.clone();
+    while !roots.iter().all(|root| root.starts_with(&common)) {
+        if !common.pop() {
+            break;
+        }
+    }
+    // A single repository is shown under its own name
+    if roots.len() == 1 {
+        common.pop();
+    }
+    let repos = roots
+        .iter()
+        .map(|root| root.strip_prefix(&common).unwrap_or(root).to_path_buf())
+        .collect();
+    Ok((common, repos))
+}
+
+/// `.rej` files below `dir`, skipping hidden directories
+fn find_reject_files(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            find_reject_files(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "rej") {
+            found.push(path);
+        }
+    }
+}
+
 fn parse_range(range: &str) -> Result<(String, String)> {
     if let Some((from, to)) = range.split_once("...") {
         if from.is_empty() || to.is_empty() {
@@ -198,37 +551,165 @@ fn parse_range(range: &str) -> Result<(String, String)> {
     anyhow::bail!("Range must be in the form A..B or A...B");
 }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<Box<dyn io::Write>>>> {
-    enable_raw_mode()?;
-    let mut stdout: Box<dyn io::Write> = if io::stdout().is_terminal() {
+/// The terminal: stdout, or the controlling tty when stdout is piped
+fn terminal_output() -> Box<dyn io::Write> {
+    if io::stdout().is_terminal() {
         Box::new(io::stdout())
     } else {
         match OpenOptions::new().read(true).write(true).open("/dev/tty") {
             Ok(file) => Box::new(file),
             Err(_) => Box::new(io::stdout()),
         }
-    };
+    }
+}
+
+fn setup_terminal() -> Result<Terminal<CrosstermBackend<Box<dyn io::Write>>>> {
+    enable_raw_mode()?;
+    let mut stdout = terminal_output();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
 }
 
+/// Leave raw mode and the alternate screen, ignoring errors (used when
+/// crashing)
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        terminal_output(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        crossterm::cursor::Show
+    );
+}
+
+/// Repository root, or the working directory outside a repository
+fn project_dir(app: &App) -> Option<PathBuf> {
+    app.multi_diff
+        .repo_root()
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+}
+
+/// Resume where a crash left off in this project, and save the position if
+/// this run crashes
+fn install_crash_handler(app: &mut App) {
+    let project = project_dir(app);
+    if let Some(session) = project.as_deref().and_then(crash::take_session) {
+        if app.restore_session(&session) {
+            app.notify(
+                Level::Info,
+                format!(
+                    "Restored {} step {} after a crash",
+                    session.file,
+                    session.step + 1
+                ),
+            );
+        }
+    }
+    crash::install(project, restore_terminal);
+}
+
+/// Compile the scripted actions (`[actions]`)
+fn load_actions(app: &mut App, config: &config::Config) {
+    match actions::Actions::load(&config.actions, config::Config::config_dir().as_deref()) {
+        Ok(actions) => app.actions = actions,
+        Err(err) => app.notify(Level::Error, err),
+    }
+}
+
+/// Start the configured plugins in the repository root
+fn start_plugins(app: &mut App, config: &config::Config) {
+    if config.plugins.is_empty() {
+        return;
+    }
+    let root = project_dir(app);
+    let (plugins, errors) = plugin::PluginHost::spawn(&config.plugins, root.as_deref());
+    if !errors.is_empty() {
+        app.notify(Level::Error, errors.join(" · "));
+    }
+    app.plugins = plugins;
+}
+
 fn apply_config_to_app(app: &mut App, config: &config::Config, args: &Args, light_mode: bool) {
+    if let Some(project) = project_dir(app) {
+        app.load_history(&project);
+    }
+    if args.overlay_stdin && !io::stdin().is_terminal() {
+        app.listen_for_overlays(overlay::spawn_stdin_reader());
+    }
+    if config.engine != config::EngineConfig::default() {
+        app.multi_diff.set_engine(config.engine.diff_engine());
+    }
+    app.engine_config = config.engine.clone();
+    app.engine_config.structural |= args.structural;
+    if app.engine_config.structural {
+        app.multi_diff.set_structural(true);
+    }
+    if args.reverse {
+        app.multi_diff.set_reversed(true);
+    }
+    let stop_patterns = config.word_diff.compile();
+    if !stop_patterns.is_empty() {
+        app.multi_diff.set_stop_patterns(stop_patterns);
+    }
+    if config.playback.step_order != config::StepOrder::default() {
+        app.multi_diff
+            .set_step_order(config.playback.step_order.into());
+    }
+    if config.playback.split_changes {
+        app.multi_diff.set_split_changes(true);
+    }
     app.zen_mode = config.ui.zen;
     app.animation_enabled = config.playback.animation;
+    app.reduced_motion = config.reduced_motion();
+    app.show_fps = config.ui.fps_overlay;
+    app.bidi = config.ui.bidi;
+    app.heat = config.ui.heat;
+    app.function_context = config.ui.function_context;
+    app.forge_remotes = config.forge.remotes.clone();
+    app.function_context_lines = config.ui.function_context_lines;
+    app.show_scrub_bar = config.ui.scrub_bar;
+    app.show_file_header = config.ui.file_header;
+    app.show_breadcrumbs = config.ui.breadcrumbs;
+    app.test_rules = config.tests.compile();
+    app.redactor = config.redact.redactor(args.redact);
+    app.force_quit = args.force;
+    app.merged_path = args.merged.clone();
+    app.search_regex_mode = config.search.regex;
+    app.search_case = config.search.case;
+    app.search_whole_word = config.search.whole_word;
     app.animation_duration = config.playback.animation_duration;
+    app.fade_out_duration = config.playback.fade_out_duration;
+    app.fade_in_duration = config.playback.fade_in_duration;
+    app.animation_easing = config.playback.easing.into();
+    app.scroll_duration = config.playback.scroll_duration;
     app.file_panel_visible = config.files.panel_visible;
     app.file_count_mode = config.files.counts;
+    app.churn_thumbnails = config.files.churn;
+    if config.files.memory_budget > 0 {
+        app.multi_diff
+            .set_memory_budget(Some(config.files.memory_budget * 1024 * 1024));
+    }
+    if config.files.sort != config::FileSortMode::Path {
+        app.set_file_sort(config.files.sort);
+    }
     app.auto_center = config.ui.auto_center;
+    if args.present {
+        app.presentation = false;
+        app.toggle_presentation();
+    }
     app.line_wrap = config.ui.line_wrap;
     app.scrollbar_visible = config.ui.scrollbar;
     app.strikethrough_deletions = config.ui.strikethrough_deletions;
+    app.kind_patterns = config.ui.patterns;
     app.diff_bg = config.ui.diff.bg;
     app.diff_fg = config.ui.diff.fg;
     app.syntax_mode = config.ui.syntax.mode;
     app.syntax_theme = config.ui.syntax.theme.clone();
     app.single_modified_step_mode = config.ui.single.modified_step_mode;
+    app.split_align_gaps = config.ui.split.align_gaps;
     app.evo_syntax = config.ui.evo.syntax;
     app.auto_step_on_enter = config.playback.auto_step_on_enter;
     app.auto_step_blank_files = config.playback.auto_step_blank_files;
@@ -255,11 +736,66 @@ fn apply_config_to_app(app: &mut App, config: &config::Config, args: &Args, ligh
     if !app.stepping {
         app.enter_no_step_mode();
     }
+    app.presets = config
+        .presets
+        .iter()
+        .map(|(name, preset)| (name.clone(), preset.clone()))
+        .collect();
+    if let Some(name) = args.preset.as_deref() {
+        app.apply_preset(name);
+    }
     app.handle_file_enter();
 }
 
+/// The commit reviewed by an input mode (the range's end commit)
+fn commit_ref_for_input_mode(input_mode: &InputMode) -> Option<String> {
+    match input_mode {
+        InputMode::GitRange { to, .. } if to != INDEX_REF => Some(to.clone()),
+        InputMode::RemoteRange { to, .. } => Some(to.clone()),
+        _ => None,
+    }
+}
+
+fn load_commit_info(app: &mut App, commit_ref: Option<String>) {
+    let (Some(commit_ref), Some(repo_root)) = (commit_ref, app.multi_diff.repo_root()) else {
+        return;
+    };
+    app.commit_info = oyo_core::git::get_commit_info(repo_root, &commit_ref)
+        .ok()
+        .map(|info| oyo_core::git::CommitInfo {
+            signature: oyo_core::git::verify_commit(repo_root, &commit_ref).ok(),
+            ..info
+        });
+    if let Some(info) = &app.commit_info {
+        app.commit_reviews = oyo_core::notes::read(repo_root, &info.id).unwrap_or_default();
+    }
+    if let Some(review) = app.commit_reviews.last() {
+        let message = format!(
+            "reviewed before: {} by {} (c for details)",
+            review.verdict.label(),
+            review.reviewer
+        );
+        app.notify(Level::Info, message);
+    }
+}
+
+/// Read a local or remote input file, decompressing .gz/.zst/.xz
+fn read_input(path: &Path, remote_ttl: Duration) -> Result<(String, Option<Compression>)> {
+    compress::decode(path, remote::read(path, remote_ttl)?)
+}
+
+/// Mention the compression of the inputs in the file header
+fn note_compression(diff: &mut MultiFileDiff, compressions: &[Option<Compression>]) {
+    let mut names: Vec<&str> = compressions.iter().flatten().map(|c| c.name()).collect();
+    names.dedup();
+    if let (false, Some(file)) = (names.is_empty(), diff.files.first_mut()) {
+        file.display_name = format!("{} ({})", file.display_name, names.join(", "));
+    }
+}
+
 fn build_diff_from_input_mode(
     input_mode: InputMode,
+    remote_ttl: Duration,
 ) -> Result<Option<(MultiFileDiff, Option<String>)>> {
     let (multi_diff, git_branch) = match input_mode {
         InputMode::GitExternal {
@@ -297,16 +833,70 @@ fn build_diff_from_input_mode(
             let diff = if old_path.is_dir() && new_path.is_dir() {
                 MultiFileDiff::from_directories(&old_path, &new_path)
                     .context("Failed to create diff from directories")?
+            } else if archive::is_archive(&old_path) && archive::is_archive(&new_path) {
+                let old_entries =
+                    archive::read_entries(&old_path, remote::read(&old_path, remote_ttl)?)?;
+                let new_entries =
+                    archive::read_entries(&new_path, remote::read(&new_path, remote_ttl)?)?;
+                MultiFileDiff::from_entries(old_entries, new_entries)
             } else {
-                let old_content = std::fs::read_to_string(&old_path)
-                    .context(format!("Failed to read: {}", old_path.display()))?;
-                let new_content = std::fs::read_to_string(&new_path)
-                    .context(format!("Failed to read: {}", new_path.display()))?;
+                let (old_content, old_compression) = read_input(&old_path, remote_ttl)?;
+                let (new_content, new_compression) = read_input(&new_path, remote_ttl)?;
 
-                MultiFileDiff::from_file_pair(old_path, new_path, old_content, new_content)
+                let mut diff =
+                    MultiFileDiff::from_file_pair(old_path, new_path, old_content, new_content);
+                note_compression(&mut diff, &[old_compression, new_compression]);
+                diff
             };
             (diff, None)
         }
+        InputMode::ContentPair {
+            old_path,
+            old_content,
+            new_path,
+            new_content,
+        } => (
+            MultiFileDiff::from_file_pair(old_path, new_path, old_content, new_content),
+            None,
+        ),
+        InputMode::ThreeWay {
+            base_path,
+            ours_path,
+            theirs_path,
+        } => {
+            let (base_content, base_compression) = read_input(&base_path, remote_ttl)?;
+            let (ours_content, ours_compression) = read_input(&ours_path, remote_ttl)?;
+            let (theirs_content, theirs_compression) = read_input(&theirs_path, remote_ttl)?;
+            let mut diff = MultiFileDiff::from_file_pair_with_base(
+                ours_path,
+                theirs_path,
+                base_content,
+                ours_content,
+                theirs_content,
+            );
+            note_compression(
+                &mut diff,
+                &[base_compression, ours_compression, theirs_compression],
+            );
+            (diff, None)
+        }
+        InputMode::PatchSeries {
+            patches,
+            repo_root,
+            fuzz,
+        } => {
+            let Some(first) = patches.first() else {
+                return Ok(None);
+            };
+            let branch = repo_root
+                .as_deref()
+                .and_then(|root| oyo_core::git::get_current_branch(root).ok());
+            (
+                MultiFileDiff::from_patch(first, repo_root.as_deref(), fuzz),
+                branch,
+            )
+        }
+        InputMode::Rejects { rejects } => (MultiFileDiff::from_rejects(&rejects), None),
         InputMode::GitUncommitted => {
             let cwd = std::env::current_dir().unwrap_or_default();
             if !oyo_core::git::is_git_repo(&cwd) {
@@ -331,6 +921,32 @@ fn build_diff_from_input_mode(
                 .context("Failed to create diff from git changes")?;
             (diff, branch)
         }
+        InputMode::SvnWorkingCopy => {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let wc_root =
+                oyo_core::svn::find_wc_root(&cwd).context("Not in an svn working copy")?;
+            let changes = oyo_core::svn::get_working_changes(&wc_root)
+                .context("Failed to get svn working copy changes")?;
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            let diff = MultiFileDiff::from_svn_changes(wc_root, changes)
+                .context("Failed to create diff from svn changes")?;
+            (diff, None)
+        }
+        InputMode::P4Opened => {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let client_root = oyo_core::p4::get_client_root(&cwd)
+                .context("Failed to find the Perforce client root")?;
+            let changes = oyo_core::p4::get_opened_files(&client_root)
+                .context("Failed to get opened Perforce files")?;
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            let diff = MultiFileDiff::from_p4_changes(client_root, changes)
+                .context("Failed to create diff from Perforce files")?;
+            (diff, None)
+        }
         InputMode::GitStaged => {
             let cwd = std::env::current_dir().unwrap_or_default();
             if !oyo_core::git::is_git_repo(&cwd) {
@@ -409,6 +1025,58 @@ fn build_diff_from_input_mode(
             let branch = oyo_core::git::get_current_branch(&repo_root).ok();
             (diff, branch)
         }
+        InputMode::RemoteRange {
+            repo_root,
+            from,
+            to,
+        } => {
+            let changes = oyo_core::git::get_changes_between(&repo_root, &from, &to)
+                .context("Failed to get range changes")?;
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            let diff = MultiFileDiff::from_git_range(repo_root, changes, from, to)
+                .context("Failed to create diff from range")?;
+            (diff, None)
+        }
+        InputMode::GitWorkspace { root, repos } => {
+            let diff = MultiFileDiff::from_git_workspace(root, repos)
+                .context("Failed to get workspace changes")?;
+            if diff.file_count() == 0 {
+                return Ok(None);
+            }
+            (diff, None)
+        }
+        InputMode::GitBisect => {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            if !oyo_core::git::is_git_repo(&cwd) {
+                anyhow::bail!("Not in a git repository.");
+            }
+            let repo_root =
+                oyo_core::git::get_repo_root(&cwd).context("Failed to get git repository root")?;
+            let bisect = oyo_core::git::get_bisect_state(&repo_root)
+                .context("Failed to read bisect state")?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No bisect in progress (start one with `git bisect start`).")
+                })?;
+            let good = bisect.good.clone().ok_or_else(|| {
+                anyhow::anyhow!("No good commit marked yet (run `git bisect good <rev>`).")
+            })?;
+            let changes = oyo_core::git::get_changes_between_paths(
+                &repo_root,
+                &good,
+                &bisect.current,
+                &bisect.paths,
+            )
+            .context("Failed to get bisect changes")?;
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            let diff = MultiFileDiff::from_git_bisect(repo_root.clone(), changes, good, &bisect)
+                .context("Failed to create diff from bisect")?;
+            let branch = oyo_core::git::get_current_branch(&repo_root).ok();
+            (diff, branch)
+        }
         InputMode::None => {
             anyhow::bail!(
                 "Usage: oy <old_file> <new_file>\n\
@@ -421,7 +1089,55 @@ fn build_diff_from_input_mode(
     Ok(Some((multi_diff, git_branch)))
 }
 
+fn run_config_command(action: &ConfigCommand) -> Result<()> {
+    match action {
+        ConfigCommand::Check { path } => {
+            let Some(path) = path.clone().or_else(config::Config::config_path) else {
+                println!("No config file found.");
+                return Ok(());
+            };
+            let text = std::fs::read_to_string(&path)
+                .context(format!("Failed to read: {}", path.display()))?;
+            let problems = validate::check(&text);
+            if problems.is_empty() {
+                println!("{}: ok", path.display());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}:{problem}", path.display());
+            }
+            anyhow::bail!("{} problem(s) in {}", problems.len(), path.display())
+        }
+        ConfigCommand::Show => {
+            for (key, value, layer) in layers::load().settings() {
+                println!("{key} = {value}  # {layer}");
+            }
+            Ok(())
+        }
+        ConfigCommand::Init { path, force } => {
+            let Some(path) = path.clone().or_else(config::Config::write_path) else {
+                anyhow::bail!("No config directory found; pass a path");
+            };
+            if path.exists() && !force {
+                anyhow::bail!(
+                    "{} already exists (use --force to overwrite)",
+                    path.display()
+                );
+            }
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)
+                    .context(format!("Failed to create: {}", dir.display()))?;
+            }
+            std::fs::write(&path, config::DEFAULT_CONFIG)
+                .context(format!("Failed to write: {}", path.display()))?;
+            println!("Wrote {}", path.display());
+            Ok(())
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::parse_range;
 
@@ -461,6 +1177,14 @@ mod tests {
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    if let Some(path) = &args.debug {
+        let path = if path.as_os_str().is_empty() {
+            logging::default_path().context("No state directory for the debug log")?
+        } else {
+            path.clone()
+        };
+        logging::init(&path)?;
+    }
     let view_limit = match args.command {
         Some(Command::Themes) => {
             for name in config::builtin_theme_names() {
@@ -475,9 +1199,41 @@ fn main() -> Result<()> {
             return Ok(());
         }
         Some(Command::View { limit }) => Some(limit),
-        None => None,
+        Some(Command::Config { ref action }) => return run_config_command(action),
+        Some(
+            Command::Bisect
+            | Command::Workspace { .. }
+            | Command::Series { .. }
+            | Command::Rejects { .. }
+            | Command::Release { .. }
+            | Command::Docker { .. }
+            | Command::K8s { .. }
+            | Command::Remote { .. }
+            | Command::Pr { .. },
+        )
+        | None => None,
     };
     let mut config = config::Config::load();
+    let diagnostics = match args.diagnostics.as_deref() {
+        Some(path) => diagnostics::parse(
+            &std::fs::read_to_string(path)
+                .context(format!("Failed to read: {}", path.display()))?,
+        ),
+        None => Vec::new(),
+    };
+    let remote_ttl = Duration::from_secs(config.remote.cache_ttl);
+    if let Some(name) = args.preset.as_deref() {
+        if !config.presets.contains_key(name) {
+            let available: Vec<&str> = config.presets.keys().map(|k| k.as_str()).collect();
+            if available.is_empty() {
+                anyhow::bail!("Unknown preset '{name}' (no presets defined in config)");
+            }
+            anyhow::bail!(
+                "Unknown preset '{name}'. Available presets: {}",
+                available.join(", ")
+            );
+        }
+    }
     if let Some(path) = args.dump_scopes.as_deref() {
         if let Some(name) = args.theme_name.as_deref() {
             config.ui.theme.name = Some(name.to_string());
@@ -574,6 +1330,7 @@ fn main() -> Result<()> {
             DashboardSelection::Range { from, to } => InputMode::GitRange { from, to },
         };
 
+        let commit_ref = commit_ref_for_input_mode(&input_mode);
         let empty_message = match &input_mode {
             InputMode::GitUncommitted => Some("No uncommitted changes found.".to_string()),
             InputMode::GitStaged => Some("No staged changes found.".to_string()),
@@ -582,7 +1339,7 @@ fn main() -> Result<()> {
             }
             _ => Some("No changes found.".to_string()),
         };
-        let (multi_diff, git_branch) = match build_diff_from_input_mode(input_mode)? {
+        let (multi_diff, git_branch) = match build_diff_from_input_mode(input_mode, remote_ttl)? {
             Some(result) => result,
             None => {
                 disable_raw_mode()?;
@@ -621,9 +1378,15 @@ fn main() -> Result<()> {
         let autoplay = args.autoplay || config.playback.autoplay;
 
         let mut app = App::new(multi_diff, view_mode, speed, autoplay, git_branch);
+        load_commit_info(&mut app, commit_ref);
         apply_config_to_app(&mut app, &config, &args, light_mode);
+        app.diagnostics = diagnostics;
+        start_plugins(&mut app, &config);
+        load_actions(&mut app, &config);
+        install_crash_handler(&mut app);
 
-        let result = run_app(&mut terminal, &mut app);
+        let mut frames = frame::FrameLimiter::new(config.ui.max_fps);
+        let result = run_app(&mut terminal, &mut app, &mut frames);
 
         disable_raw_mode()?;
         execute!(
@@ -640,11 +1403,153 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let input_mode = if args.paths.len() == 7 {
+    let mut review = None;
+    let input_mode = if matches!(args.command, Some(Command::Bisect)) {
+        InputMode::GitBisect
+    } else if let Some(Command::Workspace { repos }) = args.command.as_ref() {
+        let (root, repos) = resolve_workspace(repos)?;
+        InputMode::GitWorkspace { root, repos }
+    } else if let Some(Command::Series { path, fuzz }) = args.command.as_ref() {
+        let patches = oyo_core::patch::load_series(path)
+            .context(format!("Failed to read patches: {}", path.display()))?;
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let repo_root = oyo_core::git::get_repo_root(&cwd).ok();
+        InputMode::PatchSeries {
+            patches,
+            repo_root,
+            fuzz: *fuzz,
+        }
+    } else if let Some(Command::Rejects { paths }) = args.command.as_ref() {
+        let paths = if paths.is_empty() {
+            let cwd = std::env::current_dir().context("Failed to read current directory")?;
+            let mut found = Vec::new();
+            find_reject_files(&cwd, &mut found);
+            if found.is_empty() {
+                anyhow::bail!("No .rej files found below the current directory.");
+            }
+            let mut found: Vec<PathBuf> = found
+                .into_iter()
+                .map(|path| {
+                    path.strip_prefix(&cwd)
+                        .map_or(path.clone(), Path::to_path_buf)
+                })
+                .collect();
+            found.sort();
+            found
+        } else {
+            paths.clone()
+        };
+        let rejects = paths
+            .iter()
+            .map(|path| {
+                RejectFile::load(path)
+                    .context(format!("Failed to read rejects: {}", path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        InputMode::Rejects { rejects }
+    } else if let Some(Command::Release { from, to, output }) = args.command.as_ref() {
+        if let Some(output) = output {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let repo_root =
+                oyo_core::git::get_repo_root(&cwd).context("Not in a git repository.")?;
+            let commits = oyo_core::git::get_range_commits(&repo_root, from, to)
+                .context(format!("Failed to list commits in {from}..{to}"))?;
+            let changelog = oyo_core::conventional::changelog(to, &commits);
+            if output.as_os_str() == "-" {
+                print!("{changelog}");
+                return Ok(());
+            }
+            std::fs::write(output, changelog)
+                .context(format!("Failed to write: {}", output.display()))?;
+        }
+        InputMode::GitRange {
+            from: from.clone(),
+            to: to.clone(),
+        }
+    } else if let Some(Command::K8s {
+        object,
+        local,
+        namespace,
+    }) = args.command.as_ref()
+    {
+        let manifest = std::fs::read_to_string(local)
+            .context(format!("Failed to read: {}", local.display()))?;
+        let live = k8s::fetch_live(object, namespace.as_deref())
+            .context(format!("Failed to fetch {object}"))?;
+        let local_doc = k8s::select_document(&manifest, &live)
+            .context(format!("Failed to match {object} in {}", local.display()))?;
+        InputMode::ContentPair {
+            old_path: PathBuf::from(format!("live:{object}")),
+            old_content: k8s::normalize(live)?,
+            new_path: local.clone(),
+            new_content: k8s::normalize(local_doc)?,
+        }
+    } else if let Some(Command::Pr { number, remote }) = args.command.as_ref() {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let repo_root = oyo_core::git::get_repo_root(&cwd).context("Not in a git repository.")?;
+        let url = oyo_core::git::get_remote_url(&repo_root, remote)
+            .context(format!("Failed to read remote '{remote}'"))?;
+        let forge = forge::open(&url, &config.forge.remotes)?;
+        let pr = forge
+            .pull_request(*number)
+            .context(format!("Failed to fetch #{number} from {}", forge.name()))?;
+        forge::fetch_commits(&repo_root, remote, forge.as_ref(), &pr)
+            .context(format!("Failed to fetch the commits of #{number}"))?;
+        let threads = forge
+            .review_threads(&pr)
+            .context(format!("Failed to fetch the review threads of #{number}"))?;
+        let from = oyo_core::git::get_merge_base(&repo_root, &pr.base_sha, &pr.head_sha)
+            .unwrap_or_else(|_| pr.base_sha.clone());
+        let to = pr.head_sha.clone();
+        review = Some(forge::Review {
+            forge,
+            pr,
+            threads,
+            replies: config.forge.replies,
+        });
+        InputMode::GitRange { from, to }
+    } else if let Some(Command::Remote { url, from, to }) = args.command.as_ref() {
+        let (repo_root, commits) = remote::fetch_refs(url, &[from, to])?;
+        let [from, to] = <[String; 2]>::try_from(commits).expect("one commit per ref");
+        InputMode::RemoteRange {
+            repo_root,
+            from,
+            to,
+        }
+    } else if let Some(Command::Docker { source, local }) = args.command.as_ref() {
+        InputMode::ContentPair {
+            old_path: PathBuf::from(source),
+            old_content: docker::read(source)?,
+            new_path: local.clone(),
+            new_content: std::fs::read_to_string(local)
+                .context(format!("Failed to read: {}", local.display()))?,
+        }
+    } else if let Some(base_path) = args.base.clone() {
+        let [ours_path, theirs_path] = args.paths.as_slice() else {
+            anyhow::bail!("--base expects two files: oy --base BASE OURS THEIRS");
+        };
+        if ours_path.is_dir() || theirs_path.is_dir() || base_path.is_dir() {
+            anyhow::bail!("--base compares files, not directories");
+        }
+        InputMode::ThreeWay {
+            base_path,
+            ours_path: ours_path.clone(),
+            theirs_path: theirs_path.clone(),
+        }
+    } else if let Some(vcs) = args.vcs {
+        if !args.paths.is_empty() {
+            anyhow::bail!("--vcs cannot be used with file paths");
+        }
+        match vcs {
+            CliVcs::Git => InputMode::GitUncommitted,
+            CliVcs::Svn => InputMode::SvnWorkingCopy,
+            CliVcs::P4 => InputMode::P4Opened,
+        }
+    } else if args.paths.len() == 7 {
         detect_input_mode(&args.paths)
-    } else if args.staged || args.range.is_some() {
+    } else if args.staged || args.range.is_some() || args.amend_preview {
         if !args.paths.is_empty() {
-            anyhow::bail!("--staged/--range cannot be used with file paths");
+            anyhow::bail!("--staged/--range/--amend-preview cannot be used with file paths");
         }
         if args.staged && args.range.is_some() {
             anyhow::bail!("--staged and --range are mutually exclusive");
@@ -652,6 +1557,17 @@ fn main() -> Result<()> {
         if let Some(range) = args.range.as_deref() {
             let (from, to) = parse_range(range)?;
             InputMode::GitRange { from, to }
+        } else if args.amend_preview {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            if !oyo_core::git::is_git_repo(&cwd) {
+                anyhow::bail!("Not in a git repository.");
+            }
+            let from = oyo_core::git::get_amend_base(&cwd)
+                .context("Failed to resolve the commit to amend")?;
+            InputMode::GitRange {
+                from,
+                to: INDEX_REF.to_string(),
+            }
         } else {
             InputMode::GitStaged
         }
@@ -659,13 +1575,36 @@ fn main() -> Result<()> {
         detect_input_mode(&args.paths)
     };
 
+    let commit_ref = commit_ref_for_input_mode(&input_mode);
+    let patch_series = match &input_mode {
+        InputMode::PatchSeries {
+            patches,
+            repo_root,
+            fuzz,
+        } => Some((patches.clone(), repo_root.clone(), *fuzz)),
+        _ => None,
+    };
+    let reject_files = match &input_mode {
+        InputMode::Rejects { rejects } => Some(rejects.clone()),
+        _ => None,
+    };
     let empty_message = match &input_mode {
-        InputMode::GitUncommitted => Some("No uncommitted changes found.".to_string()),
+        InputMode::GitUncommitted | InputMode::SvnWorkingCopy | InputMode::GitWorkspace { .. } => {
+            Some("No uncommitted changes found.".to_string())
+        }
+        InputMode::P4Opened => Some("No opened files found.".to_string()),
         InputMode::GitStaged => Some("No staged changes found.".to_string()),
+        InputMode::GitRange { .. } if args.amend_preview => {
+            Some("No changes between HEAD's parent and the index.".to_string())
+        }
         InputMode::GitRange { from, to } => Some(format!("No changes in range {}..{}.", from, to)),
+        InputMode::RemoteRange { .. } => Some("No changes between the two refs.".to_string()),
+        InputMode::GitBisect => {
+            Some("No changes between the good commit and the bisect candidate.".to_string())
+        }
         _ => Some("No changes found.".to_string()),
     };
-    let (multi_diff, git_branch) = match build_diff_from_input_mode(input_mode)? {
+    let (multi_diff, git_branch) = match build_diff_from_input_mode(input_mode, remote_ttl)? {
         Some(result) => result,
         None => {
             if let Some(message) = empty_message {
@@ -680,9 +1619,6 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    // Setup terminal
-    let mut terminal = setup_terminal()?;
-
     // Determine view mode (CLI overrides config)
     let view_mode: ViewMode = args.view.into();
     let view_mode = config.parse_view_mode().unwrap_or(view_mode);
@@ -699,22 +1635,72 @@ fn main() -> Result<()> {
 
     // Create app
     let mut app = App::new(multi_diff, view_mode, speed, autoplay, git_branch);
+    load_commit_info(&mut app, commit_ref);
+    if let Some(review) = review {
+        app.show_review_panel = !review.threads.is_empty();
+        app.review = Some(review);
+    }
+    if let Some((patches, repo_root, fuzz)) = patch_series {
+        app.set_patch_series(patches, repo_root, fuzz);
+    }
+    if let Some(rejects) = reject_files {
+        app.set_reject_files(rejects);
+    }
+    if app.multi_diff.has_base() {
+        // Run as a merge tool: name the sides after the refs being merged
+        app.merge_labels =
+            oyo_core::git::get_merge_labels(&std::env::current_dir().unwrap_or_default())
+                .ok()
+                .flatten();
+    }
 
     // Apply additional config settings
     app.zen_mode = config.ui.zen;
     app.animation_enabled = config.playback.animation;
+    app.reduced_motion = config.reduced_motion();
+    app.show_fps = config.ui.fps_overlay;
+    app.bidi = config.ui.bidi;
+    app.heat = config.ui.heat;
+    app.function_context = config.ui.function_context;
+    app.forge_remotes = config.forge.remotes.clone();
+    app.function_context_lines = config.ui.function_context_lines;
+    app.show_scrub_bar = config.ui.scrub_bar;
+    app.show_file_header = config.ui.file_header;
+    app.show_breadcrumbs = config.ui.breadcrumbs;
+    app.test_rules = config.tests.compile();
+    app.redactor = config.redact.redactor(args.redact);
+    app.force_quit = args.force;
+    app.merged_path = args.merged.clone();
+    app.search_regex_mode = config.search.regex;
+    app.search_case = config.search.case;
+    app.search_whole_word = config.search.whole_word;
     app.animation_duration = config.playback.animation_duration;
+    app.fade_out_duration = config.playback.fade_out_duration;
+    app.fade_in_duration = config.playback.fade_in_duration;
+    app.animation_easing = config.playback.easing.into();
+    app.scroll_duration = config.playback.scroll_duration;
     app.file_panel_visible = config.files.panel_visible;
     app.file_count_mode = config.files.counts;
+    app.churn_thumbnails = config.files.churn;
+    if config.files.memory_budget > 0 {
+        app.multi_diff
+            .set_memory_budget(Some(config.files.memory_budget * 1024 * 1024));
+    }
     app.auto_center = config.ui.auto_center;
+    if args.present {
+        app.presentation = false;
+        app.toggle_presentation();
+    }
     app.line_wrap = config.ui.line_wrap;
     app.scrollbar_visible = config.ui.scrollbar;
     app.strikethrough_deletions = config.ui.strikethrough_deletions;
+    app.kind_patterns = config.ui.patterns;
     app.diff_bg = config.ui.diff.bg;
     app.diff_fg = config.ui.diff.fg;
     app.syntax_mode = config.ui.syntax.mode;
     app.syntax_theme = config.ui.syntax.theme.clone();
     app.single_modified_step_mode = config.ui.single.modified_step_mode;
+    app.split_align_gaps = config.ui.split.align_gaps;
     app.evo_syntax = config.ui.evo.syntax;
     app.auto_step_on_enter = config.playback.auto_step_on_enter;
     app.auto_step_blank_files = config.playback.auto_step_blank_files;
@@ -732,9 +1718,27 @@ fn main() -> Result<()> {
         .unwrap_or_else(|| "▐".to_string());
 
     apply_config_to_app(&mut app, &config, &args, light_mode);
+    app.diagnostics = diagnostics;
+    if matches!(args.command, Some(Command::Release { .. })) {
+        app.set_file_sort(config::FileSortMode::Commit);
+    }
+
+    if let Some(dir) = &args.render_frames {
+        let written =
+            headless::render_frames(&mut app, dir, args.frame_size, args.frame_format.into())?;
+        println!("Wrote {written} frames to {}", dir.display());
+        return Ok(());
+    }
+    start_plugins(&mut app, &config);
+    load_actions(&mut app, &config);
+    install_crash_handler(&mut app);
+
+    // Setup terminal
+    let mut terminal = setup_terminal()?;
 
     // Run event loop
-    let result = run_app(&mut terminal, &mut app);
+    let mut frames = frame::FrameLimiter::new(config.ui.max_fps);
+    let result = run_app(&mut terminal, &mut app, &mut frames);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -753,31 +1757,63 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
-    let tick_rate = Duration::from_millis(16);
-
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    frames: &mut frame::FrameLimiter,
+) -> Result<()> {
     loop {
-        terminal.draw(|f| ui::draw(f, app))?;
-
-        // Clear active change after render (one-frame extent marker display when animation disabled)
-        if app.clear_active_on_next_render {
-            app.multi_diff.current_navigator().clear_active_change();
-            app.clear_active_on_next_render = false;
+        let now = Instant::now();
+        if frames.should_draw(now) {
+            let render = tracing::debug_span!("render").entered();
+            terminal.draw(|f| ui::draw(f, app))?;
+            drop(render);
+            frames.record_draw(now, Instant::now());
+            app.fps = frames.fps();
+            app.frame_time_ms = frames.last_draw_time().as_secs_f32() * 1000.0;
+
+            // Clear active change after render (one-frame extent marker display when animation disabled)
+            if app.clear_active_on_next_render {
+                app.multi_diff.current_navigator().clear_active_change();
+                app.clear_active_on_next_render = false;
+                frames.mark_dirty();
+            }
+            // Scrolling to the active change may take effect on the next frame
+            if app.needs_scroll_to_active {
+                frames.mark_dirty();
+            }
         }
 
-        if event::poll(tick_rate)? {
+        let timeout = frames.poll_timeout(Instant::now(), app.is_animating());
+        if event::poll(timeout)? {
+            frames.mark_dirty();
+            app.invalidate_rows();
             match event::read()? {
                 Event::Mouse(me) => {
-                    if app.show_help || app.show_path_popup {
+                    if !matches!(me.kind, MouseEventKind::Moved) {
+                        crash::record(
+                            format!("{:?} {},{}", me.kind, me.column, me.row),
+                            app.session(),
+                        );
+                    }
+                    if app.focused_popup().is_some() {
                         continue;
                     }
                     app.reset_count();
                     match me.kind {
+                        #[allow(clippy::collapsible_match)]
                         MouseEventKind::Down(MouseButton::Left) => {
-                            if app.handle_file_list_click(me.column, me.row) {
+                            if app.handle_file_list_click(me.column, me.row)
+                                || app.handle_scrub_click(me.column, me.row)
+                            {
                                 continue;
                             }
                         }
+                        MouseEventKind::Drag(MouseButton::Left)
+                            if app.handle_scrub_click(me.column, me.row) =>
+                        {
+                            continue;
+                        }
                         MouseEventKind::ScrollUp => {
                             if app.file_list_focused {
                                 app.prev_file();
@@ -800,97 +1836,174 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                     }
                 }
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    if app.show_help {
-                        match key.code {
-                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
-                                app.toggle_help();
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                app.help_scroll_down();
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                app.help_scroll_up();
-                            }
-                            _ => {}
-                        }
+                    crash::record(key_label(&key), app.session());
+                    app.echo_key(key_label(&key));
+                    // The path popup only shows information: q/Esc close it
+                    // when it is on top, other keys go to the popup under it
+                    // or to the diff
+                    if app.focused_popup() == Some(PopupKind::Path)
+                        && matches!(key.code, KeyCode::Esc | KeyCode::Char('q'))
+                    {
+                        app.reset_count();
+                        app.close_popup(PopupKind::Path);
                         continue;
                     }
-                    if app.file_filter_active {
-                        match key.code {
-                            KeyCode::Esc | KeyCode::Enter => {
-                                app.stop_file_filter();
-                            }
-                            KeyCode::Backspace => {
-                                app.pop_file_filter_char();
+                    match app.input_popup() {
+                        Some(PopupKind::Help) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                                    app.toggle_help();
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    app.help_scroll_down();
+                                }
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    app.help_scroll_up();
+                                }
+                                _ => {}
                             }
-                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                                app.clear_file_filter();
+                            continue;
+                        }
+                        Some(PopupKind::Jumps) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') => {
+                                    app.close_popup(PopupKind::Jumps);
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    app.jump_list_down();
+                                }
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    app.jump_list_up();
+                                }
+                                KeyCode::Enter => {
+                                    app.apply_selected_jump();
+                                }
+                                _ => {}
                             }
-                            KeyCode::Char(c)
-                                if !key.modifiers.contains(KeyModifiers::CONTROL)
-                                    && !key.modifiers.contains(KeyModifiers::ALT) =>
-                            {
-                                app.push_file_filter_char(c);
+                            continue;
+                        }
+                        Some(PopupKind::Log) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') => {
+                                    app.close_popup(PopupKind::Log)
+                                }
+                                KeyCode::Up | KeyCode::Char('k') => app.scroll_log(true, 1),
+                                KeyCode::Down | KeyCode::Char('j') => app.scroll_log(false, 1),
+                                KeyCode::PageUp => app.scroll_log(true, 10),
+                                KeyCode::PageDown => app.scroll_log(false, 10),
+                                KeyCode::Char('g') => app.scroll_log(true, usize::MAX),
+                                KeyCode::Char('G') => app.scroll_log(false, usize::MAX),
+                                _ => {}
                             }
-                            _ => {}
+                            continue;
                         }
-                        continue;
-                    }
-                    if app.goto_active() {
-                        match key.code {
-                            KeyCode::Esc => {
-                                app.clear_goto();
+                        Some(PopupKind::Messages) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') => {
+                                    app.close_popup(PopupKind::Messages);
+                                }
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    app.messages_scroll += 1;
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    app.messages_scroll = app.messages_scroll.saturating_sub(1);
+                                }
+                                _ => {}
                             }
-                            KeyCode::Enter => {
-                                app.apply_goto();
-                                app.clear_goto();
+                            continue;
+                        }
+                        Some(PopupKind::Settings) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') => {
+                                    app.close_popup(PopupKind::Settings);
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    app.settings_down();
+                                }
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    app.settings_up();
+                                }
+                                KeyCode::Enter
+                                | KeyCode::Char(' ')
+                                | KeyCode::Right
+                                | KeyCode::Char('l') => {
+                                    app.change_setting(true);
+                                }
+                                KeyCode::Left | KeyCode::Char('h') => {
+                                    app.change_setting(false);
+                                }
+                                _ => {}
                             }
-                            KeyCode::Backspace => {
-                                if app.goto_query().is_empty() {
-                                    app.clear_goto();
-                                } else {
-                                    app.pop_goto_char();
+                            continue;
+                        }
+                        Some(PopupKind::PatchRejects) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') => {
+                                    app.close_popup(PopupKind::PatchRejects);
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    app.patch_rejects_scroll += 1;
                                 }
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    app.patch_rejects_scroll =
+                                        app.patch_rejects_scroll.saturating_sub(1);
+                                }
+                                _ => {}
                             }
-                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                                app.clear_goto_text();
+                            continue;
+                        }
+                        Some(PopupKind::TestImpact) => {
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('I') => {
+                                    app.close_popup(PopupKind::TestImpact);
+                                }
+                                KeyCode::Enter => {
+                                    app.run_impacted_tests();
+                                }
+                                _ => {}
                             }
-                            KeyCode::Char(c)
-                                if !key.modifiers.contains(KeyModifiers::CONTROL)
-                                    && !key.modifiers.contains(KeyModifiers::ALT) =>
-                            {
-                                app.push_goto_char(c);
+                            continue;
+                        }
+                        Some(PopupKind::Confirm) => {
+                            match key.code {
+                                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                                    app.answer_confirm(true);
+                                }
+                                KeyCode::Char('s') if app.confirm_can_save() => {
+                                    app.save_and_quit();
+                                }
+                                KeyCode::Char('n')
+                                | KeyCode::Char('N')
+                                | KeyCode::Char('q')
+                                | KeyCode::Esc => {
+                                    app.answer_confirm(false);
+                                }
+                                _ => {}
                             }
-                            _ => {}
+                            continue;
                         }
+                        Some(PopupKind::Path) | None => {}
+                    }
+                    if app.file_filter_active {
+                        app.file_filter_key(PromptKey::from_key(&key));
+                        continue;
+                    }
+                    if app.goto_active() {
+                        app.goto_key(PromptKey::from_key(&key));
                         continue;
                     }
                     if app.search_active() {
                         match key.code {
-                            KeyCode::Esc => {
-                                app.clear_search();
-                            }
-                            KeyCode::Enter => {
-                                app.stop_search();
-                                app.search_next();
-                            }
-                            KeyCode::Backspace => {
-                                if app.search_query().is_empty() {
-                                    app.clear_search();
-                                } else {
-                                    app.pop_search_char();
-                                }
+                            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
+                                app.toggle_search_regex_mode();
                             }
-                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                                app.clear_search_text();
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+                                app.cycle_search_case();
                             }
-                            KeyCode::Char(c)
-                                if !key.modifiers.contains(KeyModifiers::CONTROL)
-                                    && !key.modifiers.contains(KeyModifiers::ALT) =>
-                            {
-                                app.push_search_char(c);
+                            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::ALT) => {
+                                app.toggle_search_whole_word();
                             }
-                            _ => {}
+                            _ => app.search_key(PromptKey::from_key(&key)),
                         }
                         continue;
                     }
@@ -905,11 +2018,41 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                             app.goto_start();
                             continue;
                         }
+                        if key.code == KeyCode::Char('y') {
+                            app.pending_g_prefix = false;
+                            app.reset_count();
+                            app.copy_permalink();
+                            continue;
+                        }
+                        if key.code == KeyCode::Char('x') {
+                            app.pending_g_prefix = false;
+                            app.reset_count();
+                            app.open_in_forge();
+                            continue;
+                        }
                         app.pending_g_prefix = false;
                     }
+                    if std::mem::take(&mut app.plugin_key_pending) {
+                        if key.code != KeyCode::Esc {
+                            app.plugins.notify(
+                                "keyPressed",
+                                serde_json::json!({ "key": key_label(&key) }),
+                            );
+                        }
+                        continue;
+                    }
+                    let action = app
+                        .actions
+                        .as_ref()
+                        .and_then(|actions| actions.for_key(&key_label(&key)))
+                        .map(str::to_string);
+                    if let Some(name) = action {
+                        app.reset_count();
+                        app.run_action(&name);
+                        continue;
+                    }
                     if matches!(key.code, KeyCode::Esc)
-                        && !app.show_help
-                        && !app.show_path_popup
+                        && app.focused_popup().is_none()
                         && (app.search_active()
                             || !app.search_query().is_empty()
                             || app.goto_active()
@@ -922,6 +2065,13 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                     }
 
                     match key.code {
+                        // Alt+1..9 = jump to the Nth hunk
+                        KeyCode::Char(c @ '1'..='9')
+                            if key.modifiers.contains(KeyModifiers::ALT) =>
+                        {
+                            app.reset_count();
+                            app.goto_hunk_number((c as u8 - b'0') as usize);
+                        }
                         // Digit keys for vim-style counts (e.g., 10j, 5l)
                         KeyCode::Char(c @ '0'..='9') => {
                             // Don't treat '0' as count if no pending count (it's a command)
@@ -932,6 +2082,11 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                                 app.push_count_digit(c as u8 - b'0');
                             }
                         }
+                        // \ = send the next key to the plugins
+                        KeyCode::Char('\\') if !app.plugins.is_empty() => {
+                            app.reset_count();
+                            app.plugin_key_pending = true;
+                        }
                         // $ = go to end of line (horizontal scroll to end, like vim)
                         KeyCode::Char('$') => {
                             app.reset_count();
@@ -939,11 +2094,19 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                         }
                         KeyCode::Char('q') | KeyCode::Esc => {
                             app.reset_count();
-                            if app.show_help {
-                                app.show_help = false;
-                            } else if app.show_path_popup {
-                                app.show_path_popup = false;
-                            } else {
+                            if let Some(kind) = app.focused_popup() {
+                                app.close_popup(kind);
+                            } else if key.code == KeyCode::Esc && !app.toasts.is_empty() {
+                                app.toasts.dismiss();
+                            } else if app.split_preview_active() {
+                                app.close_split_preview();
+                            } else if app.step_compare_active() {
+                                app.close_step_compare();
+                            } else if app.merge_preview_active() {
+                                app.close_merge_preview();
+                            } else if app.group_review_active() {
+                                app.clear_group_review();
+                            } else if app.request_quit() {
                                 return Ok(());
                             }
                         }
@@ -972,6 +2135,18 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                                 }
                             }
                         }
+                        // Scrub bar seeking (Shift+arrows, supports count)
+                        KeyCode::Right | KeyCode::Left
+                            if key.modifiers.contains(KeyModifiers::SHIFT)
+                                && app.scrub_bar_area.is_some() =>
+                        {
+                            let count = app.take_count() as isize;
+                            if key.code == KeyCode::Right {
+                                app.scrub_by(count);
+                            } else {
+                                app.scrub_by(-count);
+                            }
+                        }
                         // Hunk navigation (h/l and arrow keys, supports count)
                         KeyCode::Right | KeyCode::Char('l') => {
                             if app.stepping {
@@ -1012,16 +2187,21 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                                 app.goto_hunk_end_scroll();
                             }
                         }
-                        // Peek old without stepping (single view)
+                        // Peek old without stepping (single view), or the
+                        // other pane's line in a popup (split view)
                         KeyCode::Char('p') => {
                             app.reset_count();
-                            if app.stepping {
+                            if app.view_mode == ViewMode::Split {
+                                app.toggle_split_peek(SplitPeek::Old);
+                            } else if app.stepping {
                                 app.toggle_peek_old_change();
                             }
                         }
                         KeyCode::Char('P') => {
                             app.reset_count();
-                            if app.stepping {
+                            if app.view_mode == ViewMode::Split {
+                                app.toggle_split_peek(SplitPeek::New);
+                            } else if app.stepping {
                                 app.toggle_peek_old_hunk();
                             }
                         }
@@ -1051,6 +2231,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                             app.reset_count();
                             app.goto_end();
                         }
+                        // {N}% = N% through the steps, % = next quarter checkpoint
+                        KeyCode::Char('%') => {
+                            let percent = app.pending_count.take();
+                            if app.stepping {
+                                match percent {
+                                    Some(percent) => app.goto_step_percent(percent),
+                                    None => app.goto_next_checkpoint(),
+                                }
+                            }
+                        }
                         KeyCode::Char('<') => {
                             app.reset_count();
                             if app.stepping {
@@ -1076,10 +2266,46 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                                 app.next_file();
                             }
                         }
+                        // Patch series navigation
+                        KeyCode::Char('{') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.prev_patch();
+                            }
+                        }
+                        KeyCode::Char('}') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.next_patch();
+                            }
+                        }
+                        // Commit splitting: assign the current hunk to the active bucket
+                        KeyCode::Char('x') => {
+                            app.reset_count();
+                            app.toggle_bucket_hunk();
+                        }
+                        KeyCode::Char('C') => {
+                            app.reset_count();
+                            app.toggle_hunk_collapsed();
+                        }
+                        KeyCode::Char('(') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.prev_group_hunk();
+                            }
+                        }
+                        KeyCode::Char(')') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.next_group_hunk();
+                            }
+                        }
                         // General controls
                         KeyCode::Char(' ') => {
                             app.reset_count();
-                            if app.stepping {
+                            if app.file_list_focused {
+                                app.toggle_file_reviewed();
+                            } else if app.stepping {
                                 app.toggle_autoplay();
                             }
                         }
@@ -1093,6 +2319,81 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
                             app.reset_count();
                             app.toggle_view_mode();
                         }
+                        KeyCode::Char('R') => {
+                            app.reset_count();
+                            // Toggle file order: path / riskiest first
+                            app.toggle_file_sort();
+                        }
+                        KeyCode::Char('F') => {
+                            app.reset_count();
+                            app.toggle_fps_overlay();
+                        }
+                        KeyCode::Char('o') => {
+                            app.reset_count();
+                            // Highlight other occurrences of the changed words
+                            app.toggle_occurrences();
+                        }
+                        KeyCode::Char('I') => {
+                            app.reset_count();
+                            // Tests likely affected by the changed files
+                            app.open_test_impact();
+                        }
+                        KeyCode::Char('O') => {
+                            app.reset_count();
+                            // Step only through changes doing the same rename
+                            app.toggle_rename_filter();
+                        }
+                        KeyCode::Char('v') => {
+                            app.reset_count();
+                            // Step only through deletions, insertions or modifications
+                            app.cycle_kind_filter();
+                        }
+                        KeyCode::Char('A') => {
+                            app.reset_count();
+                            // Structural diff of config files
+                            app.toggle_structural();
+                        }
+                        KeyCode::Char('T') => {
+                            app.reset_count();
+                            // Dim unchanged lines by blame age
+                            app.toggle_heat();
+                        }
+                        KeyCode::Char('X') => {
+                            app.reset_count();
+                            // Swap old and new
+                            app.toggle_reversed();
+                        }
+                        KeyCode::Char('m') => {
+                            app.reset_count();
+                            // Step only through changes matching the search
+                            app.toggle_search_filter_steps();
+                        }
+                        KeyCode::Char('c') => {
+                            app.reset_count();
+                            app.toggle_commit_panel();
+                        }
+                        KeyCode::Char('V') => {
+                            app.reset_count();
+                            // Cycle config presets
+                            app.cycle_preset();
+                        }
+                        // Breadcrumbs: up a level, then siblings at that level
+                        KeyCode::Char('U') => {
+                            app.reset_count();
+                            app.crumb_up();
+                        }
+                        KeyCode::Char(',') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.crumb_sibling(false);
+                            }
+                        }
+                        KeyCode::Char('.') => {
+                            let count = app.take_count();
+                            for _ in 0..count {
+                                app.crumb_sibling(true);
+                            }
+                        }
                         // Scroll navigation (supports count)
                         KeyCode::Char('K') => {
                             let count = app.take_count();
@@ -1261,6 +2562,12 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
 
         // Handle autoplay
         app.tick();
+        if std::mem::take(&mut app.redraw_requested) {
+            app.invalidate_rows();
+            frames.mark_dirty();
+        } else if app.is_animating() {
+            frames.mark_dirty();
+        }
 
         if app.should_quit {
             return Ok(());
@@ -1268,6 +2575,37 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
     }
 }
 
+/// Short label of a key press for the presentation mode key overlay
+fn key_label(key: &KeyEvent) -> String {
+    let name = match key.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(ch) => ch.to_string(),
+        KeyCode::Enter => "⏎".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::BackTab => "S-Tab".to_string(),
+        KeyCode::Backspace => "⌫".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        _ => "?".to_string(),
+    };
+    let mut label = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("M-");
+    }
+    label + name.as_str()
+}
+
 fn run_dashboard<B: Backend>(
     terminal: &mut Terminal<B>,
     dashboard: &mut Dashboard,
@@ -0,0 +1,71 @@
+//! Unsaved editor buffers sent over stdin (`--overlay-stdin`), one JSON
+//! message per line:
+//!
+//! ```text
+//! {"type":"overlay","path":"src/main.rs","content":"..."}
+//! {"type":"clear","path":"src/main.rs"}
+//! ```
+
+use serde::Deserialize;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OverlayMessage {
+    /// Show `content` as the new side of `path` instead of the file on disk
+    Overlay { path: PathBuf, content: String },
+    /// Go back to the file on disk
+    Clear { path: PathBuf },
+}
+
+/// Parse one protocol line; blank and malformed lines are skipped
+pub fn parse_line(line: &str) -> Option<OverlayMessage> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// Read messages from stdin on a background thread
+pub fn spawn_stdin_reader() -> Receiver<OverlayMessage> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(message) = parse_line(&line) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line(r#"{"type":"overlay","path":"a.rs","content":"fn a() {}\n"}"#),
+            Some(OverlayMessage::Overlay {
+                path: PathBuf::from("a.rs"),
+                content: "fn a() {}\n".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line(r#"{"type":"clear","path":"a.rs"}"#),
+            Some(OverlayMessage::Clear {
+                path: PathBuf::from("a.rs"),
+            })
+        );
+        assert_eq!(parse_line("not json"), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! External-process plugins (`[[plugins]]`), speaking JSON-RPC 2.0 over
+//! stdin/stdout, one message per line.
+//!
+//! oy sends notifications:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","method":"initialize","params":{"version":"0.1.0","root":"/repo"}}
+//! {"jsonrpc":"2.0","method":"fileOpened","params":{"path":"src/a.rs","index":0,"status":"modified"}}
+//! {"jsonrpc":"2.0","method":"stepChanged","params":{"path":"src/a.rs","step":3,"total":9}}
+//! {"jsonrpc":"2.0","method":"keyPressed","params":{"key":"x"}}
+//! {"jsonrpc":"2.0","method":"command","params":{"name":"lint","args":"--fix"}}
+//! ```
+//!
+//! and accepts these methods from a plugin (as notifications, or as
+//! requests answered with a `null` result):
+//!
+//! ```text
+//! setStatus        {"text":"3 todos"}          status bar segment ("" clears it)
+//! annotate         {"path":"src/a.rs","line":12,"message":"...","severity":"warning"}
+//! clearAnnotations {"path":"src/a.rs"}         all of the plugin's if `path` is left out
+//! registerCommand  {"name":"lint","description":"..."}   runs as `:lint [args]`
+//! showMessage      {"message":"..."}
+//! ```
+
+use crate::config::PluginConfig;
+use crate::diagnostics::{Diagnostic, Severity};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A method called by a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum PluginMessage {
+    SetStatus {
+        text: String,
+    },
+    Annotate {
+        path: PathBuf,
+        /// 1-based line in the new version of the file
+        line: usize,
+        message: String,
+        #[serde(default)]
+        severity: Option<String>,
+    },
+    ClearAnnotations {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    RegisterCommand {
+        name: String,
+        #[serde(default)]
+        description: String,
+    },
+    ShowMessage {
+        message: String,
+    },
+}
+
+/// JSON-RPC error code and message
+type RpcError = (i64, String);
+
+/// One line from a plugin: the message (or why it was rejected) and the
+/// request id to answer, if any
+fn parse_line(line: &str) -> Option<(Result<PluginMessage, RpcError>, Option<Value>)> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(line) else {
+        return Some((Err((-32700, "parse error".to_string())), None));
+    };
+    let id = object.remove("id");
+    // A response to a request oy never sends
+    let method = object.remove("method")?;
+    let params = object.remove("params").unwrap_or(Value::Null);
+    let message =
+        serde_json::from_value(json!({ "method": method, "params": params })).map_err(|err| {
+            if err.to_string().starts_with("unknown variant") {
+                (-32601, format!("method not found: {method}"))
+            } else {
+                (-32602, format!("invalid params: {err}"))
+            }
+        });
+    Some((message, id))
+}
+
+/// A command registered by a plugin, run as `:name [args]`
+#[derive(Debug, Clone)]
+pub struct PluginCommand {
+    pub plugin: usize,
+    pub name: String,
+    pub description: String,
+}
+
+struct Plugin {
+    name: String,
+    child: Child,
+    /// Lines for the stdin writer thread
+    input: Sender<String>,
+}
+
+/// Running plugins and everything they contributed
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+    /// Lines read from the plugins' stdout, by plugin index
+    output: Option<Receiver<(usize, String)>>,
+    /// Status bar segment of each plugin
+    statuses: Vec<String>,
+    annotations: Vec<(usize, Diagnostic)>,
+    commands: Vec<PluginCommand>,
+    /// Messages to show, oldest first
+    notices: Vec<String>,
+}
+
+impl PluginHost {
+    /// Start every configured plugin in `root`; returns the host and an
+    /// error per plugin that failed to start
+    pub fn spawn(configs: &[PluginConfig], root: Option<&Path>) -> (Self, Vec<String>) {
+        let (sender, receiver) = mpsc::channel();
+        let mut host = PluginHost::default();
+        let mut errors = Vec::new();
+        for config in configs {
+            match start(config, root, host.plugins.len(), sender.clone()) {
+                Ok(plugin) => {
+                    host.plugins.push(plugin);
+                    host.statuses.push(String::new());
+                }
+                Err(err) => errors.push(format!("plugin {}: {err}", config.name)),
+            }
+        }
+        if !host.plugins.is_empty() {
+            host.output = Some(receiver);
+            host.notify(
+                "initialize",
+                json!({ "version": env!("CARGO_PKG_VERSION"), "root": root }),
+            );
+        }
+        (host, errors)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Send a notification to every plugin
+    pub fn notify(&mut self, method: &str, params: Value) {
+        let line = json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string();
+        for plugin in &self.plugins {
+            let _ = plugin.input.send(line.clone());
+        }
+    }
+
+    /// Apply what the plugins sent since the last poll; true if anything
+    /// shown changed
+    pub fn poll(&mut self) -> bool {
+        let Some(output) = &self.output else {
+            return false;
+        };
+        let lines: Vec<(usize, String)> = output.try_iter().collect();
+        let mut changed = false;
+        for (plugin, line) in lines {
+            let Some((message, id)) = parse_line(&line) else {
+                continue;
+            };
+            let response = match message {
+                Ok(message) => {
+                    self.apply(plugin, message);
+                    changed = true;
+                    json!({ "jsonrpc": "2.0", "id": id, "result": null })
+                }
+                Err((code, message)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": message },
+                }),
+            };
+            if id.is_some() {
+                let _ = self.plugins[plugin].input.send(response.to_string());
+            }
+        }
+        changed
+    }
+
+    fn apply(&mut self, plugin: usize, message: PluginMessage) {
+        match message {
+            PluginMessage::SetStatus { text } => self.statuses[plugin] = text,
+            PluginMessage::Annotate {
+                path,
+                line,
+                message,
+                severity,
+            } => {
+                let severity = severity.as_deref().map_or(Severity::Note, Severity::parse);
+                self.annotations.push((
+                    plugin,
+                    Diagnostic {
+                        path,
+                        line,
+                        severity,
+                        message,
+                    },
+                ));
+            }
+            PluginMessage::ClearAnnotations { path } => {
+                self.annotations.retain(|(owner, diag)| {
+                    *owner != plugin || path.as_ref().is_some_and(|path| diag.path != *path)
+                });
+            }
+            PluginMessage::RegisterCommand { name, description } => {
+                self.commands.retain(|command| command.name != name);
+                self.commands.push(PluginCommand {
+                    plugin,
+                    name,
+                    description,
+                });
+            }
+            PluginMessage::ShowMessage { message } => {
+                let name = &self.plugins[plugin].name;
+                self.notices.push(format!("{name}: {message}"));
+            }
+        }
+    }
+
+    /// Non-empty status bar segments, in plugin order
+    pub fn statuses(&self) -> impl Iterator<Item = &str> {
+        self.statuses
+            .iter()
+            .map(String::as_str)
+            .filter(|text| !text.is_empty())
+    }
+
+    pub fn annotations(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.annotations.iter().map(|(_, diag)| diag)
+    }
+
+    pub fn commands(&self) -> &[PluginCommand] {
+        &self.commands
+    }
+
+    pub fn take_notice(&mut self) -> Option<String> {
+        (!self.notices.is_empty()).then(|| self.notices.remove(0))
+    }
+
+    /// Send `:name args` to the plugin that registered `name`; false if
+    /// no plugin did
+    pub fn run_command(&mut self, name: &str, args: &str) -> bool {
+        let Some(command) = self.commands.iter().find(|command| command.name == name) else {
+            return false;
+        };
+        let line = json!({
+            "jsonrpc": "2.0",
+            "method": "command",
+            "params": { "name": name, "args": args },
+        })
+        .to_string();
+        let _ = self.plugins[command.plugin].input.send(line);
+        true
+    }
+}
+
+impl Drop for PluginHost {
+    fn drop(&mut self) {
+        for plugin in &mut self.plugins {
+            let _ = plugin.child.kill();
+            let _ = plugin.child.wait();
+        }
+    }
+}
+
+fn start(
+    config: &PluginConfig,
+    root: Option<&Path>,
+    index: usize,
+    output: Sender<(usize, String)>,
+) -> std::io::Result<Plugin> {
+    let mut command = Command::new(&config.command);
+    command
+        .args(&config.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        // Anything written to stderr would draw over the TUI
+        .stderr(Stdio::null());
+    if let Some(root) = root {
+        command.current_dir(root);
+    }
+    let mut child = command.spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    // Writes go through a thread so a plugin that stops reading never
+    // blocks the UI
+    let (input, lines) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        for line in lines {
+            if writeln!(stdin, "{line}")
+                .and_then(|()| stdin.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if output.send((index, line)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(Plugin {
+        name: config.name.clone(),
+        child,
+        input,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let (message, id) = parse_line(
+            r#"{"jsonrpc":"2.0","method":"annotate","params":{"path":"a.rs","line":3,"message":"todo"}}"#,
+        )
+        .unwrap();
+        assert_eq!(id, None);
+        assert_eq!(
+            message,
+            Ok(PluginMessage::Annotate {
+                path: PathBuf::from("a.rs"),
+                line: 3,
+                message: "todo".to_string(),
+                severity: None,
+            })
+        );
+
+        let (message, id) =
+            parse_line(r#"{"jsonrpc":"2.0","id":7,"method":"explode","params":{}}"#).unwrap();
+        assert_eq!(id, Some(json!(7)));
+        assert_eq!(message.unwrap_err().0, -32601);
+
+        let (message, _) =
+            parse_line(r#"{"jsonrpc":"2.0","method":"setStatus","params":{}}"#).unwrap();
+        assert_eq!(message.unwrap_err().0, -32602);
+
+        assert!(parse_line("").is_none());
+        assert!(parse_line(r#"{"jsonrpc":"2.0","id":1,"result":null}"#).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_round_trip() {
+        // Echoes each command it is sent back as its status
+        let script = r#"
+            echo '{"jsonrpc":"2.0","method":"registerCommand","params":{"name":"hello"}}'
+            while read -r line; do
+                case "$line" in
+                    *'"command"'*) echo '{"jsonrpc":"2.0","id":1,"method":"setStatus","params":{"text":"hi"}}' ;;
+                    *'"result"'*) echo '{"jsonrpc":"2.0","method":"showMessage","params":{"message":"answered"}}' ;;
+                esac
+            done
+        "#;
+        let config = PluginConfig {
+            name: "echo".to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        };
+        let (mut host, errors) = PluginHost::spawn(&[config], None);
+        assert!(errors.is_empty());
+
+        let wait_for = |host: &mut PluginHost, done: &dyn Fn(&mut PluginHost) -> bool| {
+            for _ in 0..200 {
+                host.poll();
+                if done(host) {
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            panic!("plugin did not answer");
+        };
+        wait_for(&mut host, &|host| !host.commands().is_empty());
+        assert!(host.run_command("hello", ""));
+        assert!(!host.run_command("missing", ""));
+        wait_for(&mut host, &|host| host.statuses().next().is_some());
+        assert_eq!(host.statuses().collect::<Vec<_>>(), ["hi"]);
+        // The setStatus request was answered
+        wait_for(&mut host, &|host| !host.notices.is_empty());
+        assert_eq!(host.take_notice().as_deref(), Some("echo: answered"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Floating windows over the diff
+//!
+//! Modal popups are [`PopupKind`]s. The app keeps them stacked in the order
+//! they were opened: the last one is drawn on top and takes the keys, the
+//! ones under it are drawn with a subtle border. [`Window`] places a popup,
+//! clears what is under it, draws its shadow and border from the theme, and
+//! leaves the inside to the caller.
+
+use crate::color;
+use crate::config::ResolvedTheme;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Padding},
+    Frame,
+};
+
+/// Cells kept free around a centered window
+const MARGIN: u16 = 2;
+
+/// Modal popups, in their default stacking order (bottom first) for those
+/// never raised
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupKind {
+    TestImpact,
+    PatchRejects,
+    Settings,
+    Log,
+    Jumps,
+    Messages,
+    Path,
+    Help,
+    Confirm,
+}
+
+impl PopupKind {
+    pub const ALL: [PopupKind; 9] = [
+        PopupKind::TestImpact,
+        PopupKind::PatchRejects,
+        PopupKind::Settings,
+        PopupKind::Log,
+        PopupKind::Jumps,
+        PopupKind::Messages,
+        PopupKind::Path,
+        PopupKind::Help,
+        PopupKind::Confirm,
+    ];
+
+    /// Whether the popup takes keys; the path popup only shows information
+    pub fn takes_input(self) -> bool {
+        self != PopupKind::Path
+    }
+}
+
+/// Where a window goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Centered in the screen
+    Center,
+    /// Across `area`, under its row `row`, or above the row when there is
+    /// no room below
+    Below { area: Rect, row: u16 },
+}
+
+/// A bordered popup window
+pub struct Window<'a> {
+    title: Line<'a>,
+    footer: Option<Line<'a>>,
+    width: u16,
+    height: u16,
+    placement: Placement,
+    padding: Padding,
+    focused: bool,
+}
+
+impl<'a> Window<'a> {
+    /// A focused, centered window with a centered title
+    pub fn new(title: impl Into<Line<'a>>) -> Self {
+        Self {
+            title: title.into(),
+            footer: None,
+            width: 0,
+            height: 0,
+            placement: Placement::Center,
+            padding: Padding::ZERO,
+            focused: true,
+        }
+    }
+
+    /// Outer size, border included; shrunk to fit the screen
+    pub fn size(mut self, width: u16, height: u16) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Line centered on the bottom border
+    pub fn footer(mut self, footer: impl Into<Line<'a>>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Windows under the focused one get a subtle border
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Outer area of the window on a screen of `bounds`
+    pub fn area(&self, bounds: Rect) -> Rect {
+        match self.placement {
+            Placement::Center => {
+                let width = self
+                    .width
+                    .min(bounds.width.saturating_sub(2 * MARGIN))
+                    .max(1);
+                let height = self
+                    .height
+                    .min(bounds.height.saturating_sub(2 * MARGIN))
+                    .max(1);
+                Rect::new(
+                    bounds.x + (bounds.width.saturating_sub(width)) / 2,
+                    bounds.y + (bounds.height.saturating_sub(height)) / 2,
+                    width.min(bounds.width),
+                    height.min(bounds.height),
+                )
+            }
+            Placement::Below { area, row } => {
+                let height = self.height.min(area.height);
+                let y = if row + 1 + height <= area.height {
+                    area.y + row + 1
+                } else {
+                    area.y + row.saturating_sub(height)
+                };
+                Rect::new(area.x, y, self.width.min(area.width), height)
+            }
+        }
+    }
+
+    /// Draw the window on `frame` and return the area inside its border
+    /// and padding, for the caller to fill
+    pub fn render(self, frame: &mut Frame, theme: &ResolvedTheme) -> Rect {
+        let area = self.area(frame.area());
+        render_shadow(frame, theme, area);
+        frame.render_widget(Clear, area);
+
+        let border = if self.focused {
+            theme.border_active
+        } else {
+            theme.border_subtle
+        };
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.title)
+            .title_alignment(Alignment::Center)
+            .border_style(Style::default().fg(border))
+            .padding(self.padding);
+        if let Some(footer) = self.footer {
+            block = block.title_bottom(footer.centered());
+        }
+        let mut style = Style::default().fg(theme.text);
+        if let Some(bg) = theme.background_panel {
+            style = style.bg(bg);
+        }
+        block = block.style(style);
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        inner
+    }
+}
+
+/// Darken the cells right of and below `area`; themes without a true-color
+/// background get no shadow, as there is nothing to darken
+fn render_shadow(frame: &mut Frame, theme: &ResolvedTheme, area: Rect) {
+    let Some(base) = theme.background.or(theme.background_panel) else {
+        return;
+    };
+    let Some(shadow) = color::blend_colors(base, Color::Black, 0.5) else {
+        return;
+    };
+    let style = Style::default()
+        .bg(shadow)
+        .fg(color::dim_color(theme.text_muted))
+        .remove_modifier(Modifier::BOLD);
+    let bounds = frame.area();
+    let right = Rect::new(area.right(), area.y + 1, 1, area.height);
+    let below = Rect::new(area.x + 1, area.bottom(), area.width, 1);
+    let buffer = frame.buffer_mut();
+    for strip in [right, below] {
+        buffer.set_style(strip.intersection(bounds), style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_placement_fits_the_screen() {
+        let screen = Rect::new(0, 0, 80, 24);
+        let centered = Window::new(" Help ").size(60, 100).area(screen);
+        assert_eq!(centered, Rect::new(10, 2, 60, 20));
+
+        let pane = Rect::new(40, 0, 40, 24);
+        let below = Window::new(" old ")
+            .size(40, 3)
+            .placement(Placement::Below { area: pane, row: 5 });
+        assert_eq!(below.area(screen), Rect::new(40, 6, 40, 3));
+        let above = Window::new(" old ")
+            .size(40, 3)
+            .placement(Placement::Below {
+                area: pane,
+                row: 22,
+            });
+        assert_eq!(above.area(screen), Rect::new(40, 19, 40, 3));
+    }
+}
@@ -0,0 +1,278 @@
+//! Prompts: one-line text inputs (search, go to, file filter) and yes/no
+//! confirmations of destructive actions
+//!
+//! Every input reads keys through [`PromptKey::from_key`], so they share
+//! editing (cursor movement, deleting words, clearing) and history recall.
+//! An input may have a validator, whose error is shown as the text is typed;
+//! the `:` prompt also refuses to run a command it rejects.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Checks the text of an input; the error is shown under the prompt
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// What a key does to an input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKey {
+    Insert(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    /// Ctrl-W
+    DeleteWord,
+    /// Ctrl-U
+    Clear,
+    /// Up: an older history entry
+    Older,
+    /// Down: a newer history entry
+    Newer,
+    Submit,
+    Cancel,
+    /// Not an editing key; left to the caller
+    Other,
+}
+
+impl PromptKey {
+    pub fn from_key(key: &KeyEvent) -> Self {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Esc => PromptKey::Cancel,
+            KeyCode::Enter => PromptKey::Submit,
+            KeyCode::Backspace => PromptKey::Backspace,
+            KeyCode::Delete => PromptKey::Delete,
+            KeyCode::Left => PromptKey::Left,
+            KeyCode::Right => PromptKey::Right,
+            KeyCode::Home => PromptKey::Home,
+            KeyCode::End => PromptKey::End,
+            KeyCode::Up => PromptKey::Older,
+            KeyCode::Down => PromptKey::Newer,
+            KeyCode::Char('a') if ctrl => PromptKey::Home,
+            KeyCode::Char('e') if ctrl => PromptKey::End,
+            KeyCode::Char('u') if ctrl => PromptKey::Clear,
+            KeyCode::Char('w') if ctrl => PromptKey::DeleteWord,
+            KeyCode::Char(c) if !ctrl && !alt => PromptKey::Insert(c),
+            _ => PromptKey::Other,
+        }
+    }
+}
+
+/// Text of a one-line input with a cursor and a place in its history
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    text: String,
+    /// Byte offset of the cursor, on a char boundary
+    cursor: usize,
+    /// History entry recalled, 0 = newest
+    recalled: Option<usize>,
+    validator: Option<Validator>,
+}
+
+impl Input {
+    pub fn with_validator(validator: Validator) -> Self {
+        Self {
+            validator: Some(validator),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_validator(&mut self, validator: Option<Validator>) {
+        self.validator = validator;
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Text before and after the cursor
+    pub fn split(&self) -> (&str, &str) {
+        self.text.split_at(self.cursor)
+    }
+
+    /// Replace the text, with the cursor at its end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    /// Empty the input and forget the recalled entry
+    pub fn clear(&mut self) {
+        self.set(String::new());
+        self.recalled = None;
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.text.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(ch) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= ch.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// The validator's verdict on the text; empty text is not checked
+    pub fn error(&self) -> Option<String> {
+        if self.text.is_empty() {
+            return None;
+        }
+        self.validator
+            .and_then(|validate| validate(&self.text).err())
+    }
+
+    /// Apply an editing key; true if the text changed. Submit, Cancel and
+    /// Other are left to the caller.
+    pub fn edit<S: AsRef<str>>(&mut self, key: PromptKey, history: &[S]) -> bool {
+        match key {
+            PromptKey::Insert(ch) => self.push(ch),
+            PromptKey::Backspace => {
+                if self.cursor == 0 {
+                    return false;
+                }
+                self.pop();
+            }
+            PromptKey::Delete => {
+                if self.cursor == self.text.len() {
+                    return false;
+                }
+                self.text.remove(self.cursor);
+            }
+            PromptKey::DeleteWord => {
+                let before = self.text[..self.cursor].trim_end();
+                let start = before.rfind(char::is_whitespace).map_or(0, |idx| idx + 1);
+                if start == self.cursor {
+                    return false;
+                }
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            PromptKey::Clear => {
+                if self.text.is_empty() {
+                    return false;
+                }
+                self.set(String::new());
+            }
+            PromptKey::Older => {
+                let next = self.recalled.map_or(0, |recalled| recalled + 1);
+                if next >= history.len() {
+                    return false;
+                }
+                self.recall(Some(next), history);
+            }
+            PromptKey::Newer => match self.recalled {
+                None => return false,
+                Some(0) => self.recall(None, history),
+                Some(recalled) => self.recall(Some(recalled - 1), history),
+            },
+            PromptKey::Left => {
+                if let Some(ch) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= ch.len_utf8();
+                }
+                return false;
+            }
+            PromptKey::Right => {
+                if let Some(ch) = self.text[self.cursor..].chars().next() {
+                    self.cursor += ch.len_utf8();
+                }
+                return false;
+            }
+            PromptKey::Home => {
+                self.cursor = 0;
+                return false;
+            }
+            PromptKey::End => {
+                self.cursor = self.text.len();
+                return false;
+            }
+            PromptKey::Submit | PromptKey::Cancel | PromptKey::Other => return false,
+        }
+        true
+    }
+
+    /// Show history entry `recalled` (most recent first), or an empty
+    /// input past the newest
+    fn recall<S: AsRef<str>>(&mut self, recalled: Option<usize>, history: &[S]) {
+        self.recalled = recalled;
+        let text = recalled
+            .and_then(|recalled| history.get(recalled))
+            .map(|entry| entry.as_ref().to_string())
+            .unwrap_or_default();
+        self.set(text);
+    }
+}
+
+/// Destructive actions that ask first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAction {
+    /// Write the selected rejected hunk into its file (`:apply`)
+    ApplyReject,
+    /// Create commits from the hunk buckets (`:commit`)
+    CommitBuckets,
+    /// Quit with work that would be lost (see `App::unsaved_work`)
+    Quit,
+    /// Post a reply to a pull request review thread (`:reply`)
+    PostReply,
+}
+
+/// A yes/no question about a destructive action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirm {
+    pub question: String,
+    pub action: ConfirmAction,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_edits_at_the_cursor_and_recalls_history() {
+        let history = ["newest", "older"];
+        let mut input = Input::default();
+        for ch in "fn mian".chars() {
+            input.edit(PromptKey::Insert(ch), &history);
+        }
+        input.edit(PromptKey::Left, &history);
+        input.edit(PromptKey::Left, &history);
+        input.edit(PromptKey::Backspace, &history);
+        input.edit(PromptKey::Right, &history);
+        input.edit(PromptKey::Insert('i'), &history);
+        assert_eq!(input.text(), "fn main");
+        assert_eq!(input.split(), ("fn mai", "n"));
+
+        input.edit(PromptKey::End, &history);
+        input.edit(PromptKey::DeleteWord, &history);
+        assert_eq!(input.text(), "fn ");
+
+        assert!(input.edit(PromptKey::Older, &history));
+        assert!(input.edit(PromptKey::Older, &history));
+        assert!(!input.edit(PromptKey::Older, &history));
+        assert_eq!(input.text(), "older");
+        input.edit(PromptKey::Newer, &history);
+        input.edit(PromptKey::Newer, &history);
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn test_validator_checks_non_empty_text() {
+        let mut input = Input::with_validator(|text| {
+            text.parse::<usize>()
+                .map(|_| ())
+                .map_err(|_| "not a number".to_string())
+        });
+        assert_eq!(input.error(), None);
+        input.set("12x");
+        assert_eq!(input.error().as_deref(), Some("not a number"));
+        input.pop();
+        assert_eq!(input.error(), None);
+    }
+}
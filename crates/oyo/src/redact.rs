@@ -0,0 +1,132 @@
+//! Masking of secrets (API keys, emails, ...) for screen sharing
+//! (`--redact` or `[redact] enabled = true`)
+//!
+//! Each pattern masks its whole match, or only its first capture group if it
+//! has one (e.g. the value of `password = ...` but not the key).
+
+use ratatui::text::Span;
+use regex::Regex;
+use unicode_width::UnicodeWidthChar;
+
+/// Character covering one column of redacted text
+const MASK: char = '█';
+
+/// Patterns used when `[redact] patterns` is not set
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    r#"(?i)(?:api[_-]?key|secret|token|password|passwd)["']?\s*[:=]\s*["']?([^\s"',;]+)"#,
+    r"\b(?:AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|sk-[A-Za-z0-9_-]{20,})\b",
+];
+
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: Vec<Regex>) -> Self {
+        Self { patterns }
+    }
+
+    /// Byte ranges of `text` to mask, sorted and merged
+    fn ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = self
+            .patterns
+            .iter()
+            .flat_map(|pattern| pattern.captures_iter(text))
+            .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+            .filter(|mat| !mat.is_empty())
+            .map(|mat| (mat.start(), mat.end()))
+            .collect();
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// `text` with every match masked
+    pub fn redact(&self, text: &str) -> String {
+        mask(text, 0, &self.ranges(text))
+    }
+
+    /// Mask the matches in a rendered line, keeping each span's style; the
+    /// line is matched as a whole so secrets split across spans are caught
+    pub fn redact_spans(&self, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+        let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+        let ranges = self.ranges(&text);
+        if ranges.is_empty() {
+            return spans;
+        }
+        let mut offset = 0;
+        spans
+            .into_iter()
+            .map(|span| {
+                let start = offset;
+                offset += span.content.len();
+                if ranges.iter().any(|&(s, e)| s < offset && e > start) {
+                    Span::styled(mask(&span.content, start, &ranges), span.style)
+                } else {
+                    span
+                }
+            })
+            .collect()
+    }
+}
+
+/// Replace the characters of `text` (starting at byte `offset` of its line)
+/// inside `ranges` with one mask character per column
+fn mask(text: &str, offset: usize, ranges: &[(usize, usize)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (idx, ch) in text.char_indices() {
+        let pos = offset + idx;
+        if ranges
+            .iter()
+            .any(|&(start, end)| (start..end).contains(&pos))
+        {
+            let width = ch.width().unwrap_or(0).max(1);
+            out.extend(std::iter::repeat_n(MASK, width));
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Style};
+
+    fn default_redactor() -> Redactor {
+        Redactor::new(
+            DEFAULT_PATTERNS
+                .iter()
+                .map(|pattern| Regex::new(pattern).unwrap())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_redact_masks_matches_and_groups() {
+        let redactor = default_redactor();
+        assert_eq!(redactor.redact("mail bob@x.io"), "mail ████████");
+        assert_eq!(redactor.redact("API_KEY=abc123"), "API_KEY=██████");
+        assert_eq!(redactor.redact("nothing here"), "nothing here");
+    }
+
+    #[test]
+    fn test_redact_spans_keeps_styles() {
+        let redactor = default_redactor();
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::raw("token: \"ab"), Span::styled("cd\" x", red)];
+        let out = redactor.redact_spans(spans);
+        assert_eq!(out[0].content, "token: \"██");
+        assert_eq!(out[1].content, "██\" x");
+        assert_eq!(out[1].style, red);
+    }
+}
@@ -0,0 +1,304 @@
+//! Remote path arguments: `user@host:path` or `ssh://[user@]host/path`
+//! (fetched over ssh) and `http(s)://` URLs (fetched with curl), cached
+//! under the user cache dir, readable by the user only;
+//! and refs of repositories that are not cloned (`oy remote`), fetched
+//! shallowly into a cached repository whose blobs are fetched on demand
+
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// Where a remote file lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RemoteSource {
+    Ssh { host: String, path: String },
+    Url(String),
+}
+
+impl RemoteSource {
+    fn parse(arg: &Path) -> Option<Self> {
+        let text = arg.to_str()?;
+        if text.starts_with("https://") || text.starts_with("http://") {
+            return Some(RemoteSource::Url(text.to_string()));
+        }
+        let (host, path) = match text.strip_prefix("ssh://") {
+            Some(rest) => {
+                let slash = rest.find('/')?;
+                (&rest[..slash], &rest[slash..])
+            }
+            // scp-style user@host:path; a bare `host:path` is more likely a
+            // local path with a ':' in it
+            None => {
+                let (host, path) = text.split_once(':')?;
+                if !host.contains('@') || arg.exists() {
+                    return None;
+                }
+                (host, path)
+            }
+        };
+        // A leading '-' would be read by ssh as an option
+        let bad_host = host.starts_with('-')
+            || host.ends_with('@')
+            || host.contains(['/', ':'])
+            || host.chars().any(char::is_whitespace);
+        if host.is_empty() || bad_host || path.is_empty() {
+            return None;
+        }
+        Some(RemoteSource::Ssh {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn fetch(&self) -> Result<Vec<u8>> {
+        let mut command = match self {
+            RemoteSource::Ssh { host, path } => {
+                let mut command = Command::new("ssh");
+                command
+                    .arg("-o")
+                    .arg("BatchMode=yes")
+                    .arg("--")
+                    .arg(host)
+                    .arg(format!("cat -- '{}'", path.replace('\'', r"'\''")));
+                command
+            }
+            RemoteSource::Url(url) => {
+                let mut command = Command::new("curl");
+                command.arg("-fsSL").arg("--max-time").arg("30").arg(url);
+                command
+            }
+        };
+        let output = command.output().context("Failed to run fetch command")?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(output.stdout)
+    }
+}
+
+fn cache_path(arg: &Path) -> Option<PathBuf> {
+    cache_entry("remote", arg)
+}
+
+fn cache_entry(kind: &str, key: impl Hash) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let dir = dirs::cache_dir()?.join("oyo").join(kind);
+    Some(dir.join(format!("{:016x}", hasher.finish())))
+}
+
+/// Create `dir` (and its missing parents) readable by the user only
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700);
+        builder.create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+    }
+    #[cfg(not(unix))]
+    {
+        builder.create(dir)
+    }
+}
+
+/// Write `content` to `path`, readable by the user only
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        (&file).write_all(content)
+    }
+    #[cfg(not(unix))]
+    {
+        options.open(path)?.write_all(content)
+    }
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Fetch `refs` (branches, tags or full commit ids) of the repository at
+/// `url` into a cached repository, without history and without blobs (they
+/// are fetched when a file is shown). Returns the repository and the commit
+/// of each ref.
+pub fn fetch_refs(url: &str, refs: &[&str]) -> Result<(PathBuf, Vec<String>)> {
+    let repo = cache_entry("repos", url).context("No cache directory")?;
+    let commits = fetch_refs_into(&repo, url, refs)?;
+    Ok((repo, commits))
+}
+
+/// [`fetch_refs`] into the repository at `repo`, set up on first use; a
+/// repository whose setup failed is removed rather than left half made
+fn fetch_refs_into(repo: &Path, url: &str, refs: &[&str]) -> Result<Vec<String>> {
+    if !repo.join(".git").exists() {
+        create_private_dir(repo).context(format!("Failed to create: {}", repo.display()))?;
+        let setup = (|| -> Result<()> {
+            git(repo, &["init", "--quiet"])?;
+            git(repo, &["remote", "add", "--", "origin", url])?;
+            git(repo, &["config", "remote.origin.promisor", "true"])?;
+            git(
+                repo,
+                &["config", "remote.origin.partialclonefilter", "blob:none"],
+            )?;
+            Ok(())
+        })();
+        if let Err(err) = setup {
+            let _ = std::fs::remove_dir_all(repo);
+            return Err(err.context(format!("Failed to set up a repository for {url}")));
+        }
+    }
+    let refspecs: Vec<String> = refs
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| format!("+{name}:refs/oyo/{idx}"))
+        .collect();
+    let mut args = vec![
+        "fetch",
+        "--quiet",
+        "--depth=1",
+        "--filter=blob:none",
+        "origin",
+    ];
+    args.extend(refspecs.iter().map(String::as_str));
+    git(repo, &args).context(format!("Failed to fetch {} from {url}", refs.join(", ")))?;
+    (0..refs.len())
+        .map(|idx| git(repo, &["rev-parse", &format!("refs/oyo/{idx}")]))
+        .collect()
+}
+
+/// Read a local or remote file. Remote copies younger than `ttl` are served
+/// from the cache; a stale copy is used if fetching fails.
+pub fn read(arg: &Path, ttl: Duration) -> Result<Vec<u8>> {
+    let Some(source) = RemoteSource::parse(arg) else {
+        return std::fs::read(arg).context(format!("Failed to read: {}", arg.display()));
+    };
+
+    let cache = cache_path(arg);
+    let cached_age = cache
+        .as_ref()
+        .and_then(|path| std::fs::metadata(path).ok())
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if let (Some(path), Some(age)) = (&cache, cached_age) {
+        if age < ttl {
+            if let Ok(content) = std::fs::read(path) {
+                return Ok(content);
+            }
+        }
+    }
+
+    match source.fetch() {
+        Ok(content) => {
+            if let Some(path) = &cache {
+                if let Some(dir) = path.parent() {
+                    let _ = create_private_dir(dir);
+                }
+                let _ = write_private(path, &content);
+            }
+            Ok(content)
+        }
+        Err(err) => match cache.and_then(|path| std::fs::read(path).ok()) {
+            Some(content) => {
+                eprintln!(
+                    "Warning: failed to fetch {} ({err}); using cached copy",
+                    arg.display()
+                );
+                Ok(content)
+            }
+            None => Err(err.context(format!("Failed to fetch: {}", arg.display()))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_sources() {
+        assert_eq!(
+            RemoteSource::parse(Path::new("deploy@web1:/etc/nginx/nginx.conf")),
+            Some(RemoteSource::Ssh {
+                host: "deploy@web1".into(),
+                path: "/etc/nginx/nginx.conf".into(),
+            })
+        );
+        assert_eq!(
+            RemoteSource::parse(Path::new("https://example.com/a.toml")),
+            Some(RemoteSource::Url("https://example.com/a.toml".into()))
+        );
+        assert_eq!(
+            RemoteSource::parse(Path::new("ssh://web1/etc/hosts")),
+            Some(RemoteSource::Ssh {
+                host: "web1".into(),
+                path: "/etc/hosts".into(),
+            })
+        );
+        assert_eq!(RemoteSource::parse(Path::new("web1:/etc/hosts")), None);
+        assert_eq!(RemoteSource::parse(Path::new("-oProxyCommand=x@h:y")), None);
+        assert_eq!(RemoteSource::parse(Path::new("ssh://-oProxy/x")), None);
+        assert_eq!(RemoteSource::parse(Path::new("C:/src/a.rs")), None);
+        assert_eq!(RemoteSource::parse(Path::new("dir/a:b.rs")), None);
+        assert_eq!(RemoteSource::parse(Path::new("local.rs")), None);
+    }
+
+    #[test]
+    fn test_fetch_refs_from_a_local_repository() {
+        let dir = std::env::temp_dir().join(format!("oyo-fetch-{}", std::process::id()));
+        let source = dir.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("a.txt"), "one\n").unwrap();
+        let commit = [
+            "-c",
+            "user.name=oyo",
+            "-c",
+            "user.email=oyo@localhost",
+            "commit",
+        ];
+        git(&source, &["init", "--quiet"]).unwrap();
+        git(&source, &["add", "a.txt"]).unwrap();
+        git(&source, &[&commit[..], &["--quiet", "-m", "one"]].concat()).unwrap();
+        git(&source, &["tag", "v1"]).unwrap();
+        let head = git(&source, &["rev-parse", "HEAD"]).unwrap();
+
+        let url = format!("file://{}", source.display());
+        let cache = dir.join("cache");
+        let commits = fetch_refs_into(&cache, &url, &["v1"]).unwrap();
+        assert_eq!(commits, [head.as_str()]);
+        assert_eq!(git(&cache, &["remote", "get-url", "origin"]).unwrap(), url);
+        // The cached repository is reused
+        assert_eq!(fetch_refs_into(&cache, &url, &["v1"]).unwrap(), [head]);
+
+        // A URL that looks like an option is still taken as the URL
+        let odd = dir.join("odd");
+        assert!(fetch_refs_into(&odd, "--mirror=fetch", &["v1"]).is_err());
+        assert_eq!(
+            git(&odd, &["remote", "get-url", "origin"]).unwrap(),
+            "--mirror=fetch"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+//! Settings panel (`:settings`): config options by category with their
+//! current values; a change applies at once and is written back to the
+//! config file, keeping its comments and layout
+
+use crate::app::App;
+use crate::config::{
+    DiffBackgroundMode, DiffForegroundMode, EvoSyntaxMode, FileCountMode, ModifiedStepMode,
+    SearchCase, SyntaxMode,
+};
+use std::path::Path;
+
+/// Values of an on/off setting, written to the config as booleans
+const SWITCH: &[&str] = &["off", "on"];
+
+/// A config option shown in the settings panel
+pub struct Setting {
+    pub category: &'static str,
+    /// Dotted config key, e.g. `ui.line_wrap`
+    pub key: &'static str,
+    pub label: &'static str,
+    /// Values in cycling order
+    pub choices: &'static [&'static str],
+    get: fn(&App) -> &'static str,
+    set: fn(&mut App, &'static str),
+}
+
+impl Setting {
+    pub fn value(&self, app: &App) -> &'static str {
+        (self.get)(app)
+    }
+
+    fn is_switch(&self) -> bool {
+        self.choices == SWITCH
+    }
+
+    /// Move to the next (or previous) value; returns the new value
+    pub fn cycle(&self, app: &mut App, forward: bool) -> &'static str {
+        let len = self.choices.len();
+        let current = self
+            .choices
+            .iter()
+            .position(|&choice| choice == self.value(app))
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        let value = self.choices[next];
+        (self.set)(app, value);
+        value
+    }
+}
+
+fn switch(on: bool) -> &'static str {
+    SWITCH[usize::from(on)]
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        category: "Interface",
+        key: "ui.zen",
+        label: "Zen mode",
+        choices: SWITCH,
+        get: |app| switch(app.zen_mode),
+        set: |app, value| app.zen_mode = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.auto_center",
+        label: "Center the active change",
+        choices: SWITCH,
+        get: |app| switch(app.auto_center),
+        set: |app, value| app.auto_center = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.line_wrap",
+        label: "Wrap long lines",
+        choices: SWITCH,
+        get: |app| switch(app.line_wrap),
+        set: |app, value| {
+            if app.line_wrap != (value == "on") {
+                app.toggle_line_wrap();
+            }
+        },
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.scrollbar",
+        label: "Scrollbar",
+        choices: SWITCH,
+        get: |app| switch(app.scrollbar_visible),
+        set: |app, value| app.scrollbar_visible = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.scrub_bar",
+        label: "Scrub bar",
+        choices: SWITCH,
+        get: |app| switch(app.show_scrub_bar),
+        set: |app, value| app.show_scrub_bar = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.file_header",
+        label: "File header bar",
+        choices: SWITCH,
+        get: |app| switch(app.show_file_header),
+        set: |app, value| app.show_file_header = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.fps_overlay",
+        label: "Frame rate overlay",
+        choices: SWITCH,
+        get: |app| switch(app.show_fps),
+        set: |app, value| app.show_fps = value == "on",
+    },
+    Setting {
+        category: "Interface",
+        key: "ui.bidi",
+        label: "Reorder right-to-left text",
+        choices: SWITCH,
+        get: |app| switch(app.bidi),
+        set: |app, value| app.bidi = value == "on",
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.diff.bg",
+        label: "Background",
+        choices: &["none", "text", "line"],
+        get: |app| match app.diff_bg {
+            DiffBackgroundMode::None => "none",
+            DiffBackgroundMode::Text => "text",
+            DiffBackgroundMode::Line => "line",
+        },
+        set: |app, value| {
+            app.diff_bg = match value {
+                "none" => DiffBackgroundMode::None,
+                "line" => DiffBackgroundMode::Line,
+                _ => DiffBackgroundMode::Text,
+            }
+        },
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.diff.fg",
+        label: "Foreground",
+        choices: &["theme", "syntax"],
+        get: |app| match app.diff_fg {
+            DiffForegroundMode::Theme => "theme",
+            DiffForegroundMode::Syntax => "syntax",
+        },
+        set: |app, value| {
+            app.diff_fg = match value {
+                "syntax" => DiffForegroundMode::Syntax,
+                _ => DiffForegroundMode::Theme,
+            }
+        },
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.syntax.mode",
+        label: "Syntax highlighting",
+        choices: &["on", "off"],
+        get: |app| match app.syntax_mode {
+            SyntaxMode::On => "on",
+            SyntaxMode::Off => "off",
+        },
+        set: |app, value| {
+            if matches!(app.syntax_mode, SyntaxMode::On) != (value == "on") {
+                app.toggle_syntax();
+            }
+        },
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.strikethrough_deletions",
+        label: "Strike through deletions",
+        choices: SWITCH,
+        get: |app| switch(app.strikethrough_deletions),
+        set: |app, value| app.strikethrough_deletions = value == "on",
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.patterns",
+        label: "Mark kinds without color",
+        choices: SWITCH,
+        get: |app| switch(app.kind_patterns),
+        set: |app, value| app.kind_patterns = value == "on",
+    },
+    Setting {
+        category: "Diff",
+        key: "ui.heat",
+        label: "Change heat",
+        choices: SWITCH,
+        get: |app| switch(app.heat),
+        set: |app, value| app.heat = value == "on",
+    },
+    Setting {
+        category: "Views",
+        key: "ui.single.modified_step_mode",
+        label: "Single pane modified lines",
+        choices: &["mixed", "modified"],
+        get: |app| match app.single_modified_step_mode {
+            ModifiedStepMode::Mixed => "mixed",
+            ModifiedStepMode::Modified => "modified",
+        },
+        set: |app, value| {
+            app.single_modified_step_mode = match value {
+                "modified" => ModifiedStepMode::Modified,
+                _ => ModifiedStepMode::Mixed,
+            }
+        },
+    },
+    Setting {
+        category: "Views",
+        key: "ui.split.align_gaps",
+        label: "Split: align unchanged tails",
+        choices: SWITCH,
+        get: |app| switch(app.split_align_gaps),
+        set: |app, value| app.split_align_gaps = value == "on",
+    },
+    Setting {
+        category: "Views",
+        key: "ui.evo.syntax",
+        label: "Evolution highlighting",
+        choices: &["context", "full"],
+        get: |app| match app.evo_syntax {
+            EvoSyntaxMode::Context => "context",
+            EvoSyntaxMode::Full => "full",
+        },
+        set: |app, value| {
+            app.evo_syntax = match value {
+                "full" => EvoSyntaxMode::Full,
+                _ => EvoSyntaxMode::Context,
+            }
+        },
+    },
+    Setting {
+        category: "Playback",
+        key: "playback.animation",
+        label: "Animations",
+        choices: SWITCH,
+        get: |app| switch(app.animation_enabled),
+        set: |app, value| app.animation_enabled = value == "on",
+    },
+    Setting {
+        category: "Playback",
+        key: "playback.auto_step_on_enter",
+        label: "Step to the first change",
+        choices: SWITCH,
+        get: |app| switch(app.auto_step_on_enter),
+        set: |app, value| app.auto_step_on_enter = value == "on",
+    },
+    Setting {
+        category: "Files",
+        key: "files.panel_visible",
+        label: "File panel",
+        choices: SWITCH,
+        get: |app| switch(app.file_panel_visible),
+        set: |app, value| {
+            if app.file_panel_visible != (value == "on") {
+                app.toggle_file_panel();
+            }
+        },
+    },
+    Setting {
+        category: "Files",
+        key: "files.counts",
+        label: "Change counts",
+        choices: &["active", "focused", "all", "off"],
+        get: |app| match app.file_count_mode {
+            FileCountMode::Active => "active",
+            FileCountMode::Focused => "focused",
+            FileCountMode::All => "all",
+            FileCountMode::Off => "off",
+        },
+        set: |app, value| {
+            app.file_count_mode = match value {
+                "focused" => FileCountMode::Focused,
+                "all" => FileCountMode::All,
+                "off" => FileCountMode::Off,
+                _ => FileCountMode::Active,
+            }
+        },
+    },
+    Setting {
+        category: "Files",
+        key: "files.churn",
+        label: "Churn sparklines",
+        choices: SWITCH,
+        get: |app| switch(app.churn_thumbnails),
+        set: |app, value| app.churn_thumbnails = value == "on",
+    },
+    Setting {
+        category: "Search",
+        key: "search.regex",
+        label: "Regular expressions",
+        choices: SWITCH,
+        get: |app| switch(app.search_regex_mode),
+        set: |app, value| app.search_regex_mode = value == "on",
+    },
+    Setting {
+        category: "Search",
+        key: "search.case",
+        label: "Letter case",
+        choices: &["smart", "sensitive", "insensitive"],
+        get: |app| match app.search_case {
+            SearchCase::Smart => "smart",
+            SearchCase::Sensitive => "sensitive",
+            SearchCase::Insensitive => "insensitive",
+        },
+        set: |app, value| {
+            app.search_case = match value {
+                "sensitive" => SearchCase::Sensitive,
+                "insensitive" => SearchCase::Insensitive,
+                _ => SearchCase::Smart,
+            }
+        },
+    },
+    Setting {
+        category: "Search",
+        key: "search.whole_word",
+        label: "Whole words",
+        choices: SWITCH,
+        get: |app| switch(app.search_whole_word),
+        set: |app, value| app.search_whole_word = value == "on",
+    },
+];
+
+/// Set `setting` to `value` in the config file at `path` (created if
+/// missing), leaving the rest of the file as it is
+pub fn write(path: &Path, setting: &Setting, value: &str) -> Result<(), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.to_string()),
+    };
+    let mut doc: toml_edit::DocumentMut = text.parse().map_err(|err| format!("{err}"))?;
+    let mut segments: Vec<&str> = setting.key.split('.').collect();
+    let name = segments.pop().expect("setting keys are not empty");
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for segment in segments {
+        if table
+            .get(segment)
+            .is_some_and(|item| item.as_table_like().is_none())
+        {
+            // e.g. the `ui.syntax = "on"` shorthand, replaced by its table
+            table.remove(segment);
+        }
+        table = table
+            .entry(segment)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .expect("a table");
+    }
+    let value = if setting.is_switch() {
+        toml_edit::value(value == "on")
+    } else {
+        toml_edit::value(value)
+    };
+    match table.get_mut(name) {
+        // Keep the comment after the value
+        Some(toml_edit::Item::Value(old)) => {
+            let decor = old.decor().clone();
+            *old = value.into_value().expect("a value item");
+            *old.decor_mut() = decor;
+        }
+        _ => {
+            table.insert(name, value);
+        }
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(path, doc.to_string()).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(key: &str) -> &'static Setting {
+        SETTINGS.iter().find(|setting| setting.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_write_keeps_comments_and_creates_tables() {
+        let path = std::env::temp_dir()
+            .join(format!("oyo-settings-{}", std::process::id()))
+            .join("config.toml");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            "# mine\n[ui]\nline_wrap = false   # wrap\nsyntax = \"on\"\n",
+        )
+        .unwrap();
+
+        write(&path, setting("ui.line_wrap"), "on").unwrap();
+        write(&path, setting("ui.syntax.mode"), "off").unwrap();
+        write(&path, setting("search.case"), "sensitive").unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert!(text.starts_with("# mine\n[ui]\nline_wrap = true   # wrap\n"));
+        assert!(text.contains("[ui.syntax]\nmode = \"off\"\n"), "{text}");
+        assert!(text.contains("[search]\ncase = \"sensitive\"\n"));
+        assert!(crate::validate::check(&text).is_empty(), "{text}");
+    }
+}
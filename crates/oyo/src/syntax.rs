@@ -2,14 +2,17 @@
 
 use crate::config::Config;
 use ratatui::style::{Color as TuiColor, Modifier, Style};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::io::Cursor;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use syntect::{
     easy::HighlightLines,
     highlighting::{Color, FontStyle, Style as SynStyle, Theme, ThemeSet},
-    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
     util::LinesWithEndings,
 };
 
@@ -25,10 +28,21 @@ pub struct SyntaxSpan {
     pub style: Style,
 }
 
-#[derive(Clone, Debug)]
+/// A function definition: its name and lines (0-based)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionSpan {
+    pub name: String,
+    pub lines: RangeInclusive<usize>,
+}
+
+/// Highlighted lines of one file. The old and new contents are separate
+/// documents, highlighted on first use and invalidated independently.
+#[derive(Clone, Debug, Default)]
 pub struct SyntaxCache {
-    old: Vec<Vec<SyntaxSpan>>,
-    new: Vec<Vec<SyntaxSpan>>,
+    old: Option<Vec<Vec<SyntaxSpan>>>,
+    new: Option<Vec<Vec<SyntaxSpan>>>,
+    /// Time spent highlighting the last highlighted side
+    pub highlight_time: Option<Duration>,
 }
 
 struct EmbeddedTmTheme {
@@ -278,19 +292,49 @@ const EMBEDDED_TMTHEMES: &[EmbeddedTmTheme] = &[
     },
 ];
 
+/// Syntect state for highlighting. The grammar set and resolved themes are
+/// loaded once per process and shared by every engine.
 pub struct SyntaxEngine {
-    syntax_set: SyntaxSet,
-    theme: Theme,
+    syntax_set: Arc<SyntaxSet>,
+    theme: Arc<Theme>,
     plain: TuiColor,
 }
 
+fn shared_syntax_set() -> Arc<SyntaxSet> {
+    static SYNTAX_SET: OnceLock<Arc<SyntaxSet>> = OnceLock::new();
+    SYNTAX_SET
+        .get_or_init(|| Arc::new(two_face::syntax::extra_newlines()))
+        .clone()
+}
+
+/// Resolved themes by (name, light mode)
+type ThemeCache = HashMap<(String, bool), (Arc<Theme>, TuiColor)>;
+
+fn shared_theme(syntax_theme: &str, light_mode: bool) -> (Arc<Theme>, TuiColor) {
+    static THEMES: OnceLock<Mutex<ThemeCache>> = OnceLock::new();
+    let themes = THEMES.get_or_init(Default::default);
+    let key = (syntax_theme.to_string(), light_mode);
+    if let Some(entry) = themes
+        .lock()
+        .ok()
+        .and_then(|themes| themes.get(&key).cloned())
+    {
+        return entry;
+    }
+    let (theme, plain) = resolve_syntax_theme(syntax_theme, light_mode);
+    let entry = (Arc::new(theme), plain);
+    if let Ok(mut themes) = themes.lock() {
+        themes.insert(key, entry.clone());
+    }
+    entry
+}
+
 impl SyntaxEngine {
     pub fn new(syntax_theme: &str, light_mode: bool) -> Self {
-        let syntax_set = two_face::syntax::extra_newlines();
-        let (syntax_theme, plain) = resolve_syntax_theme(syntax_theme, light_mode);
+        let (theme, plain) = shared_theme(syntax_theme, light_mode);
         Self {
-            syntax_set,
-            theme: syntax_theme,
+            syntax_set: shared_syntax_set(),
+            theme,
             plain,
         }
     }
@@ -355,6 +399,45 @@ impl SyntaxEngine {
         counts
     }
 
+    /// Functions defined in `content`, outermost first. The syntax names
+    /// the definitions (`entity.name.function`); a function then runs
+    /// while lines are indented deeper than its first, plus a closing line
+    /// (`}`) at the same depth.
+    pub fn function_spans(&self, content: &str, file_name: &str) -> Vec<FunctionSpan> {
+        let syntax = self.syntax_for_file(file_name);
+        let Ok(function) = Scope::new("entity.name.function") else {
+            return Vec::new();
+        };
+        let mut state = ParseState::new(syntax);
+        let mut stack = ScopeStack::new();
+        let lines: Vec<&str> = content.lines().collect();
+        let mut spans = Vec::new();
+
+        for (idx, line) in LinesWithEndings::from(content).enumerate() {
+            let ops = state.parse_line(line, &self.syntax_set).unwrap_or_default();
+            let mut name = None;
+            for (i, (offset, op)) in ops.iter().enumerate() {
+                stack.apply(op).ok();
+                if name.is_some() || !stack.scopes.iter().any(|s| function.is_prefix_of(*s)) {
+                    continue;
+                }
+                let end = ops.get(i + 1).map_or(line.len(), |(next, _)| *next);
+                let text = line.get(*offset..end).unwrap_or_default().trim();
+                if !text.is_empty() {
+                    name = Some(text.to_string());
+                }
+            }
+            if let Some(name) = name {
+                spans.push(FunctionSpan {
+                    name,
+                    lines: idx..=block_end(&lines, idx),
+                });
+            }
+        }
+
+        spans
+    }
+
     pub fn syntax_name_for_file(&self, file_name: &str) -> &str {
         &self.syntax_for_file(file_name).name
     }
@@ -398,6 +481,33 @@ impl SyntaxEngine {
     }
 }
 
+/// Last line of the block starting at line `start`: the lines indented
+/// deeper than it, and a closing line at its depth (which continues the
+/// block when it opens another, as in a signature split over lines)
+fn block_end(lines: &[&str], start: usize) -> usize {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let depth = lines.get(start).map_or(0, |line| indent(line));
+    let mut end = start;
+    for (idx, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if indent(line) > depth {
+            end = idx;
+            continue;
+        }
+        if !trimmed.starts_with(['}', ')', ']']) && trimmed != "end" {
+            break;
+        }
+        end = idx;
+        if !trimmed.ends_with(['{', '(', '[', ':']) {
+            break;
+        }
+    }
+    end
+}
+
 fn resolve_syntax_theme(theme_name: &str, light_mode: bool) -> (Theme, TuiColor) {
     let (mut ansi_theme, ansi_plain) = load_ansi_theme();
     strip_theme_backgrounds(&mut ansi_theme);
@@ -566,18 +676,42 @@ fn normalize_theme_key(value: &str) -> String {
 }
 
 impl SyntaxCache {
-    pub fn new(engine: &SyntaxEngine, old: &str, new: &str, file_name: &str) -> Self {
-        let old = engine.highlight(old, file_name);
-        let new = engine.highlight(new, file_name);
-        Self { old, new }
+    fn side_mut(&mut self, side: SyntaxSide) -> &mut Option<Vec<Vec<SyntaxSpan>>> {
+        match side {
+            SyntaxSide::Old => &mut self.old,
+            SyntaxSide::New => &mut self.new,
+        }
     }
 
-    pub fn spans(&self, side: SyntaxSide, line_index: usize) -> Option<&[SyntaxSpan]> {
-        match side {
-            SyntaxSide::Old => self.old.get(line_index).map(|v| v.as_slice()),
-            SyntaxSide::New => self.new.get(line_index).map(|v| v.as_slice()),
+    /// Highlight `content` as the `side` document unless it is cached
+    pub fn ensure(
+        &mut self,
+        engine: &SyntaxEngine,
+        side: SyntaxSide,
+        content: &str,
+        file_name: &str,
+    ) {
+        let lines = self.side_mut(side);
+        if lines.is_none() {
+            let _span = tracing::debug_span!("highlight", file = file_name, ?side).entered();
+            let started = Instant::now();
+            *lines = Some(engine.highlight(content, file_name));
+            self.highlight_time = Some(started.elapsed());
         }
     }
+
+    /// Drop the highlighting of one side; the other side is kept
+    pub fn invalidate(&mut self, side: SyntaxSide) {
+        *self.side_mut(side) = None;
+    }
+
+    pub fn spans(&self, side: SyntaxSide, line_index: usize) -> Option<&[SyntaxSpan]> {
+        let lines = match side {
+            SyntaxSide::Old => self.old.as_ref(),
+            SyntaxSide::New => self.new.as_ref(),
+        };
+        lines?.get(line_index).map(|v| v.as_slice())
+    }
 }
 
 fn syntect_style_to_tui(style: SynStyle) -> Style {
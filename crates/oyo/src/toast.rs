@@ -0,0 +1,142 @@
+//! Transient notifications ("toasts")
+//!
+//! Outcomes of actions (a hunk staged, a patch exported, a file changed on
+//! disk) are shown for a few seconds in the bottom right corner without
+//! taking the keys, and kept in a history shown by `:messages`.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Toasts shown at once; older ones are only in the history
+pub const MAX_VISIBLE: usize = 3;
+/// Messages kept for `:messages`
+const MAX_HISTORY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// How long a toast stays; problems stay longer so they can be read
+    pub fn timeout(self) -> Duration {
+        match self {
+            Level::Info | Level::Success => Duration::from_secs(3),
+            Level::Warning => Duration::from_secs(5),
+            Level::Error => Duration::from_secs(8),
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            Level::Info => "•",
+            Level::Success => "✓",
+            Level::Warning => "!",
+            Level::Error => "✗",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+    pub at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    /// Messages still shown, oldest first
+    shown: VecDeque<Message>,
+    /// Every message, oldest first
+    history: VecDeque<Message>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        let message = Message {
+            level,
+            text: text.into(),
+            at: Instant::now(),
+        };
+        // A repeated message restarts its toast instead of stacking
+        self.shown
+            .retain(|shown| shown.text != message.text || shown.level != message.level);
+        self.shown.push_back(message.clone());
+        while self.shown.len() > MAX_VISIBLE {
+            self.shown.pop_front();
+        }
+        self.history.push_back(message);
+        while self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    /// Drop toasts that timed out by `now`; true if any did
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.shown.len();
+        self.shown
+            .retain(|message| now.duration_since(message.at) < message.level.timeout());
+        self.shown.len() != before
+    }
+
+    /// Dismiss the toasts shown; they stay in the history
+    pub fn dismiss(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Toasts to show, oldest first
+    pub fn shown(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.shown.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shown.is_empty()
+    }
+
+    /// Every message, oldest first
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Message> + ExactSizeIterator {
+        self.history.iter()
+    }
+}
+
+/// Age of a message for the history, e.g. `12s` or `3m`
+pub fn age(at: Instant, now: Instant) -> String {
+    let secs = now.duration_since(at).as_secs();
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_and_stay_in_the_history() {
+        let mut toasts = Toasts::default();
+        toasts.push(Level::Success, "hunk staged");
+        toasts.push(Level::Error, "patch failed");
+        toasts.push(Level::Success, "hunk staged");
+        let shown: Vec<&str> = toasts.shown().map(|m| m.text.as_str()).collect();
+        assert_eq!(shown, ["patch failed", "hunk staged"]);
+
+        let later = Instant::now() + Duration::from_secs(4);
+        assert!(toasts.expire(later));
+        let shown: Vec<&str> = toasts.shown().map(|m| m.text.as_str()).collect();
+        assert_eq!(shown, ["patch failed"]);
+        assert!(!toasts.expire(later));
+        assert_eq!(toasts.history().len(), 3);
+
+        for i in 0..5 {
+            toasts.push(Level::Info, format!("message {i}"));
+        }
+        assert_eq!(toasts.shown().count(), MAX_VISIBLE);
+        assert_eq!(toasts.history().last().unwrap().text, "message 4");
+    }
+}
@@ -1,25 +1,42 @@
 //! UI rendering for the TUI
 
-use crate::app::{App, ViewMode};
-use crate::views::{render_evolution, render_single_pane, render_split};
-use oyo_core::FileStatus;
+use crate::app::{App, CrumbLevel, ViewMode};
+use crate::config::{ResolvedTheme, SearchCase};
+use crate::forge::Side;
+use crate::popup::{PopupKind, Window};
+use crate::prompt::Input;
+use crate::settings::SETTINGS;
+use crate::toast::{self, Level};
+use crate::views::{
+    render_evolution, render_single_pane, render_split, take_width, take_width_end, truncate_text,
+};
+use oyo_core::git::{BlobFetch, SignatureStatus};
+use oyo_core::notes::Verdict;
+use oyo_core::patch::PatchLine;
+use oyo_core::{ChangeKind, ChangeOrigin, FileStatus, HunkFit};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
-        ScrollbarState,
+        Block, Borders, Clear, List, ListItem, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
     },
     Frame,
 };
+use std::time::Instant;
 use unicode_width::UnicodeWidthStr;
 
+/// Columns of padding left and right of the content in presentation mode
+const PRESENTATION_PADDING_X: u16 = 4;
+/// Rows of padding above and below the content in presentation mode
+const PRESENTATION_PADDING_Y: u16 = 1;
+
 fn truncate_filename_keep_ext(name: &str, max_width: usize) -> String {
     if max_width == 0 {
         return String::new();
     }
-    if name.len() <= max_width {
+    if text_width(name) <= max_width {
         return name.to_string();
     }
     if max_width <= 3 {
@@ -30,22 +47,18 @@ fn truncate_filename_keep_ext(name: &str, max_width: usize) -> String {
         Some(idx) if idx > 0 && idx < name.len().saturating_sub(1) => (&name[..idx], &name[idx..]),
         _ => (name, ""),
     };
-    let ext_len = ext.len();
+    let ext_len = text_width(ext);
     if ext_len >= max_width {
         let suffix_len = max_width.saturating_sub(3);
-        return format!("...{}", &name[name.len().saturating_sub(suffix_len)..]);
+        return format!("...{}", take_width_end(name, suffix_len));
     }
 
     if ext_len == 0 {
         let stem_keep = max_width.saturating_sub(3);
         let head_len = stem_keep.div_ceil(2);
         let tail_len = stem_keep.saturating_sub(head_len);
-        let head = &stem[..head_len.min(stem.len())];
-        let tail = if tail_len > 0 && tail_len <= stem.len() {
-            &stem[stem.len().saturating_sub(tail_len)..]
-        } else {
-            ""
-        };
+        let head = take_width(stem, head_len);
+        let tail = take_width_end(stem, tail_len);
         return format!("{head}...{tail}");
     }
 
@@ -58,12 +71,8 @@ fn truncate_filename_keep_ext(name: &str, max_width: usize) -> String {
     let stem_keep = max_stem_len.saturating_sub(3);
     let head_len = stem_keep.div_ceil(2);
     let tail_len = stem_keep.saturating_sub(head_len);
-    let head = &stem[..head_len.min(stem.len())];
-    let tail = if tail_len > 0 && tail_len <= stem.len() {
-        &stem[stem.len().saturating_sub(tail_len)..]
-    } else {
-        ""
-    };
+    let head = take_width(stem, head_len);
+    let tail = take_width_end(stem, tail_len);
     format!("{head}...{tail}{ext}")
 }
 
@@ -159,7 +168,7 @@ fn truncate_path(path: &str, max_width: usize) -> String {
     if max_width == 0 {
         return String::new();
     }
-    if path.len() <= max_width {
+    if text_width(path) <= max_width {
         return path.to_string();
     }
     if max_width <= 3 {
@@ -177,11 +186,11 @@ fn truncate_path(path: &str, max_width: usize) -> String {
 
     // If just first + last fits with /.../, use that
     let prefix = format!("{}/.../", first);
-    let available = max_width.saturating_sub(prefix.len());
+    let available = max_width.saturating_sub(text_width(&prefix));
     if available > 0 {
         let last_display = truncate_filename_keep_ext(last, available);
         let simple = format!("{prefix}{last_display}");
-        if simple.len() <= max_width {
+        if text_width(&simple) <= max_width {
             return simple;
         }
     }
@@ -199,59 +208,382 @@ fn truncate_path(path: &str, max_width: usize) -> String {
     format!("{prefix}{last_display}")
 }
 
-fn truncate_text(text: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
-    }
-    if max_width <= 3 {
-        return ".".repeat(max_width);
-    }
-    if text.len() <= max_width {
-        return text.to_string();
-    }
-    let mut acc = String::new();
-    let mut width = 0usize;
-    for ch in text.chars() {
-        let ch_width = UnicodeWidthStr::width(ch.to_string().as_str());
-        if width + ch_width > max_width.saturating_sub(3) {
-            break;
-        }
-        acc.push(ch);
-        width += ch_width;
-    }
-    format!("{acc}...")
-}
-
 /// Main drawing function
 pub fn draw(frame: &mut Frame, app: &mut App) {
-    if app.zen_mode {
+    if app.presentation {
+        // Presentation mode: padded content only, with the keys pressed
+        let area = frame.area().inner(Margin {
+            horizontal: PRESENTATION_PADDING_X,
+            vertical: PRESENTATION_PADDING_Y,
+        });
+        draw_content(frame, app, area);
+        draw_key_echo(frame, app);
+    } else if app.zen_mode {
         // Zen mode: just the content with minimal progress indicator
         draw_content(frame, app, frame.area());
         draw_zen_progress(frame, app);
     } else {
+        let stale = app.current_file_stale();
+        let scrub = app.show_scrub_bar && app.stepping;
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(0),    // Main content
-                Constraint::Length(1), // Status bar
+                Constraint::Min(0),                   // Main content
+                Constraint::Length(u16::from(stale)), // Stale file warning
+                Constraint::Length(1),                // Status bar
+                Constraint::Length(u16::from(scrub)), // Scrub bar
             ])
             .split(frame.area());
 
-        draw_content(frame, app, chunks[0]);
-        draw_status_bar(frame, app, chunks[1]);
+        let content_area = if app.show_commit_panel {
+            draw_commit_panel(frame, app, chunks[0])
+        } else {
+            chunks[0]
+        };
+        let content_area = if app.show_review_panel {
+            draw_review_panel(frame, app, content_area)
+        } else {
+            content_area
+        };
+        draw_content(frame, app, content_area);
+        if stale {
+            draw_stale_banner(frame, app, chunks[1]);
+        }
+        draw_status_bar(frame, app, chunks[2]);
+        if scrub {
+            draw_scrub_bar(frame, app, chunks[3]);
+        } else {
+            app.scrub_bar_area = None;
+        }
+    }
+
+    draw_toasts(frame, app);
+
+    let focused = app.focused_popup();
+    for kind in app.open_popups() {
+        let focused = Some(kind) == focused;
+        match kind {
+            PopupKind::Help => draw_help_popover(frame, app, focused),
+            PopupKind::Path => draw_path_popup(frame, app, focused),
+            PopupKind::Jumps => draw_jump_list(frame, app, focused),
+            PopupKind::Messages => draw_messages(frame, app, focused),
+            PopupKind::Settings => draw_settings(frame, app, focused),
+            PopupKind::Log => draw_log(frame, app, focused),
+            PopupKind::TestImpact => draw_test_impact(frame, app, focused),
+            PopupKind::PatchRejects => draw_patch_rejects(frame, app, focused),
+            PopupKind::Confirm => draw_confirm(frame, app, focused),
+        }
+    }
+
+    if app.show_timings {
+        draw_timings(frame, app);
+    } else if app.show_fps {
+        draw_fps_overlay(frame, app);
+    }
+}
+
+/// Recently pressed keys, bottom right (presentation mode)
+fn draw_key_echo(frame: &mut Frame, app: &App) {
+    if app.key_echo.is_empty() {
+        return;
     }
+    let label = app
+        .key_echo
+        .iter()
+        .map(|(key, count, _)| match count {
+            1 => key.clone(),
+            _ => format!("{key}×{count}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let label = format!(" {label} ");
 
-    // Draw help popover if active
-    if app.show_help {
-        draw_help_popover(frame, app);
+    let area = frame.area();
+    let width = (text_width(&label) as u16).min(area.width);
+    let x = area.width.saturating_sub(width + PRESENTATION_PADDING_X);
+    let y = area.height.saturating_sub(1 + PRESENTATION_PADDING_Y);
+    let overlay_area = Rect::new(x, y, width, 1.min(area.height));
+
+    let mut style = Style::default()
+        .fg(app.theme.accent)
+        .add_modifier(Modifier::BOLD);
+    if let Some(bg) = app.theme.background_element {
+        style = style.bg(bg);
     }
+    frame.render_widget(Clear, overlay_area);
+    frame.render_widget(Paragraph::new(label).style(style), overlay_area);
+}
+
+fn toast_color(app: &App, level: Level) -> Color {
+    match level {
+        Level::Info => app.theme.info,
+        Level::Success => app.theme.success,
+        Level::Warning => app.theme.warning,
+        Level::Error => app.theme.error,
+    }
+}
 
-    // Draw file path popup if active
-    if app.show_path_popup {
-        draw_path_popup(frame, app);
+/// Notifications stacked in the bottom right corner above the status bar,
+/// newest at the bottom
+fn draw_toasts(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let bottom = area.height.saturating_sub(2);
+    for (row, message) in app.toasts.shown().rev().enumerate() {
+        let Some(y) = bottom.checked_sub(row as u16) else {
+            break;
+        };
+        let max_width = area.width.saturating_sub(4) as usize;
+        let text = truncate_to_width(&message.text, max_width.saturating_sub(4));
+        let label = format!(" {} {text} ", message.level.icon());
+        let width = (text_width(&label) as u16).min(area.width);
+        let toast_area = Rect::new(area.width.saturating_sub(width + 1), y, width, 1);
+
+        let mut style = Style::default().fg(toast_color(app, message.level));
+        if let Some(bg) = app.theme.background_element.or(app.theme.background_panel) {
+            style = style.bg(bg);
+        }
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(Paragraph::new(label).style(style), toast_area);
     }
 }
 
+/// Notification history (`:messages`), newest first
+fn draw_messages(frame: &mut Frame, app: &App, focused: bool) {
+    let now = Instant::now();
+    let muted = Style::default().fg(app.theme.text_muted);
+    let mut lines: Vec<Line> = app
+        .toasts
+        .history()
+        .rev()
+        .map(|message| {
+            Line::from(vec![
+                Span::styled(format!("{:>4} ", toast::age(message.at, now)), muted),
+                Span::styled(
+                    format!("{} ", message.level.icon()),
+                    Style::default().fg(toast_color(app, message.level)),
+                ),
+                Span::styled(message.text.clone(), Style::default().fg(app.theme.text)),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("No messages yet", muted)));
+    }
+
+    let inner = Window::new(" Messages ")
+        .size(80, lines.len() as u16 + 2)
+        .focused(focused)
+        .render(frame, &app.theme);
+    let max_scroll = lines.len().saturating_sub(inner.height as usize);
+    let scroll = app.messages_scroll.min(max_scroll);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), inner);
+}
+
+/// Yes/no question before a destructive action
+fn draw_confirm(frame: &mut Frame, app: &App, focused: bool) {
+    let Some(confirm) = &app.confirm else {
+        return;
+    };
+    let muted = Style::default().fg(app.theme.text_muted);
+    let key = Style::default().fg(app.theme.accent);
+    let mut footer = vec![Span::styled(" y", key), Span::styled(": yes · ", muted)];
+    if app.confirm_can_save() {
+        footer.push(Span::styled("s", key));
+        footer.push(Span::styled(": commit buckets first · ", muted));
+    }
+    footer.push(Span::styled("n", key));
+    footer.push(Span::styled(": no ", muted));
+    let footer = Line::from(footer);
+    let width = (text_width(&confirm.question) as u16 + 6).max(footer.width() as u16 + 4);
+    let inner = Window::new(" Confirm ")
+        .size(width, 5)
+        .footer(footer)
+        .padding(Padding::new(2, 2, 1, 0))
+        .focused(focused)
+        .render(frame, &app.theme);
+    let question = Paragraph::new(confirm.question.as_str())
+        .style(Style::default().fg(app.theme.text))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(question, inner);
+}
+
+/// Text of a prompt with its cursor, then the validator's error; the
+/// placeholder is shown while it is empty
+fn input_spans(input: &Input, placeholder: &str, theme: &ResolvedTheme) -> Vec<Span<'static>> {
+    let cursor = Style::default()
+        .fg(theme.text)
+        .add_modifier(Modifier::REVERSED);
+    if input.is_empty() {
+        return vec![
+            Span::styled(" ", cursor),
+            Span::styled(
+                placeholder.to_string(),
+                Style::default().fg(theme.text_muted),
+            ),
+        ];
+    }
+    let (before, after) = input.split();
+    let mut chars = after.chars();
+    let under = chars.next().map_or(" ".to_string(), String::from);
+    let mut spans = vec![
+        Span::styled(before.to_string(), Style::default().fg(theme.text)),
+        Span::styled(under, cursor),
+        Span::styled(chars.as_str().to_string(), Style::default().fg(theme.text)),
+    ];
+    if let Some(err) = input.error() {
+        spans.push(Span::styled(
+            format!("  {err}"),
+            Style::default().fg(theme.error),
+        ));
+    }
+    spans
+}
+
+fn draw_fps_overlay(frame: &mut Frame, app: &App) {
+    let mut label = format!(" {} fps · {:.1}ms ", app.fps, app.frame_time_ms);
+    let (reused, rebuilt) = app.row_cache.stats();
+    if reused > 0 {
+        label.push_str(&format!("· {rebuilt}/{} rows ", reused + rebuilt));
+    }
+
+    // Position in top-right corner
+    let area = frame.area();
+    let width = (text_width(&label) as u16).min(area.width);
+    let x = area.width.saturating_sub(width + 1);
+    let overlay_area = Rect::new(x, 0, width, 1);
+
+    let mut style = Style::default().fg(app.theme.text_muted);
+    if let Some(bg) = app.theme.background_panel {
+        style = style.bg(bg);
+    }
+    frame.render_widget(Clear, overlay_area);
+    frame.render_widget(Paragraph::new(label).style(style), overlay_area);
+}
+
+/// Timings HUD in the top-right corner: frame rate and render time, the
+/// current file's diff and highlighting times, and memory estimates
+fn draw_timings(frame: &mut Frame, app: &App) {
+    let ms = |time: Option<std::time::Duration>| match time {
+        Some(time) => format!("{:.1}ms", time.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    };
+    let index = app.multi_diff.selected_index;
+    let (current, total, loaded) = app.memory_estimates();
+    let rows = [
+        (
+            "frame",
+            format!("{} fps · {:.1}ms", app.fps, app.frame_time_ms),
+        ),
+        (
+            "diff",
+            ms(app
+                .multi_diff
+                .navigator(index)
+                .and_then(|nav| nav.diff_time())),
+        ),
+        ("highlight", ms(app.highlight_time())),
+        ("file mem", format_size(current)),
+        (
+            "all mem",
+            format!(
+                "{} · {loaded}/{} loaded",
+                format_size(total),
+                app.multi_diff.file_count()
+            ),
+        ),
+    ];
+    let label_style = Style::default().fg(app.theme.text_muted);
+    let value_style = Style::default().fg(app.theme.text);
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!(" {label:<10}"), label_style),
+                Span::styled(format!("{value} "), value_style),
+            ])
+        })
+        .collect();
+
+    let area = frame.area();
+    let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16).min(area.width);
+    let height = (lines.len() as u16).min(area.height);
+    let overlay_area = Rect::new(area.width.saturating_sub(width + 1), 0, width, height);
+    let mut style = Style::default();
+    if let Some(bg) = app.theme.background_panel {
+        style = style.bg(bg);
+    }
+    frame.render_widget(Clear, overlay_area);
+    frame.render_widget(Paragraph::new(lines).style(style), overlay_area);
+}
+
+/// One cell per slice of the step sequence, colored by the kind of change
+/// stepped there; applied cells are drawn heavy and the current step is marked
+fn draw_scrub_bar(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.scrub_bar_area = Some((area.x, area.y, area.width, area.height));
+    let width = usize::from(area.width);
+    if width == 0 {
+        return;
+    }
+    let insert = app.theme.insert_base();
+    let delete = app.theme.delete_base();
+    let modify = app.theme.modify_base();
+    let marker = Style::default().fg(app.theme.text);
+    let nav = app.multi_diff.current_navigator();
+    let current_step = nav.state().current_step;
+    let diff = nav.diff();
+    let steps = diff.significant_changes.len();
+    if steps == 0 {
+        frame.render_widget(Clear, area);
+        return;
+    }
+
+    let current_cell = current_step * (width - 1) / steps;
+    let spans: Vec<Span> = (0..width)
+        .map(|cell| {
+            if cell == current_cell {
+                return Span::styled("┃", marker);
+            }
+            let step = (cell * steps / width).min(steps - 1);
+            let color = diff
+                .significant_changes
+                .get(step)
+                .and_then(|&id| diff.changes.get(id))
+                .map_or(modify, |change| {
+                    let mut kinds = change.changes().map(|span| span.kind);
+                    match kinds.next() {
+                        Some(ChangeKind::Insert) if kinds.all(|k| k == ChangeKind::Insert) => {
+                            insert
+                        }
+                        Some(ChangeKind::Delete) if kinds.all(|k| k == ChangeKind::Delete) => {
+                            delete
+                        }
+                        _ => modify,
+                    }
+                });
+            let glyph = if step < current_step { "━" } else { "─" };
+            Span::styled(glyph, Style::default().fg(color))
+        })
+        .collect();
+    let mut paragraph = Paragraph::new(Line::from(spans));
+    if let Some(bg) = app.theme.background_panel {
+        paragraph = paragraph.style(Style::default().bg(bg));
+    }
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_stale_banner(frame: &mut Frame, app: &App, area: Rect) {
+    let label = format!(
+        " {} changed on disk; press r to reload the diff",
+        app.current_file_path()
+    );
+    let mut style = Style::default().fg(app.theme.warning);
+    if let Some(bg) = app.theme.background_panel {
+        style = style.bg(bg);
+    }
+    frame.render_widget(Paragraph::new(label).style(style), area);
+}
+
 fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     let state = app.state();
     let (insertions, deletions) = app.stats();
@@ -267,15 +599,19 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     let available_width = area.width as usize;
 
     let file_name = file_path.rsplit('/').next().unwrap_or(&file_path);
-    let scope_full = if let Some(branch) = app.git_branch.as_ref() {
+    let (file_name, branch) = match app.current_repo() {
+        Some((repo, branch)) => (format!("{repo}:{file_name}"), branch),
+        None => (file_name.to_string(), app.git_branch.clone()),
+    };
+    let scope_full = if let Some(branch) = branch.as_ref() {
         format!("{}@{}", file_path, branch)
     } else {
         file_path.clone()
     };
-    let scope_short = if let Some(branch) = app.git_branch.as_ref() {
+    let scope_short = if let Some(branch) = branch.as_ref() {
         format!("{}@{}", file_name, branch)
     } else {
-        file_name.to_string()
+        file_name
     };
 
     // Step counter and autoplay indicator (flash when autoplay is on)
@@ -312,7 +648,13 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     // Hunk counter
     let (current_hunk, total_hunks) = app.hunk_info();
     let hunk_text = if total_hunks > 0 {
-        Some(format!("{}/{}", current_hunk, total_hunks))
+        let bucket = app.current_hunk_bucket().map(|name| format!(" [{name}]"));
+        Some(format!(
+            "{}/{}{}",
+            current_hunk,
+            total_hunks,
+            bucket.unwrap_or_default()
+        ))
     } else {
         None
     };
@@ -340,33 +682,30 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     if show_goto {
         center_spans.push(Span::styled(":", Style::default().fg(app.theme.text_muted)));
         center_spans.push(Span::raw(" "));
-        let query = app.goto_query();
-        let query_text = if app.goto_active() && query.is_empty() {
-            "Go to".to_string()
-        } else {
-            query.to_string()
-        };
-        let query_style = if app.goto_active() && query.is_empty() {
-            Style::default().fg(app.theme.text_muted)
-        } else {
-            Style::default().fg(app.theme.text)
-        };
-        center_spans.push(Span::styled(query_text, query_style));
+        center_spans.extend(input_spans(app.goto_input(), "Go to", &app.theme));
     } else if show_search {
         center_spans.push(Span::styled("/", Style::default().fg(app.theme.text_muted)));
         center_spans.push(Span::raw(" "));
-        let query = app.search_query();
-        let query_text = if app.search_active() && query.is_empty() {
-            "Search".to_string()
-        } else {
-            query.to_string()
-        };
-        let query_style = if app.search_active() && query.is_empty() {
-            Style::default().fg(app.theme.text_muted)
-        } else {
-            Style::default().fg(app.theme.text)
-        };
-        center_spans.push(Span::styled(query_text, query_style));
+        center_spans.extend(input_spans(app.search_input(), "Search", &app.theme));
+        // Active search options (toggled with Alt-r / Alt-c / Alt-w)
+        let mut flags = Vec::new();
+        if app.search_regex_mode {
+            flags.push(".*");
+        }
+        match app.search_case {
+            SearchCase::Smart => {}
+            SearchCase::Sensitive => flags.push("Aa"),
+            SearchCase::Insensitive => flags.push("aa"),
+        }
+        if app.search_whole_word {
+            flags.push("\\b");
+        }
+        if !flags.is_empty() {
+            center_spans.push(Span::styled(
+                format!(" {}", flags.join(" ")),
+                Style::default().fg(app.theme.accent),
+            ));
+        }
     } else if app.stepping {
         let autoplay_marker = if app.autoplay {
             if app.autoplay_reverse {
@@ -384,10 +723,185 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
             Style::default().fg(app.theme.text_muted),
         ));
         center_spans.push(Span::styled(step_text.clone(), step_style));
+        if let Some(kind) = app.kind_filter {
+            let marker = match kind {
+                ChangeKind::Delete => " -",
+                ChangeKind::Insert => " +",
+                _ => " ~",
+            };
+            center_spans.push(Span::styled(marker, Style::default().fg(app.theme.accent)));
+        }
+        if app.rename_filter.is_some() {
+            center_spans.push(Span::styled(" =", Style::default().fg(app.theme.accent)));
+        } else if app.steps_filtered() {
+            center_spans.push(Span::styled(" /", Style::default().fg(app.theme.accent)));
+        }
     }
 
-    // Build RIGHT section: stats + hunk + file
-    let mut right_spans = vec![
+    // Build RIGHT section: search counter + stats + hunk + file
+    let mut right_spans = Vec::new();
+    if let Some((position, total)) = app.search_counter() {
+        let counter = match position {
+            Some(position) => format!("{position}/{total}"),
+            None if total == 1 => "1 match".to_string(),
+            None => format!("{total} matches"),
+        };
+        right_spans.push(Span::styled(
+            counter,
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some(sites) = app.rename_sites().filter(|sites| sites.total > 1) {
+        let mut text = format!("{}→{}: {} sites", sites.old, sites.new, sites.total);
+        if sites.files > 1 {
+            text.push_str(&format!(" in {} files", sites.files));
+        }
+        right_spans.push(Span::styled(
+            text,
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    match app.multi_diff.fetch_status(app.multi_diff.selected_index) {
+        Some(BlobFetch::Fetched) => {
+            right_spans.push(Span::styled(
+                "fetched",
+                Style::default().fg(app.theme.text_muted),
+            ));
+            right_spans.push(Span::raw("  "));
+        }
+        Some(BlobFetch::Failed) => {
+            right_spans.push(Span::styled(
+                "blob fetch failed",
+                Style::default().fg(app.theme.error),
+            ));
+            right_spans.push(Span::raw("  "));
+        }
+        None => {}
+    }
+    if app.multi_diff.has_overlay(app.multi_diff.selected_index) {
+        right_spans.push(Span::styled(
+            "unsaved buffer",
+            Style::default().fg(app.theme.warning),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if app.multi_diff.is_reversed() {
+        right_spans.push(Span::styled(
+            "reversed",
+            Style::default().fg(app.theme.warning),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some(adapter) = app.multi_diff.current_adapter() {
+        right_spans.push(Span::styled(
+            format!("structural {adapter}"),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some((group, groups, hunk, hunks, reason)) = app.group_position() {
+        right_spans.push(Span::styled(
+            format!("group {group}/{groups} · {hunk}/{hunks} · {reason}"),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some((left, right)) = app.step_compare_steps() {
+        right_spans.push(Span::styled(
+            format!("step {left} ↔ step {right}"),
+            Style::default().fg(app.theme.accent),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some(conflicts) = app.merge_preview_conflicts() {
+        let (text, color) = match conflicts {
+            0 => ("merged".to_string(), app.theme.accent),
+            1 => ("merged · 1 conflict".to_string(), app.theme.warning),
+            _ => (format!("merged · {conflicts} conflicts"), app.theme.warning),
+        };
+        right_spans.push(Span::styled(text, Style::default().fg(color)));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some((position, total, message)) = app.split_preview_position() {
+        right_spans.push(Span::styled(
+            format!("commit {position}/{total} · {message}"),
+            Style::default().fg(app.theme.accent),
+        ));
+        right_spans.push(Span::raw("  "));
+    } else if let Some((name, hunks, position, total)) = app.bucket_position() {
+        right_spans.push(Span::styled(
+            format!("bucket {position}/{total} · {name} · {hunks} hunks"),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    for status in app.plugins.statuses() {
+        right_spans.push(Span::styled(
+            status.to_string(),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some(line) = app.reject_placement() {
+        right_spans.push(Span::styled(
+            format!("at line {line} · :place <line> · :apply"),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+    }
+    if let Some((position, total)) = app.patch_position() {
+        right_spans.push(Span::styled(
+            format!("patch {position}/{total}"),
+            Style::default().fg(app.theme.text_muted),
+        ));
+        right_spans.push(Span::raw("  "));
+        let path = app.multi_diff.current_file().map(|file| file.path.clone());
+        if let Some(check) = path.and_then(|path| app.multi_diff.patch_check(&path)) {
+            let count = |matches: fn(&HunkFit) -> bool| {
+                check.hunks.iter().filter(|hunk| matches(&hunk.fit)).count()
+            };
+            let clean = count(|fit| *fit == HunkFit::Clean);
+            let fuzzy = count(|fit| matches!(fit, HunkFit::Offset { fuzz, .. } if *fuzz > 0));
+            let offset = count(|fit| matches!(fit, HunkFit::Offset { fuzz: 0, .. }));
+            let rejected = count(|fit| *fit == HunkFit::Rejected);
+            let mut parts = vec![format!("{clean} clean")];
+            if offset > 0 {
+                parts.push(format!("{offset} offset"));
+            }
+            if fuzzy > 0 {
+                parts.push(format!("{fuzzy} fuzz"));
+            }
+            if rejected > 0 {
+                parts.push(format!("{rejected} rejected (:rejects)"));
+            }
+            let color = if rejected > 0 {
+                app.theme.error
+            } else if check.is_clean() {
+                app.theme.text_muted
+            } else {
+                app.theme.warning
+            };
+            right_spans.push(Span::styled(
+                format!("hunks {}", parts.join(" · ")),
+                Style::default().fg(color),
+            ));
+            right_spans.push(Span::raw("  "));
+        }
+    }
+    if app.multi_diff.has_base() {
+        // Legend for three-way line-number colors
+        for origin in [ChangeOrigin::Ours, ChangeOrigin::Theirs, ChangeOrigin::Both] {
+            right_spans.push(Span::styled(
+                app.origin_label(origin),
+                Style::default().fg(app.origin_theme_color(origin)),
+            ));
+            right_spans.push(Span::raw(" "));
+        }
+        right_spans.push(Span::raw(" "));
+    }
+    right_spans.extend([
         Span::styled(
             format!("+{}", insertions),
             Style::default().fg(app.theme.success),
@@ -397,7 +911,7 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
             format!("-{}", deletions),
             Style::default().fg(app.theme.error),
         ),
-    ];
+    ]);
     if let Some(ref hunk) = hunk_text {
         right_spans.push(Span::raw("  "));
         let hunk_label = if let Some(ref hunk_step) = hunk_step_text {
@@ -421,7 +935,11 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     let left_width = (available_width * 4) / 10;
     let center_width = (available_width * 2) / 10;
     let right_width = available_width.saturating_sub(left_width + center_width);
-    let left_fixed_width = text_width(mode) + 1;
+    let preset_label = app
+        .active_preset_name()
+        .map(|name| format!("{} ", name))
+        .unwrap_or_default();
+    let left_fixed_width = text_width(mode) + 1 + text_width(&preset_label);
     let path_max_width = left_width.saturating_sub(left_fixed_width);
     let scope_base = if available_width < 60 {
         scope_short
@@ -439,6 +957,7 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" "),
+        Span::styled(preset_label, Style::default().fg(app.theme.accent)),
         Span::styled(display_scope, Style::default().fg(app.theme.text_muted)),
     ];
 
@@ -455,12 +974,211 @@ fn draw_status_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     spans.extend(center_spans);
     spans.extend(right_spans);
 
-    let status_line = Line::from(spans);
-    let mut paragraph = Paragraph::new(status_line);
-    if let Some(bg) = app.theme.background_element.or(app.theme.background) {
-        paragraph = paragraph.style(Style::default().bg(bg));
+    let status_line = Line::from(spans);
+    let mut paragraph = Paragraph::new(status_line);
+    if let Some(bg) = app.theme.background_element.or(app.theme.background) {
+        paragraph = paragraph.style(Style::default().bg(bg));
+    }
+    frame.render_widget(paragraph, area);
+}
+
+/// Draw the commit message panel at the top of `area`, returning the remaining area
+fn draw_commit_panel(frame: &mut Frame, app: &App, area: Rect) -> Rect {
+    let Some(info) = app.commit_info.as_ref() else {
+        return area;
+    };
+
+    let label_style = Style::default().fg(app.theme.text_muted);
+    let value_style = Style::default().fg(app.theme.text);
+    let parents = info
+        .parents
+        .iter()
+        .map(|p| p.chars().take(7).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("commit  ", label_style),
+            Span::styled(info.id.clone(), Style::default().fg(app.theme.accent)),
+        ]),
+        Line::from(vec![
+            Span::styled("author  ", label_style),
+            Span::styled(
+                format!("{} <{}>", info.author, info.author_email),
+                value_style,
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("date    ", label_style),
+            Span::styled(info.date.clone(), value_style),
+        ]),
+        Line::from(vec![
+            Span::styled("parents ", label_style),
+            Span::styled(parents, value_style),
+        ]),
+    ];
+    if let Some(signature) = &info.signature {
+        let (text, color) = match signature {
+            SignatureStatus::Unsigned => ("unsigned".to_string(), app.theme.text_muted),
+            SignatureStatus::Good(signer) if signer.is_empty() => {
+                ("✓ good signature".to_string(), app.theme.success)
+            }
+            SignatureStatus::Good(signer) => (format!("✓ {signer}"), app.theme.success),
+            SignatureStatus::Bad(reason) => (format!("✗ {reason}"), app.theme.error),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("signed  ", label_style),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+    for (key, value) in info.trailers() {
+        lines.push(Line::from(vec![
+            Span::styled("trailer ", label_style),
+            Span::styled(format!("{key}: "), label_style),
+            Span::styled(value.to_string(), value_style),
+        ]));
+    }
+    for review in &app.commit_reviews {
+        let (icon, color) = match review.verdict {
+            Verdict::Approved => ("✓", app.theme.success),
+            Verdict::ChangesRequested => ("✗", app.theme.error),
+            Verdict::Commented => ("•", app.theme.info),
+        };
+        let mut spans = vec![
+            Span::styled("review  ", label_style),
+            Span::styled(
+                format!("{icon} {}", review.verdict.label()),
+                Style::default().fg(color),
+            ),
+            Span::styled(format!(" {} {}", review.reviewer, review.date), label_style),
+        ];
+        if let Some(first) = review.comment.lines().next() {
+            spans.push(Span::styled(format!("  {first}"), value_style));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    lines.extend(
+        info.body()
+            .lines()
+            .map(|line| Line::from(Span::styled(line.to_string(), value_style))),
+    );
+
+    // At most a third of the content area, leaving room for the diff
+    let max_height = (area.height / 3).max(4);
+    let height = (lines.len() as u16 + 2).min(max_height).min(area.height);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+
+    let mut block = Block::default()
+        .borders(Borders::BOTTOM)
+        .border_style(Style::default().fg(app.theme.border_subtle));
+    if let Some(bg) = app.theme.background_panel {
+        block = block.style(Style::default().bg(bg));
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), chunks[0]);
+
+    chunks[1]
+}
+
+/// Review threads of the current file on the right of the content, the
+/// ones on the current hunk marked; returns the area left for the diff
+fn draw_review_panel(frame: &mut Frame, app: &App, area: Rect) -> Rect {
+    let Some(review) = app.review.as_ref() else {
+        return area;
+    };
+    let width = (area.width / 3).clamp(30, 60);
+    if area.width < width + 50 {
+        return area;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(width)])
+        .split(area);
+
+    let muted = Style::default().fg(app.theme.text_muted);
+    let current_hunk = app
+        .multi_diff
+        .navigator(app.multi_diff.selected_index)
+        .map(|navigator| navigator.state().current_hunk);
+    let threads = app.review_threads();
+    let mut lines = vec![Line::from(Span::styled(
+        format!("#{} {}", review.pr.number, review.pr.title),
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    if threads.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("No threads on this file", muted)));
+    }
+    for (thread, hunk) in threads {
+        lines.push(Line::from(""));
+        let on_current = hunk.is_some() && hunk == current_hunk;
+        let side = match thread.side {
+            Side::Old => "old",
+            Side::New => "new",
+        };
+        let place = match hunk {
+            Some(hunk) => format!("hunk {} · {side} line {}", hunk + 1, thread.line),
+            None => format!("{side} line {} (outside the diff)", thread.line),
+        };
+        let marker_style = if on_current {
+            Style::default().fg(app.theme.accent)
+        } else {
+            muted
+        };
+        let mut header = vec![
+            Span::styled(if on_current { "▌ " } else { "  " }, marker_style),
+            Span::styled(place, marker_style),
+        ];
+        if thread.outdated {
+            header.push(Span::styled(
+                " outdated",
+                Style::default().fg(app.theme.warning),
+            ));
+        }
+        lines.push(Line::from(header));
+        for comment in &thread.comments {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(
+                    format!("@{}", comment.author),
+                    Style::default()
+                        .fg(app.theme.info)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" {}", comment.created_at.get(..10).unwrap_or_default()),
+                    muted,
+                ),
+            ]));
+            lines.extend(comment.body.lines().map(|line| {
+                Line::from(Span::styled(
+                    format!("  {line}"),
+                    Style::default().fg(app.theme.text),
+                ))
+            }));
+        }
+    }
+
+    let mut block = Block::default()
+        .borders(Borders::LEFT)
+        .border_style(Style::default().fg(app.theme.border_subtle))
+        .padding(Padding::horizontal(1));
+    if let Some(bg) = app.theme.background_panel {
+        block = block.style(Style::default().bg(bg));
     }
-    frame.render_widget(paragraph, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        chunks[1],
+    );
+
+    chunks[0]
 }
 
 fn draw_content(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -475,7 +1193,9 @@ fn draw_content(frame: &mut Frame, app: &mut App, area: Rect) {
         && area.width < min_width_for_panel
         && !app.file_panel_manually_set;
 
-    let show_panel = if app.file_panel_manually_set {
+    let show_panel = if app.presentation {
+        false
+    } else if app.file_panel_manually_set {
         // User explicitly toggled, respect their preference
         app.is_multi_file() && app.file_panel_visible
     } else {
@@ -504,6 +1224,19 @@ fn draw_content(frame: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+/// Sparkline of where a file's changes are, one cell per slice of the file
+fn churn_thumbnail(churn: &[usize]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = churn.iter().copied().max().unwrap_or(0).max(1);
+    churn
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            _ => LEVELS[(count * LEVELS.len()).div_ceil(max).clamp(1, LEVELS.len()) - 1],
+        })
+        .collect()
+}
+
 fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
     // Split area: content on left, separator on right
     let chunks = Layout::default()
@@ -569,7 +1302,7 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
     let mut renamed = 0usize;
 
     for file in files {
-        match file.status {
+        match app.multi_diff.shown_status(file) {
             FileStatus::Added | FileStatus::Untracked => added += 1,
             FileStatus::Deleted => deleted += 1,
             FileStatus::Modified => modified += 1,
@@ -577,11 +1310,21 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
         }
     }
 
-    let via_text = if app.multi_diff.is_git_mode() {
+    let via = if app.multi_diff.is_svn() {
+        "via svn"
+    } else if app.multi_diff.is_p4() {
+        "via p4"
+    } else if app.multi_diff.is_git_mode() {
         "via git"
     } else {
         "via diff"
     };
+    let via_text = match app.file_sort {
+        crate::config::FileSortMode::Risk => format!("{via} · by risk"),
+        crate::config::FileSortMode::Commit => format!("{via} · by type"),
+        crate::config::FileSortMode::Churn => format!("{via} · by churn"),
+        _ => via.to_string(),
+    };
     let root_path = app
         .multi_diff
         .repo_root()
@@ -676,9 +1419,17 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
     while idx < filtered_indices.len() && remaining > 0 {
         let file_idx = filtered_indices[idx];
         let file = &files[file_idx];
-        let group = match file.display_name.rsplit_once('/') {
-            Some((dir, _)) => dir.to_string(),
-            None => "Root Path".to_string(),
+        let dir = file.display_name.rsplit_once('/').map(|(dir, _)| dir);
+        let group = match (&app.commit_type_groups, dir) {
+            (Some(groups), _) => {
+                let label = groups.label(&file.path);
+                match groups.commit_count(label) {
+                    1 => format!("{label} · 1 commit"),
+                    count => format!("{label} · {count} commits"),
+                }
+            }
+            (None, Some(dir)) => dir.to_string(),
+            (None, None) => "Root Path".to_string(),
         };
 
         if current_group.as_deref() != Some(&group) {
@@ -690,9 +1441,34 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
                     break;
                 }
             }
-            let header_max = list_area.width.saturating_sub(6).max(1) as usize;
+            // Directory rollup: the +/- counts and reviewed share of its files
+            let mut rollup_spans = Vec::new();
+            if app.commit_type_groups.is_none() {
+                let rollup = app.dir_rollup(dir.unwrap_or(""));
+                let rollup_style = Style::default().fg(app.theme.text_muted);
+                if !matches!(app.file_count_mode, crate::config::FileCountMode::Off)
+                    && (rollup.insertions > 0 || rollup.deletions > 0)
+                {
+                    rollup_spans.push(Span::styled(
+                        format!(" +{} -{}", rollup.insertions, rollup.deletions),
+                        rollup_style,
+                    ));
+                }
+                if app.has_reviewed_files() {
+                    let (text, style) = match rollup.reviewed_percent() {
+                        100 => (" ✓".to_string(), Style::default().fg(app.theme.success)),
+                        percent => (format!(" {percent}%"), rollup_style),
+                    };
+                    rollup_spans.push(Span::styled(text, style));
+                }
+            }
+            let rollup_len: usize = rollup_spans
+                .iter()
+                .map(|span| text_width(&span.content))
+                .sum();
+            let header_max = list_area.width.saturating_sub(6 + rollup_len as u16).max(1) as usize;
             let header_text = truncate_path(&group, header_max);
-            let header_line = Line::from(vec![
+            let mut header_spans = vec![
                 Span::raw("  "),
                 Span::styled(
                     header_text,
@@ -700,7 +1476,9 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
                         .fg(app.theme.text_muted)
                         .add_modifier(Modifier::DIM),
                 ),
-            ]);
+            ];
+            header_spans.extend(rollup_spans);
+            let header_line = Line::from(header_spans);
             items.push(ListItem::new(header_line));
             row_map.push(None);
             current_group = Some(group);
@@ -710,7 +1488,7 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
             }
         }
 
-        let status_style = match file.status {
+        let status_style = match app.multi_diff.shown_status(file) {
             FileStatus::Added | FileStatus::Untracked => Style::default().fg(app.theme.success),
             FileStatus::Deleted => Style::default().fg(app.theme.error),
             FileStatus::Modified => Style::default().fg(app.theme.warning),
@@ -751,13 +1529,27 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
             0
         };
 
-        // Truncate filename to fit (preserve extension)
-        let file_name = file
-            .display_name
-            .rsplit('/')
-            .next()
-            .unwrap_or(&file.display_name);
-        let max_name_len = list_area.width.saturating_sub(8 + signs_len as u16).max(1) as usize;
+        // Truncate filename to fit (preserve extension); type groups mix
+        // directories, so they keep the whole path
+        let file_name = if app.commit_type_groups.is_some() {
+            &file.display_name
+        } else {
+            file.display_name
+                .rsplit('/')
+                .next()
+                .unwrap_or(&file.display_name)
+        };
+        let churn = app
+            .churn_thumbnails
+            .then(|| app.file_churn(&file.path))
+            .flatten()
+            .filter(|churn| churn.iter().any(|&count| count > 0))
+            .map(churn_thumbnail);
+        let churn_len = churn.as_ref().map_or(0, |churn| 1 + churn.chars().count());
+        let max_name_len = list_area
+            .width
+            .saturating_sub(8 + (signs_len + churn_len) as u16)
+            .max(1) as usize;
         let name = truncate_filename_keep_ext(file_name, max_name_len);
 
         let mut icon_style = status_style;
@@ -780,16 +1572,43 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
         } else {
             Style::default().fg(app.theme.text_muted)
         };
-        let marker = if is_selected { "•" } else { " " };
+        // Once the memory budget dropped a diff, show which files hold
+        // theirs (·) and which will recompute it (◦)
+        let marker = if is_selected {
+            "•"
+        } else if !app.multi_diff.has_evictions() {
+            " "
+        } else if app.multi_diff.is_evicted(file_idx) {
+            "◦"
+        } else if app.multi_diff.is_loaded(file_idx) {
+            "·"
+        } else {
+            " "
+        };
 
+        let icon = if app.is_file_reviewed(&file.path) {
+            "✓"
+        } else {
+            "■"
+        };
         let mut line_spans = vec![
             Span::styled(marker, marker_style),
             Span::raw(" "),
-            Span::styled("■", icon_style),
+            Span::styled(icon, icon_style),
             Span::raw(" "),
             Span::styled(name, name_style),
         ];
 
+        if let Some(churn) = churn {
+            line_spans.push(Span::raw(" "));
+            line_spans.push(Span::styled(
+                churn,
+                Style::default()
+                    .fg(status_style.fg.unwrap_or(app.theme.text_muted))
+                    .add_modifier(Modifier::DIM),
+            ));
+        }
+
         if show_signs {
             line_spans.push(Span::raw(" "));
             let sign_style = if app.file_list_focused && is_selected {
@@ -854,27 +1673,26 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
             .background_element
             .or(app.theme.background_panel)
             .or(app.theme.background);
-        let filter_text = if app.file_filter_active {
-            if has_query {
-                format!("> {}", app.file_filter)
-            } else {
-                "> Filter file name".to_string()
-            }
+        let mut filter_spans = vec![Span::raw(" ")];
+        if app.file_filter_active {
+            filter_spans.push(Span::styled("> ", Style::default().fg(app.theme.text)));
+            filter_spans.extend(input_spans(
+                &app.file_filter,
+                "Filter file name",
+                &app.theme,
+            ));
         } else if has_query {
-            app.file_filter.clone()
-        } else {
-            "\"/\" Filter".to_string()
-        };
-        let filter_style = if app.file_filter_active {
-            Style::default().fg(app.theme.text)
+            filter_spans.push(Span::styled(
+                app.file_filter.text().to_string(),
+                Style::default().fg(app.theme.text_muted),
+            ));
         } else {
-            Style::default().fg(app.theme.text_muted)
-        };
-        let mut filter = Paragraph::new(Line::from(vec![
-            Span::raw(" "),
-            Span::styled(filter_text, filter_style),
-        ]))
-        .alignment(Alignment::Left);
+            filter_spans.push(Span::styled(
+                "\"/\" Filter",
+                Style::default().fg(app.theme.text_muted),
+            ));
+        }
+        let mut filter = Paragraph::new(Line::from(filter_spans)).alignment(Alignment::Left);
         let mut filter_block = Block::default().padding(ratatui::widgets::Padding::new(1, 1, 1, 0));
         if let Some(bg) = filter_bg {
             filter_block = filter_block.style(Style::default().bg(bg));
@@ -887,11 +1705,237 @@ fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
 }
 
 fn draw_diff_view(frame: &mut Frame, app: &mut App, area: Rect) {
+    let bars = !app.zen_mode && !app.presentation;
+    let (header, area) = if app.show_file_header && bars && area.height > 2 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+        app.last_viewport_height = chunks[1].height as usize;
+        (Some(chunks[0]), chunks[1])
+    } else {
+        (None, area)
+    };
+    let (breadcrumbs, area) = if app.show_breadcrumbs && bars && area.height > 2 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+        app.last_viewport_height = chunks[1].height as usize;
+        (Some(chunks[0]), chunks[1])
+    } else {
+        (None, area)
+    };
+    app.refresh_occurrences();
+    app.ensure_function_context();
     match app.view_mode {
         ViewMode::SinglePane => render_single_pane(frame, app, area),
         ViewMode::Split => render_split(frame, app, area),
         ViewMode::Evolution => render_evolution(frame, app, area),
     }
+    // After the view, which loads the syntax engine that names the language
+    if let Some(header) = header {
+        draw_file_header(frame, app, header);
+    }
+    if let Some(breadcrumbs) = breadcrumbs {
+        draw_breadcrumbs(frame, app, breadcrumbs);
+    }
+}
+
+/// Byte size as `812 B`, `4.2 KB` or `1.3 MB`
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// `old → new` when the versions differ, else the one value; true if they
+/// differ
+fn header_fact(old: Option<String>, new: Option<String>) -> Option<(String, bool)> {
+    match (old, new) {
+        (Some(old), Some(new)) if old != new => Some((format!("{old} → {new}"), true)),
+        (old, new) => new.or(old).map(|value| (value, false)),
+    }
+}
+
+/// Path and status of the current file on the left; language, encoding,
+/// line endings, sizes and +/- counts on the right. When the bar is too
+/// narrow, facts are dropped (sizes first, a changed encoding or line
+/// ending last), then the path is shortened from the middle.
+/// Where the view is: repo ▸ dir ▸ file ▸ hunk ▸ change, with the level
+/// `,` / `.` move along highlighted. Leading segments are dropped to fit.
+fn draw_breadcrumbs(frame: &mut Frame, app: &mut App, area: Rect) {
+    const SEPARATOR: &str = " ▸ ";
+    let mut crumbs = app.breadcrumbs();
+    let width = area.width.saturating_sub(1) as usize;
+    let crumbs_width = |crumbs: &[(Option<CrumbLevel>, String)]| -> usize {
+        crumbs
+            .iter()
+            .map(|(_, text)| text_width(text))
+            .sum::<usize>()
+            + crumbs.len().saturating_sub(1) * text_width(SEPARATOR)
+    };
+    while crumbs.len() > 1 && crumbs_width(&crumbs) > width {
+        crumbs.remove(0);
+    }
+
+    let muted = Style::default().fg(app.theme.text_muted);
+    let mut spans = vec![Span::raw(" ")];
+    for (index, (level, text)) in crumbs.into_iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::styled(SEPARATOR, muted));
+        }
+        let style = if level == Some(app.crumb_level) {
+            Style::default()
+                .fg(app.theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else if level == Some(CrumbLevel::File) {
+            Style::default().fg(app.theme.text)
+        } else {
+            muted
+        };
+        spans.push(Span::styled(truncate_text(&text, width), style));
+    }
+
+    let mut style = Style::default();
+    if let Some(bg) = app.theme.background_panel {
+        style = style.bg(bg);
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)).style(style), area);
+}
+
+fn draw_file_header(frame: &mut Frame, app: &mut App, area: Rect) {
+    /// Path width kept before the path is shortened to fit the facts
+    const MIN_PATH_WIDTH: usize = 24;
+    const SEPARATOR: &str = " · ";
+
+    let header = app.file_header();
+    let muted = Style::default().fg(app.theme.text_muted);
+    let warning = Style::default().fg(app.theme.warning);
+    let (status, status_color) = match header.status {
+        FileStatus::Added => ("added", app.theme.success),
+        FileStatus::Untracked => ("untracked", app.theme.success),
+        FileStatus::Deleted => ("deleted", app.theme.error),
+        FileStatus::Modified => ("modified", app.theme.warning),
+        FileStatus::Renamed => ("renamed", app.theme.info),
+    };
+
+    // (drop order, spans) in display order; lowest is dropped first
+    let mut facts: Vec<(u8, Vec<Span>)> = Vec::new();
+    if let Some(language) = header.language {
+        facts.push((1, vec![Span::styled(language, muted)]));
+    }
+    let (old, new) = (header.old, header.new);
+    let formats = [
+        (
+            2,
+            header_fact(
+                old.map(|(format, _)| format.encoding.to_string()),
+                new.map(|(format, _)| format.encoding.to_string()),
+            ),
+        ),
+        (
+            3,
+            header_fact(
+                old.map(|(format, _)| format.line_ending.to_string()),
+                new.map(|(format, _)| format.line_ending.to_string()),
+            ),
+        ),
+    ];
+    for (order, fact) in formats {
+        match fact {
+            Some((text, true)) => facts.push((5, vec![Span::styled(text, warning)])),
+            Some((text, false)) => facts.push((order, vec![Span::styled(text, muted)])),
+            None => {}
+        }
+    }
+    if let Some((text, _)) = header_fact(
+        old.map(|(_, size)| format_size(size)),
+        new.map(|(_, size)| format_size(size)),
+    ) {
+        facts.push((0, vec![Span::styled(text, muted)]));
+    }
+    facts.push((
+        4,
+        vec![
+            Span::styled(
+                format!("+{}", header.insertions),
+                Style::default().fg(app.theme.success),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!("-{}", header.deletions),
+                Style::default().fg(app.theme.error),
+            ),
+        ],
+    ));
+
+    let width = area.width as usize;
+    let facts_width = |facts: &[(u8, Vec<Span>)]| -> usize {
+        let spans: usize = facts
+            .iter()
+            .flat_map(|(_, spans)| spans)
+            .map(|span| text_width(&span.content))
+            .sum();
+        // A gap before the facts and a blank after them
+        spans
+            + facts.len().saturating_sub(1) * text_width(SEPARATOR)
+            + usize::from(!facts.is_empty()) * 2
+    };
+    let status_width = status.len() + 2;
+    let path_width = text_width(&header.path).min(MIN_PATH_WIDTH);
+    while !facts.is_empty() && 1 + path_width + status_width + facts_width(&facts) > width {
+        let drop = facts
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (order, _))| *order)
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        facts.remove(drop);
+    }
+    let show_status = width >= 1 + path_width + status_width;
+    let path_room = width
+        .saturating_sub(1 + facts_width(&facts))
+        .saturating_sub(if show_status { status_width } else { 0 });
+
+    let mut spans = vec![
+        Span::raw(" "),
+        Span::styled(
+            truncate_path(&header.path, path_room),
+            Style::default()
+                .fg(app.theme.text)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if show_status {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(status, Style::default().fg(status_color)));
+    }
+    let used: usize = spans.iter().map(|span| text_width(&span.content)).sum();
+    if !facts.is_empty() {
+        let fill = width.saturating_sub(used + facts_width(&facts)) + 1;
+        spans.push(Span::raw(" ".repeat(fill)));
+        for (index, (_, fact)) in facts.into_iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::styled(SEPARATOR, muted));
+            }
+            spans.extend(fact);
+        }
+    }
+
+    let mut style = Style::default();
+    if let Some(bg) = app.theme.background_panel {
+        style = style.bg(bg);
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)).style(style), area);
 }
 
 fn draw_zen_progress(frame: &mut Frame, app: &mut App) {
@@ -910,7 +1954,7 @@ fn draw_zen_progress(frame: &mut Frame, app: &mut App) {
     frame.render_widget(text, progress_area);
 }
 
-fn draw_help_popover(frame: &mut Frame, app: &mut App) {
+fn draw_help_popover(frame: &mut Frame, app: &mut App, focused: bool) {
     let area = frame.area();
 
     // Calculate popover size and position (centered)
@@ -926,12 +1970,39 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
         "b / e",
         "p / P",
         "y / Y",
+        "gy / gx",
         "/",
+        "M-r / M-c / M-w",
+        "↑ / ↓",
         "n / N",
+        "m",
+        "o",
+        "O",
+        "v",
+        "I",
         ":<line>",
         ":h<num>",
         ":s<num>",
+        ":jumps",
+        ":settings",
+        ":log",
+        ":messages",
+        ":timings",
+        "\\ <key>",
+        ":compare <a> [<b>]",
+        "C",
+        ":collapse / :expand",
+        ":scrub",
+        ":header",
+        ":breadcrumbs",
+        "U",
+        ", / .",
+        ":present",
+        "S-← / S-→",
         "< / >",
+        "%",
+        "{N}%",
+        "Alt+1..9",
         "gg / G",
         "J / K",
         "H / L",
@@ -947,12 +2018,42 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
         "+ / -",
         "a",
         "Tab",
+        "A",
+        "X",
+        "T",
+        "V",
+        "c",
         "Z",
+        "F",
         "r",
     ];
     if app.is_multi_file() {
-        help_keys.extend_from_slice(&["[ / ]", "f", "Enter", "j / k / ↑↓", "/", "r"]);
+        help_keys.extend_from_slice(&["[ / ]", "f", "R", "( / )", "Enter", "j / k / ↑↓", "/", "r"]);
+    }
+    if app.patch_position().is_some() {
+        help_keys.extend_from_slice(&["{ / }", ":rejects"]);
+    }
+    if app.reject_placement().is_some() {
+        help_keys.extend_from_slice(&[":place <line>", ":apply"]);
+    }
+    if app.multi_diff.is_git_mode() || app.split_preview_active() {
+        help_keys.extend_from_slice(&["x", ":bucket <n>", ":preview", "{ / }", ":commit"]);
     }
+    let action_keys: Vec<&str> = app
+        .actions
+        .iter()
+        .flat_map(|actions| actions.keys().keys())
+        .map(String::as_str)
+        .collect();
+    help_keys.extend(action_keys.iter().copied());
+    help_keys.push(":action <name>");
+    let plugin_keys: Vec<String> = app
+        .plugins
+        .commands()
+        .iter()
+        .map(|command| format!(":{}", command.name))
+        .collect();
+    help_keys.extend(plugin_keys.iter().map(String::as_str));
 
     let content_width = popup_width.saturating_sub(2) as usize;
     let max_key_width = help_keys
@@ -1088,13 +2189,82 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
     push_help_line(&mut lines, "b / e", "Hunk begin/end");
     push_help_line(&mut lines, "p", "Peek change (modified -> old -> mixed)");
     push_help_line(&mut lines, "P", "Peek old hunk");
+    push_help_line(&mut lines, "p / P", "Split: old / new line in a popup");
     push_help_line(&mut lines, "y / Y", "Yank line/hunk");
+    push_help_line(
+        &mut lines,
+        "gy / gx",
+        "Copy forge permalink / open in browser",
+    );
     push_help_line(&mut lines, "/", "Search (diff pane)");
-    push_help_line(&mut lines, "n / N", "Next/prev match");
+    push_help_line(
+        &mut lines,
+        "M-r / M-c / M-w",
+        "While searching: regex / case / whole word",
+    );
+    push_help_line(
+        &mut lines,
+        "↑ / ↓",
+        "In / and :, recall recent searches/commands",
+    );
+    push_help_line(
+        &mut lines,
+        "C-a / C-e / C-w",
+        "In prompts: start / end / delete word",
+    );
+    push_help_line(&mut lines, "n / N", "Next/prev match (across files)");
+    push_help_line(&mut lines, "m", "Step only through search matches");
+    push_help_line(
+        &mut lines,
+        "o",
+        "Highlight other occurrences of changed words",
+    );
+    push_help_line(&mut lines, "O", "Step only through the same rename");
+    push_help_line(
+        &mut lines,
+        "v",
+        "Only deletions / insertions / modifications",
+    );
+    push_help_line(&mut lines, "I", "Tests affected by the changes");
     push_help_line(&mut lines, ":<line>", "Go to line");
     push_help_line(&mut lines, ":h<num>", "Go to hunk");
     push_help_line(&mut lines, ":s<num>", "Go to step");
+    push_help_line(&mut lines, ":jumps", "List recent jumps");
+    push_help_line(&mut lines, ":settings", "Change and save settings");
+    push_help_line(&mut lines, ":log", "Show the debug log (--debug)");
+    push_help_line(&mut lines, ":messages", "Show past notifications");
+    push_help_line(&mut lines, ":timings", "Toggle the timings HUD");
+    push_help_line(&mut lines, "\\ <key>", "Send a key to the plugins");
+    push_help_line(
+        &mut lines,
+        ":compare <a> [<b>]",
+        "File at step a vs step b (or current)",
+    );
+    push_help_line(&mut lines, "C", "Collapse/expand hunk or function");
+    push_help_line(
+        &mut lines,
+        ":collapse / :expand",
+        "Collapse/expand all hunks",
+    );
+    push_help_line(
+        &mut lines,
+        ":scrub",
+        "Toggle step scrub bar (click to seek)",
+    );
+    push_help_line(&mut lines, ":header", "Toggle file header bar");
+    push_help_line(&mut lines, ":breadcrumbs", "Toggle breadcrumb bar");
+    push_help_line(
+        &mut lines,
+        "U",
+        "Breadcrumb level up (change/hunk/file/dir)",
+    );
+    push_help_line(&mut lines, ", / .", "Prev/next sibling at breadcrumb level");
+    push_help_line(&mut lines, ":present", "Toggle presentation mode");
+    push_help_line(&mut lines, "S-← / S-→", "Scrub one bar cell back/forward");
     push_help_line(&mut lines, "< / >", "First/last applied step");
+    push_help_line(&mut lines, "%", "Next 0/25/50/75/100% checkpoint");
+    push_help_line(&mut lines, "{N}%", "Jump N% through the steps");
+    push_help_line(&mut lines, "Alt+1..9", "Jump to hunk 1-9");
     push_help_line(&mut lines, "gg / G", "Go to start/end");
     push_help_line(&mut lines, "J / K", "Scroll up/down");
     push_help_line(&mut lines, "H / L", "Scroll left/right");
@@ -1117,11 +2287,30 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
         "+ / -",
         &format!("Speed ({}ms)", app.animation_speed),
     );
-    push_help_line(&mut lines, "a", "Toggle animation");
+    if app.reduced_motion {
+        push_help_line(&mut lines, "a", "Animation (off: reduced motion)");
+    } else {
+        push_help_line(&mut lines, "a", "Toggle animation");
+    }
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(" View", section_style)));
     push_help_line(&mut lines, "Tab", "Cycle view mode");
+    push_help_line(&mut lines, "A", "Structural diff (.env/.ini/.toml/.sql)");
+    push_help_line(&mut lines, "X", "Reverse diff (new → old)");
+    push_help_line(&mut lines, "T", "Heat: dim old unchanged lines");
+    if !app.presets.is_empty() {
+        push_help_line(&mut lines, "V", "Cycle presets");
+    }
+    if app.commit_info.is_some() {
+        push_help_line(&mut lines, "c", "Toggle commit info");
+        push_help_line(
+            &mut lines,
+            ":verdict <v>",
+            "Save review (approve/changes/comment)",
+        );
+    }
     push_help_line(&mut lines, "Z", "Zen mode");
+    push_help_line(&mut lines, "F", "FPS overlay");
     push_help_line(&mut lines, "r", "Refresh from disk");
 
     if app.is_multi_file() {
@@ -1129,10 +2318,75 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
         lines.push(Line::from(Span::styled(" Files", section_style)));
         push_help_line(&mut lines, "[ / ]", "Prev/next file");
         push_help_line(&mut lines, "f", "Toggle file panel");
+        push_help_line(&mut lines, "R", "Sort files by path/risk/type/churn");
+        push_help_line(&mut lines, "( / )", "Review hunks by group (or risk)");
         push_help_line(&mut lines, "Enter", "Focus file list");
         push_help_line(&mut lines, "j / k / ↑↓", "Move selection (focused)");
         push_help_line(&mut lines, "/", "Filter files (when focused)");
         push_help_line(&mut lines, "r", "Refresh all (when focused)");
+        push_help_line(&mut lines, "Space", "Mark file reviewed (when focused)");
+    }
+
+    if app.patch_position().is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Patches", section_style)));
+        push_help_line(&mut lines, "{ / }", "Prev/next patch");
+        push_help_line(&mut lines, ":rejects", "Show hunks that did not apply");
+    }
+
+    if app.review.is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Pull Request", section_style)));
+        push_help_line(&mut lines, ":threads", "Show/hide review threads");
+        push_help_line(&mut lines, ":reply <text>", "Reply to thread on hunk");
+    }
+
+    if app.multi_diff.has_base() || app.merge_preview_active() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Three-way", section_style)));
+        push_help_line(&mut lines, ":merged", "Merge result vs ours/theirs");
+    }
+
+    if app.reject_placement().is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Rejected Hunks", section_style)));
+        push_help_line(
+            &mut lines,
+            ":place <line>",
+            "Move the hunk to a target line",
+        );
+        push_help_line(
+            &mut lines,
+            ":apply",
+            "Write the hunk and drop it from the .rej",
+        );
+    }
+
+    if app.multi_diff.is_git_mode() || app.split_preview_active() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Split commits", section_style)));
+        push_help_line(&mut lines, "x", "Add/remove hunk in bucket");
+        push_help_line(&mut lines, ":bucket <n>", "New/switch bucket (message)");
+        push_help_line(&mut lines, ":preview", "Preview resulting commits");
+        push_help_line(&mut lines, "{ / }", "Prev/next commit (preview)");
+        push_help_line(&mut lines, ":commit", "Create one commit per bucket");
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(" Actions", section_style)));
+    if let Some(actions) = &app.actions {
+        for (key, name) in actions.keys() {
+            push_help_line(&mut lines, key, name);
+        }
+    }
+    push_help_line(&mut lines, ":action <name>", "Run a scripted action");
+
+    if !plugin_keys.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Plugins", section_style)));
+        for (key, command) in plugin_keys.iter().zip(app.plugins.commands()) {
+            push_help_line(&mut lines, key, &command.description);
+        }
     }
 
     lines.push(Line::from(""));
@@ -1148,36 +2402,22 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
     let base_height = if app.is_multi_file() { 31 } else { 26 };
     let min_height = (base_height as u16).min(area.height.saturating_sub(4));
     let needed_height = (lines.len() as u16).saturating_add(2);
-    let popup_height = needed_height
-        .max(min_height)
-        .min(area.height.saturating_sub(4));
-    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
-
-    // Clear the area behind the popup
-    frame.render_widget(Clear, popup_area);
-
-    let mut block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Help ")
-        .title_alignment(Alignment::Center)
-        .border_style(Style::default().fg(app.theme.border_active));
-    if let Some(bg) = app.theme.background_panel {
-        block = block.style(Style::default().bg(bg));
-    }
+    let window = Window::new(" Help ")
+        .size(popup_width, needed_height.max(min_height))
+        .focused(focused);
+    let popup_area = window.area(area);
+    let inner = window.render(frame, &app.theme);
 
-    let inner_height = popup_height.saturating_sub(2) as usize;
+    let inner_height = inner.height as usize;
     let max_scroll = lines.len().saturating_sub(inner_height);
     app.help_max_scroll = max_scroll;
     let scroll = app.help_scroll.min(max_scroll) as u16;
     let total_lines = max_scroll + inner_height;
     let help_block = Paragraph::new(lines)
-        .block(block)
         .alignment(Alignment::Left)
         .scroll((scroll, 0));
 
-    frame.render_widget(help_block, popup_area);
+    frame.render_widget(help_block, inner);
 
     // Render scrollbar if content overflows
     if max_scroll > 0 {
@@ -1196,44 +2436,281 @@ fn draw_help_popover(frame: &mut Frame, app: &mut App) {
     }
 }
 
-fn draw_path_popup(frame: &mut Frame, app: &App) {
-    let area = frame.area();
+fn draw_path_popup(frame: &mut Frame, app: &App, focused: bool) {
     let file_path = app.current_file_path();
 
-    // Calculate popup size based on path length
-    let popup_width = (file_path.len() as u16 + 6).min(area.width.saturating_sub(4));
-    let popup_height = 3u16;
-    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
-
-    // Clear the area behind the popup
-    frame.render_widget(Clear, popup_area);
+    // Size the popup to the path
+    let window = Window::new(" File Path ")
+        .size(text_width(&file_path) as u16 + 6, 3)
+        .focused(focused);
+    let popup_width = window.area(frame.area()).width;
 
     // Truncate path if too long for popup
     let max_path_len = (popup_width.saturating_sub(4)) as usize;
-    let display_path = if file_path.len() > max_path_len {
+    let display_path = if text_width(&file_path) > max_path_len {
         format!(
             "…{}",
-            &file_path[file_path.len().saturating_sub(max_path_len - 1)..]
+            take_width_end(&file_path, max_path_len.saturating_sub(1))
         )
     } else {
         file_path
     };
 
-    let mut block = Block::default()
-        .borders(Borders::ALL)
-        .title(" File Path ")
-        .title_alignment(Alignment::Center)
-        .border_style(Style::default().fg(app.theme.border_active));
-    if let Some(bg) = app.theme.background_panel {
-        block = block.style(Style::default().bg(bg));
+    let inner = window.render(frame, &app.theme);
+    frame.render_widget(
+        Paragraph::new(display_path).alignment(Alignment::Center),
+        inner,
+    );
+}
+
+fn draw_test_impact(frame: &mut Frame, app: &App, focused: bool) {
+    let muted = Style::default().fg(app.theme.text_muted);
+    let text = Style::default().fg(app.theme.text);
+    let mut lines: Vec<Line> = Vec::new();
+    if app.test_hints().is_empty() {
+        let message = if app.test_rules.is_empty() {
+            "No [[tests.rules]] configured"
+        } else {
+            "No tests mapped to the changed files"
+        };
+        lines.push(Line::from(Span::styled(message, muted)));
+    }
+    for hint in app.test_hints() {
+        lines.push(Line::from(Span::styled(hint.source.clone(), muted)));
+        for test in &hint.tests {
+            lines.push(Line::from(Span::styled(format!("  {test}"), text)));
+        }
+        if let Some(command) = &hint.command {
+            lines.push(Line::from(Span::styled(
+                format!("  $ {command}"),
+                Style::default().fg(app.theme.accent),
+            )));
+        }
+    }
+    if !app.test_runs().is_empty() || app.test_runs_pending() > 0 {
+        lines.push(Line::from(""));
+    }
+    for run in app.test_runs() {
+        let (mark, color) = if run.success {
+            ("✓", app.theme.success)
+        } else {
+            ("✗", app.theme.error)
+        };
+        let mut spans = vec![
+            Span::styled(format!("{mark} "), Style::default().fg(color)),
+            Span::styled(run.command.clone(), text),
+        ];
+        if !run.summary.is_empty() {
+            spans.push(Span::styled(format!("  {}", run.summary), muted));
+        }
+        lines.push(Line::from(spans));
+    }
+    if app.test_runs_pending() > 0 {
+        lines.push(Line::from(Span::styled(
+            format!("running {} more…", app.test_runs_pending()),
+            muted,
+        )));
     }
 
-    let path_block = Paragraph::new(display_path)
-        .block(block)
-        .style(Style::default().fg(app.theme.text))
-        .alignment(Alignment::Center);
+    let inner = Window::new(" Test Impact (Enter: run) ")
+        .size(80, lines.len().max(1) as u16 + 2)
+        .focused(focused)
+        .render(frame, &app.theme);
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Hunks of the current patch that did not apply, as unified diff text
+fn draw_patch_rejects(frame: &mut Frame, app: &App, focused: bool) {
+    let muted = Style::default().fg(app.theme.text_muted);
+    let text = Style::default().fg(app.theme.text);
+    let rejects = app.patch_rejects();
+    let mut lines: Vec<Line> = Vec::new();
+    if rejects.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Every hunk of this patch applied",
+            muted,
+        )));
+    }
+    for (path, idx, hunk) in rejects {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            format!("{} · hunk {}", path.display(), idx + 1),
+            muted,
+        )));
+        lines.push(Line::from(Span::styled(
+            hunk.header.clone(),
+            Style::default().fg(app.theme.accent),
+        )));
+        for line in &hunk.lines {
+            let span = match line {
+                PatchLine::Context(line) => Span::styled(format!(" {line}"), text),
+                PatchLine::Delete(line) => Span::styled(
+                    format!("-{line}"),
+                    Style::default().fg(app.theme.delete_base()),
+                ),
+                PatchLine::Insert(line) => Span::styled(
+                    format!("+{line}"),
+                    Style::default().fg(app.theme.insert_base()),
+                ),
+            };
+            lines.push(Line::from(app.redact_spans(vec![span])));
+        }
+    }
+
+    let inner = Window::new(" Rejected Hunks ")
+        .size(80, lines.len().max(1) as u16 + 2)
+        .focused(focused)
+        .render(frame, &app.theme);
+    let max_scroll = lines.len().saturating_sub(inner.height as usize);
+    let scroll = app.patch_rejects_scroll.min(max_scroll);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), inner);
+}
+
+fn draw_jump_list(frame: &mut Frame, app: &App, focused: bool) {
+    let jumps = app.jumps();
+    let window = Window::new(" Jumps ")
+        .size(60, jumps.len().max(1) as u16 + 2)
+        .focused(focused);
+    let popup_area = window.area(frame.area());
+
+    let visible = popup_area.height.saturating_sub(2) as usize;
+    let selected = app.jump_list_index();
+    let offset = (selected + 1).saturating_sub(visible);
+    let content_width = popup_area.width.saturating_sub(4) as usize;
+    let lines: Vec<Line> = if jumps.is_empty() {
+        vec![Line::from(Span::styled(
+            "No jumps yet",
+            Style::default().fg(app.theme.text_muted),
+        ))]
+    } else {
+        jumps
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(index, jump)| {
+                let query = format!(":{}", jump.query);
+                let file_width = content_width.saturating_sub(text_width(&query) + 2);
+                let file = if text_width(&jump.file) > file_width {
+                    format!(
+                        "…{}",
+                        take_width_end(&jump.file, file_width.saturating_sub(1))
+                    )
+                } else {
+                    jump.file.clone()
+                };
+                let style = if index == selected {
+                    Style::default().fg(app.theme.accent)
+                } else {
+                    Style::default().fg(app.theme.text)
+                };
+                Line::from(vec![
+                    Span::styled(format!("{query}  "), style),
+                    Span::styled(file, Style::default().fg(app.theme.text_muted)),
+                ])
+            })
+            .collect()
+    };
+
+    let inner = window.render(frame, &app.theme);
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_settings(frame: &mut Frame, app: &App, focused: bool) {
+    let selected = app.settings_index();
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    let mut category = "";
+    for (index, setting) in SETTINGS.iter().enumerate() {
+        if setting.category != category {
+            category = setting.category;
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(Span::styled(
+                category,
+                Style::default()
+                    .fg(app.theme.text_muted)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        if index == selected {
+            selected_line = lines.len();
+        }
+        let (marker, style) = if index == selected {
+            ("▶ ", Style::default().fg(app.theme.accent))
+        } else {
+            ("  ", Style::default().fg(app.theme.text))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{marker}{:<30}", setting.label), style),
+            Span::styled(setting.value(app), Style::default().fg(app.theme.accent)),
+        ]));
+    }
 
-    frame.render_widget(path_block, popup_area);
+    let inner = Window::new(" Settings ")
+        .size(50, lines.len() as u16 + 2)
+        .footer(" h/l change · saved to config ")
+        .focused(focused)
+        .render(frame, &app.theme);
+
+    let visible = inner.height as usize;
+    // Keep the first category heading in view at the top
+    let offset = if selected == 0 {
+        0
+    } else {
+        (selected_line + 1).saturating_sub(visible)
+    };
+    frame.render_widget(Paragraph::new(lines).scroll((offset as u16, 0)), inner);
+}
+
+/// Whether a log line closes a span that took 100ms or more
+fn is_slow_log_line(line: &str) -> bool {
+    let Some((_, rest)) = line.split_once("time.busy=") else {
+        return false;
+    };
+    let value = rest.split_whitespace().next().unwrap_or_default();
+    match value.strip_suffix("ms") {
+        Some(ms) => ms.parse::<f64>().is_ok_and(|ms| ms >= 100.0),
+        None => value.ends_with('s') && !value.ends_with("µs") && !value.ends_with("ns"),
+    }
+}
+
+fn draw_log(frame: &mut Frame, app: &mut App, focused: bool) {
+    let area = frame.area();
+    let title = match crate::logging::path() {
+        Some(path) => format!(" Log: {} ", path.display()),
+        None => " Log ".to_string(),
+    };
+    let inner = Window::new(title)
+        .size(
+            area.width.saturating_sub(8).max(20),
+            area.height.saturating_sub(6).max(5),
+        )
+        .padding(Padding::horizontal(1))
+        .focused(focused)
+        .render(frame, &app.theme);
+
+    let log = crate::logging::recent();
+    let visible = inner.height as usize;
+    let max_scroll = log.len().saturating_sub(visible);
+    // Clamp so scrolling back down responds at once
+    app.scroll_log(false, app.log_scroll().saturating_sub(max_scroll));
+    let end = log.len() - app.log_scroll();
+    let start = end.saturating_sub(visible);
+    let content_width = inner.width as usize;
+    let lines: Vec<Line> = log[start..end]
+        .iter()
+        .map(|line| {
+            let style = if is_slow_log_line(line) {
+                Style::default().fg(app.theme.warning)
+            } else {
+                Style::default().fg(app.theme.text)
+            };
+            Line::from(Span::styled(truncate_to_width(line, content_width), style))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
 }
@@ -0,0 +1,573 @@
+//! Config validation (`oy config check`, and warnings when the config is
+//! loaded): unknown keys, bad enum values and invalid colors, each located
+//! by line and key with a suggested fix
+//!
+//! Unknown keys are found by deserializing through [`ValueDeserializer`],
+//! which records every key the config structs ignore, so the check always
+//! follows the structs instead of a separate schema.
+
+use crate::color;
+use crate::config::{self, Config};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::fmt;
+
+/// One problem found in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 1-based line of the offending key, if it could be located
+    pub line: Option<usize>,
+    /// Dotted path of the key, e.g. `ui.diff.bg` (empty for syntax errors)
+    pub key: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{line}: ")?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{suggestion}`?)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every problem in the config file `text`, in file order
+pub fn check(text: &str) -> Vec<Problem> {
+    let table: toml::Table = match text.parse() {
+        Ok(table) => table,
+        Err(err) => {
+            return vec![Problem {
+                line: err.span().map(|span| line_of(text, span.start)),
+                key: String::new(),
+                message: err.message().to_string(),
+                suggestion: None,
+            }]
+        }
+    };
+    let root = toml::Value::Table(table);
+
+    let mut problems = Vec::new();
+    let unknown = RefCell::new(Vec::new());
+    let de = ValueDeserializer {
+        value: &root,
+        path: String::new(),
+        fields: &[],
+        unknown: &unknown,
+    };
+    match Config::deserialize(de) {
+        Ok(_) => check_values(&root, &mut problems),
+        Err(err) => problems.push(Problem {
+            line: None,
+            key: err.path.unwrap_or_default(),
+            message: err.message,
+            suggestion: err.suggestion,
+        }),
+    }
+    problems.extend(unknown.into_inner());
+
+    let doc = toml_edit::ImDocument::parse(text).ok();
+    for problem in &mut problems {
+        if problem.line.is_none() {
+            problem.line = doc
+                .as_ref()
+                .and_then(|doc| key_offset(doc.as_item(), &problem.key))
+                .map(|offset| line_of(text, offset));
+        }
+    }
+    problems.sort_by_key(|problem| problem.line);
+    problems
+}
+
+/// Values the structs accept as plain strings but only understand in part:
+/// view modes, theme names and modes, and colors
+fn check_values(root: &toml::Value, problems: &mut Vec<Problem>) {
+    let get = |path: &[&str]| {
+        path.iter()
+            .try_fold(root, |value, key| value.get(key))
+            .and_then(|value| value.as_str())
+    };
+    fn check_choice(
+        problems: &mut Vec<Problem>,
+        key: String,
+        value: &str,
+        choices: &[&str],
+        what: &str,
+    ) {
+        if !choices.contains(&value) {
+            problems.push(Problem {
+                line: None,
+                key,
+                message: format!(
+                    "unknown {what} `{value}`, expected one of {}",
+                    list(choices)
+                ),
+                suggestion: closest(value, choices).map(str::to_string),
+            });
+        }
+    }
+
+    const VIEW_MODES: &[&str] = &["single", "split", "evolution", "sbs", "evo"];
+    if let Some(mode) = get(&["ui", "view_mode"]) {
+        check_choice(
+            problems,
+            "ui.view_mode".to_string(),
+            mode,
+            VIEW_MODES,
+            "view mode",
+        );
+    }
+    for (name, preset) in root
+        .get("presets")
+        .and_then(|presets| presets.as_table())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(mode) = preset.get("view_mode").and_then(|mode| mode.as_str()) {
+            check_choice(
+                problems,
+                format!("presets.{name}.view_mode"),
+                mode,
+                VIEW_MODES,
+                "view mode",
+            );
+        }
+    }
+    if let Some(name) = get(&["ui", "theme", "name"]) {
+        let themes = config::builtin_theme_names();
+        check_choice(
+            problems,
+            "ui.theme.name".to_string(),
+            &name.to_ascii_lowercase(),
+            &themes,
+            "theme",
+        );
+    }
+    if let Some(mode) = get(&["ui", "theme", "mode"]) {
+        check_choice(
+            problems,
+            "ui.theme.mode".to_string(),
+            &mode.to_ascii_lowercase(),
+            &["dark", "light"],
+            "theme mode",
+        );
+    }
+
+    let theme = root.get("ui").and_then(|ui| ui.get("theme"));
+    let defs: std::collections::HashMap<String, String> = theme
+        .and_then(|theme| theme.get("defs"))
+        .and_then(|defs| defs.as_table())
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+        .collect();
+    for (name, hex) in &defs {
+        if let Err(err) = color::parse_hex(hex) {
+            problems.push(Problem {
+                line: None,
+                key: format!("ui.theme.defs.{name}"),
+                message: format!("invalid color `{hex}`: {err}"),
+                suggestion: None,
+            });
+        }
+    }
+    let tokens = theme
+        .and_then(|theme| theme.get("theme"))
+        .and_then(|tokens| tokens.as_table());
+    for (token, sides) in tokens.into_iter().flatten() {
+        for side in ["dark", "light"] {
+            let Some(value) = sides.get(side).and_then(|value| value.as_str()) else {
+                continue;
+            };
+            let transparent = matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "transparent" | "none"
+            );
+            if !transparent && color::resolve_color(value, &defs).is_none() {
+                let names: Vec<&str> = defs.keys().map(String::as_str).collect();
+                problems.push(Problem {
+                    line: None,
+                    key: format!("ui.theme.theme.{token}.{side}"),
+                    message: format!(
+                        "invalid color `{value}`: expected `#rrggbb`, an ANSI color name, or a name from ui.theme.defs"
+                    ),
+                    suggestion: closest(value, &names).map(str::to_string),
+                });
+            }
+        }
+    }
+}
+
+/// `a`, `b`, or `c` as "`a`, `b`, `c`"
+fn list(choices: &[&str]) -> String {
+    choices
+        .iter()
+        .map(|choice| format!("`{choice}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The choice closest to `value`, if it is a plausible typo of it
+fn closest<'a>(value: &str, choices: &[&'a str]) -> Option<&'a str> {
+    let value = value.to_ascii_lowercase();
+    choices
+        .iter()
+        .map(|&choice| (edit_distance(&value, &choice.to_ascii_lowercase()), choice))
+        .filter(|&(distance, choice)| distance > 0 && distance <= (choice.len() / 3).max(1))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, choice)| choice)
+}
+
+/// Edit distance in characters, counting a swap of neighbours as one edit
+/// (`spilt` is one edit from `split`)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// 1-based line of byte `offset`
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Byte offset of the key at dotted `path` (`tests.rules[0].path`), or of
+/// its closest ancestor that could be found
+fn key_offset(root: &toml_edit::Item, path: &str) -> Option<usize> {
+    let mut table = root.as_table_like()?;
+    let mut offset = None;
+    for segment in path.split('.') {
+        let (key, index) = match segment.split_once('[') {
+            Some((key, index)) => (key, index.trim_end_matches(']').parse::<usize>().ok()),
+            None => (segment, None),
+        };
+        let Some((key, item)) = table.get_key_value(key) else {
+            break;
+        };
+        offset = key.span().map(|span| span.start).or(offset);
+        let next = match (item, index) {
+            (toml_edit::Item::ArrayOfTables(tables), Some(index)) => tables
+                .get(index)
+                .map(|table| table as &dyn toml_edit::TableLike),
+            (item, Some(index)) => item
+                .as_array()
+                .and_then(|items| items.get(index))
+                .and_then(|item| item.as_inline_table())
+                .map(|table| table as &dyn toml_edit::TableLike),
+            (item, None) => item.as_table_like(),
+        };
+        match next {
+            Some(next) => table = next,
+            None => break,
+        }
+    }
+    offset
+}
+
+/// A key the config structs ignored
+fn unknown_key(path: &str, fields: &[&'static str]) -> Problem {
+    let name = path.rsplit('.').next().unwrap_or(path);
+    Problem {
+        line: None,
+        key: path.to_string(),
+        message: "unknown key".to_string(),
+        suggestion: closest(name, fields).map(str::to_string),
+    }
+}
+
+#[derive(Debug)]
+struct Error {
+    path: Option<String>,
+    message: String,
+    suggestion: Option<String>,
+}
+
+impl Error {
+    /// Attach the path of the innermost failing value
+    fn at(mut self, path: &str) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            path: None,
+            message: msg.to_string(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Deserializer over a parsed TOML value that records ignored keys
+struct ValueDeserializer<'de, 'u> {
+    value: &'de toml::Value,
+    path: String,
+    /// Fields of the struct holding this value, to suggest for unknown keys
+    fields: &'static [&'static str],
+    unknown: &'u RefCell<Vec<Problem>>,
+}
+
+impl<'de, 'u> ValueDeserializer<'de, 'u> {
+    fn child(
+        &self,
+        value: &'de toml::Value,
+        path: String,
+        fields: &'static [&'static str],
+    ) -> Self {
+        Self {
+            value,
+            path,
+            fields,
+            unknown: self.unknown,
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_table(&[], visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_table(fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            toml::Value::String(name) => visitor
+                .visit_enum(name.as_str().into_deserializer())
+                .map_err(|mut err: Error| {
+                    err.suggestion = closest(name, variants).map(str::to_string);
+                    err
+                }),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.unknown
+            .borrow_mut()
+            .push(unknown_key(&self.path, self.fields));
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+    }
+}
+
+impl<'de> ValueDeserializer<'de, '_> {
+    /// Visit the value; a table's keys are checked against `fields`
+    fn deserialize_table<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            toml::Value::String(s) => visitor.visit_borrowed_str(s),
+            toml::Value::Integer(n) => visitor.visit_i64(*n),
+            toml::Value::Float(n) => visitor.visit_f64(*n),
+            toml::Value::Boolean(b) => visitor.visit_bool(*b),
+            toml::Value::Datetime(d) => visitor.visit_string(d.to_string()),
+            toml::Value::Array(items) => visitor.visit_seq(SeqDeserializer {
+                items: items.iter().enumerate(),
+                parent: self,
+            }),
+            toml::Value::Table(table) => visitor.visit_map(MapDeserializer {
+                entries: table.iter(),
+                pending: None,
+                fields,
+                parent: self,
+            }),
+        }
+    }
+}
+
+struct SeqDeserializer<'de, 'u> {
+    items: std::iter::Enumerate<std::slice::Iter<'de, toml::Value>>,
+    parent: ValueDeserializer<'de, 'u>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some((index, value)) = self.items.next() else {
+            return Ok(None);
+        };
+        let path = format!("{}[{index}]", self.parent.path);
+        seed.deserialize(self.parent.child(value, path.clone(), &[]))
+            .map(Some)
+            .map_err(|err| err.at(&path))
+    }
+}
+
+struct MapDeserializer<'de, 'u> {
+    entries: toml::map::Iter<'de>,
+    pending: Option<(&'de String, &'de toml::Value)>,
+    fields: &'static [&'static str],
+    parent: ValueDeserializer<'de, 'u>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.pending = Some((key, value));
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (key, value) = self
+            .pending
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        let path = if self.parent.path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{key}", self.parent.path)
+        };
+        seed.deserialize(self.parent.child(value, path.clone(), self.fields))
+            .map_err(|err| err.at(&path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_unknown_keys_and_bad_values() {
+        let text = "[ui]\n\
+                    zenn = true\n\
+                    view_mode = \"spilt\"\n\
+                    \n\
+                    [ui.theme.defs]\n\
+                    red = \"#ff00zz\"\n\
+                    \n\
+                    [ui.theme.theme.diffAdded]\n\
+                    dark = \"gren\"\n";
+        let problems = check(text);
+        let shown: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert_eq!(shown.len(), 4, "{shown:#?}");
+        assert_eq!(shown[0], "2: ui.zenn: unknown key (did you mean `zen`?)");
+        assert!(shown[1].starts_with("3: ui.view_mode: unknown view mode `spilt`"));
+        assert!(shown[1].ends_with("(did you mean `split`?)"));
+        assert!(shown[2].starts_with("6: ui.theme.defs.red: invalid color"));
+        assert!(shown[3].starts_with("9: ui.theme.theme.diffAdded.dark: invalid color `gren`"));
+    }
+
+    #[test]
+    fn test_check_locates_enum_and_syntax_errors() {
+        let problems = check("[playback]\nspeed = 100\neasing = \"ease_inout\"\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(3));
+        assert_eq!(problems[0].key, "playback.easing");
+        assert_eq!(problems[0].suggestion.as_deref(), Some("ease_in_out"));
+
+        let problems = check("[ui]\nzen = \n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(2));
+
+        assert_eq!(
+            check("[ui]\nzen = true\n[presets.demo]\nspeed = 600\n"),
+            Vec::new()
+        );
+        assert_eq!(check(crate::config::DEFAULT_CONFIG), Vec::new());
+    }
+
+    #[test]
+    fn test_check_rejects_histogram_algorithm() {
+        let problems = check("[presets.review]\nalgorithm = \"histogram\"\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].key, "presets.review.algorithm");
+        assert!(problems[0].message.contains("provider = \"imara\""));
+        let problems = check("[presets.review]\nprovider = \"imara\"\n");
+        if cfg!(feature = "imara") {
+            assert!(problems.is_empty());
+        } else {
+            assert_eq!(problems.len(), 1);
+            assert!(problems[0].message.contains("--features imara"));
+        }
+
+        let problems = check("[[forge.remotes]]\nurl = 'git\\.corp('\nkind = \"gitea\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.starts_with("invalid regex"));
+    }
+}
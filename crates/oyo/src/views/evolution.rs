@@ -6,6 +6,7 @@ use super::{
     spans_width, truncate_text, wrap_count_for_spans, wrap_count_for_text, TAB_WIDTH,
 };
 use crate::app::{AnimationPhase, App};
+use crate::bidi::reorder_spans;
 use crate::syntax::SyntaxSide;
 use oyo_core::{LineKind, StepDirection, ViewLine, ViewSpanKind};
 use ratatui::{
@@ -22,7 +23,8 @@ const GUTTER_WIDTH: u16 = 8; // "▶1234   " (matches single-pane width)
 /// Render the evolution view - file morphing without deletion markers
 pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
     let visible_height = area.height as usize;
-    let visible_width = area.width.saturating_sub(GUTTER_WIDTH) as usize;
+    let gutter_width = super::gutter_width(app, GUTTER_WIDTH);
+    let visible_width = area.width.saturating_sub(gutter_width) as usize;
     if !app.line_wrap {
         app.clamp_horizontal_scroll_cached(visible_width);
     }
@@ -77,7 +79,7 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
     // Split area into gutter (fixed) and content (scrollable)
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(GUTTER_WIDTH), Constraint::Min(0)])
+        .constraints([Constraint::Length(gutter_width), Constraint::Min(0)])
         .split(area);
 
     let gutter_area = chunks[0];
@@ -141,11 +143,13 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
 
     let query = app.search_query().trim().to_ascii_lowercase();
     let has_query = !query.is_empty();
+    let mut prev_origin = None;
     for (raw_idx, view_line) in view_lines.iter().enumerate() {
         // Skip lines that are deleted or pending delete (they disappear in evolution view)
         if !is_visible(view_line) {
             continue;
         }
+        let origin_label = super::origin_label_span(app, view_line, &mut prev_origin);
 
         if app.line_wrap {
             let display_idx = display_len;
@@ -174,7 +178,9 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
 
         // In evolution mode, use subtle line number coloring based on type
         let line_num_style = match view_line.kind {
-            LineKind::Context => Style::default().fg(app.theme.diff_line_number),
+            LineKind::Context | LineKind::Collapsed => {
+                Style::default().fg(app.theme.diff_line_number)
+            }
             LineKind::Inserted | LineKind::PendingInsert => {
                 // Use insert gradient base color for line numbers
                 let rgb = crate::color::gradient_color(&app.theme.insert, 0.5);
@@ -227,6 +233,16 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
             (" ", Style::default())
         };
 
+        // Three-way mode: line numbers show which side introduced the change
+        let line_num_style = match app.origin_color(view_line) {
+            Some(color) => line_num_style.fg(color),
+            None => line_num_style,
+        };
+        let line_num_style = match super::diagnostic_color(app, view_line) {
+            Some(color) => line_num_style.fg(color),
+            None => line_num_style,
+        };
+
         // Build gutter line (fixed, no horizontal scroll)
         // Matches single-pane: marker(1) + line_num(4) + space(1) + blank_sign(1) + space(1) = 8
         let gutter_spans = vec![
@@ -243,6 +259,7 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
         let mut content_spans: Vec<Span<'static>> = Vec::new();
         let mut used_syntax = false;
         let allow_syntax = app.syntax_enabled()
+            && view_line.kind != LineKind::Collapsed
             && match app.evo_syntax {
                 crate::config::EvoSyntaxMode::Context => !view_line.has_changes,
                 crate::config::EvoSyntaxMode::Full => !view_line.is_active_change,
@@ -254,7 +271,7 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
                 | LineKind::Modified
                 | LineKind::PendingInsert
                 | LineKind::PendingModify => false,
-                LineKind::Context => view_line.has_changes,
+                LineKind::Context | LineKind::Collapsed => view_line.has_changes,
             };
             let side = if use_old {
                 SyntaxSide::Old
@@ -279,8 +296,9 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
                     view_line.is_active,
                     app,
                 );
+                let style = super::pattern_style(app, view_span.kind, view_line.kind, style);
                 // For deleted spans, don't strikethrough leading whitespace
-                if app.strikethrough_deletions
+                if (app.strikethrough_deletions || app.kind_patterns)
                     && matches!(
                         view_span.kind,
                         ViewSpanKind::Deleted | ViewSpanKind::PendingDelete
@@ -295,13 +313,17 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
                             .push(Span::styled(text[..leading_ws_len].to_string(), ws_style));
                         content_spans.push(Span::styled(trimmed.to_string(), style));
                     } else {
-                        content_spans.push(Span::styled(view_span.text.clone(), style));
+                        content_spans.push(Span::styled(view_span.text.to_string(), style));
                     }
                 } else {
-                    content_spans.push(Span::styled(view_span.text.clone(), style));
+                    content_spans.push(Span::styled(view_span.text.to_string(), style));
                 }
             }
         }
+        content_spans = super::apply_heat(app, view_line, content_spans);
+        content_spans = super::mark_trailing_whitespace(app, view_line, content_spans);
+        content_spans = super::mute_filtered(app, view_line, content_spans);
+        content_spans = app.redact_spans(content_spans);
 
         // Evolution view ignores diff background modes to keep the morph view clean.
 
@@ -310,8 +332,15 @@ pub fn render_evolution(frame: &mut Frame, app: &mut App, area: Rect) {
             && has_query
             && line_text.to_ascii_lowercase().contains(&query);
         content_spans = app.highlight_search_spans(content_spans, &line_text, is_active_match);
+        content_spans = app.highlight_occurrence_spans(content_spans, &line_text, None);
 
+        content_spans.extend(origin_label);
+        content_spans.extend(super::eof_newline_span(app, view_line, None));
+        content_spans.extend(super::diagnostic_span(app, view_line));
         content_spans = expand_tabs_in_spans(&content_spans, TAB_WIDTH);
+        if app.bidi {
+            content_spans = reorder_spans(content_spans);
+        }
 
         // Track max line width
         let line_width = spans_width(&content_spans);
@@ -481,6 +510,9 @@ fn get_evolution_span_style(
     app: &App,
 ) -> Style {
     let theme = &app.theme;
+    if line_kind == LineKind::Collapsed {
+        return super::collapsed_style(theme);
+    }
     // Check if this is a modification line - use modify gradient instead of insert
     let is_modification = matches!(line_kind, LineKind::Modified | LineKind::PendingModify);
     let added_bg = None;
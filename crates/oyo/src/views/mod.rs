@@ -13,7 +13,7 @@ mod tests;
 
 use std::collections::VecDeque;
 
-use oyo_core::LineKind;
+use oyo_core::{ChangeOrigin, LineKind, ViewLine, ViewSpanKind};
 use ratatui::text::Span;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
@@ -37,6 +37,154 @@ pub(crate) fn pending_tail_text(count: usize) -> String {
     format!("... +{} more", count)
 }
 
+/// Width of a line number gutter: none in presentation mode
+pub(crate) fn gutter_width(app: &App, width: u16) -> u16 {
+    if app.presentation {
+        0
+    } else {
+        width
+    }
+}
+
+/// How far the oldest unchanged lines fade toward the muted text color
+const HEAT_FADE: f32 = 0.75;
+
+/// Dim an unchanged line by the age of its last change (heat mode)
+pub(crate) fn apply_heat(
+    app: &mut App,
+    view_line: &ViewLine,
+    spans: Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    if view_line.kind != LineKind::Context || view_line.has_changes {
+        return spans;
+    }
+    let Some(age) = view_line.old_line.and_then(|line| app.line_age(line)) else {
+        return spans;
+    };
+    let muted = app.theme.text_muted;
+    spans
+        .into_iter()
+        .map(|span| {
+            let fg = span.style.fg.unwrap_or(app.theme.diff_context);
+            let style = match (fg, muted) {
+                (Color::Rgb(..), Color::Rgb(..)) => {
+                    span.style
+                        .fg(color::lerp_rgb_color(fg, muted, age * HEAT_FADE))
+                }
+                _ if age >= 0.5 => span.style.add_modifier(Modifier::DIM),
+                _ => span.style,
+            };
+            Span::styled(span.content, style)
+        })
+        .collect()
+}
+
+/// Dim a changed line hidden by the kind filter to plain muted text
+pub(crate) fn mute_filtered(
+    app: &mut App,
+    view_line: &ViewLine,
+    spans: Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    if !app.filtered_out(view_line) {
+        return spans;
+    }
+    let style = Style::default().fg(app.theme.text_muted);
+    spans
+        .into_iter()
+        .map(|span| Span::styled(span.content, style))
+        .collect()
+}
+
+/// Show the trailing whitespace of a whitespace-only change as visible
+/// glyphs (`·` for spaces, `→` for tabs)
+pub(crate) fn mark_trailing_whitespace(
+    app: &mut App,
+    view_line: &ViewLine,
+    mut spans: Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    if view_line.kind == LineKind::Collapsed
+        || !app
+            .multi_diff
+            .current_navigator()
+            .diff()
+            .whitespace_changes
+            .contains(&view_line.change_id)
+    {
+        return spans;
+    }
+    for span in spans.iter_mut().rev() {
+        let text = span.content.as_ref();
+        let body = text.trim_end_matches([' ', '\t']);
+        if body.len() == text.len() {
+            break;
+        }
+        let marks: String = text[body.len()..]
+            .chars()
+            .map(|ch| if ch == '\t' { '→' } else { '·' })
+            .collect();
+        let done = !body.is_empty();
+        *span = Span::styled(format!("{body}{marks}"), span.style);
+        if done {
+            break;
+        }
+    }
+    spans
+}
+
+/// Virtual text after the last line when only one version ends with a
+/// newline. `side` is the pane of a split view (the marker goes on the side
+/// lacking the newline); unified views say which way it changed.
+pub(crate) fn eof_newline_span(
+    app: &mut App,
+    view_line: &ViewLine,
+    side: Option<SyntaxSide>,
+) -> Option<Span<'static>> {
+    let eof = app.multi_diff.current_navigator().diff().eof_newline?;
+    if eof.change_id != view_line.change_id || view_line.kind == LineKind::Collapsed {
+        return None;
+    }
+    let text = match (side, eof.added) {
+        (None, true) => "newline added at end of file",
+        (None, false) | (Some(SyntaxSide::New), false) | (Some(SyntaxSide::Old), true) => {
+            "no newline at end of file"
+        }
+        _ => return None,
+    };
+    Some(Span::styled(
+        format!("  ⏎ {text}"),
+        Style::default()
+            .fg(app.theme.warning)
+            .add_modifier(Modifier::ITALIC),
+    ))
+}
+
+/// Add the text attribute of a change kind to `style` (`ui.patterns`), so
+/// kinds stay distinguishable without color: inserts underlined, deletions
+/// struck through, modifications italic
+pub(crate) fn pattern_style(
+    app: &App,
+    kind: ViewSpanKind,
+    line_kind: LineKind,
+    style: Style,
+) -> Style {
+    if !app.kind_patterns || kind == ViewSpanKind::Equal {
+        return style;
+    }
+    let modifier = match (line_kind, kind) {
+        (LineKind::Modified | LineKind::PendingModify, _) => Modifier::ITALIC,
+        (_, ViewSpanKind::Inserted | ViewSpanKind::PendingInsert) => Modifier::UNDERLINED,
+        _ => Modifier::CROSSED_OUT,
+    };
+    style.add_modifier(modifier)
+}
+
+/// Style of the summary row of a collapsed hunk
+pub(crate) fn collapsed_style(theme: &ResolvedTheme) -> Style {
+    Style::default()
+        .fg(theme.text_muted)
+        .add_modifier(Modifier::ITALIC)
+}
+
 pub(crate) fn diff_line_bg(kind: LineKind, theme: &ResolvedTheme) -> Option<Color> {
     match kind {
         LineKind::Inserted | LineKind::PendingInsert => theme.diff_added_bg,
@@ -179,6 +327,11 @@ pub(crate) fn expand_tabs_in_text(text: &str, tab_width: usize) -> String {
     out
 }
 
+/// Window spans to the visible column range `[start_col, start_col + width)`.
+///
+/// Spans entirely outside the window are skipped by width alone, and spans
+/// entirely inside are reused as-is, so only the (at most two) spans crossing
+/// the window edges are split. Very long lines only pay for what is visible.
 pub(crate) fn slice_spans(
     spans: &[Span<'static>],
     start_col: usize,
@@ -187,20 +340,43 @@ pub(crate) fn slice_spans(
     if width == 0 {
         return Vec::new();
     }
-    let line_width = spans_width(spans);
-    if start_col >= line_width {
-        return Vec::new();
-    }
     let end_col = start_col.saturating_add(width);
     let mut out = Vec::new();
     let mut col = 0usize;
 
     for span in spans {
-        if span.content.is_empty() {
+        if col >= end_col {
+            break;
+        }
+        let content = span.content.as_ref();
+        if content.is_empty() {
             continue;
         }
+
+        if !content.contains('\n') {
+            let span_width = UnicodeWidthStr::width(content);
+            let span_end = col.saturating_add(span_width);
+            if span_end <= start_col {
+                col = span_end;
+                continue;
+            }
+            if col >= start_col && span_end <= end_col {
+                out.push(span.clone());
+                col = span_end;
+                continue;
+            }
+            // Single-width ASCII: columns are byte offsets
+            if content.is_ascii() && span_width == content.len() {
+                let from = start_col.saturating_sub(col);
+                let to = (end_col - col).min(content.len());
+                out.push(Span::styled(content[from..to].to_string(), span.style));
+                col += to;
+                continue;
+            }
+        }
+
         let mut buf = String::new();
-        for g in span.content.as_ref().graphemes(true) {
+        for g in content.graphemes(true) {
             if g == "\n" {
                 col = 0;
                 continue;
@@ -223,9 +399,6 @@ pub(crate) fn slice_spans(
         if !buf.is_empty() {
             out.push(Span::styled(buf, span.style));
         }
-        if col >= end_col {
-            break;
-        }
     }
 
     out
@@ -377,17 +550,54 @@ where
     rows.max(1)
 }
 
+/// Display width in terminal columns (CJK and emoji count as 2)
+pub(crate) fn text_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Longest prefix of `text` that fits in `max_width` columns (whole graphemes only)
+pub(crate) fn take_width(text: &str, max_width: usize) -> &str {
+    let mut width = 0usize;
+    for (idx, g) in text.grapheme_indices(true) {
+        width += UnicodeWidthStr::width(g);
+        if width > max_width {
+            return &text[..idx];
+        }
+    }
+    text
+}
+
+/// Longest suffix of `text` that fits in `max_width` columns (whole graphemes only)
+pub(crate) fn take_width_end(text: &str, max_width: usize) -> &str {
+    let mut width = 0usize;
+    for (idx, g) in text.grapheme_indices(true).rev() {
+        width += UnicodeWidthStr::width(g);
+        if width > max_width {
+            return &text[idx + g.len()..];
+        }
+    }
+    text
+}
+
+/// Truncate to `max_width` columns, ending with "..." when shortened
 pub(crate) fn truncate_text(text: &str, max_width: usize) -> String {
-    if max_width == 0 || text.len() <= max_width {
+    if max_width == 0 {
+        return String::new();
+    }
+    if text_width(text) <= max_width {
         return text.to_string();
     }
-    let suffix_len = max_width.saturating_sub(3);
-    format!("{}...", &text[..suffix_len])
+    if max_width <= 3 {
+        return ".".repeat(max_width);
+    }
+    format!("{}...", take_width(text, max_width - 3))
 }
 
-use crate::app::AnimationPhase;
+use crate::app::{AnimationPhase, App};
 use crate::color;
 use crate::config::ResolvedTheme;
+use crate::diagnostics::Severity;
+use crate::syntax::SyntaxSide;
 use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
@@ -549,3 +759,55 @@ fn render_empty_state(frame: &mut Frame, area: Rect, theme: &ResolvedTheme, has_
     let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
     frame.render_widget(paragraph, centered_area);
 }
+
+/// Three-way mode: label the first line of each run of changes introduced by
+/// one side, e.g. "current: main" or "incoming: feature/x"
+pub(crate) fn origin_label_span(
+    app: &mut App,
+    view_line: &ViewLine,
+    prev_origin: &mut Option<ChangeOrigin>,
+) -> Option<Span<'static>> {
+    let origin = app.change_origin(view_line);
+    let starts_run = origin != *prev_origin;
+    *prev_origin = origin;
+    let origin = origin.filter(|_| starts_run)?;
+    Some(Span::styled(
+        format!("  {}", app.origin_label(origin)),
+        Style::default()
+            .fg(app.origin_theme_color(origin))
+            .add_modifier(Modifier::ITALIC),
+    ))
+}
+
+fn severity_color(app: &App, severity: Severity) -> Color {
+    match severity {
+        Severity::Error => app.theme.error,
+        Severity::Warning => app.theme.warning,
+        Severity::Note => app.theme.info,
+    }
+}
+
+/// Line number color of a line with compiler/CI diagnostics
+pub(crate) fn diagnostic_color(app: &App, view_line: &ViewLine) -> Option<Color> {
+    let (diag, _) = app.line_diagnostic(view_line)?;
+    Some(severity_color(app, diag.severity))
+}
+
+/// The most severe diagnostic of a line as virtual text after it
+pub(crate) fn diagnostic_span(app: &App, view_line: &ViewLine) -> Option<Span<'static>> {
+    let (diag, count) = app.line_diagnostic(view_line)?;
+    let more = if count > 1 {
+        format!(" (+{})", count - 1)
+    } else {
+        String::new()
+    };
+    Some(Span::styled(
+        format!(
+            "  ● {}{more}",
+            diag.message.lines().next().unwrap_or_default()
+        ),
+        Style::default()
+            .fg(severity_color(app, diag.severity))
+            .add_modifier(Modifier::ITALIC),
+    ))
+}
@@ -6,6 +6,7 @@ use super::{
     truncate_text, wrap_count_for_spans, wrap_count_for_text, TAB_WIDTH,
 };
 use crate::app::{AnimationPhase, App};
+use crate::bidi::reorder_spans;
 use crate::color;
 use crate::config::{DiffBackgroundMode, DiffForegroundMode, ModifiedStepMode};
 use crate::syntax::SyntaxSide;
@@ -20,6 +21,8 @@ use ratatui::{
 
 /// Width of the fixed line number gutter (marker + line num + prefix + space)
 const GUTTER_WIDTH: u16 = 8; // "▶1234 + "
+/// Lines built above and below the viewport when not wrapping
+const OVERSCAN: usize = 8;
 
 fn build_inline_modified_spans(
     change: &Change,
@@ -74,7 +77,7 @@ fn build_inline_modified_spans(
                 if !include_equal {
                     continue;
                 }
-                spans.push(Span::styled(span.text.clone(), context_style));
+                spans.push(Span::styled(span.text.to_string(), context_style));
             }
             ChangeKind::Delete => {
                 has_old = true;
@@ -95,7 +98,7 @@ fn build_inline_modified_spans(
             }
             ChangeKind::Insert => {
                 has_new = true;
-                spans.push(Span::styled(span.text.clone(), insert_style));
+                spans.push(Span::styled(span.text.to_string(), insert_style));
             }
             ChangeKind::Replace => {
                 has_old = true;
@@ -115,7 +118,7 @@ fn build_inline_modified_spans(
                     spans.push(Span::styled(text.to_string(), delete_style));
                 }
                 spans.push(Span::styled(
-                    span.new_text.clone().unwrap_or_else(|| span.text.clone()),
+                    span.new_text.as_deref().unwrap_or(&span.text).to_string(),
                     insert_style,
                 ));
             }
@@ -162,14 +165,14 @@ fn build_modified_only_spans(
     for span in &change.spans {
         match span.kind {
             ChangeKind::Equal => {
-                spans.push(Span::styled(span.text.clone(), context_style));
+                spans.push(Span::styled(span.text.to_string(), context_style));
             }
             ChangeKind::Insert => {
-                spans.push(Span::styled(span.text.clone(), modify_style));
+                spans.push(Span::styled(span.text.to_string(), modify_style));
             }
             ChangeKind::Replace => {
                 spans.push(Span::styled(
-                    span.new_text.clone().unwrap_or_else(|| span.text.clone()),
+                    span.new_text.as_deref().unwrap_or(&span.text).to_string(),
                     modify_style,
                 ));
             }
@@ -186,7 +189,8 @@ fn build_modified_only_spans(
 /// Render the single-pane morphing view
 pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let visible_height = area.height as usize;
-    let visible_width = area.width.saturating_sub(GUTTER_WIDTH) as usize;
+    let gutter_width = super::gutter_width(app, GUTTER_WIDTH);
+    let visible_width = area.width.saturating_sub(gutter_width) as usize;
     if !app.line_wrap {
         app.clamp_horizontal_scroll_cached(visible_width);
     }
@@ -204,19 +208,34 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     app.multi_diff
         .current_navigator()
         .set_show_hunk_extent_while_stepping(app.stepping);
-    let view_lines = app
-        .multi_diff
-        .current_navigator()
-        .current_view_with_frame(animation_frame);
+    // Wrapping measures every line, and so does the scope target; otherwise
+    // only the viewport's lines (plus overscan) are built
+    let windowed = !app.line_wrap && !app.syntax_scopes_shown();
+    let view_len = app.multi_diff.current_navigator().view_len(animation_frame);
     if !app.line_wrap {
-        app.clamp_scroll(view_lines.len(), visible_height, app.allow_overscroll());
+        app.clamp_scroll(view_len, visible_height, app.allow_overscroll());
     }
+    let window_start = if windowed {
+        app.scroll_offset.saturating_sub(OVERSCAN)
+    } else {
+        0
+    };
+    let view_lines = if windowed {
+        let end = app.scroll_offset + visible_height + OVERSCAN;
+        app.multi_diff
+            .current_navigator()
+            .view_window(animation_frame, window_start..end)
+    } else {
+        app.multi_diff
+            .current_navigator()
+            .current_view_with_frame(animation_frame)
+    };
     let debug_target = app.syntax_scope_target(&view_lines);
 
     // Split area into gutter (fixed) and content (scrollable)
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(GUTTER_WIDTH), Constraint::Min(0)])
+        .constraints([Constraint::Length(gutter_width), Constraint::Min(0)])
         .split(area);
 
     let gutter_area = chunks[0];
@@ -227,7 +246,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let mut content_lines: Vec<Line> = Vec::new();
     let mut max_line_width: usize = 0;
     let wrap_width = visible_width;
-    let mut display_len = if app.line_wrap { 0 } else { view_lines.len() };
+    let mut display_len = if app.line_wrap { 0 } else { view_len };
     let mut primary_display_idx: Option<usize> = None;
     let mut active_display_idx: Option<usize> = None;
 
@@ -251,7 +270,11 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     } else {
         None
     };
-    for (idx, view_line) in view_lines.iter().enumerate() {
+    let row_key = app.row_cache_key(animation_frame, visible_width);
+    app.row_cache.begin(row_key);
+    let mut prev_origin = None;
+    for (idx, view_line) in (window_start..).zip(view_lines.iter()) {
+        let origin_label = super::origin_label_span(app, view_line, &mut prev_origin);
         // When wrapping, we need all lines for proper wrap calculation
         // When not wrapping, skip lines before scroll offset
         if !app.line_wrap && idx < app.scroll_offset {
@@ -261,8 +284,33 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             break;
         }
 
+        // Rows that don't animate look the same in every frame of a step
+        // animation; the tail and scope lines are drawn after a row, so rows
+        // followed by one are always rebuilt
+        let has_tail = pending_insert_only > 0 && tail_change_id == Some(view_line.change_id);
+        let cacheable = !view_line.is_active
+            && !view_line.is_primary_active
+            && origin_label.is_none()
+            && !has_tail
+            && debug_target
+                .as_ref()
+                .is_none_or(|(debug_idx, _)| *debug_idx != idx);
+        if cacheable {
+            if let Some((gutter, content, width)) = app.row_cache.get(idx) {
+                gutter_lines.push(gutter);
+                content_lines.push(content);
+                max_line_width = max_line_width.max(width);
+                continue;
+            }
+        }
+
         let line_num = view_line.old_line.or(view_line.new_line).unwrap_or(0);
         let line_num_str = format!("{:4}", line_num);
+        let bg_kind = if app.filtered_out(view_line) {
+            LineKind::Context
+        } else {
+            view_line.kind
+        };
 
         // Line number color from theme - use gradient base for diff types
         let insert_base = color::gradient_color(&app.theme.insert, 0.5);
@@ -271,6 +319,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
 
         let (line_prefix, line_num_style) = match view_line.kind {
             LineKind::Context => (" ", Style::default().fg(app.theme.diff_line_number)),
+            LineKind::Collapsed => ("⋯", Style::default().fg(app.theme.diff_line_number)),
             LineKind::Inserted => (
                 "+",
                 Style::default().fg(Color::Rgb(insert_base.r, insert_base.g, insert_base.b)),
@@ -298,14 +347,16 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
         };
 
         let line_bg_gutter = if app.diff_bg == DiffBackgroundMode::Line {
-            diff_line_bg(view_line.kind, &app.theme)
+            diff_line_bg(bg_kind, &app.theme)
         } else {
             None
         };
 
         // Sign column should fade with the line animation
         let sign_style = match view_line.kind {
-            LineKind::Context => Style::default().fg(app.theme.diff_line_number),
+            LineKind::Context | LineKind::Collapsed => {
+                Style::default().fg(app.theme.diff_line_number)
+            }
             LineKind::Inserted | LineKind::PendingInsert => {
                 if view_line.is_active {
                     super::insert_style(
@@ -368,9 +419,19 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             (" ", Style::default())
         };
 
+        // Three-way mode: line numbers show which side introduced the change
+        let line_num_style = match app.origin_color(view_line) {
+            Some(color) => line_num_style.fg(color),
+            None => line_num_style,
+        };
+        let line_num_style = match super::diagnostic_color(app, view_line) {
+            Some(color) => line_num_style.fg(color),
+            None => line_num_style,
+        };
+
         // Build gutter line (fixed, no horizontal scroll)
         let mut gutter_spans = vec![
-            Span::styled(active_marker, active_style),
+            Span::styled(active_marker.to_string(), active_style),
             Span::styled(line_num_str, line_num_style),
             Span::styled(" ", Style::default()),
             Span::styled(line_prefix, sign_style),
@@ -461,6 +522,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 || !matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify));
         if !used_inline_modified
             && app.syntax_enabled()
+            && view_line.kind != LineKind::Collapsed
             && !view_line.is_active_change
             && (pure_context || can_use_diff_syntax || in_preview_hunk)
         {
@@ -579,8 +641,9 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             for view_span in spans {
                 let style =
                     get_span_style(view_span.kind, style_line_kind, view_line.is_active, app);
+                let style = super::pattern_style(app, view_span.kind, style_line_kind, style);
                 // For deleted spans, don't strikethrough leading whitespace
-                if app.strikethrough_deletions
+                if (app.strikethrough_deletions || app.kind_patterns)
                     && matches!(
                         view_span.kind,
                         ViewSpanKind::Deleted | ViewSpanKind::PendingDelete
@@ -596,16 +659,21 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                             .push(Span::styled(text[..leading_ws_len].to_string(), ws_style));
                         content_spans.push(Span::styled(trimmed.to_string(), style));
                     } else {
-                        content_spans.push(Span::styled(view_span.text.clone(), style));
+                        content_spans.push(Span::styled(view_span.text.to_string(), style));
                     }
                 } else {
-                    content_spans.push(Span::styled(view_span.text.clone(), style));
+                    content_spans.push(Span::styled(view_span.text.to_string(), style));
                 }
             }
         }
 
+        content_spans = super::apply_heat(app, view_line, content_spans);
+        content_spans = super::mark_trailing_whitespace(app, view_line, content_spans);
+        content_spans = super::mute_filtered(app, view_line, content_spans);
+        content_spans = app.redact_spans(content_spans);
+
         let line_bg_line = if app.diff_bg == DiffBackgroundMode::Line {
-            diff_line_bg(view_line.kind, &app.theme)
+            diff_line_bg(bg_kind, &app.theme)
         } else {
             None
         };
@@ -614,7 +682,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
         }
 
         if app.diff_bg == DiffBackgroundMode::Text && used_syntax {
-            if let Some(bg) = diff_line_bg(view_line.kind, &app.theme) {
+            if let Some(bg) = diff_line_bg(bg_kind, &app.theme) {
                 content_spans = apply_spans_bg(content_spans, bg);
             }
         }
@@ -628,6 +696,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             && has_query
             && line_text.to_ascii_lowercase().contains(&query);
         content_spans = app.highlight_search_spans(content_spans, &line_text, is_active_match);
+        content_spans = app.highlight_occurrence_spans(content_spans, &line_text, None);
 
         if app.line_wrap {
             if view_line.is_primary_active && primary_display_idx.is_none() {
@@ -638,7 +707,13 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             }
         }
 
+        content_spans.extend(origin_label);
+        content_spans.extend(super::eof_newline_span(app, view_line, None));
+        content_spans.extend(super::diagnostic_span(app, view_line));
         content_spans = expand_tabs_in_spans(&content_spans, TAB_WIDTH);
+        if app.bidi {
+            content_spans = reorder_spans(content_spans);
+        }
 
         // Track max line width for horizontal scroll clamping
         let line_width = spans_width(&content_spans);
@@ -657,13 +732,18 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
         if !app.line_wrap {
             display_spans = slice_spans(&display_spans, app.horizontal_scroll, visible_width);
             if app.diff_bg == DiffBackgroundMode::Line {
-                if let Some(bg) = diff_line_bg(view_line.kind, &app.theme) {
+                if let Some(bg) = diff_line_bg(bg_kind, &app.theme) {
                     display_spans = pad_spans_bg(display_spans, bg, visible_width);
                 }
             }
         }
 
-        content_lines.push(Line::from(display_spans));
+        let content_line = Line::from(display_spans);
+        if let Some(gutter_line) = gutter_lines.last() {
+            let row = (gutter_line.clone(), content_line.clone(), line_width);
+            app.row_cache.insert(idx, row, cacheable);
+        }
+        content_lines.push(content_line);
         if app.line_wrap && wrap_count > 1 {
             for _ in 1..wrap_count {
                 gutter_lines.push(Line::from(Span::raw(" ")));
@@ -787,11 +867,7 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 .begin_symbol(Some("↑"))
                 .end_symbol(Some("↓"));
 
-            let total_lines = if app.line_wrap {
-                display_len
-            } else {
-                view_lines.len()
-            };
+            let total_lines = if app.line_wrap { display_len } else { view_len };
             let visible_lines = content_area.height as usize;
             if total_lines > visible_lines {
                 let mut scrollbar_state =
@@ -813,6 +889,9 @@ pub fn render_single_pane(frame: &mut Frame, app: &mut App, area: Rect) {
 fn get_span_style(kind: ViewSpanKind, line_kind: LineKind, is_active: bool, app: &App) -> Style {
     let backward = app.is_backward_animation();
     let theme = &app.theme;
+    if line_kind == LineKind::Collapsed {
+        return super::collapsed_style(theme);
+    }
     let is_modification = matches!(line_kind, LineKind::Modified | LineKind::PendingModify);
     let use_bg = app.diff_bg == DiffBackgroundMode::Text;
     let added_bg = if use_bg { theme.diff_added_bg } else { None };
@@ -5,11 +5,13 @@ use super::{
     pad_spans_bg, pending_tail_text, render_empty_state, slice_spans, spans_to_text, spans_width,
     truncate_text, wrap_count_for_spans, wrap_count_for_text, TAB_WIDTH,
 };
-use crate::app::{AnimationPhase, App};
+use crate::app::{AnimationPhase, App, SplitPeek};
+use crate::bidi::reorder_spans;
 use crate::config::{DiffBackgroundMode, DiffForegroundMode};
+use crate::popup::{Placement, Window};
 use crate::syntax::SyntaxSide;
 use oyo_core::{
-    AnimationFrame, ChangeKind, LineKind, StepDirection, ViewLine, ViewSpan, ViewSpanKind,
+    AnimationFrame, Change, ChangeKind, LineKind, StepDirection, ViewLine, ViewSpan, ViewSpanKind,
 };
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -18,6 +20,24 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
+
+/// Index of the last unchanged span of a change; alignment gaps are only
+/// needed before it
+fn aligned_tail(change: &Change) -> Option<usize> {
+    change
+        .spans
+        .iter()
+        .rposition(|span| span.kind == ChangeKind::Equal && !span.text.is_empty())
+}
+
+/// Blank stand-in for `width` columns of text only the other side has
+fn gap_span(width: usize) -> Option<ViewSpan> {
+    (width > 0).then(|| ViewSpan {
+        text: " ".repeat(width).into(),
+        kind: ViewSpanKind::Equal,
+    })
+}
 
 fn split_old_bg_kind(kind: LineKind) -> LineKind {
     match kind {
@@ -62,13 +82,15 @@ pub fn render_split(frame: &mut Frame, app: &mut App, area: Rect) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
 
-    if app.line_wrap {
+    let active_idx = if app.line_wrap {
         let old_width = chunks[0]
             .width
-            .saturating_sub(GUTTER_WIDTH + OLD_BORDER_WIDTH) as usize;
+            .saturating_sub(super::gutter_width(app, GUTTER_WIDTH) + OLD_BORDER_WIDTH)
+            as usize;
         let new_width = chunks[1]
             .width
-            .saturating_sub(NEW_GUTTER_WIDTH + NEW_MARKER_WIDTH) as usize;
+            .saturating_sub(super::gutter_width(app, NEW_GUTTER_WIDTH) + NEW_MARKER_WIDTH)
+            as usize;
         let (display_len, active_idx) = split_wrap_display_metrics(
             app,
             &view_lines,
@@ -79,8 +101,9 @@ pub fn render_split(frame: &mut Frame, app: &mut App, area: Rect) {
         );
         app.ensure_active_visible_if_needed_wrapped(visible_height, display_len, active_idx);
         app.clamp_scroll(display_len, visible_height, app.allow_overscroll());
+        active_idx
     } else {
-        let (display_len, _) = crate::app::display_metrics(
+        let (display_len, active_idx) = crate::app::display_metrics(
             &view_lines,
             app.view_mode,
             app.animation_phase,
@@ -88,20 +111,74 @@ pub fn render_split(frame: &mut Frame, app: &mut App, area: Rect) {
             step_direction,
         );
         app.clamp_scroll(display_len, visible_height, app.allow_overscroll());
-    }
+        active_idx
+    };
     if !app.line_wrap {
         let old_width = chunks[0]
             .width
-            .saturating_sub(GUTTER_WIDTH + OLD_BORDER_WIDTH) as usize;
+            .saturating_sub(super::gutter_width(app, GUTTER_WIDTH) + OLD_BORDER_WIDTH)
+            as usize;
         let new_width = chunks[1]
             .width
-            .saturating_sub(NEW_GUTTER_WIDTH + NEW_MARKER_WIDTH) as usize;
+            .saturating_sub(super::gutter_width(app, NEW_GUTTER_WIDTH) + NEW_MARKER_WIDTH)
+            as usize;
         app.clamp_horizontal_scroll_cached(old_width.min(new_width));
     }
     app.reset_current_max_line_width();
 
     render_old_pane(frame, app, chunks[0]);
     render_new_pane(frame, app, chunks[1]);
+    if let Some(peek) = app.split_peek {
+        let row = active_idx.and_then(|idx| idx.checked_sub(app.scroll_offset));
+        render_peek(frame, app, &view_lines, peek, &chunks, row);
+    }
+}
+
+/// Float the other side's version of the active line under it, over the
+/// pane that lacks it, so long lines can be compared without looking across
+fn render_peek(
+    frame: &mut Frame,
+    app: &mut App,
+    view_lines: &[ViewLine],
+    peek: SplitPeek,
+    panes: &[Rect],
+    row: Option<usize>,
+) {
+    let Some(view_line) = view_lines.iter().find(|line| line.is_primary_active) else {
+        return;
+    };
+    let (side, line_num, pane, label) = match peek {
+        SplitPeek::Old => (SyntaxSide::Old, view_line.old_line, panes[1], "old"),
+        SplitPeek::New => (SyntaxSide::New, view_line.new_line, panes[0], "new"),
+    };
+    let Some(row) = row.filter(|&row| row < pane.height as usize) else {
+        return;
+    };
+    let row = row as u16;
+
+    let spans = line_num.and_then(|line_num| app.side_line_spans(side, line_num));
+    let (title, spans) = match (line_num, spans) {
+        (Some(line_num), Some(spans)) => (
+            format!(" {label} {line_num} "),
+            expand_tabs_in_spans(&spans, TAB_WIDTH),
+        ),
+        _ => (
+            format!(" {label} "),
+            vec![Span::styled(
+                format!("no {label} line"),
+                Style::default().fg(app.theme.text_muted),
+            )],
+        ),
+    };
+
+    let inner_width = pane.width.saturating_sub(2).max(1) as usize;
+    let wanted = wrap_count_for_spans(&spans, inner_width) as u16 + 2;
+    let inner = Window::new(title)
+        .size(pane.width, wanted.min((pane.height / 2).max(3)))
+        .placement(Placement::Below { area: pane, row })
+        .render(frame, &app.theme);
+    let paragraph = Paragraph::new(Line::from(spans)).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, inner);
 }
 
 fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -114,7 +191,8 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
         .current_navigator()
         .current_view_with_frame(AnimationFrame::Idle);
     let visible_height = area.height as usize;
-    let visible_width = area.width.saturating_sub(GUTTER_WIDTH + 1) as usize; // +1 for border
+    let gutter_width = super::gutter_width(app, GUTTER_WIDTH);
+    let visible_width = area.width.saturating_sub(gutter_width + 1) as usize; // +1 for border
     let debug_target = app.syntax_scope_target(&view_lines);
     let (preview_mode, preview_hunk) = {
         let state = app.multi_diff.current_navigator().state();
@@ -139,7 +217,7 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(GUTTER_WIDTH),
+            Constraint::Length(gutter_width),
             Constraint::Min(0),
             Constraint::Length(1), // For border
         ])
@@ -156,8 +234,10 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let has_query = !query.is_empty();
     let mut max_line_width: usize = 0;
 
+    let mut prev_origin = None;
     for view_line in view_lines.iter() {
         if let Some(old_line_num) = view_line.old_line {
+            let origin_label = super::origin_label_span(app, view_line, &mut prev_origin);
             // When wrapping, we need all lines
             if !app.line_wrap && line_idx < app.scroll_offset {
                 line_idx += 1;
@@ -168,7 +248,11 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             }
 
             let line_num_str = format!("{:4}", old_line_num);
-            let bg_kind = split_old_bg_kind(view_line.kind);
+            let bg_kind = if app.filtered_out(view_line) {
+                LineKind::Context
+            } else {
+                split_old_bg_kind(view_line.kind)
+            };
             let line_bg_gutter = if app.diff_bg == DiffBackgroundMode::Line {
                 diff_line_bg(bg_kind, &app.theme)
             } else {
@@ -197,7 +281,9 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 Span::styled(active_marker, active_style),
                 Span::styled(
                     line_num_str,
-                    Style::default().fg(app.theme.diff_line_number),
+                    Style::default().fg(app
+                        .origin_color(view_line)
+                        .unwrap_or(app.theme.diff_line_number)),
                 ),
                 Span::styled(" ", Style::default()),
             ];
@@ -235,6 +321,7 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 && matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify);
             let can_use_diff_syntax = wants_diff_syntax;
             if app.syntax_enabled()
+                && view_line.kind != LineKind::Collapsed
                 && !preview_modified
                 && !view_line.is_active_change
                 && (pure_context || can_use_diff_syntax || in_preview_hunk)
@@ -255,7 +342,9 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                     .contains(&view_line.change_id);
                 let show_inline = view_line.old_line.is_some()
                     && view_line.new_line.is_some()
+                    && view_line.kind != LineKind::Collapsed
                     && (view_line.is_active || is_applied);
+                let align_gaps = app.split_align_gaps;
                 let spans = if show_inline {
                     if let Some(change) = app
                         .multi_diff
@@ -264,7 +353,10 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                         .changes
                         .get(view_line.change_id)
                     {
-                        for span in &change.spans {
+                        let tail = aligned_tail(change).filter(|_| align_gaps);
+                        for (idx, span) in change.spans.iter().enumerate() {
+                            let align = tail.is_some_and(|tail| idx < tail);
+                            let new_width = span.new_text.as_deref().map_or(0, str::width);
                             match span.kind {
                                 ChangeKind::Equal => rebuilt_spans.push(ViewSpan {
                                     text: span.text.clone(),
@@ -275,6 +367,14 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                                         text: span.text.clone(),
                                         kind: ViewSpanKind::Deleted,
                                     });
+                                    if align {
+                                        rebuilt_spans.extend(gap_span(
+                                            new_width.saturating_sub(span.text.width()),
+                                        ));
+                                    }
+                                }
+                                ChangeKind::Insert if align => {
+                                    rebuilt_spans.extend(gap_span(span.text.width()));
                                 }
                                 ChangeKind::Insert => {}
                             }
@@ -296,8 +396,9 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                         view_line.is_active,
                         app,
                     );
+                    let style = super::pattern_style(app, view_span.kind, view_line.kind, style);
                     // For deleted spans, don't strikethrough leading whitespace
-                    if app.strikethrough_deletions
+                    if (app.strikethrough_deletions || app.kind_patterns)
                         && matches!(
                             view_span.kind,
                             ViewSpanKind::Deleted | ViewSpanKind::PendingDelete
@@ -312,14 +413,19 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                                 .push(Span::styled(text[..leading_ws_len].to_string(), ws_style));
                             content_spans.push(Span::styled(trimmed.to_string(), style));
                         } else {
-                            content_spans.push(Span::styled(view_span.text.clone(), style));
+                            content_spans.push(Span::styled(view_span.text.to_string(), style));
                         }
                     } else {
-                        content_spans.push(Span::styled(view_span.text.clone(), style));
+                        content_spans.push(Span::styled(view_span.text.to_string(), style));
                     }
                 }
             }
 
+            content_spans = super::apply_heat(app, view_line, content_spans);
+            content_spans = super::mark_trailing_whitespace(app, view_line, content_spans);
+            content_spans = super::mute_filtered(app, view_line, content_spans);
+            content_spans = app.redact_spans(content_spans);
+
             let line_bg_line = if app.diff_bg == DiffBackgroundMode::Line {
                 diff_line_bg(bg_kind, &app.theme)
             } else {
@@ -344,8 +450,19 @@ fn render_old_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 && has_query
                 && line_text.to_ascii_lowercase().contains(&query);
             content_spans = app.highlight_search_spans(content_spans, &line_text, is_active_match);
-
+            content_spans =
+                app.highlight_occurrence_spans(content_spans, &line_text, Some(SyntaxSide::Old));
+
+            content_spans.extend(origin_label);
+            content_spans.extend(super::eof_newline_span(
+                app,
+                view_line,
+                Some(SyntaxSide::Old),
+            ));
             content_spans = expand_tabs_in_spans(&content_spans, TAB_WIDTH);
+            if app.bidi {
+                content_spans = reorder_spans(content_spans);
+            }
 
             let line_width = spans_width(&content_spans);
             max_line_width = max_line_width.max(line_width);
@@ -517,7 +634,7 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(5), // "1234 "
+            Constraint::Length(super::gutter_width(app, NEW_GUTTER_WIDTH)),
             Constraint::Min(0),
             Constraint::Length(1), // For active marker
         ])
@@ -536,12 +653,14 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
     let has_query = !query.is_empty();
     let mut max_line_width: usize = 0;
 
+    let mut prev_origin = None;
     for view_line in view_lines.iter() {
         if let Some(new_line_num) = view_line.new_line {
             // Skip lines that represent deletions (they don't exist in new file)
             if matches!(view_line.kind, LineKind::Deleted | LineKind::PendingDelete) {
                 continue;
             }
+            let origin_label = super::origin_label_span(app, view_line, &mut prev_origin);
 
             // When wrapping, we need all lines
             if !app.line_wrap && line_idx < app.scroll_offset {
@@ -553,7 +672,11 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             }
 
             let line_num_str = format!("{:4}", new_line_num);
-            let bg_kind = split_new_bg_kind(view_line.kind);
+            let bg_kind = if app.filtered_out(view_line) {
+                LineKind::Context
+            } else {
+                split_new_bg_kind(view_line.kind)
+            };
             let line_bg_gutter = if app.diff_bg == DiffBackgroundMode::Line {
                 diff_line_bg(bg_kind, &app.theme)
             } else {
@@ -578,11 +701,11 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
             };
 
             // Build gutter line
+            let line_num_color = super::diagnostic_color(app, view_line)
+                .or_else(|| app.origin_color(view_line))
+                .unwrap_or(app.theme.diff_line_number);
             let mut gutter_spans = vec![
-                Span::styled(
-                    line_num_str,
-                    Style::default().fg(app.theme.diff_line_number),
-                ),
+                Span::styled(line_num_str, Style::default().fg(line_num_color)),
                 Span::styled(" ", Style::default()),
             ];
             if let Some(bg) = line_bg_gutter {
@@ -612,6 +735,7 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 && matches!(view_line.kind, LineKind::Modified | LineKind::PendingModify);
             let can_use_diff_syntax = wants_diff_syntax;
             if app.syntax_enabled()
+                && view_line.kind != LineKind::Collapsed
                 && !preview_modified
                 && !view_line.is_active_change
                 && (pure_context || can_use_diff_syntax || in_preview_hunk)
@@ -642,7 +766,9 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                     .contains(&view_line.change_id);
                 let show_inline = view_line.old_line.is_some()
                     && view_line.new_line.is_some()
+                    && view_line.kind != LineKind::Collapsed
                     && (view_line.is_active || is_applied);
+                let align_gaps = app.split_align_gaps;
                 let spans = if show_inline {
                     if let Some(change) = app
                         .multi_diff
@@ -651,7 +777,9 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                         .changes
                         .get(view_line.change_id)
                     {
-                        for span in &change.spans {
+                        let tail = aligned_tail(change).filter(|_| align_gaps);
+                        for (idx, span) in change.spans.iter().enumerate() {
+                            let align = tail.is_some_and(|tail| idx < tail);
                             match span.kind {
                                 ChangeKind::Equal => rebuilt_spans.push(ViewSpan {
                                     text: span.text.clone(),
@@ -665,17 +793,25 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                                         ViewSpanKind::Inserted
                                     },
                                 }),
-                                ChangeKind::Replace => rebuilt_spans.push(ViewSpan {
-                                    text: span
-                                        .new_text
-                                        .clone()
-                                        .unwrap_or_else(|| span.text.clone()),
-                                    kind: if view_line.is_active {
-                                        ViewSpanKind::PendingInsert
-                                    } else {
-                                        ViewSpanKind::Inserted
-                                    },
-                                }),
+                                ChangeKind::Replace => {
+                                    let text =
+                                        span.new_text.clone().unwrap_or_else(|| span.text.clone());
+                                    let gap = span.text.width().saturating_sub(text.width());
+                                    rebuilt_spans.push(ViewSpan {
+                                        text,
+                                        kind: if view_line.is_active {
+                                            ViewSpanKind::PendingInsert
+                                        } else {
+                                            ViewSpanKind::Inserted
+                                        },
+                                    });
+                                    if align {
+                                        rebuilt_spans.extend(gap_span(gap));
+                                    }
+                                }
+                                ChangeKind::Delete if align => {
+                                    rebuilt_spans.extend(gap_span(span.text.width()));
+                                }
                                 ChangeKind::Delete => {}
                             }
                         }
@@ -695,9 +831,15 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                         view_line.is_active,
                         app,
                     );
-                    content_spans.push(Span::styled(view_span.text.clone(), style));
+                    let style = super::pattern_style(app, view_span.kind, view_line.kind, style);
+                    content_spans.push(Span::styled(view_span.text.to_string(), style));
                 }
             }
+            content_spans = super::apply_heat(app, view_line, content_spans);
+            content_spans = super::mark_trailing_whitespace(app, view_line, content_spans);
+            content_spans = super::mute_filtered(app, view_line, content_spans);
+            content_spans = app.redact_spans(content_spans);
+
             let line_bg_line = if app.diff_bg == DiffBackgroundMode::Line {
                 diff_line_bg(bg_kind, &app.theme)
             } else {
@@ -722,8 +864,20 @@ fn render_new_pane(frame: &mut Frame, app: &mut App, area: Rect) {
                 && has_query
                 && line_text.to_ascii_lowercase().contains(&query);
             content_spans = app.highlight_search_spans(content_spans, &line_text, is_active_match);
-
+            content_spans =
+                app.highlight_occurrence_spans(content_spans, &line_text, Some(SyntaxSide::New));
+
+            content_spans.extend(origin_label);
+            content_spans.extend(super::eof_newline_span(
+                app,
+                view_line,
+                Some(SyntaxSide::New),
+            ));
+            content_spans.extend(super::diagnostic_span(app, view_line));
             content_spans = expand_tabs_in_spans(&content_spans, TAB_WIDTH);
+            if app.bidi {
+                content_spans = reorder_spans(content_spans);
+            }
 
             let line_width = spans_width(&content_spans);
             max_line_width = max_line_width.max(line_width);
@@ -1001,11 +1155,14 @@ fn view_spans_to_text(spans: &[ViewSpan]) -> String {
 
 fn get_old_span_style(
     kind: ViewSpanKind,
-    _line_kind: LineKind,
+    line_kind: LineKind,
     is_active: bool,
     app: &App,
 ) -> Style {
     let theme = &app.theme;
+    if line_kind == LineKind::Collapsed {
+        return super::collapsed_style(theme);
+    }
     let use_bg = app.diff_bg == DiffBackgroundMode::Text;
     let removed_bg = if use_bg { theme.diff_removed_bg } else { None };
     match kind {
@@ -1078,11 +1235,14 @@ fn get_old_span_style(
 
 fn get_new_span_style(
     kind: ViewSpanKind,
-    _line_kind: LineKind,
+    line_kind: LineKind,
     is_active: bool,
     app: &App,
 ) -> Style {
     let theme = &app.theme;
+    if line_kind == LineKind::Collapsed {
+        return super::collapsed_style(theme);
+    }
     let use_bg = app.diff_bg == DiffBackgroundMode::Text;
     let added_bg = if use_bg { theme.diff_added_bg } else { None };
     match kind {
@@ -1,10 +1,10 @@
 use std::path::PathBuf;
 
-use crate::app::{AnimationPhase, App, ViewMode};
+use crate::app::{AnimationPhase, App, SplitPeek, ViewMode};
 use crate::config::{DiffBackgroundMode, DiffForegroundMode, EvoSyntaxMode, SyntaxMode};
 use crate::views::{render_evolution, render_single_pane, render_split};
 use oyo_core::MultiFileDiff;
-use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use ratatui::{backend::TestBackend, buffer::Buffer, style::Modifier, Terminal};
 
 fn make_app(old: &str, new: &str, view_mode: ViewMode) -> App {
     let diff = MultiFileDiff::from_file_pair(
@@ -80,6 +80,55 @@ fn test_single_modified_lifecycle_render() {
     assert!(after.contains("NEWSIDE"));
 }
 
+#[test]
+fn test_collapsed_hunk_renders_summary_in_all_views() {
+    let old = "line1\nOLDFOLD\nline3\n";
+    let new = "line1\nNEWFOLD\nline3\n";
+    for view_mode in [ViewMode::SinglePane, ViewMode::Split, ViewMode::Evolution] {
+        let mut app = make_app(old, new, view_mode);
+        app.toggle_hunk_collapsed();
+        let text = buffer_text(&render_buffer(&mut app, 100, 20)).join("\n");
+        assert!(text.contains("hunk 1: +1 −1 at line 2"), "{view_mode:?}");
+        assert!(!text.contains("OLDFOLD"), "{view_mode:?}");
+        assert!(!text.contains("NEWFOLD"), "{view_mode:?}");
+    }
+}
+
+#[test]
+fn test_diagnostics_render_after_line_in_all_views() {
+    let old = "line1\nOLD\nline3\n";
+    let new = "line1\nNEW\nline3\n";
+    for view_mode in [ViewMode::SinglePane, ViewMode::Split, ViewMode::Evolution] {
+        let mut app = make_app(old, new, view_mode);
+        app.diagnostics = crate::diagnostics::parse("new.txt:3:1: warning: unused line\n");
+        let text = buffer_text(&render_buffer(&mut app, 100, 20)).join("\n");
+        assert_eq!(
+            count_occurrences(&text, "line3  ● unused line"),
+            1,
+            "{view_mode:?}"
+        );
+    }
+}
+
+#[test]
+fn test_eof_newline_and_trailing_whitespace_markers() {
+    let old = "line1\nx = 1;  \nlast";
+    let new = "line1\nx = 1;\nlast\n";
+    for view_mode in [ViewMode::SinglePane, ViewMode::Split, ViewMode::Evolution] {
+        let mut app = make_app(old, new, view_mode);
+        let text = buffer_text(&render_buffer(&mut app, 120, 20)).join("\n");
+        let marker = match view_mode {
+            ViewMode::Split => "last  ⏎ no newline at end of file",
+            _ => "last  ⏎ newline added at end of file",
+        };
+        assert_eq!(count_occurrences(&text, marker), 1, "{view_mode:?}\n{text}");
+    }
+
+    let mut app = make_app(old, new, ViewMode::Split);
+    let text = buffer_text(&render_buffer(&mut app, 120, 20)).join("\n");
+    assert!(text.contains("x = 1;··"));
+}
+
 #[test]
 fn test_split_modified_lifecycle_render() {
     let old = "line1\nOLDSPLIT\nline3\n";
@@ -97,6 +146,77 @@ fn test_split_modified_lifecycle_render() {
     assert_eq!(count_occurrences(&after, "NEWSPLIT"), 1);
 }
 
+#[test]
+fn test_split_peek_floats_the_other_side_under_the_line() {
+    let old = "line1\nlet total = compute_old();\nline3\n";
+    let new = "line1\nlet total = compute_new();\nline3\n";
+    let mut app = make_app(old, new, ViewMode::Split);
+    app.next_step();
+    app.toggle_split_peek(SplitPeek::Old);
+
+    let lines = buffer_text(&render_buffer(&mut app, 80, 12));
+    let row = lines
+        .iter()
+        .position(|line| line.contains("compute_new"))
+        .unwrap();
+    let right_pane = |line: &String| line.chars().skip(40).collect::<String>();
+    assert!(right_pane(&lines[row + 1]).contains("old 2"));
+    assert!(right_pane(&lines[row + 2]).contains("compute_old"));
+
+    app.toggle_split_peek(SplitPeek::Old);
+    let closed = buffer_text(&render_buffer(&mut app, 80, 12)).join("\n");
+    assert!(!closed.contains("old 2"));
+}
+
+#[test]
+fn test_kind_patterns_mark_changes_without_color() {
+    let modifier_of = |old: &str, new: &str, needle: &str| {
+        let mut app = make_app(old, new, ViewMode::SinglePane);
+        app.kind_patterns = true;
+        app.next_step();
+        app.multi_diff.current_navigator().clear_active_change();
+        let buf = render_buffer(&mut app, 100, 10);
+        let (y, row) = buffer_text(&buf)
+            .into_iter()
+            .enumerate()
+            .find(|(_, row)| row.contains(needle))
+            .unwrap_or_else(|| panic!("{needle}: {:?}", buffer_text(&buf)));
+        let x = row[..row.find(needle).unwrap()].chars().count() as u16;
+        buf[(x, y as u16)].modifier
+    };
+    let added = modifier_of("keep\n", "keep\nADDED\n", "ADDED");
+    assert!(added.contains(Modifier::UNDERLINED));
+    let deleted = modifier_of("keep\nGONE\n", "keep\n", "GONE");
+    assert!(deleted.contains(Modifier::CROSSED_OUT));
+    let modified = modifier_of("keep\nx = 1\n", "keep\nx = 222\n", "222");
+    assert!(modified.contains(Modifier::ITALIC));
+    let context = modifier_of("keep\nx = 1\n", "keep\nx = 2\n", "keep");
+    assert!(!context.intersects(Modifier::UNDERLINED | Modifier::ITALIC));
+}
+
+#[test]
+fn test_split_align_gaps_line_up_tails() {
+    let old = "let x = foo(a);\n";
+    let new = "let x = foo(a, extra);\n";
+    let mut app = make_app(old, new, ViewMode::Split);
+    app.split_align_gaps = true;
+    app.next_step();
+    app.multi_diff.current_navigator().clear_active_change();
+
+    let text = buffer_text(&render_buffer(&mut app, 100, 5));
+    let row = text
+        .iter()
+        .find(|row| row.contains("extra"))
+        .expect("modified row");
+    let (old_side, new_side) = row.split_at(row.find("│").expect("divider"));
+    let tail = |side: &str| side.find(");").expect("tail");
+    let start = |side: &str| side.find("let").expect("start");
+    assert_eq!(
+        tail(old_side) - start(old_side),
+        tail(new_side) - start(new_side)
+    );
+}
+
 #[test]
 fn test_evolution_full_preview_no_duplicate_modified_line() {
     let old = "line1\nOLDEVO\nline3\n";
@@ -125,3 +245,103 @@ fn test_evolution_deleted_active_fallback_marker() {
         "cursor marker should remain visible when deleted line is hidden"
     );
 }
+
+#[test]
+fn test_slice_spans_windows_long_lines() {
+    use crate::views::slice_spans;
+    use ratatui::style::{Color, Style};
+    use ratatui::text::Span;
+
+    let red = Style::default().fg(Color::Red);
+    let spans = vec![
+        Span::raw("a".repeat(10_000)),
+        Span::styled("0123456789".to_string(), red),
+        Span::raw("界界界".to_string()),
+        Span::raw("tail".to_string()),
+    ];
+
+    let sliced = slice_spans(&spans, 9_998, 6);
+    assert_eq!(sliced.len(), 2);
+    assert_eq!(sliced[0].content, "aa");
+    assert_eq!(sliced[1].content, "0123");
+    assert_eq!(sliced[1].style, red);
+
+    // Wide graphemes straddling the window edge are kept whole
+    let sliced = slice_spans(&spans, 10_009, 4);
+    let text: String = sliced.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(text, "9界界");
+
+    assert!(slice_spans(&spans, 20_000, 10).is_empty());
+}
+
+#[test]
+fn test_width_helpers_respect_wide_graphemes() {
+    use crate::views::{take_width, take_width_end, text_width, truncate_text};
+
+    assert_eq!(text_width("漢字ab"), 6);
+    assert_eq!(take_width("漢字ab", 3), "漢");
+    assert_eq!(take_width_end("ab漢字", 5), "b漢字");
+    // ZWJ family emoji is one grapheme and is never split
+    let family = "👨\u{200d}👩\u{200d}👧";
+    assert_eq!(take_width(&format!("{family}x"), 1), "");
+    assert_eq!(truncate_text("日本語のテキスト", 9), "日本語...");
+    assert_eq!(truncate_text("short", 9), "short");
+}
+
+#[test]
+fn test_presentation_hides_gutter_and_echoes_keys() {
+    let mut app = make_app("alpha\nbeta\n", "alpha\nBETA\n", ViewMode::SinglePane);
+    app.auto_center = false;
+    let lines = buffer_text(&render_buffer(&mut app, 40, 4));
+    assert!(lines[0].trim_start().starts_with('1'));
+
+    app.toggle_presentation();
+    assert!(app.auto_center);
+    let lines = buffer_text(&render_buffer(&mut app, 40, 4));
+    assert!(
+        lines.iter().any(|line| line.starts_with("alpha")),
+        "{lines:?}"
+    );
+
+    app.echo_key("j".to_string());
+    app.echo_key("j".to_string());
+    app.echo_key("k".to_string());
+    let echoed: Vec<(&str, usize)> = app
+        .key_echo
+        .iter()
+        .map(|(key, count, _)| (key.as_str(), *count))
+        .collect();
+    assert_eq!(echoed, vec![("j", 2), ("k", 1)]);
+
+    app.toggle_presentation();
+    assert!(app.key_echo.is_empty());
+    assert!(!app.auto_center);
+    app.echo_key("j".to_string());
+    assert!(app.key_echo.is_empty());
+}
+
+#[test]
+fn test_app_steps_render_as_core_frames() {
+    let mut app = make_app(
+        "line1\nOLDSIDE\nline3\n",
+        "line1\nNEWSIDE\nline3\n",
+        ViewMode::SinglePane,
+    );
+    let frames = oyo_core::testing::render_steps(app.multi_diff.current_navigator());
+    assert_eq!(
+        frames,
+        "── step 0/1 ──\n\
+         \x20   1    1   line1\n\
+         \x20   2    2   OLDSIDE\n\
+         \x20   3    3   line3\n\
+         ── step 1/1 ──\n\
+         \x20   1    1   line1\n\
+         >   2    2 ~ {+NEWSIDE+}\n\
+         \x20   3    3   line3\n"
+    );
+
+    // The frames leave the navigator at the end, as the view shows it
+    app.multi_diff.current_navigator().clear_active_change();
+    let view = buffer_text(&render_buffer(&mut app, 80, 20)).join("\n");
+    assert!(view.contains("NEWSIDE") && !view.contains("OLDSIDE"));
+}
@@ -0,0 +1,24 @@
+//! Diff arbitrary pairs of texts and check the engine's invariants
+//!
+//! Input: the old and new texts separated by the first NUL byte.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use oyo_core::{invariants, DiffEngine, ReindentMode};
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let (old, new) = text.split_once('\0').unwrap_or((&*text, ""));
+    for word_level in [false, true] {
+        for reindent in [ReindentMode::Off, ReindentMode::Skip] {
+            let diff = DiffEngine::new()
+                .with_word_level(word_level)
+                .with_reindent(reindent)
+                .diff_strings(old, new);
+            if let Err(violation) = invariants::check(&diff, old, new) {
+                panic!("{violation} (word level: {word_level}, reindent: {reindent:?})");
+            }
+        }
+    }
+});